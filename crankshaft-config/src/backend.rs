@@ -3,16 +3,27 @@
 use serde::Deserialize;
 use serde::Serialize;
 
+pub mod apptainer;
 mod builder;
 mod defaults;
 pub mod docker;
 pub mod generic;
+pub mod k8s;
 mod kind;
+pub mod local;
+pub mod lsf;
+pub mod queue;
+pub mod retry;
+pub mod schedule;
+pub mod slurm;
 pub mod tes;
 
 pub use builder::Builder;
 pub use defaults::Defaults;
 pub use kind::Kind;
+pub use queue::QueueOrder;
+pub use retry::RetryPolicy;
+pub use schedule::Schedule;
 
 /// A configuration object for an execution backend.
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -30,6 +41,23 @@ pub struct Config {
 
     /// The execution defaults.
     defaults: Option<Defaults>,
+
+    /// The windows during which the backend accepts submissions.
+    ///
+    /// Outside of these windows, tasks are queued within the engine.
+    #[serde(default)]
+    schedule: Schedule,
+
+    /// The order in which queued tasks are started once the backend has free
+    /// capacity.
+    #[serde(default)]
+    queue_order: QueueOrder,
+
+    /// The number of seconds a running task is given to shut down gracefully
+    /// once it is canceled before it is forcibly killed.
+    ///
+    /// If this is not set, the engine's default grace period is used.
+    termination_grace_period: Option<u64>,
 }
 
 impl Config {
@@ -58,6 +86,23 @@ impl Config {
         self.defaults.as_ref()
     }
 
+    /// Gets the windows during which the backend accepts submissions.
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+
+    /// Gets the order in which queued tasks are started once the backend has
+    /// free capacity.
+    pub fn queue_order(&self) -> QueueOrder {
+        self.queue_order
+    }
+
+    /// Gets the number of seconds a running task is given to shut down
+    /// gracefully once it is canceled (if it is configured).
+    pub fn termination_grace_period(&self) -> Option<u64> {
+        self.termination_grace_period
+    }
+
     /// Consumes `self` returns the constituent parts of the [`Config`].
     pub fn into_parts(self) -> (String, Kind, usize, Option<Defaults>) {
         (self.name, self.kind, self.max_tasks, self.defaults)
@@ -0,0 +1,87 @@
+//! Configuration related to the _Apptainer_ execution backend.
+
+mod builder;
+
+pub use builder::Builder;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::backend::generic::driver;
+
+/// The default executable used to run containers.
+pub const DEFAULT_EXECUTABLE: &str = "apptainer";
+
+/// A configuration object for an Apptainer (or Singularity) execution
+/// backend.
+///
+/// Executions are run with `apptainer exec` through the same command driver
+/// as generic backends, so containers may be run either locally or on a
+/// remote host over SSH.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Configuration related to the command driver.
+    #[serde(flatten)]
+    driver: driver::Config,
+
+    /// The executable used to run containers (e.g., `singularity`).
+    ///
+    /// If this is not set, [`DEFAULT_EXECUTABLE`] is used.
+    executable: Option<String>,
+
+    /// Additional arguments passed to `exec` (e.g., `--nv` or `--cleanenv`).
+    #[serde(default)]
+    extra_args: Vec<String>,
+}
+
+impl Config {
+    /// Gets a default [`Builder`] for a [`Config`].
+    pub fn builder() -> Builder {
+        Builder::default()
+    }
+
+    /// Gets the driver configuration.
+    pub fn driver(&self) -> &driver::Config {
+        &self.driver
+    }
+
+    /// Gets the executable used to run containers.
+    pub fn executable(&self) -> &str {
+        self.executable.as_deref().unwrap_or(DEFAULT_EXECUTABLE)
+    }
+
+    /// Gets the additional arguments passed to `exec`.
+    pub fn extra_args(&self) -> &[String] {
+        &self.extra_args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_are_deserialized() {
+        let backends = crate::Config::parse_backends(
+            r#"[
+                {"name": "apptainer", "kind": "Apptainer", "max-tasks": 4},
+                {
+                    "name": "singularity",
+                    "kind": "Apptainer",
+                    "max-tasks": 4,
+                    "executable": "singularity",
+                    "extra-args": ["--nv"]
+                }
+            ]"#,
+        )
+        .unwrap();
+
+        let config = backends[0].kind().as_apptainer().unwrap();
+        assert_eq!(config.executable(), DEFAULT_EXECUTABLE);
+        assert!(config.extra_args().is_empty());
+
+        let config = backends[1].kind().as_apptainer().unwrap();
+        assert_eq!(config.executable(), "singularity");
+        assert_eq!(config.extra_args(), ["--nv"]);
+    }
+}
@@ -0,0 +1,61 @@
+//! Builders for the [_Apptainer_ execution backend configuration](Config).
+
+use crate::backend::apptainer::Config;
+use crate::backend::generic::driver;
+
+/// A builder for an [Apptainer execution backend configuration object](Config).
+#[derive(Default)]
+pub struct Builder {
+    /// Configuration related to the command driver.
+    driver: driver::Config,
+
+    /// The executable used to run containers.
+    executable: Option<String>,
+
+    /// Additional arguments passed to `exec`.
+    extra_args: Vec<String>,
+}
+
+impl Builder {
+    /// Sets the driver configuration for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous driver configuration set
+    /// within the builder.
+    pub fn driver(mut self, driver: driver::Config) -> Self {
+        self.driver = driver;
+        self
+    }
+
+    /// Sets the executable used to run containers for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous executables set within the
+    /// builder.
+    pub fn executable(mut self, executable: impl Into<String>) -> Self {
+        self.executable = Some(executable.into());
+        self
+    }
+
+    /// Adds additional arguments passed to `exec` for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// Arguments are appended to any arguments previously added to the
+    /// builder.
+    pub fn extend_extra_args(mut self, args: impl IntoIterator<Item: Into<String>>) -> Self {
+        self.extra_args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Consumes `self` and returns a built [`Config`].
+    pub fn build(self) -> Config {
+        Config {
+            driver: self.driver,
+            executable: self.executable,
+            extra_args: self.extra_args,
+        }
+    }
+}
@@ -3,6 +3,8 @@
 use crate::backend::Config;
 use crate::backend::Defaults;
 use crate::backend::Kind;
+use crate::backend::QueueOrder;
+use crate::backend::Schedule;
 
 /// An error related to a [`Builder`].
 #[derive(Debug)]
@@ -41,6 +43,16 @@ pub struct Builder {
 
     /// The execution defaults.
     defaults: Option<Defaults>,
+
+    /// The windows during which the backend accepts submissions.
+    schedule: Schedule,
+
+    /// The order in which queued tasks are started.
+    queue_order: QueueOrder,
+
+    /// The number of seconds a running task is given to shut down gracefully
+    /// once it is canceled.
+    termination_grace_period: Option<u64>,
 }
 
 impl Builder {
@@ -87,6 +99,41 @@ impl Builder {
         self
     }
 
+    /// Sets the windows during which the backend accepts submissions for the
+    /// [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous schedules set within the
+    /// builder.
+    pub fn schedule(mut self, schedule: Schedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    /// Sets the order in which queued tasks are started for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous queue orders set within the
+    /// builder.
+    pub fn queue_order(mut self, order: QueueOrder) -> Self {
+        self.queue_order = order;
+        self
+    }
+
+    /// Sets the number of seconds a running task is given to shut down
+    /// gracefully once it is canceled for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous grace periods set within the
+    /// builder.
+    pub fn termination_grace_period(mut self, seconds: u64) -> Self {
+        self.termination_grace_period = Some(seconds);
+        self
+    }
+
     /// Consumes `self` and attempts to build a [`Config`].
     pub fn try_build(self) -> Result<Config> {
         let name = self.name.ok_or(Error::Missing("name"))?;
@@ -98,6 +145,9 @@ impl Builder {
             kind,
             max_tasks,
             defaults: self.defaults,
+            schedule: self.schedule,
+            queue_order: self.queue_order,
+            termination_grace_period: self.termination_grace_period,
         })
     }
 }
@@ -4,6 +4,8 @@
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::backend::RetryPolicy;
+
 /// Default resource requests.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
@@ -24,6 +26,16 @@ pub struct Defaults {
     /// gigabytes. These may be rounded to any level of precision that is
     /// required for a particular environment.
     disk: Option<f64>,
+
+    /// The compute zones (e.g., cloud availability zones) to run within.
+    ///
+    /// Backends that support zones (e.g., TES) place tasks within one of
+    /// these zones so that they run close to their data.
+    zones: Option<Vec<String>>,
+
+    /// The policy for retrying tasks that do not have a retry policy of their
+    /// own.
+    retry: Option<RetryPolicy>,
 }
 
 impl Defaults {
@@ -41,4 +53,15 @@ impl Defaults {
     pub fn disk(&self) -> Option<f64> {
         self.disk
     }
+
+    /// Gets the compute zones to run within (if configured).
+    pub fn zones(&self) -> Option<&[String]> {
+        self.zones.as_deref()
+    }
+
+    /// Gets the policy for retrying tasks that do not have a retry policy of
+    /// their own (if one is configured).
+    pub fn retry(&self) -> Option<&RetryPolicy> {
+        self.retry.as_ref()
+    }
 }
@@ -1,6 +1,11 @@
 //! Configuration related to the _Docker_ execution backend.
 
 mod builder;
+pub mod mount;
+pub mod security;
+
+use std::path::Path;
+use std::path::PathBuf;
 
 pub use builder::Builder;
 use serde::Deserialize;
@@ -22,6 +27,90 @@ pub struct Config {
     /// (regardless of whether the job was a success or failure).
     #[serde(default = "default_cleanup")]
     cleanup: bool,
+
+    /// The maximum number of bytes to fetch for any single task input.
+    input_size_limit: Option<u64>,
+
+    /// The directory within which fetched task inputs are cached.
+    input_cache_dir: Option<PathBuf>,
+
+    /// The maximum number of outputs of a task that are uploaded at once.
+    output_upload_concurrency: Option<usize>,
+
+    /// The maximum number of times a failed upload of a task output is
+    /// retried.
+    output_upload_retries: Option<u32>,
+
+    /// The propagation mode of volume mounts.
+    mount_propagation: Option<mount::Propagation>,
+
+    /// The SELinux relabeling applied to volume mounts.
+    selinux_relabel: Option<mount::Relabel>,
+
+    /// The reference directories that are mounted with a copy-on-write
+    /// overlay (with a scratch upper layer for each task).
+    #[serde(default)]
+    reference_overlays: Vec<mount::Overlay>,
+
+    /// The directory within which the scratch upper layers of reference
+    /// overlays are created.
+    ///
+    /// If this is not set, the system's temporary directory is used. Files
+    /// written to overlaid references are stored here, so it should be on a
+    /// filesystem with enough free space.
+    overlay_scratch_dir: Option<PathBuf>,
+
+    /// Whether or not containers are prevented from using swap.
+    ///
+    /// When enabled, the memory limit of a container (from the requested RAM
+    /// of the task) also limits its combined memory and swap usage.
+    #[serde(default)]
+    disable_swap: bool,
+
+    /// The user (and, optionally, the group) that containers run as (e.g.,
+    /// `1000:1000`).
+    ///
+    /// This is overridden by the user of an individual execution (if one is
+    /// set).
+    user: Option<String>,
+
+    /// The supplementary groups that the user within containers is added to.
+    #[serde(default)]
+    groups: Vec<String>,
+
+    /// Whether or not containers run as the user (and group) invoking
+    /// Crankshaft when no other user is set.
+    ///
+    /// This ensures that files written to bind-mounted directories are owned
+    /// by the invoking user (rather than by `root`).
+    #[serde(default)]
+    run_as_host_user: bool,
+
+    /// Whether or not executions are resubmitted (in a new container) when
+    /// their container is lost (e.g., because the Docker daemon restarted
+    /// and the container did not survive the restart).
+    ///
+    /// When disabled, the task fails instead.
+    #[serde(default)]
+    resubmit_lost_containers: bool,
+
+    /// The Docker API version (e.g., `1.41`) to pin the client to.
+    ///
+    /// If this is not set, the version is negotiated with the Docker daemon.
+    api_version: Option<String>,
+
+    /// The seccomp profile applied to containers (either `unconfined` or the
+    /// path to a JSON seccomp profile).
+    ///
+    /// If this is not set, Docker's default seccomp profile is applied.
+    seccomp_profile: Option<security::Seccomp>,
+
+    /// The AppArmor profile applied to containers (either `unconfined` or the
+    /// name of a profile loaded on the host).
+    ///
+    /// If this is not set, Docker's default AppArmor profile is applied (on
+    /// hosts with AppArmor enabled).
+    apparmor_profile: Option<String>,
 }
 
 impl Config {
@@ -36,6 +125,99 @@ impl Config {
     pub fn cleanup(&self) -> bool {
         self.cleanup
     }
+
+    /// Gets the maximum number of bytes to fetch for any single task input (if
+    /// a limit is configured).
+    pub fn input_size_limit(&self) -> Option<u64> {
+        self.input_size_limit
+    }
+
+    /// Gets the directory within which fetched task inputs are cached (if a
+    /// cache is configured).
+    pub fn input_cache_dir(&self) -> Option<&Path> {
+        self.input_cache_dir.as_deref()
+    }
+
+    /// Gets the maximum number of outputs of a task that are uploaded at once
+    /// (if configured).
+    pub fn output_upload_concurrency(&self) -> Option<usize> {
+        self.output_upload_concurrency
+    }
+
+    /// Gets the maximum number of times a failed upload of a task output is
+    /// retried (if configured).
+    pub fn output_upload_retries(&self) -> Option<u32> {
+        self.output_upload_retries
+    }
+
+    /// Gets the propagation mode of volume mounts (if one is configured).
+    pub fn mount_propagation(&self) -> Option<mount::Propagation> {
+        self.mount_propagation
+    }
+
+    /// Gets the SELinux relabeling applied to volume mounts (if one is
+    /// configured).
+    pub fn selinux_relabel(&self) -> Option<mount::Relabel> {
+        self.selinux_relabel
+    }
+
+    /// Gets the reference directories that are mounted with a copy-on-write
+    /// overlay.
+    pub fn reference_overlays(&self) -> &[mount::Overlay] {
+        &self.reference_overlays
+    }
+
+    /// Gets the directory within which the scratch upper layers of reference
+    /// overlays are created (if one is configured).
+    pub fn overlay_scratch_dir(&self) -> Option<&Path> {
+        self.overlay_scratch_dir.as_deref()
+    }
+
+    /// Gets whether or not containers are prevented from using swap.
+    pub fn disable_swap(&self) -> bool {
+        self.disable_swap
+    }
+
+    /// Gets the user (and, optionally, the group) that containers run as (if
+    /// one is configured).
+    pub fn user(&self) -> Option<&str> {
+        self.user.as_deref()
+    }
+
+    /// Gets the supplementary groups that the user within containers is added
+    /// to.
+    pub fn groups(&self) -> &[String] {
+        &self.groups
+    }
+
+    /// Gets whether or not containers run as the user (and group) invoking
+    /// Crankshaft when no other user is set.
+    pub fn run_as_host_user(&self) -> bool {
+        self.run_as_host_user
+    }
+
+    /// Gets whether or not executions are resubmitted when their container is
+    /// lost.
+    pub fn resubmit_lost_containers(&self) -> bool {
+        self.resubmit_lost_containers
+    }
+
+    /// Gets the Docker API version the client is pinned to (if it is
+    /// pinned).
+    pub fn api_version(&self) -> Option<&str> {
+        self.api_version.as_deref()
+    }
+
+    /// Gets the seccomp profile applied to containers (if one is configured).
+    pub fn seccomp_profile(&self) -> Option<&security::Seccomp> {
+        self.seccomp_profile.as_ref()
+    }
+
+    /// Gets the AppArmor profile applied to containers (if one is
+    /// configured).
+    pub fn apparmor_profile(&self) -> Option<&str> {
+        self.apparmor_profile.as_deref()
+    }
 }
 
 impl Default for Config {
@@ -1,7 +1,11 @@
 //! Builders for the [_Docker_ execution backend configuration](Config).
 
+use std::path::PathBuf;
+
 use crate::backend::docker::Config;
 use crate::backend::docker::DEFAULT_CLEANUP;
+use crate::backend::docker::mount;
+use crate::backend::docker::security;
 
 /// A builder for a [Docker execution backend configuration object](Config).
 // **NOTE:** all default values for this struct need to be tested below to
@@ -10,6 +14,58 @@ pub struct Builder {
     /// Whether or not to remove the containers after completion of the tasks
     /// (regardless of whether the job was a success or failure).
     cleanup: bool,
+
+    /// The maximum number of bytes to fetch for any single task input.
+    input_size_limit: Option<u64>,
+
+    /// The directory within which fetched task inputs are cached.
+    input_cache_dir: Option<PathBuf>,
+
+    /// The maximum number of outputs of a task that are uploaded at once.
+    output_upload_concurrency: Option<usize>,
+
+    /// The maximum number of times a failed upload of a task output is
+    /// retried.
+    output_upload_retries: Option<u32>,
+
+    /// The propagation mode of volume mounts.
+    mount_propagation: Option<mount::Propagation>,
+
+    /// The SELinux relabeling applied to volume mounts.
+    selinux_relabel: Option<mount::Relabel>,
+
+    /// The reference directories that are mounted with a copy-on-write
+    /// overlay.
+    reference_overlays: Vec<mount::Overlay>,
+
+    /// The directory within which the scratch upper layers of reference
+    /// overlays are created.
+    overlay_scratch_dir: Option<PathBuf>,
+
+    /// Whether or not containers are prevented from using swap.
+    disable_swap: bool,
+
+    /// The user (and, optionally, the group) that containers run as.
+    user: Option<String>,
+
+    /// The supplementary groups that the user within containers is added to.
+    groups: Vec<String>,
+
+    /// Whether or not containers run as the user (and group) invoking
+    /// Crankshaft when no other user is set.
+    run_as_host_user: bool,
+
+    /// Whether or not executions are resubmitted when their container is lost.
+    resubmit_lost_containers: bool,
+
+    /// The Docker API version to pin the client to.
+    api_version: Option<String>,
+
+    /// The seccomp profile applied to containers.
+    seccomp_profile: Option<security::Seccomp>,
+
+    /// The AppArmor profile applied to containers.
+    apparmor_profile: Option<String>,
 }
 
 impl Default for Builder {
@@ -17,6 +73,39 @@ impl Default for Builder {
         Self {
             // By default, Docker should clean up containers.
             cleanup: DEFAULT_CLEANUP,
+            // By default, task inputs are not limited in size.
+            input_size_limit: None,
+            // By default, task inputs are not cached.
+            input_cache_dir: None,
+            // By default, the engine's default upload concurrency is used.
+            output_upload_concurrency: None,
+            // By default, the engine's default number of upload retries is
+            // used.
+            output_upload_retries: None,
+            // By default, Docker's default propagation mode is used.
+            mount_propagation: None,
+            // By default, volume mounts are not relabeled.
+            selinux_relabel: None,
+            // By default, no references are overlaid.
+            reference_overlays: Vec::new(),
+            // By default, the system's temporary directory is used.
+            overlay_scratch_dir: None,
+            // By default, Docker's default swap limit is used.
+            disable_swap: false,
+            // By default, the user of the container image is used.
+            user: None,
+            // By default, no supplementary groups are added.
+            groups: Vec::new(),
+            // By default, containers do not run as the invoking user.
+            run_as_host_user: false,
+            // By default, tasks with lost containers fail.
+            resubmit_lost_containers: false,
+            // By default, the Docker API version is negotiated.
+            api_version: None,
+            // By default, Docker's default seccomp profile is applied.
+            seccomp_profile: None,
+            // By default, Docker's default AppArmor profile is applied.
+            apparmor_profile: None,
         }
     }
 }
@@ -33,10 +122,215 @@ impl Builder {
         self
     }
 
+    /// Sets the maximum number of bytes to fetch for any single task input
+    /// for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous input size limits set within
+    /// the builder.
+    pub fn input_size_limit(mut self, bytes: u64) -> Self {
+        self.input_size_limit = Some(bytes);
+        self
+    }
+
+    /// Sets the directory within which fetched task inputs are cached for the
+    /// [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous input cache directories set
+    /// within the builder.
+    pub fn input_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.input_cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Sets the maximum number of outputs of a task that are uploaded at once
+    /// for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous upload concurrencies set
+    /// within the builder.
+    pub fn output_upload_concurrency(mut self, concurrency: usize) -> Self {
+        self.output_upload_concurrency = Some(concurrency);
+        self
+    }
+
+    /// Sets the maximum number of times a failed upload of a task output is
+    /// retried for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous upload retries set within
+    /// the builder.
+    pub fn output_upload_retries(mut self, retries: u32) -> Self {
+        self.output_upload_retries = Some(retries);
+        self
+    }
+
+    /// Sets the propagation mode of volume mounts for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous propagation modes set within
+    /// the builder.
+    pub fn mount_propagation(mut self, propagation: mount::Propagation) -> Self {
+        self.mount_propagation = Some(propagation);
+        self
+    }
+
+    /// Sets the SELinux relabeling applied to volume mounts for the
+    /// [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous SELinux relabeling set within
+    /// the builder.
+    pub fn selinux_relabel(mut self, relabel: mount::Relabel) -> Self {
+        self.selinux_relabel = Some(relabel);
+        self
+    }
+
+    /// Adds reference directories that are mounted with a copy-on-write
+    /// overlay for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// Overlays are appended to any overlays previously added to the builder.
+    pub fn extend_reference_overlays(
+        mut self,
+        overlays: impl IntoIterator<Item = mount::Overlay>,
+    ) -> Self {
+        self.reference_overlays.extend(overlays);
+        self
+    }
+
+    /// Sets the directory within which the scratch upper layers of reference
+    /// overlays are created for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous scratch directories set
+    /// within the builder.
+    pub fn overlay_scratch_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.overlay_scratch_dir = Some(dir.into());
+        self
+    }
+
+    /// Sets whether or not containers are prevented from using swap for the
+    /// [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous swap settings set within the
+    /// builder.
+    pub fn disable_swap(mut self, disable: bool) -> Self {
+        self.disable_swap = disable;
+        self
+    }
+
+    /// Sets the user (and, optionally, the group) that containers run as for
+    /// the [`Builder`] (e.g., `1000:1000`).
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous users set within the builder.
+    pub fn user(mut self, user: impl Into<String>) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    /// Adds supplementary groups that the user within containers is added to
+    /// for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// Groups are appended to any groups previously added to the builder.
+    pub fn extend_groups(mut self, groups: impl IntoIterator<Item: Into<String>>) -> Self {
+        self.groups.extend(groups.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets whether or not containers run as the user (and group) invoking
+    /// Crankshaft when no other user is set for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous host user settings set within
+    /// the builder.
+    pub fn run_as_host_user(mut self, value: bool) -> Self {
+        self.run_as_host_user = value;
+        self
+    }
+
+    /// Sets whether or not executions are resubmitted when their container is
+    /// lost for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous resubmission settings set
+    /// within the builder.
+    pub fn resubmit_lost_containers(mut self, value: bool) -> Self {
+        self.resubmit_lost_containers = value;
+        self
+    }
+
+    /// Sets the Docker API version (e.g., `1.41`) to pin the client to for
+    /// the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous API versions set within the
+    /// builder.
+    pub fn api_version(mut self, version: impl Into<String>) -> Self {
+        self.api_version = Some(version.into());
+        self
+    }
+
+    /// Sets the seccomp profile applied to containers for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous seccomp profiles set within
+    /// the builder.
+    pub fn seccomp_profile(mut self, profile: security::Seccomp) -> Self {
+        self.seccomp_profile = Some(profile);
+        self
+    }
+
+    /// Sets the AppArmor profile applied to containers for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous AppArmor profiles set within
+    /// the builder.
+    pub fn apparmor_profile(mut self, profile: impl Into<String>) -> Self {
+        self.apparmor_profile = Some(profile.into());
+        self
+    }
+
     /// Consumes `self` and returns a built [`Config`].
     pub fn build(self) -> Config {
         Config {
             cleanup: self.cleanup,
+            input_size_limit: self.input_size_limit,
+            input_cache_dir: self.input_cache_dir,
+            output_upload_concurrency: self.output_upload_concurrency,
+            output_upload_retries: self.output_upload_retries,
+            mount_propagation: self.mount_propagation,
+            selinux_relabel: self.selinux_relabel,
+            reference_overlays: self.reference_overlays,
+            overlay_scratch_dir: self.overlay_scratch_dir,
+            disable_swap: self.disable_swap,
+            user: self.user,
+            groups: self.groups,
+            run_as_host_user: self.run_as_host_user,
+            resubmit_lost_containers: self.resubmit_lost_containers,
+            api_version: self.api_version,
+            seccomp_profile: self.seccomp_profile,
+            apparmor_profile: self.apparmor_profile,
         }
     }
 }
@@ -51,5 +345,35 @@ mod tests {
 
         // Docker should clean up containers by default.
         assert!(options.cleanup());
+
+        // Task inputs should not be limited or cached by default.
+        assert_eq!(options.input_size_limit(), None);
+        assert_eq!(options.input_cache_dir(), None);
+
+        // Volume mounts should use Docker's defaults.
+        assert_eq!(options.mount_propagation(), None);
+        assert_eq!(options.selinux_relabel(), None);
+
+        // No references should be overlaid by default.
+        assert!(options.reference_overlays().is_empty());
+        assert_eq!(options.overlay_scratch_dir(), None);
+
+        // Containers should be allowed to use swap by default.
+        assert!(!options.disable_swap());
+
+        // Containers should run as the user of the image by default.
+        assert_eq!(options.user(), None);
+        assert!(options.groups().is_empty());
+        assert!(!options.run_as_host_user());
+
+        // Tasks with lost containers should fail by default.
+        assert!(!options.resubmit_lost_containers());
+
+        // The Docker API version should be negotiated by default.
+        assert_eq!(options.api_version(), None);
+
+        // Docker's default security profiles should be applied by default.
+        assert_eq!(options.seccomp_profile(), None);
+        assert_eq!(options.apparmor_profile(), None);
     }
 }
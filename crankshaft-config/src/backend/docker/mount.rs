@@ -0,0 +1,90 @@
+//! Configuration related to the mounts within the _Docker_ execution backend.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+/// The propagation mode of a bind mount.
+///
+/// See the [Docker documentation] for more details.
+///
+/// [Docker documentation]: https://docs.docker.com/engine/storage/bind-mounts/#configure-bind-propagation
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Propagation {
+    /// Mounts are not propagated in either direction.
+    Private,
+
+    /// Like [`Propagation::Private`], but applies recursively to submounts.
+    Rprivate,
+
+    /// Mounts are propagated in both directions.
+    Shared,
+
+    /// Like [`Propagation::Shared`], but applies recursively to submounts.
+    Rshared,
+
+    /// Mounts are propagated from the host into the container only.
+    Slave,
+
+    /// Like [`Propagation::Slave`], but applies recursively to submounts.
+    Rslave,
+}
+
+/// The SELinux relabeling applied to the source of a bind mount.
+///
+/// This is required on hosts with SELinux enforcing (e.g., RHEL-based hosts),
+/// as containers are otherwise denied access to the mounted directories.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Relabel {
+    /// The content is labeled such that it can be shared among multiple
+    /// containers (the `z` option).
+    Shared,
+
+    /// The content is labeled such that it is private to the container (the
+    /// `Z` option).
+    Private,
+}
+
+/// A reference directory that is mounted with a copy-on-write overlay.
+///
+/// The directory on the host is the read-only lower layer of an overlay
+/// filesystem, and each task is given its own scratch upper layer. Tools that
+/// write files next to their references (e.g., index files) can do so without
+/// modifying the references or requiring a full copy of them per task.
+///
+/// This requires the Docker daemon to run on a Linux host that supports
+/// `overlay` filesystems.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Overlay {
+    /// The directory on the host containing the references.
+    source: PathBuf,
+
+    /// The path the directory is mounted at within containers.
+    target: String,
+}
+
+impl Overlay {
+    /// Creates a new [`Overlay`] that mounts the `source` directory on the host
+    /// at the `target` path within containers.
+    pub fn new(source: impl Into<PathBuf>, target: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            target: target.into(),
+        }
+    }
+
+    /// Gets the directory on the host containing the references.
+    pub fn source(&self) -> &Path {
+        &self.source
+    }
+
+    /// Gets the path the directory is mounted at within containers.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+}
@@ -0,0 +1,72 @@
+//! Configuration related to the security options of containers within the
+//! _Docker_ execution backend.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+/// The value of a seccomp profile that disables seccomp confinement.
+const UNCONFINED: &str = "unconfined";
+
+/// The seccomp profile applied to containers.
+///
+/// Within a configuration file, this is either `unconfined` or the path to a
+/// JSON seccomp profile.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(from = "String", into = "String")]
+pub enum Seccomp {
+    /// No seccomp profile is applied (i.e., every system call is allowed).
+    Unconfined,
+
+    /// The seccomp profile at the given path is applied.
+    Profile(PathBuf),
+}
+
+impl Seccomp {
+    /// Gets the path to the seccomp profile (if one is applied).
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Seccomp::Unconfined => None,
+            Seccomp::Profile(path) => Some(path),
+        }
+    }
+}
+
+impl From<String> for Seccomp {
+    fn from(value: String) -> Self {
+        if value == UNCONFINED {
+            Seccomp::Unconfined
+        } else {
+            Seccomp::Profile(PathBuf::from(value))
+        }
+    }
+}
+
+impl From<Seccomp> for String {
+    fn from(value: Seccomp) -> Self {
+        match value {
+            Seccomp::Unconfined => String::from(UNCONFINED),
+            Seccomp::Profile(path) => path.display().to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seccomp_profiles_are_parsed() {
+        assert_eq!(
+            Seccomp::from(String::from("unconfined")),
+            Seccomp::Unconfined
+        );
+        assert_eq!(
+            Seccomp::from(String::from("/etc/docker/seccomp.json")),
+            Seccomp::Profile(PathBuf::from("/etc/docker/seccomp.json"))
+        );
+        assert_eq!(String::from(Seccomp::Unconfined), "unconfined");
+    }
+}
@@ -61,6 +61,45 @@ pub fn substitute(input: &str, replacements: &HashMap<String, String>) -> String
         .to_string()
 }
 
+/// A segment of a generic configuration value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Segment<'a> {
+    /// Literal text.
+    Literal(&'a str),
+
+    /// A placeholder (e.g., `~{job_id}`) with the provided key.
+    Placeholder(&'a str),
+}
+
+/// Splits a generic configuration value into its literal text and its
+/// placeholders (in order).
+///
+/// Replacing each placeholder with its value and concatenating the segments
+/// is equivalent to calling [`substitute()`].
+pub fn segments(input: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut end = 0;
+
+    for captures in PLACEHOLDER_REGEX.captures_iter(input) {
+        // SAFETY: the `PLACEHOLDER_REGEX` above is hardcoded to ensure a group
+        // is included. This is tested statically below.
+        let (placeholder, key) = (captures.get(0).unwrap(), captures.get(1).unwrap());
+
+        if placeholder.start() > end {
+            segments.push(Segment::Literal(&input[end..placeholder.start()]));
+        }
+
+        segments.push(Segment::Placeholder(key.as_str()));
+        end = placeholder.end();
+    }
+
+    if end < input.len() {
+        segments.push(Segment::Literal(&input[end..]));
+    }
+
+    segments
+}
+
 /// A configuration object for a generic execution backend.
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
@@ -70,6 +109,12 @@ pub struct Config {
     driver: driver::Config,
 
     /// The script used for job submission.
+    ///
+    /// If the script references the `~{exit_code_file}` substitution (e.g.,
+    /// by wrapping the command as `~{shell}; echo $? > ~{exit_code_file}`),
+    /// the exit code of a job is read from that file once the monitor script
+    /// reports that the job has finished. The path is quoted for the shell
+    /// when it is substituted, so it must not be quoted within the script.
     submit: String,
 
     /// A regex used to extract the job id from standard out.
@@ -84,6 +129,64 @@ pub struct Config {
     /// The script used to kill a job.
     kill: String,
 
+    /// The script used to forcibly kill a job that is still running once the
+    /// termination grace period of a canceled task has elapsed.
+    ///
+    /// The [kill script](Self::kill) is expected to request a graceful
+    /// shutdown (e.g., by sending `SIGTERM`), while this script should not
+    /// allow the job to continue (e.g., by sending `SIGKILL`). If this is not
+    /// set, only the kill script is run.
+    force_kill: Option<String>,
+
+    /// The script used to query the scheduler state of a submitted job.
+    ///
+    /// The (trimmed) standard output of the script is compared against the
+    /// [suspended states](Self::suspended_states).
+    state: Option<String>,
+
+    /// The scheduler states (compared case-insensitively) that indicate that a
+    /// job is held or suspended.
+    #[serde(default)]
+    suspended_states: Vec<String>,
+
+    /// The number of seconds a job may remain held or suspended before it is
+    /// released (if a release script is configured) or failed.
+    ///
+    /// If this is not set, held or suspended jobs are waited on indefinitely.
+    suspended_grace_period: Option<u64>,
+
+    /// The script used to release a held or suspended job.
+    release: Option<String>,
+
+    /// The script used to query the accounting data of a completed job.
+    ///
+    /// The standard output of the script must either be a JSON object or
+    /// `key=value` pairs (one per line).
+    accounting: Option<String>,
+
+    /// The script used to probe the resources currently used by a running
+    /// job.
+    ///
+    /// The script is run each time a job is monitored and found to be
+    /// running. Its standard output must either be a JSON object or
+    /// `key=value` pairs (one per line) with any of the `cpu_percent`,
+    /// `memory` (in bytes), and `disk` (in bytes) keys.
+    usage: Option<String>,
+
+    /// The maximum number of CPU cores a task may request (e.g., the number
+    /// of cores of the largest node within the cluster).
+    max_cpu: Option<usize>,
+
+    /// The maximum amount of RAM (in gigabytes) a task may request.
+    max_ram: Option<f64>,
+
+    /// The maximum number of outputs of a task that are uploaded at once.
+    output_upload_concurrency: Option<usize>,
+
+    /// The maximum number of times a failed upload of a task output is
+    /// retried.
+    output_upload_retries: Option<u32>,
+
     /// The runtime attributes.
     attributes: Option<HashMap<String, String>>,
 }
@@ -139,6 +242,16 @@ impl Config {
         &self.submit
     }
 
+    /// Gets whether or not the submit command writes the exit code of a job to
+    /// the file given by the `~{exit_code_file}` substitution.
+    ///
+    /// The exit code file is only supported with a POSIX shell (see
+    /// [`Shell::is_posix()`](driver::Shell::is_posix)), as the file is read
+    /// (and its path is quoted) using POSIX shell syntax.
+    pub fn uses_exit_code_file(&self) -> bool {
+        self.submit.contains("~{exit_code_file}")
+    }
+
     /// Gets the job id regex.
     pub fn job_id_regex(&self) -> Option<&str> {
         self.job_id_regex.as_deref()
@@ -159,6 +272,103 @@ impl Config {
         self.kill.as_ref()
     }
 
+    /// Gets the force kill command (if it exists).
+    pub fn force_kill(&self) -> Option<&str> {
+        self.force_kill.as_deref()
+    }
+
+    /// Gets the state command (if it exists).
+    pub fn state(&self) -> Option<&str> {
+        self.state.as_deref()
+    }
+
+    /// Gets the scheduler states that indicate that a job is held or
+    /// suspended.
+    pub fn suspended_states(&self) -> &[String] {
+        &self.suspended_states
+    }
+
+    /// Gets whether or not a scheduler state indicates that a job is held or
+    /// suspended.
+    pub fn is_suspended(&self, state: &str) -> bool {
+        let state = state.trim();
+
+        self.suspended_states
+            .iter()
+            .any(|suspended| suspended.eq_ignore_ascii_case(state))
+    }
+
+    /// Gets the number of seconds a job may remain held or suspended (if it
+    /// exists).
+    pub fn suspended_grace_period(&self) -> Option<u64> {
+        self.suspended_grace_period
+    }
+
+    /// Gets the release command (if it exists).
+    pub fn release(&self) -> Option<&str> {
+        self.release.as_deref()
+    }
+
+    /// Gets the accounting command (if it exists).
+    pub fn accounting(&self) -> Option<&str> {
+        self.accounting.as_deref()
+    }
+
+    /// Gets the resource usage command (if it exists).
+    pub fn usage(&self) -> Option<&str> {
+        self.usage.as_deref()
+    }
+
+    /// Gets the maximum number of CPU cores a task may request (if it
+    /// exists).
+    pub fn max_cpu(&self) -> Option<usize> {
+        self.max_cpu
+    }
+
+    /// Gets the maximum amount of RAM (in gigabytes) a task may request (if
+    /// it exists).
+    pub fn max_ram(&self) -> Option<f64> {
+        self.max_ram
+    }
+
+    /// Gets the maximum number of outputs of a task that are uploaded at once
+    /// (if configured).
+    pub fn output_upload_concurrency(&self) -> Option<usize> {
+        self.output_upload_concurrency
+    }
+
+    /// Gets the maximum number of times a failed upload of a task output is
+    /// retried (if configured).
+    pub fn output_upload_retries(&self) -> Option<u32> {
+        self.output_upload_retries
+    }
+
+    /// Checks requested resources against the maximums of the backend.
+    ///
+    /// Returns a description of every resource that exceeds its maximum (or
+    /// [`None`] if none do).
+    pub fn check_limits(&self, cpu: Option<usize>, ram: Option<f64>) -> Option<String> {
+        let mut exceeded = Vec::new();
+
+        if let (Some(cpu), Some(max)) = (cpu, self.max_cpu) {
+            if cpu > max {
+                exceeded.push(format!("{cpu} CPU cores requested (maximum: {max})"));
+            }
+        }
+
+        if let (Some(ram), Some(max)) = (ram, self.max_ram) {
+            if ram > max {
+                exceeded.push(format!("{ram} GB of RAM requested (maximum: {max} GB)"));
+            }
+        }
+
+        if exceeded.is_empty() {
+            None
+        } else {
+            Some(exceeded.join(", "))
+        }
+    }
+
     /// Gets the runtime attributes.
     pub fn attributes(&self) -> Option<&HashMap<String, String>> {
         self.attributes.as_ref()
@@ -174,21 +384,108 @@ impl Config {
         self.resolve(&self.monitor, substitutions)
     }
 
+    /// Gets the state command (if it exists) with all of the substitutions
+    /// resolved.
+    pub fn resolve_state(&self, substitutions: &HashMap<String, String>) -> Option<ResolveResult> {
+        self.state
+            .as_ref()
+            .map(|state| self.resolve(state, substitutions))
+    }
+
+    /// Gets the release command (if it exists) with all of the substitutions
+    /// resolved.
+    pub fn resolve_release(
+        &self,
+        substitutions: &HashMap<String, String>,
+    ) -> Option<ResolveResult> {
+        self.release
+            .as_ref()
+            .map(|release| self.resolve(release, substitutions))
+    }
+
+    /// Gets the accounting command (if it exists) with all of the
+    /// substitutions resolved.
+    pub fn resolve_accounting(
+        &self,
+        substitutions: &HashMap<String, String>,
+    ) -> Option<ResolveResult> {
+        self.accounting
+            .as_ref()
+            .map(|accounting| self.resolve(accounting, substitutions))
+    }
+
+    /// Gets the resource usage command (if it exists) with all of the
+    /// substitutions resolved.
+    pub fn resolve_usage(&self, substitutions: &HashMap<String, String>) -> Option<ResolveResult> {
+        self.usage
+            .as_ref()
+            .map(|usage| self.resolve(usage, substitutions))
+    }
+
     /// Gets the kill command with all of the substitutions resolved.
     pub fn resolve_kill(&self, substitutions: HashMap<String, String>) -> ResolveResult {
         self.resolve(&self.kill, &substitutions)
     }
+
+    /// Gets the force kill command (if it exists) with all of the
+    /// substitutions resolved.
+    pub fn resolve_force_kill(
+        &self,
+        substitutions: &HashMap<String, String>,
+    ) -> Option<ResolveResult> {
+        self.force_kill
+            .as_ref()
+            .map(|force_kill| self.resolve(force_kill, substitutions))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn limits_are_checked() {
+        let config = Config::builder()
+            .default_driver()
+            .submit("")
+            .monitor("")
+            .kill("")
+            .max_cpu(4usize)
+            .max_ram(16.0)
+            .try_build()
+            .unwrap();
+
+        assert_eq!(config.check_limits(Some(4), Some(16.0)), None);
+        assert_eq!(config.check_limits(None, None), None);
+        assert_eq!(
+            config.check_limits(Some(8), Some(32.0)).unwrap(),
+            "8 CPU cores requested (maximum: 4), 32 GB of RAM requested (maximum: 16 GB)"
+        );
+    }
+
     #[test]
     fn the_placeholder_regex_unwraps() {
         let _ = PLACEHOLDER_REGEX;
     }
 
+    #[test]
+    fn values_are_split_into_segments() {
+        assert_eq!(
+            segments("bkill ~{job_id}; echo ~{job_id}~{cwd}!"),
+            [
+                Segment::Literal("bkill "),
+                Segment::Placeholder("job_id"),
+                Segment::Literal("; echo "),
+                Segment::Placeholder("job_id"),
+                Segment::Placeholder("cwd"),
+                Segment::Literal("!"),
+            ]
+        );
+
+        assert_eq!(segments("true"), [Segment::Literal("true")]);
+        assert!(segments("").is_empty());
+    }
+
     #[test]
     fn replacement_works() -> Result<(), Box<dyn std::error::Error>> {
         let mut replacements = HashMap::new();
@@ -198,4 +495,30 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn suspended_states_are_matched() {
+        let config = Config::builder()
+            .default_driver()
+            .submit("echo submit")
+            .monitor("echo monitor")
+            .kill("echo kill")
+            .state("squeue -h -j ~{job_id} -o %T")
+            .extend_suspended_states([String::from("SUSPENDED"), String::from("held")])
+            .try_build()
+            .unwrap();
+
+        assert!(config.is_suspended("suspended\n"));
+        assert!(config.is_suspended("HELD"));
+        assert!(!config.is_suspended("RUNNING"));
+
+        let mut substitutions = HashMap::new();
+        substitutions.insert(String::from("job_id"), String::from("42"));
+        assert_eq!(
+            config.resolve_state(&substitutions).unwrap().unwrap(),
+            "squeue -h -j 42 -o %T"
+        );
+        assert!(config.resolve_release(&substitutions).is_none());
+        assert!(config.resolve_force_kill(&substitutions).is_none());
+    }
 }
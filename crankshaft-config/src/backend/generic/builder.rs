@@ -49,6 +49,40 @@ pub struct Builder {
     /// The script used to kill a job.
     kill: Option<String>,
 
+    /// The script used to forcibly kill a job.
+    force_kill: Option<String>,
+
+    /// The script used to query the scheduler state of a submitted job.
+    state: Option<String>,
+
+    /// The scheduler states that indicate that a job is held or suspended.
+    suspended_states: Vec<String>,
+
+    /// The number of seconds a job may remain held or suspended.
+    suspended_grace_period: Option<u64>,
+
+    /// The script used to release a held or suspended job.
+    release: Option<String>,
+
+    /// The script used to query the accounting data of a completed job.
+    accounting: Option<String>,
+
+    /// The script used to probe the resources used by a running job.
+    usage: Option<String>,
+
+    /// The maximum number of CPU cores a task may request.
+    max_cpu: Option<usize>,
+
+    /// The maximum amount of RAM (in gigabytes) a task may request.
+    max_ram: Option<f64>,
+
+    /// The maximum number of outputs of a task that are uploaded at once.
+    output_upload_concurrency: Option<usize>,
+
+    /// The maximum number of times a failed upload of a task output is
+    /// retried.
+    output_upload_retries: Option<u32>,
+
     /// The runtime attributes.
     attributes: Option<HashMap<String, String>>,
 }
@@ -132,6 +166,126 @@ impl Builder {
         self
     }
 
+    /// Sets the force kill command for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous force kill commands set
+    /// within the builder.
+    pub fn force_kill(mut self, command: impl Into<String>) -> Self {
+        self.force_kill = Some(command.into());
+        self
+    }
+
+    /// Sets the state command for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous state commands set within
+    /// the builder.
+    pub fn state(mut self, command: impl Into<String>) -> Self {
+        self.state = Some(command.into());
+        self
+    }
+
+    /// Extends the suspended states in the [`Builder`].
+    pub fn extend_suspended_states(mut self, values: impl IntoIterator<Item = String>) -> Self {
+        self.suspended_states.extend(values);
+        self
+    }
+
+    /// Sets the suspended grace period (in seconds) for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous suspended grace periods set
+    /// within the builder.
+    pub fn suspended_grace_period(mut self, seconds: impl Into<u64>) -> Self {
+        self.suspended_grace_period = Some(seconds.into());
+        self
+    }
+
+    /// Sets the release command for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous release commands set within
+    /// the builder.
+    pub fn release(mut self, command: impl Into<String>) -> Self {
+        self.release = Some(command.into());
+        self
+    }
+
+    /// Sets the accounting command for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous accounting commands set
+    /// within the builder.
+    pub fn accounting(mut self, command: impl Into<String>) -> Self {
+        self.accounting = Some(command.into());
+        self
+    }
+
+    /// Sets the resource usage command for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous resource usage commands set
+    /// within the builder.
+    pub fn usage(mut self, command: impl Into<String>) -> Self {
+        self.usage = Some(command.into());
+        self
+    }
+
+    /// Sets the maximum number of CPU cores a task may request for the
+    /// [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous maximum number of CPU cores
+    /// set within the builder.
+    pub fn max_cpu(mut self, value: impl Into<usize>) -> Self {
+        self.max_cpu = Some(value.into());
+        self
+    }
+
+    /// Sets the maximum amount of RAM (in gigabytes) a task may request for
+    /// the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous maximum amount of RAM set
+    /// within the builder.
+    pub fn max_ram(mut self, value: impl Into<f64>) -> Self {
+        self.max_ram = Some(value.into());
+        self
+    }
+
+    /// Sets the maximum number of outputs of a task that are uploaded at once
+    /// for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous upload concurrency set
+    /// within the builder.
+    pub fn output_upload_concurrency(mut self, concurrency: usize) -> Self {
+        self.output_upload_concurrency = Some(concurrency);
+        self
+    }
+
+    /// Sets the maximum number of times a failed upload of a task output is
+    /// retried for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous upload retries set within
+    /// the builder.
+    pub fn output_upload_retries(mut self, retries: u32) -> Self {
+        self.output_upload_retries = Some(retries);
+        self
+    }
+
     /// Extends the runtime attributes in the [`Builder`].
     pub fn extend_attrs(mut self, values: impl IntoIterator<Item = (String, String)>) -> Self {
         let mut attributes = self.attributes.unwrap_or_default();
@@ -154,6 +308,17 @@ impl Builder {
             monitor,
             monitor_frequency: self.monitor_frequency,
             kill,
+            force_kill: self.force_kill,
+            state: self.state,
+            suspended_states: self.suspended_states,
+            suspended_grace_period: self.suspended_grace_period,
+            release: self.release,
+            accounting: self.accounting,
+            usage: self.usage,
+            max_cpu: self.max_cpu,
+            max_ram: self.max_ram,
+            output_upload_concurrency: self.output_upload_concurrency,
+            output_upload_retries: self.output_upload_retries,
             attributes: self.attributes,
         })
     }
@@ -56,6 +56,24 @@ impl Builder {
         self
     }
 
+    /// Configures the generic backend to localize to a pool of remote machines
+    /// over SSH before executing commands.
+    ///
+    /// Each execution is dispatched to one of the reachable hosts in the pool
+    /// (see [`Locale::SSHPool`]).
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous locale declarations provided
+    /// to the builder.
+    pub fn with_ssh_pool(mut self, hosts: impl IntoIterator<Item = ssh::Host>) -> Self {
+        self.locale = Some(Locale::SSHPool {
+            hosts: hosts.into_iter().collect(),
+        });
+
+        self
+    }
+
     /// Configures the generic backend to use the specified shell for execution.
     ///
     /// # Notes
@@ -22,4 +22,13 @@ pub enum Locale {
         #[serde(default)]
         options: ssh::Config,
     },
+
+    /// Remote execution over SSH across a pool of hosts.
+    ///
+    /// Executions are distributed across the reachable hosts in the pool in
+    /// proportion to their weights (and within their limits).
+    SSHPool {
+        /// The hosts within the pool.
+        hosts: Vec<ssh::Host>,
+    },
 }
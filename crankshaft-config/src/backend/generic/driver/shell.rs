@@ -2,12 +2,17 @@
 
 use std::ffi::OsString;
 
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
 use serde::Deserialize;
 use serde::Serialize;
 
 /// The expected path for the `env` binary.
 const ENV_PATH: &str = "/usr/bin/env";
 
+/// The arguments passed to PowerShell before the command.
+const POWERSHELL_ARGS: [&str; 3] = ["powershell", "-NoProfile", "-NonInteractive"];
+
 /// A shell within which to run commands.
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
@@ -18,28 +23,118 @@ pub enum Shell {
 
     /// Run commands using `sh`.
     Sh,
+
+    /// Run commands using Windows PowerShell.
+    #[serde(rename = "powershell")]
+    PowerShell,
+
+    /// Run commands using the Windows command prompt (`cmd.exe`).
+    Cmd,
 }
 
 impl Shell {
+    /// Gets whether or not the shell is a POSIX shell (i.e., `bash` or `sh`).
+    pub fn is_posix(&self) -> bool {
+        matches!(self, Shell::Bash | Shell::Sh)
+    }
+
     /// Gets a series of args that can be passed through to a driver for
     /// commands.
-    pub fn args<I, S>(&self, args: I) -> impl Iterator<Item = OsString> + use<I, S>
+    pub fn args<I>(&self, args: I) -> impl Iterator<Item = OsString> + use<I>
     where
         I: IntoIterator<Item = OsString>,
     {
         let base_args = match self {
-            Shell::Bash => [
+            Shell::Bash => vec![
                 OsString::from(ENV_PATH),
                 OsString::from("bash"),
                 OsString::from("-c"),
             ],
-            Shell::Sh => [
+            Shell::Sh => vec![
                 OsString::from(ENV_PATH),
                 OsString::from("sh"),
                 OsString::from("-c"),
             ],
+            Shell::PowerShell => POWERSHELL_ARGS
+                .into_iter()
+                .chain(["-Command"])
+                .map(OsString::from)
+                .collect(),
+            Shell::Cmd => vec![OsString::from("cmd"), OsString::from("/C")],
         };
 
         base_args.into_iter().chain(args)
     }
+
+    /// Renders a command as a single command line that runs the command within
+    /// the shell.
+    ///
+    /// This is used when the command line itself is interpreted by another
+    /// shell (e.g., the login shell of a remote user over SSH), so the command
+    /// is quoted such that it reaches this shell unchanged:
+    ///
+    /// * For `bash` and `sh`, the command is quoted using POSIX shell quoting.
+    /// * For PowerShell, the command is passed via `-EncodedCommand` (base64
+    ///   encoded UTF-16LE), which sidesteps quoting entirely.
+    /// * For `cmd.exe`, the command is wrapped in quotes and passed with `/S`,
+    ///   which instructs `cmd.exe` to strip only the outermost quotes.
+    pub fn render(&self, command: &str) -> String {
+        match self {
+            Shell::Bash | Shell::Sh => {
+                let shell = if let Shell::Bash = self { "bash" } else { "sh" };
+
+                // NOTE: quoting only fails for commands containing a nul byte,
+                // which cannot be passed as an argument anyway. In that case,
+                // the command is passed through as is.
+                let command = shlex::try_quote(command)
+                    .map(|quoted| quoted.into_owned())
+                    .unwrap_or_else(|_| command.to_owned());
+
+                format!("{ENV_PATH} {shell} -c {command}")
+            }
+            Shell::PowerShell => {
+                let bytes = command
+                    .encode_utf16()
+                    .flat_map(u16::to_le_bytes)
+                    .collect::<Vec<_>>();
+
+                format!(
+                    "{} -EncodedCommand {}",
+                    POWERSHELL_ARGS.join(" "),
+                    STANDARD.encode(bytes)
+                )
+            }
+            Shell::Cmd => format!("cmd /S /C \"{command}\""),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn posix_commands_are_quoted() {
+        assert_eq!(
+            Shell::Bash.render("echo 'hello world'"),
+            r#"/usr/bin/env bash -c "echo 'hello world'""#
+        );
+        assert_eq!(Shell::Sh.render("true"), "/usr/bin/env sh -c true");
+    }
+
+    #[test]
+    fn powershell_commands_are_encoded() {
+        assert_eq!(
+            Shell::PowerShell.render("exit 1"),
+            "powershell -NoProfile -NonInteractive -EncodedCommand ZQB4AGkAdAAgADEA"
+        );
+    }
+
+    #[test]
+    fn cmd_commands_are_wrapped() {
+        assert_eq!(
+            Shell::Cmd.render(r#"echo "a" & exit /b 2"#),
+            r#"cmd /S /C "echo "a" & exit /b 2""#
+        );
+    }
 }
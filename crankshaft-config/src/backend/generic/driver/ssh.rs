@@ -55,3 +55,68 @@ impl Default for Config {
         }
     }
 }
+
+/// The default weight of a host within a pool of SSH hosts.
+pub const DEFAULT_HOST_WEIGHT: u32 = 1;
+
+/// A utility function used to set the default value for `weight` via serde.
+fn default_weight() -> u32 {
+    DEFAULT_HOST_WEIGHT
+}
+
+/// A host within a pool of SSH hosts.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Host {
+    /// The host for the connection.
+    pub host: String,
+
+    /// The relative weight of the host when distributing commands across the
+    /// pool.
+    ///
+    /// A host with a weight of zero is never used.
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+
+    /// The maximum number of executions dispatched to the host at once (if
+    /// the host is limited).
+    pub max_tasks: Option<usize>,
+
+    /// Any options for the SSH connection.
+    #[serde(default)]
+    pub options: Config,
+}
+
+impl Host {
+    /// Creates a new [`Host`] with the default weight, no limit, and the
+    /// default connection options.
+    pub fn new(host: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            weight: DEFAULT_HOST_WEIGHT,
+            max_tasks: None,
+            options: Default::default(),
+        }
+    }
+
+    /// Gets the host for the connection.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Gets the relative weight of the host.
+    pub fn weight(&self) -> u32 {
+        self.weight
+    }
+
+    /// Gets the maximum number of executions dispatched to the host at once
+    /// (if the host is limited).
+    pub fn max_tasks(&self) -> Option<usize> {
+        self.max_tasks
+    }
+
+    /// Gets the options for the SSH connection.
+    pub fn options(&self) -> &Config {
+        &self.options
+    }
+}
@@ -0,0 +1,140 @@
+//! Configuration related to the _Kubernetes_ execution backend.
+
+mod builder;
+
+use std::collections::BTreeMap;
+
+pub use builder::Builder;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// The default namespace within which Jobs are created.
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+/// The default value for cleaning up Kubernetes Jobs.
+pub const DEFAULT_CLEANUP: bool = true;
+
+/// The default number of seconds between polls of the status of a Job.
+pub const DEFAULT_POLL_INTERVAL: u64 = 5;
+
+/// A utility function used to set the default value for `namespace` via serde.
+fn default_namespace() -> String {
+    String::from(DEFAULT_NAMESPACE)
+}
+
+/// A utility function used to set the default value for `cleanup` via serde.
+fn default_cleanup() -> bool {
+    DEFAULT_CLEANUP
+}
+
+/// A utility function used to set the default value for `poll_interval` via
+/// serde.
+fn default_poll_interval() -> u64 {
+    DEFAULT_POLL_INTERVAL
+}
+
+/// A configuration object for a Kubernetes execution backend.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// The namespace within which Jobs are created.
+    #[serde(default = "default_namespace")]
+    namespace: String,
+
+    /// The service account that the pods of Jobs run as.
+    ///
+    /// If this is not set, the default service account of the namespace is
+    /// used.
+    service_account: Option<String>,
+
+    /// The node labels that the pods of Jobs are constrained to (e.g.,
+    /// `node-pool = "highmem"`).
+    #[serde(default)]
+    node_selector: BTreeMap<String, String>,
+
+    /// Additional node labels that the pods of preemptible tasks are
+    /// constrained to (e.g., `"cloud.google.com/gke-spot" = "true"`).
+    ///
+    /// These labels are merged over the node selector above.
+    #[serde(default)]
+    preemptible_node_selector: BTreeMap<String, String>,
+
+    /// Whether or not to remove the Jobs (and their pods) after completion of
+    /// the tasks (regardless of whether the job was a success or failure).
+    #[serde(default = "default_cleanup")]
+    cleanup: bool,
+
+    /// The number of seconds between polls of the status of a Job.
+    #[serde(default = "default_poll_interval")]
+    poll_interval: u64,
+}
+
+impl Config {
+    /// Gets a builder for [`Config`].
+    pub fn builder() -> Builder {
+        Builder::default()
+    }
+
+    /// Gets the namespace within which Jobs are created.
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// Gets the service account that the pods of Jobs run as (if one is
+    /// configured).
+    pub fn service_account(&self) -> Option<&str> {
+        self.service_account.as_deref()
+    }
+
+    /// Gets the node labels that the pods of Jobs are constrained to.
+    pub fn node_selector(&self) -> &BTreeMap<String, String> {
+        &self.node_selector
+    }
+
+    /// Gets the additional node labels that the pods of preemptible tasks are
+    /// constrained to.
+    pub fn preemptible_node_selector(&self) -> &BTreeMap<String, String> {
+        &self.preemptible_node_selector
+    }
+
+    /// Gets whether the backend is configured to remove the Jobs after
+    /// completion of the tasks (regardless of whether the job was a success or
+    /// failure).
+    pub fn cleanup(&self) -> bool {
+        self.cleanup
+    }
+
+    /// Gets the number of seconds between polls of the status of a Job.
+    pub fn poll_interval(&self) -> u64 {
+        self.poll_interval
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_unwraps() {
+        Config::default();
+    }
+
+    #[test]
+    fn defaults_are_deserialized() {
+        let backends = crate::Config::parse_backends(
+            r#"[{"name": "k8s", "kind": "Kubernetes", "max-tasks": 10}]"#,
+        )
+        .unwrap();
+
+        let config = backends[0].kind().as_kubernetes().unwrap();
+        assert_eq!(config.namespace(), DEFAULT_NAMESPACE);
+        assert_eq!(config.poll_interval(), DEFAULT_POLL_INTERVAL);
+        assert!(config.cleanup());
+    }
+}
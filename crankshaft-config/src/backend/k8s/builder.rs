@@ -0,0 +1,174 @@
+//! Builders for the [_Kubernetes_ execution backend configuration](Config).
+
+use std::collections::BTreeMap;
+
+use crate::backend::k8s::Config;
+use crate::backend::k8s::DEFAULT_CLEANUP;
+use crate::backend::k8s::DEFAULT_NAMESPACE;
+use crate::backend::k8s::DEFAULT_POLL_INTERVAL;
+
+/// A builder for a [Kubernetes execution backend configuration
+/// object](Config).
+// **NOTE:** all default values for this struct need to be tested below to
+// ensure the defaults never change.
+pub struct Builder {
+    /// The namespace within which Jobs are created.
+    namespace: String,
+
+    /// The service account that the pods of Jobs run as.
+    service_account: Option<String>,
+
+    /// The node labels that the pods of Jobs are constrained to.
+    node_selector: BTreeMap<String, String>,
+
+    /// The additional node labels that the pods of preemptible tasks are
+    /// constrained to.
+    preemptible_node_selector: BTreeMap<String, String>,
+
+    /// Whether or not to remove the Jobs after completion of the tasks.
+    cleanup: bool,
+
+    /// The number of seconds between polls of the status of a Job.
+    poll_interval: u64,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self {
+            // By default, Jobs are created within the `default` namespace.
+            namespace: String::from(DEFAULT_NAMESPACE),
+            // By default, the default service account of the namespace is used.
+            service_account: None,
+            // By default, pods may be scheduled onto any node.
+            node_selector: BTreeMap::new(),
+            // By default, preemptible tasks are scheduled like any other.
+            preemptible_node_selector: BTreeMap::new(),
+            // By default, Jobs should be cleaned up.
+            cleanup: DEFAULT_CLEANUP,
+            // By default, Jobs are polled every few seconds.
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+}
+
+impl Builder {
+    /// Sets the namespace within which Jobs are created for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous namespaces set within the
+    /// builder.
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+
+    /// Sets the service account that the pods of Jobs run as for the
+    /// [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous service accounts set within
+    /// the builder.
+    pub fn service_account(mut self, account: impl Into<String>) -> Self {
+        self.service_account = Some(account.into());
+        self
+    }
+
+    /// Adds node labels that the pods of Jobs are constrained to for the
+    /// [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous values of the same labels
+    /// set within the builder.
+    pub fn extend_node_selector(
+        mut self,
+        labels: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> Self {
+        self.node_selector.extend(
+            labels
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into())),
+        );
+        self
+    }
+
+    /// Adds node labels that the pods of preemptible tasks are additionally
+    /// constrained to for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous values of the same labels
+    /// set within the builder.
+    pub fn extend_preemptible_node_selector(
+        mut self,
+        labels: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> Self {
+        self.preemptible_node_selector.extend(
+            labels
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into())),
+        );
+        self
+    }
+
+    /// Sets the cleanup property for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous cleanup properties set within
+    /// the builder.
+    pub fn cleanup(mut self, cleanup: bool) -> Self {
+        self.cleanup = cleanup;
+        self
+    }
+
+    /// Sets the number of seconds between polls of the status of a Job for
+    /// the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous poll intervals set within the
+    /// builder.
+    pub fn poll_interval(mut self, seconds: u64) -> Self {
+        self.poll_interval = seconds;
+        self
+    }
+
+    /// Consumes `self` and returns a built [`Config`].
+    pub fn build(self) -> Config {
+        Config {
+            namespace: self.namespace,
+            service_account: self.service_account,
+            node_selector: self.node_selector,
+            preemptible_node_selector: self.preemptible_node_selector,
+            cleanup: self.cleanup,
+            poll_interval: self.poll_interval,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_values() {
+        let options = Config::default();
+
+        // Jobs should be created within the `default` namespace by default.
+        assert_eq!(options.namespace(), "default");
+
+        // Pods should run as the default service account on any node by
+        // default.
+        assert_eq!(options.service_account(), None);
+        assert!(options.node_selector().is_empty());
+
+        // Kubernetes should clean up Jobs by default.
+        assert!(options.cleanup());
+
+        // Jobs should be polled every five seconds by default.
+        assert_eq!(options.poll_interval(), 5);
+    }
+}
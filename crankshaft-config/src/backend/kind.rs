@@ -3,11 +3,19 @@
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::backend::apptainer;
 use crate::backend::docker;
 use crate::backend::generic;
+use crate::backend::k8s;
+use crate::backend::local;
+use crate::backend::lsf;
+use crate::backend::slurm;
 use crate::backend::tes;
 
 /// A kind of execution backend.
+// NOTE: kinds are only created when a configuration is loaded, so the size
+// difference between the variants doesn't warrant boxing them.
+#[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(tag = "kind", rename_all = "PascalCase")]
 pub enum Kind {
@@ -19,6 +27,21 @@ pub enum Kind {
 
     /// A TES backend.
     TES(tes::Config),
+
+    /// A Kubernetes backend.
+    Kubernetes(k8s::Config),
+
+    /// A SLURM backend.
+    Slurm(slurm::Config),
+
+    /// An LSF backend.
+    Lsf(lsf::Config),
+
+    /// A local backend.
+    Local(local::Config),
+
+    /// An Apptainer (or Singularity) backend.
+    Apptainer(apptainer::Config),
 }
 
 impl Kind {
@@ -113,4 +136,159 @@ impl Kind {
             _ => panic!("the inner kind is not `Kind::TES`"),
         }
     }
+
+    /// Attempts to return a reference to the inner [Kubernetes
+    /// configuration][`k8s::Config`].
+    pub fn as_kubernetes(&self) -> Option<&k8s::Config> {
+        match self {
+            Kind::Kubernetes(config) => Some(config),
+            _ => None,
+        }
+    }
+
+    /// Consumes `self` and attempts to return an inner [Kubernetes
+    /// configuration][`k8s::Config`].
+    pub fn into_kubernetes(self) -> Option<k8s::Config> {
+        match self {
+            Kind::Kubernetes(config) => Some(config),
+            _ => None,
+        }
+    }
+
+    /// Consumes `self` and returns an inner [Kubernetes
+    /// configuration][`k8s::Config`].
+    ///
+    /// # Panics
+    ///
+    /// If the inner kind is not [`Kind::Kubernetes`].
+    pub fn unwrap_kubernetes(self) -> k8s::Config {
+        match self {
+            Kind::Kubernetes(config) => config,
+            _ => panic!("the inner kind is not `Kind::Kubernetes`"),
+        }
+    }
+
+    /// Attempts to return a reference to the inner [SLURM
+    /// configuration][`slurm::Config`].
+    pub fn as_slurm(&self) -> Option<&slurm::Config> {
+        match self {
+            Kind::Slurm(config) => Some(config),
+            _ => None,
+        }
+    }
+
+    /// Consumes `self` and attempts to return an inner [SLURM
+    /// configuration][`slurm::Config`].
+    pub fn into_slurm(self) -> Option<slurm::Config> {
+        match self {
+            Kind::Slurm(config) => Some(config),
+            _ => None,
+        }
+    }
+
+    /// Consumes `self` and returns an inner [SLURM
+    /// configuration][`slurm::Config`].
+    ///
+    /// # Panics
+    ///
+    /// If the inner kind is not [`Kind::Slurm`].
+    pub fn unwrap_slurm(self) -> slurm::Config {
+        match self {
+            Kind::Slurm(config) => config,
+            _ => panic!("the inner kind is not `Kind::Slurm`"),
+        }
+    }
+
+    /// Attempts to return a reference to the inner [LSF
+    /// configuration][`lsf::Config`].
+    pub fn as_lsf(&self) -> Option<&lsf::Config> {
+        match self {
+            Kind::Lsf(config) => Some(config),
+            _ => None,
+        }
+    }
+
+    /// Consumes `self` and attempts to return an inner [LSF
+    /// configuration][`lsf::Config`].
+    pub fn into_lsf(self) -> Option<lsf::Config> {
+        match self {
+            Kind::Lsf(config) => Some(config),
+            _ => None,
+        }
+    }
+
+    /// Consumes `self` and returns an inner [LSF
+    /// configuration][`lsf::Config`].
+    ///
+    /// # Panics
+    ///
+    /// If the inner kind is not [`Kind::Lsf`].
+    pub fn unwrap_lsf(self) -> lsf::Config {
+        match self {
+            Kind::Lsf(config) => config,
+            _ => panic!("the inner kind is not `Kind::Lsf`"),
+        }
+    }
+
+    /// Attempts to return a reference to the inner [local
+    /// configuration][`local::Config`].
+    pub fn as_local(&self) -> Option<&local::Config> {
+        match self {
+            Kind::Local(config) => Some(config),
+            _ => None,
+        }
+    }
+
+    /// Consumes `self` and attempts to return an inner [local
+    /// configuration][`local::Config`].
+    pub fn into_local(self) -> Option<local::Config> {
+        match self {
+            Kind::Local(config) => Some(config),
+            _ => None,
+        }
+    }
+
+    /// Consumes `self` and returns an inner [local
+    /// configuration][`local::Config`].
+    ///
+    /// # Panics
+    ///
+    /// If the inner kind is not [`Kind::Local`].
+    pub fn unwrap_local(self) -> local::Config {
+        match self {
+            Kind::Local(config) => config,
+            _ => panic!("the inner kind is not `Kind::Local`"),
+        }
+    }
+
+    /// Attempts to return a reference to the inner [Apptainer
+    /// configuration][`apptainer::Config`].
+    pub fn as_apptainer(&self) -> Option<&apptainer::Config> {
+        match self {
+            Kind::Apptainer(config) => Some(config),
+            _ => None,
+        }
+    }
+
+    /// Consumes `self` and attempts to return an inner [Apptainer
+    /// configuration][`apptainer::Config`].
+    pub fn into_apptainer(self) -> Option<apptainer::Config> {
+        match self {
+            Kind::Apptainer(config) => Some(config),
+            _ => None,
+        }
+    }
+
+    /// Consumes `self` and returns an inner [Apptainer
+    /// configuration][`apptainer::Config`].
+    ///
+    /// # Panics
+    ///
+    /// If the inner kind is not [`Kind::Apptainer`].
+    pub fn unwrap_apptainer(self) -> apptainer::Config {
+        match self {
+            Kind::Apptainer(config) => config,
+            _ => panic!("the inner kind is not `Kind::Apptainer`"),
+        }
+    }
 }
@@ -0,0 +1,52 @@
+//! Configuration related to the _local_ execution backend.
+
+mod builder;
+
+pub use builder::Builder;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// A configuration object for a local execution backend.
+///
+/// Executions are run directly as child processes of the engine (without a
+/// container), so the image of each execution is ignored.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Whether or not the environment of the engine is cleared before the
+    /// environment variables of an execution are applied.
+    ///
+    /// By default, executions inherit the environment of the engine.
+    #[serde(default)]
+    clear_env: bool,
+}
+
+impl Config {
+    /// Gets a default [`Builder`] for a [`Config`].
+    pub fn builder() -> Builder {
+        Builder::default()
+    }
+
+    /// Gets whether or not the environment of the engine is cleared before
+    /// the environment variables of an execution are applied.
+    pub fn clear_env(&self) -> bool {
+        self.clear_env
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn options_are_deserialized() {
+        let backends = crate::Config::parse_backends(
+            r#"[
+                {"name": "local", "kind": "Local", "max-tasks": 4},
+                {"name": "clean", "kind": "Local", "max-tasks": 4, "clear-env": true}
+            ]"#,
+        )
+        .unwrap();
+
+        assert!(!backends[0].kind().as_local().unwrap().clear_env());
+        assert!(backends[1].kind().as_local().unwrap().clear_env());
+    }
+}
@@ -0,0 +1,31 @@
+//! Builders for the [_local_ execution backend configuration](Config).
+
+use crate::backend::local::Config;
+
+/// A builder for a [local execution backend configuration object](Config).
+#[derive(Default)]
+pub struct Builder {
+    /// Whether or not the environment of the engine is cleared.
+    clear_env: bool,
+}
+
+impl Builder {
+    /// Sets whether or not the environment of the engine is cleared before the
+    /// environment variables of an execution are applied for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous values set within the
+    /// builder.
+    pub fn clear_env(mut self, value: bool) -> Self {
+        self.clear_env = value;
+        self
+    }
+
+    /// Consumes `self` and returns a built [`Config`].
+    pub fn build(self) -> Config {
+        Config {
+            clear_env: self.clear_env,
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! Configuration related to the _LSF_ execution backend.
+
+mod builder;
+
+pub use builder::Builder;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::backend::generic::driver;
+
+/// A configuration object for an LSF execution backend.
+///
+/// Jobs are submitted with `bsub` and monitored (down to their exit codes)
+/// with `bjobs`. Commands are run through the same command driver as generic
+/// backends, so the LSF commands may be run either locally or on a login node
+/// over SSH.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Configuration related to the command driver.
+    #[serde(flatten)]
+    driver: driver::Config,
+
+    /// The queue that jobs are submitted to.
+    ///
+    /// If this is not set, the default queue of the cluster is used.
+    queue: Option<String>,
+
+    /// The project that jobs are charged to.
+    ///
+    /// If this is not set, the default project of the user is used.
+    project: Option<String>,
+
+    /// Additional arguments passed to `bsub` (e.g., `-R "select[avx2]"`).
+    #[serde(default)]
+    extra_args: Vec<String>,
+
+    /// The frequency in seconds that the state of a job is queried.
+    monitor_frequency: Option<u64>,
+}
+
+impl Config {
+    /// Gets a default [`Builder`] for a [`Config`].
+    pub fn builder() -> Builder {
+        Builder::default()
+    }
+
+    /// Gets the driver configuration.
+    pub fn driver(&self) -> &driver::Config {
+        &self.driver
+    }
+
+    /// Gets the queue that jobs are submitted to (if one is configured).
+    pub fn queue(&self) -> Option<&str> {
+        self.queue.as_deref()
+    }
+
+    /// Gets the project that jobs are charged to (if one is configured).
+    pub fn project(&self) -> Option<&str> {
+        self.project.as_deref()
+    }
+
+    /// Gets the additional arguments passed to `bsub`.
+    pub fn extra_args(&self) -> &[String] {
+        &self.extra_args
+    }
+
+    /// Gets the monitor frequency (in seconds).
+    pub fn monitor_frequency(&self) -> Option<u64> {
+        self.monitor_frequency
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn options_are_deserialized() {
+        let backends = crate::Config::parse_backends(
+            r#"[{
+                "name": "lsf",
+                "kind": "Lsf",
+                "max-tasks": 10,
+                "queue": "compbio",
+                "monitor-frequency": 10
+            }]"#,
+        )
+        .unwrap();
+
+        let config = backends[0].kind().as_lsf().unwrap();
+        assert_eq!(config.queue(), Some("compbio"));
+        assert_eq!(config.project(), None);
+        assert!(config.extra_args().is_empty());
+        assert_eq!(config.monitor_frequency(), Some(10));
+    }
+}
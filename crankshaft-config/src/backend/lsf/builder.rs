@@ -0,0 +1,91 @@
+//! Builders for the [_LSF_ execution backend configuration](Config).
+
+use crate::backend::generic::driver;
+use crate::backend::lsf::Config;
+
+/// A builder for an [LSF execution backend configuration object](Config).
+#[derive(Default)]
+pub struct Builder {
+    /// Configuration related to the command driver.
+    driver: driver::Config,
+
+    /// The queue that jobs are submitted to.
+    queue: Option<String>,
+
+    /// The project that jobs are charged to.
+    project: Option<String>,
+
+    /// Additional arguments passed to `bsub`.
+    extra_args: Vec<String>,
+
+    /// The frequency in seconds that the state of a job is queried.
+    monitor_frequency: Option<u64>,
+}
+
+impl Builder {
+    /// Sets the driver configuration for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous driver configuration set
+    /// within the builder.
+    pub fn driver(mut self, driver: driver::Config) -> Self {
+        self.driver = driver;
+        self
+    }
+
+    /// Sets the queue that jobs are submitted to for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous queues set within the
+    /// builder.
+    pub fn queue(mut self, queue: impl Into<String>) -> Self {
+        self.queue = Some(queue.into());
+        self
+    }
+
+    /// Sets the project that jobs are charged to for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous projects set within the
+    /// builder.
+    pub fn project(mut self, project: impl Into<String>) -> Self {
+        self.project = Some(project.into());
+        self
+    }
+
+    /// Adds additional arguments passed to `bsub` for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// Arguments are appended to any arguments previously added to the
+    /// builder.
+    pub fn extend_extra_args(mut self, args: impl IntoIterator<Item: Into<String>>) -> Self {
+        self.extra_args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets the monitor frequency (in seconds) for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous monitor frequencies set
+    /// within the builder.
+    pub fn monitor_frequency(mut self, seconds: u64) -> Self {
+        self.monitor_frequency = Some(seconds);
+        self
+    }
+
+    /// Consumes `self` and returns a built [`Config`].
+    pub fn build(self) -> Config {
+        Config {
+            driver: self.driver,
+            queue: self.queue,
+            project: self.project,
+            extra_args: self.extra_args,
+            monitor_frequency: self.monitor_frequency,
+        }
+    }
+}
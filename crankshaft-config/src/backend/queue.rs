@@ -0,0 +1,49 @@
+//! Configuration related to the ordering of the tasks queued for a backend.
+
+use serde::Deserialize;
+use serde::Serialize;
+
+/// The order in which tasks waiting on a backend with no free capacity are
+/// started.
+///
+/// ```toml
+/// [[backends]]
+/// queue-order = "fair-share"
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum QueueOrder {
+    /// Tasks are started in the order they were submitted.
+    #[default]
+    Fifo,
+
+    /// Tasks with a higher priority are started first (and tasks with the
+    /// same priority are started in the order they were submitted).
+    Priority,
+
+    /// Tasks of the group with the fewest running tasks are started first, so
+    /// that every group receives a fair share of the backend (and ties are
+    /// broken by priority and then by the order the tasks were submitted).
+    ///
+    /// Tasks without a group share a single group.
+    FairShare,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queue_orders_are_deserialized() {
+        let backends = crate::Config::parse_backends(
+            r#"[
+                {"name": "a", "kind": "Local", "max-tasks": 1},
+                {"name": "b", "kind": "Local", "max-tasks": 1, "queue-order": "fair-share"}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(backends[0].queue_order(), QueueOrder::Fifo);
+        assert_eq!(backends[1].queue_order(), QueueOrder::FairShare);
+    }
+}
@@ -0,0 +1,120 @@
+//! Configuration related to the retrying of tasks.
+
+mod builder;
+
+pub use builder::Builder;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// The default maximum number of attempts of a task (including the first
+/// attempt).
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// The default number of seconds before the first retry of a task.
+pub const DEFAULT_BACKOFF: u64 = 10;
+
+/// The default maximum number of seconds between attempts of a task.
+pub const DEFAULT_MAX_BACKOFF: u64 = 300;
+
+/// Gets the default value for whether or not preempted tasks are retried.
+fn default_on_preemption() -> bool {
+    true
+}
+
+/// A policy for retrying tasks that were preempted or that failed.
+///
+/// The delay between attempts starts at the backoff and doubles with each
+/// subsequent retry (up to the maximum backoff).
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RetryPolicy {
+    /// The maximum number of attempts of a task (including the first attempt).
+    max_attempts: u32,
+
+    /// The number of seconds before the first retry of a task.
+    backoff: Option<u64>,
+
+    /// The maximum number of seconds between attempts of a task.
+    max_backoff: Option<u64>,
+
+    /// Whether or not tasks that were preempted are retried.
+    #[serde(default = "default_on_preemption")]
+    on_preemption: bool,
+
+    /// Whether or not tasks that failed are retried.
+    ///
+    /// A task failed if its backend failed to run it (for reasons that may be
+    /// transient) or if any of its executions exited unsuccessfully.
+    #[serde(default)]
+    on_failure: bool,
+}
+
+impl RetryPolicy {
+    /// Gets a default [`Builder`] for a [`RetryPolicy`].
+    pub fn builder() -> Builder {
+        Builder::default()
+    }
+
+    /// Gets the maximum number of attempts of a task (including the first
+    /// attempt).
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Gets the number of seconds before the first retry of a task (if
+    /// configured).
+    pub fn backoff(&self) -> Option<u64> {
+        self.backoff
+    }
+
+    /// Gets the maximum number of seconds between attempts of a task (if
+    /// configured).
+    pub fn max_backoff(&self) -> Option<u64> {
+        self.max_backoff
+    }
+
+    /// Gets whether or not tasks that were preempted are retried.
+    pub fn on_preemption(&self) -> bool {
+        self.on_preemption
+    }
+
+    /// Gets whether or not tasks that failed are retried.
+    pub fn on_failure(&self) -> bool {
+        self.on_failure
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_policies_are_deserialized() {
+        let backends = crate::Config::parse_backends(
+            r#"[{
+                "name": "cluster",
+                "kind": "Local",
+                "max-tasks": 1,
+                "defaults": {"retry": {"max-attempts": 5, "on-failure": true}}
+            }]"#,
+        )
+        .unwrap();
+
+        let policy = backends[0].defaults().unwrap().retry().unwrap();
+        assert_eq!(policy.max_attempts(), 5);
+        assert_eq!(policy.backoff(), None);
+        assert!(policy.on_preemption());
+        assert!(policy.on_failure());
+
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.max_attempts(), DEFAULT_MAX_ATTEMPTS);
+        assert!(policy.on_preemption());
+        assert!(!policy.on_failure());
+    }
+}
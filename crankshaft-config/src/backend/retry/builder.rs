@@ -0,0 +1,107 @@
+//! Builders for [retry policies](RetryPolicy).
+
+use crate::backend::retry::DEFAULT_MAX_ATTEMPTS;
+use crate::backend::retry::RetryPolicy;
+
+/// A builder for a [`RetryPolicy`].
+pub struct Builder {
+    /// The maximum number of attempts of a task.
+    max_attempts: u32,
+
+    /// The number of seconds before the first retry of a task.
+    backoff: Option<u64>,
+
+    /// The maximum number of seconds between attempts of a task.
+    max_backoff: Option<u64>,
+
+    /// Whether or not tasks that were preempted are retried.
+    on_preemption: bool,
+
+    /// Whether or not tasks that failed are retried.
+    on_failure: bool,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            backoff: None,
+            max_backoff: None,
+            // By default, only preempted tasks are retried.
+            on_preemption: true,
+            on_failure: false,
+        }
+    }
+}
+
+impl Builder {
+    /// Sets the maximum number of attempts of a task (including the first
+    /// attempt) for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous maximum number of attempts
+    /// set within the builder.
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts;
+        self
+    }
+
+    /// Sets the number of seconds before the first retry of a task for the
+    /// [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous backoffs set within the
+    /// builder.
+    pub fn backoff(mut self, seconds: u64) -> Self {
+        self.backoff = Some(seconds);
+        self
+    }
+
+    /// Sets the maximum number of seconds between attempts of a task for the
+    /// [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous maximum backoffs set within
+    /// the builder.
+    pub fn max_backoff(mut self, seconds: u64) -> Self {
+        self.max_backoff = Some(seconds);
+        self
+    }
+
+    /// Sets whether or not tasks that were preempted are retried for the
+    /// [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous values set within the
+    /// builder.
+    pub fn on_preemption(mut self, value: bool) -> Self {
+        self.on_preemption = value;
+        self
+    }
+
+    /// Sets whether or not tasks that failed are retried for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous values set within the
+    /// builder.
+    pub fn on_failure(mut self, value: bool) -> Self {
+        self.on_failure = value;
+        self
+    }
+
+    /// Consumes `self` and returns a built [`RetryPolicy`].
+    pub fn build(self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.max_attempts,
+            backoff: self.backoff,
+            max_backoff: self.max_backoff,
+            on_preemption: self.on_preemption,
+            on_failure: self.on_failure,
+        }
+    }
+}
@@ -0,0 +1,368 @@
+//! Submission schedules of execution backends.
+//!
+//! A [`Schedule`] restricts when a backend accepts submissions (e.g., to avoid
+//! submitting to a cluster during its maintenance). It is made up of recurring
+//! weekly [`Window`]s in the local time of the engine:
+//!
+//! * If any open windows are configured, the backend only accepts submissions
+//!   within them (e.g., during working hours).
+//! * The backend never accepts submissions within a maintenance window.
+//!
+//! A window whose end is before its start spans midnight (and applies to the
+//! days on which it starts), and a window whose start and end are equal spans
+//! the whole day.
+//!
+//! ```toml
+//! [backends.schedule]
+//! open-windows = [{ days = ["monday", "friday"], start = "08:00", end = "18:00" }]
+//! maintenance-windows = [{ days = ["tuesday"], start = "22:00", end = "02:00" }]
+//! ```
+
+use serde::Deserialize;
+use serde::Serialize;
+
+/// The number of minutes in a day.
+pub const MINUTES_PER_DAY: u16 = 24 * 60;
+
+/// The number of minutes in a week.
+const MINUTES_PER_WEEK: u32 = 7 * MINUTES_PER_DAY as u32;
+
+/// A day of the week.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Day {
+    /// Monday.
+    #[serde(alias = "mon")]
+    Monday,
+
+    /// Tuesday.
+    #[serde(alias = "tue")]
+    Tuesday,
+
+    /// Wednesday.
+    #[serde(alias = "wed")]
+    Wednesday,
+
+    /// Thursday.
+    #[serde(alias = "thu")]
+    Thursday,
+
+    /// Friday.
+    #[serde(alias = "fri")]
+    Friday,
+
+    /// Saturday.
+    #[serde(alias = "sat")]
+    Saturday,
+
+    /// Sunday.
+    #[serde(alias = "sun")]
+    Sunday,
+}
+
+impl Day {
+    /// The days of the week (starting on Monday).
+    pub const ALL: [Day; 7] = [
+        Day::Monday,
+        Day::Tuesday,
+        Day::Wednesday,
+        Day::Thursday,
+        Day::Friday,
+        Day::Saturday,
+        Day::Sunday,
+    ];
+
+    /// Gets the number of days since Monday.
+    pub fn days_from_monday(self) -> u8 {
+        self as u8
+    }
+
+    /// Gets the day before this day.
+    pub fn previous(self) -> Day {
+        Self::ALL[(self.days_from_monday() as usize + 6) % 7]
+    }
+}
+
+/// A time of day (with a resolution of minutes).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeOfDay {
+    /// The number of minutes since midnight.
+    minutes: u16,
+}
+
+impl TimeOfDay {
+    /// Creates a new [`TimeOfDay`] from an hour (`0..24`) and a minute
+    /// (`0..60`).
+    ///
+    /// Returns [`None`] if either is out of range.
+    pub fn new(hour: u16, minute: u16) -> Option<Self> {
+        (hour < 24 && minute < 60).then_some(Self {
+            minutes: hour * 60 + minute,
+        })
+    }
+
+    /// Gets the number of minutes since midnight.
+    pub fn minutes(&self) -> u16 {
+        self.minutes
+    }
+}
+
+impl TryFrom<String> for TimeOfDay {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value
+            .split_once(':')
+            .filter(|(hour, minute)| hour.len() <= 2 && minute.len() == 2)
+            .and_then(|(hour, minute)| Self::new(hour.parse().ok()?, minute.parse().ok()?))
+            .ok_or_else(|| format!("invalid time of day `{value}` (expected `HH:MM`)"))
+    }
+}
+
+impl From<TimeOfDay> for String {
+    fn from(value: TimeOfDay) -> Self {
+        format!("{:02}:{:02}", value.minutes / 60, value.minutes % 60)
+    }
+}
+
+/// A recurring weekly window of time.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Window {
+    /// The days of the week on which the window starts.
+    ///
+    /// If this is empty, the window starts on every day.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    days: Vec<Day>,
+
+    /// The time of day the window starts.
+    start: TimeOfDay,
+
+    /// The time of day the window ends.
+    end: TimeOfDay,
+}
+
+impl Window {
+    /// Creates a new [`Window`] that starts on the provided days (or on every
+    /// day if no days are provided).
+    pub fn new(days: impl IntoIterator<Item = Day>, start: TimeOfDay, end: TimeOfDay) -> Self {
+        Self {
+            days: days.into_iter().collect(),
+            start,
+            end,
+        }
+    }
+
+    /// Gets the days of the week on which the window starts.
+    ///
+    /// If this is empty, the window starts on every day.
+    pub fn days(&self) -> &[Day] {
+        &self.days
+    }
+
+    /// Gets the time of day the window starts.
+    pub fn start(&self) -> TimeOfDay {
+        self.start
+    }
+
+    /// Gets the time of day the window ends.
+    pub fn end(&self) -> TimeOfDay {
+        self.end
+    }
+
+    /// Gets whether or not the window starts on a particular day.
+    fn starts_on(&self, day: Day) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    /// Gets whether or not a minute (since midnight) of a day falls within the
+    /// window.
+    pub fn contains(&self, day: Day, minute: u16) -> bool {
+        let (start, end) = (self.start.minutes, self.end.minutes);
+
+        match start.cmp(&end) {
+            std::cmp::Ordering::Less => self.starts_on(day) && (start..end).contains(&minute),
+            std::cmp::Ordering::Greater => {
+                (self.starts_on(day) && minute >= start)
+                    || (self.starts_on(day.previous()) && minute < end)
+            }
+            std::cmp::Ordering::Equal => self.starts_on(day),
+        }
+    }
+}
+
+/// A schedule of the windows during which a backend accepts submissions.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Schedule {
+    /// The windows during which the backend accepts submissions.
+    ///
+    /// If this is empty, the backend accepts submissions outside of its
+    /// maintenance windows.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    open_windows: Vec<Window>,
+
+    /// The windows during which the backend never accepts submissions.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    maintenance_windows: Vec<Window>,
+}
+
+impl Schedule {
+    /// Creates a new [`Schedule`] from its open and maintenance windows.
+    pub fn new(
+        open_windows: impl IntoIterator<Item = Window>,
+        maintenance_windows: impl IntoIterator<Item = Window>,
+    ) -> Self {
+        Self {
+            open_windows: open_windows.into_iter().collect(),
+            maintenance_windows: maintenance_windows.into_iter().collect(),
+        }
+    }
+
+    /// Gets the windows during which the backend accepts submissions.
+    pub fn open_windows(&self) -> &[Window] {
+        &self.open_windows
+    }
+
+    /// Gets the windows during which the backend never accepts submissions.
+    pub fn maintenance_windows(&self) -> &[Window] {
+        &self.maintenance_windows
+    }
+
+    /// Gets whether or not the backend always accepts submissions (i.e., no
+    /// windows are configured).
+    pub fn is_always_open(&self) -> bool {
+        self.open_windows.is_empty() && self.maintenance_windows.is_empty()
+    }
+
+    /// Gets whether or not the backend accepts submissions at a minute (since
+    /// midnight) of a day.
+    pub fn is_open(&self, day: Day, minute: u16) -> bool {
+        (self.open_windows.is_empty()
+            || self
+                .open_windows
+                .iter()
+                .any(|window| window.contains(day, minute)))
+            && !self
+                .maintenance_windows
+                .iter()
+                .any(|window| window.contains(day, minute))
+    }
+
+    /// Gets the number of minutes from a minute (since midnight) of a day
+    /// until the backend accepts submissions (which is zero if it accepts them
+    /// at that minute).
+    ///
+    /// Returns [`None`] if the backend never accepts submissions.
+    pub fn minutes_until_open(&self, day: Day, minute: u16) -> Option<u32> {
+        let now = day.days_from_monday() as u32 * MINUTES_PER_DAY as u32 + minute as u32;
+
+        // NOTE: schedules repeat weekly, so a schedule that does not open
+        // within a week never opens.
+        (0..MINUTES_PER_WEEK).find(|offset| {
+            let minute = (now + offset) % MINUTES_PER_WEEK;
+            let day = Day::ALL[(minute / MINUTES_PER_DAY as u32) as usize];
+
+            self.is_open(day, (minute % MINUTES_PER_DAY as u32) as u16)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gets a time of day.
+    fn time(hour: u16, minute: u16) -> TimeOfDay {
+        TimeOfDay::new(hour, minute).unwrap()
+    }
+
+    #[test]
+    fn times_of_day_are_parsed() {
+        assert_eq!(TimeOfDay::try_from(String::from("08:30")), Ok(time(8, 30)));
+        assert_eq!(TimeOfDay::try_from(String::from("0:00")), Ok(time(0, 0)));
+        assert_eq!(String::from(time(8, 5)), "08:05");
+
+        for invalid in ["24:00", "12:60", "8", "08:3", "noon"] {
+            assert!(TimeOfDay::try_from(String::from(invalid)).is_err());
+        }
+    }
+
+    #[test]
+    fn windows_may_span_midnight() {
+        let window = Window::new([Day::Tuesday], time(22, 0), time(2, 0));
+        assert!(window.contains(Day::Tuesday, 23 * 60));
+        assert!(window.contains(Day::Wednesday, 60));
+        assert!(!window.contains(Day::Wednesday, 23 * 60));
+        assert!(!window.contains(Day::Tuesday, 60));
+
+        let window = Window::new([], time(0, 0), time(0, 0));
+        assert!(Day::ALL.iter().all(|day| window.contains(*day, 0)));
+    }
+
+    #[test]
+    fn schedules_wait_for_open_windows() {
+        let schedule = Schedule::new(
+            [Window::new(
+                [Day::Monday, Day::Friday],
+                time(8, 0),
+                time(18, 0),
+            )],
+            [Window::new([Day::Monday], time(12, 0), time(13, 0))],
+        );
+
+        assert_eq!(schedule.minutes_until_open(Day::Monday, 9 * 60), Some(0));
+        assert_eq!(schedule.minutes_until_open(Day::Monday, 7 * 60), Some(60));
+        assert_eq!(
+            schedule.minutes_until_open(Day::Monday, 12 * 60 + 30),
+            Some(30)
+        );
+
+        // Friday evening waits until Monday morning (wrapping the week).
+        assert_eq!(
+            schedule.minutes_until_open(Day::Friday, 18 * 60),
+            Some((2 * 24 + 14) * 60)
+        );
+
+        let never = Schedule::new([], [Window::new([], time(0, 0), time(0, 0))]);
+        assert_eq!(never.minutes_until_open(Day::Monday, 0), None);
+        assert!(Schedule::default().is_always_open());
+    }
+
+    #[test]
+    fn schedules_are_deserialized() {
+        let backends = crate::Config::parse_backends(
+            r#"[{
+                "name": "cluster",
+                "kind": "Local",
+                "max-tasks": 1,
+                "schedule": {
+                    "open-windows": [{"days": ["mon", "friday"], "start": "08:00", "end": "18:00"}],
+                    "maintenance-windows": [{"start": "22:00", "end": "02:00"}]
+                }
+            }]"#,
+        )
+        .unwrap();
+
+        let schedule = backends[0].schedule();
+        assert_eq!(
+            schedule.open_windows()[0].days(),
+            [Day::Monday, Day::Friday]
+        );
+        assert_eq!(schedule.maintenance_windows()[0].start(), time(22, 0));
+        assert!(!schedule.is_open(Day::Monday, 23 * 60));
+
+        assert!(
+            crate::Config::parse_backends(
+                r#"[{
+                    "name": "cluster",
+                    "kind": "Local",
+                    "max-tasks": 1,
+                    "schedule": {"open-windows": [{"start": "8am", "end": "18:00"}]}
+                }]"#,
+            )
+            .is_err()
+        );
+    }
+}
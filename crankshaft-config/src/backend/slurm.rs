@@ -0,0 +1,133 @@
+//! Configuration related to the _SLURM_ execution backend.
+
+mod builder;
+
+pub use builder::Builder;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::backend::generic::driver;
+
+/// A configuration object for a SLURM execution backend.
+///
+/// Jobs are submitted with `sbatch`, monitored with `squeue`, and their exit
+/// codes (and accounting data) are queried with `sacct`. Commands are run
+/// through the same command driver as generic backends, so the SLURM
+/// commands may be run either locally or on a login node over SSH.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// Configuration related to the command driver.
+    #[serde(flatten)]
+    driver: driver::Config,
+
+    /// The partition that jobs are submitted to.
+    ///
+    /// If this is not set, the default partition of the cluster is used.
+    partition: Option<String>,
+
+    /// The partition that preemptible tasks are submitted to.
+    ///
+    /// If this is not set, preemptible tasks are submitted to the same
+    /// partition as any other task.
+    preemptible_partition: Option<String>,
+
+    /// The number of seconds before a job is killed (at the end of its time
+    /// limit) that the checkpoint command of its task is signaled to run.
+    ///
+    /// Jobs that are preempted are signaled as soon as they are selected for
+    /// preemption, so this should be no longer than the `GraceTime` of the
+    /// preempting partition.
+    checkpoint_grace: Option<u64>,
+
+    /// The account that jobs are charged to.
+    ///
+    /// If this is not set, the default account of the user is used.
+    account: Option<String>,
+
+    /// The quality of service (QOS) of jobs.
+    ///
+    /// If this is not set, the default QOS of the account is used.
+    qos: Option<String>,
+
+    /// Additional arguments passed to `sbatch` (e.g., `--constraint=avx2`).
+    #[serde(default)]
+    extra_args: Vec<String>,
+
+    /// The frequency in seconds that the state of a job is queried.
+    monitor_frequency: Option<u64>,
+}
+
+impl Config {
+    /// Gets a default [`Builder`] for a [`Config`].
+    pub fn builder() -> Builder {
+        Builder::default()
+    }
+
+    /// Gets the driver configuration.
+    pub fn driver(&self) -> &driver::Config {
+        &self.driver
+    }
+
+    /// Gets the partition that jobs are submitted to (if one is configured).
+    pub fn partition(&self) -> Option<&str> {
+        self.partition.as_deref()
+    }
+
+    /// Gets the partition that preemptible tasks are submitted to (if one is
+    /// configured).
+    pub fn preemptible_partition(&self) -> Option<&str> {
+        self.preemptible_partition.as_deref()
+    }
+
+    /// Gets the checkpoint grace period (in seconds).
+    pub fn checkpoint_grace(&self) -> Option<u64> {
+        self.checkpoint_grace
+    }
+
+    /// Gets the account that jobs are charged to (if one is configured).
+    pub fn account(&self) -> Option<&str> {
+        self.account.as_deref()
+    }
+
+    /// Gets the quality of service of jobs (if one is configured).
+    pub fn qos(&self) -> Option<&str> {
+        self.qos.as_deref()
+    }
+
+    /// Gets the additional arguments passed to `sbatch`.
+    pub fn extra_args(&self) -> &[String] {
+        &self.extra_args
+    }
+
+    /// Gets the monitor frequency (in seconds).
+    pub fn monitor_frequency(&self) -> Option<u64> {
+        self.monitor_frequency
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn options_are_deserialized() {
+        let backends = crate::Config::parse_backends(
+            r#"[{
+                "name": "slurm",
+                "kind": "Slurm",
+                "max-tasks": 10,
+                "partition": "compute",
+                "preemptible-partition": "scavenger",
+                "extra-args": ["--constraint=avx2"]
+            }]"#,
+        )
+        .unwrap();
+
+        let config = backends[0].kind().as_slurm().unwrap();
+        assert_eq!(config.partition(), Some("compute"));
+        assert_eq!(config.preemptible_partition(), Some("scavenger"));
+        assert_eq!(config.account(), None);
+        assert!(config.checkpoint_grace().is_none());
+        assert_eq!(config.extra_args(), ["--constraint=avx2"]);
+        assert!(config.monitor_frequency().is_none());
+    }
+}
@@ -0,0 +1,137 @@
+//! Builders for the [_SLURM_ execution backend configuration](Config).
+
+use crate::backend::generic::driver;
+use crate::backend::slurm::Config;
+
+/// A builder for a [SLURM execution backend configuration object](Config).
+#[derive(Default)]
+pub struct Builder {
+    /// Configuration related to the command driver.
+    driver: driver::Config,
+
+    /// The partition that jobs are submitted to.
+    partition: Option<String>,
+
+    /// The partition that preemptible tasks are submitted to.
+    preemptible_partition: Option<String>,
+
+    /// The checkpoint grace period (in seconds).
+    checkpoint_grace: Option<u64>,
+
+    /// The account that jobs are charged to.
+    account: Option<String>,
+
+    /// The quality of service of jobs.
+    qos: Option<String>,
+
+    /// Additional arguments passed to `sbatch`.
+    extra_args: Vec<String>,
+
+    /// The frequency in seconds that the state of a job is queried.
+    monitor_frequency: Option<u64>,
+}
+
+impl Builder {
+    /// Sets the driver configuration for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous driver configuration set
+    /// within the builder.
+    pub fn driver(mut self, driver: driver::Config) -> Self {
+        self.driver = driver;
+        self
+    }
+
+    /// Sets the partition that jobs are submitted to for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous partitions set within the
+    /// builder.
+    pub fn partition(mut self, partition: impl Into<String>) -> Self {
+        self.partition = Some(partition.into());
+        self
+    }
+
+    /// Sets the partition that preemptible tasks are submitted to for the
+    /// [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous preemptible partitions set
+    /// within the builder.
+    pub fn preemptible_partition(mut self, partition: impl Into<String>) -> Self {
+        self.preemptible_partition = Some(partition.into());
+        self
+    }
+
+    /// Sets the checkpoint grace period (in seconds) for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous checkpoint grace periods set
+    /// within the builder.
+    pub fn checkpoint_grace(mut self, seconds: u64) -> Self {
+        self.checkpoint_grace = Some(seconds);
+        self
+    }
+
+    /// Sets the account that jobs are charged to for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous accounts set within the
+    /// builder.
+    pub fn account(mut self, account: impl Into<String>) -> Self {
+        self.account = Some(account.into());
+        self
+    }
+
+    /// Sets the quality of service of jobs for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous QOS values set within the
+    /// builder.
+    pub fn qos(mut self, qos: impl Into<String>) -> Self {
+        self.qos = Some(qos.into());
+        self
+    }
+
+    /// Adds additional arguments passed to `sbatch` for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// Arguments are appended to any arguments previously added to the
+    /// builder.
+    pub fn extend_extra_args(mut self, args: impl IntoIterator<Item: Into<String>>) -> Self {
+        self.extra_args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets the monitor frequency (in seconds) for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous monitor frequencies set
+    /// within the builder.
+    pub fn monitor_frequency(mut self, seconds: u64) -> Self {
+        self.monitor_frequency = Some(seconds);
+        self
+    }
+
+    /// Consumes `self` and returns a built [`Config`].
+    pub fn build(self) -> Config {
+        Config {
+            driver: self.driver,
+            partition: self.partition,
+            preemptible_partition: self.preemptible_partition,
+            checkpoint_grace: self.checkpoint_grace,
+            account: self.account,
+            qos: self.qos,
+            extra_args: self.extra_args,
+            monitor_frequency: self.monitor_frequency,
+        }
+    }
+}
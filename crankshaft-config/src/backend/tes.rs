@@ -17,6 +17,35 @@ pub struct Config {
 
     /// More nuanced, HTTP-related configuration.
     http: http::Config,
+
+    /// The mappings from local output URLs to the remote locations where the
+    /// service stores them.
+    #[serde(default)]
+    output_mappings: Vec<OutputMapping>,
+
+    /// The view of a task that is requested from the service once the task
+    /// has completed.
+    #[serde(default)]
+    completion_view: View,
+}
+
+/// A view of a task requested from the service.
+///
+/// While a task is running, only its state is requested (the `MINIMAL` view).
+/// Once the task has completed, it is requested once more with this view.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum View {
+    /// Every field except the standard output and standard error of the
+    /// executors, the content of inputs, and the system logs.
+    ///
+    /// This is useful for services where the full view of a task is very
+    /// large (e.g., megabytes of system logs).
+    Basic,
+
+    /// Every field.
+    #[default]
+    Full,
 }
 
 impl Config {
@@ -34,4 +63,79 @@ impl Config {
     pub fn http(&self) -> &http::Config {
         &self.http
     }
+
+    /// Gets the mappings from local output URLs to the remote locations where
+    /// the service stores them.
+    pub fn output_mappings(&self) -> &[OutputMapping] {
+        &self.output_mappings
+    }
+
+    /// Gets the view of a task that is requested from the service once the
+    /// task has completed.
+    pub fn completion_view(&self) -> View {
+        self.completion_view
+    }
+}
+
+/// A mapping from local output URLs to the remote location where the service
+/// stores them.
+///
+/// Outputs with a URL that starts with the `local` prefix are submitted to the
+/// service with that prefix replaced by the `remote` prefix. Once the task
+/// completes, the outputs are downloaded from the remote location to the local
+/// URL.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct OutputMapping {
+    /// The prefix of local output URLs (e.g., `file:///data/results/`).
+    local: Url,
+
+    /// The prefix of the remote location (e.g., `s3://bucket/results/`).
+    remote: Url,
+}
+
+impl OutputMapping {
+    /// Creates a new [`OutputMapping`].
+    pub fn new(local: Url, remote: Url) -> Self {
+        Self { local, remote }
+    }
+
+    /// Gets the prefix of local output URLs.
+    pub fn local(&self) -> &Url {
+        &self.local
+    }
+
+    /// Gets the prefix of the remote location.
+    pub fn remote(&self) -> &Url {
+        &self.remote
+    }
+
+    /// Maps a local output URL to its remote location (or returns [`None`] if
+    /// the URL does not start with the local prefix).
+    pub fn map(&self, url: &Url) -> Option<Url> {
+        let rest = url.as_str().strip_prefix(self.local.as_str())?;
+        Url::parse(&format!("{}{rest}", self.remote)).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outputs_are_mapped() {
+        let mapping = OutputMapping::new(
+            "file:///data/results/".parse().unwrap(),
+            "s3://bucket/results/".parse().unwrap(),
+        );
+
+        assert_eq!(
+            mapping
+                .map(&"file:///data/results/sample/out.bam".parse().unwrap())
+                .unwrap()
+                .as_str(),
+            "s3://bucket/results/sample/out.bam"
+        );
+        assert_eq!(mapping.map(&"file:///tmp/out.bam".parse().unwrap()), None);
+    }
 }
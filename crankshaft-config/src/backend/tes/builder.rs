@@ -3,6 +3,8 @@
 use url::Url;
 
 use crate::backend::tes::Config;
+use crate::backend::tes::OutputMapping;
+use crate::backend::tes::View;
 use crate::backend::tes::http;
 
 /// An error related to a [`Builder`].
@@ -36,6 +38,14 @@ pub struct Builder {
 
     /// More nuanced, HTTP-related configuration.
     http: Option<http::Config>,
+
+    /// The mappings from local output URLs to the remote locations where the
+    /// service stores them.
+    output_mappings: Vec<OutputMapping>,
+
+    /// The view of a task that is requested from the service once the task
+    /// has completed.
+    completion_view: View,
 }
 
 impl Builder {
@@ -62,6 +72,48 @@ impl Builder {
         self
     }
 
+    /// Sets the maximum number of retries for a failed request for the
+    /// [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous maximum number of retries set
+    /// within the builder.
+    pub fn retries(mut self, retries: u32) -> Self {
+        let mut http = self.http.unwrap_or_default();
+        http.retries = Some(retries);
+        self.http = Some(http);
+        self
+    }
+
+    /// Sets the number of consecutive failed requests after which the backend
+    /// stops submitting tasks for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous failure thresholds set within
+    /// the builder.
+    pub fn failure_threshold(mut self, threshold: u32) -> Self {
+        let mut http = self.http.unwrap_or_default();
+        http.failure_threshold = Some(threshold);
+        self.http = Some(http);
+        self
+    }
+
+    /// Sets the number of seconds between checks of whether the service is
+    /// reachable again for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous probe intervals set within the
+    /// builder.
+    pub fn probe_interval(mut self, seconds: u64) -> Self {
+        let mut http = self.http.unwrap_or_default();
+        http.probe_interval = Some(seconds);
+        self.http = Some(http);
+        self
+    }
+
     /// Sets the HTTP-related configuration for the [`Builder`].
     ///
     /// # Notes
@@ -73,11 +125,35 @@ impl Builder {
         self
     }
 
+    /// Adds a mapping from local output URLs to the remote location where the
+    /// service stores them to the [`Builder`].
+    pub fn output_mapping(mut self, mapping: OutputMapping) -> Self {
+        self.output_mappings.push(mapping);
+        self
+    }
+
+    /// Sets the view of a task that is requested from the service once the
+    /// task has completed for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous completion views set within
+    /// the builder.
+    pub fn completion_view(mut self, view: View) -> Self {
+        self.completion_view = view;
+        self
+    }
+
     /// Consumes `self` and returns a built [`Config`].
     pub fn try_build(self) -> Result<Config> {
         let url = self.url.ok_or(Error::Missing("url"))?;
         let http = self.http.ok_or(Error::Missing("http"))?;
 
-        Ok(Config { url, http })
+        Ok(Config {
+            url,
+            http,
+            output_mappings: self.output_mappings,
+            completion_view: self.completion_view,
+        })
     }
 }
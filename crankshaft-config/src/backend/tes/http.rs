@@ -11,6 +11,18 @@ use serde::Serialize;
 pub struct Config {
     /// If needed, the basic auth token to provide to the service.
     pub(crate) basic_auth_token: Option<String>,
+
+    /// The maximum number of times a failed request is retried (with
+    /// exponential backoff) before it is considered a failure.
+    pub(crate) retries: Option<u32>,
+
+    /// The number of consecutive failed requests after which the backend stops
+    /// submitting tasks until the service is reachable again.
+    pub(crate) failure_threshold: Option<u32>,
+
+    /// The number of seconds between checks of whether the service is
+    /// reachable again once the failure threshold has been reached.
+    pub(crate) probe_interval: Option<u64>,
 }
 
 impl Config {
@@ -18,6 +30,23 @@ impl Config {
     pub fn basic_auth_token(&self) -> Option<&str> {
         self.basic_auth_token.as_deref()
     }
+
+    /// Gets the maximum number of retries for a failed request (if it exists).
+    pub fn retries(&self) -> Option<u32> {
+        self.retries
+    }
+
+    /// Gets the number of consecutive failed requests after which the backend
+    /// stops submitting tasks (if it exists).
+    pub fn failure_threshold(&self) -> Option<u32> {
+        self.failure_threshold
+    }
+
+    /// Gets the number of seconds between checks of whether the service is
+    /// reachable again (if it exists).
+    pub fn probe_interval(&self) -> Option<u64> {
+        self.probe_interval
+    }
 }
 
 #[cfg(test)]
@@ -28,5 +57,8 @@ mod tests {
     fn defaults() {
         let options = Config::default();
         assert_eq!(options.basic_auth_token, None);
+        assert_eq!(options.retries, None);
+        assert_eq!(options.failure_threshold, None);
+        assert_eq!(options.probe_interval, None);
     }
 }
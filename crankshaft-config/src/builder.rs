@@ -2,12 +2,16 @@
 
 use crate::Config;
 use crate::backend;
+use crate::webhook;
 
 /// A builder for a [global configuration object for Crankshaft](Config).
 #[derive(Default)]
 pub struct Builder {
     /// All registered backends.
     backends: Vec<backend::Config>,
+
+    /// The webhooks that receive the events of the engine.
+    webhooks: Vec<webhook::Config>,
 }
 
 impl Builder {
@@ -17,10 +21,18 @@ impl Builder {
         self
     }
 
+    /// Adds a webhook to the [`Builder`].
+    pub fn push_webhook(mut self, config: webhook::Config) -> Self {
+        self.webhooks.push(config);
+        self
+    }
+
     /// Consumes `self` and builds a [`Config`].
     pub fn build(self) -> Config {
         Config {
             backends: self.backends,
+            webhooks: self.webhooks,
+            sources: Default::default(),
         }
     }
 }
@@ -0,0 +1,262 @@
+//! Machine-readable exports of an effective configuration.
+//!
+//! A [`Document`] contains the fully merged configuration along with the
+//! [`Source`] that each value came from, which is useful for understanding why
+//! a configuration behaves the way it does (e.g., when attached to a support
+//! ticket). See [`Config::to_document()`](crate::Config::to_document).
+//!
+//! Values are identified by the same dotted paths that
+//! [overrides](crate::overrides) use (e.g., `backends.0.max-tasks`). Secrets
+//! (such as the secrets of webhooks and the basic auth tokens of TES backends)
+//! are redacted.
+
+use std::collections::BTreeMap;
+
+use config::Value;
+use config::ValueKind;
+use serde::Serialize;
+
+use crate::BACKENDS_ENV_VAR;
+use crate::ENV_PREFIX;
+
+/// The keys of values that are redacted within a [`Document`].
+const REDACTED_KEYS: &[&str] = &["secret", "basic-auth-token"];
+
+/// The value that redacted values are replaced with.
+const REDACTED: &str = "<redacted>";
+
+/// The origin that the [`config`] crate assigns to values from environment
+/// variables.
+const ENVIRONMENT_ORIGIN: &str = "the environment";
+
+/// The source a configuration value came from.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Source {
+    /// A configuration file (at the provided path).
+    File(String),
+
+    /// An environment variable (with the provided name).
+    Environment(String),
+
+    /// An [override](crate::Override).
+    Override,
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::File(path) => write!(f, "file `{path}`"),
+            Source::Environment(name) => write!(f, "environment variable `{name}`"),
+            Source::Override => write!(f, "override"),
+        }
+    }
+}
+
+/// The sources of the values within a configuration.
+///
+/// Sources are keyed by the dotted path of a value. A source for a path also
+/// applies to every value nested beneath it (unless a more specific path has
+/// its own source), which is how backends defined inline within
+/// [`BACKENDS_ENV_VAR`] are recorded.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Sources(BTreeMap<String, Source>);
+
+impl Sources {
+    /// Collects the sources of the values within a configuration built by the
+    /// [`config`] crate.
+    pub(crate) fn collect(root: &Value) -> Self {
+        let mut sources = Self::default();
+        sources.walk(String::new(), root);
+        sources
+    }
+
+    /// Records the sources of a value and every value nested beneath it.
+    fn walk(&mut self, path: String, value: &Value) {
+        let child = |key: &dyn std::fmt::Display| match path.is_empty() {
+            true => key.to_string(),
+            false => format!("{path}.{key}"),
+        };
+
+        match &value.kind {
+            ValueKind::Table(table) => {
+                for (key, value) in table {
+                    self.walk(child(key), value);
+                }
+            }
+            ValueKind::Array(array) => {
+                for (index, value) in array.iter().enumerate() {
+                    self.walk(child(&index), value);
+                }
+            }
+            _ => {
+                let source = match value.origin() {
+                    // NOTE: the environment variables are only read with a
+                    // prefix (and no nesting separator), so the name of the
+                    // variable is recovered from the key of the value.
+                    Some(ENVIRONMENT_ORIGIN) => {
+                        Source::Environment(format!("{ENV_PREFIX}_{}", path.to_uppercase()))
+                    }
+                    Some(origin) => Source::File(origin.to_owned()),
+                    None => return,
+                };
+
+                self.0.insert(path, source);
+            }
+        }
+    }
+
+    /// Sets the source of the value at a path (and every value nested beneath
+    /// it).
+    pub(crate) fn set(&mut self, path: impl Into<String>, source: Source) {
+        let path = path.into();
+        let nested = format!("{path}.");
+        self.0.retain(|key, _| !key.starts_with(&nested));
+        self.0.insert(path, source);
+    }
+
+    /// Sets the source of a backend defined inline within
+    /// [`BACKENDS_ENV_VAR`].
+    pub(crate) fn set_inline_backend(&mut self, index: usize) {
+        self.set(
+            format!("backends.{index}"),
+            Source::Environment(BACKENDS_ENV_VAR.to_owned()),
+        );
+    }
+
+    /// Gets the source of the value at a path (if it is known).
+    pub(crate) fn get(&self, path: &str) -> Option<&Source> {
+        let mut path = path;
+
+        loop {
+            if let Some(source) = self.0.get(path) {
+                return Some(source);
+            }
+
+            path = &path[..path.rfind('.')?];
+        }
+    }
+}
+
+/// A value within a [`Document`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Entry {
+    /// The value.
+    value: serde_json::Value,
+
+    /// The source the value came from (if it is known).
+    ///
+    /// Values without a source were set programmatically or were not set at
+    /// all (in which case the default applies).
+    source: Option<Source>,
+}
+
+impl Entry {
+    /// Gets the value.
+    pub fn value(&self) -> &serde_json::Value {
+        &self.value
+    }
+
+    /// Gets the source the value came from (if it is known).
+    pub fn source(&self) -> Option<&Source> {
+        self.source.as_ref()
+    }
+}
+
+/// A fully merged configuration annotated with the source of each value.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Document {
+    /// The merged configuration.
+    config: serde_json::Value,
+
+    /// Every value within the configuration, keyed by its dotted path.
+    values: BTreeMap<String, Entry>,
+}
+
+impl Document {
+    /// Creates a new [`Document`] from a serialized configuration and the
+    /// sources of its values.
+    pub(crate) fn new(mut config: serde_json::Value, sources: &Sources) -> Self {
+        redact(&mut config);
+
+        let mut values = BTreeMap::new();
+        flatten(String::new(), &config, &mut |path, value| {
+            let source = sources.get(&path).cloned();
+            values.insert(
+                path,
+                Entry {
+                    value: value.clone(),
+                    source,
+                },
+            );
+        });
+
+        Self { config, values }
+    }
+
+    /// Gets the merged configuration.
+    pub fn config(&self) -> &serde_json::Value {
+        &self.config
+    }
+
+    /// Gets the value at a dotted path (e.g., `backends.0.max-tasks`).
+    pub fn get(&self, path: &str) -> Option<&Entry> {
+        self.values.get(path)
+    }
+
+    /// Gets every value within the configuration, keyed by its dotted path.
+    pub fn values(&self) -> &BTreeMap<String, Entry> {
+        &self.values
+    }
+
+    /// Serializes the document as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        // SAFETY: a document only contains JSON values and strings, which
+        // always serialize.
+        serde_json::to_string_pretty(self).expect("documents should serialize")
+    }
+}
+
+/// Replaces the values of every [redacted key](REDACTED_KEYS) that is set.
+fn redact(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(object) => {
+            for (key, value) in object {
+                if REDACTED_KEYS.contains(&key.as_str()) && !value.is_null() {
+                    *value = serde_json::Value::from(REDACTED);
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        serde_json::Value::Array(array) => array.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Calls `f` with the dotted path of every value that is not an object or an
+/// array.
+fn flatten(
+    path: String,
+    value: &serde_json::Value,
+    f: &mut impl FnMut(String, &serde_json::Value),
+) {
+    let child = |key: &dyn std::fmt::Display| match path.is_empty() {
+        true => key.to_string(),
+        false => format!("{path}.{key}"),
+    };
+
+    match value {
+        serde_json::Value::Object(object) => {
+            for (key, value) in object {
+                flatten(child(key), value, f);
+            }
+        }
+        serde_json::Value::Array(array) => {
+            for (index, value) in array.iter().enumerate() {
+                flatten(child(&index), value, f);
+            }
+        }
+        value => f(path, value),
+    }
+}
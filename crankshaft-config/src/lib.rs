@@ -15,19 +15,40 @@ use config::ConfigBuilder;
 use config::ConfigError as Error;
 use config::Environment;
 use config::File;
+use config::FileFormat;
+use config::Map;
 use config::builder::DefaultState;
 use serde::Deserialize;
 use serde::Serialize;
 
 pub mod backend;
 mod builder;
+pub mod document;
+pub mod lint;
+pub mod overrides;
+pub mod webhook;
 
 pub use builder::Builder;
+pub use document::Document;
+use document::Source;
+use document::Sources;
+pub use lint::Lint;
+pub use overrides::Override;
 
 /// The prefix for any environment variables that influence the configuration of
 /// Crankshaft.
 pub const ENV_PREFIX: &str = "CRANKSHAFT";
 
+/// The environment variable that may contain inline backend definitions.
+///
+/// The value is expected to be a JSON array of backend configurations (in the
+/// same shape as the `backends` array within a configuration file), e.g.,
+///
+/// ```text
+/// CRANKSHAFT_BACKENDS='[{"name": "docker", "kind": "Docker", "max-tasks": 10}]'
+/// ```
+pub const BACKENDS_ENV_VAR: &str = "CRANKSHAFT_BACKENDS";
+
 /// The file name (sans the extension) used when looking for configuration files
 /// for Crankshaft.
 ///
@@ -45,10 +66,32 @@ pub type Result<T> = std::result::Result<T, Error>;
 /// * `<CONFIG DIR>/crankshaft/Crankshaft.toml`.
 /// * `<CWD>/Crankshaft.toml`.
 /// * Environment variables starting with `CRANKSHAFT_`.
+/// * Inline backend definitions within the `CRANKSHAFT_BACKENDS` environment
+///   variable (see [`BACKENDS_ENV_VAR`]).
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "kebab-case")]
 pub struct Config {
     /// All registered backends.
+    #[serde(default)]
+    backends: Vec<backend::Config>,
+
+    /// The webhooks that receive the events of the engine.
+    #[serde(default)]
+    webhooks: Vec<webhook::Config>,
+
+    /// The sources of the values within the configuration (if it was loaded
+    /// from sources).
+    #[serde(skip)]
+    sources: Sources,
+}
+
+/// The backends within a configuration.
+///
+/// This is used to deserialize the inline backend definitions provided within
+/// [`BACKENDS_ENV_VAR`].
+#[derive(Deserialize)]
+struct Backends {
+    /// The backends.
     backends: Vec<backend::Config>,
 }
 
@@ -63,6 +106,35 @@ impl Config {
         self.backends.as_slice()
     }
 
+    /// Gets the webhooks that receive the events of the engine.
+    pub fn webhooks(&self) -> &[webhook::Config] {
+        &self.webhooks
+    }
+
+    /// Validates the configuration.
+    ///
+    /// Every problem found is returned as a [`Lint`] (ordered by backend).
+    /// Lints at the [`Error`](lint::Level::Error) level indicate
+    /// configurations that will not work, whereas lints at the
+    /// [`Warning`](lint::Level::Warning) level indicate configurations that
+    /// are likely to behave unexpectedly.
+    pub fn validate(&self) -> Vec<Lint> {
+        lint::lint(self)
+    }
+
+    /// Exports the effective configuration as a [`Document`].
+    ///
+    /// The document contains the fully merged configuration along with the
+    /// source (the file, environment variable, or override) of each value
+    /// that was loaded from a source. It is typically serialized as JSON with
+    /// [`Document::to_json()`] (e.g., to attach to a support ticket).
+    pub fn to_document(&self) -> Document {
+        // SAFETY: configurations are always deserialized from (or built out
+        // of) values that serialize.
+        let config = serde_json::to_value(self).expect("configurations should serialize");
+        Document::new(config, &self.sources)
+    }
+
     /// Consumes `self` and returns the backends.
     pub fn into_backends(self) -> impl Iterator<Item = backend::Config> {
         self.backends.into_iter()
@@ -83,7 +155,78 @@ impl Config {
             builder = builder.add_source(File::from(path));
         }
 
-        builder.add_source(Environment::with_prefix(ENV_PREFIX))
+        // NOTE: the inline backend definitions are excluded here, as they are
+        // JSON rather than a single value and are merged in separately (see
+        // [`Self::from_sources()`]).
+        let vars = std::env::vars()
+            .filter(|(key, _)| key != BACKENDS_ENV_VAR)
+            .collect::<Map<_, _>>();
+
+        builder.add_source(Environment::with_prefix(ENV_PREFIX).source(Some(vars)))
+    }
+
+    /// Builds a [`Config`] from a set of sources and merges in any inline
+    /// backend definitions from [`BACKENDS_ENV_VAR`].
+    fn from_sources(builder: ConfigBuilder<DefaultState>) -> Result<Self> {
+        let mut config = Self::build(builder)?;
+
+        if let Ok(json) = std::env::var(BACKENDS_ENV_VAR) {
+            config.merge_backends(Self::parse_backends(&json)?);
+        }
+
+        Ok(config)
+    }
+
+    /// Builds a [`Config`] from a set of sources, recording the source of each
+    /// value.
+    fn build(builder: ConfigBuilder<DefaultState>) -> Result<Self> {
+        let built = builder.build()?;
+        let sources = Sources::collect(&built.cache);
+
+        let mut config: Self = built.try_deserialize()?;
+        config.sources = sources;
+        Ok(config)
+    }
+
+    /// Parses a JSON array of backend definitions.
+    fn parse_backends(json: &str) -> Result<Vec<backend::Config>> {
+        let backends: Backends = ConfigCrate::builder()
+            .add_source(File::from_str(
+                &format!(r#"{{"backends": {json}}}"#),
+                FileFormat::Json,
+            ))
+            .build()?
+            .try_deserialize()?;
+
+        Ok(backends.backends)
+    }
+
+    /// Merges a set of backends into the configuration.
+    ///
+    /// A backend with the same name as an existing backend replaces it (in
+    /// place). All other backends are appended.
+    fn merge_backends(&mut self, backends: impl IntoIterator<Item = backend::Config>) {
+        for backend in backends {
+            let name = backend.name().to_owned();
+
+            match self
+                .backends
+                .iter_mut()
+                .find(|existing| existing.name() == backend.name())
+            {
+                Some(existing) => *existing = backend,
+                None => self.backends.push(backend),
+            }
+
+            // SAFETY: a backend with the same name was either just replaced
+            // or appended above.
+            let index = self
+                .backends
+                .iter()
+                .position(|existing| existing.name() == name)
+                .unwrap();
+            self.sources.set_inline_backend(index);
+        }
     }
 
     /// Loads a [`Config`] from the default set of sources.
@@ -91,7 +234,7 @@ impl Config {
     /// The default set of sources are loaded first (see the docs for [`Config`]
     /// for the listed default sources).
     pub fn load() -> Result<Self> {
-        Self::default_sources().build()?.try_deserialize()
+        Self::from_sources(Self::default_sources())
     }
 
     /// Loads the global configuration from a set of sources.
@@ -110,7 +253,33 @@ impl Config {
             builder = builder.add_source(File::from(path.as_ref()));
         }
 
-        builder.build()?.try_deserialize()
+        Self::from_sources(builder)
+    }
+
+    /// Applies a set of [`Override`]s to the configuration.
+    ///
+    /// Overrides take precedence over every other source (including inline
+    /// backend definitions), so this is typically called on a loaded
+    /// configuration with the overrides provided on the command line, e.g.,
+    ///
+    /// ```text
+    /// --set backends.0.max-tasks=20
+    /// ```
+    ///
+    /// Overrides are applied in order, so later overrides of the same value
+    /// take precedence over earlier ones.
+    pub fn with_overrides(self, overrides: impl IntoIterator<Item = Override>) -> Result<Self> {
+        let mut builder = ConfigCrate::builder().add_source(ConfigCrate::try_from(&self)?);
+        let mut sources = self.sources;
+
+        for value in overrides {
+            builder = builder.set_override(value.key(), value.value())?;
+            sources.set(value.path(), Source::Override);
+        }
+
+        let mut config: Self = builder.build()?.try_deserialize()?;
+        config.sources = sources;
+        Ok(config)
     }
 
     /// Loads a config from a test fixture.
@@ -125,16 +294,16 @@ impl Config {
 
         full_path.push(path);
 
-        ConfigCrate::builder()
-            .add_source(File::from(full_path))
-            .build()?
-            .try_deserialize()
+        Self::build(ConfigCrate::builder().add_source(File::from(full_path)))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::BACKENDS_ENV_VAR;
     use super::Config;
+    use super::Override;
+    use super::Source;
 
     #[test]
     fn loading_file_returns_valid_backends() {
@@ -151,4 +320,101 @@ mod tests {
         assert_eq!(backend.defaults().unwrap().cpu(), Some(1));
         assert_eq!(backend.defaults().unwrap().ram(), Some(1.0));
     }
+
+    #[test]
+    fn inline_backends_are_merged() {
+        let mut config = Config::fixture("example.toml").unwrap();
+        let backends = Config::parse_backends(
+            r#"[
+                {"name": "quux", "kind": "Docker", "max-tasks": 2},
+                {"name": "inline", "kind": "Docker", "max-tasks": 5}
+            ]"#,
+        )
+        .unwrap();
+
+        config.merge_backends(backends);
+
+        assert_eq!(config.backends.len(), 4);
+        assert_eq!(config.backends[1].name(), "quux");
+        assert_eq!(config.backends[1].max_tasks(), 2);
+        assert!(config.backends[1].defaults().is_none());
+        assert_eq!(config.backends[3].name(), "inline");
+        assert_eq!(config.backends[3].max_tasks(), 5);
+    }
+
+    #[test]
+    fn invalid_inline_backends_are_rejected() {
+        assert!(Config::parse_backends(r#"{"name": "docker"}"#).is_err());
+    }
+
+    #[test]
+    fn overrides_are_applied() {
+        let config = Config::fixture("example.toml")
+            .unwrap()
+            .with_overrides(
+                ["backends.1.max-tasks=20", "backends.2.name=renamed"]
+                    .into_iter()
+                    .map(|value| value.parse::<Override>().unwrap()),
+            )
+            .unwrap();
+
+        assert_eq!(config.backends.len(), 3);
+        assert_eq!(config.backends[1].name(), "quux");
+        assert_eq!(config.backends[1].max_tasks(), 20);
+        assert_eq!(config.backends[1].defaults().unwrap().cpu(), Some(1));
+        assert_eq!(config.backends[2].name(), "renamed");
+    }
+
+    #[test]
+    fn documents_record_the_source_of_each_value() {
+        let mut config = Config::fixture("example.toml").unwrap();
+        config.merge_backends(
+            Config::parse_backends(r#"[{"name": "inline", "kind": "Docker", "max-tasks": 5}]"#)
+                .unwrap(),
+        );
+        config.webhooks.push(
+            crate::webhook::Config::builder()
+                .url("https://example.com/hook".parse::<url::Url>().unwrap())
+                .secret("hunter2")
+                .try_build()
+                .unwrap(),
+        );
+
+        let config = config
+            .with_overrides(["backends.1.max-tasks=20".parse::<Override>().unwrap()])
+            .unwrap();
+        let document = config.to_document();
+
+        let entry = document.get("backends.0.max-tasks").unwrap();
+        assert_eq!(entry.value(), 10);
+        assert!(matches!(
+            entry.source(),
+            Some(Source::File(path)) if path.ends_with("example.toml")
+        ));
+
+        let entry = document.get("backends.1.max-tasks").unwrap();
+        assert_eq!(entry.value(), 20);
+        assert_eq!(entry.source(), Some(&Source::Override));
+
+        assert_eq!(
+            document.get("backends.3.name").unwrap().source(),
+            Some(&Source::Environment(String::from(BACKENDS_ENV_VAR)))
+        );
+
+        // Secrets are redacted, and programmatically set values have no
+        // source.
+        let entry = document.get("webhooks.0.secret").unwrap();
+        assert_eq!(entry.value(), "<redacted>");
+        assert_eq!(entry.source(), None);
+        assert!(!document.to_json().contains("hunter2"));
+    }
+
+    #[test]
+    fn invalid_override_values_are_rejected() {
+        let result = Config::fixture("example.toml")
+            .unwrap()
+            .with_overrides(["backends.1.max-tasks=lots".parse::<Override>().unwrap()]);
+
+        assert!(result.is_err());
+    }
 }
@@ -0,0 +1,460 @@
+//! Linting of configurations.
+//!
+//! Unlike deserialization errors (which prevent a configuration from being
+//! loaded at all), lints flag configurations that load successfully but are
+//! likely to be mistakes. Each [`Lint`] has a [`Level`] and (where possible) an
+//! actionable hint, so that command line tools can report them to users. See
+//! [`Config::validate()`](crate::Config::validate).
+
+use std::collections::HashSet;
+
+use regex::Regex;
+
+use crate::Config;
+use crate::backend;
+use crate::backend::Kind;
+use crate::backend::schedule::Day;
+
+/// The level of a [`Lint`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// The configuration is likely to behave unexpectedly.
+    Warning,
+
+    /// The configuration will not work.
+    Error,
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Level::Warning => write!(f, "warning"),
+            Level::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found within a configuration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lint {
+    /// The level of the lint.
+    level: Level,
+
+    /// The name of the backend the lint applies to (if it applies to a
+    /// backend).
+    backend: Option<String>,
+
+    /// A description of the problem.
+    message: String,
+
+    /// A hint for how to address the problem (if one exists).
+    hint: Option<String>,
+}
+
+impl Lint {
+    /// Creates a new [`Lint`] for a backend.
+    fn backend(
+        level: Level,
+        backend: &backend::Config,
+        message: impl Into<String>,
+        hint: impl Into<String>,
+    ) -> Self {
+        Self {
+            level,
+            backend: Some(backend.name().to_owned()),
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    /// Gets the level of the lint.
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    /// Gets the name of the backend the lint applies to (if it applies to a
+    /// backend).
+    pub fn backend_name(&self) -> Option<&str> {
+        self.backend.as_deref()
+    }
+
+    /// Gets a description of the problem.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Gets a hint for how to address the problem (if one exists).
+    pub fn hint(&self) -> Option<&str> {
+        self.hint.as_deref()
+    }
+}
+
+impl std::fmt::Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: ", self.level)?;
+
+        if let Some(backend) = &self.backend {
+            write!(f, "backend `{backend}`: ")?;
+        }
+
+        write!(f, "{}", self.message)?;
+
+        if let Some(hint) = &self.hint {
+            write!(f, "\n  hint: {hint}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Lints a backend.
+fn lint_backend(config: &backend::Config, lints: &mut Vec<Lint>) {
+    if config.max_tasks() == 0 {
+        lints.push(Lint::backend(
+            Level::Error,
+            config,
+            "`max-tasks` is zero, so no tasks will ever run",
+            "set `max-tasks` to at least one",
+        ));
+    }
+
+    // NOTE: schedules repeat weekly, so checking from any minute suffices.
+    if config
+        .schedule()
+        .minutes_until_open(Day::Monday, 0)
+        .is_none()
+    {
+        lints.push(Lint::backend(
+            Level::Error,
+            config,
+            "the schedule never accepts submissions, so no tasks will ever run",
+            "make sure the maintenance windows do not cover every open window",
+        ));
+    }
+
+    if let Some(retry) = config.defaults().and_then(|defaults| defaults.retry()) {
+        if retry.max_attempts() == 0 {
+            lints.push(Lint::backend(
+                Level::Error,
+                config,
+                "the retry policy has zero `max-attempts`",
+                "set `max-attempts` to at least one (which disables retries)",
+            ));
+        } else if retry.max_attempts() > 1 && !retry.on_preemption() && !retry.on_failure() {
+            lints.push(Lint::backend(
+                Level::Warning,
+                config,
+                "the retry policy allows retries but neither `on-preemption` nor `on-failure` is \
+                 set, so tasks are never retried",
+                "set `on-preemption` or `on-failure` (or remove the retry policy)",
+            ));
+        }
+    }
+
+    match config.kind() {
+        Kind::Docker(docker) => {
+            if !docker.cleanup() {
+                lints.push(Lint::backend(
+                    Level::Warning,
+                    config,
+                    "containers are not removed after their tasks complete",
+                    "set `cleanup = true` unless containers need to be inspected after the fact",
+                ));
+            }
+
+            if let Some(version) = docker.api_version() {
+                let valid = version.trim_start_matches('v').split_once('.').is_some_and(
+                    |(major, minor)| {
+                        major.parse::<usize>().is_ok() && minor.parse::<usize>().is_ok()
+                    },
+                );
+
+                if !valid {
+                    lints.push(Lint::backend(
+                        Level::Error,
+                        config,
+                        format!("`api-version` (`{version}`) is not a valid Docker API version"),
+                        "use a version of the form `1.41` (or remove it to negotiate the version)",
+                    ));
+                }
+            }
+        }
+        Kind::Generic(generic) => {
+            if generic.kill().trim().is_empty() {
+                lints.push(Lint::backend(
+                    Level::Warning,
+                    config,
+                    "the `kill` command is empty, so canceled jobs keep running",
+                    "set `kill` to the command that cancels a job (e.g., `scancel ~{job_id}`)",
+                ));
+            }
+
+            if let Some(pattern) = generic.job_id_regex() {
+                match Regex::new(pattern) {
+                    Ok(regex) if regex.captures_len() < 2 => lints.push(Lint::backend(
+                        Level::Error,
+                        config,
+                        format!("`job-id-regex` (`{pattern}`) has no capture group"),
+                        "wrap the part of the pattern matching the job id in parentheses",
+                    )),
+                    Ok(_) => {}
+                    Err(err) => lints.push(Lint::backend(
+                        Level::Error,
+                        config,
+                        format!("`job-id-regex` is not a valid regex: {err}"),
+                        "fix the syntax of the pattern",
+                    )),
+                }
+            }
+
+            if generic.uses_exit_code_file()
+                && !generic
+                    .driver()
+                    .shell()
+                    .is_none_or(|shell| shell.is_posix())
+            {
+                lints.push(Lint::backend(
+                    Level::Error,
+                    config,
+                    "`submit` uses `~{exit_code_file}`, which requires a POSIX shell",
+                    "use the `bash` or `sh` shell (or remove `~{exit_code_file}` from `submit`)",
+                ));
+            }
+
+            if let Some(defaults) = config.defaults() {
+                if let Some(exceeded) = generic.check_limits(defaults.cpu(), defaults.ram()) {
+                    lints.push(Lint::backend(
+                        Level::Error,
+                        config,
+                        format!("the default resources exceed the maximums: {exceeded}"),
+                        "lower the defaults (or raise `max-cpu`/`max-ram`)",
+                    ));
+                }
+            }
+
+            if !generic.suspended_states().is_empty() && generic.state().is_none() {
+                lints.push(Lint::backend(
+                    Level::Warning,
+                    config,
+                    "`suspended-states` is set without a `state` command, so suspended jobs are \
+                     never detected",
+                    "set `state` to the command that prints the state of a job",
+                ));
+            }
+        }
+        Kind::TES(tes) => {
+            if tes.http().retries().unwrap_or_default() == 0 {
+                lints.push(Lint::backend(
+                    Level::Warning,
+                    config,
+                    "failed requests to the TES service are not retried",
+                    "set `retries` to retry transient failures with exponential backoff",
+                ));
+            }
+
+            if tes.http().basic_auth_token().is_some() && tes.url().scheme() == "http" {
+                lints.push(Lint::backend(
+                    Level::Warning,
+                    config,
+                    "the basic auth token is sent over unencrypted HTTP",
+                    "use an `https` URL for the TES service",
+                ));
+            }
+        }
+        Kind::Kubernetes(k8s) => {
+            if k8s.poll_interval() == 0 {
+                lints.push(Lint::backend(
+                    Level::Warning,
+                    config,
+                    "`poll-interval` is zero, so the status of each Job is polled continuously",
+                    "set `poll-interval` to at least one second",
+                ));
+            }
+        }
+        Kind::Slurm(slurm) => {
+            if let Some(arg) = slurm.extra_args().iter().find(|arg| {
+                ["--parsable", "--wrap", "--output", "--error", "-o", "-e"]
+                    .iter()
+                    .any(|flag| arg.split('=').next() == Some(flag))
+            }) {
+                lints.push(Lint::backend(
+                    Level::Error,
+                    config,
+                    format!("`extra-args` contains `{arg}`, which is set by the backend itself"),
+                    "remove the argument from `extra-args`",
+                ));
+            }
+        }
+        Kind::Lsf(lsf) => {
+            if let Some(arg) = lsf
+                .extra_args()
+                .iter()
+                .find(|arg| ["-o", "-oo", "-e", "-eo", "-J", "-K"].contains(&arg.as_str()))
+            {
+                lints.push(Lint::backend(
+                    Level::Error,
+                    config,
+                    format!("`extra-args` contains `{arg}`, which is set by the backend itself"),
+                    "remove the argument from `extra-args`",
+                ));
+            }
+        }
+        Kind::Local(_) | Kind::Apptainer(_) => {}
+    }
+}
+
+/// Lints a configuration.
+pub(crate) fn lint(config: &Config) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    let mut names = HashSet::new();
+
+    for backend in config.backends() {
+        if !names.insert(backend.name()) {
+            lints.push(Lint::backend(
+                Level::Error,
+                backend,
+                "multiple backends share this name, so only one of them can be used",
+                "give each backend a unique name",
+            ));
+        }
+
+        lint_backend(backend, &mut lints);
+    }
+
+    lints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::Schedule;
+    use crate::backend::generic::driver::Shell;
+    use crate::backend::schedule::TimeOfDay;
+    use crate::backend::schedule::Window;
+
+    /// Gets a time of day.
+    fn time(hour: u16, minute: u16) -> TimeOfDay {
+        TimeOfDay::new(hour, minute).unwrap()
+    }
+
+    #[test]
+    fn exit_code_files_require_a_posix_shell() {
+        let backend = |shell| {
+            backend::Config::builder()
+                .name("generic")
+                .kind(Kind::Generic(
+                    backend::generic::Config::builder()
+                        .driver(
+                            backend::generic::driver::Config::builder()
+                                .shell(shell)
+                                .build(),
+                        )
+                        .submit("(~{shell}); echo $? > ~{exit_code_file}")
+                        .monitor("true")
+                        .kill("true")
+                        .try_build()
+                        .unwrap(),
+                ))
+                .max_tasks(1)
+                .try_build()
+                .unwrap()
+        };
+
+        let config = Config::builder().push_backend(backend(Shell::Sh)).build();
+        assert!(config.validate().is_empty());
+
+        let config = Config::builder()
+            .push_backend(backend(Shell::PowerShell))
+            .build();
+        let lints = config.validate();
+        assert_eq!(lints.len(), 1);
+        assert_eq!(
+            lints[0].message(),
+            "`submit` uses `~{exit_code_file}`, which requires a POSIX shell"
+        );
+    }
+
+    #[test]
+    fn backends_are_linted() {
+        let config = Config::builder()
+            .push_backend(
+                backend::Config::builder()
+                    .name("slurm")
+                    .kind(Kind::Generic(
+                        backend::generic::Config::builder()
+                            .default_driver()
+                            .submit("sbatch ~{shell}")
+                            .job_id_regex(r"Submitted batch job \d+")
+                            .monitor("squeue -j ~{job_id}")
+                            .kill(" ")
+                            .try_build()
+                            .unwrap(),
+                    ))
+                    .max_tasks(10)
+                    .try_build()
+                    .unwrap(),
+            )
+            .push_backend(
+                backend::Config::builder()
+                    .name("slurm")
+                    .kind(Kind::Docker(
+                        backend::docker::Config::builder().cleanup(false).build(),
+                    ))
+                    .max_tasks(0)
+                    .schedule(Schedule::new(
+                        [Window::new([Day::Monday], time(8, 0), time(18, 0))],
+                        [Window::new([], time(6, 0), time(20, 0))],
+                    ))
+                    .try_build()
+                    .unwrap(),
+            )
+            .build();
+
+        let lints = config.validate();
+        let messages = lints
+            .iter()
+            .map(|lint| (lint.level(), lint.message()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            messages,
+            [
+                (
+                    Level::Warning,
+                    "the `kill` command is empty, so canceled jobs keep running"
+                ),
+                (
+                    Level::Error,
+                    r"`job-id-regex` (`Submitted batch job \d+`) has no capture group"
+                ),
+                (
+                    Level::Error,
+                    "multiple backends share this name, so only one of them can be used"
+                ),
+                (
+                    Level::Error,
+                    "`max-tasks` is zero, so no tasks will ever run"
+                ),
+                (
+                    Level::Error,
+                    "the schedule never accepts submissions, so no tasks will ever run"
+                ),
+                (
+                    Level::Warning,
+                    "containers are not removed after their tasks complete"
+                ),
+            ]
+        );
+
+        assert!(
+            lints
+                .iter()
+                .all(|lint| lint.backend_name() == Some("slurm"))
+        );
+        assert_eq!(
+            lints[3].to_string(),
+            "error: backend `slurm`: `max-tasks` is zero, so no tasks will ever run\n  hint: set \
+             `max-tasks` to at least one"
+        );
+    }
+}
@@ -0,0 +1,138 @@
+//! Overrides of individual configuration values.
+//!
+//! Overrides are written as `<path>=<value>`, where the path is a dotted path
+//! to the value within the configuration and numeric segments index into
+//! arrays (e.g., `backends.0.max-tasks=20`). They are intended to be accepted
+//! directly from the command line, e.g., with `clap`:
+//!
+//! ```ignore
+//! #[arg(long = "set", value_name = "PATH=VALUE")]
+//! overrides: Vec<crankshaft_config::Override>,
+//! ```
+//!
+//! See [`Config::with_overrides()`](crate::Config::with_overrides) for applying
+//! them.
+
+use std::str::FromStr;
+
+/// An error related to parsing an [`Override`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The override did not contain a `=` separating the path and the value.
+    MissingValue(String),
+
+    /// The path of the override was empty or contained an empty segment.
+    InvalidPath(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::MissingValue(s) => {
+                write!(f, "override `{s}` must be in the form `<path>=<value>`")
+            }
+            Error::InvalidPath(path) => write!(f, "override path `{path}` is not valid"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// An override of a single configuration value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Override {
+    /// The dotted path to the value.
+    path: String,
+
+    /// The value.
+    value: String,
+}
+
+impl Override {
+    /// Gets the dotted path to the value (e.g., `backends.0.max-tasks`).
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Gets the value.
+    ///
+    /// Values are converted to the type of the configuration value they
+    /// override when the configuration is deserialized.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Gets the key of the override in the path expression syntax used by
+    /// the [`config`] crate (e.g., `backends[0].max-tasks`).
+    pub(crate) fn key(&self) -> String {
+        let mut key = String::new();
+
+        for segment in self.path.split('.') {
+            if segment.bytes().all(|b| b.is_ascii_digit()) {
+                key.push_str(&format!("[{segment}]"));
+            } else {
+                if !key.is_empty() {
+                    key.push('.');
+                }
+
+                key.push_str(segment);
+            }
+        }
+
+        key
+    }
+}
+
+impl FromStr for Override {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (path, value) = s
+            .split_once('=')
+            .ok_or_else(|| Error::MissingValue(s.to_owned()))?;
+
+        let path = path.trim();
+
+        // NOTE: the first segment must name a field (rather than index into
+        // an array), as the root of the configuration is not an array.
+        let mut segments = path.split('.');
+        if segments.clone().any(str::is_empty)
+            || segments
+                .next()
+                .is_some_and(|first| first.bytes().all(|b| b.is_ascii_digit()))
+        {
+            return Err(Error::InvalidPath(path.to_owned()));
+        }
+
+        Ok(Self {
+            path: path.to_owned(),
+            value: value.to_owned(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_are_parsed() {
+        let value = "backends.0.max-tasks=20".parse::<Override>().unwrap();
+        assert_eq!(value.path(), "backends.0.max-tasks");
+        assert_eq!(value.value(), "20");
+        assert_eq!(value.key(), "backends[0].max-tasks");
+
+        assert_eq!(
+            "backends.0".parse::<Override>(),
+            Err(Error::MissingValue(String::from("backends.0")))
+        );
+        assert_eq!(
+            "backends..name=foo".parse::<Override>(),
+            Err(Error::InvalidPath(String::from("backends..name")))
+        );
+        assert_eq!(
+            "0.name=foo".parse::<Override>(),
+            Err(Error::InvalidPath(String::from("0.name")))
+        );
+    }
+}
@@ -0,0 +1,55 @@
+//! Configuration related to webhooks.
+//!
+//! A webhook receives every event emitted by the engine as a JSON `POST`
+//! request, which allows external systems (e.g., a LIMS or a chat bridge) to
+//! react to the lifecycle of tasks.
+
+mod builder;
+
+pub use builder::Builder;
+use serde::Deserialize;
+use serde::Serialize;
+use url::Url;
+
+/// The default number of times a failed delivery is retried.
+pub const DEFAULT_RETRIES: u32 = 3;
+
+/// A configuration object for a webhook.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    /// The URL that events are sent to.
+    url: Url,
+
+    /// The secret used to sign the body of each request (if one is set).
+    ///
+    /// When set, each request includes an `X-Crankshaft-Signature` header
+    /// containing `sha256=` followed by the hex-encoded HMAC-SHA256 of the
+    /// body.
+    secret: Option<String>,
+
+    /// The number of times a failed delivery is retried.
+    retries: Option<u32>,
+}
+
+impl Config {
+    /// Gets a builder for [`Config`].
+    pub fn builder() -> Builder {
+        Builder::default()
+    }
+
+    /// Gets the URL that events are sent to.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Gets the secret used to sign the body of each request (if one is set).
+    pub fn secret(&self) -> Option<&str> {
+        self.secret.as_deref()
+    }
+
+    /// Gets the number of times a failed delivery is retried (if configured).
+    pub fn retries(&self) -> Option<u32> {
+        self.retries
+    }
+}
@@ -0,0 +1,88 @@
+//! Builders for the [webhook configuration](Config).
+
+use url::Url;
+
+use crate::webhook::Config;
+
+/// An error related to a [`Builder`].
+#[derive(Debug)]
+pub enum Error {
+    /// A required value was missing for a builder field.
+    Missing(&'static str),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Missing(field) => write!(
+                f,
+                "missing required value for '{field}' in the webhook configuration builder"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A [`Result`](std::result::Result) with an [`Error`].
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A builder for a [webhook configuration object](Config).
+#[derive(Default)]
+pub struct Builder {
+    /// The URL that events are sent to.
+    url: Option<Url>,
+
+    /// The secret used to sign the body of each request.
+    secret: Option<String>,
+
+    /// The number of times a failed delivery is retried.
+    retries: Option<u32>,
+}
+
+impl Builder {
+    /// Sets the URL for the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous URLs set within the builder.
+    pub fn url(mut self, url: impl Into<Url>) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    /// Sets the secret used to sign the body of each request for the
+    /// [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous secrets set within the
+    /// builder.
+    pub fn secret(mut self, secret: impl Into<String>) -> Self {
+        self.secret = Some(secret.into());
+        self
+    }
+
+    /// Sets the number of times a failed delivery is retried for the
+    /// [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous numbers of retries set within
+    /// the builder.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = Some(retries);
+        self
+    }
+
+    /// Consumes `self` and returns a built [`Config`].
+    pub fn try_build(self) -> Result<Config> {
+        let url = self.url.ok_or(Error::Missing("url"))?;
+
+        Ok(Config {
+            url,
+            secret: self.secret,
+            retries: self.retries,
+        })
+    }
+}
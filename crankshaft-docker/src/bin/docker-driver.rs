@@ -9,7 +9,9 @@ use clap::Subcommand;
 use clap_verbosity_flag::Verbosity;
 use crankshaft_docker::Container;
 use crankshaft_docker::Docker;
+use crankshaft_docker::containers::Filter;
 use eyre::Result;
+use futures::StreamExt as _;
 use tracing_log::AsTrace;
 use tracing_subscriber::EnvFilter;
 
@@ -60,6 +62,23 @@ enum Command {
         #[arg(short, long, default_value_t = false)]
         force: bool,
     },
+    /// Lists all containers created by Crankshaft.
+    ListContainers {
+        /// Whether or not to only list running containers.
+        #[arg(short, long, default_value_t = false)]
+        running: bool,
+    },
+
+    /// Stops all running containers created by Crankshaft.
+    StopAllContainers,
+
+    /// Removes all containers created by Crankshaft.
+    RemoveAllContainers {
+        /// Whether or not to force the removal of running containers.
+        #[arg(short, long, default_value_t = false)]
+        force: bool,
+    },
+
     /// Ensures an image is stored (either by pulling it or it already
     /// existing).
     EnsureImage {
@@ -74,6 +93,16 @@ enum Command {
     /// Lists all images.
     ListImages,
 
+    /// Pushes an image to its registry.
+    PushImage {
+        /// The name of the image.
+        image: String,
+
+        #[arg(short, long, default_value = "latest")]
+        /// The tag for the image.
+        tag: String,
+    },
+
     /// Removes an image.
     RemoveImage {
         /// The name of the image.
@@ -147,12 +176,42 @@ async fn run(args: &Args) -> Result<()> {
                 container.remove().await?;
             }
         }
+        Command::ListContainers { running } => {
+            let filter = Filter::default().running(*running);
+
+            for container in docker.list_crankshaft_containers(&filter).await? {
+                println!(
+                    "{} {}",
+                    container.id.unwrap_or_default(),
+                    container.state.unwrap_or_default()
+                );
+            }
+        }
+        Command::StopAllContainers => {
+            docker
+                .stop_crankshaft_containers(&Filter::default())
+                .await?;
+        }
+        Command::RemoveAllContainers { force } => {
+            docker
+                .remove_crankshaft_containers(&Filter::default(), *force)
+                .await?;
+        }
         Command::EnsureImage { image, tag } => {
             docker.ensure_image(image, tag).await?;
         }
         Command::ListImages => {
             docker.list_images().await?;
         }
+        Command::PushImage { image, tag } => {
+            let mut stream = docker.push_image(image, tag, None);
+
+            while let Some(update) = stream.next().await {
+                if let Some(status) = update?.status {
+                    println!("{status}");
+                }
+            }
+        }
         Command::RemoveImage { image, tag } => {
             docker.remove_image(image, tag).await?;
         }
@@ -1,29 +1,54 @@
 //! Containers.
 
 mod builder;
+pub mod details;
+mod logs;
+pub mod security;
+pub mod usage;
 
 use std::io::Cursor;
 #[cfg(unix)]
 use std::os::unix::process::ExitStatusExt as _;
 #[cfg(windows)]
 use std::os::windows::process::ExitStatusExt as _;
+use std::path::Path;
+use std::pin::Pin;
 use std::process::ExitStatus;
 use std::process::Output;
+use std::time::Duration;
 
 use bollard::Docker;
 use bollard::container::AttachContainerOptions;
+use bollard::container::DownloadFromContainerOptions;
+use bollard::container::InspectContainerOptions;
 use bollard::container::LogOutput;
+use bollard::container::LogsOptions;
 use bollard::container::RemoveContainerOptions;
+use bollard::container::ResizeContainerTtyOptions;
 use bollard::container::StartContainerOptions;
+use bollard::container::StatsOptions;
+use bollard::container::StopContainerOptions;
 use bollard::container::UploadToContainerOptions;
 use bollard::container::WaitContainerOptions;
+use bollard::secret::ContainerStateStatusEnum;
 pub use builder::Builder;
+pub use builder::Problem;
+pub use details::Details;
 use futures::TryStreamExt as _;
+use futures::stream::BoxStream;
+use logs::Collector;
+pub use security::Seccomp;
+use tokio::io::AsyncReadExt as _;
+use tokio::io::AsyncWrite;
 use tokio_stream::StreamExt as _;
+use tokio_util::bytes::Bytes;
+use tokio_util::io::ReaderStream;
 use tracing::Level;
 use tracing::debug;
 use tracing::enabled;
 use tracing::trace;
+use tracing::warn;
+pub use usage::Usage;
 
 use crate::Error;
 use crate::Result;
@@ -35,6 +60,76 @@ use crate::Result;
 /// allocations.
 const DEFAULT_TAR_CAPACITY: usize = 0xFFFF;
 
+/// The size of a block within a TAR.
+const TAR_BLOCK_SIZE: u64 = 512;
+
+/// The number of consecutive times the output of a container is resumed from
+/// its logs (after the output stream drops) before giving up.
+const MAX_OUTPUT_RESUMES: usize = 5;
+
+/// The delay before resuming the output of a container from its logs.
+const OUTPUT_RESUME_DELAY: Duration = Duration::from_secs(1);
+
+/// The time the wait for a container may go without a response before the
+/// container is inspected for whether it has already exited.
+const WAIT_WATCHDOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Streams a TAR containing a single local file that is placed at `path`.
+async fn archive(path: &str, local: &Path) -> Result<BoxStream<'static, Bytes>> {
+    let file = tokio::fs::File::open(local).await.map_err(Error::Io)?;
+    let size = file.metadata().await.map_err(Error::Io)?.len();
+
+    // NOTE: a USTAR header is used so that paths longer than 100 bytes are
+    // split across the prefix and the name fields of the header.
+    let mut header = tar::Header::new_ustar();
+    header
+        .set_path(path.trim_start_matches("/"))
+        .map_err(Error::Io)?;
+    header.set_size(size);
+    header.set_mode(0o644);
+    header.set_cksum();
+
+    // NOTE: the contents of an entry are padded to a multiple of the block
+    // size, and the archive ends with two empty blocks.
+    let trailer = (TAR_BLOCK_SIZE - size % TAR_BLOCK_SIZE) % TAR_BLOCK_SIZE + 2 * TAR_BLOCK_SIZE;
+
+    // NOTE: a file that cannot be read (or that shrinks) ends the stream
+    // early, which truncates the archive so that the upload is rejected by the
+    // Docker daemon.
+    let name = local.display().to_string();
+    let contents = ReaderStream::new(file.take(size)).map_while(move |chunk| {
+        chunk
+            .inspect_err(|err| warn!("failed to read `{name}`: {err}"))
+            .ok()
+    });
+
+    Ok(Box::pin(
+        tokio_stream::once(Bytes::copy_from_slice(header.as_bytes()))
+            .chain(contents)
+            .chain(tokio_stream::once(Bytes::from(vec![0; trailer as usize]))),
+    ))
+}
+
+/// An interactive session with a container.
+///
+/// See [`Container::attach_interactive()`].
+pub struct Session {
+    /// A writer to the standard input of the container.
+    pub stdin: Pin<Box<dyn AsyncWrite + Send>>,
+
+    /// The output of the container.
+    ///
+    /// When a TTY is allocated, the output is not multiplexed, so everything
+    /// is received as [`LogOutput::Console`].
+    pub output: BoxStream<'static, Result<LogOutput>>,
+}
+
+impl std::fmt::Debug for Session {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Session").finish_non_exhaustive()
+    }
+}
+
 /// A container.
 pub struct Container {
     /// A reference to the [`Docker`] client that will be used to create this
@@ -67,6 +162,11 @@ impl Container {
         }
     }
 
+    /// Gets the name (or id) of the container.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     /// Uploads an input file to the container.
     pub async fn upload_file(&self, path: &str, contents: Vec<u8>) -> Result<()> {
         let mut tar = tar::Builder::new(Vec::with_capacity(DEFAULT_TAR_CAPACITY));
@@ -95,9 +195,79 @@ impl Container {
             .map_err(Error::Docker)
     }
 
+    /// Uploads a local file to the container as an input file.
+    ///
+    /// Unlike [`Self::upload_file()`], the contents of the file are streamed
+    /// to the Docker daemon rather than read into memory.
+    pub async fn upload_path(&self, path: &str, local: &Path) -> Result<()> {
+        let archive = archive(path, local).await?;
+
+        self.client
+            .upload_to_container_streaming(
+                &self.name,
+                Some(UploadToContainerOptions {
+                    path: "/",
+                    ..Default::default()
+                }),
+                archive,
+            )
+            .await
+            .map_err(Error::Docker)
+    }
+
+    /// Downloads a file or directory from the container into a local
+    /// directory.
+    ///
+    /// The file or directory is placed within `dir` under its own name (e.g.,
+    /// downloading `/outputs/result.txt` creates `<dir>/result.txt`).
+    pub async fn download(&self, path: &str, dir: &Path) -> Result<()> {
+        let archive = self
+            .client
+            .download_from_container(&self.name, Some(DownloadFromContainerOptions { path }))
+            .try_fold(Vec::new(), |mut archive, bytes| async move {
+                archive.extend_from_slice(&bytes);
+                Ok(archive)
+            })
+            .await
+            .map_err(Error::Docker)?;
+
+        let dir = dir.to_owned();
+        tokio::task::spawn_blocking(move || tar::Archive::new(Cursor::new(archive)).unpack(dir))
+            .await
+            .map_err(|err| Error::Io(err.into()))?
+            .map_err(Error::Io)
+    }
+
     /// Runs a container and waits for the execution to end.
     pub async fn run(&self) -> Result<Output> {
         // (1) Attach to the logs stream.
+        let stream = self.attach_output(false).await?;
+
+        // (2) Start the container.
+        self.client
+            .start_container(&self.name, None::<StartContainerOptions<String>>)
+            .await
+            .map_err(Error::Docker)?;
+
+        self.finish(stream).await
+    }
+
+    /// Reattaches to an already started container and waits for the execution
+    /// to end.
+    ///
+    /// This is intended for recovering an execution after the connection to
+    /// the container was interrupted (e.g., by a restart of the Docker daemon).
+    /// All of the output of the container since it started is collected, even
+    /// if the container has already exited.
+    pub async fn reattach(&self) -> Result<Output> {
+        let stream = self.attach_output(true).await?;
+        self.finish(stream).await
+    }
+
+    /// Attaches to the output streams of the container.
+    ///
+    /// If `logs` is `true`, the output produced before attaching is included.
+    async fn attach_output(&self, logs: bool) -> Result<BoxStream<'static, Result<LogOutput>>> {
         let stream = self
             .client
             .attach_container(
@@ -106,6 +276,7 @@ impl Container {
                     stdout: Some(self.attached),
                     stderr: Some(self.attached),
                     stream: Some(true),
+                    logs: Some(logs),
                     ..Default::default()
                 }),
             )
@@ -113,62 +284,70 @@ impl Container {
             .map_err(Error::Docker)?
             .output;
 
-        // (2) Start the container.
-        self.client
-            .start_container(&self.name, None::<StartContainerOptions<String>>)
-            .await
-            .map_err(Error::Docker)?;
+        Ok(Box::pin(stream.map_err(Error::Docker)))
+    }
 
-        // (3) Collect standard out/standard err.
-        let (stdout, stderr) = stream
-            .try_fold(
-                (
-                    Vec::<u8>::with_capacity(0x0FFF),
-                    Vec::<u8>::with_capacity(0x0FFF),
-                ),
-                |(mut stdout, mut stderr), log| async move {
-                    match log {
-                        LogOutput::StdOut { message } => {
-                            stdout.extend(&message);
-                        }
-                        LogOutput::StdErr { message } => {
-                            stderr.extend(&message);
-                        }
-                        v => {
-                            trace!("unhandled log message: {v:?}")
-                        }
-                    }
+    /// Follows the (timestamped) logs of the container since a UNIX
+    /// timestamp (in seconds).
+    fn follow_logs(&self, since: i64) -> BoxStream<'static, Result<LogOutput>> {
+        let stream = self.client.logs(
+            &self.name,
+            Some(LogsOptions::<String> {
+                follow: true,
+                stdout: self.attached,
+                stderr: self.attached,
+                since,
+                timestamps: true,
+                tail: String::from("all"),
+                ..Default::default()
+            }),
+        );
 
-                    Ok((stdout, stderr))
-                },
-            )
-            .await
-            .map_err(Error::Docker)?;
+        Box::pin(stream.map_err(Error::Docker))
+    }
 
-        // (4) Wait for the container to be completed.
-        let mut wait_stream = self
-            .client
-            .wait_container(&self.name, None::<WaitContainerOptions<String>>);
+    /// Collects the output of an attached container and waits for the
+    /// execution to end.
+    ///
+    /// If the output stream drops before the container exits (e.g., because
+    /// of a hiccup of the Docker daemon), the output is resumed from the logs
+    /// of the container.
+    async fn finish(&self, mut stream: BoxStream<'static, Result<LogOutput>>) -> Result<Output> {
+        // (3) Collect standard out/standard err.
+        let mut collector = Collector::default();
+        let mut resumes = 0;
 
-        while let Some(result) = wait_stream.next().await {
-            let response = result.map_err(Error::Docker)?;
+        while let Some(result) = stream.next().await {
+            match result {
+                Ok(log) => {
+                    resumes = 0;
+                    collector.push(log);
+                }
+                Err(err) if resumes < MAX_OUTPUT_RESUMES => {
+                    resumes += 1;
+                    warn!(
+                        "the output stream of container `{}` dropped ({err}); resuming from its \
+                         logs",
+                        self.name
+                    );
 
-            if enabled!(Level::TRACE) {
-                trace!("{response:?}");
+                    tokio::time::sleep(OUTPUT_RESUME_DELAY).await;
+                    stream = self.follow_logs(collector.resume());
+                }
+                Err(err) => return Err(err),
             }
         }
 
-        // (5) Get the exit code.
-        let inspect = self
-            .client
-            .inspect_container(&self.name, None)
-            .await
-            .map_err(Error::Docker)?;
+        let (stdout, stderr) = collector.into_output();
+
+        // (4) Wait for the container to be completed.
+        self.wait().await?;
 
-        let status = inspect
-            .state
-            .expect("state should be present at this point")
-            .exit_code
+        // (5) Get the exit code.
+        let status = self
+            .inspect()
+            .await?
+            .exit_code()
             .expect("exit code should be present at this point") as i32;
 
         #[cfg(unix)]
@@ -188,8 +367,159 @@ impl Container {
         Ok(output)
     }
 
+    /// Waits for the container to exit.
+    ///
+    /// The Docker daemon occasionally never responds to a wait for a container
+    /// that has already exited. To avoid waiting forever, the container is
+    /// inspected whenever the wait goes without a response for a while, and
+    /// the wait ends once the container is observed to have exited.
+    async fn wait(&self) -> Result<()> {
+        let mut stream = self
+            .client
+            .wait_container(&self.name, None::<WaitContainerOptions<String>>);
+
+        loop {
+            let next = tokio::time::timeout(WAIT_WATCHDOG_INTERVAL, stream.next()).await;
+
+            match next {
+                Ok(Some(result)) => {
+                    let response = result.map_err(Error::Docker)?;
+
+                    if enabled!(Level::TRACE) {
+                        trace!("{response:?}");
+                    }
+                }
+                Ok(None) => return Ok(()),
+                Err(_) => {
+                    if self.inspect().await?.has_exited() {
+                        warn!(
+                            "the wait for container `{}` did not complete even though the \
+                             container exited; no longer waiting",
+                            self.name
+                        );
+
+                        return Ok(());
+                    }
+
+                    debug!("container `{}` is still running", self.name);
+                }
+            }
+        }
+    }
+
+    /// Attaches to the standard input, standard output, and standard error of
+    /// the container for interactive use (e.g., debugging a running task).
+    ///
+    /// If the container has been created but not yet started, it is started
+    /// after attaching so that no output is missed.
+    ///
+    /// The container must have been created as
+    /// [interactive](Builder::interactive) for the standard input to be
+    /// received, and typically with a [TTY](Builder::tty) allocated (see
+    /// [`Self::resize_tty()`]).
+    pub async fn attach_interactive(&self) -> Result<Session> {
+        let results = self
+            .client
+            .attach_container(
+                &self.name,
+                Some(AttachContainerOptions::<String> {
+                    stdin: Some(true),
+                    stdout: Some(true),
+                    stderr: Some(true),
+                    stream: Some(true),
+                    ..Default::default()
+                }),
+            )
+            .await
+            .map_err(Error::Docker)?;
+
+        if self.inspect().await?.status() == Some(ContainerStateStatusEnum::CREATED) {
+            self.client
+                .start_container(&self.name, None::<StartContainerOptions<String>>)
+                .await
+                .map_err(Error::Docker)?;
+        }
+
+        Ok(Session {
+            stdin: results.input,
+            output: Box::pin(results.output.map_err(Error::Docker)),
+        })
+    }
+
+    /// Resizes the TTY allocated for the container (in characters).
+    pub async fn resize_tty(&self, width: u16, height: u16) -> Result<()> {
+        self.client
+            .resize_container_tty(&self.name, ResizeContainerTtyOptions { width, height })
+            .await
+            .map_err(Error::Docker)
+    }
+
+    /// Inspects the container and returns a summary of its low-level details.
+    pub async fn inspect(&self) -> Result<Details> {
+        self.client
+            .inspect_container(&self.name, None)
+            .await
+            .map(Details::from)
+            .map_err(Error::Docker)
+    }
+
+    /// Samples the resources currently used by the container.
+    ///
+    /// Docker waits for a second sample of the CPU statistics of the container
+    /// so that its CPU usage can be computed, so this takes around a second to
+    /// complete.
+    pub async fn usage(&self) -> Result<Usage> {
+        let stats = self
+            .client
+            .stats(
+                &self.name,
+                Some(StatsOptions {
+                    stream: false,
+                    one_shot: false,
+                }),
+            )
+            .try_collect::<Vec<_>>()
+            .await
+            .map_err(Error::Docker)?
+            .pop();
+
+        // NOTE: the size of the writable layer is only computed when asked
+        // for, which is why the container is inspected separately.
+        let size = self
+            .client
+            .inspect_container(&self.name, Some(InspectContainerOptions { size: true }))
+            .await
+            .map_err(Error::Docker)?
+            .size_rw;
+
+        Ok(Usage::new(stats.as_ref(), size))
+    }
+
+    /// Stops a container.
+    ///
+    /// The container is sent `SIGTERM` (or the stop signal of its image) and
+    /// is killed with `SIGKILL` if it has not exited once `timeout` has
+    /// elapsed.
+    pub async fn stop(&self, timeout: Duration) -> Result<()> {
+        debug!("stopping container: `{}`", self.name);
+
+        self.client
+            .stop_container(
+                &self.name,
+                Some(StopContainerOptions {
+                    t: i64::try_from(timeout.as_secs()).unwrap_or(i64::MAX),
+                }),
+            )
+            .await
+            .map_err(Error::Docker)?;
+
+        Ok(())
+    }
+
     /// Removes a container with the level of force specified.
     ///
+    /// The anonymous volumes of the container are removed along with it.
+    ///
     /// This is an inner function, meaning it's not public. There are two public
     /// versions made available: [`Self::remove()`] and
     /// [`Self::force_remove()`].
@@ -199,6 +529,7 @@ impl Container {
                 &self.name,
                 Some(RemoveContainerOptions {
                     force,
+                    v: true,
                     ..Default::default()
                 }),
             )
@@ -226,3 +557,43 @@ impl Container {
         self.remove_inner(true).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read as _;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn files_are_archived_at_their_path() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, b"hello, world!").unwrap();
+
+        let path = format!("/workdir/{}input.txt", "nested/".repeat(16));
+        let archive = archive(&path, file.path())
+            .await
+            .unwrap()
+            .fold(Vec::new(), |mut archive, bytes| {
+                archive.extend_from_slice(&bytes);
+                archive
+            })
+            .await;
+
+        assert_eq!(archive.len() as u64 % TAR_BLOCK_SIZE, 0);
+
+        let mut archive = tar::Archive::new(Cursor::new(archive));
+        let mut entries = archive.entries().unwrap();
+
+        let mut entry = entries.next().unwrap().unwrap();
+        assert_eq!(
+            entry.path().unwrap(),
+            Path::new(path.trim_start_matches('/'))
+        );
+
+        let mut contents = String::new();
+        entry.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "hello, world!");
+
+        assert!(entries.next().is_none());
+    }
+}
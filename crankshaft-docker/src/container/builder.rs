@@ -1,5 +1,8 @@
 //! Builders for containers.
 
+use std::collections::HashMap;
+use std::collections::HashSet;
+
 use bollard::Docker;
 use bollard::container::Config;
 use bollard::container::CreateContainerOptions;
@@ -9,6 +12,80 @@ use tracing::warn;
 use crate::Container;
 use crate::Error;
 use crate::Result;
+use crate::container::Seccomp;
+use crate::container::security;
+use crate::containers::MANAGED_LABEL;
+use crate::version;
+use crate::version::Feature;
+
+/// A problem with the options provided to a [`Builder`].
+///
+/// Problems are found before any request is made to the Docker daemon (see
+/// [`Builder::validate()`]).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Problem {
+    /// No image was provided.
+    MissingImage,
+
+    /// No command was provided.
+    MissingCommand,
+
+    /// Whether or not the output streams are attached was not provided.
+    MissingAttached,
+
+    /// The command is empty or its program is empty.
+    EmptyProgram,
+
+    /// The name of an environment variable is empty or contains an `=` (or a
+    /// NUL character).
+    InvalidEnvName(String),
+
+    /// The working directory is not an absolute path.
+    RelativeWorkdir(String),
+
+    /// A mount has a target that is not an absolute path.
+    RelativeMountTarget(String),
+
+    /// More than one mount has the same target.
+    DuplicateMountTarget(String),
+
+    /// The working directory is on a read-only filesystem (i.e., a read-only
+    /// mount or a read-only root filesystem), so nothing can be written to
+    /// it.
+    ReadOnlyWorkdir(String),
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::MissingImage => write!(f, "no image was provided"),
+            Problem::MissingCommand => write!(f, "no command was provided"),
+            Problem::MissingAttached => {
+                write!(
+                    f,
+                    "whether or not the container is attached was not provided"
+                )
+            }
+            Problem::EmptyProgram => write!(f, "the program of the command is empty"),
+            Problem::InvalidEnvName(name) => {
+                write!(f, "`{name}` is not a valid environment variable name")
+            }
+            Problem::RelativeWorkdir(path) => {
+                write!(f, "the working directory `{path}` is not an absolute path")
+            }
+            Problem::RelativeMountTarget(path) => {
+                write!(f, "the mount target `{path}` is not an absolute path")
+            }
+            Problem::DuplicateMountTarget(path) => {
+                write!(f, "more than one mount targets `{path}`")
+            }
+            Problem::ReadOnlyWorkdir(path) => write!(
+                f,
+                "the working directory `{path}` is on a read-only filesystem"
+            ),
+        }
+    }
+}
 
 /// A builder for a [`Container`].
 pub struct Builder {
@@ -27,13 +104,31 @@ pub struct Builder {
     attached: Option<bool>,
 
     /// Environment variables.
-    env: Option<Vec<String>>,
+    env: Option<Vec<(String, String)>>,
 
     /// The working directory.
     workdir: Option<String>,
 
+    /// The user (and, optionally, the group) to run as.
+    user: Option<String>,
+
+    /// Whether or not a TTY is allocated.
+    tty: Option<bool>,
+
+    /// Whether or not the standard input is kept open (and attached).
+    interactive: Option<bool>,
+
     /// Host configuration.
     host_config: Option<HostConfig>,
+
+    /// Labels (in addition to the [`MANAGED_LABEL`]).
+    labels: HashMap<String, String>,
+
+    /// The seccomp profile.
+    seccomp: Option<Seccomp>,
+
+    /// The AppArmor profile.
+    apparmor: Option<String>,
 }
 
 impl Builder {
@@ -46,7 +141,13 @@ impl Builder {
             attached: Default::default(),
             env: Default::default(),
             workdir: Default::default(),
+            user: Default::default(),
+            tty: Default::default(),
+            interactive: Default::default(),
             host_config: Default::default(),
+            labels: Default::default(),
+            seccomp: Default::default(),
+            apparmor: Default::default(),
         }
     }
 
@@ -98,7 +199,7 @@ impl Builder {
         env.extend(
             variables
                 .into_iter()
-                .map(|(key, value)| format!("{}={}", key.into(), value.into())),
+                .map(|(key, value)| (key.into(), value.into())),
         );
         self.env = Some(env);
         self
@@ -115,6 +216,42 @@ impl Builder {
         self
     }
 
+    /// Sets the user (and, optionally, the group) that the container runs as
+    /// (e.g., `1000` or `1000:1000`).
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous user values provided to the
+    /// builder.
+    pub fn user(mut self, user: impl Into<String>) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    /// Sets whether or not a TTY is allocated for the container.
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous TTY values provided to the
+    /// builder.
+    pub fn tty(mut self, tty: bool) -> Self {
+        self.tty = Some(tty);
+        self
+    }
+
+    /// Sets whether or not the standard input of the container is kept open
+    /// so that it can be written to (see
+    /// [`Container::attach_interactive()`]).
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous interactive values provided
+    /// to the builder.
+    pub fn interactive(mut self, interactive: bool) -> Self {
+        self.interactive = Some(interactive);
+        self
+    }
+
     /// Sets the host configuration.
     ///
     /// # Notes
@@ -126,22 +263,177 @@ impl Builder {
         self
     }
 
+    /// Adds a set of labels.
+    ///
+    /// Every container is labeled with the [`MANAGED_LABEL`] regardless of the
+    /// labels added here.
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous values of the same labels
+    /// provided to the builder.
+    pub fn extend_labels(
+        mut self,
+        labels: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> Self {
+        self.labels.extend(
+            labels
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into())),
+        );
+        self
+    }
+
+    /// Sets the seccomp profile applied to the container.
+    ///
+    /// This is added to the security options of the host configuration (see
+    /// [`Builder::host_config()`]).
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous seccomp profiles provided to
+    /// the builder.
+    pub fn seccomp(mut self, seccomp: Seccomp) -> Self {
+        self.seccomp = Some(seccomp);
+        self
+    }
+
+    /// Sets the AppArmor profile (e.g., `unconfined` or the name of a loaded
+    /// profile) applied to the container.
+    ///
+    /// This is added to the security options of the host configuration (see
+    /// [`Builder::host_config()`]).
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous AppArmor profiles provided to
+    /// the builder.
+    pub fn apparmor(mut self, profile: impl Into<String>) -> Self {
+        self.apparmor = Some(profile.into());
+        self
+    }
+
+    /// Checks the options provided to the builder for problems that the
+    /// Docker daemon would reject (or that would prevent the container from
+    /// running as intended).
+    ///
+    /// Every problem is returned (rather than only the first), and an empty
+    /// list means that no problems were found.
+    pub fn validate(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+
+        if self.image.as_deref().is_none_or(str::is_empty) {
+            problems.push(Problem::MissingImage);
+        }
+
+        match &self.command {
+            None => problems.push(Problem::MissingCommand),
+            Some(command) if command.first().is_none_or(|program| program.is_empty()) => {
+                problems.push(Problem::EmptyProgram)
+            }
+            Some(_) => {}
+        }
+
+        if self.attached.is_none() {
+            problems.push(Problem::MissingAttached);
+        }
+
+        problems.extend(
+            self.env
+                .iter()
+                .flatten()
+                .filter(|(name, _)| name.is_empty() || name.contains(['=', '\0']))
+                .map(|(name, _)| Problem::InvalidEnvName(name.clone())),
+        );
+
+        let mounts = self
+            .host_config
+            .as_ref()
+            .map(mount_targets)
+            .unwrap_or_default();
+        let mut targets = HashSet::new();
+
+        for (target, _) in &mounts {
+            if !target.starts_with('/') {
+                problems.push(Problem::RelativeMountTarget(target.clone()));
+            } else if !targets.insert(target.trim_end_matches('/')) {
+                problems.push(Problem::DuplicateMountTarget(target.clone()));
+            }
+        }
+
+        if let Some(workdir) = &self.workdir {
+            if !workdir.starts_with('/') {
+                problems.push(Problem::RelativeWorkdir(workdir.clone()));
+            } else {
+                // NOTE: the working directory is on the most specific mount
+                // that contains it (or on the root filesystem if no mount
+                // does).
+                let read_only = mounts
+                    .iter()
+                    .filter(|(target, _)| contains(target, workdir))
+                    .max_by_key(|(target, _)| target.trim_end_matches('/').len())
+                    .map(|(_, read_only)| *read_only)
+                    .unwrap_or_else(|| {
+                        self.host_config
+                            .as_ref()
+                            .and_then(|config| config.readonly_rootfs)
+                            .unwrap_or_default()
+                    });
+
+                if read_only {
+                    problems.push(Problem::ReadOnlyWorkdir(workdir.clone()));
+                }
+            }
+        }
+
+        problems
+    }
+
     /// Consumes `self` and attempts to create a Docker container.
     ///
     /// Note that the creation of a container does not indicate that it has
-    /// started.
+    /// started. The options are [validated](Builder::validate()) first, and
+    /// any problems are returned within an [`Invalid`](Error::Invalid) error
+    /// before any request is made. Likewise, features that the Docker API
+    /// version of the client does not support (e.g., device requests) fail
+    /// with an [`Unsupported`](Error::Unsupported) error.
     pub async fn try_create(self, name: impl AsRef<str>) -> Result<Container> {
         let name = name.as_ref();
 
-        let image = self
-            .image
-            .expect("the `image` field must be set for a container builder");
-        let command = self
-            .command
-            .expect("the `command` field must be set for a container builder");
-        let attached = self
-            .attached
-            .expect("the `attached` field must be set for a container builder");
+        let problems = self.validate();
+
+        if !problems.is_empty() {
+            return Err(Error::Invalid(problems));
+        }
+
+        // SAFETY: validation fails when any of these fields are not set, so
+        // each of these will always unwrap.
+        let image = self.image.unwrap();
+        let command = self.command.unwrap();
+        let attached = self.attached.unwrap();
+
+        if self
+            .host_config
+            .as_ref()
+            .and_then(|config| config.device_requests.as_ref())
+            .is_some_and(|requests| !requests.is_empty())
+        {
+            version::require(&self.client, Feature::DeviceRequests)?;
+        }
+
+        let mut host_config = self.host_config;
+        let security = security::options(self.seccomp.as_ref(), self.apparmor.as_deref());
+
+        if !security.is_empty() {
+            host_config
+                .get_or_insert_with(Default::default)
+                .security_opt
+                .get_or_insert_with(Default::default)
+                .extend(security);
+        }
+
+        let mut labels = self.labels;
+        labels.insert(String::from(MANAGED_LABEL), String::from("true"));
 
         let response = self
             .client
@@ -160,8 +452,17 @@ impl Builder {
                     attach_stderr: Some(attached),
                     // END NOTE
                     working_dir: self.workdir,
-                    host_config: self.host_config,
-                    env: self.env,
+                    user: self.user,
+                    tty: self.tty,
+                    open_stdin: self.interactive,
+                    attach_stdin: self.interactive,
+                    host_config,
+                    env: self.env.map(|env| {
+                        env.into_iter()
+                            .map(|(key, value)| format!("{key}={value}"))
+                            .collect()
+                    }),
+                    labels: Some(labels),
                     ..Default::default()
                 },
             )
@@ -179,3 +480,131 @@ impl Builder {
         })
     }
 }
+
+/// Gets the targets of the bind mounts and mounts within a host configuration
+/// along with whether or not each is read-only.
+fn mount_targets(config: &HostConfig) -> Vec<(String, bool)> {
+    // NOTE: binds are formatted as `source:target[:options]`, where the
+    // options are comma-delimited.
+    let binds = config.binds.iter().flatten().filter_map(|bind| {
+        let mut parts = bind.splitn(3, ':');
+        let target = parts.nth(1)?;
+        let read_only = parts
+            .next()
+            .is_some_and(|options| options.split(',').any(|option| option == "ro"));
+        Some((target.to_owned(), read_only))
+    });
+
+    let mounts = config
+        .mounts
+        .iter()
+        .flatten()
+        .filter_map(|mount| Some((mount.target.clone()?, mount.read_only.unwrap_or_default())));
+
+    binds.chain(mounts).collect()
+}
+
+/// Gets whether or not a path is within a directory (or is the directory).
+fn contains(directory: &str, path: &str) -> bool {
+    let directory = directory.trim_end_matches('/');
+
+    match path.strip_prefix(directory) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bollard::models::Mount;
+
+    use super::*;
+
+    /// Creates a builder with a client that never connects.
+    fn builder() -> Builder {
+        Builder::new(
+            Docker::connect_with_http("http://localhost:2375", 120, bollard::API_DEFAULT_VERSION)
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        let builder = builder()
+            .command(vec![String::new()])
+            .extend_env([("", "empty"), ("A=B", "equals"), ("OK", "fine")].into_iter())
+            .workdir(String::from("work"))
+            .host_config(HostConfig {
+                binds: Some(vec![String::from("/data:/data:ro")]),
+                mounts: Some(vec![
+                    Mount {
+                        target: Some(String::from("/data/")),
+                        ..Default::default()
+                    },
+                    Mount {
+                        target: Some(String::from("scratch")),
+                        ..Default::default()
+                    },
+                ]),
+                ..Default::default()
+            });
+
+        assert_eq!(
+            builder.validate(),
+            [
+                Problem::MissingImage,
+                Problem::EmptyProgram,
+                Problem::MissingAttached,
+                Problem::InvalidEnvName(String::new()),
+                Problem::InvalidEnvName(String::from("A=B")),
+                Problem::DuplicateMountTarget(String::from("/data/")),
+                Problem::RelativeMountTarget(String::from("scratch")),
+                Problem::RelativeWorkdir(String::from("work")),
+            ]
+        );
+    }
+
+    #[test]
+    fn working_directories_must_be_writable() {
+        let builder = || {
+            builder()
+                .image("ubuntu")
+                .command(vec![String::from("echo")])
+                .attached(true)
+        };
+
+        assert!(
+            builder()
+                .workdir(String::from("/work"))
+                .validate()
+                .is_empty()
+        );
+
+        let host_config = HostConfig {
+            binds: Some(vec![
+                String::from("/ref:/ref:ro,z"),
+                String::from("/out:/ref/out:rw"),
+            ]),
+            readonly_rootfs: Some(true),
+            ..Default::default()
+        };
+
+        let validate = |workdir: &str| {
+            builder()
+                .workdir(workdir.to_owned())
+                .host_config(host_config.clone())
+                .validate()
+        };
+
+        assert_eq!(
+            validate("/work"),
+            [Problem::ReadOnlyWorkdir(String::from("/work"))]
+        );
+        assert_eq!(
+            validate("/ref/index"),
+            [Problem::ReadOnlyWorkdir(String::from("/ref/index"))]
+        );
+        assert!(validate("/ref/out").is_empty());
+        assert!(validate("/ref/out/sample").is_empty());
+    }
+}
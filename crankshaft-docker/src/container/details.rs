@@ -0,0 +1,246 @@
+//! Low-level details of a container.
+
+use bollard::secret::ContainerInspectResponse;
+use bollard::secret::ContainerStateStatusEnum;
+
+/// The timestamp reported by Docker for events that have not occurred.
+const UNSET_TIMESTAMP: &str = "0001-01-01T00:00:00Z";
+
+/// A network a container is attached to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Network {
+    /// The name of the network.
+    name: String,
+
+    /// The IP address of the container within the network (if one is
+    /// assigned).
+    ip_address: Option<String>,
+}
+
+impl Network {
+    /// Gets the name of the network.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the IP address of the container within the network (if one is
+    /// assigned).
+    pub fn ip_address(&self) -> Option<&str> {
+        self.ip_address.as_deref()
+    }
+}
+
+/// A mount within a container.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mount {
+    /// The source of the mount on the host (if it has one).
+    source: Option<String>,
+
+    /// The destination of the mount within the container.
+    destination: String,
+
+    /// Whether or not the mount is writable.
+    writable: bool,
+}
+
+impl Mount {
+    /// Gets the source of the mount on the host (if it has one).
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Gets the destination of the mount within the container.
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    /// Gets whether or not the mount is writable.
+    pub fn writable(&self) -> bool {
+        self.writable
+    }
+}
+
+/// A typed summary of the low-level details of a container.
+///
+/// This is returned by [`Container::inspect()`](super::Container::inspect).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Details {
+    /// The status of the container (if it was reported).
+    status: Option<ContainerStateStatusEnum>,
+
+    /// The exit code of the container (if it has exited).
+    exit_code: Option<i64>,
+
+    /// Whether or not the container was killed because it ran out of memory.
+    oom_killed: bool,
+
+    /// The error reported by Docker for the container (if there is one).
+    error: Option<String>,
+
+    /// When the container was started (if it was started).
+    started_at: Option<String>,
+
+    /// When the container finished (if it has finished).
+    finished_at: Option<String>,
+
+    /// The number of times the container was restarted.
+    restart_count: i64,
+
+    /// The networks the container is attached to.
+    networks: Vec<Network>,
+
+    /// The mounts within the container.
+    mounts: Vec<Mount>,
+}
+
+impl Details {
+    /// Gets the status of the container (if it was reported).
+    pub fn status(&self) -> Option<ContainerStateStatusEnum> {
+        self.status
+    }
+
+    /// Gets the exit code of the container (if it has exited).
+    pub fn exit_code(&self) -> Option<i64> {
+        self.exit_code
+    }
+
+    /// Gets whether or not the container has exited (i.e., it is no longer
+    /// running and will not be restarted).
+    pub fn has_exited(&self) -> bool {
+        matches!(
+            self.status,
+            Some(ContainerStateStatusEnum::EXITED) | Some(ContainerStateStatusEnum::DEAD)
+        )
+    }
+
+    /// Gets whether or not the container was killed because it ran out of
+    /// memory.
+    pub fn oom_killed(&self) -> bool {
+        self.oom_killed
+    }
+
+    /// Gets the error reported by Docker for the container (if there is one).
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Gets when the container was started as an RFC 3339 timestamp (if it was
+    /// started).
+    pub fn started_at(&self) -> Option<&str> {
+        self.started_at.as_deref()
+    }
+
+    /// Gets when the container finished as an RFC 3339 timestamp (if it has
+    /// finished).
+    pub fn finished_at(&self) -> Option<&str> {
+        self.finished_at.as_deref()
+    }
+
+    /// Gets the number of times the container was restarted.
+    pub fn restart_count(&self) -> i64 {
+        self.restart_count
+    }
+
+    /// Gets the networks the container is attached to.
+    pub fn networks(&self) -> &[Network] {
+        &self.networks
+    }
+
+    /// Gets the mounts within the container.
+    pub fn mounts(&self) -> &[Mount] {
+        &self.mounts
+    }
+}
+
+impl From<ContainerInspectResponse> for Details {
+    fn from(response: ContainerInspectResponse) -> Self {
+        let state = response.state.unwrap_or_default();
+
+        /// Filters out timestamps for events that have not occurred.
+        fn timestamp(value: Option<String>) -> Option<String> {
+            value.filter(|value| !value.is_empty() && value != UNSET_TIMESTAMP)
+        }
+
+        let mut networks = response
+            .network_settings
+            .and_then(|settings| settings.networks)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, endpoint)| Network {
+                name,
+                ip_address: endpoint.ip_address.filter(|address| !address.is_empty()),
+            })
+            .collect::<Vec<_>>();
+
+        // NOTE: the networks are reported as a map, so they are sorted to keep
+        // the order stable.
+        networks.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mounts = response
+            .mounts
+            .unwrap_or_default()
+            .into_iter()
+            .map(|mount| Mount {
+                source: mount.source.filter(|source| !source.is_empty()),
+                destination: mount.destination.unwrap_or_default(),
+                writable: mount.rw.unwrap_or_default(),
+            })
+            .collect();
+
+        Self {
+            status: state.status,
+            exit_code: state.exit_code,
+            oom_killed: state.oom_killed.unwrap_or_default(),
+            error: state.error.filter(|error| !error.is_empty()),
+            started_at: timestamp(state.started_at),
+            finished_at: timestamp(state.finished_at),
+            restart_count: response.restart_count.unwrap_or_default(),
+            networks,
+            mounts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bollard::secret::ContainerState;
+    use bollard::secret::MountPoint;
+
+    use super::*;
+
+    #[test]
+    fn details_are_summarized() {
+        let details = Details::from(ContainerInspectResponse {
+            state: Some(ContainerState {
+                status: Some(ContainerStateStatusEnum::EXITED),
+                exit_code: Some(137),
+                oom_killed: Some(true),
+                error: Some(String::new()),
+                started_at: Some(String::from("2024-01-01T00:00:00Z")),
+                finished_at: Some(String::from(UNSET_TIMESTAMP)),
+                ..Default::default()
+            }),
+            restart_count: Some(2),
+            mounts: Some(vec![MountPoint {
+                source: Some(String::from("/tmp/foo")),
+                destination: Some(String::from("/foo")),
+                rw: Some(true),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        });
+
+        assert_eq!(details.status(), Some(ContainerStateStatusEnum::EXITED));
+        assert!(details.has_exited());
+        assert_eq!(details.exit_code(), Some(137));
+        assert!(details.oom_killed());
+        assert_eq!(details.error(), None);
+        assert_eq!(details.started_at(), Some("2024-01-01T00:00:00Z"));
+        assert_eq!(details.finished_at(), None);
+        assert_eq!(details.restart_count(), 2);
+        assert!(details.networks().is_empty());
+        assert_eq!(details.mounts()[0].source(), Some("/tmp/foo"));
+        assert_eq!(details.mounts()[0].destination(), "/foo");
+        assert!(details.mounts()[0].writable());
+    }
+}
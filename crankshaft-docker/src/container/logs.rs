@@ -0,0 +1,226 @@
+//! Collection of the output of a container.
+//!
+//! The output of a running container is followed over a single connection to
+//! the Docker daemon. If that connection drops (e.g., because of a hiccup of
+//! the daemon), the output is resumed from the logs of the container, which
+//! are requested with timestamps so that output that was already received is
+//! not collected twice.
+
+use bollard::container::LogOutput;
+use chrono::DateTime;
+use chrono::Utc;
+use tracing::trace;
+
+/// Parses the RFC 3339 timestamp that prefixes a message of the (timestamped)
+/// logs of a container (e.g., `2024-01-02T03:04:05.123456789Z`).
+fn parse_timestamp(s: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s)
+        .ok()
+        .map(|timestamp| timestamp.to_utc())
+}
+
+/// Collects the standard output and standard error of a container.
+#[derive(Debug, Default)]
+pub(crate) struct Collector {
+    /// The standard output collected so far.
+    stdout: Vec<u8>,
+
+    /// The standard error collected so far.
+    stderr: Vec<u8>,
+
+    /// Whether or not the output is being received from the (timestamped)
+    /// logs of the container.
+    resumed: bool,
+
+    /// The timestamp of the last message received from the logs of the
+    /// container.
+    last: Option<DateTime<Utc>>,
+
+    /// The messages received with the timestamp of the last message (along
+    /// with whether each was written to standard output).
+    boundary: Vec<(bool, Vec<u8>)>,
+
+    /// The messages at the boundary that have not yet been received again
+    /// since resuming.
+    ///
+    /// As many messages may share a timestamp, replayed messages at the
+    /// boundary are only skipped if they were already received.
+    replayed: Vec<(bool, Vec<u8>)>,
+
+    /// The number of bytes of the standard output and standard error that
+    /// remain to be skipped, as they were received before resuming.
+    skip: (usize, usize),
+}
+
+impl Collector {
+    /// Collects a message from the output of the container.
+    pub(crate) fn push(&mut self, log: LogOutput) {
+        let (message, stdout) = match log {
+            LogOutput::StdOut { message } => (message, true),
+            LogOutput::StdErr { message } => (message, false),
+            v => {
+                trace!("unhandled log message: {v:?}");
+                return;
+            }
+        };
+
+        let mut message = &message[..];
+
+        if self.resumed {
+            // NOTE: each message of timestamped logs is prefixed with its
+            // timestamp and a space.
+            if let Some(index) = message.iter().position(|b| *b == b' ') {
+                let timestamp = std::str::from_utf8(&message[..index])
+                    .ok()
+                    .and_then(parse_timestamp);
+
+                if let Some(timestamp) = timestamp {
+                    message = &message[index + 1..];
+
+                    match self.last {
+                        Some(last) if timestamp < last => return,
+                        Some(last) if timestamp == last => {
+                            if let Some(index) = self
+                                .replayed
+                                .iter()
+                                .position(|(out, received)| *out == stdout && received == message)
+                            {
+                                self.replayed.swap_remove(index);
+                                return;
+                            }
+                        }
+                        _ => {
+                            self.last = Some(timestamp);
+                            self.boundary.clear();
+                            self.replayed.clear();
+                        }
+                    }
+
+                    self.boundary.push((stdout, message.to_vec()));
+                }
+            }
+
+            let skip = if stdout {
+                &mut self.skip.0
+            } else {
+                &mut self.skip.1
+            };
+
+            let skipped = (*skip).min(message.len());
+            *skip -= skipped;
+            message = &message[skipped..];
+        }
+
+        if stdout {
+            self.stdout.extend(message);
+        } else {
+            self.stderr.extend(message);
+        }
+    }
+
+    /// Prepares to resume collecting the output of the container from its
+    /// logs.
+    ///
+    /// Returns the UNIX timestamp (in seconds) that the logs should be
+    /// requested since (where zero requests all of the logs).
+    pub(crate) fn resume(&mut self) -> i64 {
+        self.resumed = true;
+
+        match self.last {
+            Some(last) => {
+                self.skip = (0, 0);
+                self.replayed = self.boundary.clone();
+                last.timestamp()
+            }
+            // NOTE: without a timestamp, all of the logs are requested and
+            // the output that was already received is skipped.
+            None => {
+                self.skip = (self.stdout.len(), self.stderr.len());
+                0
+            }
+        }
+    }
+
+    /// Consumes `self` and returns the standard output and standard error.
+    pub(crate) fn into_output(self) -> (Vec<u8>, Vec<u8>) {
+        (self.stdout, self.stderr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gets a standard output message.
+    fn stdout(message: &str) -> LogOutput {
+        LogOutput::StdOut {
+            message: message.to_owned().into(),
+        }
+    }
+
+    /// Gets a standard error message.
+    fn stderr(message: &str) -> LogOutput {
+        LogOutput::StdErr {
+            message: message.to_owned().into(),
+        }
+    }
+
+    #[test]
+    fn timestamps_are_parsed() {
+        let timestamp = parse_timestamp("2024-03-01T12:34:56.5Z").unwrap();
+        assert_eq!(timestamp.timestamp(), 1_709_296_496);
+        assert_eq!(timestamp.timestamp_subsec_nanos(), 500_000_000);
+
+        assert!(
+            parse_timestamp("2024-03-01T12:34:56.000000001Z")
+                > parse_timestamp("2024-03-01T12:34:56Z")
+        );
+        assert_eq!(parse_timestamp("hello"), None);
+    }
+
+    #[test]
+    fn output_is_resumed_without_duplicates() {
+        let mut collector = Collector::default();
+        collector.push(stdout("one\ntw"));
+        collector.push(stderr("oops\n"));
+
+        // The stream drops before any timestamp was seen, so everything is
+        // replayed and the output that was already received is skipped.
+        assert_eq!(collector.resume(), 0);
+        collector.push(stdout("2024-03-01T12:34:55.1Z one\n"));
+        collector.push(stderr("2024-03-01T12:34:55.2Z oops\n"));
+        collector.push(stdout("2024-03-01T12:34:56.3Z two\n"));
+        collector.push(stdout("2024-03-01T12:34:56.4Z three\n"));
+
+        // The stream drops again, so the logs are replayed since the last
+        // timestamp that was seen.
+        assert_eq!(collector.resume(), 1_709_296_496);
+        collector.push(stdout("2024-03-01T12:34:56.3Z two\n"));
+        collector.push(stdout("2024-03-01T12:34:56.4Z three\n"));
+        collector.push(stderr("2024-03-01T12:34:57Z done\n"));
+
+        let (stdout, stderr) = collector.into_output();
+        assert_eq!(String::from_utf8(stdout).unwrap(), "one\ntwo\nthree\n");
+        assert_eq!(String::from_utf8(stderr).unwrap(), "oops\ndone\n");
+    }
+
+    #[test]
+    fn messages_sharing_a_timestamp_are_kept() {
+        let mut collector = Collector::default();
+        assert_eq!(collector.resume(), 0);
+        collector.push(stdout("2024-03-01T12:34:56Z one\n"));
+        collector.push(stdout("2024-03-01T12:34:56Z two\n"));
+
+        // NOTE: only the messages at the boundary that were already received
+        // are skipped when the logs are replayed.
+        assert_eq!(collector.resume(), 1_709_296_496);
+        collector.push(stdout("2024-03-01T12:34:55Z zero\n"));
+        collector.push(stdout("2024-03-01T12:34:56Z one\n"));
+        collector.push(stdout("2024-03-01T12:34:56Z two\n"));
+        collector.push(stdout("2024-03-01T12:34:56Z three\n"));
+        collector.push(stdout("2024-03-01T12:34:56Z one\n"));
+
+        let (stdout, _) = collector.into_output();
+        assert_eq!(String::from_utf8(stdout).unwrap(), "one\ntwo\nthree\none\n");
+    }
+}
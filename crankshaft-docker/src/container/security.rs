@@ -0,0 +1,57 @@
+//! Security options of containers.
+
+/// The seccomp profile applied to a container.
+///
+/// See the [Docker documentation] for more details.
+///
+/// [Docker documentation]: https://docs.docker.com/engine/security/seccomp/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Seccomp {
+    /// No seccomp profile is applied (i.e., every system call is allowed).
+    Unconfined,
+
+    /// A custom seccomp profile (the JSON contents of the profile rather than
+    /// the path to it, as the profile is read by the client).
+    Profile(String),
+}
+
+/// Gets the security options (in the form of the `SecurityOpt` field of the
+/// Docker API) for a seccomp profile and an AppArmor profile.
+pub(crate) fn options(seccomp: Option<&Seccomp>, apparmor: Option<&str>) -> Vec<String> {
+    let mut options = Vec::new();
+
+    match seccomp {
+        Some(Seccomp::Unconfined) => options.push(String::from("seccomp=unconfined")),
+        Some(Seccomp::Profile(profile)) => options.push(format!("seccomp={profile}")),
+        None => {}
+    }
+
+    if let Some(profile) = apparmor {
+        options.push(format!("apparmor={profile}"));
+    }
+
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_are_formatted() {
+        assert!(options(None, None).is_empty());
+        assert_eq!(
+            options(Some(&Seccomp::Unconfined), Some("unconfined")),
+            ["seccomp=unconfined", "apparmor=unconfined"]
+        );
+        assert_eq!(
+            options(
+                Some(&Seccomp::Profile(String::from(
+                    r#"{"defaultAction":"SCMP_ACT_ALLOW"}"#
+                ))),
+                None
+            ),
+            [r#"seccomp={"defaultAction":"SCMP_ACT_ALLOW"}"#]
+        );
+    }
+}
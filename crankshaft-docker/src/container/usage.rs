@@ -0,0 +1,124 @@
+//! Resource usage of a container.
+
+use bollard::container::CPUStats;
+use bollard::container::Stats;
+
+/// A sample of the resources used by a running container.
+///
+/// This is returned by [`Container::usage()`](super::Container::usage).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Usage {
+    /// The CPU usage as a percentage of a single CPU (if it could be
+    /// computed).
+    cpu_percent: Option<f64>,
+
+    /// The memory used in bytes (if it was reported).
+    memory: Option<u64>,
+
+    /// The size of the writable layer of the container in bytes (if it was
+    /// reported).
+    disk: Option<u64>,
+}
+
+impl Usage {
+    /// Creates a new [`Usage`] from the statistics reported by Docker (if any
+    /// were) and the size of the writable layer of the container.
+    pub(crate) fn new(stats: Option<&Stats>, disk: Option<i64>) -> Self {
+        Self {
+            cpu_percent: stats.and_then(|stats| cpu_percent(&stats.cpu_stats, &stats.precpu_stats)),
+            memory: stats.and_then(|stats| stats.memory_stats.usage),
+            disk: disk.and_then(|disk| u64::try_from(disk).ok()),
+        }
+    }
+
+    /// Gets the CPU usage as a percentage of a single CPU (if it could be
+    /// computed).
+    ///
+    /// A container fully using two CPUs has a usage of `200.0`.
+    pub fn cpu_percent(&self) -> Option<f64> {
+        self.cpu_percent
+    }
+
+    /// Gets the memory used in bytes (if it was reported).
+    pub fn memory(&self) -> Option<u64> {
+        self.memory
+    }
+
+    /// Gets the size of the writable layer of the container in bytes (if it
+    /// was reported).
+    pub fn disk(&self) -> Option<u64> {
+        self.disk
+    }
+}
+
+/// Computes the CPU usage of a container from two samples of its CPU
+/// statistics.
+///
+/// This is the same computation that `docker stats` performs.
+fn cpu_percent(current: &CPUStats, previous: &CPUStats) -> Option<f64> {
+    let container = current
+        .cpu_usage
+        .total_usage
+        .checked_sub(previous.cpu_usage.total_usage)?;
+    let system = current
+        .system_cpu_usage?
+        .checked_sub(previous.system_cpu_usage?)?;
+
+    if system == 0 {
+        return None;
+    }
+
+    let cpus = current.online_cpus.filter(|cpus| *cpus > 0).or_else(|| {
+        current
+            .cpu_usage
+            .percpu_usage
+            .as_ref()
+            .map(|usage| usage.len() as u64)
+    })?;
+
+    Some(container as f64 / system as f64 * cpus as f64 * 100.)
+}
+
+#[cfg(test)]
+mod tests {
+    use bollard::container::CPUUsage;
+    use bollard::container::ThrottlingData;
+
+    use super::*;
+
+    /// Builds a sample of CPU statistics.
+    fn cpu(total: u64, system: Option<u64>) -> CPUStats {
+        CPUStats {
+            cpu_usage: CPUUsage {
+                percpu_usage: None,
+                usage_in_usermode: 0,
+                total_usage: total,
+                usage_in_kernelmode: 0,
+            },
+            system_cpu_usage: system,
+            online_cpus: Some(4),
+            throttling_data: ThrottlingData {
+                periods: 0,
+                throttled_periods: 0,
+                throttled_time: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn cpu_usage_is_computed_from_samples() {
+        // Half of the system time across four CPUs is two CPUs.
+        assert_eq!(
+            cpu_percent(&cpu(1_500, Some(11_000)), &cpu(1_000, Some(10_000))),
+            Some(200.)
+        );
+
+        // NOTE: Docker reports no system usage for the previous sample of the
+        // first statistics of a container.
+        assert_eq!(cpu_percent(&cpu(1_500, Some(11_000)), &cpu(0, None)), None);
+        assert_eq!(
+            cpu_percent(&cpu(1_500, Some(10_000)), &cpu(1_000, Some(10_000))),
+            None
+        );
+    }
+}
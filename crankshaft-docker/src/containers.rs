@@ -0,0 +1,231 @@
+//! Bulk operations on the containers created by Crankshaft.
+//!
+//! Every container created through a [`Builder`](crate::container::Builder)
+//! is labeled with [`MANAGED_LABEL`], which allows the containers created by
+//! Crankshaft to be told apart from any other containers on the host (e.g., to
+//! inspect what is currently running or to clean up after a crash).
+
+use std::collections::HashMap;
+
+use bollard::container::ListContainersOptions;
+use bollard::container::RemoveContainerOptions;
+use bollard::secret::ContainerSummary;
+use futures::stream::FuturesUnordered;
+use tokio_stream::StreamExt as _;
+use tracing::Level;
+use tracing::debug;
+use tracing::enabled;
+use tracing::trace;
+
+use crate::Docker;
+use crate::Error;
+use crate::Result;
+
+/// The label applied to every container created by Crankshaft.
+pub const MANAGED_LABEL: &str = "crankshaft.managed";
+
+/// The label containing the name of the task a container was created for.
+pub const TASK_LABEL: &str = "crankshaft.task";
+
+/// A filter over the containers created by Crankshaft.
+///
+/// The default filter matches every container created by Crankshaft
+/// (regardless of its state).
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    /// The labels (and their values) that a container must have.
+    labels: Vec<(String, String)>,
+
+    /// Whether or not only running containers are matched.
+    running: bool,
+}
+
+impl Filter {
+    /// Only matches containers with a label set to a particular value.
+    ///
+    /// Multiple labels may be added, in which case a container must have all
+    /// of them to be matched.
+    pub fn label(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.labels.push((key.into(), value.into()));
+        self
+    }
+
+    /// Only matches containers created for a particular task.
+    pub fn task(self, name: impl Into<String>) -> Self {
+        self.label(TASK_LABEL, name)
+    }
+
+    /// Sets whether or not only running containers are matched.
+    pub fn running(mut self, running: bool) -> Self {
+        self.running = running;
+        self
+    }
+
+    /// Gets the filters to pass to the Docker daemon.
+    fn filters(&self) -> HashMap<String, Vec<String>> {
+        let mut filters = HashMap::new();
+
+        filters.insert(
+            String::from("label"),
+            std::iter::once(format!("{MANAGED_LABEL}=true"))
+                .chain(
+                    self.labels
+                        .iter()
+                        .map(|(key, value)| format!("{key}={value}")),
+                )
+                .collect(),
+        );
+
+        if self.running {
+            filters.insert(String::from("status"), vec![String::from("running")]);
+        }
+
+        filters
+    }
+}
+
+/// Gets all of the containers created by Crankshaft that match a filter.
+pub(crate) async fn list_crankshaft_containers(
+    docker: &Docker,
+    filter: &Filter,
+) -> Result<Vec<ContainerSummary>> {
+    debug!("listing crankshaft containers");
+
+    let containers = docker
+        .inner()
+        .list_containers(Some(ListContainersOptions {
+            // NOTE: stopped containers are only included when they are not
+            // filtered out by the `status` filter.
+            all: true,
+            filters: filter.filters(),
+            ..Default::default()
+        }))
+        .await
+        .map_err(Error::Docker)?;
+
+    debug!("found {} containers", containers.len());
+
+    if enabled!(Level::TRACE) {
+        for container in &containers {
+            trace!(
+                "  container: {} (state: {})",
+                container.id.as_deref().unwrap_or("<unknown>"),
+                container.state.as_deref().unwrap_or("<unknown>")
+            );
+        }
+    }
+
+    Ok(containers)
+}
+
+/// Gets the ids of all of the containers created by Crankshaft that match a
+/// filter.
+async fn ids(docker: &Docker, filter: &Filter) -> Result<Vec<String>> {
+    Ok(list_crankshaft_containers(docker, filter)
+        .await?
+        .into_iter()
+        .filter_map(|container| container.id)
+        .collect())
+}
+
+/// Stops all of the containers created by Crankshaft that match a filter.
+///
+/// Returns the ids of the stopped containers.
+pub(crate) async fn stop_crankshaft_containers(
+    docker: &Docker,
+    filter: &Filter,
+) -> Result<Vec<String>> {
+    debug!("stopping crankshaft containers");
+
+    // NOTE: only running containers can be stopped.
+    let ids = ids(docker, &filter.clone().running(true)).await?;
+
+    {
+        let mut futures = ids
+            .iter()
+            .map(|id| docker.inner().stop_container(id, None))
+            .collect::<FuturesUnordered<_>>();
+
+        // NOTE: each result is bound before it is checked so that it is not a
+        // temporary of the loop condition (whose drop order changes in Rust
+        // 2024).
+        loop {
+            let result = futures.next().await;
+
+            match result {
+                Some(result) => result.map_err(Error::Docker)?,
+                None => break,
+            }
+        }
+    }
+
+    debug!("stopped {} containers", ids.len());
+    Ok(ids)
+}
+
+/// Removes all of the containers created by Crankshaft that match a filter.
+///
+/// Running containers are only removed if `force` is `true`. Returns the ids
+/// of the removed containers.
+pub(crate) async fn remove_crankshaft_containers(
+    docker: &Docker,
+    filter: &Filter,
+    force: bool,
+) -> Result<Vec<String>> {
+    debug!("removing crankshaft containers");
+
+    let ids = ids(docker, filter).await?;
+
+    {
+        let mut futures = ids
+            .iter()
+            .map(|id| {
+                docker.inner().remove_container(
+                    id,
+                    Some(RemoveContainerOptions {
+                        force,
+                        ..Default::default()
+                    }),
+                )
+            })
+            .collect::<FuturesUnordered<_>>();
+
+        // NOTE: each result is bound before it is checked so that it is not a
+        // temporary of the loop condition (whose drop order changes in Rust
+        // 2024).
+        loop {
+            let result = futures.next().await;
+
+            match result {
+                Some(result) => result.map_err(Error::Docker)?,
+                None => break,
+            }
+        }
+    }
+
+    debug!("removed {} containers", ids.len());
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_always_match_managed_containers() {
+        let filters = Filter::default().filters();
+        assert_eq!(filters.len(), 1);
+        assert_eq!(filters["label"], ["crankshaft.managed=true"]);
+
+        let filters = Filter::default()
+            .task("foo")
+            .label("team", "bar")
+            .running(true)
+            .filters();
+        assert_eq!(
+            filters["label"],
+            ["crankshaft.managed=true", "crankshaft.task=foo", "team=bar"]
+        );
+        assert_eq!(filters["status"], ["running"]);
+    }
+}
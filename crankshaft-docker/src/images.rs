@@ -2,10 +2,14 @@
 
 use std::collections::HashMap;
 
+use bollard::auth::DockerCredentials;
 use bollard::image::CreateImageOptions;
 use bollard::image::ListImagesOptions;
+use bollard::image::PushImageOptions;
 use bollard::secret::ImageDeleteResponseItem;
 use bollard::secret::ImageSummary;
+use bollard::secret::PushImageInfo;
+use futures::stream::BoxStream;
 use futures::stream::FuturesUnordered;
 use tokio_stream::StreamExt as _;
 use tracing::Level;
@@ -145,6 +149,80 @@ pub(crate) async fn ensure_image(
     Ok(())
 }
 
+/// Pushes an image from the Docker daemon to its registry.
+///
+/// The returned stream yields the progress updates reported by the Docker
+/// daemon and must be polled to completion for the push to finish. Any error
+/// reported during the push is yielded as an [`Error`].
+pub(crate) fn push_image(
+    docker: &Docker,
+    name: impl AsRef<str>,
+    tag: impl AsRef<str>,
+    credentials: Option<DockerCredentials>,
+) -> BoxStream<'static, Result<PushImageInfo>> {
+    let name = name.as_ref();
+    let tag = tag.as_ref();
+    debug!("pushing image: `{name}:{tag}`");
+
+    let stream = docker
+        .inner()
+        .push_image(
+            name,
+            Some(PushImageOptions {
+                tag: tag.to_owned(),
+            }),
+            credentials,
+        )
+        .map(|result| {
+            let update = result.map_err(Error::Docker)?;
+
+            if enabled!(Level::TRACE) {
+                trace!(
+                    "push update: {}",
+                    [
+                        update
+                            .status
+                            .as_ref()
+                            .map(|status| format!("status: {status}")),
+                        update
+                            .progress
+                            .as_ref()
+                            .map(|progress| format!("progress: {progress}")),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join("; ")
+                );
+            }
+
+            Ok(update)
+        });
+
+    Box::pin(stream)
+}
+
+/// Gets the digest of an image stored in the Docker daemon.
+///
+/// If the image was pulled from a registry, its (first) repository digest is
+/// returned (e.g., `ubuntu@sha256:...`). Otherwise, the id of the image is
+/// returned (which is itself a digest of the configuration of the image).
+pub(crate) async fn image_digest(docker: &Docker, name: impl AsRef<str>) -> Result<Option<String>> {
+    let name = name.as_ref();
+    debug!("inspecting image: `{name}`");
+
+    let image = docker
+        .inner()
+        .inspect_image(name)
+        .await
+        .map_err(Error::Docker)?;
+
+    Ok(image
+        .repo_digests
+        .and_then(|digests| digests.into_iter().next())
+        .or(image.id))
+}
+
 /// Removes an image from the Docker daemon.
 pub(crate) async fn remove_image(
     docker: &Docker,
@@ -1,25 +1,77 @@
 //! A Docker client that uses [`bollard`].
 
+use bollard::auth::DockerCredentials;
+use bollard::secret::ContainerSummary;
 use bollard::secret::ImageDeleteResponseItem;
 use bollard::secret::ImageSummary;
+use bollard::secret::PushImageInfo;
+use bollard::secret::SystemInfo;
+use futures::stream::BoxStream;
 
 pub mod container;
+pub mod containers;
 pub mod images;
+pub mod version;
 
 pub use crate::container::Container;
+use crate::containers::*;
 use crate::images::*;
+pub use crate::version::ApiVersion;
+pub use crate::version::Feature;
 
 /// A global error within this crate.
 #[derive(Debug)]
 pub enum Error {
     /// An error from [`bollard`].
     Docker(bollard::errors::Error),
+
+    /// An i/o error.
+    Io(std::io::Error),
+
+    /// The options provided to a [container builder](container::Builder)
+    /// have problems.
+    Invalid(Vec<container::Problem>),
+
+    /// A feature is not supported by the Docker API version of the client.
+    Unsupported {
+        /// The unsupported feature.
+        feature: Feature,
+
+        /// The minimum Docker API version that supports the feature.
+        required: ApiVersion,
+
+        /// The Docker API version of the client.
+        actual: ApiVersion,
+    },
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Error::Docker(err) => write!(f, "docker error: {err}"),
+            Error::Io(err) => write!(f, "i/o error: {err}"),
+            Error::Invalid(problems) => {
+                write!(f, "invalid container: ")?;
+
+                for (i, problem) in problems.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+
+                    write!(f, "{problem}")?;
+                }
+
+                Ok(())
+            }
+            Error::Unsupported {
+                feature,
+                required,
+                actual,
+            } => write!(
+                f,
+                "{feature} require Docker API version {required} or later, but the client is \
+                 using version {actual}"
+            ),
         }
     }
 }
@@ -29,6 +81,17 @@ impl std::error::Error for Error {}
 /// A [`Result`](std::result::Result) with an [`Error`].
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// The address of the Docker daemon when `DOCKER_HOST` is not set.
+#[cfg(unix)]
+const DEFAULT_DOCKER_HOST: &str = "unix:///var/run/docker.sock";
+
+/// The address of the Docker daemon when `DOCKER_HOST` is not set.
+#[cfg(windows)]
+const DEFAULT_DOCKER_HOST: &str = "npipe:////./pipe/docker_engine";
+
+/// The timeout (in seconds) of requests to the Docker daemon.
+const DEFAULT_TIMEOUT: u64 = 120;
+
 /// A Docker client.
 #[derive(Clone, Debug)]
 pub struct Docker(bollard::Docker);
@@ -57,11 +120,60 @@ impl Docker {
         Ok(Self::new(client))
     }
 
+    /// Attempts to create a new [`Docker`] with the default connection details
+    /// that is pinned to a particular Docker API version.
+    ///
+    /// Like [`Docker::with_defaults()`], the daemon is located through the
+    /// `DOCKER_HOST` environment variable (if it is set).
+    pub fn with_pinned_version(version: ApiVersion) -> Result<Self> {
+        let host =
+            std::env::var("DOCKER_HOST").unwrap_or_else(|_| String::from(DEFAULT_DOCKER_HOST));
+        let version = version.into();
+
+        let client = if host.starts_with("tcp://") || host.starts_with("http://") {
+            bollard::Docker::connect_with_http(&host, DEFAULT_TIMEOUT, &version)
+        } else {
+            bollard::Docker::connect_with_local(&host, DEFAULT_TIMEOUT, &version)
+        }
+        .map_err(Error::Docker)?;
+
+        Ok(Self::new(client))
+    }
+
+    /// Negotiates the Docker API version with the daemon, downgrading the
+    /// version used by the client if the daemon does not support it.
+    pub async fn negotiate(self) -> Result<Self> {
+        let client = self.0.negotiate_version().await.map_err(Error::Docker)?;
+        Ok(Self::new(client))
+    }
+
+    /// Gets the Docker API version used by the client.
+    pub fn api_version(&self) -> ApiVersion {
+        self.0.client_version().into()
+    }
+
+    /// Checks whether a feature is supported by the Docker API version used by
+    /// the client.
+    pub fn require(&self, feature: Feature) -> Result<()> {
+        version::require(&self.0, feature)
+    }
+
     /// Gets a reference to the inner [`bollard::Docker`].
     pub fn inner(&self) -> &bollard::Docker {
         &self.0
     }
 
+    /// Checks whether the Docker daemon is reachable.
+    pub async fn ping(&self) -> Result<()> {
+        self.0.ping().await.map(|_| ()).map_err(Error::Docker)
+    }
+
+    /// Gets system-wide information about the Docker daemon (e.g., the number
+    /// of CPUs and the total memory available to it).
+    pub async fn info(&self) -> Result<SystemInfo> {
+        self.0.info().await.map_err(Error::Docker)
+    }
+
     //----------------------------------------------------------------------------------
     // Images
     //----------------------------------------------------------------------------------
@@ -81,6 +193,28 @@ impl Docker {
         ensure_image(self, name, tag).await
     }
 
+    /// Pushes an image from the Docker daemon to its registry.
+    ///
+    /// The returned stream yields the progress updates reported by the Docker
+    /// daemon and must be polled to completion for the push to finish. If no
+    /// `credentials` are provided, the registry is accessed anonymously.
+    pub fn push_image(
+        &self,
+        name: impl AsRef<str>,
+        tag: impl AsRef<str>,
+        credentials: Option<DockerCredentials>,
+    ) -> BoxStream<'static, Result<PushImageInfo>> {
+        push_image(self, name, tag, credentials)
+    }
+
+    /// Gets the digest of an image stored in the Docker daemon.
+    ///
+    /// This is the repository digest of the image if it was pulled from a
+    /// registry or the id of the image otherwise.
+    pub async fn image_digest(&self, name: impl AsRef<str>) -> Result<Option<String>> {
+        image_digest(self, name).await
+    }
+
     /// Removes an image from the Docker daemon.
     pub async fn remove_image(
         &self,
@@ -114,6 +248,36 @@ impl Docker {
     pub fn container_from_name(&self, id: impl Into<String>, attached: bool) -> Container {
         Container::new(self.0.clone(), id.into(), attached)
     }
+
+    /// Gets all of the containers created by Crankshaft that match a
+    /// [`Filter`].
+    pub async fn list_crankshaft_containers(
+        &self,
+        filter: &Filter,
+    ) -> Result<Vec<ContainerSummary>> {
+        list_crankshaft_containers(self, filter).await
+    }
+
+    /// Stops all of the running containers created by Crankshaft that match a
+    /// [`Filter`].
+    ///
+    /// Returns the ids of the stopped containers.
+    pub async fn stop_crankshaft_containers(&self, filter: &Filter) -> Result<Vec<String>> {
+        stop_crankshaft_containers(self, filter).await
+    }
+
+    /// Removes all of the containers created by Crankshaft that match a
+    /// [`Filter`].
+    ///
+    /// Running containers are only removed if `force` is `true`. Returns the
+    /// ids of the removed containers.
+    pub async fn remove_crankshaft_containers(
+        &self,
+        filter: &Filter,
+        force: bool,
+    ) -> Result<Vec<String>> {
+        remove_crankshaft_containers(self, filter, force).await
+    }
 }
 
 #[cfg(test)]
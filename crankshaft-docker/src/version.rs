@@ -0,0 +1,161 @@
+//! Docker API versions and the features that depend on them.
+//!
+//! Older Docker daemons reject requests that use fields introduced in newer
+//! API versions (usually with an unhelpful `400 Bad Request`). The API version
+//! of a client is either negotiated with the daemon (see
+//! [`Docker::negotiate()`](crate::Docker::negotiate)) or pinned when connecting
+//! (see [`Docker::with_pinned_version()`](crate::Docker::with_pinned_version)),
+//! and features that require a newer API version are checked against it before
+//! any request is made (see [`Feature`]).
+
+use std::str::FromStr;
+
+use bollard::ClientVersion;
+
+/// A version of the Docker API.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ApiVersion {
+    /// The major version.
+    major: usize,
+
+    /// The minor version.
+    minor: usize,
+}
+
+impl ApiVersion {
+    /// Creates a new [`ApiVersion`].
+    pub const fn new(major: usize, minor: usize) -> Self {
+        Self { major, minor }
+    }
+
+    /// Gets the major version.
+    pub fn major(&self) -> usize {
+        self.major
+    }
+
+    /// Gets the minor version.
+    pub fn minor(&self) -> usize {
+        self.minor
+    }
+}
+
+impl std::fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl FromStr for ApiVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid Docker API version `{s}` (expected e.g. `1.41`)");
+
+        let (major, minor) = s
+            .trim_start_matches('v')
+            .split_once('.')
+            .ok_or_else(invalid)?;
+
+        Ok(Self {
+            major: major.parse().map_err(|_| invalid())?,
+            minor: minor.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl From<ClientVersion> for ApiVersion {
+    fn from(version: ClientVersion) -> Self {
+        Self::new(version.major_version, version.minor_version)
+    }
+}
+
+impl From<ApiVersion> for ClientVersion {
+    fn from(version: ApiVersion) -> Self {
+        ClientVersion {
+            major_version: version.major,
+            minor_version: version.minor,
+        }
+    }
+}
+
+/// A feature that requires a minimum version of the Docker API.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Feature {
+    /// Requesting devices (e.g., GPUs) for a container.
+    DeviceRequests,
+}
+
+impl Feature {
+    /// Gets the minimum version of the Docker API that supports the feature.
+    pub fn min_version(&self) -> ApiVersion {
+        match self {
+            Feature::DeviceRequests => ApiVersion::new(1, 40),
+        }
+    }
+}
+
+impl std::fmt::Display for Feature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Feature::DeviceRequests => write!(f, "device requests"),
+        }
+    }
+}
+
+/// Checks whether a feature is supported by a client.
+pub(crate) fn require(client: &bollard::Docker, feature: Feature) -> crate::Result<()> {
+    let actual = ApiVersion::from(client.client_version());
+    let required = feature.min_version();
+
+    if actual < required {
+        return Err(crate::Error::Unsupported {
+            feature,
+            required,
+            actual,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_are_parsed_and_ordered() {
+        let version = "1.41".parse::<ApiVersion>().unwrap();
+        assert_eq!(version, ApiVersion::new(1, 41));
+        assert_eq!(version.to_string(), "1.41");
+        assert_eq!("v1.9".parse::<ApiVersion>(), Ok(ApiVersion::new(1, 9)));
+        assert!("1".parse::<ApiVersion>().is_err());
+        assert!("1.x".parse::<ApiVersion>().is_err());
+
+        // NOTE: minor versions are compared numerically rather than lexically.
+        assert!(ApiVersion::new(1, 9) < ApiVersion::new(1, 40));
+    }
+
+    #[test]
+    fn features_require_their_minimum_version() {
+        let old = bollard::Docker::connect_with_http(
+            "http://localhost:2375",
+            120,
+            &ApiVersion::new(1, 39).into(),
+        )
+        .unwrap();
+        let err = require(&old, Feature::DeviceRequests).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "device requests require Docker API version 1.40 or later, but the client is using \
+             version 1.39"
+        );
+
+        let new = bollard::Docker::connect_with_http(
+            "http://localhost:2375",
+            120,
+            &ApiVersion::new(1, 40).into(),
+        )
+        .unwrap();
+        assert!(require(&new, Feature::DeviceRequests).is_ok());
+    }
+}
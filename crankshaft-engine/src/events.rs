@@ -0,0 +1,1061 @@
+//! Events emitted by the engine.
+//!
+//! Events are broadcast to every subscriber (see [`Engine::subscribe()`]) as
+//! tasks move through their lifecycle. Subscribers that fall behind by more
+//! than [`CHANNEL_CAPACITY`] events will observe a
+//! [`RecvError::Lagged`](tokio::sync::broadcast::error::RecvError::Lagged)
+//! error, and the oldest events are skipped.
+//!
+//! Task ids are unique within an engine (across all of its backends). Every
+//! event about a task also names the backend the task was submitted to, so
+//! that consumers of engines with multiple backends can correlate events with
+//! backend-native identifiers (e.g., a job id) without tracking the
+//! [`TaskCreated`](Event::TaskCreated) event of each task.
+//!
+//! Embedders with a single consumer of events can instead register a handler
+//! (see [`Engine::with_event_handler()`]) that is called directly with every
+//! event. A handler never lags, and events are only cloned when they are also
+//! broadcast to subscribers.
+//!
+//! The current state of each task can also be queried at any time through a
+//! [`Tracker`] (see [`Engine::tracker()`]), which folds the events of each
+//! task into a summary as they are emitted.
+//!
+//! [`Engine::subscribe()`]: crate::Engine::subscribe
+//! [`Engine::with_event_handler()`]: crate::Engine::with_event_handler
+//! [`Engine::tracker()`]: crate::Engine::tracker
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::process::ExitStatus;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use serde::Serialize;
+use serde::Serializer;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::service::runner::backend::Accounting;
+use crate::service::runner::backend::FailureReason;
+
+pub mod tracker;
+pub mod webhook;
+
+pub use tracker::Tracker;
+
+/// The number of events that are buffered for each subscriber.
+pub const CHANNEL_CAPACITY: usize = 1024;
+
+/// The interval at which heartbeats are sent for running tasks by backends
+/// that are able to observe their tasks continuously (see
+/// [`TaskEvents::with_heartbeats()`]).
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The interval at which the resources used by running tasks are sampled by
+/// backends that are able to measure them continuously (see
+/// [`TaskEvents::with_resource_usage()`]).
+pub const RESOURCE_USAGE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The default time a canceled task that is running is given to shut down
+/// gracefully before it is forcibly killed (see [`TaskEvents::terminated()`]).
+pub const DEFAULT_TERMINATION_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+/// A unique identifier for a task within an engine.
+pub type TaskId = u64;
+
+/// A unique identifier for a batch of tasks within an engine.
+pub type BatchId = u64;
+
+/// The resource limits a task is constrained to by its backend.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Limits {
+    /// The memory limit in bytes (if one is set).
+    pub memory: Option<i64>,
+
+    /// The combined memory and swap limit in bytes (if one is set).
+    ///
+    /// When this is equal to [`memory`](Limits::memory), no swap is available.
+    pub memory_swap: Option<i64>,
+}
+
+/// A sample of the resources used by a running task.
+///
+/// Each field is only set when the backend was able to measure it.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ResourceUsage {
+    /// The CPU usage as a percentage of a single CPU (e.g., `200.0` for a task
+    /// fully using two CPUs).
+    pub cpu_percent: Option<f64>,
+
+    /// The memory used in bytes.
+    pub memory: Option<u64>,
+
+    /// The disk space used in bytes.
+    pub disk: Option<u64>,
+}
+
+// NOTE: CPU usage is always computed from measured (finite) values, so it is
+// never `NaN`.
+impl Eq for ResourceUsage {}
+
+/// The reason a task is queued within the engine (rather than within the
+/// scheduler of its backend).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum QueueReason {
+    /// The [schedule](crankshaft_config::backend::Schedule) of the backend
+    /// does not currently accept submissions.
+    BackendWindowClosed,
+
+    /// The backend does not currently have enough unreserved resources (e.g.,
+    /// CPUs or memory) for the task.
+    InsufficientResources,
+
+    /// The run the task belongs to already has as many running tasks as its
+    /// concurrency ceiling allows (see
+    /// [`Spawner::with_concurrency()`](crate::service::runner::Spawner::with_concurrency)).
+    RunConcurrencyLimit,
+}
+
+/// Serializes an exit status as its exit code (or `null` if the execution was
+/// terminated by a signal).
+fn serialize_exit_status<S: Serializer>(
+    status: &ExitStatus,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    status.code().serialize(serializer)
+}
+
+/// An event emitted by the engine.
+///
+/// Events are serialized with a `type` field containing the kebab-case name of
+/// the event (e.g., `task-completed`), which is how they are delivered to
+/// [webhooks](webhook).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Event {
+    /// A backend was initialized and added to the engine.
+    BackendInitialized {
+        /// The name of the backend.
+        name: String,
+
+        /// The kind of the backend (e.g., `docker`).
+        kind: String,
+
+        /// The maximum number of tasks the backend runs concurrently.
+        capacity: usize,
+
+        /// Whether or not the backend reports the resources used by its
+        /// running tasks (see [`TaskResourceUsage`](Event::TaskResourceUsage)).
+        reports_resource_usage: bool,
+    },
+
+    /// A backend became unhealthy and stopped accepting work.
+    BackendUnhealthy {
+        /// The name of the backend.
+        name: String,
+
+        /// The reason the backend became unhealthy.
+        reason: String,
+    },
+
+    /// A previously unhealthy backend became healthy again.
+    BackendRestored {
+        /// The name of the backend.
+        name: String,
+    },
+
+    /// A backend started prefetching an image (see
+    /// [`Engine::prefetch_images()`](crate::Engine::prefetch_images)).
+    ImagePrefetchStarted {
+        /// The name of the backend.
+        backend: String,
+
+        /// The image being prefetched.
+        image: String,
+    },
+
+    /// A backend finished prefetching an image.
+    ImagePrefetchFinished {
+        /// The name of the backend.
+        backend: String,
+
+        /// The image that was prefetched.
+        image: String,
+
+        /// The reason the image could not be prefetched (if it failed).
+        error: Option<String>,
+    },
+
+    /// A task was submitted to a backend.
+    TaskCreated {
+        /// The id of the task.
+        task_id: TaskId,
+
+        /// The name of the backend the task was submitted to.
+        backend: String,
+
+        /// The name of the task (if it has one).
+        name: Option<String>,
+
+        /// The resource limits chosen for the task by the backend (if the
+        /// backend reports them).
+        limits: Option<Limits>,
+    },
+
+    /// A task within a [group](crate::service::runner::group) is waiting on
+    /// its dependencies before it is queued.
+    TaskBlocked {
+        /// The id of the task.
+        task_id: TaskId,
+
+        /// The name of the backend the task was submitted to.
+        backend: String,
+
+        /// The ids of the tasks that the task depends on.
+        dependencies: Vec<TaskId>,
+    },
+
+    /// Every dependency of a blocked task succeeded, so the task is queued.
+    TaskUnblocked {
+        /// The id of the task.
+        task_id: TaskId,
+
+        /// The name of the backend the task was submitted to.
+        backend: String,
+    },
+
+    /// A task is queued within the engine until its backend accepts it.
+    ///
+    /// This is sent at most once per task for each reason.
+    TaskQueued {
+        /// The id of the task.
+        task_id: TaskId,
+
+        /// The name of the backend the task was submitted to.
+        backend: String,
+
+        /// The reason the task is queued.
+        reason: QueueReason,
+
+        /// When the backend is expected to accept the task (if known).
+        until: Option<SystemTime>,
+    },
+
+    /// A task acquired a permit from its backend and started running.
+    ///
+    /// For backends that submit tasks to a scheduler (such as LSF), this is
+    /// instead sent once the scheduler reports that the job started running.
+    TaskStarted {
+        /// The id of the task.
+        task_id: TaskId,
+
+        /// The name of the backend the task was submitted to.
+        backend: String,
+    },
+
+    /// An execution within a task started.
+    ExecutionStarted {
+        /// The id of the task.
+        task_id: TaskId,
+
+        /// The name of the backend the task was submitted to.
+        backend: String,
+
+        /// The index of the execution within the task.
+        index: usize,
+    },
+
+    /// A running task was observed to still be alive by its backend.
+    ///
+    /// Backends send these periodically (e.g., while a container is still
+    /// running or while a monitor command still reports the job as running),
+    /// so consumers can detect tasks that have hung without producing any
+    /// output.
+    TaskHeartbeat {
+        /// The id of the task.
+        task_id: TaskId,
+
+        /// The name of the backend the task was submitted to.
+        backend: String,
+
+        /// When the task was observed to be alive.
+        at: SystemTime,
+    },
+
+    /// A running task was observed to be held or suspended by its scheduler.
+    ///
+    /// This is sent once each time a task becomes held or suspended. No
+    /// heartbeats are sent for the task while it remains held or suspended.
+    TaskSuspended {
+        /// The id of the task.
+        task_id: TaskId,
+
+        /// The name of the backend the task was submitted to.
+        backend: String,
+
+        /// The reason the task is held or suspended (e.g., the state reported
+        /// by the scheduler).
+        reason: String,
+    },
+
+    /// The checkpoint command of a task ran because the task was about to be
+    /// preempted.
+    ///
+    /// Only backends that can observe preemption (e.g., through a signal sent
+    /// by the scheduler ahead of the kill deadline) send this event.
+    TaskCheckpointed {
+        /// The id of the task.
+        task_id: TaskId,
+
+        /// The name of the backend the task was submitted to.
+        backend: String,
+
+        /// The index of the execution that was running when the checkpoint
+        /// command ran.
+        index: usize,
+
+        /// Whether or not the checkpoint command exited successfully.
+        success: bool,
+    },
+
+    /// An execution within a task finished.
+    ExecutionFinished {
+        /// The id of the task.
+        task_id: TaskId,
+
+        /// The name of the backend the task was submitted to.
+        backend: String,
+
+        /// The index of the execution within the task.
+        index: usize,
+
+        /// The exit status of the execution.
+        #[serde(serialize_with = "serialize_exit_status")]
+        exit_status: ExitStatus,
+    },
+
+    /// The scheduler reported the accounting data of the job for an execution
+    /// within a task (after the execution finished).
+    ExecutionAccounted {
+        /// The id of the task.
+        task_id: TaskId,
+
+        /// The name of the backend the task was submitted to.
+        backend: String,
+
+        /// The index of the execution within the task.
+        index: usize,
+
+        /// The accounting data reported by the scheduler.
+        accounting: Accounting,
+    },
+
+    /// A running task was sampled for the resources it is using.
+    ///
+    /// Backends that are able to measure the resources used by their tasks
+    /// (see [`Backend::reports_resource_usage()`]) send these periodically
+    /// while a task runs.
+    ///
+    /// [`Backend::reports_resource_usage()`]: crate::Backend::reports_resource_usage
+    TaskResourceUsage {
+        /// The id of the task.
+        task_id: TaskId,
+
+        /// The name of the backend the task was submitted to.
+        backend: String,
+
+        /// The resources used by the task.
+        #[serde(flatten)]
+        usage: ResourceUsage,
+
+        /// When the resources were sampled.
+        at: SystemTime,
+    },
+
+    /// An output of a task was uploaded to its URL (after the executions of
+    /// the task finished).
+    TaskOutputUploaded {
+        /// The id of the task.
+        task_id: TaskId,
+
+        /// The name of the backend the task was submitted to.
+        backend: String,
+
+        /// The path of the output within the task.
+        path: String,
+
+        /// The URL the output was uploaded to.
+        url: String,
+
+        /// The number of bytes that were uploaded.
+        size: u64,
+    },
+
+    /// An attempt of a task was preempted or failed, so the task is retried
+    /// according to its [retry policy](crate::task::RetryPolicy).
+    TaskRetried {
+        /// The id of the task.
+        task_id: TaskId,
+
+        /// The name of the backend the task was submitted to.
+        backend: String,
+
+        /// The number of the attempt that is about to run (where the first
+        /// attempt is one).
+        attempt: u32,
+
+        /// The reason the previous attempt is retried.
+        reason: String,
+    },
+
+    /// A task ran to completion.
+    TaskCompleted {
+        /// The id of the task.
+        task_id: TaskId,
+
+        /// The name of the backend the task was submitted to.
+        backend: String,
+
+        /// Whether or not every execution within the task exited successfully.
+        success: bool,
+    },
+
+    /// A task failed because its backend was unable to run it to completion
+    /// (e.g., because the backend panicked).
+    ///
+    /// The failure is isolated to the task: other tasks within the backend
+    /// continue to run.
+    TaskFailed {
+        /// The id of the task.
+        task_id: TaskId,
+
+        /// The name of the backend the task was submitted to.
+        backend: String,
+
+        /// The reason the task failed.
+        reason: FailureReason,
+    },
+
+    /// A running task was canceled, so its backend was asked to shut it down
+    /// gracefully.
+    ///
+    /// This is only sent for backends that terminate tasks gracefully (see
+    /// [`Backend::terminates_gracefully()`]). The backend requests a graceful
+    /// shutdown (e.g., by sending `SIGTERM`) and forcibly kills the task if it
+    /// is still running at the deadline. A
+    /// [`TaskCanceled`](Event::TaskCanceled) event follows once the task was
+    /// shut down.
+    ///
+    /// [`Backend::terminates_gracefully()`]: crate::Backend::terminates_gracefully
+    TaskTerminating {
+        /// The id of the task.
+        task_id: TaskId,
+
+        /// The name of the backend the task was submitted to.
+        backend: String,
+
+        /// When the task is forcibly killed if it is still running.
+        deadline: SystemTime,
+    },
+
+    /// A task was canceled before it ran to completion.
+    TaskCanceled {
+        /// The id of the task.
+        task_id: TaskId,
+
+        /// The name of the backend the task was submitted to.
+        backend: String,
+    },
+
+    /// An annotation of a task was set (see [`Engine::annotate()`]).
+    ///
+    /// Annotations are free-form labels attached to tasks by external systems
+    /// (e.g., `review=flagged`). Setting an annotation that was already set
+    /// replaces its value.
+    ///
+    /// [`Engine::annotate()`]: crate::Engine::annotate
+    TaskAnnotated {
+        /// The id of the task.
+        task_id: TaskId,
+
+        /// The name of the backend the task was submitted to.
+        backend: String,
+
+        /// The key of the annotation.
+        key: String,
+
+        /// The value of the annotation.
+        value: String,
+    },
+
+    /// A batch of tasks was aborted because its failures reached the threshold
+    /// of its [`AbortPolicy`](crate::service::runner::batch::AbortPolicy).
+    ///
+    /// The remaining tasks within the batch are canceled.
+    BatchAborted {
+        /// The id of the batch.
+        batch_id: BatchId,
+
+        /// The number of failed tasks within the batch when it was aborted.
+        failures: usize,
+    },
+
+    /// A run was canceled through a
+    /// [`Canceler`](crate::service::runner::Canceler).
+    ///
+    /// This is sent once the run finishes and summarizes the tasks that were
+    /// canceled.
+    RunCanceled {
+        /// The number of tasks that were canceled while queued.
+        queued: usize,
+
+        /// The number of tasks that were canceled while running.
+        running: usize,
+    },
+}
+
+/// A callback that directly receives every event (see
+/// [`Engine::with_event_handler()`](crate::Engine::with_event_handler)).
+type Handler = Box<dyn Fn(Event) + Send + Sync>;
+
+/// A handle to the events of an engine.
+#[derive(Clone)]
+pub struct Events {
+    /// The sending half of the broadcast channel.
+    sender: broadcast::Sender<Event>,
+
+    /// The callback that directly receives every event (if one is set).
+    handler: Arc<OnceLock<Handler>>,
+
+    /// The tracker of the state of each task (if tracking was started).
+    tracker: Arc<OnceLock<Tracker>>,
+
+    /// The next task id to be handed out.
+    next_id: Arc<AtomicU64>,
+
+    /// The name of the backend of each task that has not finished yet (by
+    /// task id).
+    backends: Arc<Mutex<HashMap<TaskId, Arc<str>>>>,
+
+    /// The next batch id to be handed out.
+    next_batch_id: Arc<AtomicU64>,
+
+    /// The unique id of the run of the engine.
+    run_id: Arc<str>,
+}
+
+impl Events {
+    /// Gets the unique id of the run of the engine.
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    /// Subscribes to the events.
+    ///
+    /// Only events sent after subscribing are received.
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+
+    /// Sets the callback that directly receives every event.
+    ///
+    /// Returns `false` if a callback was already set (in which case the
+    /// callback is not replaced).
+    pub(crate) fn set_handler(&self, handler: impl Fn(Event) + Send + Sync + 'static) -> bool {
+        self.handler.set(Box::new(handler)).is_ok()
+    }
+
+    /// Gets the tracker of the state of each task.
+    ///
+    /// Tracking starts the first time this is called.
+    pub(crate) fn tracker(&self) -> Tracker {
+        self.tracker.get_or_init(Default::default).clone()
+    }
+
+    /// Sends an event to the tracker (if tracking was started), the handler
+    /// (if one is set), and all current subscribers.
+    pub(crate) fn send(&self, event: Event) {
+        // NOTE: finished tasks can no longer be annotated, so their backends
+        // are forgotten to keep the map from growing with every task.
+        if let Event::TaskCompleted { task_id, .. }
+        | Event::TaskFailed { task_id, .. }
+        | Event::TaskCanceled { task_id, .. } = &event
+        {
+            self.backends.lock().unwrap().remove(task_id);
+        }
+
+        if let Some(tracker) = self.tracker.get() {
+            tracker.fold(&event);
+        }
+
+        if let Some(handler) = self.handler.get() {
+            // NOTE: the event is only cloned if it is also broadcast.
+            if self.sender.receiver_count() == 0 {
+                handler(event);
+                return;
+            }
+
+            handler(event.clone());
+        }
+
+        // NOTE: sending only fails when there are no subscribers, which simply
+        // means that nobody is interested in the event.
+        let _ = self.sender.send(event);
+    }
+
+    /// Reserves the next task id.
+    pub(crate) fn next_id(&self) -> TaskId {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Reserves the next batch id.
+    pub(crate) fn next_batch_id(&self) -> BatchId {
+        self.next_batch_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Gets the name of the backend a task was submitted to (if the task
+    /// exists and has not finished yet).
+    pub(crate) fn backend(&self, task_id: TaskId) -> Option<Arc<str>> {
+        self.backends.lock().unwrap().get(&task_id).cloned()
+    }
+
+    /// Gets the events for a particular task submitted to a backend.
+    pub(crate) fn task(&self, task_id: TaskId, backend: impl Into<Arc<str>>) -> TaskEvents {
+        let backend = backend.into();
+        self.backends
+            .lock()
+            .unwrap()
+            .insert(task_id, backend.clone());
+
+        TaskEvents {
+            task_id,
+            backend,
+            terminate: CancellationToken::new(),
+            grace_period: DEFAULT_TERMINATION_GRACE_PERIOD,
+            events: self.clone(),
+        }
+    }
+}
+
+impl Default for Events {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+
+        Self {
+            sender,
+            handler: Default::default(),
+            tracker: Default::default(),
+            next_id: Default::default(),
+            backends: Default::default(),
+            next_batch_id: Default::default(),
+            run_id: Uuid::new_v4().to_string().into(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Events {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Events")
+            .field("sender", &self.sender)
+            .field("handler", &self.handler.get().is_some())
+            .field("tracker", &self.tracker.get())
+            .field("next_id", &self.next_id)
+            .field("next_batch_id", &self.next_batch_id)
+            .field("run_id", &self.run_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The events for a single task.
+///
+/// This is handed to a [`Backend`](crate::Backend) when a task is run so that
+/// the backend can report progress within the task.
+#[derive(Clone, Debug)]
+pub struct TaskEvents {
+    /// The id of the task.
+    task_id: TaskId,
+
+    /// The name of the backend the task was submitted to.
+    backend: Arc<str>,
+
+    /// The token that is canceled once the task should terminate.
+    terminate: CancellationToken,
+
+    /// The time the task is given to shut down gracefully once it should
+    /// terminate.
+    grace_period: Duration,
+
+    /// The events of the engine.
+    events: Events,
+}
+
+impl TaskEvents {
+    /// Gets the id of the task.
+    pub fn task_id(&self) -> TaskId {
+        self.task_id
+    }
+
+    /// Gets the name of the backend the task was submitted to.
+    pub fn backend(&self) -> &str {
+        &self.backend
+    }
+
+    /// Gets the unique id of the run of the engine that the task was
+    /// submitted to.
+    pub fn run_id(&self) -> &str {
+        self.events.run_id()
+    }
+
+    /// Gets the time the task is given to shut down gracefully once it should
+    /// terminate (see [`TaskEvents::terminated()`]).
+    pub fn grace_period(&self) -> Duration {
+        self.grace_period
+    }
+
+    /// Completes once the task was canceled while running and should
+    /// terminate.
+    ///
+    /// This is only meant to be awaited by backends that
+    /// [terminate tasks gracefully](crate::Backend::terminates_gracefully).
+    /// Once this completes, the backend should request a graceful shutdown of
+    /// the task, forcibly kill it if it is still running after the
+    /// [grace period](Self::grace_period), and then return.
+    pub async fn terminated(&self) {
+        self.terminate.cancelled().await
+    }
+
+    /// Gets whether or not the task should terminate.
+    pub fn is_terminated(&self) -> bool {
+        self.terminate.is_cancelled()
+    }
+
+    /// Sets the time the task is given to shut down gracefully once it should
+    /// terminate.
+    pub(crate) fn with_grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    /// Requests that the task terminate and reports that it is terminating.
+    pub(crate) fn terminate(&self) {
+        self.terminate.cancel();
+        self.events.send(Event::TaskTerminating {
+            task_id: self.task_id,
+            backend: self.backend.to_string(),
+            deadline: SystemTime::now() + self.grace_period,
+        });
+    }
+
+    /// Reports that the task started running.
+    ///
+    /// This is only meant to be called by backends that
+    /// [report when tasks started](crate::Backend::reports_started) (and only
+    /// once per task); otherwise, the engine reports it.
+    pub fn started(&self) {
+        self.events.send(Event::TaskStarted {
+            task_id: self.task_id,
+            backend: self.backend.to_string(),
+        });
+    }
+
+    /// Reports that the task is queued within the backend until it can be
+    /// accepted (e.g., until enough resources are released by other tasks).
+    pub fn queued(&self, reason: QueueReason, until: Option<SystemTime>) {
+        self.events.send(Event::TaskQueued {
+            task_id: self.task_id,
+            backend: self.backend.to_string(),
+            reason,
+            until,
+        });
+    }
+
+    /// Reports that an execution within the task started.
+    pub fn execution_started(&self, index: usize) {
+        self.events.send(Event::ExecutionStarted {
+            task_id: self.task_id,
+            backend: self.backend.to_string(),
+            index,
+        });
+    }
+
+    /// Reports that an execution within the task finished.
+    pub fn execution_finished(&self, index: usize, exit_status: ExitStatus) {
+        self.events.send(Event::ExecutionFinished {
+            task_id: self.task_id,
+            backend: self.backend.to_string(),
+            index,
+            exit_status,
+        });
+    }
+
+    /// Reports that the task was observed to still be alive.
+    pub fn heartbeat(&self) {
+        self.events.send(Event::TaskHeartbeat {
+            task_id: self.task_id,
+            backend: self.backend.to_string(),
+            at: SystemTime::now(),
+        });
+    }
+
+    /// Reports that the task was observed to be held or suspended.
+    pub fn suspended(&self, reason: impl Into<String>) {
+        self.events.send(Event::TaskSuspended {
+            task_id: self.task_id,
+            backend: self.backend.to_string(),
+            reason: reason.into(),
+        });
+    }
+
+    /// Reports that the checkpoint command of the task ran while an execution
+    /// was running.
+    pub fn checkpointed(&self, index: usize, success: bool) {
+        self.events.send(Event::TaskCheckpointed {
+            task_id: self.task_id,
+            backend: self.backend.to_string(),
+            index,
+            success,
+        });
+    }
+
+    /// Reports the accounting data of the job for an execution within the
+    /// task.
+    pub fn accounted(&self, index: usize, accounting: Accounting) {
+        self.events.send(Event::ExecutionAccounted {
+            task_id: self.task_id,
+            backend: self.backend.to_string(),
+            index,
+            accounting,
+        });
+    }
+
+    /// Reports a sample of the resources used by the task.
+    pub fn resource_usage(&self, usage: ResourceUsage) {
+        self.events.send(Event::TaskResourceUsage {
+            task_id: self.task_id,
+            backend: self.backend.to_string(),
+            usage,
+            at: SystemTime::now(),
+        });
+    }
+
+    /// Reports that an output of the task was uploaded to its URL.
+    pub fn output_uploaded(&self, path: impl Into<String>, url: impl Into<String>, size: u64) {
+        self.events.send(Event::TaskOutputUploaded {
+            task_id: self.task_id,
+            backend: self.backend.to_string(),
+            path: path.into(),
+            url: url.into(),
+            size,
+        });
+    }
+
+    /// Drives a future to completion while reporting a heartbeat every
+    /// [`HEARTBEAT_INTERVAL`].
+    ///
+    /// This is intended for backends that wait on a running task without
+    /// polling it (e.g., waiting for a container to exit).
+    pub async fn with_heartbeats<F: Future>(&self, future: F) -> F::Output {
+        let start = tokio::time::Instant::now() + HEARTBEAT_INTERVAL;
+        let mut interval = tokio::time::interval_at(start, HEARTBEAT_INTERVAL);
+        tokio::pin!(future);
+
+        loop {
+            tokio::select! {
+                biased;
+                output = &mut future => return output,
+                _ = interval.tick() => self.heartbeat(),
+            }
+        }
+    }
+
+    /// Drives a future to completion while reporting a sample of the resources
+    /// used by the task every [`RESOURCE_USAGE_INTERVAL`].
+    ///
+    /// Each sample is taken with `sample`; samples that could not be taken
+    /// (i.e., [`None`]) are not reported. The future keeps being driven while
+    /// a sample is taken.
+    pub async fn with_resource_usage<F, S, Fut>(&self, future: F, mut sample: S) -> F::Output
+    where
+        F: Future,
+        S: FnMut() -> Fut,
+        Fut: Future<Output = Option<ResourceUsage>>,
+    {
+        let start = tokio::time::Instant::now() + RESOURCE_USAGE_INTERVAL;
+        let mut interval = tokio::time::interval_at(start, RESOURCE_USAGE_INTERVAL);
+        tokio::pin!(future);
+
+        loop {
+            tokio::select! {
+                biased;
+                output = &mut future => return output,
+                _ = interval.tick() => {
+                    let sampling = sample();
+                    tokio::pin!(sampling);
+
+                    tokio::select! {
+                        biased;
+                        output = &mut future => return output,
+                        usage = &mut sampling => {
+                            if let Some(usage) = usage {
+                                self.resource_usage(usage);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sends an event for the task.
+    pub(crate) fn send(&self, event: Event) {
+        self.events.send(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn task_ids_are_unique() {
+        let events = Events::default();
+        let other = events.clone();
+
+        assert_eq!(events.next_id(), 0);
+        assert_eq!(other.next_id(), 1);
+        assert_eq!(events.next_id(), 2);
+    }
+
+    #[test]
+    fn task_events_are_broadcast() {
+        let events = Events::default();
+        let mut first = events.subscribe();
+        let mut second = events.subscribe();
+
+        let task = events.task(events.next_id(), "docker");
+        task.execution_started(0);
+
+        for receiver in [&mut first, &mut second] {
+            assert_eq!(
+                receiver.try_recv().unwrap(),
+                Event::ExecutionStarted {
+                    task_id: 0,
+                    backend: String::from("docker"),
+                    index: 0
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn handlers_receive_events_directly() {
+        let events = Events::default();
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        assert!(events.set_handler({
+            let received = received.clone();
+            move |event| received.lock().unwrap().push(event)
+        }));
+        assert!(!events.set_handler(|_| {}));
+
+        let task = events.task(events.next_id(), "docker");
+        task.execution_started(0);
+
+        let mut receiver = events.subscribe();
+        task.execution_started(1);
+
+        assert_eq!(received.lock().unwrap().len(), 2);
+        assert!(matches!(
+            receiver.try_recv().unwrap(),
+            Event::ExecutionStarted { index: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn backends_of_finished_tasks_are_forgotten() {
+        let events = Events::default();
+
+        let task_id = events.next_id();
+        let _task = events.task(task_id, "docker");
+        assert_eq!(events.backend(task_id).as_deref(), Some("docker"));
+
+        events.send(Event::TaskCompleted {
+            task_id,
+            backend: String::from("docker"),
+            success: true,
+        });
+        assert_eq!(events.backend(task_id), None);
+    }
+
+    #[test]
+    fn heartbeats_are_sent_while_waiting() {
+        crate::simulation::runtime().unwrap().block_on(async {
+            let events = Events::default();
+            let mut receiver = events.subscribe();
+            let task = events.task(events.next_id(), "docker");
+
+            let output = task
+                .with_heartbeats(async {
+                    tokio::time::sleep(HEARTBEAT_INTERVAL * 3 + Duration::from_secs(1)).await;
+                    42
+                })
+                .await;
+            assert_eq!(output, 42);
+
+            let mut heartbeats = 0;
+            while let Ok(event) = receiver.try_recv() {
+                assert!(matches!(event, Event::TaskHeartbeat { task_id: 0, .. }));
+                heartbeats += 1;
+            }
+
+            assert_eq!(heartbeats, 3);
+        });
+    }
+
+    #[test]
+    fn resource_usage_is_sampled_while_waiting() {
+        crate::simulation::runtime().unwrap().block_on(async {
+            let events = Events::default();
+            let mut receiver = events.subscribe();
+            let task = events.task(events.next_id(), "docker");
+
+            let mut samples = 0u64;
+            task.with_resource_usage(
+                tokio::time::sleep(RESOURCE_USAGE_INTERVAL * 3 + Duration::from_secs(1)),
+                || {
+                    samples += 1;
+                    let memory = samples * 1024;
+
+                    // NOTE: the second sample could not be taken.
+                    async move {
+                        (memory != 2048).then_some(ResourceUsage {
+                            memory: Some(memory),
+                            ..Default::default()
+                        })
+                    }
+                },
+            )
+            .await;
+
+            let mut memory = Vec::new();
+            while let Ok(event) = receiver.try_recv() {
+                match event {
+                    Event::TaskResourceUsage {
+                        task_id: 0, usage, ..
+                    } => memory.push(usage.memory.unwrap()),
+                    event => panic!("unexpected event: {event:?}"),
+                }
+            }
+
+            assert_eq!(memory, [1024, 3072]);
+        });
+    }
+}
@@ -0,0 +1,475 @@
+//! Tracking of the current state of each task.
+//!
+//! Subscribers only receive the events emitted after they subscribe, so a
+//! consumer that starts late (e.g., a status listing similar to `bjobs`) has
+//! no way to learn about the tasks that came before it. A [`Tracker`] folds
+//! every event into a [`TaskSummary`] for each task as the events are emitted,
+//! so the current state of every task can be queried at any time (see
+//! [`Engine::tracker()`](crate::Engine::tracker)).
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use serde::Serialize;
+
+use crate::events::Event;
+use crate::events::QueueReason;
+use crate::events::TaskId;
+
+/// The status of a task.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TaskStatus {
+    /// The task was submitted but has not started running.
+    Pending,
+
+    /// The task is waiting on its dependencies within a group.
+    Blocked,
+
+    /// The task is queued within the engine until its backend accepts it.
+    Queued,
+
+    /// The task is running.
+    Running,
+
+    /// The task is held or suspended by its scheduler.
+    Suspended,
+
+    /// The task was canceled while running and is shutting down.
+    Terminating,
+
+    /// Every execution within the task exited successfully.
+    Succeeded,
+
+    /// An execution within the task failed or the backend was unable to run
+    /// the task.
+    Failed,
+
+    /// The task was canceled before it ran to completion.
+    Canceled,
+}
+
+impl TaskStatus {
+    /// Gets whether or not the task has finished (i.e., it will not change
+    /// status again).
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            TaskStatus::Succeeded | TaskStatus::Failed | TaskStatus::Canceled
+        )
+    }
+}
+
+/// A summary of the current state of a task.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TaskSummary {
+    /// The id of the task.
+    task_id: TaskId,
+
+    /// The name of the backend the task was submitted to.
+    backend: String,
+
+    /// The name of the task (if it has one).
+    name: Option<String>,
+
+    /// The status of the task.
+    status: TaskStatus,
+
+    /// The reason the task is queued (if it is queued).
+    queue_reason: Option<QueueReason>,
+
+    /// The reason given for the most recent suspension, retry, or failure of
+    /// the task (if any).
+    reason: Option<String>,
+
+    /// The number of the current attempt of the task (where the first attempt
+    /// is one).
+    attempt: u32,
+
+    /// When the task was submitted.
+    created: SystemTime,
+
+    /// When the task first started running (if it has).
+    started: Option<SystemTime>,
+
+    /// When the task finished (if it has).
+    finished: Option<SystemTime>,
+
+    /// When the task was last observed to be alive by its backend (if it
+    /// has been).
+    last_seen: Option<SystemTime>,
+
+    /// The exit codes of the executions of the current attempt that finished
+    /// (by index).
+    ///
+    /// The exit code of an execution that has not finished (or that was
+    /// terminated by a signal) is [`None`].
+    exit_codes: Vec<Option<i32>>,
+
+    /// The annotations of the task (by key).
+    annotations: BTreeMap<String, String>,
+}
+
+impl TaskSummary {
+    /// Creates a new [`TaskSummary`] for a task that was just submitted.
+    fn new(task_id: TaskId, backend: String, name: Option<String>) -> Self {
+        Self {
+            task_id,
+            backend,
+            name,
+            status: TaskStatus::Pending,
+            queue_reason: None,
+            reason: None,
+            attempt: 1,
+            created: SystemTime::now(),
+            started: None,
+            finished: None,
+            last_seen: None,
+            exit_codes: Vec::new(),
+            annotations: BTreeMap::new(),
+        }
+    }
+
+    /// Gets the id of the task.
+    pub fn task_id(&self) -> TaskId {
+        self.task_id
+    }
+
+    /// Gets the name of the backend the task was submitted to.
+    pub fn backend(&self) -> &str {
+        &self.backend
+    }
+
+    /// Gets the name of the task (if it has one).
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Gets the status of the task.
+    pub fn status(&self) -> TaskStatus {
+        self.status
+    }
+
+    /// Gets the reason the task is queued (if it is queued).
+    pub fn queue_reason(&self) -> Option<QueueReason> {
+        self.queue_reason
+    }
+
+    /// Gets the reason given for the most recent suspension, retry, or
+    /// failure of the task (if any).
+    pub fn reason(&self) -> Option<&str> {
+        self.reason.as_deref()
+    }
+
+    /// Gets the number of the current attempt of the task (where the first
+    /// attempt is one).
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// Gets when the task was submitted.
+    pub fn created(&self) -> SystemTime {
+        self.created
+    }
+
+    /// Gets when the task first started running (if it has).
+    pub fn started(&self) -> Option<SystemTime> {
+        self.started
+    }
+
+    /// Gets when the task finished (if it has).
+    pub fn finished(&self) -> Option<SystemTime> {
+        self.finished
+    }
+
+    /// Gets when the task was last observed to be alive by its backend (if it
+    /// has been).
+    pub fn last_seen(&self) -> Option<SystemTime> {
+        self.last_seen
+    }
+
+    /// Gets the exit codes of the executions of the current attempt (by
+    /// index).
+    pub fn exit_codes(&self) -> &[Option<i32>] {
+        &self.exit_codes
+    }
+
+    /// Gets the annotations of the task (by key).
+    pub fn annotations(&self) -> &BTreeMap<String, String> {
+        &self.annotations
+    }
+
+    /// Marks the task as finished with a status.
+    fn finish(&mut self, status: TaskStatus) {
+        self.status = status;
+        self.queue_reason = None;
+        self.finished = Some(SystemTime::now());
+    }
+}
+
+/// A handle to the current state of each task within an engine.
+///
+/// The handle can be cloned cheaply, and every clone observes the same state.
+#[derive(Clone, Debug, Default)]
+pub struct Tracker(Arc<Mutex<BTreeMap<TaskId, TaskSummary>>>);
+
+impl Tracker {
+    /// Gets the summaries of every task (ordered by id).
+    pub fn tasks(&self) -> Vec<TaskSummary> {
+        self.0.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Gets the summary of a task (if it is known).
+    pub fn task(&self, task_id: TaskId) -> Option<TaskSummary> {
+        self.0.lock().unwrap().get(&task_id).cloned()
+    }
+
+    /// Folds an event into the state of its task.
+    ///
+    /// Events about tasks that were created before tracking started are
+    /// ignored.
+    pub(crate) fn fold(&self, event: &Event) {
+        let mut tasks = self.0.lock().unwrap();
+
+        if let Event::TaskCreated {
+            task_id,
+            backend,
+            name,
+            ..
+        } = event
+        {
+            tasks.insert(
+                *task_id,
+                TaskSummary::new(*task_id, backend.clone(), name.clone()),
+            );
+            return;
+        }
+
+        let Some(task) = task_id(event).and_then(|id| tasks.get_mut(&id)) else {
+            return;
+        };
+
+        match event {
+            Event::TaskBlocked { .. } => task.status = TaskStatus::Blocked,
+            Event::TaskUnblocked { .. } => task.status = TaskStatus::Pending,
+            Event::TaskQueued { reason, .. } => {
+                task.status = TaskStatus::Queued;
+                task.queue_reason = Some(*reason);
+            }
+            Event::TaskStarted { .. } | Event::ExecutionStarted { .. } => {
+                task.status = TaskStatus::Running;
+                task.queue_reason = None;
+                task.started.get_or_insert_with(SystemTime::now);
+            }
+            Event::TaskHeartbeat { at, .. } => {
+                // NOTE: no heartbeats are sent while a task is suspended, so a
+                // heartbeat means that a suspended task was resumed.
+                if task.status == TaskStatus::Suspended {
+                    task.status = TaskStatus::Running;
+                }
+
+                task.last_seen = Some(*at);
+            }
+            Event::TaskSuspended { reason, .. } => {
+                task.status = TaskStatus::Suspended;
+                task.reason = Some(reason.clone());
+            }
+            Event::ExecutionFinished {
+                index, exit_status, ..
+            } => {
+                if task.exit_codes.len() <= *index {
+                    task.exit_codes.resize(index + 1, None);
+                }
+
+                task.exit_codes[*index] = exit_status.code();
+            }
+            Event::TaskRetried {
+                attempt, reason, ..
+            } => {
+                task.status = TaskStatus::Pending;
+                task.attempt = *attempt;
+                task.reason = Some(reason.clone());
+                task.exit_codes.clear();
+            }
+            Event::TaskCompleted { success, .. } => task.finish(match success {
+                true => TaskStatus::Succeeded,
+                false => TaskStatus::Failed,
+            }),
+            Event::TaskFailed { reason, .. } => {
+                task.reason = Some(reason.to_string());
+                task.finish(TaskStatus::Failed);
+            }
+            Event::TaskTerminating { .. } => task.status = TaskStatus::Terminating,
+            Event::TaskCanceled { .. } => task.finish(TaskStatus::Canceled),
+            Event::TaskAnnotated { key, value, .. } => {
+                task.annotations.insert(key.clone(), value.clone());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Gets the id of the task an event is about (if it is about a task).
+fn task_id(event: &Event) -> Option<TaskId> {
+    match event {
+        Event::TaskCreated { task_id, .. }
+        | Event::TaskBlocked { task_id, .. }
+        | Event::TaskUnblocked { task_id, .. }
+        | Event::TaskQueued { task_id, .. }
+        | Event::TaskStarted { task_id, .. }
+        | Event::ExecutionStarted { task_id, .. }
+        | Event::TaskHeartbeat { task_id, .. }
+        | Event::TaskSuspended { task_id, .. }
+        | Event::TaskCheckpointed { task_id, .. }
+        | Event::ExecutionFinished { task_id, .. }
+        | Event::ExecutionAccounted { task_id, .. }
+        | Event::TaskResourceUsage { task_id, .. }
+        | Event::TaskOutputUploaded { task_id, .. }
+        | Event::TaskRetried { task_id, .. }
+        | Event::TaskCompleted { task_id, .. }
+        | Event::TaskFailed { task_id, .. }
+        | Event::TaskTerminating { task_id, .. }
+        | Event::TaskCanceled { task_id, .. }
+        | Event::TaskAnnotated { task_id, .. } => Some(*task_id),
+        Event::BackendInitialized { .. }
+        | Event::BackendUnhealthy { .. }
+        | Event::BackendRestored { .. }
+        | Event::ImagePrefetchStarted { .. }
+        | Event::ImagePrefetchFinished { .. }
+        | Event::BatchAborted { .. }
+        | Event::RunCanceled { .. } => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::Engine;
+    use crate::Task;
+    use crate::simulation::MockBackend;
+    use crate::simulation::runtime;
+    use crate::task::Execution;
+
+    #[test]
+    fn tasks_are_summarized_from_their_events() {
+        runtime().unwrap().block_on(async {
+            let backend = MockBackend::new(|task, _| match task.name() {
+                Some("fail") => (Duration::from_secs(60), 3),
+                _ => (Duration::from_secs(60), 0),
+            });
+
+            let engine = Engine::default().with_backend("mock", Arc::new(backend), 10);
+            let tracker = engine.tracker();
+
+            for name in ["succeed", "fail"] {
+                let execution = Execution::builder()
+                    .image("ubuntu")
+                    .args(["echo", "hello"])
+                    .try_build()
+                    .unwrap();
+
+                let task = Task::builder()
+                    .name(name)
+                    .extend_executions([execution])
+                    .try_build()
+                    .unwrap();
+
+                engine.submit("mock", task).detach();
+            }
+
+            // NOTE: tasks are pending until the engine runs.
+            let tasks = tracker.tasks();
+            assert_eq!(tasks.len(), 2);
+            assert!(
+                tasks
+                    .iter()
+                    .all(|task| task.status() == TaskStatus::Pending)
+            );
+
+            engine.run().await;
+
+            let tasks = tracker.tasks();
+            let succeeded = tasks.iter().find(|t| t.name() == Some("succeed")).unwrap();
+            assert_eq!(succeeded.status(), TaskStatus::Succeeded);
+            assert_eq!(succeeded.backend(), "mock");
+            assert_eq!(succeeded.exit_codes(), [Some(0)]);
+            assert!(succeeded.started().is_some());
+            assert!(succeeded.finished().is_some());
+
+            let failed = tracker
+                .task(
+                    tasks
+                        .iter()
+                        .find(|t| t.name() == Some("fail"))
+                        .unwrap()
+                        .task_id(),
+                )
+                .unwrap();
+            assert_eq!(failed.status(), TaskStatus::Failed);
+            assert!(failed.status().is_finished());
+            assert_eq!(failed.exit_codes(), [Some(3)]);
+        });
+    }
+
+    #[test]
+    fn annotations_are_tracked() {
+        runtime().unwrap().block_on(async {
+            let backend = MockBackend::new(|_, _| (Duration::from_secs(60), 0));
+            let engine = Engine::default().with_backend("mock", Arc::new(backend), 10);
+            let tracker = engine.tracker();
+            let mut events = engine.subscribe();
+
+            let task = Task::builder()
+                .name("foo")
+                .extend_executions([Execution::builder()
+                    .image("ubuntu")
+                    .args(["echo", "hello"])
+                    .try_build()
+                    .unwrap()])
+                .try_build()
+                .unwrap();
+
+            let handle = engine.submit("mock", task);
+            let id = handle.id();
+
+            // NOTE: setting an annotation again replaces its value, and tasks
+            // that were never submitted cannot be annotated.
+            assert!(engine.annotate(id, "review", "pending"));
+            assert!(engine.annotate(id, "review", "flagged"));
+            assert!(engine.annotate(id, "qc", "failed"));
+            assert!(!engine.annotate(id + 1, "review", "flagged"));
+
+            engine.run().await;
+
+            assert_eq!(
+                tracker
+                    .task(id)
+                    .unwrap()
+                    .annotations()
+                    .iter()
+                    .collect::<Vec<_>>(),
+                [
+                    (&String::from("qc"), &String::from("failed")),
+                    (&String::from("review"), &String::from("flagged"))
+                ]
+            );
+
+            let mut annotated = Vec::new();
+            while let Ok(event) = events.try_recv() {
+                if let Event::TaskAnnotated {
+                    task_id, backend, ..
+                } = event
+                {
+                    annotated.push((task_id, backend));
+                }
+            }
+
+            assert_eq!(annotated, vec![(id, String::from("mock")); 3]);
+        });
+    }
+}
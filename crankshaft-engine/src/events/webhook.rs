@@ -0,0 +1,193 @@
+//! Webhooks.
+//!
+//! A webhook delivers every [`Event`] emitted by the engine to a URL as a JSON
+//! `POST` request (see
+//! [`Engine::with_webhook()`](crate::Engine::with_webhook)). Events are
+//! delivered one at a time and in order. Failed deliveries are retried with
+//! exponential backoff before the event is dropped.
+//!
+//! If a secret is configured, each request is signed with an
+//! `X-Crankshaft-Signature` header containing `sha256=` followed by the
+//! hex-encoded HMAC-SHA256 of the body, so that receivers can verify that the
+//! request originated from the engine.
+
+use std::time::Duration;
+
+use crankshaft_config::webhook::Config;
+use crankshaft_config::webhook::DEFAULT_RETRIES;
+use sha2::Digest as _;
+use sha2::Sha256;
+use tokio::sync::broadcast::Receiver;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tracing::debug;
+use tracing::warn;
+
+use crate::events::Event;
+
+/// The name of the header containing the signature of the body.
+pub const SIGNATURE_HEADER: &str = "X-Crankshaft-Signature";
+
+/// The delay before the first retry of a failed delivery.
+///
+/// The delay doubles with each subsequent retry.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// The block size of SHA-256 in bytes.
+const BLOCK_SIZE: usize = 64;
+
+/// Computes the HMAC-SHA256 of a message.
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; BLOCK_SIZE];
+
+    // NOTE: keys longer than the block size are hashed first.
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let pad = |value: u8| block.map(|byte| byte ^ value);
+
+    let inner = Sha256::new()
+        .chain_update(pad(0x36))
+        .chain_update(message)
+        .finalize();
+
+    Sha256::new()
+        .chain_update(pad(0x5C))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+/// Computes the value of the [`SIGNATURE_HEADER`] for a body.
+pub fn signature(secret: &str, body: &[u8]) -> String {
+    format!(
+        "sha256={}",
+        hex::encode(hmac_sha256(secret.as_bytes(), body))
+    )
+}
+
+/// A webhook.
+#[derive(Debug)]
+pub struct Webhook {
+    /// The HTTP client.
+    client: reqwest::Client,
+
+    /// The configuration of the webhook.
+    config: Config,
+}
+
+impl Webhook {
+    /// Creates a new [`Webhook`].
+    pub fn new(config: Config) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            config,
+        }
+    }
+
+    /// Spawns a task that delivers every event received to the webhook.
+    ///
+    /// The task finishes once every sender of the events has been dropped.
+    ///
+    /// # Panics
+    ///
+    /// If this is not called from within a [`tokio`] runtime.
+    pub fn spawn(self, mut events: Receiver<Event>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => self.deliver(&event).await,
+                    Err(RecvError::Lagged(skipped)) => warn!(
+                        "webhook `{}` fell behind and skipped {skipped} events",
+                        self.config.url()
+                    ),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    /// Delivers an event to the webhook, retrying failed deliveries.
+    async fn deliver(&self, event: &Event) {
+        // SAFETY: events only contain types that always serialize to JSON.
+        let body = serde_json::to_vec(event).unwrap();
+        let retries = self.config.retries().unwrap_or(DEFAULT_RETRIES);
+        let mut backoff = INITIAL_BACKOFF;
+
+        for attempt in 0..=retries {
+            if attempt > 0 {
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+
+            let mut request = self
+                .client
+                .post(self.config.url().clone())
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.clone());
+
+            if let Some(secret) = self.config.secret() {
+                request = request.header(SIGNATURE_HEADER, signature(secret, &body));
+            }
+
+            match request.send().await.and_then(|r| r.error_for_status()) {
+                Ok(_) => {
+                    debug!("delivered event to webhook `{}`", self.config.url());
+                    return;
+                }
+                Err(err) => debug!(
+                    "attempt {} to deliver event to webhook `{}` failed: {err}",
+                    attempt + 1,
+                    self.config.url()
+                ),
+            }
+        }
+
+        warn!(
+            "dropping event after failing to deliver it to webhook `{}` {} times",
+            self.config.url(),
+            retries + 1
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signatures_are_hmac_sha256() {
+        // NOTE: this is test case 2 from RFC 4231.
+        assert_eq!(
+            signature("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+
+        // NOTE: this is test case 6 from RFC 4231 (a key longer than the block
+        // size).
+        assert_eq!(
+            hex::encode(hmac_sha256(
+                &[0xAA; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn events_are_serialized_with_their_type() {
+        let event = Event::TaskCompleted {
+            task_id: 1,
+            backend: String::from("docker"),
+            success: true,
+        };
+
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"type":"task-completed","task_id":1,"backend":"docker","success":true}"#
+        );
+    }
+}
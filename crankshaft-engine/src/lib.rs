@@ -1,23 +1,56 @@
 //! The engine that powers Crankshaft.
 
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
 use std::time::Duration;
 
 use crankshaft_config::backend::Config;
+use eyre::bail;
+use futures::FutureExt as _;
 use futures::StreamExt;
+use futures::stream;
 use futures::stream::FuturesUnordered;
 use indexmap::IndexMap;
+use indexmap::IndexSet;
 use indicatif::ProgressBar;
 use indicatif::ProgressStyle;
+use tokio::sync::broadcast;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
 use tracing::debug;
 
+pub mod events;
 pub mod service;
+#[cfg(any(test, feature = "simulation"))]
+pub mod simulation;
+pub mod stage;
 pub mod task;
 
 pub use task::Task;
 
+use crate::events::Event;
+use crate::events::Events;
+use crate::events::TaskId;
+use crate::events::Tracker;
+use crate::events::webhook::Webhook;
 use crate::service::Runner;
+use crate::service::metrics::Counters;
+use crate::service::metrics::EngineMetrics;
+use crate::service::report::BackendReport;
+use crate::service::report::RunReport;
+use crate::service::runner::AbortPolicy;
 use crate::service::runner::Backend;
+use crate::service::runner::Batch;
+use crate::service::runner::Canceler;
+use crate::service::runner::Group;
+use crate::service::runner::SubmitInterceptor;
+use crate::service::runner::TaskGroup;
 use crate::service::runner::TaskHandle;
+use crate::service::runner::WeakTaskHandle;
+use crate::service::runner::batch;
+use crate::service::runner::cancellation;
+use crate::service::runner::group;
 
 /// The top-level result returned within the engine.
 ///
@@ -30,36 +63,307 @@ use crate::service::runner::TaskHandle;
 /// [`anyhow`] equivalent for display).
 pub type Result<T> = eyre::Result<T>;
 
+/// The maximum number of images that are prefetched concurrently by
+/// [`Engine::prefetch_images()`].
+pub const PREFETCH_CONCURRENCY: usize = 4;
+
 /// Runners stored within the engine.
 type Runners = IndexMap<String, Runner>;
 
+/// The minimum number of idempotency keys held by an engine before the keys
+/// of dead tasks are pruned.
+const MIN_KEYS_PRUNED: usize = 64;
+
+/// The handles of submitted tasks with an idempotency key.
+///
+/// The keys of dead tasks (see [`WeakTaskHandle::is_dead()`]) are pruned
+/// whenever the number of keys doubles, so that the keys do not accumulate
+/// over the life of the engine.
+#[derive(Debug, Default)]
+struct Keys {
+    /// The handles (by the name of the backend and the key).
+    handles: HashMap<(String, String), WeakTaskHandle>,
+
+    /// The number of handles at which the keys are next pruned.
+    prune_at: usize,
+}
+
+impl Keys {
+    /// Gets a new handle to the task submitted to a backend with a key (if
+    /// the task may still be used).
+    fn get(&mut self, backend: &str, key: &str) -> Option<TaskHandle> {
+        let id = (backend.to_owned(), key.to_owned());
+
+        if self.handles.get(&id)?.is_dead() {
+            self.handles.remove(&id);
+            return None;
+        }
+
+        self.handles.get(&id).map(WeakTaskHandle::upgrade)
+    }
+
+    /// Inserts the handle of a task submitted to a backend with a key.
+    fn insert(&mut self, backend: &str, key: &str, handle: WeakTaskHandle) {
+        if self.handles.len() >= self.prune_at {
+            self.handles.retain(|_, handle| !handle.is_dead());
+            self.prune_at = (self.handles.len() * 2).max(MIN_KEYS_PRUNED);
+        }
+
+        self.handles
+            .insert((backend.to_owned(), key.to_owned()), handle);
+    }
+}
+
 /// A workflow execution engine.
 #[derive(Debug, Default)]
 pub struct Engine {
     /// The task runner(s).
     runners: Runners,
+
+    /// The metrics for the engine.
+    metrics: EngineMetrics,
+
+    /// The events of the engine.
+    events: Events,
+
+    /// The handles of submitted tasks with an idempotency key.
+    keys: Mutex<Keys>,
+
+    /// Whether or not submitted tasks are detached from their handles (i.e.,
+    /// are not canceled when their handles are dropped).
+    detach: bool,
+
+    /// The cancellation of the run.
+    cancellation: Arc<cancellation::State>,
 }
 
 impl Engine {
     /// Adds a [`Backend`] to the engine.
     pub async fn with(mut self, config: Config) -> Result<Self> {
+        let schedule = config.schedule().clone();
+        let queue_order = config.queue_order();
+        let grace_period = config.termination_grace_period();
         let (name, kind, max_tasks, defaults) = config.into_parts();
-        let runner = Runner::initialize(kind, max_tasks, defaults).await?;
-        self.runners.insert(name, runner);
+        let mut runner =
+            Runner::initialize(&name, kind, max_tasks, defaults, self.events.clone()).await?;
+        runner.set_schedule(schedule);
+        runner.set_queue_order(queue_order);
+
+        if let Some(seconds) = grace_period {
+            runner.set_grace_period(Duration::from_secs(seconds));
+        }
+
+        self.insert(name, runner, max_tasks);
         Ok(self)
     }
 
+    /// Adds an already initialized [`Backend`] to the engine.
+    ///
+    /// This is useful for backends that are not configurable through a
+    /// [`Config`] (e.g., a custom backend implemented by an embedder).
+    pub fn with_backend(
+        mut self,
+        name: impl Into<String>,
+        backend: Arc<dyn Backend>,
+        max_tasks: usize,
+    ) -> Self {
+        let name = name.into();
+        let runner = Runner::with_backend(
+            &name,
+            backend,
+            max_tasks,
+            Arc::new(Counters::default()),
+            self.events.clone(),
+        );
+        self.insert(name, runner, max_tasks);
+        self
+    }
+
+    /// Adds a webhook that receives every [`Event`] subsequently emitted by the
+    /// engine (see the [`webhook`](events::webhook) module).
+    ///
+    /// # Panics
+    ///
+    /// If this is not called from within a [`tokio`] runtime.
+    pub fn with_webhook(self, config: crankshaft_config::webhook::Config) -> Self {
+        Webhook::new(config).spawn(self.events.subscribe());
+        self
+    }
+
+    /// Sets a handler that is called directly with every [`Event`]
+    /// subsequently emitted by the engine.
+    ///
+    /// This is an alternative to [subscribing](Engine::subscribe) for
+    /// embedders with a single consumer of events: a handler never lags and
+    /// events are not cloned for it (unless there are also subscribers). The
+    /// handler is called from within the task that emits the event, so it
+    /// must not block.
+    ///
+    /// # Panics
+    ///
+    /// If a handler was already set.
+    pub fn with_event_handler(self, handler: impl Fn(Event) + Send + Sync + 'static) -> Self {
+        assert!(
+            self.events.set_handler(handler),
+            "an event handler was already set for the engine"
+        );
+        self
+    }
+
+    /// Sets whether or not tasks are canceled when every handle to them has
+    /// been dropped (which is the default).
+    ///
+    /// When disabled, every task submitted through the engine is
+    /// [detached](TaskHandle::detach). Child tasks submitted through a
+    /// [`Spawner`](service::runner::Spawner) are not affected.
+    pub fn with_cancel_on_drop(mut self, enabled: bool) -> Self {
+        self.detach = !enabled;
+        self
+    }
+
+    /// Adds a [`SubmitInterceptor`] to a backend within the engine.
+    ///
+    /// Every task subsequently submitted to the backend is passed through the
+    /// interceptor (after any previously added interceptors) before it reaches
+    /// the backend.
+    ///
+    /// # Panics
+    ///
+    /// If the backend does not exist within the engine.
+    pub fn with_interceptor(
+        mut self,
+        name: impl AsRef<str>,
+        interceptor: Arc<dyn SubmitInterceptor>,
+    ) -> Self {
+        let name = name.as_ref();
+
+        self.runners
+            .get_mut(name)
+            .unwrap_or_else(|| panic!("backend not found: {name}"))
+            .add_interceptor(interceptor);
+
+        self
+    }
+
+    /// Inserts an initialized runner into the engine.
+    fn insert(&mut self, name: String, mut runner: Runner, capacity: usize) {
+        runner.link(self.cancellation.clone());
+        self.metrics.register(name.clone(), runner.counters());
+        self.metrics.register_queue(name.clone(), runner.queue());
+        self.events.send(Event::BackendInitialized {
+            name: name.clone(),
+            kind: runner.kind().to_owned(),
+            capacity,
+            reports_resource_usage: runner.reports_resource_usage(),
+        });
+        self.runners.insert(name, runner);
+    }
+
+    /// Gets a [`Canceler`] through which the run can be canceled (e.g., from
+    /// a signal handler while the engine is running).
+    ///
+    /// See the [`cancellation`](service::runner::cancellation) module for how
+    /// the tasks of a canceled run are torn down.
+    pub fn canceler(&self) -> Canceler {
+        Canceler::new(self.cancellation.clone())
+    }
+
     /// Gets the names of the runners.
     pub fn runners(&self) -> impl Iterator<Item = &str> {
         self.runners.keys().map(|key| key.as_ref())
     }
 
+    /// Gets a handle to the metrics of the engine.
+    ///
+    /// The handle remains valid (and continues to be updated) after the engine
+    /// is run.
+    pub fn metrics(&self) -> EngineMetrics {
+        self.metrics.clone()
+    }
+
+    /// Gets the unique id of the run of the engine.
+    ///
+    /// The id is available to every execution as the `CRANKSHAFT_RUN_ID`
+    /// environment variable (see [`RUN_ID_ENV`](service::runner::RUN_ID_ENV)).
+    pub fn run_id(&self) -> &str {
+        self.events.run_id()
+    }
+
+    /// Subscribes to the [`Event`]s emitted by the engine.
+    ///
+    /// Only events emitted after subscribing are received.
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.events.subscribe()
+    }
+
+    /// Gets a [`Tracker`] of the current state of each task within the engine.
+    ///
+    /// Tracking starts the first time this is called, so only tasks submitted
+    /// afterwards are tracked. To track every task, call this before
+    /// submitting any tasks.
+    pub fn tracker(&self) -> Tracker {
+        self.events.tracker()
+    }
+
+    /// Sets an annotation of a task (e.g., to mark the task as flagged for
+    /// review), which is broadcast as an [`Event::TaskAnnotated`].
+    ///
+    /// Annotations can be set until the task finishes, and setting an
+    /// annotation that was already set replaces its value. The current
+    /// annotations of each task are available through the [`Tracker`] (see
+    /// [`Engine::tracker()`]).
+    ///
+    /// Returns `false` (without broadcasting an event) if no task with the
+    /// provided id was submitted to the engine or if the task already
+    /// finished.
+    pub fn annotate(
+        &self,
+        task_id: TaskId,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> bool {
+        let Some(backend) = self.events.backend(task_id) else {
+            return false;
+        };
+
+        self.events.send(Event::TaskAnnotated {
+            task_id,
+            backend: backend.to_string(),
+            key: key.into(),
+            value: value.into(),
+        });
+
+        true
+    }
+
     /// Submits a [`Task`] to be executed.
     ///
-    /// A [`Handle`] is returned, which contains a channel that can be awaited
-    /// for the result of the job.
+    /// A [`TaskHandle`] is returned, which can be awaited for the result of
+    /// the task. Unless the engine was configured otherwise
+    /// (see [`Engine::with_cancel_on_drop()`]), the task is canceled once
+    /// every handle to it has been dropped.
+    ///
+    /// If the task has an [idempotency key](Task::idempotency_key) that was
+    /// already used by a task submitted to the same backend of this engine,
+    /// the task is not run again. Instead, a new handle to the existing task
+    /// is returned. Keys are released once their task is canceled, fails to
+    /// run, or finishes after every handle to it was dropped, so that the
+    /// task may be submitted again (e.g., to retry it after a network error).
     pub fn submit(&self, name: impl AsRef<str>, task: Task) -> TaskHandle {
         let name = name.as_ref();
+
+        let mut keys = self.keys.lock().unwrap();
+
+        if let Some(key) = task.idempotency_key() {
+            if let Some(existing) = keys.get(name, key) {
+                debug!(
+                    "task with idempotency key `{key}` was already submitted to the `{name}` \
+                     backend; returning the existing task"
+                );
+                return existing;
+            }
+        }
+
         let backend = self
             .runners
             .get(name)
@@ -73,7 +377,219 @@ impl Engine {
             name
         );
 
-        backend.submit(task)
+        let key = task.idempotency_key().map(ToOwned::to_owned);
+        let mut handle = backend.submit(task);
+
+        if self.detach {
+            handle = handle.detach();
+        }
+
+        if let Some(key) = key {
+            keys.insert(name, &key, handle.downgrade());
+        }
+
+        handle
+    }
+
+    /// Checks the [preconditions](task::input::Preconditions) of the inputs of
+    /// a [`Task`] and submits it to be executed if they are all satisfied.
+    ///
+    /// Otherwise, the task is not submitted and every input that failed its
+    /// preconditions is returned. See [`Engine::submit()`] for the details of
+    /// submission.
+    pub async fn submit_verified(
+        &self,
+        name: impl AsRef<str>,
+        task: Task,
+    ) -> std::result::Result<TaskHandle, Vec<task::input::precondition::Error>> {
+        task.check_preconditions().await?;
+        Ok(self.submit(name, task))
+    }
+
+    /// Submits a batch of [`Task`]s to be executed.
+    ///
+    /// Once the failures within the batch reach the threshold of the provided
+    /// [`AbortPolicy`], every task within the batch that has not yet completed
+    /// is canceled and a [`BatchAborted`](Event::BatchAborted) event is
+    /// emitted.
+    ///
+    /// Unlike [`Engine::submit()`], the [idempotency
+    /// keys](Task::idempotency_key) of the tasks are not considered.
+    pub fn submit_batch(
+        &self,
+        name: impl AsRef<str>,
+        tasks: impl IntoIterator<Item = Task>,
+        policy: AbortPolicy,
+    ) -> Batch {
+        self.submit_batch_inner(name.as_ref(), tasks, policy, None)
+    }
+
+    /// Submits a batch of [`Task`]s to be executed with at most `max` of them
+    /// running at once.
+    ///
+    /// The ceiling is independent of the `max-tasks` of the backend, so that
+    /// a large batch submitted first does not monopolize the capacity of the
+    /// backend (see
+    /// [`Spawner::with_concurrency()`](service::runner::Spawner::with_concurrency)).
+    /// See [`Engine::submit_batch()`] for the details of submission.
+    ///
+    /// # Panics
+    ///
+    /// If `max` is zero.
+    pub fn submit_batch_with_concurrency(
+        &self,
+        name: impl AsRef<str>,
+        tasks: impl IntoIterator<Item = Task>,
+        policy: AbortPolicy,
+        max: usize,
+    ) -> Batch {
+        self.submit_batch_inner(name.as_ref(), tasks, policy, Some(max))
+    }
+
+    /// Submits a batch of [`Task`]s with an optional concurrency ceiling.
+    fn submit_batch_inner(
+        &self,
+        name: &str,
+        tasks: impl IntoIterator<Item = Task>,
+        policy: AbortPolicy,
+        concurrency: Option<usize>,
+    ) -> Batch {
+        let backend = self
+            .runners
+            .get(name)
+            .unwrap_or_else(|| panic!("backend not found: {name}"));
+
+        let tasks = tasks.into_iter().collect::<Vec<_>>();
+        debug!(
+            "submitting batch of {} tasks to the `{name}` backend",
+            tasks.len()
+        );
+
+        let state = Arc::new(batch::State::new(policy, tasks.len(), self.events.clone()));
+        let mut handles = backend.submit_batch(tasks, state.clone(), concurrency);
+
+        if self.detach {
+            handles = handles.into_iter().map(TaskHandle::detach).collect();
+        }
+
+        Batch::new(state, handles)
+    }
+
+    /// Spawns a [group](TaskGroup) of tasks with dependencies between them.
+    ///
+    /// Every task is submitted at once, but each is
+    /// [blocked](Event::TaskBlocked) until all of its dependencies have
+    /// succeeded. If a dependency fails or is canceled, every task that
+    /// (transitively) depends on it fails without running. Canceling the
+    /// returned [`Group`] cancels every task within it.
+    ///
+    /// Unlike [`Engine::submit()`], the [idempotency
+    /// keys](Task::idempotency_key) of the tasks are not considered.
+    ///
+    /// An error is returned (and nothing is submitted) if a dependency refers
+    /// to a task that is not within the group or the dependencies form a
+    /// cycle.
+    ///
+    /// # Panics
+    ///
+    /// If the backend does not exist within the engine.
+    pub fn spawn_group(
+        &self,
+        name: impl AsRef<str>,
+        group: TaskGroup,
+    ) -> std::result::Result<Group, group::Error> {
+        let name = name.as_ref();
+        let backend = self
+            .runners
+            .get(name)
+            .unwrap_or_else(|| panic!("backend not found: {name}"));
+
+        let tasks = group.into_ordered()?;
+        debug!(
+            "spawning group of {} tasks within the `{name}` backend",
+            tasks.len()
+        );
+
+        let token = CancellationToken::new();
+        let mut handles = backend.submit_group(tasks, token.clone());
+
+        if self.detach {
+            handles = handles
+                .into_iter()
+                .map(|(name, handle)| (name, handle.detach()))
+                .collect();
+        }
+
+        Ok(Group::new(token, handles))
+    }
+
+    /// Prefetches images within a backend ahead of the tasks that run in them.
+    ///
+    /// Without prefetching, the first tasks to run in each image serialize
+    /// behind the pull of the image. Duplicate images are only prefetched once
+    /// and at most [`PREFETCH_CONCURRENCY`] images are prefetched at a time.
+    /// Progress is reported through the
+    /// [`ImagePrefetchStarted`](Event::ImagePrefetchStarted) and
+    /// [`ImagePrefetchFinished`](Event::ImagePrefetchFinished) events.
+    ///
+    /// Every image is attempted, even if others fail. If any image fails to be
+    /// prefetched, an error listing the failures is returned.
+    ///
+    /// # Panics
+    ///
+    /// If the backend does not exist within the engine.
+    pub async fn prefetch_images(
+        &self,
+        name: impl AsRef<str>,
+        images: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Result<()> {
+        let name = name.as_ref();
+
+        let backend = self
+            .runners
+            .get(name)
+            .unwrap_or_else(|| panic!("backend not found: {name}"))
+            .backend();
+
+        let images = images.into_iter().map(Into::into).collect::<IndexSet<_>>();
+        debug!(
+            "prefetching {} images within the `{name}` backend",
+            images.len()
+        );
+
+        let failures = stream::iter(images)
+            .map(|image| {
+                self.events.send(Event::ImagePrefetchStarted {
+                    backend: name.to_owned(),
+                    image: image.clone(),
+                });
+
+                backend
+                    .prefetch_image(&image)
+                    .map(move |result| (image, result.err()))
+            })
+            .buffer_unordered(PREFETCH_CONCURRENCY)
+            .filter_map(|(image, error)| {
+                self.events.send(Event::ImagePrefetchFinished {
+                    backend: name.to_owned(),
+                    image: image.clone(),
+                    error: error.clone(),
+                });
+
+                async move { error.map(|error| format!("`{image}`: {error}")) }
+            })
+            .collect::<Vec<_>>()
+            .await;
+
+        if !failures.is_empty() {
+            bail!(
+                "failed to prefetch {} image(s): {}",
+                failures.len(),
+                failures.join("; ")
+            );
+        }
+
+        Ok(())
     }
 
     /// Starts an instrumentation loop.
@@ -94,10 +610,18 @@ impl Engine {
     }
 
     /// Runs all of the tasks scheduled in the engine.
-    pub async fn run(self) {
+    ///
+    /// Once every task has finished, a [`RunReport`] of the usage of each
+    /// backend within the run is returned. If the run was canceled (see
+    /// [`Engine::canceler()`]), a [`RunCanceled`](Event::RunCanceled) event is
+    /// emitted beforehand.
+    pub async fn run(self) -> RunReport {
+        let start = Instant::now();
         let mut futures = FuturesUnordered::new();
+        let mut records = Vec::new();
 
-        for (_, runner) in self.runners {
+        for (name, runner) in self.runners {
+            records.push((name, runner.records()));
             futures.extend(runner.tasks());
         }
 
@@ -120,5 +644,17 @@ impl Engine {
             task_completion_bar.inc(1);
             count += 1;
         }
+
+        if self.cancellation.is_canceled() {
+            let (queued, running) = self.cancellation.counts();
+            self.events.send(Event::RunCanceled { queued, running });
+        }
+
+        let backends = records
+            .into_iter()
+            .map(|(name, records)| BackendReport::new(name, records.take(start)))
+            .collect();
+
+        RunReport::new(self.events.run_id().to_owned(), start.elapsed(), backends)
     }
 }
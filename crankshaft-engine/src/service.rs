@@ -1,6 +1,8 @@
 //! Services for various functionality within the execution engine.
 
+pub mod metrics;
 pub mod name;
+pub mod report;
 pub mod runner;
 
 pub use runner::Runner;
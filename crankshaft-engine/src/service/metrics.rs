@@ -0,0 +1,583 @@
+//! Metrics services.
+//!
+//! Metrics are recorded with atomics so they can be read at any time (and from
+//! any thread) by embedders that already expose their own metrics endpoint.
+//! See [`EngineMetrics::to_prometheus()`] for rendering the metrics in the
+//! Prometheus text exposition format.
+
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use indexmap::IndexMap;
+
+use crate::service::runner::QueueStats;
+use crate::service::runner::queue::Queue;
+
+/// The upper bounds (in seconds) of the buckets within the spawn latency
+/// histogram.
+pub const SPAWN_LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 300.0,
+];
+
+/// The prefix for all metric names when rendered.
+const PREFIX: &str = "crankshaft";
+
+/// A counter or gauge to render: the name, the type, the help text, and an
+/// accessor for the value within a [`Snapshot`].
+type Metric = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&Snapshot) -> u64,
+);
+
+/// A histogram backed by atomics.
+#[derive(Debug)]
+pub struct Histogram {
+    /// The upper bounds (in seconds) of the buckets.
+    bounds: &'static [f64],
+
+    /// The (non-cumulative) number of observations within each bucket.
+    ///
+    /// This has one more entry than `bounds`, as the last entry holds
+    /// observations above the largest bound.
+    counts: Vec<AtomicU64>,
+
+    /// The sum of all observations in microseconds.
+    sum: AtomicU64,
+}
+
+impl Histogram {
+    /// Creates a new [`Histogram`] with the provided bucket bounds (in
+    /// seconds).
+    pub fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            counts: (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect(),
+            sum: AtomicU64::new(0),
+        }
+    }
+
+    /// Records an observation.
+    pub fn observe(&self, value: Duration) {
+        let seconds = value.as_secs_f64();
+        let index = self
+            .bounds
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(self.bounds.len());
+
+        self.counts[index].fetch_add(1, Ordering::Relaxed);
+        self.sum
+            .fetch_add(value.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Takes a snapshot of the histogram.
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        HistogramSnapshot {
+            bounds: self.bounds,
+            counts: self
+                .counts
+                .iter()
+                .map(|count| count.load(Ordering::Relaxed))
+                .collect(),
+            sum: self.sum.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A point-in-time snapshot of a [`Histogram`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistogramSnapshot {
+    /// The upper bounds (in seconds) of the buckets.
+    bounds: &'static [f64],
+
+    /// The (non-cumulative) number of observations within each bucket.
+    counts: Vec<u64>,
+
+    /// The sum of all observations in microseconds.
+    sum: u64,
+}
+
+impl HistogramSnapshot {
+    /// Gets the upper bounds (in seconds) of the buckets.
+    pub fn bounds(&self) -> &[f64] {
+        self.bounds
+    }
+
+    /// Gets the cumulative number of observations less than or equal to each
+    /// bound.
+    ///
+    /// The last entry is the total number of observations (the `+Inf`
+    /// bucket).
+    pub fn cumulative(&self) -> impl Iterator<Item = u64> + '_ {
+        self.counts.iter().scan(0, |total, count| {
+            *total += count;
+            Some(*total)
+        })
+    }
+
+    /// Gets the total number of observations.
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Gets the sum of all observations.
+    pub fn sum(&self) -> Duration {
+        Duration::from_micros(self.sum)
+    }
+
+    /// Merges another snapshot (with the same bucket bounds) into this one.
+    fn merge(&mut self, other: &Self) {
+        for (count, other) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other;
+        }
+
+        self.sum += other.sum;
+    }
+}
+
+/// A guard that decrements a gauge when dropped.
+#[derive(Debug)]
+pub(crate) struct GaugeGuard(Arc<Counters>, fn(&Counters) -> &AtomicU64);
+
+impl Drop for GaugeGuard {
+    fn drop(&mut self) {
+        (self.1)(&self.0).fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Task metrics for a single backend.
+#[derive(Debug)]
+pub struct Counters {
+    /// The number of tasks submitted.
+    spawned: AtomicU64,
+
+    /// The number of tasks that ran to completion.
+    completed: AtomicU64,
+
+    /// The number of completed tasks where at least one execution failed.
+    failed: AtomicU64,
+
+    /// The number of tasks canceled before completion.
+    canceled: AtomicU64,
+
+    /// The number of times tasks were retried.
+    retried: AtomicU64,
+
+    /// The number of submitted tasks waiting to start.
+    queued: AtomicU64,
+
+    /// The number of tasks currently running.
+    running: AtomicU64,
+
+    /// Whether (`1`) or not (`0`) the backend is currently unhealthy.
+    unhealthy: AtomicU64,
+
+    /// The time between submitting a task and the task starting.
+    spawn_latency: Histogram,
+}
+
+impl Default for Counters {
+    fn default() -> Self {
+        Self {
+            spawned: Default::default(),
+            completed: Default::default(),
+            failed: Default::default(),
+            canceled: Default::default(),
+            retried: Default::default(),
+            queued: Default::default(),
+            running: Default::default(),
+            unhealthy: Default::default(),
+            spawn_latency: Histogram::new(SPAWN_LATENCY_BUCKETS),
+        }
+    }
+}
+
+impl Counters {
+    /// Records a submitted task, returning a guard that holds the task within
+    /// the queue until dropped.
+    pub(crate) fn spawned(self: &Arc<Self>) -> GaugeGuard {
+        self.spawned.fetch_add(1, Ordering::Relaxed);
+        self.queued.fetch_add(1, Ordering::Relaxed);
+        GaugeGuard(self.clone(), |counters| &counters.queued)
+    }
+
+    /// Records a started task, returning a guard that holds the task as
+    /// running until dropped.
+    pub(crate) fn started(self: &Arc<Self>, latency: Duration) -> GaugeGuard {
+        self.spawn_latency.observe(latency);
+        self.running.fetch_add(1, Ordering::Relaxed);
+        GaugeGuard(self.clone(), |counters| &counters.running)
+    }
+
+    /// Records a completed task.
+    pub(crate) fn completed(&self, success: bool) {
+        self.completed.fetch_add(1, Ordering::Relaxed);
+
+        if !success {
+            self.failed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Records a canceled task.
+    pub(crate) fn canceled(&self) {
+        self.canceled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a retried task.
+    pub(crate) fn retried(&self) {
+        self.retried.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records whether or not the backend is healthy.
+    pub(crate) fn set_healthy(&self, healthy: bool) {
+        self.unhealthy.store(u64::from(!healthy), Ordering::Relaxed);
+    }
+
+    /// Takes a snapshot of the metrics.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            spawned: self.spawned.load(Ordering::Relaxed),
+            completed: self.completed.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            canceled: self.canceled.load(Ordering::Relaxed),
+            retried: self.retried.load(Ordering::Relaxed),
+            queued: self.queued.load(Ordering::Relaxed),
+            running: self.running.load(Ordering::Relaxed),
+            unhealthy: self.unhealthy.load(Ordering::Relaxed),
+            spawn_latency: self.spawn_latency.snapshot(),
+        }
+    }
+}
+
+/// A point-in-time snapshot of task metrics.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    /// The number of tasks submitted.
+    spawned: u64,
+
+    /// The number of tasks that ran to completion.
+    completed: u64,
+
+    /// The number of completed tasks where at least one execution failed.
+    failed: u64,
+
+    /// The number of tasks canceled before completion.
+    canceled: u64,
+
+    /// The number of times tasks were retried.
+    retried: u64,
+
+    /// The number of submitted tasks waiting to start.
+    queued: u64,
+
+    /// The number of tasks currently running.
+    running: u64,
+
+    /// The number of unhealthy backends.
+    unhealthy: u64,
+
+    /// The time between submitting a task and the task starting.
+    spawn_latency: HistogramSnapshot,
+}
+
+impl Default for Snapshot {
+    fn default() -> Self {
+        Counters::default().snapshot()
+    }
+}
+
+impl Snapshot {
+    /// Gets the number of tasks submitted.
+    pub fn spawned(&self) -> u64 {
+        self.spawned
+    }
+
+    /// Gets the number of tasks that ran to completion.
+    pub fn completed(&self) -> u64 {
+        self.completed
+    }
+
+    /// Gets the number of completed tasks where at least one execution failed.
+    pub fn failed(&self) -> u64 {
+        self.failed
+    }
+
+    /// Gets the number of tasks canceled before completion.
+    pub fn canceled(&self) -> u64 {
+        self.canceled
+    }
+
+    /// Gets the number of times tasks were retried.
+    ///
+    /// A task that was retried more than once is counted once per retry.
+    pub fn retried(&self) -> u64 {
+        self.retried
+    }
+
+    /// Gets the number of submitted tasks waiting to start (the queue depth).
+    pub fn queued(&self) -> u64 {
+        self.queued
+    }
+
+    /// Gets the number of tasks currently running.
+    pub fn running(&self) -> u64 {
+        self.running
+    }
+
+    /// Gets the number of unhealthy backends.
+    ///
+    /// For the snapshot of a single backend, this is `1` if the backend is
+    /// unhealthy and `0` otherwise.
+    pub fn unhealthy(&self) -> u64 {
+        self.unhealthy
+    }
+
+    /// Gets whether or not all of the backends are healthy.
+    pub fn is_healthy(&self) -> bool {
+        self.unhealthy == 0
+    }
+
+    /// Gets the histogram of times between submitting a task and the task
+    /// starting.
+    pub fn spawn_latency(&self) -> &HistogramSnapshot {
+        &self.spawn_latency
+    }
+
+    /// Merges another snapshot into this one.
+    fn merge(&mut self, other: &Self) {
+        self.spawned += other.spawned;
+        self.completed += other.completed;
+        self.failed += other.failed;
+        self.canceled += other.canceled;
+        self.retried += other.retried;
+        self.queued += other.queued;
+        self.running += other.running;
+        self.unhealthy += other.unhealthy;
+        self.spawn_latency.merge(&other.spawn_latency);
+    }
+}
+
+/// A handle to the metrics of an engine.
+///
+/// Handles are cheap to clone and remain valid after the engine is run.
+#[derive(Clone, Debug, Default)]
+pub struct EngineMetrics {
+    /// The metrics for each backend (by name).
+    backends: Arc<RwLock<IndexMap<String, Arc<Counters>>>>,
+
+    /// The queue of each backend (by name).
+    queues: Arc<RwLock<IndexMap<String, Arc<Queue>>>>,
+}
+
+impl EngineMetrics {
+    /// Registers the metrics for a backend.
+    pub(crate) fn register(&self, name: impl Into<String>, counters: Arc<Counters>) {
+        self.backends.write().unwrap().insert(name.into(), counters);
+    }
+
+    /// Registers the queue of a backend.
+    pub(crate) fn register_queue(&self, name: impl Into<String>, queue: Arc<Queue>) {
+        self.queues.write().unwrap().insert(name.into(), queue);
+    }
+
+    /// Takes a snapshot of the queue of a backend (if it exists).
+    pub fn queue(&self, name: &str) -> Option<QueueStats> {
+        self.queues
+            .read()
+            .unwrap()
+            .get(name)
+            .map(|queue| queue.stats())
+    }
+
+    /// Takes a snapshot of the queue of each backend.
+    pub fn queues(&self) -> Vec<(String, QueueStats)> {
+        self.queues
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, queue)| (name.clone(), queue.stats()))
+            .collect()
+    }
+
+    /// Takes a snapshot of the metrics for a backend (if it exists).
+    pub fn backend(&self, name: &str) -> Option<Snapshot> {
+        self.backends
+            .read()
+            .unwrap()
+            .get(name)
+            .map(|counters| counters.snapshot())
+    }
+
+    /// Takes a snapshot of the metrics for each backend.
+    pub fn backends(&self) -> Vec<(String, Snapshot)> {
+        self.backends
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, counters)| (name.clone(), counters.snapshot()))
+            .collect()
+    }
+
+    /// Takes a snapshot of the metrics summed across all backends.
+    pub fn global(&self) -> Snapshot {
+        let mut global = Snapshot::default();
+
+        for (_, snapshot) in self.backends() {
+            global.merge(&snapshot);
+        }
+
+        global
+    }
+
+    /// Renders the metrics for each backend in the Prometheus text exposition
+    /// format.
+    ///
+    /// Each metric is labeled with the name of the backend it belongs to.
+    pub fn to_prometheus(&self) -> String {
+        let backends = self.backends();
+        let mut result = String::new();
+
+        /// Renders a single counter or gauge for all backends.
+        fn render(
+            result: &mut String,
+            backends: &[(String, Snapshot)],
+            name: &str,
+            kind: &str,
+            help: &str,
+            value: fn(&Snapshot) -> u64,
+        ) {
+            // NOTE: writing to a `String` never fails.
+            let _ = writeln!(result, "# HELP {PREFIX}_{name} {help}");
+            let _ = writeln!(result, "# TYPE {PREFIX}_{name} {kind}");
+
+            for (backend, snapshot) in backends {
+                let _ = writeln!(
+                    result,
+                    "{PREFIX}_{name}{{backend=\"{backend}\"}} {}",
+                    value(snapshot)
+                );
+            }
+        }
+
+        #[rustfmt::skip]
+        let metrics: [Metric; 8] = [
+            ("tasks_spawned_total", "counter", "The number of tasks submitted.", Snapshot::spawned),
+            ("tasks_completed_total", "counter", "The number of tasks that ran to completion.", Snapshot::completed),
+            ("tasks_failed_total", "counter", "The number of completed tasks with a failed execution.", Snapshot::failed),
+            ("tasks_canceled_total", "counter", "The number of tasks canceled before completion.", Snapshot::canceled),
+            ("tasks_retried_total", "counter", "The number of times tasks were retried.", Snapshot::retried),
+            ("tasks_queued", "gauge", "The number of submitted tasks waiting to start.", Snapshot::queued),
+            ("tasks_running", "gauge", "The number of tasks currently running.", Snapshot::running),
+            ("backend_unhealthy", "gauge", "Whether (1) or not (0) the backend is unhealthy.", Snapshot::unhealthy),
+        ];
+
+        for (name, kind, help, value) in metrics {
+            render(&mut result, &backends, name, kind, help, value);
+        }
+
+        let name = format!("{PREFIX}_spawn_latency_seconds");
+        let _ = writeln!(
+            result,
+            "# HELP {name} The time between submitting a task and the task starting."
+        );
+        let _ = writeln!(result, "# TYPE {name} histogram");
+
+        for (backend, snapshot) in &backends {
+            let histogram = snapshot.spawn_latency();
+            let bounds = histogram
+                .bounds()
+                .iter()
+                .map(|bound| bound.to_string())
+                .chain(std::iter::once(String::from("+Inf")));
+
+            for (bound, count) in bounds.zip(histogram.cumulative()) {
+                let _ = writeln!(
+                    result,
+                    "{name}_bucket{{backend=\"{backend}\",le=\"{bound}\"}} {count}"
+                );
+            }
+
+            let _ = writeln!(
+                result,
+                "{name}_sum{{backend=\"{backend}\"}} {}",
+                histogram.sum().as_secs_f64()
+            );
+            let _ = writeln!(
+                result,
+                "{name}_count{{backend=\"{backend}\"}} {}",
+                histogram.count()
+            );
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histograms_bucket_observations() {
+        let histogram = Histogram::new(&[0.1, 1.0]);
+        histogram.observe(Duration::from_millis(50));
+        histogram.observe(Duration::from_millis(500));
+        histogram.observe(Duration::from_secs(5));
+
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.cumulative().collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(snapshot.count(), 3);
+        assert_eq!(snapshot.sum(), Duration::from_millis(5550));
+    }
+
+    #[test]
+    fn gauges_are_released_when_guards_drop() {
+        let counters = Arc::new(Counters::default());
+
+        let queued = counters.spawned();
+        assert_eq!(counters.snapshot().queued(), 1);
+
+        drop(queued);
+        let running = counters.started(Duration::from_millis(1));
+        assert_eq!(counters.snapshot().queued(), 0);
+        assert_eq!(counters.snapshot().running(), 1);
+
+        drop(running);
+        counters.completed(false);
+
+        let snapshot = counters.snapshot();
+        assert_eq!(snapshot.running(), 0);
+        assert_eq!(snapshot.completed(), 1);
+        assert_eq!(snapshot.failed(), 1);
+    }
+
+    #[test]
+    fn global_metrics_are_summed_across_backends() {
+        let metrics = EngineMetrics::default();
+
+        for name in ["foo", "bar"] {
+            let counters = Arc::new(Counters::default());
+            drop(counters.spawned());
+            counters.retried();
+            metrics.register(name, counters);
+        }
+
+        assert_eq!(metrics.global().spawned(), 2);
+        assert_eq!(metrics.global().retried(), 2);
+        assert_eq!(metrics.backend("foo").unwrap().spawned(), 1);
+
+        let rendered = metrics.to_prometheus();
+        assert!(rendered.contains("crankshaft_tasks_spawned_total{backend=\"bar\"} 1"));
+        assert!(rendered.contains("crankshaft_tasks_retried_total{backend=\"foo\"} 1"));
+        assert!(
+            rendered
+                .contains("crankshaft_spawn_latency_seconds_bucket{backend=\"foo\",le=\"+Inf\"} 0")
+        );
+    }
+}
@@ -0,0 +1,333 @@
+//! Reports of the usage within a run of the engine.
+//!
+//! Every task records how long it waited for a permit from its backend and
+//! how long it ran. At the end of a run, these records are rolled up into a
+//! [`RunReport`] (see [`Engine::run()`](crate::Engine::run)), which can be
+//! used to summarize the usage of each backend (similar to `sacct` rollups).
+//!
+//! A report can also be exported as a self-contained HTML document (see
+//! [`RunReport::to_html()`]) for sharing the outcome of a run.
+
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::events::TaskId;
+use crate::service::runner::backend::TaskResult;
+
+mod html;
+
+/// The maximum number of lines of the standard error of a failed execution
+/// kept within a [`TaskRecord`].
+const EXCERPT_LINES: usize = 20;
+
+/// The outcome of a task.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// Every execution within the task exited successfully.
+    Succeeded,
+
+    /// At least one execution within the task failed.
+    Failed,
+
+    /// The task was canceled before it ran to completion.
+    Canceled,
+}
+
+/// The usage of a single task.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TaskRecord {
+    /// The id of the task.
+    task_id: TaskId,
+
+    /// The name of the task (if it has one).
+    name: Option<String>,
+
+    /// The time the task waited for a permit from its backend.
+    queued: Duration,
+
+    /// The wall-clock time the task ran for.
+    ///
+    /// This is zero for tasks that were canceled before starting.
+    duration: Duration,
+
+    /// The outcome of the task.
+    outcome: Outcome,
+
+    /// When the task started running relative to the start of the run.
+    ///
+    /// This is only known once the record is taken for a report (and is
+    /// `None` for tasks that were canceled before starting).
+    start: Option<Duration>,
+
+    /// An excerpt describing why the task failed (if it did).
+    failure: Option<String>,
+}
+
+impl TaskRecord {
+    /// Creates a new [`TaskRecord`].
+    pub(crate) fn new(
+        task_id: TaskId,
+        name: Option<String>,
+        queued: Duration,
+        duration: Duration,
+        outcome: Outcome,
+    ) -> Self {
+        Self {
+            task_id,
+            name,
+            queued,
+            duration,
+            outcome,
+            start: None,
+            failure: None,
+        }
+    }
+
+    /// Sets the excerpt describing why the task failed.
+    pub(crate) fn with_failure(mut self, failure: Option<String>) -> Self {
+        self.failure = failure;
+        self
+    }
+
+    /// Gets the id of the task.
+    pub fn task_id(&self) -> TaskId {
+        self.task_id
+    }
+
+    /// Gets the name of the task (if it has one).
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Gets the time the task waited for a permit from its backend.
+    pub fn queued(&self) -> Duration {
+        self.queued
+    }
+
+    /// Gets the wall-clock time the task ran for.
+    ///
+    /// This is zero for tasks that were canceled before starting.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Gets the outcome of the task.
+    pub fn outcome(&self) -> Outcome {
+        self.outcome
+    }
+
+    /// Gets when the task started running relative to the start of the run
+    /// (if it started).
+    pub fn start(&self) -> Option<Duration> {
+        self.start
+    }
+
+    /// Gets an excerpt describing why the task failed (if it did).
+    ///
+    /// For tasks with a failed execution, this is the end of the standard
+    /// error of the first failed execution. For tasks the backend failed to
+    /// run, this is the reason the backend failed.
+    pub fn failure(&self) -> Option<&str> {
+        self.failure.as_deref()
+    }
+}
+
+/// Gets an excerpt of the standard error of the first failed execution within
+/// a task result (if any execution failed).
+pub(crate) fn excerpt(result: &TaskResult) -> Option<String> {
+    let output = result
+        .executions()
+        .iter()
+        .find(|output| !output.status.success())?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let lines = stderr.lines().collect::<Vec<_>>();
+
+    if lines.is_empty() {
+        return Some(format!("execution exited with {}", output.status));
+    }
+
+    Some(lines[lines.len().saturating_sub(EXCERPT_LINES)..].join("\n"))
+}
+
+/// The task records of a backend.
+#[derive(Debug, Default)]
+pub(crate) struct Records(Mutex<Vec<(TaskRecord, Option<Instant>)>>);
+
+impl Records {
+    /// Adds a task record along with when the task started running (if it
+    /// started).
+    pub(crate) fn push(&self, record: TaskRecord, started: Option<Instant>) {
+        self.0.lock().unwrap().push((record, started));
+    }
+
+    /// Takes all of the task records recorded so far.
+    ///
+    /// The start of each task is made relative to the start of the run.
+    pub(crate) fn take(&self, run: Instant) -> Vec<TaskRecord> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+            .into_iter()
+            .map(|(mut record, started)| {
+                record.start = started.map(|started| started.saturating_duration_since(run));
+                record
+            })
+            .collect()
+    }
+}
+
+/// A report of the usage of a single backend within a run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BackendReport {
+    /// The name of the backend.
+    name: String,
+
+    /// The records of the tasks run by the backend.
+    tasks: Vec<TaskRecord>,
+}
+
+impl BackendReport {
+    /// Creates a new [`BackendReport`].
+    pub(crate) fn new(name: String, tasks: Vec<TaskRecord>) -> Self {
+        Self { name, tasks }
+    }
+
+    /// Gets the name of the backend.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the records of the tasks run by the backend.
+    pub fn tasks(&self) -> &[TaskRecord] {
+        &self.tasks
+    }
+
+    /// Gets the number of tasks with a particular outcome.
+    pub fn count(&self, outcome: Outcome) -> usize {
+        self.tasks
+            .iter()
+            .filter(|task| task.outcome == outcome)
+            .count()
+    }
+
+    /// Gets the total wall-clock time of all tasks run by the backend.
+    pub fn total_duration(&self) -> Duration {
+        self.tasks.iter().map(|task| task.duration).sum()
+    }
+
+    /// Gets the total time that tasks waited for a permit from the backend.
+    pub fn total_queued(&self) -> Duration {
+        self.tasks.iter().map(|task| task.queued).sum()
+    }
+
+    /// Gets the longest wall-clock time of any task run by the backend.
+    pub fn max_duration(&self) -> Duration {
+        self.tasks
+            .iter()
+            .map(|task| task.duration)
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+/// A report of the usage within a run of the engine.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RunReport {
+    /// The unique id of the run.
+    run_id: String,
+
+    /// The wall-clock time of the run.
+    elapsed: Duration,
+
+    /// The reports for each backend.
+    backends: Vec<BackendReport>,
+}
+
+impl RunReport {
+    /// Creates a new [`RunReport`].
+    pub(crate) fn new(run_id: String, elapsed: Duration, backends: Vec<BackendReport>) -> Self {
+        Self {
+            run_id,
+            elapsed,
+            backends,
+        }
+    }
+
+    /// Gets the unique id of the run.
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    /// Gets the wall-clock time of the run.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Gets the reports for each backend.
+    pub fn backends(&self) -> &[BackendReport] {
+        &self.backends
+    }
+
+    /// Gets the report for a particular backend.
+    pub fn backend(&self, name: &str) -> Option<&BackendReport> {
+        self.backends.iter().find(|backend| backend.name == name)
+    }
+
+    /// Gets the total wall-clock time of all tasks across all backends.
+    pub fn total_duration(&self) -> Duration {
+        self.backends
+            .iter()
+            .map(BackendReport::total_duration)
+            .sum()
+    }
+
+    /// Renders the report as a self-contained HTML document.
+    ///
+    /// The document contains a summary of each backend (with a chart of the
+    /// number of tasks running over the course of the run), a table of every
+    /// task, and an excerpt of the failure of each failed task. It has no
+    /// external dependencies, so it can be shared as a single file.
+    pub fn to_html(&self) -> String {
+        html::render(self)
+    }
+
+    /// Writes the report as a self-contained HTML document to a path.
+    ///
+    /// See [`RunReport::to_html()`].
+    pub fn write_html(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_html())
+    }
+}
+
+impl std::fmt::Display for RunReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<20} {:>10} {:>10} {:>10} {:>14} {:>14} {:>14}",
+            "backend", "succeeded", "failed", "canceled", "total (s)", "queued (s)", "max (s)"
+        )?;
+
+        for backend in &self.backends {
+            writeln!(
+                f,
+                "{:<20} {:>10} {:>10} {:>10} {:>14.3} {:>14.3} {:>14.3}",
+                backend.name,
+                backend.count(Outcome::Succeeded),
+                backend.count(Outcome::Failed),
+                backend.count(Outcome::Canceled),
+                backend.total_duration().as_secs_f64(),
+                backend.total_queued().as_secs_f64(),
+                backend.max_duration().as_secs_f64(),
+            )?;
+        }
+
+        write!(
+            f,
+            "elapsed: {:.3}s; total task time: {:.3}s",
+            self.elapsed.as_secs_f64(),
+            self.total_duration().as_secs_f64()
+        )
+    }
+}
@@ -0,0 +1,285 @@
+//! Rendering of run reports as self-contained HTML documents.
+
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use super::BackendReport;
+use super::Outcome;
+use super::RunReport;
+
+/// The width of a utilization chart (in pixels).
+const CHART_WIDTH: f64 = 600.;
+
+/// The height of a utilization chart (in pixels).
+const CHART_HEIGHT: f64 = 80.;
+
+/// The styles of the document.
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { padding: 0.25em 0.75em; border-bottom: 1px solid #ddd; text-align: left; }
+td.number { text-align: right; font-variant-numeric: tabular-nums; }
+.succeeded { color: #1a7f37; }
+.failed { color: #cf222e; }
+.canceled { color: #9a6700; }
+.timeline { position: relative; width: 200px; height: 0.75em; background: #eee; }
+.timeline div { position: absolute; height: 100%; background: #0969da; }
+svg { background: #f6f8fa; }
+pre { background: #f6f8fa; padding: 0.75em; overflow-x: auto; }";
+
+/// Escapes text for use within HTML.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Formats a duration in seconds.
+fn seconds(duration: Duration) -> String {
+    format!("{:.3}s", duration.as_secs_f64())
+}
+
+/// Gets the CSS class and label of an outcome.
+fn outcome(outcome: Outcome) -> &'static str {
+    match outcome {
+        Outcome::Succeeded => "succeeded",
+        Outcome::Failed => "failed",
+        Outcome::Canceled => "canceled",
+    }
+}
+
+/// Gets the fraction of the run that a duration spans (as a percentage).
+fn percent(duration: Duration, elapsed: Duration) -> f64 {
+    if elapsed.is_zero() {
+        return 0.;
+    }
+
+    (duration.as_secs_f64() / elapsed.as_secs_f64() * 100.).min(100.)
+}
+
+/// Renders a chart of the number of tasks of a backend running over the
+/// course of a run as an SVG step chart.
+fn chart(backend: &BackendReport, elapsed: Duration) -> String {
+    // NOTE: tasks that finished are counted before tasks that started at the
+    // same instant so that back-to-back tasks do not overlap.
+    let mut changes = backend
+        .tasks()
+        .iter()
+        .filter_map(|task| Some((task.start()?, task.duration())))
+        .flat_map(|(start, duration)| [(start, 1), (start + duration, -1)])
+        .collect::<Vec<(Duration, i64)>>();
+    changes.sort();
+
+    let mut running = 0;
+    let mut points = vec![(0., 0)];
+
+    for (at, change) in &changes {
+        let x = percent(*at, elapsed) / 100. * CHART_WIDTH;
+        points.push((x, running));
+        running += change;
+        points.push((x, running));
+    }
+
+    points.push((CHART_WIDTH, running));
+
+    let peak = points
+        .iter()
+        .map(|(_, running)| *running)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let path = points
+        .iter()
+        .map(|(x, running)| {
+            let y = CHART_HEIGHT - (*running as f64 / peak as f64 * CHART_HEIGHT);
+            format!("{x:.1},{y:.1}")
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    format!(
+        "<svg width=\"{CHART_WIDTH}\" height=\"{CHART_HEIGHT}\" role=\"img\" aria-label=\"running \
+         tasks over time (peak of {peak})\"><polyline fill=\"none\" stroke=\"#0969da\" \
+         stroke-width=\"1.5\" points=\"{path}\"/></svg>"
+    )
+}
+
+/// Renders a run report as a self-contained HTML document.
+pub(crate) fn render(report: &RunReport) -> String {
+    let elapsed = report.elapsed();
+    let mut html = String::new();
+
+    // NOTE: writing to a [`String`] never fails, so the results of the
+    // `write!()`s below are ignored.
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Crankshaft \
+         run {id}</title>\n<style>\n{STYLE}\n</style>\n</head>\n<body>\n<h1>Crankshaft run \
+         <code>{id}</code></h1>\n<p>Elapsed: {elapsed}; total task time: {total}</p>\n",
+        id = escape(report.run_id()),
+        elapsed = seconds(elapsed),
+        total = seconds(report.total_duration()),
+    );
+
+    html.push_str(
+        "<h2>Backends</h2>\n<table>\n<tr><th>Backend</th><th>Succeeded</th><th>Failed</\
+         th><th>Canceled</th><th>Total</th><th>Queued</th><th>Max</th><th>Running \
+         tasks</th></tr>\n",
+    );
+
+    for backend in report.backends() {
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td class=\"number\">{}</td><td class=\"number\">{}</td><td \
+             class=\"number\">{}</td><td class=\"number\">{}</td><td class=\"number\">{}</td><td \
+             class=\"number\">{}</td><td>{}</td></tr>",
+            escape(backend.name()),
+            backend.count(Outcome::Succeeded),
+            backend.count(Outcome::Failed),
+            backend.count(Outcome::Canceled),
+            seconds(backend.total_duration()),
+            seconds(backend.total_queued()),
+            seconds(backend.max_duration()),
+            chart(backend, elapsed),
+        );
+    }
+
+    html.push_str(
+        "</table>\n<h2>Tasks</h2>\n<table>\n<tr><th>Id</th><th>Name</th><th>Backend</\
+         th><th>Outcome</th><th>Queued</th><th>Duration</th><th>Timeline</th></tr>\n",
+    );
+
+    for backend in report.backends() {
+        for task in backend.tasks() {
+            let timeline = match task.start() {
+                Some(start) => format!(
+                    "<div style=\"left: {:.2}%; width: {:.2}%\"></div>",
+                    percent(start, elapsed),
+                    percent(task.duration(), elapsed).max(0.5)
+                ),
+                None => String::new(),
+            };
+
+            let _ = writeln!(
+                html,
+                "<tr><td class=\"number\">{}</td><td>{}</td><td>{}</td><td \
+                 class=\"{outcome}\">{outcome}</td><td class=\"number\">{}</td><td \
+                 class=\"number\">{}</td><td><div class=\"timeline\">{timeline}</div></td></tr>",
+                task.task_id(),
+                escape(task.name().unwrap_or_default()),
+                escape(backend.name()),
+                seconds(task.queued()),
+                seconds(task.duration()),
+                outcome = outcome(task.outcome()),
+            );
+        }
+    }
+
+    html.push_str("</table>\n<h2>Failures</h2>\n");
+
+    let mut failures = report
+        .backends()
+        .iter()
+        .flat_map(|backend| backend.tasks())
+        .filter_map(|task| Some((task, task.failure()?)))
+        .peekable();
+
+    if failures.peek().is_none() {
+        html.push_str("<p>No tasks failed.</p>\n");
+    }
+
+    for (task, failure) in failures {
+        let _ = writeln!(
+            html,
+            "<details>\n<summary>Task {}{}</summary>\n<pre>{}</pre>\n</details>",
+            task.task_id(),
+            task.name()
+                .map(|name| format!(" (<code>{}</code>)", escape(name)))
+                .unwrap_or_default(),
+            escape(failure),
+        );
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::report::TaskRecord;
+
+    #[test]
+    fn reports_are_rendered_as_html() {
+        let mut failed = TaskRecord::new(
+            1,
+            Some(String::from("align <sample>")),
+            Duration::from_secs(1),
+            Duration::from_secs(5),
+            Outcome::Failed,
+        )
+        .with_failure(Some(String::from("error: missing index & reference")));
+        failed.start = Some(Duration::from_secs(5));
+
+        let mut succeeded = TaskRecord::new(
+            0,
+            None,
+            Duration::ZERO,
+            Duration::from_secs(10),
+            Outcome::Succeeded,
+        );
+        succeeded.start = Some(Duration::ZERO);
+
+        let report = RunReport::new(
+            String::from("run-1"),
+            Duration::from_secs(10),
+            vec![BackendReport::new(
+                String::from("docker"),
+                vec![succeeded, failed],
+            )],
+        );
+
+        let html = report.to_html();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<code>run-1</code>"));
+        assert!(html.contains("align &lt;sample&gt;"));
+        assert!(html.contains("<pre>error: missing index &amp; reference</pre>"));
+        assert!(html.contains("left: 50.00%; width: 50.00%"));
+
+        // Two tasks overlap halfway through the run.
+        assert!(html.contains("peak of 2"));
+    }
+
+    #[test]
+    fn charts_count_running_tasks() {
+        let task = |start, duration| {
+            let mut task = TaskRecord::new(
+                0,
+                None,
+                Duration::ZERO,
+                Duration::from_secs(duration),
+                Outcome::Succeeded,
+            );
+            task.start = Some(Duration::from_secs(start));
+            task
+        };
+
+        // NOTE: back-to-back tasks never run at the same time.
+        let backend = BackendReport::new(String::from("mock"), vec![task(0, 5), task(5, 5)]);
+        assert!(chart(&backend, Duration::from_secs(10)).contains("peak of 1"));
+
+        let backend = BackendReport::new(String::from("mock"), vec![task(0, 6), task(5, 5)]);
+        assert!(chart(&backend, Duration::from_secs(10)).contains("peak of 2"));
+    }
+}
@@ -1,124 +1,1108 @@
 //! Task runner services.
 
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::IntoFuture;
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::sync::Weak;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::SystemTime;
 
 use crankshaft_config::backend::Defaults;
 use crankshaft_config::backend::Kind;
+use crankshaft_config::backend::QueueOrder;
+use crankshaft_config::backend::Schedule;
+use futures::FutureExt as _;
 use futures::future::BoxFuture;
 use futures::future::join_all;
 use futures::stream::FuturesUnordered;
+use indexmap::IndexMap;
 use tokio::sync::Semaphore;
 use tokio::sync::oneshot::Receiver;
+use tokio::sync::oneshot::Sender;
+use tokio::sync::watch;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+use tracing::error;
 use tracing::trace;
+use tracing::warn;
 
 pub mod backend;
+pub mod batch;
+pub mod cancellation;
+pub mod group;
+pub mod interceptor;
+pub(crate) mod queue;
+mod retry;
+mod schedule;
+pub mod set;
 
 pub use backend::Backend;
+pub use batch::AbortPolicy;
+pub use batch::Batch;
+pub use cancellation::Canceler;
+pub use group::Group;
+pub use group::TaskGroup;
+pub use interceptor::SubmitInterceptor;
+pub use queue::QueueStats;
+pub use set::TaskSet;
 
 use crate::Result;
 use crate::Task;
+use crate::events::DEFAULT_TERMINATION_GRACE_PERIOD;
+use crate::events::Event;
+use crate::events::Events;
+use crate::events::QueueReason;
+use crate::events::TaskId;
+use crate::service::metrics::Counters;
 use crate::service::name::GeneratorIterator;
 use crate::service::name::UniqueAlphanumeric;
+use crate::service::report;
+use crate::service::report::Outcome;
+use crate::service::report::Records;
+use crate::service::report::TaskRecord;
+use crate::service::runner::backend::FailureReason;
+use crate::service::runner::backend::Health;
 use crate::service::runner::backend::TaskResult;
+use crate::service::runner::backend::apptainer;
 use crate::service::runner::backend::docker;
 use crate::service::runner::backend::generic;
+#[cfg(feature = "kubernetes")]
+use crate::service::runner::backend::k8s;
+use crate::service::runner::backend::local;
+use crate::service::runner::backend::lsf;
+use crate::service::runner::backend::provenance;
+use crate::service::runner::backend::slurm;
 use crate::service::runner::backend::tes;
+use crate::service::runner::group::Dependencies;
+use crate::service::runner::group::Dependency;
+use crate::service::runner::queue::Permit;
+use crate::service::runner::queue::Queue;
+use crate::task::RetryPolicy;
 
 /// The size of the name buffer.
 const NAME_BUFFER_LEN: usize = 4096;
 
+/// The time (beyond its grace period) that a terminating task is waited on
+/// before it is abandoned.
+///
+/// This bounds how long a backend may take to forcibly kill a task once the
+/// grace period has elapsed.
+const TERMINATION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The environment variable containing the id of a task within each of its
+/// executions.
+pub const TASK_ID_ENV: &str = "CRANKSHAFT_TASK_ID";
+
+/// The environment variable containing the unique id of the run of the engine
+/// within each execution.
+pub const RUN_ID_ENV: &str = "CRANKSHAFT_RUN_ID";
+
+/// The environment variable containing the name of the backend that a task
+/// was submitted to within each of its executions.
+pub const BACKEND_ENV: &str = "CRANKSHAFT_BACKEND";
+
+/// Cancels a task once every handle to it has been dropped (unless the task
+/// was [detached](TaskHandle::detach)).
+#[derive(Debug)]
+struct Guard {
+    /// The cancellation token for the task.
+    token: CancellationToken,
+
+    /// Whether or not the task is canceled when the guard is dropped.
+    armed: AtomicBool,
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        if self.armed.load(Ordering::Relaxed) {
+            self.token.cancel();
+        }
+    }
+}
+
 /// A submitted task handle.
+///
+/// By default, a task is canceled once every handle to it has been dropped,
+/// which prevents containers or jobs from leaking when the calling code
+/// returns early (e.g., because of an error) without canceling its tasks. Use
+/// [`TaskHandle::detach()`] for tasks that should run to completion
+/// regardless.
+///
+/// The handle is awaited for the result of the task, which keeps the handle
+/// (and thus the task) alive until the result is received.
 #[derive(Debug)]
 pub struct TaskHandle {
+    /// The id of the task.
+    id: TaskId,
+
     /// A callback that is executed when a task is completed.
-    pub callback: Receiver<TaskResult>,
+    ///
+    /// If the task is canceled before it completes (or its backend fails to
+    /// run it), the sending half of this channel is dropped without
+    /// a value being sent.
+    callback: Receiver<TaskResult>,
+
+    /// The callbacks of all handles to the task.
+    callbacks: Arc<Mutex<Callbacks>>,
+
+    /// The cancellation token for the task.
+    token: CancellationToken,
+
+    /// A spawner for child tasks linked to this task.
+    spawner: Spawner,
+
+    /// The guard that cancels the task once every handle to it has been
+    /// dropped.
+    guard: Option<Arc<Guard>>,
 }
 
-/// A generic task runner.
+impl TaskHandle {
+    /// Gets the id of the task.
+    ///
+    /// This is the id that is used to refer to the task within
+    /// [`Event`](crate::events::Event)s.
+    pub fn id(&self) -> TaskId {
+        self.id
+    }
+
+    /// Cancels the task.
+    ///
+    /// Any child tasks submitted through this task's [`Spawner`] (and,
+    /// transitively, their children) are canceled as well.
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// Detaches the task from its handles.
+    ///
+    /// A detached task is no longer canceled when its handles are dropped: it
+    /// runs to completion unless it is explicitly [canceled](Self::cancel).
+    /// This applies to every handle to the task.
+    pub fn detach(self) -> Self {
+        if let Some(guard) = &self.guard {
+            guard.armed.store(false, Ordering::Relaxed);
+        }
+
+        self
+    }
+
+    /// Gets whether or not the task has been canceled.
+    pub fn is_canceled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Gets a [`Spawner`] for submitting child tasks linked to this task.
+    pub fn spawner(&self) -> Spawner {
+        self.spawner.clone()
+    }
+
+    /// Creates a weak reference to the task that does not keep it from being
+    /// canceled when every handle to it has been dropped.
+    pub(crate) fn downgrade(&self) -> WeakTaskHandle {
+        WeakTaskHandle {
+            id: self.id,
+            callbacks: self.callbacks.clone(),
+            token: self.token.clone(),
+            spawner: self.spawner.clone(),
+            guard: self.guard.as_ref().map(Arc::downgrade),
+        }
+    }
+}
+
+impl IntoFuture for TaskHandle {
+    type IntoFuture = BoxFuture<'static, Option<TaskResult>>;
+    type Output = Option<TaskResult>;
+
+    /// Waits for the result of the task.
+    ///
+    /// [`None`] is returned if the task was canceled before it completed (or
+    /// its backend failed to run it).
+    fn into_future(self) -> Self::IntoFuture {
+        // NOTE: the handle as a whole is moved into the future (rather than
+        // only its callback) so that the task is not canceled while its result
+        // is awaited.
+        async move {
+            let mut handle = self;
+            (&mut handle.callback).await.ok()
+        }
+        .boxed()
+    }
+}
+
+/// A weak reference to a submitted task (see [`TaskHandle::downgrade()`]).
 #[derive(Debug)]
-pub struct Runner {
+pub(crate) struct WeakTaskHandle {
+    /// The id of the task.
+    id: TaskId,
+
+    /// The callbacks of all handles to the task.
+    callbacks: Arc<Mutex<Callbacks>>,
+
+    /// The cancellation token for the task.
+    token: CancellationToken,
+
+    /// A spawner for child tasks linked to the task.
+    spawner: Spawner,
+
+    /// The guard of the task (if it still exists).
+    guard: Option<Weak<Guard>>,
+}
+
+impl WeakTaskHandle {
+    /// Creates a new handle to the task with its own callback.
+    ///
+    /// If every other handle to the task has already been dropped, the new
+    /// handle does not cancel the task when it is dropped.
+    pub(crate) fn upgrade(&self) -> TaskHandle {
+        TaskHandle {
+            id: self.id,
+            callback: self.callbacks.lock().unwrap().subscribe(),
+            callbacks: self.callbacks.clone(),
+            token: self.token.clone(),
+            spawner: self.spawner.clone(),
+            guard: self.guard.as_ref().and_then(Weak::upgrade),
+        }
+    }
+
+    /// Gets whether or not the task will never produce a result (i.e., it was
+    /// canceled or its backend failed to run it).
+    pub(crate) fn is_abandoned(&self) -> bool {
+        let callbacks = self.callbacks.lock().unwrap();
+        callbacks.result.is_none() && (callbacks.finished || self.token.is_cancelled())
+    }
+
+    /// Gets whether or not the task is of no further use (i.e., it was
+    /// abandoned or it finished after every handle to it was dropped).
+    pub(crate) fn is_dead(&self) -> bool {
+        let dropped = self
+            .guard
+            .as_ref()
+            .is_none_or(|guard| guard.strong_count() == 0);
+
+        self.is_abandoned() || (dropped && self.callbacks.lock().unwrap().finished)
+    }
+}
+
+/// The callbacks awaiting the result of a task.
+#[derive(Debug, Default)]
+struct Callbacks {
+    /// The senders for each callback still awaiting the result.
+    senders: Vec<Sender<TaskResult>>,
+
+    /// Whether or not the task has finished (either by completing or by being
+    /// canceled).
+    finished: bool,
+
+    /// The result of the task (if it completed).
+    result: Option<TaskResult>,
+}
+
+impl Callbacks {
+    /// Creates a new callback for the result of the task.
+    fn subscribe(&mut self) -> Receiver<TaskResult> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+
+        match (&self.result, self.finished) {
+            (Some(result), _) => {
+                let _ = tx.send(result.clone());
+            }
+            // NOTE: the task was canceled, so the sender is dropped without a
+            // value being sent.
+            (None, true) => {}
+            (None, false) => self.senders.push(tx),
+        }
+
+        rx
+    }
+
+    /// Finishes the task with a result (or [`None`] if it was canceled or its
+    /// backend failed to run it).
+    fn finish(&mut self, result: Option<TaskResult>) {
+        self.finished = true;
+
+        for tx in self.senders.drain(..) {
+            if let Some(result) = &result {
+                // NOTE: if the send does not succeed, that is almost certainly
+                // because the receiver was dropped. That is a relatively
+                // standard practice if you don't specifically _want_ to keep a
+                // handle to the returned result, so we ignore any errors
+                // related to that.
+                let _ = tx.send(result.clone());
+            }
+        }
+
+        self.result = result;
+    }
+}
+
+/// Gets the message of a panic from its payload.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| String::from("unknown panic"))
+}
+
+/// Waits until a task may run on a backend (i.e., until the schedule of the
+/// backend accepts submissions and the task is started from the queue of the
+/// backend).
+///
+/// If the task has to wait on the schedule, `queued` is called with the
+/// expected delay.
+async fn admit(
+    queue: &Arc<Queue>,
+    task: &Task,
+    schedule: &Schedule,
+    mut queued: impl FnMut(Duration),
+) -> std::result::Result<Permit, FailureReason> {
+    loop {
+        match schedule::delay(schedule) {
+            Some(delay) if delay.is_zero() => {}
+            Some(delay) => {
+                queued(delay);
+                tokio::time::sleep(delay).await;
+                continue;
+            }
+            None => {
+                return Err(FailureReason::Rejected(String::from(
+                    "the schedule of the backend never accepts submissions",
+                )));
+            }
+        }
+
+        let permit = queue.acquire(task.priority(), task.group()).await;
+
+        // NOTE: the schedule may have closed while the task was waiting within
+        // the queue.
+        if schedule::delay(schedule).is_some_and(|delay| delay.is_zero()) {
+            return Ok(permit);
+        }
+    }
+}
+
+/// A handle for submitting tasks to a backend.
+///
+/// Every task submitted through a [`Spawner`] is linked to the spawner's
+/// parent: when the parent is canceled, all of the tasks submitted through the
+/// spawner are canceled too. A [`Spawner`] for a particular task can be
+/// retrieved with [`TaskHandle::spawner()`], which allows the controller code
+/// of a running task to submit child tasks to the same backend.
+///
+/// Unlike tasks submitted through a [`Runner`] (which are driven when the
+/// runner is run), tasks submitted through a [`Spawner`] are spawned onto the
+/// current [`tokio`] runtime immediately.
+#[derive(Clone, Debug)]
+pub struct Spawner {
     /// The task runner itself.
     backend: Arc<dyn Backend>,
 
-    /// The task lock.
-    lock: Arc<tokio::sync::Semaphore>,
+    /// The name of the backend within the engine.
+    name: Arc<str>,
 
-    /// The list of submitted tasks.
-    pub tasks: FuturesUnordered<BoxFuture<'static, TaskResult>>,
+    /// The hash of the configuration of the backend (if the backend was
+    /// initialized from a configuration).
+    config_hash: Option<Arc<str>>,
+
+    /// The queue of the tasks waiting for the backend to have free capacity.
+    queue: Arc<Queue>,
+
+    /// The windows during which the backend accepts submissions.
+    schedule: Arc<Schedule>,
+
+    /// The policy for retrying tasks that do not have a retry policy of their
+    /// own (if one exists).
+    retry_policy: Option<Arc<RetryPolicy>>,
 
     /// The unique name generator for tasks without names being sent to backends
     /// that may need names.
     name_generator: Arc<Mutex<GeneratorIterator<UniqueAlphanumeric>>>,
+
+    /// The cancellation token of the parent that submitted tasks are linked to.
+    parent: CancellationToken,
+
+    /// The metrics for the backend.
+    counters: Arc<Counters>,
+
+    /// The events of the engine.
+    events: Events,
+
+    /// The usage records of the tasks run by the backend.
+    records: Arc<Records>,
+
+    /// The interceptors applied to tasks before they reach the backend.
+    interceptors: Arc<Vec<Arc<dyn SubmitInterceptor>>>,
+
+    /// The cancellation of the run that submitted tasks belong to.
+    run: Arc<cancellation::State>,
+
+    /// The ceiling on the number of submitted tasks that run at once (if one
+    /// was set with [`Spawner::with_concurrency()`]).
+    ceiling: Option<Arc<Semaphore>>,
+
+    /// The time a canceled task that is running is given to shut down
+    /// gracefully.
+    grace_period: Duration,
+}
+
+impl Spawner {
+    /// Creates a [`Spawner`] whose tasks run at most `max` at once.
+    ///
+    /// The ceiling applies to every task submitted through the returned
+    /// spawner (and through the spawners of their handles), independent of
+    /// the `max-tasks` of the backend: a run that submits many tasks first
+    /// no longer monopolizes the capacity of the backend. Tasks wait on the
+    /// ceiling before they enter the queue of the backend, so tasks held back
+    /// by the ceiling never occupy the capacity of the backend.
+    ///
+    /// # Panics
+    ///
+    /// If `max` is zero.
+    pub fn with_concurrency(&self, max: usize) -> Self {
+        assert!(max > 0, "the concurrency ceiling of a run must be positive");
+
+        Self {
+            ceiling: Some(Arc::new(Semaphore::new(max))),
+            ..self.clone()
+        }
+    }
+
+    /// Submits a task to be executed by the backend.
+    ///
+    /// # Panics
+    ///
+    /// If this is not called from within a [`tokio`] runtime.
+    pub fn submit(&self, task: Task) -> TaskHandle {
+        let (handle, future) = self.prepare(task);
+        tokio::spawn(future);
+        handle
+    }
+
+    /// Prepares a task for execution within the backend.
+    ///
+    /// The returned future runs the task to completion (returning the result)
+    /// or until the task is canceled (returning [`None`]).
+    ///
+    /// If the backend fails to run the task (including by panicking, which is
+    /// caught rather than unwinding through every other task driven alongside
+    /// it), only that task fails (also returning [`None`]).
+    fn prepare(&self, task: Task) -> (TaskHandle, BoxFuture<'static, Option<TaskResult>>) {
+        self.prepare_with(task, None)
+    }
+
+    /// Prepares a task for execution within the backend once its
+    /// dependencies (if any) have succeeded.
+    ///
+    /// The task is blocked (and does not enter the queue of the backend)
+    /// until every dependency has succeeded. If any dependency fails, the
+    /// task fails without running.
+    fn prepare_with(
+        &self,
+        mut task: Task,
+        dependencies: Option<Dependencies>,
+    ) -> (TaskHandle, BoxFuture<'static, Option<TaskResult>>) {
+        trace!(backend = ?self.backend, task = ?task);
+
+        let callbacks = Arc::new(Mutex::new(Callbacks::default()));
+        let rx = callbacks.lock().unwrap().subscribe();
+        let backend = self.backend.clone();
+        let backend_name = self.name.clone();
+        let config_hash = self.config_hash.clone();
+        let queue = self.queue.clone();
+        let schedule = self.schedule.clone();
+        let retry_policy = self.retry_policy.clone();
+        let token = self.parent.child_token();
+        let run = self.run.clone();
+        let ceiling = self.ceiling.clone();
+        let counters = self.counters.clone();
+        let records = self.records.clone();
+        let submitted = Instant::now();
+        let started = OnceLock::new();
+        let queued = counters.spawned();
+        let id = self.events.next_id();
+        let events = self
+            .events
+            .task(id, self.name.clone())
+            .with_grace_period(self.grace_period);
+
+        if backend.default_name() == "docker" && task.name().is_none() {
+            let mut generator = self.name_generator.lock().unwrap();
+            // SAFETY: this generator should _never_ run out of entries.
+            task.override_name(generator.next().unwrap());
+        }
+
+        let task = interceptor::apply(
+            self.interceptors
+                .iter()
+                .map(|interceptor| interceptor.as_ref()),
+            task,
+        )
+        .map(|mut task| {
+            // NOTE: the variables are set after the interceptors have run so
+            // that in-task tooling can always correlate itself with the
+            // events of the task.
+            let task_id = id.to_string();
+            task.extend_env(&[
+                (TASK_ID_ENV, &task_id),
+                (RUN_ID_ENV, events.run_id()),
+                (BACKEND_ENV, events.backend()),
+            ]);
+            task
+        })
+        .map_err(FailureReason::Rejected)
+        .and_then(|task| backend.check(&task).map(|_| task));
+
+        let name = match &task {
+            Ok(task) => task.name().map(ToOwned::to_owned),
+            Err(_) => None,
+        };
+
+        events.send(Event::TaskCreated {
+            task_id: id,
+            backend: self.name.to_string(),
+            name: name.clone(),
+            limits: task.as_ref().ok().and_then(|task| backend.limits(task)),
+        });
+
+        let handle = TaskHandle {
+            id,
+            callback: rx,
+            callbacks: callbacks.clone(),
+            token: token.clone(),
+            spawner: Self {
+                parent: token.clone(),
+                ..self.clone()
+            },
+            guard: Some(Arc::new(Guard {
+                token: token.clone(),
+                armed: AtomicBool::new(true),
+            })),
+        };
+
+        let fun = async move {
+            // Records the usage of the task once it has finished.
+            let record = |started: Option<&Instant>, outcome, failure| {
+                let (queued, duration) = match started {
+                    Some(started) => (*started - submitted, started.elapsed()),
+                    None => (submitted.elapsed(), Default::default()),
+                };
+
+                records.push(
+                    TaskRecord::new(id, name, queued, duration, outcome).with_failure(failure),
+                    started.copied(),
+                );
+            };
+
+            let attempts = async {
+                // NOTE: rejected tasks (and tasks that fail the check of the
+                // backend) fail without acquiring a permit.
+                let task = task?;
+
+                if let Some(dependencies) = dependencies {
+                    events.send(Event::TaskBlocked {
+                        task_id: id,
+                        backend: backend_name.to_string(),
+                        dependencies: dependencies.ids(),
+                    });
+
+                    dependencies
+                        .wait()
+                        .await
+                        .map_err(FailureReason::DependencyFailed)?;
+
+                    events.send(Event::TaskUnblocked {
+                        task_id: id,
+                        backend: backend_name.to_string(),
+                    });
+                }
+
+                let policy = task.retry_policy().or(retry_policy.as_deref()).cloned();
+                let mut task = Some(task);
+                let mut queued = Some(queued);
+                let mut running = None;
+                let mut notified = false;
+                let mut attempt = 1;
+
+                loop {
+                    // SAFETY: the task is only taken for the last attempt,
+                    // after which the loop is always exited.
+                    let current = task.as_ref().unwrap();
+
+                    // NOTE: the ceiling of the run is acquired before the
+                    // queue of the backend is entered (the semaphores are
+                    // nested) so that tasks held back by their run never
+                    // occupy the capacity of the backend.
+                    let ceiling = match &ceiling {
+                        Some(ceiling) => {
+                            let permit = match ceiling.clone().try_acquire_owned() {
+                                Ok(permit) => permit,
+                                Err(_) => {
+                                    events.queued(QueueReason::RunConcurrencyLimit, None);
+
+                                    // SAFETY: the semaphore is never closed.
+                                    ceiling.clone().acquire_owned().await.unwrap()
+                                }
+                            };
+
+                            Some(permit)
+                        }
+                        None => None,
+                    };
+
+                    let permit = admit(&queue, current, &schedule, |delay| {
+                        if !notified {
+                            notified = true;
+                            events.queued(
+                                QueueReason::BackendWindowClosed,
+                                Some(SystemTime::now() + delay),
+                            );
+                        }
+                    })
+                    .await?;
+
+                    drop(queued.take());
+                    running.get_or_insert_with(|| counters.started(submitted.elapsed()));
+
+                    if started.set(Instant::now()).is_ok() && !backend.reports_started() {
+                        events.send(Event::TaskStarted {
+                            task_id: id,
+                            backend: backend_name.to_string(),
+                        });
+                    }
+
+                    // NOTE: the task is only cloned for attempts that may be
+                    // retried, so the last attempt runs the task itself.
+                    let last = policy
+                        .as_ref()
+                        .is_none_or(|policy| attempt >= policy.max_attempts());
+
+                    let attempted = if last {
+                        // SAFETY: the task was checked to be present above.
+                        task.take().unwrap()
+                    } else {
+                        current.clone()
+                    };
+
+                    let result = AssertUnwindSafe(backend.run(attempted, events.clone()))
+                        .catch_unwind()
+                        .await
+                        .unwrap_or_else(|payload| {
+                            Err(FailureReason::Panicked(panic_message(payload.as_ref())))
+                        });
+
+                    // NOTE: the permits are released while waiting to
+                    // retry so that other tasks may run in the meantime.
+                    drop(permit);
+                    drop(ceiling);
+
+                    // NOTE: the attempt of a terminated task ended because
+                    // it was terminated, so it is never retried.
+                    if events.is_terminated() {
+                        break result;
+                    }
+
+                    let retry = policy
+                        .as_ref()
+                        .filter(|policy| attempt < policy.max_attempts())
+                        .and_then(|policy| Some((policy, retry::reason(policy, &result)?)));
+
+                    let Some((policy, reason)) = retry else {
+                        break result;
+                    };
+
+                    let delay = retry::backoff(policy, attempt);
+                    attempt += 1;
+
+                    debug!("retrying task {id} (attempt {attempt}) in {delay:?}: {reason}");
+                    counters.retried();
+                    events.send(Event::TaskRetried {
+                        task_id: id,
+                        backend: backend_name.to_string(),
+                        attempt,
+                        reason,
+                    });
+
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        // NOTE: a task that is terminated while waiting to
+                        // be retried is not run again.
+                        _ = events.terminated() => {
+                            let reason = String::from("the task was terminated");
+                            return Err(FailureReason::Request(reason));
+                        }
+                    }
+                }
+            };
+            tokio::pin!(attempts);
+
+            tokio::select! {
+                // NOTE: cancellation is polled first so that tasks that are
+                // canceled while waiting on a permit are never started.
+                biased;
+                _ = async {
+                    tokio::select! {
+                        _ = token.cancelled() => {}
+                        _ = run.cancelled(&started) => {}
+                    }
+                } => {
+                    // NOTE: a running task is given the chance to shut down
+                    // gracefully (if its backend supports it) before it is
+                    // abandoned.
+                    if started.get().is_some() && backend.terminates_gracefully() {
+                        events.terminate();
+
+                        let timeout = events.grace_period() + TERMINATION_TIMEOUT;
+
+                        if tokio::time::timeout(timeout, &mut attempts).await.is_err() {
+                            warn!("task {id} did not terminate within {timeout:?}");
+                        }
+                    }
+
+                    debug!("task was canceled before completion");
+                    counters.canceled();
+                    queue.finished();
+                    record(started.get(), Outcome::Canceled, None);
+                    events.send(Event::TaskCanceled {
+                        task_id: id,
+                        backend: backend_name.to_string(),
+                    });
+                    callbacks.lock().unwrap().finish(None);
+                    None
+                }
+                result = &mut attempts => {
+                    let mut result = match result {
+                        Ok(result) => result,
+                        Err(reason) => {
+                            error!("backend failed to run task {id}: {reason}");
+
+                            counters.completed(false);
+                            queue.finished();
+                            record(started.get(), Outcome::Failed, Some(reason.to_string()));
+                            events.send(Event::TaskFailed {
+                                task_id: id,
+                                backend: backend_name.to_string(),
+                                reason,
+                            });
+                            callbacks.lock().unwrap().finish(None);
+                            return None;
+                        }
+                    };
+
+                    result
+                        .provenance
+                        .set_backend(&backend_name, config_hash.as_deref());
+
+                    counters.completed(result.is_success());
+                    queue.finished();
+                    let outcome = if result.is_success() {
+                        Outcome::Succeeded
+                    } else {
+                        Outcome::Failed
+                    };
+
+                    record(started.get(), outcome, report::excerpt(&result));
+                    events.send(Event::TaskCompleted {
+                        task_id: id,
+                        backend: backend_name.to_string(),
+                        success: result.is_success(),
+                    });
+
+                    callbacks.lock().unwrap().finish(Some(result.clone()));
+                    Some(result)
+                }
+            }
+        };
+
+        (handle, fun.boxed())
+    }
+}
+
+/// A generic task runner.
+#[derive(Debug)]
+pub struct Runner {
+    /// The spawner through which tasks are submitted to the backend.
+    spawner: Spawner,
+
+    /// The list of submitted tasks.
+    pub tasks: FuturesUnordered<BoxFuture<'static, Option<TaskResult>>>,
 }
 
 impl Runner {
     /// Creates a new [`Runner`].
     pub async fn initialize(
+        name: &str,
         config: Kind,
         max_tasks: usize,
         defaults: Option<Defaults>,
+        events: Events,
     ) -> Result<Self> {
+        let counters = Arc::new(Counters::default());
+        let health = Health::new(name, counters.clone(), events.clone());
+        let config_hash = provenance::hash(&(&config, max_tasks, &defaults));
+        let retry_policy = defaults
+            .as_ref()
+            .and_then(Defaults::retry)
+            .cloned()
+            .map(Arc::new);
+
         let backend = match config {
             Kind::Docker(config) => {
-                let backend = docker::Backend::initialize_default_with(config)?;
+                let backend = docker::Backend::initialize(config).await?;
                 Arc::new(backend) as Arc<dyn Backend>
             }
             Kind::Generic(config) => {
                 let backend = generic::Backend::initialize(config, defaults).await?;
                 Arc::new(backend)
             }
-            Kind::TES(config) => Arc::new(tes::Backend::initialize(config)),
+            Kind::TES(config) => Arc::new(tes::Backend::initialize(config, defaults, health)),
+            #[cfg(feature = "kubernetes")]
+            Kind::Kubernetes(config) => Arc::new(k8s::Backend::initialize(config).await?),
+            #[cfg(not(feature = "kubernetes"))]
+            Kind::Kubernetes(_) => eyre::bail!(
+                "the `{name}` backend requires the `kubernetes` feature of `crankshaft-engine`"
+            ),
+            Kind::Slurm(config) => Arc::new(slurm::Backend::initialize(config, defaults).await?),
+            Kind::Lsf(config) => Arc::new(lsf::Backend::initialize(config, defaults).await?),
+            Kind::Local(config) => Arc::new(local::Backend::new(config)),
+            Kind::Apptainer(config) => Arc::new(apptainer::Backend::initialize(config).await?),
         };
 
+        let mut runner = Self::with_backend(name, backend, max_tasks, counters, events);
+        runner.spawner.config_hash = Some(config_hash.into());
+        runner.spawner.retry_policy = retry_policy;
+        Ok(runner)
+    }
+
+    /// Sets the windows during which the backend accepts submissions for the
+    /// tasks subsequently submitted to the backend.
+    pub(crate) fn set_schedule(&mut self, schedule: Schedule) {
+        self.spawner.schedule = Arc::new(schedule);
+    }
+
+    /// Sets the time a canceled task that is running is given to shut down
+    /// gracefully for the tasks subsequently submitted to the backend.
+    pub(crate) fn set_grace_period(&mut self, grace_period: Duration) {
+        self.spawner.grace_period = grace_period;
+    }
+
+    /// Sets the order in which the tasks subsequently submitted to the backend
+    /// are started once the backend has free capacity.
+    pub(crate) fn set_queue_order(&mut self, order: QueueOrder) {
+        self.spawner.queue = Arc::new(self.spawner.queue.with_order(order));
+    }
+
+    /// Creates a new [`Runner`] for an already initialized [`Backend`].
+    pub(crate) fn with_backend(
+        name: &str,
+        backend: Arc<dyn Backend>,
+        max_tasks: usize,
+        counters: Arc<Counters>,
+        events: Events,
+    ) -> Self {
         let generator = UniqueAlphanumeric::default_with_expected_generations(max_tasks);
 
-        Ok(Self {
-            backend,
-            lock: Arc::new(Semaphore::new(max_tasks)),
+        Self {
+            spawner: Spawner {
+                backend,
+                name: name.into(),
+                config_hash: None,
+                queue: Arc::new(Queue::new(max_tasks, QueueOrder::default())),
+                schedule: Default::default(),
+                retry_policy: None,
+                name_generator: Arc::new(Mutex::new(GeneratorIterator::new(
+                    generator,
+                    NAME_BUFFER_LEN,
+                ))),
+                parent: CancellationToken::new(),
+                counters,
+                events,
+                records: Default::default(),
+                interceptors: Default::default(),
+                run: Default::default(),
+                ceiling: None,
+                grace_period: DEFAULT_TERMINATION_GRACE_PERIOD,
+            },
             tasks: Default::default(),
-            name_generator: Arc::new(Mutex::new(GeneratorIterator::new(
-                generator,
-                NAME_BUFFER_LEN,
-            ))),
-        })
+        }
+    }
+
+    /// Links the tasks subsequently submitted to the backend to the
+    /// cancellation of a run.
+    pub(crate) fn link(&mut self, run: Arc<cancellation::State>) {
+        self.spawner.run = run;
+    }
+
+    /// Adds an interceptor that is applied to every task subsequently
+    /// submitted to the backend.
+    ///
+    /// Interceptors are applied in the order they are added.
+    pub fn add_interceptor(&mut self, interceptor: Arc<dyn SubmitInterceptor>) {
+        Arc::make_mut(&mut self.spawner.interceptors).push(interceptor);
     }
 
     /// Submits a task to be executed by the backend.
-    pub fn submit(&self, mut task: Task) -> TaskHandle {
-        trace!(backend = ?self.backend, task = ?task);
+    pub fn submit(&self, task: Task) -> TaskHandle {
+        let (handle, future) = self.spawner.prepare(task);
+        self.tasks.push(future);
+        handle
+    }
 
-        let (tx, rx) = tokio::sync::oneshot::channel();
-        let backend = self.backend.clone();
-        let lock = self.lock.clone();
+    /// Submits a batch of tasks to be executed by the backend.
+    ///
+    /// The tasks are linked to the batch: if the batch is aborted (or
+    /// canceled), every task within it that has not yet completed is canceled.
+    ///
+    /// If a concurrency ceiling is provided, at most that many tasks within
+    /// the batch run at once (see [`Spawner::with_concurrency()`]).
+    pub(crate) fn submit_batch(
+        &self,
+        tasks: impl IntoIterator<Item = Task>,
+        state: Arc<batch::State>,
+        concurrency: Option<usize>,
+    ) -> Vec<TaskHandle> {
+        let mut spawner = Spawner {
+            parent: state.token().clone(),
+            ..self.spawner.clone()
+        };
 
-        if backend.default_name() == "docker" && task.name().is_none() {
-            let mut generator = self.name_generator.lock().unwrap();
-            // SAFETY: this generator should _never_ run out of entries.
-            task.override_name(generator.next().unwrap());
+        if let Some(max) = concurrency {
+            spawner = spawner.with_concurrency(max);
         }
 
-        let fun = async move {
-            let _permit = lock.acquire().await;
+        tasks
+            .into_iter()
+            .map(|task| {
+                let (handle, future) = spawner.prepare(task);
+                let state = state.clone();
 
-            let result = backend.clone().run(task).await;
+                self.tasks.push(
+                    async move {
+                        let result = future.await;
+                        state.record(result.as_ref());
+                        result
+                    }
+                    .boxed(),
+                );
 
-            // NOTE: if the send does not succeed, that is almost certainly
-            // because the receiver was dropped. That is a relatively standard
-            // practice if you don't specifically _want_ to keep a handle to the
-            // returned result, so we ignore any errors related to that.
-            let _ = tx.send(result.clone());
-            drop(_permit);
+                handle
+            })
+            .collect()
+    }
 
-            result
+    /// Submits a group of tasks (ordered such that every task comes after its
+    /// dependencies) to be executed by the backend.
+    ///
+    /// The tasks are linked to the provided cancellation token, and each task
+    /// is blocked until its dependencies have succeeded.
+    pub(crate) fn submit_group(
+        &self,
+        tasks: Vec<(String, Task, Vec<String>)>,
+        token: CancellationToken,
+    ) -> IndexMap<String, TaskHandle> {
+        let spawner = Spawner {
+            parent: token,
+            ..self.spawner.clone()
         };
 
-        self.tasks.push(Box::pin(fun));
-        TaskHandle { callback: rx }
+        let mut outcomes = HashMap::<String, (TaskId, watch::Sender<Option<Outcome>>)>::new();
+        let run = self.spawner.run.clone();
+
+        tasks
+            .into_iter()
+            .map(|(name, task, dependencies)| {
+                // SAFETY: every task comes after its dependencies, so the
+                // outcome of every dependency was already created.
+                let dependencies = (!dependencies.is_empty()).then(|| {
+                    Dependencies::new(
+                        dependencies
+                            .into_iter()
+                            .map(|dependency| {
+                                let (id, outcome) = &outcomes[&dependency];
+                                Dependency::new(dependency, *id, outcome.subscribe())
+                            })
+                            .collect(),
+                    )
+                });
+
+                let (handle, future) = spawner.prepare_with(task, dependencies);
+                let (sender, _) = watch::channel(None);
+                let token = handle.token.clone();
+                let run = run.clone();
+
+                outcomes.insert(name.clone(), (handle.id(), sender.clone()));
+
+                self.tasks.push(
+                    async move {
+                        let result = future.await;
+                        let outcome = match &result {
+                            Some(result) if result.is_success() => Outcome::Succeeded,
+                            // NOTE: canceled tasks finish without a result,
+                            // just like tasks that their backend failed to run.
+                            // A task canceled along with its run keeps its own
+                            // token, so the run is checked as well.
+                            None if token.is_cancelled() || run.is_canceled() => Outcome::Canceled,
+                            _ => Outcome::Failed,
+                        };
+
+                        sender.send_replace(Some(outcome));
+                        result
+                    }
+                    .boxed(),
+                );
+
+                (name, handle)
+            })
+            .collect()
+    }
+
+    /// Gets the backend.
+    pub(crate) fn backend(&self) -> &Arc<dyn Backend> {
+        &self.spawner.backend
+    }
+
+    /// Gets the kind of the backend (i.e., its [default
+    /// name](Backend::default_name)).
+    pub fn kind(&self) -> &'static str {
+        self.spawner.backend.default_name()
+    }
+
+    /// Gets whether or not the backend reports the resources used by its
+    /// running tasks.
+    pub fn reports_resource_usage(&self) -> bool {
+        self.spawner.backend.reports_resource_usage()
+    }
+
+    /// Gets the usage records of the tasks run by the backend.
+    pub(crate) fn records(&self) -> Arc<Records> {
+        self.spawner.records.clone()
+    }
+
+    /// Gets the metrics for the runner.
+    pub fn counters(&self) -> Arc<Counters> {
+        self.spawner.counters.clone()
+    }
+
+    /// Takes a snapshot of the queue of the runner.
+    pub fn queue_stats(&self) -> QueueStats {
+        self.spawner.queue.stats()
+    }
+
+    /// Gets the queue of the runner.
+    pub(crate) fn queue(&self) -> Arc<Queue> {
+        self.spawner.queue.clone()
     }
 
     /// Gets the tasks from the runner.
-    pub fn tasks(self) -> impl Iterator<Item = BoxFuture<'static, TaskResult>> {
+    ///
+    /// Each task resolves to [`None`] if it was canceled before completion.
+    pub fn tasks(self) -> impl Iterator<Item = BoxFuture<'static, Option<TaskResult>>> {
         self.tasks.into_iter()
     }
 
@@ -127,3 +1111,464 @@ impl Runner {
         join_all(self.tasks).await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[cfg(unix)]
+    use std::os::unix::process::ExitStatusExt as _;
+    #[cfg(windows)]
+    use std::os::windows::process::ExitStatusExt as _;
+    use std::process::ExitStatus;
+    use std::process::Output;
+    use std::sync::atomic::AtomicI32;
+
+    use crankshaft_config::backend::schedule::TimeOfDay;
+    use crankshaft_config::backend::schedule::Window;
+    use nonempty::NonEmpty;
+
+    use super::*;
+    use crate::Engine;
+    use crate::service::runner::backend::provenance::CRANKSHAFT_VERSION;
+    use crate::simulation::MockBackend;
+    use crate::simulation::runtime;
+    use crate::task::Execution;
+
+    fn result() -> TaskResult {
+        TaskResult {
+            executions: NonEmpty::new(Output {
+                status: ExitStatus::from_raw(0),
+                stdout: Vec::new(),
+                stderr: Vec::new(),
+            }),
+            provenance: Default::default(),
+            accounting: Default::default(),
+        }
+    }
+
+    /// Creates an execution that echoes `hello`.
+    fn echo() -> Execution {
+        Execution::builder()
+            .image("ubuntu")
+            .args(["echo", "hello"])
+            .try_build()
+            .unwrap()
+    }
+
+    /// Creates a task with a name.
+    fn task(name: &str) -> Task {
+        Task::builder()
+            .name(name)
+            .extend_executions([echo()])
+            .try_build()
+            .unwrap()
+    }
+
+    /// Creates an engine with a `mock` backend that runs ten tasks at once.
+    fn engine(backend: MockBackend) -> Engine {
+        Engine::default().with_backend("mock", Arc::new(backend), 10)
+    }
+
+    #[test]
+    fn callbacks_receive_the_result() {
+        let mut callbacks = Callbacks::default();
+        let mut before = callbacks.subscribe();
+
+        callbacks.finish(Some(result()));
+        let mut after = callbacks.subscribe();
+
+        assert!(before.try_recv().unwrap().is_success());
+        assert!(after.try_recv().unwrap().is_success());
+    }
+
+    #[test]
+    fn callbacks_are_closed_when_canceled() {
+        let mut callbacks = Callbacks::default();
+        let mut before = callbacks.subscribe();
+
+        callbacks.finish(None);
+        let mut after = callbacks.subscribe();
+
+        assert!(before.try_recv().is_err());
+        assert!(after.try_recv().is_err());
+    }
+
+    #[test]
+    fn backend_panics_fail_only_their_task() {
+        runtime().unwrap().block_on(async {
+            let engine = engine(MockBackend::new(|task, _| match task.name() {
+                Some("panic") => panic!("something went wrong"),
+                _ => (Duration::from_secs(1), 0),
+            }));
+            let metrics = engine.metrics();
+            let mut events = engine.subscribe();
+
+            let handles = ["panic", "succeed", "succeed"]
+                .into_iter()
+                .map(|name| engine.submit("mock", task(name)))
+                .collect::<Vec<_>>();
+
+            engine.run().await;
+
+            let snapshot = metrics.global();
+            assert_eq!(snapshot.completed(), 3);
+            assert_eq!(snapshot.failed(), 1);
+
+            let mut handles = handles.into_iter();
+            assert!(handles.next().unwrap().await.is_none());
+            for handle in handles {
+                assert!(handle.await.unwrap().is_success());
+            }
+
+            loop {
+                if let Event::TaskFailed {
+                    task_id, reason, ..
+                } = events.recv().await.unwrap()
+                {
+                    assert_eq!(task_id, 0);
+                    assert_eq!(
+                        reason,
+                        FailureReason::Panicked(String::from("something went wrong"))
+                    );
+                    break;
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn dropped_handles_cancel_their_tasks() {
+        runtime().unwrap().block_on(async {
+            let engine = engine(MockBackend::new(|_, _| (Duration::from_secs(1), 0)));
+            let metrics = engine.metrics();
+
+            drop(engine.submit("mock", task("dropped")));
+            drop(engine.submit("mock", task("detached")).detach());
+            let kept = engine.submit("mock", task("kept"));
+
+            // Awaiting a handle keeps it (and thus its task) alive.
+            let awaited = engine.submit("mock", task("awaited")).into_future();
+
+            engine.run().await;
+
+            assert!(kept.await.unwrap().is_success());
+            assert!(awaited.await.unwrap().is_success());
+
+            let snapshot = metrics.global();
+            assert_eq!(snapshot.completed(), 3);
+            assert_eq!(snapshot.canceled(), 1);
+        });
+    }
+
+    #[test]
+    fn idempotency_keys_are_scoped_to_live_tasks() {
+        runtime().unwrap().block_on(async {
+            let backend = Arc::new(MockBackend::new(|_, _| (Duration::from_secs(1), 0)));
+            let engine = Engine::default()
+                .with_backend("mock", backend.clone(), 10)
+                .with_backend("other", backend, 10);
+            let metrics = engine.metrics();
+
+            let task = Task::builder()
+                .idempotency_key("key")
+                .extend_executions([echo()])
+                .try_build()
+                .unwrap();
+
+            let first = engine.submit("mock", task.clone());
+            assert_eq!(engine.submit("mock", task.clone()).id(), first.id());
+
+            // Keys are scoped to the backend the task was submitted to.
+            let other = engine.submit("other", task.clone());
+            assert_ne!(other.id(), first.id());
+
+            // Canceled tasks may be submitted again with the same key.
+            first.cancel();
+            let second = engine.submit("mock", task.clone());
+            assert_ne!(second.id(), first.id());
+            assert_eq!(engine.submit("mock", task).id(), second.id());
+
+            engine.run().await;
+
+            assert!(second.await.unwrap().is_success());
+            assert!(other.await.unwrap().is_success());
+
+            let snapshot = metrics.global();
+            assert_eq!(snapshot.completed(), 2);
+            assert_eq!(snapshot.canceled(), 1);
+        });
+    }
+
+    #[test]
+    fn results_record_their_provenance() {
+        runtime().unwrap().block_on(async {
+            let engine = engine(MockBackend::new(|_, _| (Duration::from_secs(1), 0)));
+
+            let handle = engine.submit("mock", task("foo"));
+            engine.run().await;
+
+            let result = handle.await.unwrap();
+            let provenance = result.provenance();
+            assert_eq!(provenance.backend(), "mock");
+            assert_eq!(provenance.version(), CRANKSHAFT_VERSION);
+            assert_eq!(provenance.config_hash(), None);
+        });
+    }
+
+    #[test]
+    fn executions_receive_tracing_variables() {
+        runtime().unwrap().block_on(async {
+            let env = Arc::new(Mutex::new(Vec::new()));
+            let engine = engine(MockBackend::new({
+                let env = env.clone();
+                move |task, _| {
+                    let execution = task.executions().next().unwrap();
+                    env.lock().unwrap().extend(execution.env().unwrap().clone());
+                    (Duration::from_secs(1), 0)
+                }
+            }));
+            let run_id = engine.run_id().to_owned();
+
+            let task = Task::builder()
+                .extend_executions([Execution::builder()
+                    .image("ubuntu")
+                    .args(["echo", "hello"])
+                    .env("FOO", "bar")
+                    .env(BACKEND_ENV, "overwritten")
+                    .try_build()
+                    .unwrap()])
+                .try_build()
+                .unwrap();
+
+            let handle = engine.submit("mock", task);
+            let id = handle.id;
+            engine.run().await;
+
+            assert_eq!(
+                *env.lock().unwrap(),
+                [
+                    (String::from("FOO"), String::from("bar")),
+                    (String::from(BACKEND_ENV), String::from("mock")),
+                    (String::from(TASK_ID_ENV), id.to_string()),
+                    (String::from(RUN_ID_ENV), run_id),
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn tasks_are_rejected_by_schedules_that_never_open() {
+        runtime().unwrap().block_on(async {
+            let mut engine = engine(MockBackend::new(|_, _| (Duration::from_secs(1), 0)));
+            let midnight = TimeOfDay::new(0, 0).unwrap();
+            engine
+                .runners
+                .get_mut("mock")
+                .unwrap()
+                .set_schedule(Schedule::new([], [Window::new([], midnight, midnight)]));
+
+            let mut events = engine.subscribe();
+            let handle = engine.submit("mock", task("foo"));
+            engine.run().await;
+            assert!(handle.await.is_none());
+
+            let mut rejected = false;
+            while let Ok(event) = events.try_recv() {
+                assert!(!matches!(
+                    event,
+                    Event::TaskQueued { .. } | Event::TaskStarted { .. }
+                ));
+
+                if let Event::TaskFailed { reason, .. } = event {
+                    rejected = matches!(reason, FailureReason::Rejected(_));
+                }
+            }
+
+            assert!(rejected);
+        });
+    }
+
+    #[test]
+    fn failed_tasks_are_retried() {
+        runtime().unwrap().block_on(async {
+            // NOTE: the first two attempts fail.
+            let attempts = Arc::new(AtomicI32::new(0));
+            let backend = MockBackend::new({
+                let attempts = attempts.clone();
+                move |_, _| {
+                    let attempt = attempts.fetch_add(1, Ordering::Relaxed);
+                    (Duration::from_secs(1), (attempt < 2) as i32)
+                }
+            });
+
+            let engine = Engine::default().with_backend("mock", Arc::new(backend), 1);
+            let metrics = engine.metrics();
+            let mut events = engine.subscribe();
+
+            let task = Task::builder()
+                .extend_executions([echo()])
+                .retry_policy(RetryPolicy::builder().on_failure(true).build())
+                .try_build()
+                .unwrap();
+
+            let handle = engine.submit("mock", task);
+            engine.run().await;
+            assert!(handle.await.unwrap().is_success());
+            assert_eq!(attempts.load(Ordering::Relaxed), 3);
+
+            let mut retried = Vec::new();
+            let mut started = 0;
+            while let Ok(event) = events.try_recv() {
+                match event {
+                    Event::TaskRetried { attempt, .. } => retried.push(attempt),
+                    Event::TaskStarted { .. } => started += 1,
+                    _ => {}
+                }
+            }
+
+            assert_eq!(retried, [2, 3]);
+            assert_eq!(started, 1);
+            assert_eq!(metrics.backend("mock").unwrap().retried(), 2);
+        });
+    }
+
+    #[test]
+    fn runs_are_limited_by_their_concurrency_ceiling() {
+        runtime().unwrap().block_on(async {
+            let begin = Instant::now();
+            let started = Arc::new(Mutex::new(Vec::new()));
+            let engine = engine(MockBackend::new({
+                let started = started.clone();
+                move |task, _| {
+                    started
+                        .lock()
+                        .unwrap()
+                        .push((task.name().unwrap().to_owned(), begin.elapsed().as_secs()));
+                    (Duration::from_secs(10), 0)
+                }
+            }));
+            let mut events = engine.subscribe();
+
+            let handles = engine.submit_batch_with_concurrency(
+                "mock",
+                ["a", "b", "c", "d"].map(task),
+                AbortPolicy::Never,
+                2,
+            );
+
+            // NOTE: the task submitted after the batch is not held back by the
+            // ceiling of the batch.
+            let other = engine.submit("mock", task("other"));
+            engine.run().await;
+            drop((handles, other));
+
+            // NOTE: the order in which the tasks of the batch are started is
+            // not defined, so only the times are compared.
+            let started = started.lock().unwrap().clone();
+            let mut batch = started
+                .iter()
+                .filter(|(name, _)| name != "other")
+                .map(|(_, secs)| *secs)
+                .collect::<Vec<_>>();
+            batch.sort();
+
+            assert_eq!(batch, [0, 0, 10, 10]);
+            assert!(started.contains(&(String::from("other"), 0)));
+
+            let mut limited = 0;
+            while let Ok(event) = events.try_recv() {
+                if let Event::TaskQueued {
+                    reason: QueueReason::RunConcurrencyLimit,
+                    ..
+                } = event
+                {
+                    limited += 1;
+                }
+            }
+
+            assert_eq!(limited, 2);
+        });
+    }
+
+    #[test]
+    fn queued_tasks_are_started_by_priority() {
+        runtime().unwrap().block_on(async {
+            let started = Arc::new(Mutex::new(Vec::new()));
+            let backend = MockBackend::new({
+                let started = started.clone();
+                move |task, _| {
+                    started
+                        .lock()
+                        .unwrap()
+                        .push(task.name().unwrap().to_owned());
+                    (Duration::from_secs(1), 0)
+                }
+            });
+
+            let mut runner = Runner::with_backend(
+                "mock",
+                Arc::new(backend),
+                1,
+                Default::default(),
+                Default::default(),
+            );
+            runner.set_queue_order(QueueOrder::Priority);
+
+            // NOTE: the handles are kept so that the tasks are not canceled.
+            let mut handles = Vec::new();
+
+            for (name, priority) in [("low", -1), ("high", 10), ("normal", 0), ("urgent", 100)] {
+                handles.push(
+                    runner.submit(
+                        Task::builder()
+                            .name(name)
+                            .priority(priority)
+                            .extend_executions([echo()])
+                            .try_build()
+                            .unwrap(),
+                    ),
+                );
+            }
+
+            // NOTE: the backend is kept busy until every task is queued.
+            let queue = runner.queue();
+            let permit = queue.acquire(0, None).await;
+
+            tokio::join!(runner.run(), async {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                assert_eq!(queue.stats().queued(), 4);
+                drop(permit);
+            });
+
+            assert_eq!(
+                *started.lock().unwrap(),
+                ["urgent", "high", "normal", "low"]
+            );
+            assert_eq!(queue.stats().finished(), 4);
+            assert_eq!(queue.stats().running(), 0);
+        });
+    }
+
+    #[test]
+    fn images_are_prefetched_once() {
+        runtime().unwrap().block_on(async {
+            let engine = engine(MockBackend::new(|_, _| (Duration::from_secs(1), 0)));
+            let mut events = engine.subscribe();
+
+            engine
+                .prefetch_images("mock", ["ubuntu", "alpine", "ubuntu"])
+                .await
+                .unwrap();
+
+            let mut prefetched = Vec::new();
+            while let Ok(event) = events.try_recv() {
+                if let Event::ImagePrefetchFinished { image, error, .. } = event {
+                    assert_eq!(error, None);
+                    prefetched.push(image);
+                }
+            }
+
+            prefetched.sort();
+            assert_eq!(prefetched, ["alpine", "ubuntu"]);
+        });
+    }
+}
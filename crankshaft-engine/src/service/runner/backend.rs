@@ -1,23 +1,56 @@
 //! Supported backends.
 
+use std::collections::BTreeMap;
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::process::Output;
+use std::sync::Arc;
 
 use async_trait::async_trait;
+use futures::FutureExt as _;
 use futures::future::BoxFuture;
 use nonempty::NonEmpty;
+use serde::Serialize;
+use tracing::warn;
 
 use crate::Task;
+use crate::events::Event;
+use crate::events::Events;
+use crate::events::Limits;
+use crate::events::TaskEvents;
+use crate::service::metrics::Counters;
+use crate::service::runner::backend::provenance::Provenance;
+use crate::task::Execution;
+use crate::task::execution::redirection::Stream;
 
+pub mod apptainer;
 pub mod docker;
 pub mod generic;
+#[cfg(feature = "kubernetes")]
+pub mod k8s;
+pub mod local;
+pub mod lsf;
+#[cfg(any(test, feature = "simulation"))]
+pub mod mock;
+pub mod provenance;
+pub mod slurm;
 pub mod tes;
 
+/// The accounting data reported by a scheduler for a completed job (e.g., the
+/// CPU-hours it was charged or the queue it ran in).
+pub type Accounting = BTreeMap<String, serde_json::Value>;
+
 /// A reply from a backend when a task is completed.
 #[derive(Clone, Debug)]
 pub struct TaskResult {
     /// The results from each execution.
     pub(crate) executions: NonEmpty<Output>,
+
+    /// The provenance of the result.
+    pub(crate) provenance: Provenance,
+
+    /// The accounting data of each execution (by index) that reported it.
+    pub(crate) accounting: HashMap<usize, Accounting>,
 }
 
 impl TaskResult {
@@ -25,6 +58,182 @@ impl TaskResult {
     pub fn executions(&self) -> &NonEmpty<Output> {
         &self.executions
     }
+
+    /// Gets the provenance of the result (i.e., the environment the task was
+    /// run in).
+    pub fn provenance(&self) -> &Provenance {
+        &self.provenance
+    }
+
+    /// Gets the accounting data reported for an execution (if any was
+    /// reported).
+    ///
+    /// Only backends that integrate with a scheduler's accounting (e.g., a
+    /// generic backend with an `accounting` command) report accounting data.
+    pub fn accounting(&self, index: usize) -> Option<&Accounting> {
+        self.accounting.get(&index)
+    }
+
+    /// Gets whether or not every execution exited successfully.
+    pub fn is_success(&self) -> bool {
+        self.executions.iter().all(|output| output.status.success())
+    }
+}
+
+/// The reason a backend failed to run a task to completion.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FailureReason {
+    /// The backend panicked while running the task.
+    Panicked(String),
+
+    /// The task was rejected by a
+    /// [`SubmitInterceptor`](crate::service::runner::SubmitInterceptor) (or
+    /// because the [schedule](crankshaft_config::backend::Schedule) of the
+    /// backend never accepts submissions) before it reached the backend.
+    Rejected(String),
+
+    /// The container of an execution was lost (e.g., because the Docker
+    /// daemon restarted and the container did not survive the restart).
+    ContainerLost(String),
+
+    /// The task requested more resources than the backend allows (e.g., more
+    /// CPU cores than the largest node within a cluster has), so it would
+    /// never be scheduled.
+    ExceedsLimits(String),
+
+    /// The task was preempted by the scheduler of the backend (e.g., to make
+    /// room for a job with a higher priority).
+    Preempted(String),
+
+    /// The task uses a feature that the backend does not support.
+    Unsupported(String),
+
+    /// A request to the service underlying the backend (e.g., the Kubernetes
+    /// API server) failed.
+    Request(String),
+
+    /// A task within a [group](crate::service::runner::group) did not run
+    /// because one of its dependencies failed (or was canceled).
+    DependencyFailed(String),
+
+    /// A job remained held or suspended by its scheduler for longer than the
+    /// configured grace period (and no release command was configured).
+    Suspended(String),
+
+    /// The job id of a submitted job could not be parsed from the output of
+    /// the submit command.
+    SubmissionParseError {
+        /// The standard output of the submit command.
+        stdout: String,
+
+        /// The standard error of the submit command.
+        stderr: String,
+
+        /// The pattern that did not match the standard output.
+        pattern: String,
+    },
+}
+
+impl std::fmt::Display for FailureReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FailureReason::Panicked(message) => write!(f, "backend panicked: {message}"),
+            FailureReason::Rejected(reason) => write!(f, "task was rejected: {reason}"),
+            FailureReason::ContainerLost(reason) => write!(f, "container was lost: {reason}"),
+            FailureReason::ExceedsLimits(reason) => {
+                write!(
+                    f,
+                    "task exceeds the resource limits of the backend: {reason}"
+                )
+            }
+            FailureReason::Preempted(reason) => write!(f, "task was preempted: {reason}"),
+            FailureReason::Unsupported(reason) => write!(f, "task is not supported: {reason}"),
+            FailureReason::Request(reason) => write!(f, "request failed: {reason}"),
+            FailureReason::DependencyFailed(reason) => {
+                write!(f, "a dependency of the task failed: {reason}")
+            }
+            FailureReason::Suspended(state) => {
+                write!(f, "job remained suspended for too long (state: `{state}`)")
+            }
+            FailureReason::SubmissionParseError {
+                stdout, pattern, ..
+            } => write!(
+                f,
+                "could not match the job id pattern `{pattern}` within the standard output of the \
+                 submit command: `{stdout}`"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FailureReason {}
+
+/// Applies the redirections of the output streams of an execution to its
+/// output.
+///
+/// If a redirection fails, the contents of the stream are kept within the
+/// output so that they are not lost.
+pub(crate) async fn redirect(execution: &Execution, mut output: Output) -> Output {
+    for (stream, redirection, contents) in [
+        (Stream::Stdout, execution.stdout(), &mut output.stdout),
+        (Stream::Stderr, execution.stderr(), &mut output.stderr),
+    ] {
+        if let Err(err) = redirection.apply(stream, contents).await {
+            warn!("failed to redirect {stream:?} of execution: {err}");
+        }
+    }
+
+    output
+}
+
+/// A handle through which a backend reports changes to its health.
+///
+/// Health changes are recorded within the metrics of the backend and broadcast
+/// as [`Event`]s.
+#[derive(Clone, Debug)]
+pub struct Health {
+    /// The name of the backend.
+    name: String,
+
+    /// The metrics for the backend.
+    counters: Arc<Counters>,
+
+    /// The events of the engine.
+    events: Events,
+}
+
+impl Health {
+    /// Creates a new [`Health`].
+    pub(crate) fn new(name: impl Into<String>, counters: Arc<Counters>, events: Events) -> Self {
+        Self {
+            name: name.into(),
+            counters,
+            events,
+        }
+    }
+
+    /// Gets the name of the backend.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Reports that the backend became unhealthy.
+    pub fn unhealthy(&self, reason: impl Into<String>) {
+        self.counters.set_healthy(false);
+        self.events.send(Event::BackendUnhealthy {
+            name: self.name.clone(),
+            reason: reason.into(),
+        });
+    }
+
+    /// Reports that the backend became healthy again.
+    pub fn restored(&self) {
+        self.counters.set_healthy(true);
+        self.events.send(Event::BackendRestored {
+            name: self.name.clone(),
+        });
+    }
 }
 
 /// An execution backend.
@@ -33,6 +242,78 @@ pub trait Backend: Debug + Send + Sync + 'static {
     /// Gets the default name for the backend.
     fn default_name(&self) -> &'static str;
 
+    /// Gets the resource limits the backend will constrain a task to (if the
+    /// backend enforces any).
+    ///
+    /// These are reported within the [`TaskCreated`](Event::TaskCreated)
+    /// event of the task.
+    fn limits(&self, _task: &Task) -> Option<Limits> {
+        None
+    }
+
+    /// Checks whether a task can be run by the backend at all.
+    ///
+    /// This is called when the task is submitted, so tasks that fail the
+    /// check (e.g., because they request more resources than the backend
+    /// allows) fail with the returned [`FailureReason`] without ever entering
+    /// the queue of the backend.
+    fn check(&self, _task: &Task) -> std::result::Result<(), FailureReason> {
+        Ok(())
+    }
+
+    /// Gets whether or not the backend reports when a task started itself.
+    ///
+    /// By default, a [`TaskStarted`](Event::TaskStarted) event is sent as soon
+    /// as a task acquires a permit from its backend. Backends that submit
+    /// tasks to a scheduler (where a submitted job may wait in a queue for
+    /// some time) can instead report when the task actually started running
+    /// through [`TaskEvents::started()`].
+    fn reports_started(&self) -> bool {
+        false
+    }
+
+    /// Gets whether or not the backend reports the resources used by its
+    /// running tasks.
+    ///
+    /// Backends that are able to measure the resources used by a task (e.g.,
+    /// through the statistics of its container) report samples of them
+    /// through [`TaskEvents::resource_usage()`] while the task runs.
+    fn reports_resource_usage(&self) -> bool {
+        false
+    }
+
+    /// Gets whether or not the backend terminates canceled tasks gracefully.
+    ///
+    /// By default, a running task that is canceled is simply abandoned by the
+    /// engine. Backends that are able to shut down a task gracefully (e.g., by
+    /// sending `SIGTERM` and then `SIGKILL` once a grace period elapses)
+    /// instead observe [`TaskEvents::terminated()`], and the engine waits for
+    /// them to do so (sending a [`TaskTerminating`](Event::TaskTerminating)
+    /// event beforehand).
+    fn terminates_gracefully(&self) -> bool {
+        false
+    }
+
+    /// Ensures that an image is available to the backend ahead of the tasks
+    /// that run in it (e.g., by pulling it).
+    ///
+    /// By default, nothing is done (which is appropriate for backends that
+    /// do not manage images themselves). See
+    /// [`Engine::prefetch_images()`](crate::Engine::prefetch_images).
+    fn prefetch_image(&self, _image: &str) -> BoxFuture<'static, std::result::Result<(), String>> {
+        futures::future::ok(()).boxed()
+    }
+
     /// Runs a task in a backend.
-    fn run(&self, task: Task) -> BoxFuture<'static, TaskResult>;
+    ///
+    /// Backends report progress within the task (such as the start and finish
+    /// of each execution) through the provided [`TaskEvents`]. If the backend
+    /// is unable to run the task to completion, a [`FailureReason`] is
+    /// returned (and reported within a [`TaskFailed`](Event::TaskFailed)
+    /// event).
+    fn run(
+        &self,
+        task: Task,
+        events: TaskEvents,
+    ) -> BoxFuture<'static, std::result::Result<TaskResult, FailureReason>>;
 }
@@ -0,0 +1,547 @@
+//! An Apptainer (or Singularity) backend.
+//!
+//! Executions are run with `apptainer exec` through the
+//! [driver](super::generic::driver) of generic backends, so containers may be
+//! run locally or on a remote host over SSH (where Docker is often not
+//! available, such as on HPC login or compute nodes).
+//!
+//! Images without a transport (e.g., `ubuntu:24.04`) are pulled from Docker
+//! registries (i.e., as `docker://ubuntu:24.04`), whereas images with a
+//! transport (e.g., `oras://...`) and paths to SIF files are used as is.
+//!
+//! Task inputs, outputs, and shared volumes are bind mounted into each
+//! container:
+//!
+//! * Inputs with `file://` URLs are bound read-only from the host.
+//! * Inputs with `http(s)://` URLs are downloaded (with `curl`) and literal
+//!   inputs are written into a staging directory on the host.
+//! * Outputs with `file://` URLs are bound from the staging directory and moved
+//!   to their destination on the host once every execution has finished.
+//! * Shared volumes are bound from the staging directory.
+//!
+//! As every command runs on the host of the driver, paths within `file://`
+//! URLs refer to paths on that host (e.g., on a shared filesystem when the
+//! driver connects over SSH). The staging directory is removed once the task
+//! finishes (or is canceled).
+
+use std::sync::Arc;
+
+use crankshaft_config::backend::apptainer::Config;
+use futures::FutureExt as _;
+use futures::future::BoxFuture;
+use nonempty::NonEmpty;
+use tracing::debug;
+
+use crate::Result;
+use crate::Task;
+use crate::events::TaskEvents;
+use crate::service::runner::backend::FailureReason;
+use crate::service::runner::backend::TaskResult;
+use crate::service::runner::backend::generic::driver::Cancel;
+use crate::service::runner::backend::generic::driver::Driver;
+use crate::service::runner::backend::generic::driver::Lease;
+use crate::service::runner::backend::generic::driver::quote;
+use crate::service::runner::backend::provenance::ExecutionProvenance;
+use crate::service::runner::backend::provenance::Provenance;
+use crate::service::runner::backend::redirect;
+use crate::task::Execution;
+use crate::task::input::Contents;
+use crate::task::input::Type as InputType;
+use crate::task::output::Type as OutputType;
+
+/// An Apptainer execution backend.
+#[derive(Debug)]
+pub struct Backend {
+    /// The driver.
+    driver: Arc<Driver>,
+
+    /// The inner configuration.
+    config: Config,
+}
+
+impl Backend {
+    /// Attempts to initialize a new Apptainer [`Backend`] with the provided
+    /// configuration for the backend.
+    pub async fn initialize(config: Config) -> Result<Self> {
+        let driver = Driver::initialize(config.driver().clone())
+            .await
+            .map(Arc::new)?;
+
+        Ok(Self { driver, config })
+    }
+}
+
+impl crate::Backend for Backend {
+    fn default_name(&self) -> &'static str {
+        "apptainer"
+    }
+
+    fn run(
+        &self,
+        task: Task,
+        events: TaskEvents,
+    ) -> BoxFuture<'static, std::result::Result<TaskResult, FailureReason>> {
+        run(self, task, events)
+    }
+}
+
+/// Gets the image reference passed to `apptainer exec` for an image.
+fn image(image: &str) -> String {
+    if image.contains("://") || image.ends_with(".sif") {
+        image.to_owned()
+    } else {
+        format!("docker://{image}")
+    }
+}
+
+/// A bind mount of a path on the host into the container.
+#[derive(Debug, PartialEq, Eq)]
+struct Bind {
+    /// The path on the host.
+    host: String,
+
+    /// The path within the container.
+    container: String,
+
+    /// Whether or not the mount is read-only.
+    read_only: bool,
+}
+
+impl Bind {
+    /// Gets the argument that specifies the bind mount.
+    fn arg(&self) -> String {
+        let spec = format!("{}:{}", self.host, self.container);
+
+        if self.read_only {
+            format!("--bind {}", quote(&format!("{spec}:ro")))
+        } else {
+            format!("--bind {}", quote(&spec))
+        }
+    }
+}
+
+/// The staging of the inputs, outputs, and shared volumes of a task.
+#[derive(Debug, Default)]
+struct Staging {
+    /// The commands that stage the inputs, outputs, and shared volumes before
+    /// any execution runs.
+    setup: Vec<String>,
+
+    /// The bind mounts of every execution.
+    binds: Vec<Bind>,
+
+    /// The commands that move the outputs to their destinations after every
+    /// execution has finished.
+    finish: Vec<String>,
+}
+
+/// Plans the staging of a task within a staging directory.
+fn stage(task: &Task, dir: &str) -> std::result::Result<Staging, FailureReason> {
+    let unsupported = FailureReason::Unsupported;
+    let mut staging = Staging::default();
+
+    for (index, input) in task.inputs().into_iter().flatten().enumerate() {
+        let staged = format!("{dir}/input-{index}");
+
+        let host = match (input.contents(), input.r#type()) {
+            (Contents::URL(url), _) if url.scheme() == "file" => url
+                .to_file_path()
+                .map_err(|_| unsupported(format!("input URL `{url}` is not a valid path")))?
+                .display()
+                .to_string(),
+            (Contents::URL(url), InputType::File) if matches!(url.scheme(), "http" | "https") => {
+                staging.setup.push(format!(
+                    "curl -fsSL -o {} {}",
+                    quote(&staged),
+                    quote(url.as_str())
+                ));
+                staged
+            }
+            (Contents::Literal(literal), InputType::File) => {
+                staging
+                    .setup
+                    .push(format!("printf %s {} > {}", quote(literal), quote(&staged)));
+                staged
+            }
+            (Contents::URL(url), _) => {
+                return Err(unsupported(format!(
+                    "the Apptainer backend does not support the input `{url}`"
+                )));
+            }
+            (Contents::Literal(_), InputType::Directory) => {
+                return Err(unsupported(String::from(
+                    "literal inputs cannot be directories",
+                )));
+            }
+        };
+
+        staging.binds.push(Bind {
+            host,
+            container: input.path().to_owned(),
+            read_only: true,
+        });
+    }
+
+    for (index, output) in task.outputs().into_iter().flatten().enumerate() {
+        let staged = format!("{dir}/output-{index}");
+        let target = url::Url::parse(output.url())
+            .ok()
+            .filter(|url| url.scheme() == "file")
+            .and_then(|url| url.to_file_path().ok())
+            .ok_or_else(|| {
+                unsupported(format!(
+                    "the Apptainer backend only supports `file://` output URLs (got `{}`)",
+                    output.url()
+                ))
+            })?;
+
+        staging.setup.push(match output.r#type() {
+            OutputType::File => format!(": > {}", quote(&staged)),
+            OutputType::Directory => format!("mkdir {}", quote(&staged)),
+        });
+
+        let parent = target
+            .parent()
+            .map(|parent| parent.display().to_string())
+            .unwrap_or_else(|| String::from("/"));
+        let target = target.display().to_string();
+
+        staging.finish.push(format!(
+            "mkdir -p {parent} && rm -rf {target} && mv {staged} {target}",
+            parent = quote(&parent),
+            target = quote(&target),
+            staged = quote(&staged)
+        ));
+
+        staging.binds.push(Bind {
+            host: staged,
+            container: output.path().to_owned(),
+            read_only: false,
+        });
+    }
+
+    for (index, volume) in task.shared_volumes().into_iter().flatten().enumerate() {
+        let staged = format!("{dir}/volume-{index}");
+        staging.setup.push(format!("mkdir {}", quote(&staged)));
+        staging.binds.push(Bind {
+            host: staged,
+            container: volume.to_owned(),
+            read_only: false,
+        });
+    }
+
+    Ok(staging)
+}
+
+/// Gets the `apptainer exec` command that runs an execution.
+fn exec(config: &Config, execution: &Execution, binds: &[Bind]) -> String {
+    let mut args = vec![quote(config.executable()), String::from("exec")];
+    args.extend(config.extra_args().iter().cloned());
+    args.extend(binds.iter().map(Bind::arg));
+
+    if let Some(workdir) = execution.workdir() {
+        args.push(format!("--pwd {}", quote(workdir)));
+    }
+
+    for (name, value) in execution.env().into_iter().flatten() {
+        args.push(format!("--env {}", quote(&format!("{name}={value}"))));
+    }
+
+    args.push(quote(&image(execution.image())));
+
+    // NOTE: the standard input is redirected within the container, as its
+    // path refers to a path within the container.
+    if let Some(stdin) = execution.stdin() {
+        args.push(String::from("sh -c"));
+        args.push(quote(&format!("exec \"$@\" < {}", quote(stdin))));
+        args.push(String::from("sh"));
+    }
+
+    args.extend(execution.args().iter().map(|arg| quote(arg)));
+    args.join(" ")
+}
+
+/// Runs a [`Task`] in the backend.
+fn run(
+    backend: &Backend,
+    task: Task,
+    events: TaskEvents,
+) -> BoxFuture<'static, std::result::Result<TaskResult, FailureReason>> {
+    let driver = backend.driver.clone();
+    let config = backend.config.clone();
+
+    async move {
+        if task
+            .executions()
+            .any(|execution| execution.user().is_some())
+        {
+            return Err(FailureReason::Unsupported(String::from(
+                "the Apptainer backend does not support running executions as a user",
+            )));
+        }
+
+        // NOTE: the staging directory must be on the same host as the
+        // containers (e.g., on the same host of a pool).
+        let lease = driver
+            .lease()
+            .await
+            .map_err(|err| FailureReason::Request(format!("leasing a host: {err}")))?;
+
+        let needs_staging =
+            task.inputs().is_some() || task.outputs().is_some() || task.shared_volumes().is_some();
+
+        // (1) Staging the inputs, outputs, and shared volumes.
+        let (staging, _cleanup) = if needs_staging {
+            let output = lease
+                .run("mktemp -d")
+                .await
+                .map_err(|err| FailureReason::Request(format!("running `mktemp`: {err}")))?;
+
+            let dir = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+
+            if !output.status.success() || dir.is_empty() {
+                return Err(FailureReason::Request(format!(
+                    "creating a staging directory: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )));
+            }
+
+            debug!("staging task within `{dir}`");
+
+            // NOTE: the staging directory is removed however the task ends.
+            let cleanup = Cancel::new(driver.clone(), format!("rm -rf {}", quote(&dir)));
+            let staging = stage(&task, &dir)?;
+            run_all(&lease, &staging.setup, "staging the task").await?;
+
+            (staging, Some(cleanup))
+        } else {
+            (Staging::default(), None)
+        };
+
+        // (2) Running each execution.
+        let mut outputs = Vec::new();
+        let mut provenance = Provenance::default();
+
+        for (index, execution) in task.executions().enumerate() {
+            events.execution_started(index);
+
+            let output = events
+                .with_heartbeats(lease.run(exec(&config, execution, &staging.binds)))
+                .await
+                .map_err(|err| FailureReason::Request(format!("running the container: {err}")))?;
+
+            events.execution_finished(index, output.status);
+            outputs.push(redirect(execution, output).await);
+
+            provenance.push_execution(ExecutionProvenance::new(
+                image(execution.image()),
+                None,
+                None,
+            ));
+        }
+
+        // (3) Moving the outputs to their destinations.
+        run_all(&lease, &staging.finish, "moving the task outputs").await?;
+
+        let mut outputs = outputs.into_iter();
+
+        // SAFETY: each task _must_ have at least one execution, so at least one
+        // execution result _must_ exist at this stage. Thus, this will always unwrap.
+        let mut executions = NonEmpty::new(outputs.next().unwrap());
+        executions.extend(outputs);
+
+        Ok(TaskResult {
+            executions,
+            provenance,
+            accounting: Default::default(),
+        })
+    }
+    .boxed()
+}
+
+/// Runs a list of commands (as a single command) on a leased host.
+async fn run_all(
+    lease: &Lease<'_>,
+    commands: &[String],
+    action: &str,
+) -> std::result::Result<(), FailureReason> {
+    if commands.is_empty() {
+        return Ok(());
+    }
+
+    let output = lease
+        .run(commands.join(" && "))
+        .await
+        .map_err(|err| FailureReason::Request(format!("{action}: {err}")))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(FailureReason::Request(format!(
+            "{action}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Input;
+    use crate::task::output::Builder as OutputBuilder;
+
+    #[test]
+    fn images_are_translated() {
+        assert_eq!(image("ubuntu:24.04"), "docker://ubuntu:24.04");
+        assert_eq!(image("oras://ghcr.io/foo:1"), "oras://ghcr.io/foo:1");
+        assert_eq!(image("/images/foo.sif"), "/images/foo.sif");
+    }
+
+    #[test]
+    fn exec_commands_are_built() {
+        let execution = Execution::builder()
+            .image("ubuntu")
+            .args(["cat", "hello world"])
+            .working_directory("/work")
+            .env("FOO", "a b")
+            .stdin("/inputs/stdin")
+            .try_build()
+            .unwrap();
+
+        let config = Config::builder().extend_extra_args(["--nv"]).build();
+        let binds = [Bind {
+            host: String::from("/data/in"),
+            container: String::from("/inputs/in"),
+            read_only: true,
+        }];
+
+        let args = shlex::split(&exec(&config, &execution, &binds)).unwrap();
+        assert_eq!(
+            args,
+            [
+                "apptainer",
+                "exec",
+                "--nv",
+                "--bind",
+                "/data/in:/inputs/in:ro",
+                "--pwd",
+                "/work",
+                "--env",
+                "FOO=a b",
+                "docker://ubuntu",
+                "sh",
+                "-c",
+                "exec \"$@\" < /inputs/stdin",
+                "sh",
+                "cat",
+                "hello world",
+            ]
+        );
+    }
+
+    #[test]
+    fn tasks_are_staged() {
+        let task = Task::builder()
+            .extend_executions([Execution::builder()
+                .image("ubuntu")
+                .args(["true"])
+                .try_build()
+                .unwrap()])
+            .extend_inputs([
+                Input::builder()
+                    .path("/inputs/a")
+                    .contents(Contents::URL("file:///data/a".parse().unwrap()))
+                    .r#type(InputType::File)
+                    .try_build()
+                    .unwrap(),
+                Input::builder()
+                    .path("/inputs/b")
+                    .contents(Contents::Literal(String::from("hello")))
+                    .r#type(InputType::File)
+                    .try_build()
+                    .unwrap(),
+            ])
+            .extend_outputs([OutputBuilder::default()
+                .path("/outputs/c")
+                .url("file:///results/c".parse::<url::Url>().unwrap())
+                .r#type(OutputType::File)
+                .try_build()
+                .unwrap()])
+            .extend_volumes([String::from("/shared")])
+            .try_build()
+            .unwrap();
+
+        let staging = stage(&task, "/tmp/stage").unwrap();
+        assert_eq!(
+            staging.setup,
+            [
+                "printf %s hello > /tmp/stage/input-1",
+                ": > /tmp/stage/output-0",
+                "mkdir /tmp/stage/volume-0",
+            ]
+        );
+        assert_eq!(
+            staging
+                .binds
+                .iter()
+                .map(|bind| (bind.host.as_str(), bind.container.as_str(), bind.read_only))
+                .collect::<Vec<_>>(),
+            [
+                ("/data/a", "/inputs/a", true),
+                ("/tmp/stage/input-1", "/inputs/b", true),
+                ("/tmp/stage/output-0", "/outputs/c", false),
+                ("/tmp/stage/volume-0", "/shared", false),
+            ]
+        );
+        assert_eq!(
+            staging.finish,
+            ["mkdir -p /results && rm -rf /results/c && mv /tmp/stage/output-0 /results/c"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn tasks_are_run_with_their_outputs_moved() {
+        use crate::Backend as _;
+        use crate::events::Events;
+
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("results").join("out.txt");
+
+        // NOTE: `echo` stands in for `apptainer`, so the command is echoed.
+        let backend = Backend::initialize(Config::builder().executable("echo").build())
+            .await
+            .unwrap();
+
+        let task = Task::builder()
+            .extend_executions([Execution::builder()
+                .image("ubuntu")
+                .args(["true"])
+                .try_build()
+                .unwrap()])
+            .extend_outputs([OutputBuilder::default()
+                .path("/outputs/out.txt")
+                .url(url::Url::from_file_path(&target).unwrap())
+                .r#type(OutputType::File)
+                .try_build()
+                .unwrap()])
+            .try_build()
+            .unwrap();
+
+        let events = Events::default();
+        let result = backend
+            .run(task, events.task(events.next_id(), "apptainer"))
+            .await
+            .unwrap();
+
+        let output = result.executions().first();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success());
+        assert!(stdout.starts_with("exec --bind /"), "{stdout}");
+        assert!(
+            stdout.ends_with("/output-0:/outputs/out.txt docker://ubuntu true\n"),
+            "{stdout}"
+        );
+        assert!(target.is_file());
+    }
+}
@@ -1,26 +1,80 @@
 //! A Docker backend.
+//!
+//! When the capacity (i.e., the number of CPUs and the total memory) of the
+//! Docker daemon is known, tasks are only started once the daemon has enough
+//! unreserved capacity for the resources they request. Until then, they are
+//! [queued](crate::events::Event::TaskQueued) within the backend.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Output;
+use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
+use bollard::errors::Error as BollardError;
+use bollard::secret::ContainerStateStatusEnum;
 use bollard::secret::HostConfig;
 use bollard::secret::Mount;
 use bollard::secret::MountTypeEnum;
+use bollard::secret::MountVolumeOptions;
+use bollard::secret::MountVolumeOptionsDriverConfig;
 use crankshaft_config::backend::docker::Config;
+#[cfg(doc)]
+use crankshaft_config::backend::docker::mount::Overlay;
+use crankshaft_config::backend::docker::mount::Propagation;
+use crankshaft_config::backend::docker::mount::Relabel;
+use crankshaft_config::backend::docker::security;
+use crankshaft_docker::Container;
 use crankshaft_docker::Docker;
+use crankshaft_docker::container::Seccomp;
+use crankshaft_docker::containers::TASK_LABEL;
 use eyre::Context;
 use futures::FutureExt;
-use futures::StreamExt;
 use futures::future::BoxFuture;
-use futures::stream::FuturesUnordered;
+use futures::future::try_join_all;
 use nonempty::NonEmpty;
 use tempfile::TempDir;
+use tracing::debug;
+use tracing::warn;
 
 use crate::Result;
 use crate::Task;
+use crate::events::Limits;
+use crate::events::QueueReason;
+use crate::events::ResourceUsage;
+use crate::events::TaskEvents;
+use crate::service::runner::backend::FailureReason;
 use crate::service::runner::backend::TaskResult;
+use crate::service::runner::backend::docker::admission::Admission;
+use crate::service::runner::backend::docker::admission::Request;
+use crate::service::runner::backend::generic;
+use crate::service::runner::backend::provenance::ExecutionProvenance;
+use crate::service::runner::backend::provenance::Provenance;
+use crate::service::runner::backend::redirect;
+use crate::stage::Stager;
+use crate::task::Execution;
+use crate::task::input::Fetchers;
+use crate::task::input::fetcher::Cache;
+use crate::task::input::fetcher::Http;
+
+mod admission;
 
 /// The working dir name inside the docker container
 pub const WORKDIR: &str = "/workdir";
 
+/// The number of times the Docker daemon is checked for whether it is
+/// reachable again after the connection to a container was interrupted.
+const RECONNECT_ATTEMPTS: u32 = 20;
+
+/// The time to wait between checks of whether the Docker daemon is reachable
+/// again.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(3);
+
+/// The maximum number of times an execution is resubmitted after its
+/// container was lost (if resubmission is enabled).
+const MAX_RESUBMISSIONS: usize = 3;
+
 /// A local execution backend.
 #[derive(Debug)]
 pub struct Backend {
@@ -28,6 +82,13 @@ pub struct Backend {
     client: Docker,
     /// Configuration for the backend.
     config: Config,
+    /// The stager of task inputs and outputs.
+    stager: Stager,
+    /// The seccomp profile applied to containers (if one is configured).
+    seccomp: Option<Seccomp>,
+    /// The admission of tasks based on the capacity of the daemon (if the
+    /// capacity is known).
+    admission: Option<Arc<Admission>>,
 }
 
 impl Backend {
@@ -37,11 +98,107 @@ impl Backend {
     /// Note that, currently, we connect [using
     /// defaults](Docker::connect_with_defaults) when attempting to connect to
     /// the Docker daemon.
+    ///
+    /// If the backend configuration pins a Docker API version, the client uses
+    /// that version. Otherwise, the client uses the latest version it supports
+    /// (see [`Backend::initialize()`] to negotiate the version instead).
     pub fn initialize_default_with(config: Config) -> Result<Self> {
-        let client = Docker::with_defaults()
-            .context("error connecting to the Docker daemon—is it running?")?;
+        let client = match config.api_version() {
+            Some(version) => Docker::with_pinned_version(
+                version
+                    .parse()
+                    .map_err(|err: String| eyre::eyre!(err))
+                    .context("parsing the pinned Docker API version")?,
+            ),
+            None => Docker::with_defaults(),
+        }
+        .context("error connecting to the Docker daemon—is it running?")?;
+
+        let mut fetchers = Fetchers::default().with(Http::default());
+
+        if let Some(limit) = config.input_size_limit() {
+            fetchers = fetchers.limit(limit);
+        }
+
+        if let Some(dir) = config.input_cache_dir() {
+            fetchers = fetchers.cache(Cache::new(dir));
+        }
+
+        let stager = Stager::new(fetchers).with_uploaders(generic::uploaders(
+            config.output_upload_concurrency(),
+            config.output_upload_retries(),
+        ));
+
+        // NOTE: the seccomp profile is read once (rather than for every
+        // container) so that a missing or unreadable profile is reported when
+        // the backend is initialized.
+        let seccomp = match config.seccomp_profile() {
+            Some(security::Seccomp::Unconfined) => Some(Seccomp::Unconfined),
+            Some(security::Seccomp::Profile(path)) => Some(Seccomp::Profile(
+                std::fs::read_to_string(path)
+                    .with_context(|| format!("reading the seccomp profile `{}`", path.display()))?,
+            )),
+            None => None,
+        };
 
-        Ok(Self { client, config })
+        Ok(Self {
+            client,
+            config,
+            stager,
+            seccomp,
+            admission: None,
+        })
+    }
+
+    /// Attempts to initialize a new Docker [`Backend`] with the default
+    /// connection settings and the provided configuration for the backend,
+    /// negotiating the Docker API version with the daemon (unless the
+    /// configuration pins a version).
+    ///
+    /// Negotiating ensures that requests are never made with a newer API
+    /// version than the daemon supports, and features that require a newer
+    /// version fail with a clear error rather than being rejected by the
+    /// daemon.
+    ///
+    /// The capacity of the daemon is also retrieved so that tasks are only
+    /// started once the daemon has enough unreserved capacity for them.
+    pub async fn initialize(config: Config) -> Result<Self> {
+        let mut backend = Self::initialize_default_with(config)?;
+
+        if backend.config.api_version().is_none() {
+            backend.client = backend
+                .client
+                .negotiate()
+                .await
+                .context("error negotiating the Docker API version—is the daemon running?")?;
+        }
+
+        debug!("using Docker API version {}", backend.client.api_version());
+
+        backend.admission = match backend.client.info().await {
+            Ok(info) => match (info.ncpu, info.mem_total) {
+                (Some(cpu), Some(memory)) if cpu > 0 && memory > 0 => {
+                    debug!("the Docker daemon has {cpu} CPUs and {memory} bytes of memory");
+                    Some(Arc::new(Admission::new(Request::new(
+                        cpu as u64,
+                        memory as u64,
+                    ))))
+                }
+                _ => {
+                    warn!(
+                        "the Docker daemon did not report its capacity; tasks are not admitted \
+                         based on their resources"
+                    );
+                    None
+                }
+            },
+            Err(err) => {
+                warn!("could not determine the capacity of the Docker daemon: {err}");
+                None
+            }
+        };
+
+        Ok(backend)
     }
 
     /// Attempts to initialize a new Docker [`Backend`] with the default
@@ -61,123 +218,736 @@ impl crate::Backend for Backend {
         "docker"
     }
 
-    fn run(&self, task: Task) -> BoxFuture<'static, TaskResult> {
-        run(self, task)
+    fn reports_started(&self) -> bool {
+        // NOTE: tasks may wait on the capacity of the daemon after acquiring
+        // a permit, so they are only reported as started once admitted.
+        self.admission.is_some()
+    }
+
+    fn reports_resource_usage(&self) -> bool {
+        true
     }
+
+    fn terminates_gracefully(&self) -> bool {
+        true
+    }
+
+    fn limits(&self, task: &Task) -> Option<Limits> {
+        let host_config = host_config(task, &self.config);
+
+        Some(Limits {
+            memory: host_config.memory,
+            memory_swap: host_config.memory_swap,
+        })
+    }
+
+    fn run(
+        &self,
+        task: Task,
+        events: TaskEvents,
+    ) -> BoxFuture<'static, std::result::Result<TaskResult, FailureReason>> {
+        run(self, task, events)
+    }
+
+    fn prefetch_image(&self, image: &str) -> BoxFuture<'static, std::result::Result<(), String>> {
+        let client = self.client.clone();
+        let (name, tag) = split_reference(image);
+        let (name, tag) = (name.to_owned(), tag.to_owned());
+
+        async move {
+            client
+                .ensure_image(&name, &tag)
+                .await
+                .map_err(|err| err.to_string())
+        }
+        .boxed()
+    }
+}
+
+/// Splits an image reference into its name and its tag (or digest).
+///
+/// References without a tag refer to the `latest` tag.
+fn split_reference(image: &str) -> (&str, &str) {
+    if let Some((name, digest)) = image.split_once('@') {
+        return (name, digest);
+    }
+
+    // NOTE: a colon before the last slash separates a registry from its port
+    // rather than a name from its tag.
+    let start = image.rfind('/').map(|i| i + 1).unwrap_or_default();
+
+    match image[start..].rfind(':') {
+        Some(i) => (&image[..start + i], &image[start + i + 1..]),
+        None => (image, "latest"),
+    }
+}
+
+/// Gets the bind for a mount from `source` to `target` with the mount options
+/// from the backend configuration applied.
+///
+/// Binds are used (rather than [`Mount`](bollard::secret::Mount)s) because
+/// SELinux relabeling is only supported by Docker for binds.
+fn bind(source: &str, target: &str, config: &Config) -> String {
+    let mut options = vec!["rw"];
+
+    if let Some(relabel) = config.selinux_relabel() {
+        options.push(match relabel {
+            Relabel::Shared => "z",
+            Relabel::Private => "Z",
+        });
+    }
+
+    if let Some(propagation) = config.mount_propagation() {
+        options.push(match propagation {
+            Propagation::Private => "private",
+            Propagation::Rprivate => "rprivate",
+            Propagation::Shared => "shared",
+            Propagation::Rshared => "rshared",
+            Propagation::Slave => "slave",
+            Propagation::Rslave => "rslave",
+        });
+    }
+
+    format!("{source}:{target}:{}", options.join(","))
+}
+
+/// The scratch directory of a task's reference overlays.
+///
+/// Each [reference overlay](Overlay) is given an `upper` directory (which
+/// receives the files written to the overlaid references) and a `work`
+/// directory (required by overlay filesystems) within a subdirectory named by
+/// its index. The executions of a task share the scratch directory, so files
+/// written by one execution are visible to the next.
+struct Scratch {
+    /// The scratch directory.
+    dir: TempDir,
 }
 
-/// Gets the shared mounts (if any exist) from the shared volumes in a [`Task`]
+impl Scratch {
+    /// Creates the scratch directory for the reference overlays within the
+    /// backend configuration.
+    ///
+    /// Returns `Ok(None)` if no references are overlaid.
+    fn new(config: &Config) -> std::io::Result<Option<Self>> {
+        if config.reference_overlays().is_empty() {
+            return Ok(None);
+        }
+
+        let dir = match config.overlay_scratch_dir() {
+            Some(parent) => {
+                std::fs::create_dir_all(parent)?;
+                TempDir::new_in(parent)?
+            }
+            None => TempDir::new()?,
+        };
+
+        for index in 0..config.reference_overlays().len() {
+            std::fs::create_dir_all(dir.path().join(index.to_string()).join("upper"))?;
+            std::fs::create_dir_all(dir.path().join(index.to_string()).join("work"))?;
+        }
+
+        Ok(Some(Self { dir }))
+    }
+
+    /// Gets the mounts of the reference overlays within the backend
+    /// configuration.
+    ///
+    /// Each overlay is mounted as an anonymous volume of the `local` driver
+    /// with the `overlay` filesystem type, so the overlay filesystem is
+    /// mounted (and unmounted) by the Docker daemon along with the container.
+    fn mounts(&self, config: &Config) -> Vec<Mount> {
+        config
+            .reference_overlays()
+            .iter()
+            .enumerate()
+            .map(|(index, overlay)| {
+                let dir = self.dir.path().join(index.to_string());
+                let options = format!(
+                    "lowerdir={},upperdir={},workdir={}",
+                    overlay.source().display(),
+                    dir.join("upper").display(),
+                    dir.join("work").display()
+                );
+
+                Mount {
+                    target: Some(overlay.target().to_owned()),
+                    typ: Some(MountTypeEnum::VOLUME),
+                    volume_options: Some(MountVolumeOptions {
+                        driver_config: Some(MountVolumeOptionsDriverConfig {
+                            name: Some(String::from("local")),
+                            options: Some(HashMap::from([
+                                (String::from("type"), String::from("overlay")),
+                                (String::from("device"), String::from("overlay")),
+                                (String::from("o"), options),
+                            ])),
+                        }),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+
+    /// Removes the scratch directory (or keeps it if `keep` is set).
+    fn finish(self, keep: bool) {
+        if keep {
+            debug!(
+                "keeping overlay scratch directory `{}`",
+                self.dir.into_path().display()
+            );
+            return;
+        }
+
+        let path = self.dir.path().to_owned();
+
+        // NOTE: files written within containers may be owned by a different
+        // user (e.g., `root`), in which case the scratch directory cannot be
+        // fully removed. This is not fatal to the task.
+        if let Err(err) = self.dir.close() {
+            warn!(
+                "could not remove overlay scratch directory `{}`: {err}",
+                path.display()
+            );
+        }
+    }
+}
+
+/// Gets the host configuration (excluding binds) for the containers of a
+/// [`Task`].
+///
+/// The memory limit is taken from the hard amount of RAM of the task (and the
+/// memory reservation from the soft amount). If swap is disabled within the
+/// backend configuration, the combined memory and swap limit is set to the
+/// same value so that no swap is available; otherwise, Docker's default swap
+/// limit applies.
+fn host_config(task: &Task, config: &Config) -> HostConfig {
+    let mut host_config = task.resources().map(HostConfig::from).unwrap_or_default();
+
+    if config.disable_swap() {
+        host_config.memory_swap = host_config.memory;
+    }
+
+    if !config.groups().is_empty() {
+        host_config.group_add = Some(config.groups().to_vec());
+    }
+
+    host_config
+}
+
+/// Gets the user (and group) invoking Crankshaft in the `uid:gid` form
+/// expected by Docker.
+#[cfg(unix)]
+fn host_user() -> Option<String> {
+    // SAFETY: these functions are always successful and have no side effects.
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    Some(format!("{uid}:{gid}"))
+}
+
+/// Gets the user (and group) invoking Crankshaft in the `uid:gid` form
+/// expected by Docker.
+///
+/// Users are not numeric on Windows, so this is never available.
+#[cfg(windows)]
+fn host_user() -> Option<String> {
+    None
+}
+
+/// Gets the user that the container for an execution runs as (if one is set).
+///
+/// The user of the execution takes precedence over the user within the
+/// configuration, which takes precedence over the invoking user (if the
+/// backend is configured to run as the invoking user).
+fn user(execution: &Execution, config: &Config) -> Option<String> {
+    execution
+        .user()
+        .or(config.user())
+        .map(ToOwned::to_owned)
+        .or_else(|| {
+            if !config.run_as_host_user() {
+                return None;
+            }
+
+            let user = host_user();
+
+            if user.is_none() {
+                warn!("the invoking user cannot be determined on this platform");
+            }
+
+            user
+        })
+}
+
+/// Gets the shared binds (if any exist) from the shared volumes in a [`Task`]
 /// (via [`Task::shared_volumes()`]).
-fn get_shared_mounts<'a>(volumes: Option<impl Iterator<Item = &'a str>>) -> Option<Vec<Mount>> {
+fn get_shared_binds<'a>(
+    volumes: Option<impl Iterator<Item = &'a str>>,
+    config: &Config,
+) -> Option<Vec<String>> {
     volumes.map(|iter| {
         iter.map(|inner_path| {
-            Mount {
-                target: Some(inner_path.to_owned()),
-                source: Some(
-                    TempDir::new()
-                        // SAFETY: for now, this is essentially a workaround to the fact
-                        // that we do not return a [`Result`] in the `run()` method. It's
-                        // certainly possible for this to fail, but I feel it's unlikely
-                        // enough to occur in early development that handling this properly
-                        // can be elided for now.
-                        //
-                        // TODO(clay): more properly handle this later.
-                        .expect("could not initialize tempdir")
-                        // NOTE: this is *required* because it causes the
-                        // temporary directory to no longer be dropped when the
-                        // [`TempDir`] goes out of scope. In other words, simply
-                        // referring to [`path()`] isn't sufficient (even though
-                        // it would suit our purposes from the perspective of
-                        // getting a [`str`] representation).
-                        .into_path()
-                        .to_str()
-                        // SAFETY: essentially the above reasoning—it's unlikely
-                        // that this will fail in early testing, but we should
-                        // come back to more properly handling this later.
-                        //
-                        // TODO(clay): more properly handle this later.
-                        .unwrap()
-                        .to_owned(),
-                ),
-                typ: Some(MountTypeEnum::BIND),
-                read_only: Some(false),
-                ..Default::default()
-            }
+            let source = TempDir::new()
+                // SAFETY: for now, this is essentially a workaround to the fact
+                // that we do not return a [`Result`] in the `run()` method. It's
+                // certainly possible for this to fail, but I feel it's unlikely
+                // enough to occur in early development that handling this properly
+                // can be elided for now.
+                //
+                // TODO(clay): more properly handle this later.
+                .expect("could not initialize tempdir")
+                // NOTE: this is *required* because it causes the
+                // temporary directory to no longer be dropped when the
+                // [`TempDir`] goes out of scope. In other words, simply
+                // referring to [`path()`] isn't sufficient (even though
+                // it would suit our purposes from the perspective of
+                // getting a [`str`] representation).
+                .into_path();
+
+            let source = source
+                .to_str()
+                // SAFETY: essentially the above reasoning—it's unlikely
+                // that this will fail in early testing, but we should
+                // come back to more properly handling this later.
+                //
+                // TODO(clay): more properly handle this later.
+                .unwrap();
+
+            bind(source, inner_path, config)
         })
         .collect::<Vec<_>>()
     })
 }
 
+/// Converts an error creating the container of an execution into the reason
+/// its task failed.
+///
+/// A container whose options are invalid (e.g., an environment variable with
+/// an invalid name) or that uses a feature the Docker API version of the
+/// client does not support is never created, so the task fails rather than
+/// the backend.
+fn creation_failure(err: crankshaft_docker::Error) -> FailureReason {
+    match err {
+        crankshaft_docker::Error::Invalid(_) | crankshaft_docker::Error::Unsupported { .. } => {
+            FailureReason::Unsupported(err.to_string())
+        }
+        err => FailureReason::Request(format!("creating the container: {err}")),
+    }
+}
+
+/// Samples the resources used by the container of a running execution.
+///
+/// Failing to sample the container is not fatal to the task, so it is only
+/// logged.
+async fn usage(container: &Container) -> Option<ResourceUsage> {
+    match container.usage().await {
+        Ok(usage) => Some(ResourceUsage {
+            cpu_percent: usage.cpu_percent(),
+            memory: usage.memory(),
+            disk: usage.disk(),
+        }),
+        Err(err) => {
+            debug!(
+                "failed to sample the resource usage of container `{}`: {err}",
+                container.name()
+            );
+            None
+        }
+    }
+}
+
+/// Terminates the container of a running execution.
+///
+/// The container is stopped (i.e., sent `SIGTERM` and then `SIGKILL` if it has
+/// not exited within the grace period) and then removed (if the backend
+/// cleans up containers).
+async fn terminate(container: &Container, grace_period: Duration, cleanup: bool) {
+    if let Err(err) = container.stop(grace_period).await {
+        warn!(
+            "failed to stop container `{}` of terminated task: {err}",
+            container.name()
+        );
+    }
+
+    if cleanup {
+        if let Err(err) = container.force_remove().await {
+            debug!("could not remove container of terminated task: {err}");
+        }
+    }
+}
+
+/// Gets whether or not an error running a container means that the connection
+/// to the Docker daemon was interrupted (e.g., because the daemon restarted).
+///
+/// Only then is the execution recovered (see [`recover()`]); any other error
+/// fails the task immediately.
+fn is_connection_error(err: &crankshaft_docker::Error) -> bool {
+    matches!(
+        err,
+        crankshaft_docker::Error::Docker(
+            BollardError::IOError { .. }
+                | BollardError::HyperResponseError { .. }
+                | BollardError::HyperLegacyError { .. }
+                | BollardError::RequestTimeoutError
+                | BollardError::SocketNotFoundError(_)
+        )
+    )
+}
+
+/// Recovers an execution after the connection to its container was
+/// interrupted (e.g., because the Docker daemon restarted).
+///
+/// Once the daemon is reachable again, the container is reattached to if it
+/// survived. Otherwise, the reason the container was lost is returned.
+async fn recover(client: &Docker, container: &Container) -> std::result::Result<Output, String> {
+    let mut attempts = 0;
+
+    while let Err(err) = client.ping().await {
+        attempts += 1;
+
+        if attempts >= RECONNECT_ATTEMPTS {
+            return Err(format!("the Docker daemon is unreachable: {err}"));
+        }
+
+        debug!("the Docker daemon is unreachable; checking again in {RECONNECT_INTERVAL:?}");
+        tokio::time::sleep(RECONNECT_INTERVAL).await;
+    }
+
+    let details = container
+        .inspect()
+        .await
+        .map_err(|err| format!("the container could not be inspected: {err}"))?;
+
+    match details.status() {
+        Some(ContainerStateStatusEnum::RUNNING)
+        | Some(ContainerStateStatusEnum::PAUSED)
+        | Some(ContainerStateStatusEnum::RESTARTING)
+        | Some(ContainerStateStatusEnum::EXITED) => container
+            .reattach()
+            .await
+            .map_err(|err| format!("the container could not be reattached to: {err}")),
+        status => Err(format!(
+            "the container is in an unrecoverable state ({status:?})"
+        )),
+    }
+}
+
+/// Downloads the outputs of a task from a container and uploads them to their
+/// URLs.
+async fn upload_outputs<'a>(
+    container: &Container,
+    stager: &Stager,
+    outputs: impl Iterator<Item = &'a crate::task::Output>,
+    events: &TaskEvents,
+) -> std::result::Result<(), String> {
+    let dir = TempDir::new().map_err(|err| format!("creating a directory: {err}"))?;
+    let mut uploads = Vec::new();
+
+    for (index, output) in outputs.enumerate() {
+        // NOTE: each output is downloaded into its own directory, as outputs
+        // from different directories may share a name.
+        let parent = dir.path().join(index.to_string());
+        container
+            .download(output.path(), &parent)
+            .await
+            .map_err(|err| format!("downloading `{}`: {err}", output.path()))?;
+
+        let path = match Path::new(output.path()).file_name() {
+            Some(name) => parent.join(name),
+            None => parent,
+        };
+
+        uploads.push((output, path));
+    }
+
+    stager
+        .upload_all(uploads, events)
+        .await
+        .map_err(|err| err.to_string())
+}
+
 /// Runs a task using the Docker backend.
-fn run(backend: &Backend, task: Task) -> BoxFuture<'static, TaskResult> {
+fn run(
+    backend: &Backend,
+    task: Task,
+    events: TaskEvents,
+) -> BoxFuture<'static, std::result::Result<TaskResult, FailureReason>> {
     let client = backend.client.clone();
     let cleanup = backend.config.cleanup();
-    let mounts = get_shared_mounts(task.shared_volumes());
+    let binds = get_shared_binds(task.shared_volumes(), &backend.config);
+    let scratch = Scratch::new(&backend.config);
+    let host_config = host_config(&task, &backend.config);
+    let config = backend.config.clone();
+    let stager = backend.stager.clone();
+    let executions = task.executions().count();
+    let seccomp = backend.seccomp.clone();
+    let admission = backend.admission.clone();
 
     async move {
         let mut outputs = Vec::new();
+        let mut provenance = Provenance::default().with_resources(task.resources().cloned());
 
-        for execution in task.executions() {
-            // (1) Create the container.
-            let mut builder = client
-                .container_builder()
-                .image(execution.image())
-                .command(
-                    execution
-                        .args()
-                        .into_iter()
-                        .map(|s| s.to_owned())
-                        .collect::<Vec<_>>(),
-                )
-                .attached(true)
-                .host_config(HostConfig {
-                    mounts: mounts.clone(),
-                    ..task.resources().map(HostConfig::from).unwrap_or_default()
-                });
-
-            if let Some(workdir) = execution.workdir() {
-                builder = builder.workdir(workdir.to_owned());
-            }
+        let scratch = scratch.map_err(|err| {
+            FailureReason::Request(format!("creating the overlay scratch directory: {err}"))
+        })?;
+        let mounts = scratch.as_ref().map(|scratch| scratch.mounts(&config));
 
-            let container = builder.try_create(&task.name().unwrap()).await.unwrap();
+        // (0) Stage the contents of all inputs concurrently.
+        //
+        // NOTE: this is done once per task (rather than once per execution),
+        // as the same inputs are uploaded to the container of each execution.
+        // The contents are streamed to disk (and from there into each
+        // container) so that inputs are never held in memory.
+        let staging = TempDir::new().map_err(|err| {
+            FailureReason::Request(format!("creating the input staging directory: {err}"))
+        })?;
 
-            // (2) Upload inputs to the container.
-            //
-            // TODO(clay): these could be cached.
-            if let Some(inputs) = task.inputs() {
-                let futures = inputs
-                    .map(|input| async {
-                        let contents = input.fetch().await;
-                        container.upload_file(input.path(), contents).await
+        let inputs = match task.inputs() {
+            Some(inputs) => {
+                let inputs = inputs.collect::<Vec<_>>();
+                let staged = stager
+                    .stage_all(inputs.iter().copied(), Some(staging.path()))
+                    .await
+                    .map_err(|err| FailureReason::Request(format!("staging inputs: {err}")))?;
+
+                inputs
+                    .into_iter()
+                    .map(|input| input.path())
+                    .zip(staged)
+                    .collect::<Vec<_>>()
+            }
+            None => Vec::new(),
+        };
+
+        // NOTE: the capacity is reserved for the whole task (rather than for
+        // each execution), as the executions of a task run one after another.
+        let _reservation = match &admission {
+            Some(admission) => {
+                let reservation = admission
+                    .reserve(Request::from_resources(task.resources()), || {
+                        events.queued(QueueReason::InsufficientResources, None)
                     })
-                    .collect::<FuturesUnordered<_>>();
+                    .await;
 
-                // NOTE: this is just an unfancy way to evaluate all of the
-                // above futures.
-                //
-                // TODO(clay): make this more elegant.
-                futures.for_each(|_| async {}).await;
+                events.started();
+                Some(reservation)
+            }
+            None => None,
+        };
+
+        for (index, execution) in task.executions().enumerate() {
+            let mut resubmissions = 0;
+
+            let (container, output) = loop {
+                // (1) Create the container.
+                let mut builder = client
+                    .container_builder()
+                    .image(execution.image())
+                    .command(
+                        execution
+                            .args()
+                            .into_iter()
+                            .map(|s| s.to_owned())
+                            .collect::<Vec<_>>(),
+                    )
+                    .attached(true)
+                    .extend_labels([(TASK_LABEL, task.name().unwrap())])
+                    .host_config(HostConfig {
+                        binds: binds.clone(),
+                        mounts: mounts.clone(),
+                        ..host_config.clone()
+                    });
+
+                if let Some(workdir) = execution.workdir() {
+                    builder = builder.workdir(workdir.to_owned());
+                }
+
+                if let Some(env) = execution.env() {
+                    builder = builder.extend_env(env.iter());
+                }
+
+                if let Some(user) = user(execution, &config) {
+                    builder = builder.user(user);
+                }
+
+                if let Some(seccomp) = &seccomp {
+                    builder = builder.seccomp(seccomp.clone());
+                }
+
+                if let Some(profile) = config.apparmor_profile() {
+                    builder = builder.apparmor(profile);
+                }
+
+                let container = builder
+                    .try_create(&task.name().unwrap())
+                    .await
+                    .map_err(creation_failure)?;
+
+                // (2) Upload inputs to the container.
+                let uploaded = try_join_all(
+                    inputs
+                        .iter()
+                        .map(|(path, staged)| container.upload_path(path, staged.path())),
+                )
+                .await;
+
+                // NOTE: an execution never runs without all of its inputs, so
+                // the task fails before the container is started.
+                if let Err(err) = uploaded {
+                    if cleanup {
+                        if let Err(err) = container.force_remove().await {
+                            debug!("could not remove container with missing inputs: {err}");
+                        }
+                    }
+
+                    return Err(FailureReason::Request(format!("uploading inputs: {err}")));
+                }
+
+                // (3) Start the container.
+                if resubmissions == 0 {
+                    events.execution_started(index);
+                }
+
+                let running = events.with_resource_usage(container.run(), || usage(&container));
+
+                let err = tokio::select! {
+                    result = events.with_heartbeats(running) => match result {
+                        Ok(output) => break (container, output),
+                        Err(err) => err,
+                    },
+                    _ = events.terminated() => {
+                        terminate(&container, events.grace_period(), cleanup).await;
+                        return Err(FailureReason::Request(String::from(
+                            "the task was terminated",
+                        )));
+                    }
+                };
+
+                if !is_connection_error(&err) {
+                    if cleanup {
+                        if let Err(err) = container.force_remove().await {
+                            debug!("could not remove failed container: {err}");
+                        }
+                    }
+
+                    return Err(FailureReason::Request(format!(
+                        "running the container: {err}"
+                    )));
+                }
+
+                // NOTE: the connection to the container was interrupted (most
+                // commonly because the Docker daemon restarted), so the
+                // execution is recovered if possible.
+                warn!(
+                    "lost connection to the container of execution {index} of task `{}`: {err}",
+                    task.name().unwrap()
+                );
+
+                match recover(&client, &container).await {
+                    Ok(output) => break (container, output),
+                    Err(reason)
+                        if config.resubmit_lost_containers()
+                            && resubmissions < MAX_RESUBMISSIONS =>
+                    {
+                        resubmissions += 1;
+                        warn!(
+                            "resubmitting execution {index} of task `{}` (attempt {resubmissions} \
+                             of {MAX_RESUBMISSIONS}): {reason}",
+                            task.name().unwrap()
+                        );
+
+                        // NOTE: the lost container is removed (if it still
+                        // exists) so that its name can be reused.
+                        if let Err(err) = container.force_remove().await {
+                            debug!("could not remove lost container: {err}");
+                        }
+                    }
+                    Err(reason) => {
+                        if cleanup {
+                            if let Err(err) = container.force_remove().await {
+                                debug!("could not remove lost container: {err}");
+                            }
+                        }
+
+                        return Err(FailureReason::ContainerLost(reason));
+                    }
+                }
             };
 
-            // (3) Start the container.
-            let output = container.run().await.unwrap();
+            events.execution_finished(index, output.status);
+            let output = redirect(execution, output).await;
 
-            // (4) Cleanup the container (if desired).
+            let digest = match client.image_digest(execution.image()).await {
+                Ok(digest) => digest,
+                Err(err) => {
+                    debug!(
+                        "could not resolve the digest of `{}`: {err}",
+                        execution.image()
+                    );
+                    None
+                }
+            };
+
+            provenance.push_execution(ExecutionProvenance::new(
+                execution.image(),
+                digest,
+                Some(container.name().to_owned()),
+            ));
+
+            // (4) Inspect the container for diagnostics (if the execution failed).
+            if !output.status.success() {
+                match container.inspect().await {
+                    Ok(details) => {
+                        if details.oom_killed() {
+                            warn!(
+                                "execution {index} of task `{}` was killed because it ran out of \
+                                 memory",
+                                task.name().unwrap()
+                            );
+                        }
+
+                        debug!("failed container details: {details:?}");
+                    }
+                    Err(err) => debug!("could not inspect failed container: {err}"),
+                }
+            }
+
+            // (5) Upload the outputs of the task from the container of the last
+            // execution (if every execution succeeded).
+            //
+            // NOTE: a failed upload fails the task only once the container has
+            // been cleaned up.
+            let uploaded = match task.outputs() {
+                Some(uploads)
+                    if index + 1 == executions
+                        && output.status.success()
+                        && outputs
+                            .iter()
+                            .all(|output: &Output| output.status.success()) =>
+                {
+                    upload_outputs(&container, &stager, uploads, &events).await
+                }
+                _ => Ok(()),
+            };
+
+            // (6) Cleanup the container (if desired).
+            //
+            // NOTE: the container may already be gone (e.g., if the Docker
+            // daemon restarted while it ran), so failing to remove it does not
+            // fail the task.
             if cleanup {
-                container
-                    .remove()
-                    .await
-                    // SAFETY: this should always unwrap for now, but we should
-                    // revisit this in the future to more elegantly handle the
-                    // situation.
-                    //
-                    // TODO(clay): more elegantly handle this situation.
-                    .unwrap();
+                if let Err(err) = container.remove().await {
+                    warn!("failed to remove container `{}`: {err}", container.name());
+                }
             }
 
+            uploaded.map_err(|err| FailureReason::Request(format!("uploading outputs: {err}")))?;
             outputs.push(output);
         }
 
+        if let Some(scratch) = scratch {
+            scratch.finish(!cleanup);
+        }
+
         let mut outputs = outputs.into_iter();
 
         // SAFETY: each task _must_ have at least one execution, so at least one
@@ -185,7 +955,213 @@ fn run(backend: &Backend, task: Task) -> BoxFuture<'static, TaskResult> {
         let mut executions = NonEmpty::new(outputs.next().unwrap());
         executions.extend(outputs);
 
-        TaskResult { executions }
+        Ok(TaskResult {
+            executions,
+            provenance,
+            accounting: Default::default(),
+        })
     }
     .boxed()
 }
+
+#[cfg(test)]
+mod tests {
+    use crankshaft_config::backend::docker::mount::Overlay;
+    use crankshaft_docker::ApiVersion;
+    use crankshaft_docker::Feature;
+    use crankshaft_docker::container::Problem;
+
+    use super::*;
+    use crate::task::resources::Builder as ResourcesBuilder;
+
+    #[test]
+    fn invalid_containers_fail_the_task() {
+        let err = crankshaft_docker::Error::Invalid(vec![
+            Problem::EmptyProgram,
+            Problem::InvalidEnvName(String::from("FOO=BAR")),
+        ]);
+
+        match creation_failure(err) {
+            FailureReason::Unsupported(reason) => {
+                assert!(reason.contains("the program of the command is empty"));
+                assert!(reason.contains("FOO=BAR"));
+            }
+            reason => panic!("unexpected failure reason: {reason:?}"),
+        }
+    }
+
+    #[test]
+    fn unsupported_features_fail_the_task() {
+        let err = crankshaft_docker::Error::Unsupported {
+            feature: Feature::DeviceRequests,
+            required: ApiVersion::new(1, 40),
+            actual: ApiVersion::new(1, 39),
+        };
+
+        match creation_failure(err) {
+            FailureReason::Unsupported(reason) => {
+                assert!(reason.contains("1.40"));
+                assert!(reason.contains("1.39"));
+            }
+            reason => panic!("unexpected failure reason: {reason:?}"),
+        }
+    }
+
+    #[test]
+    fn only_connection_errors_are_recovered() {
+        let err = crankshaft_docker::Error::Docker(BollardError::RequestTimeoutError);
+        assert!(is_connection_error(&err));
+
+        let err = crankshaft_docker::Error::Docker(BollardError::DockerResponseServerError {
+            status_code: 404,
+            message: String::from("no such image"),
+        });
+        assert!(!is_connection_error(&err));
+    }
+
+    #[test]
+    fn binds_include_mount_options() {
+        let config = Config::default();
+        assert_eq!(bind("/tmp/foo", "/foo", &config), "/tmp/foo:/foo:rw");
+
+        let config = Config::builder()
+            .selinux_relabel(Relabel::Private)
+            .mount_propagation(Propagation::Rslave)
+            .build();
+        assert_eq!(
+            bind("/tmp/foo", "/foo", &config),
+            "/tmp/foo:/foo:rw,Z,rslave"
+        );
+    }
+
+    #[test]
+    fn references_are_overlaid_with_a_scratch_directory() {
+        let config = Config::default();
+        assert!(Scratch::new(&config).unwrap().is_none());
+
+        let parent = TempDir::new().unwrap();
+        let config = Config::builder()
+            .extend_reference_overlays([Overlay::new("/refs/hg38", "/references")])
+            .overlay_scratch_dir(parent.path().join("scratch"))
+            .build();
+
+        let scratch = Scratch::new(&config).unwrap().unwrap();
+        let dir = scratch.dir.path().join("0");
+        assert!(
+            scratch
+                .dir
+                .path()
+                .starts_with(parent.path().join("scratch"))
+        );
+        assert!(dir.join("upper").is_dir());
+        assert!(dir.join("work").is_dir());
+
+        let mounts = scratch.mounts(&config);
+        assert_eq!(mounts.len(), 1);
+        assert_eq!(mounts[0].target.as_deref(), Some("/references"));
+        assert_eq!(mounts[0].typ, Some(MountTypeEnum::VOLUME));
+
+        let options = mounts[0]
+            .volume_options
+            .as_ref()
+            .and_then(|options| options.driver_config.as_ref())
+            .and_then(|config| config.options.as_ref())
+            .unwrap();
+        assert_eq!(options["type"], "overlay");
+        assert_eq!(
+            options["o"],
+            format!(
+                "lowerdir=/refs/hg38,upperdir={},workdir={}",
+                dir.join("upper").display(),
+                dir.join("work").display()
+            )
+        );
+
+        let path = scratch.dir.path().to_owned();
+        scratch.finish(false);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn references_are_split() {
+        assert_eq!(split_reference("ubuntu"), ("ubuntu", "latest"));
+        assert_eq!(split_reference("ubuntu:22.04"), ("ubuntu", "22.04"));
+        assert_eq!(
+            split_reference("localhost:5000/foo/bar"),
+            ("localhost:5000/foo/bar", "latest")
+        );
+        assert_eq!(
+            split_reference("localhost:5000/foo/bar:1.0"),
+            ("localhost:5000/foo/bar", "1.0")
+        );
+        assert_eq!(
+            split_reference("ubuntu@sha256:abc"),
+            ("ubuntu", "sha256:abc")
+        );
+    }
+
+    #[test]
+    fn swap_follows_the_memory_limit() {
+        let execution = Execution::builder()
+            .image("ubuntu")
+            .args(["echo", "hello"])
+            .try_build()
+            .unwrap();
+
+        let task = Task::builder()
+            .name("foo")
+            .extend_executions([execution])
+            .resources(ResourcesBuilder::default().ram(1.0).build())
+            .try_build()
+            .unwrap();
+
+        let config = host_config(&task, &Config::default());
+        assert_eq!(config.memory, Some(1024 * 1024 * 1024));
+        assert_eq!(config.memory_swap, None);
+
+        let config = host_config(&task, &Config::builder().disable_swap(true).build());
+        assert_eq!(config.memory, Some(1024 * 1024 * 1024));
+        assert_eq!(config.memory_swap, Some(1024 * 1024 * 1024));
+    }
+
+    #[test]
+    fn users_are_resolved_in_order() {
+        let execution = Execution::builder()
+            .image("ubuntu")
+            .args(["id"])
+            .try_build()
+            .unwrap();
+
+        assert_eq!(user(&execution, &Config::default()), None);
+
+        let config = Config::builder()
+            .user("1000:1000")
+            .run_as_host_user(true)
+            .build();
+        assert_eq!(user(&execution, &config).as_deref(), Some("1000:1000"));
+
+        let execution = Execution::builder()
+            .image("ubuntu")
+            .args(["id"])
+            .user("nobody")
+            .try_build()
+            .unwrap();
+        assert_eq!(user(&execution, &config).as_deref(), Some("nobody"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn host_users_are_numeric() {
+        let execution = Execution::builder()
+            .image("ubuntu")
+            .args(["id"])
+            .try_build()
+            .unwrap();
+
+        let config = Config::builder().run_as_host_user(true).build();
+        let user = user(&execution, &config).unwrap();
+        let (uid, gid) = user.split_once(':').unwrap();
+        assert!(uid.parse::<u32>().is_ok());
+        assert!(gid.parse::<u32>().is_ok());
+    }
+}
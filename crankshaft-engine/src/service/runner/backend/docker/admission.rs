@@ -0,0 +1,200 @@
+//! Admission of tasks based on the capacity of the Docker daemon.
+//!
+//! While a task runs, its containers reserve the CPUs and memory that the task
+//! requested. A task whose request does not fit within the capacity of the
+//! daemon that remains unreserved waits until enough is released by other
+//! tasks (rather than overcommitting the host of the daemon).
+
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use tokio::sync::Notify;
+
+use crate::task::Resources;
+
+/// The number of bytes in a gigabyte.
+const BYTES_PER_GB: f64 = 1024. * 1024. * 1024.;
+
+/// An amount of CPUs and memory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Request {
+    /// The number of CPUs.
+    cpu: u64,
+
+    /// The amount of memory in bytes.
+    memory: u64,
+}
+
+impl Request {
+    /// Creates a new [`Request`] for a number of CPUs and an amount of memory
+    /// (in bytes).
+    pub(crate) fn new(cpu: u64, memory: u64) -> Self {
+        Self { cpu, memory }
+    }
+
+    /// Gets the request of a task with the provided resources.
+    ///
+    /// The soft amount of RAM is requested, as it is the amount used for
+    /// scheduling. Tasks without resources request nothing.
+    pub(crate) fn from_resources(resources: Option<&Resources>) -> Self {
+        Self {
+            cpu: resources
+                .and_then(Resources::cpu)
+                .map(|cpu| cpu as u64)
+                .unwrap_or_default(),
+            memory: resources
+                .and_then(Resources::ram_soft)
+                .map(|ram| (ram * BYTES_PER_GB).round() as u64)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// The admission of tasks to a Docker daemon with a limited capacity.
+#[derive(Debug)]
+pub(crate) struct Admission {
+    /// The capacity of the daemon.
+    capacity: Request,
+
+    /// The capacity currently reserved by running tasks.
+    reserved: Mutex<Request>,
+
+    /// Notified whenever reserved capacity is released.
+    released: Notify,
+}
+
+impl Admission {
+    /// Creates a new [`Admission`] for a daemon with the provided capacity.
+    pub(crate) fn new(capacity: Request) -> Self {
+        Self {
+            capacity,
+            reserved: Default::default(),
+            released: Notify::new(),
+        }
+    }
+
+    /// Attempts to reserve capacity for a request.
+    ///
+    /// Returns [`None`] if the unreserved capacity does not fit the request.
+    fn try_reserve(self: &Arc<Self>, request: Request) -> Option<Reservation> {
+        // NOTE: requests that exceed the capacity of the daemon are clamped to
+        // it so that they still run (by themselves) rather than never running.
+        let request = Request {
+            cpu: request.cpu.min(self.capacity.cpu),
+            memory: request.memory.min(self.capacity.memory),
+        };
+
+        let mut reserved = self.reserved.lock().unwrap();
+
+        if reserved.cpu + request.cpu > self.capacity.cpu
+            || reserved.memory + request.memory > self.capacity.memory
+        {
+            return None;
+        }
+
+        reserved.cpu += request.cpu;
+        reserved.memory += request.memory;
+
+        Some(Reservation {
+            admission: self.clone(),
+            request,
+        })
+    }
+
+    /// Waits until the unreserved capacity fits a request and reserves it.
+    ///
+    /// If the request has to wait, `queued` is called (once) beforehand.
+    pub(crate) async fn reserve(
+        self: &Arc<Self>,
+        request: Request,
+        queued: impl FnOnce(),
+    ) -> Reservation {
+        let mut queued = Some(queued);
+
+        loop {
+            // NOTE: the notification is registered before the capacity is
+            // checked so that capacity released in between is not missed.
+            let released = self.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+
+            if let Some(reservation) = self.try_reserve(request) {
+                return reservation;
+            }
+
+            if let Some(queued) = queued.take() {
+                queued();
+            }
+
+            released.await;
+        }
+    }
+}
+
+/// Capacity of a Docker daemon reserved by a task.
+///
+/// The capacity is released when the reservation is dropped.
+#[derive(Debug)]
+pub(crate) struct Reservation {
+    /// The admission that the capacity was reserved from.
+    admission: Arc<Admission>,
+
+    /// The reserved capacity.
+    request: Request,
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        let mut reserved = self.admission.reserved.lock().unwrap();
+        reserved.cpu -= self.request.cpu;
+        reserved.memory -= self.request.memory;
+        drop(reserved);
+
+        self.admission.released.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::FutureExt as _;
+
+    use super::*;
+
+    #[test]
+    fn requests_wait_for_released_capacity() {
+        let admission = Arc::new(Admission::new(Request::new(4, 8)));
+        let first = admission
+            .reserve(Request::new(3, 2), || panic!("the first request fits"))
+            .now_or_never()
+            .unwrap();
+
+        let mut queued = false;
+        let mut second = Box::pin(admission.reserve(Request::new(2, 2), || queued = true));
+        assert!(second.as_mut().now_or_never().is_none());
+        drop(first);
+        assert!(second.now_or_never().is_some());
+        assert!(queued);
+
+        // Requests that exceed the capacity run by themselves.
+        let oversized = admission
+            .reserve(Request::new(16, 16), || panic!("nothing else is reserved"))
+            .now_or_never()
+            .unwrap();
+        assert_eq!(oversized.request, Request::new(4, 8));
+    }
+
+    #[test]
+    fn requests_are_taken_from_resources() {
+        let resources = crate::task::resources::Builder::default()
+            .cpu(2usize)
+            .ram(4.0)
+            .ram_soft(1.5)
+            .build();
+
+        assert_eq!(
+            Request::from_resources(Some(&resources)),
+            Request::new(2, 3 * 512 * 1024 * 1024)
+        );
+        assert_eq!(Request::from_resources(None), Request::default());
+    }
+}
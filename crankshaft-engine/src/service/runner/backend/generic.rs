@@ -2,26 +2,60 @@
 //!
 //! Generic backends are intended to be relatively maleable and configurable by
 //! the end user without requiring the need to write Rust code.
+//!
+//! Task inputs are [staged](crate::stage) at their paths on the host of the
+//! engine before any command runs (and task outputs are uploaded from their
+//! paths once every execution succeeded), so they are only supported when the
+//! driver runs commands locally.
 
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Output;
 use std::sync::Arc;
 use std::time::Duration;
 
 use crankshaft_config::backend::Defaults;
+use crankshaft_config::backend::Kind;
 use crankshaft_config::backend::generic::Config;
+use crankshaft_config::backend::generic::ResolveResult;
+use crankshaft_config::backend::generic::substitute;
 use eyre::Context as _;
+use eyre::bail;
+use eyre::eyre;
 use futures::FutureExt;
 use futures::future::BoxFuture;
 use nonempty::NonEmpty;
 use regex::Regex;
+use tokio::time::Instant;
+use tracing::debug;
 use tracing::warn;
 
 use crate::Result;
 use crate::Task;
+use crate::events::ResourceUsage;
+use crate::events::TaskEvents;
+use crate::events::TaskId;
+use crate::service::runner::backend::Accounting;
+use crate::service::runner::backend::FailureReason;
 use crate::service::runner::backend::TaskResult;
 use crate::service::runner::backend::generic::driver::Driver;
+use crate::service::runner::backend::generic::driver::Lease;
+use crate::service::runner::backend::generic::driver::Transport;
+use crate::service::runner::backend::generic::driver::exit_status;
+use crate::service::runner::backend::generic::driver::quote;
+use crate::service::runner::backend::generic::driver::with_env;
+use crate::service::runner::backend::generic::preview::Rendered;
+use crate::service::runner::backend::provenance::ExecutionProvenance;
+use crate::service::runner::backend::provenance::Provenance;
+use crate::service::runner::backend::redirect;
+use crate::stage::Stager;
+use crate::task::Execution;
 use crate::task::Resources;
+use crate::task::output::Uploaders;
+use crate::task::output::uploader;
 
 pub mod driver;
+pub mod preview;
 
 /// The default number of seconds to wait between monitor commands.
 pub const DEFAULT_MONITOR_FREQUENCY: u64 = 5;
@@ -35,23 +69,50 @@ pub struct Backend {
     /// The inner configuration.
     config: Config,
 
+    /// The compiled job id regex (if one is configured).
+    job_id_regex: Option<Regex>,
+
     /// The execution defaults.
     defaults: Option<Defaults>,
+
+    /// The stager of task inputs.
+    stager: Stager,
 }
 
 impl Backend {
     /// Attempts to initialize a new generic [`Backend`] with the default
     /// connection settings and the provided configuration for the backend.
     pub async fn initialize(config: Config, defaults: Option<Defaults>) -> Result<Self> {
+        // NOTE: the exit code file is read using POSIX shell syntax (see
+        // [`EXIT_CODE_COMMAND`]).
+        if config.uses_exit_code_file()
+            && !config.driver().shell().is_none_or(|shell| shell.is_posix())
+        {
+            bail!("the `~{{exit_code_file}}` substitution requires a POSIX shell");
+        }
+
+        let job_id_regex = config
+            .job_id_regex()
+            .map(Regex::new)
+            .transpose()
+            .context("compiling the job id regex")?;
+
         // TODO(clay): this could be "taken" instead to avoid the clone.
         let driver = Driver::initialize(config.driver().clone())
             .await
             .map(Arc::new)?;
 
+        let stager = Stager::default().with_uploaders(uploaders(
+            config.output_upload_concurrency(),
+            config.output_upload_retries(),
+        ));
+
         Ok(Self {
             driver,
             config,
+            job_id_regex,
             defaults,
+            stager,
         })
     }
 
@@ -65,26 +126,200 @@ impl Backend {
         &self.driver
     }
 
-    /// Resolves the resources for a particular task.
-    // NOTE: first, the default resources from the code are assumed. Then, the
-    // default resources from the configuration are applied (if they are
-    // provided). Last, the resources from the execution itself are applied.
-    // This is the relative level of priority resource resolution should have,
-    // and the order is important to preserve.
-    fn resolve_resources(&self, task: Option<&Resources>) -> Option<Resources> {
-        let mut resources: Option<Resources> = None;
+    /// Attempts to initialize the generic backend with the provided name from
+    /// a configuration.
+    ///
+    /// This is intended for tooling that works with a single backend of a
+    /// configuration (e.g., when [previewing](Self::preview) its commands).
+    pub async fn initialize_named(config: &crankshaft_config::Config, name: &str) -> Result<Self> {
+        let backend = config
+            .backends()
+            .iter()
+            .find(|backend| backend.name() == name)
+            .ok_or_else(|| eyre!("backend `{name}` was not found"))?;
 
-        if let Some(defaults) = &self.defaults {
-            let defaults = Resources::from(defaults);
-            resources = Some(resources.unwrap_or_default().apply(&defaults));
+        match backend.kind() {
+            Kind::Generic(generic) => {
+                Self::initialize(generic.clone(), backend.defaults().cloned()).await
+            }
+            _ => bail!("backend `{name}` is not a generic backend"),
         }
+    }
+
+    /// Renders every configured command for each execution within a sample
+    /// task.
+    ///
+    /// The task is rendered as the first task submitted to an engine (i.e.,
+    /// with a task id of zero). As the id of a job is only known once it is
+    /// submitted, `~{job_id}` placeholders are replaced with the provided job
+    /// id (or left unresolved if one isn't provided).
+    ///
+    /// Nothing is run.
+    pub fn preview(&self, task: &Task, job_id: Option<&str>) -> Vec<Rendered> {
+        task.executions()
+            .enumerate()
+            .flat_map(|(index, execution)| {
+                let substitutions = self.sample_substitutions(task, execution, index, job_id);
+                let exit_code = substitutions
+                    .get("exit_code_file")
+                    .map(|path| HashMap::from([(String::from("exit_code_file"), path.clone())]));
+
+                preview::render(
+                    &self.config,
+                    index,
+                    &substitutions,
+                    exit_code
+                        .as_ref()
+                        .map(|substitutions| (EXIT_CODE_COMMAND, substitutions)),
+                )
+            })
+            .collect()
+    }
+
+    /// Runs only the monitor command for an existing job through the driver
+    /// of the backend, as a dry probe of the monitor template.
+    ///
+    /// The command is rendered for the first execution within a sample task
+    /// (see [`Self::preview()`]). Nothing is submitted or killed, and the
+    /// output of the command is returned as is (where a successful exit
+    /// indicates that the job is still running).
+    pub async fn probe_monitor(&self, task: &Task, job_id: &str) -> Result<Output> {
+        // SAFETY: tasks always have at least one execution.
+        let execution = task.executions().next().unwrap();
+        let substitutions = self.sample_substitutions(task, execution, 0, Some(job_id));
+        let monitor = self.config.resolve_monitor(&substitutions)?;
+
+        self.driver.run(monitor).await
+    }
+
+    /// Builds the substitutions of an execution within a sample task.
+    fn sample_substitutions(
+        &self,
+        task: &Task,
+        execution: &Execution,
+        index: usize,
+        job_id: Option<&str>,
+    ) -> HashMap<String, String> {
+        let defaults = self
+            .resolve_resources(task.resources())
+            .and_then(|resources| resources.to_hashmap())
+            .unwrap_or_default();
+
+        let mut substitutions = substitutions(&self.config, &defaults, execution, index, 0);
 
-        if let Some(task) = task {
-            resources = Some(resources.unwrap_or_default().apply(task));
+        if let Some(job_id) = job_id {
+            substitutions.insert(String::from("job_id"), job_id.to_owned());
         }
 
-        resources
+        substitutions
+    }
+
+    /// Resolves the resources for a particular task.
+    fn resolve_resources(&self, task: Option<&Resources>) -> Option<Resources> {
+        resolve_resources(self.defaults.as_ref(), task)
+    }
+}
+
+/// The command used to read (and remove) the exit code file of a job.
+///
+/// The `~{exit_code_file}` substitution is the quoted path of the file (as it
+/// is within the submit command).
+const EXIT_CODE_COMMAND: &str = "cat ~{exit_code_file} && rm -f ~{exit_code_file}";
+
+/// Builds the substitutions for an execution within a task.
+///
+/// The `defaults` are the substitutions shared by every execution within the
+/// task (i.e., those of its resources).
+fn substitutions(
+    config: &Config,
+    defaults: &HashMap<String, String>,
+    execution: &Execution,
+    index: usize,
+    task_id: TaskId,
+) -> HashMap<String, String> {
+    // TODO(clay): surely we can do better than a reallocation here.
+    let shell = with_env(
+        execution,
+        execution
+            .args()
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<String>>()
+            .join(" "),
+    );
+
+    let mut subtitutions = defaults.clone();
+
+    if subtitutions.insert(String::from("shell"), shell).is_some() {
+        unreachable!("the `shell` key should not be present here");
+    };
+
+    if let Some(cwd) = execution.workdir() {
+        if subtitutions
+            .insert(String::from("cwd"), cwd.into())
+            .is_some()
+        {
+            unreachable!("the `cwd` key should not be present here");
+        };
+    }
+
+    if config.uses_exit_code_file() {
+        // NOTE: the process id is included so that engines sharing a working
+        // directory don't clobber each other's files.
+        let file = format!(".crankshaft-{}-{task_id}-{index}.exit", std::process::id());
+
+        let path = match execution.workdir() {
+            Some(cwd) => format!("{}/{file}", cwd.trim_end_matches('/')),
+            None => file,
+        };
+
+        // NOTE: the path is quoted once here so that it is substituted the
+        // same way into every command (i.e., templates must not quote it).
+        subtitutions.insert(String::from("exit_code_file"), quote(&path));
+    }
+
+    subtitutions
+}
+
+/// Gets the uploaders of task outputs for a backend with the provided upload
+/// concurrency and retries (if they are configured).
+pub(crate) fn uploaders(concurrency: Option<usize>, retries: Option<u32>) -> Uploaders {
+    let mut uploaders = Uploaders::default().with(uploader::Http::default());
+
+    if let Some(concurrency) = concurrency {
+        uploaders = uploaders.concurrency(concurrency);
+    }
+
+    if let Some(retries) = retries {
+        uploaders = uploaders.retries(retries);
+    }
+
+    uploaders
+}
+
+/// Resolves the resources for a particular task from the execution defaults of
+/// a backend.
+// NOTE: first, the default resources from the code are assumed. Then, the
+// default resources from the configuration are applied (if they are
+// provided). Last, the resources from the execution itself are applied.
+// This is the relative level of priority resource resolution should have,
+// and the order is important to preserve.
+pub(crate) fn resolve_resources(
+    defaults: Option<&Defaults>,
+    task: Option<&Resources>,
+) -> Option<Resources> {
+    let mut resources: Option<Resources> = None;
+
+    if let Some(defaults) = defaults {
+        let defaults = Resources::from(defaults);
+        resources = Some(resources.unwrap_or_default().apply(&defaults));
+    }
+
+    if let Some(task) = task {
+        resources = Some(resources.unwrap_or_default().apply(task));
     }
+
+    resources
 }
 
 impl crate::Backend for Backend {
@@ -93,25 +328,78 @@ impl crate::Backend for Backend {
         "generic"
     }
 
+    /// Checks that a task does not exceed the maximum resources of the
+    /// backend.
+    // NOTE: tasks exceeding the maximums of the backend are rejected before
+    // submission, as schedulers often queue such jobs forever.
+    fn check(&self, task: &Task) -> std::result::Result<(), FailureReason> {
+        let exceeded = self
+            .resolve_resources(task.resources())
+            .and_then(|resources| {
+                self.config
+                    .check_limits(resources.cpu(), resources.ram_hard())
+            });
+
+        match exceeded {
+            Some(exceeded) => Err(FailureReason::ExceedsLimits(exceeded)),
+            None => Ok(()),
+        }
+    }
+
+    /// Gets whether or not the backend reports the resources used by its
+    /// running tasks (i.e., whether a resource usage command is configured).
+    fn reports_resource_usage(&self) -> bool {
+        self.config.usage().is_some()
+    }
+
+    /// Gets whether or not the backend terminates its running tasks when
+    /// they are canceled (by running the kill command).
+    fn terminates_gracefully(&self) -> bool {
+        true
+    }
+
     /// Runs a task in a backend.
-    fn run(&self, task: Task) -> BoxFuture<'static, TaskResult> {
+    fn run(
+        &self,
+        task: Task,
+        events: TaskEvents,
+    ) -> BoxFuture<'static, std::result::Result<TaskResult, FailureReason>> {
         let driver = self.driver.clone();
         let config = self.config.clone();
+        let job_id_regex = self.job_id_regex.clone();
+        let stager = self.stager.clone();
 
-        let default_substitutions = self
-            .resolve_resources(task.resources())
+        let resources = self.resolve_resources(task.resources());
+        let default_substitutions = resources
+            .as_ref()
             .and_then(|resources| resources.to_hashmap())
             .unwrap_or_default();
 
         async move {
+            // NOTE: inputs and outputs are staged at their paths on the host
+            // of the engine, so they are only available to commands that run
+            // there.
+            if (task.inputs().is_some() || task.outputs().is_some())
+                && !matches!(driver.transport(), Transport::Local)
+            {
+                return Err(FailureReason::Unsupported(String::from(
+                    "task inputs and outputs are only supported by generic backends with a local \
+                     driver",
+                )));
+            }
+
+            if let Some(inputs) = task.inputs() {
+                stager
+                    .stage_all(inputs, None)
+                    .await
+                    .map_err(|err| FailureReason::Request(format!("staging inputs: {err}")))?;
+            }
+
             let mut outputs = Vec::new();
-            let job_id_regex = config.job_id_regex().map(|pattern| {
-                Regex::new(pattern)
-                    .context("compiling job id regex")
-                    .unwrap()
-            });
+            let mut accounting = HashMap::new();
+            let mut provenance = Provenance::default().with_resources(resources);
 
-            for execution in task.executions() {
+            for (index, execution) in task.executions().enumerate() {
                 // TODO(clay): this will warn every time for now. We need to
                 // change the model of how tasks are done internally to remove
                 // this need.
@@ -121,75 +409,285 @@ impl crate::Backend for Backend {
                     execution.image()
                 );
 
-                // TODO(clay): surely we can do better than a reallocation here.
-                let shell = execution
-                    .args()
-                    .into_iter()
-                    .map(String::from)
-                    .collect::<Vec<String>>()
-                    .join(" ");
-
-                let mut subtitutions = default_substitutions.clone();
-
-                if subtitutions.insert(String::from("shell"), shell).is_some() {
-                    unreachable!("the `shell` key should not be present here");
-                };
-
-                if let Some(cwd) = execution.workdir() {
-                    if subtitutions
-                        .insert(String::from("cwd"), cwd.into())
-                        .is_some()
-                    {
-                        unreachable!("the `cwd` key should not be present here");
-                    };
-                }
+                let mut subtitutions = substitutions(
+                    &config,
+                    &default_substitutions,
+                    execution,
+                    index,
+                    events.task_id(),
+                );
+
+                // NOTE: the submit and monitor commands of an execution must
+                // run in the same place (e.g., on the same host of a pool).
+                let lease = driver
+                    .lease()
+                    .await
+                    .map_err(|err| FailureReason::Request(format!("leasing a host: {err}")))?;
+
+                events.execution_started(index);
 
                 // (1) Submitting the initial job.
-                // TODO(clay): we should probably handle this more gracefully.
-                let submit = config.resolve_submit(&subtitutions).unwrap();
+                let submit = config.resolve_submit(&subtitutions).map_err(|err| {
+                    FailureReason::Request(format!("resolving the submit command: {err}"))
+                })?;
 
-                // TODO(clay): we should probably handle this more gracefully.
-                let output = driver.run(submit).await.unwrap();
+                // NOTE: a host of a pool that fails to run the command is
+                // marked as unreachable by the lease, so only this task fails.
+                let output = lease.run(submit).await.map_err(|err| {
+                    FailureReason::Request(format!("running the submit command: {err}"))
+                })?;
 
                 // (2) Monitoring the output.
                 match job_id_regex {
                     Some(ref regex) => {
                         let stdout = String::from_utf8_lossy(&output.stdout);
-                        let captures = regex.captures_iter(&stdout).next().unwrap_or_else(|| {
-                            panic!(
-                                "could not match the job id regex within stdout: `{}`",
-                                stdout
-                            )
-                        });
-
-                        // SAFETY: this will always unwrap, as the group is
-                        // _required_ for the pattern to match.
-                        let id = captures.get(1).map(|c| String::from(c.as_str())).unwrap();
+
+                        // NOTE: the job id is the first capture group of the
+                        // pattern, so a pattern without a group never matches.
+                        let id = match regex.captures(&stdout).and_then(|c| c.get(1)) {
+                            Some(id) => String::from(id.as_str()),
+                            None => {
+                                return Err(FailureReason::SubmissionParseError {
+                                    stdout: stdout.into_owned(),
+                                    stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+                                    pattern: regex.as_str().to_owned(),
+                                });
+                            }
+                        };
+
+                        provenance.push_execution(ExecutionProvenance::new(
+                            execution.image(),
+                            None,
+                            Some(id.clone()),
+                        ));
                         subtitutions.insert(String::from("job_id"), id);
 
+                        // NOTE: this is when the job was first observed to be
+                        // held or suspended (if it currently is).
+                        let mut suspended_since: Option<Instant> = None;
+
                         loop {
-                            let monitor = config.resolve_monitor(&subtitutions).unwrap();
-                            let output = driver.run(monitor).await.unwrap();
+                            let monitor = config.resolve_monitor(&subtitutions).map_err(|err| {
+                                FailureReason::Request(format!(
+                                    "resolving the monitor command: {err}"
+                                ))
+                            })?;
+                            let mut output = lease.run(monitor).await.map_err(|err| {
+                                FailureReason::Request(format!(
+                                    "running the monitor command: {err}"
+                                ))
+                            })?;
 
                             if !output.status.success() {
-                                outputs.push(output);
+                                if let Some(path) = subtitutions.get("exit_code_file") {
+                                    match read_exit_code(&lease, path).await {
+                                        Ok(status) => output.status = status,
+                                        Err(err) => warn!(
+                                            "failed to read the exit code of job `{}`: {err}",
+                                            subtitutions["job_id"]
+                                        ),
+                                    }
+                                }
+
+                                events.execution_finished(index, output.status);
+                                outputs.push(redirect(execution, output).await);
+
+                                // (4) Querying the accounting data of the
+                                // completed job.
+                                if let Some(command) = config.resolve_accounting(&subtitutions) {
+                                    // NOTE: accounting is best-effort, so a job
+                                    // whose accounting data could not be
+                                    // queried is still considered finished.
+                                    let output = match run_resolved(&lease, command).await {
+                                        Ok(output) => output,
+                                        Err(err) => {
+                                            warn!(
+                                                "failed to query the accounting data of job `{}`: \
+                                                 {err}",
+                                                subtitutions["job_id"]
+                                            );
+                                            break;
+                                        }
+                                    };
+
+                                    match parse_accounting(&String::from_utf8_lossy(&output.stdout))
+                                    {
+                                        Ok(data) if output.status.success() => {
+                                            events.accounted(index, data.clone());
+                                            accounting.insert(index, data);
+                                        }
+                                        Ok(_) => warn!(
+                                            "accounting command for job `{}` exited with {}",
+                                            subtitutions["job_id"], output.status
+                                        ),
+                                        Err(err) => warn!(
+                                            "failed to parse the accounting data of job `{}`: \
+                                             {err}",
+                                            subtitutions["job_id"]
+                                        ),
+                                    }
+                                }
+
                                 break;
                             }
 
-                            tokio::time::sleep(Duration::from_secs(
+                            // (3) Checking whether the job is held or
+                            // suspended by the scheduler.
+                            let state = match config.resolve_state(&subtitutions) {
+                                Some(command) => {
+                                    run_resolved(&lease, command).await.map(|output| {
+                                        Some(
+                                            String::from_utf8_lossy(&output.stdout)
+                                                .trim()
+                                                .to_owned(),
+                                        )
+                                    })
+                                }
+                                None => Ok(None),
+                            };
+
+                            match state {
+                                // NOTE: the state of a job that could not be
+                                // queried is unknown, so the job is neither
+                                // considered held nor running until the next
+                                // poll.
+                                Err(err) => warn!(
+                                    "failed to query the state of job `{}`: {err}",
+                                    subtitutions["job_id"]
+                                ),
+                                Ok(Some(state)) if config.is_suspended(&state) => {
+                                    let since = *suspended_since.get_or_insert_with(|| {
+                                        events.suspended(state.clone());
+                                        Instant::now()
+                                    });
+
+                                    let grace =
+                                        config.suspended_grace_period().map(Duration::from_secs);
+
+                                    if grace.is_some_and(|grace| since.elapsed() >= grace) {
+                                        match config.resolve_release(&subtitutions) {
+                                            Some(release) => {
+                                                warn!(
+                                                    "releasing job `{}` after it remained in the \
+                                                     `{state}` state for longer than the grace \
+                                                     period",
+                                                    subtitutions["job_id"]
+                                                );
+
+                                                // NOTE: a release that failed is
+                                                // attempted again at the next poll.
+                                                match run_resolved(&lease, release).await {
+                                                    Ok(_) => suspended_since = None,
+                                                    Err(err) => warn!(
+                                                        "failed to release job `{}`: {err}",
+                                                        subtitutions["job_id"]
+                                                    ),
+                                                }
+                                            }
+                                            None => {
+                                                // NOTE: the job is killed so that it doesn't
+                                                // linger within the scheduler after the task
+                                                // has failed.
+                                                let kill =
+                                                    config.resolve_kill(subtitutions.clone());
+
+                                                if let Err(err) = run_resolved(&lease, kill).await {
+                                                    warn!(
+                                                        "failed to kill suspended job `{}`: {err}",
+                                                        subtitutions["job_id"]
+                                                    );
+                                                }
+
+                                                return Err(FailureReason::Suspended(state));
+                                            }
+                                        }
+                                    }
+                                }
+                                Ok(_) => {
+                                    suspended_since = None;
+                                    events.heartbeat();
+
+                                    if let Some(command) = config.resolve_usage(&subtitutions) {
+                                        match run_resolved(&lease, command).await {
+                                            Err(err) => debug!(
+                                                "failed to query the resource usage of job `{}`: \
+                                                 {err}",
+                                                subtitutions["job_id"]
+                                            ),
+                                            Ok(output) => match parse_usage(
+                                                &String::from_utf8_lossy(&output.stdout),
+                                            ) {
+                                                Ok(usage) if output.status.success() => {
+                                                    events.resource_usage(usage)
+                                                }
+                                                Ok(_) => debug!(
+                                                    "resource usage command for job `{}` exited \
+                                                     with {}",
+                                                    subtitutions["job_id"], output.status
+                                                ),
+                                                Err(err) => debug!(
+                                                    "failed to parse the resource usage of job \
+                                                     `{}`: {err}",
+                                                    subtitutions["job_id"]
+                                                ),
+                                            },
+                                        }
+                                    }
+                                }
+                            }
+
+                            let frequency = Duration::from_secs(
                                 config
                                     .monitor_frequency()
                                     .unwrap_or(DEFAULT_MONITOR_FREQUENCY),
-                            ))
-                            .await;
+                            );
+
+                            tokio::select! {
+                                _ = tokio::time::sleep(frequency) => {}
+                                _ = events.terminated() => {
+                                    terminate(
+                                        &lease,
+                                        &config,
+                                        &subtitutions,
+                                        frequency,
+                                        events.grace_period(),
+                                    )
+                                    .await;
+
+                                    return Err(FailureReason::Request(String::from(
+                                        "the task was terminated",
+                                    )));
+                                }
+                            }
                         }
                     }
                     _ => {
-                        outputs.push(output);
+                        provenance.push_execution(ExecutionProvenance::new(
+                            execution.image(),
+                            None,
+                            None,
+                        ));
+                        events.execution_finished(index, output.status);
+                        outputs.push(redirect(execution, output).await);
                     }
                 }
             }
 
+            // NOTE: outputs are only uploaded once every execution succeeded.
+            if let Some(uploads) = task.outputs() {
+                if outputs.iter().all(|output| output.status.success()) {
+                    stager
+                        .upload_all(
+                            uploads.map(|output| (output, PathBuf::from(output.path()))),
+                            &events,
+                        )
+                        .await
+                        .map_err(|err| {
+                            FailureReason::Request(format!("uploading outputs: {err}"))
+                        })?;
+                }
+            }
+
             let mut outputs = outputs.into_iter();
 
             // SAFETY: each task _must_ have at least one execution, so at least one
@@ -197,8 +695,833 @@ impl crate::Backend for Backend {
             let mut executions = NonEmpty::new(outputs.next().unwrap());
             executions.extend(outputs);
 
-            TaskResult { executions }
+            Ok(TaskResult {
+                executions,
+                provenance,
+                accounting,
+            })
         }
         .boxed()
     }
 }
+
+/// Terminates a job that is running within the scheduler.
+///
+/// The kill command runs first. If a force kill command is configured, the job
+/// is then monitored (at the provided frequency) until it exits or the grace
+/// period elapses, at which point the force kill command runs.
+async fn terminate(
+    lease: &Lease<'_>,
+    config: &Config,
+    substitutions: &HashMap<String, String>,
+    frequency: Duration,
+    grace_period: Duration,
+) {
+    let job_id = &substitutions["job_id"];
+
+    match config.resolve_kill(substitutions.clone()) {
+        Ok(kill) => {
+            if let Err(err) = lease.run(kill).await {
+                warn!("failed to kill job `{job_id}`: {err}");
+            }
+        }
+        Err(err) => warn!("failed to kill job `{job_id}`: {err}"),
+    }
+
+    let Some(force_kill) = config.resolve_force_kill(substitutions) else {
+        return;
+    };
+
+    let deadline = Instant::now() + grace_period;
+
+    loop {
+        // NOTE: the monitor command exits with a non-zero exit code once the
+        // job is no longer running.
+        if let Ok(monitor) = config.resolve_monitor(substitutions) {
+            match lease.run(monitor).await {
+                Ok(output) if !output.status.success() => return,
+                Ok(_) => {}
+                Err(err) => debug!("failed to monitor job `{job_id}`: {err}"),
+            }
+        }
+
+        let now = Instant::now();
+
+        if now >= deadline {
+            break;
+        }
+
+        tokio::time::sleep(frequency.min(deadline - now)).await;
+    }
+
+    debug!("job `{job_id}` is still running after the grace period; force killing it");
+
+    match force_kill {
+        Ok(force_kill) => {
+            if let Err(err) = lease.run(force_kill).await {
+                warn!("failed to force kill job `{job_id}`: {err}");
+            }
+        }
+        Err(err) => warn!("failed to force kill job `{job_id}`: {err}"),
+    }
+}
+
+/// Runs a command resolved from a template of the backend (if it resolved).
+///
+/// This is used for the commands that are only advisory (e.g., querying the
+/// state of a job), whose failures are reported by the caller rather than
+/// failing the task.
+async fn run_resolved(
+    lease: &Lease<'_>,
+    command: ResolveResult,
+) -> std::result::Result<Output, String> {
+    let command = command.map_err(|err| format!("resolving the command: {err}"))?;
+    lease.run(command).await.map_err(|err| err.to_string())
+}
+
+/// Reads (and removes) the file that the exit code of a job was written to.
+///
+/// The `path` is the (already quoted) `~{exit_code_file}` substitution.
+async fn read_exit_code(
+    lease: &Lease<'_>,
+    path: &str,
+) -> std::result::Result<std::process::ExitStatus, String> {
+    let command = substitute(
+        EXIT_CODE_COMMAND,
+        &HashMap::from([(String::from("exit_code_file"), path.to_owned())]),
+    );
+    let output = lease.run(command).await.map_err(|err| err.to_string())?;
+
+    if !output.status.success() {
+        return Err(format!("reading `{path}` exited with {}", output.status));
+    }
+
+    let contents = String::from_utf8_lossy(&output.stdout);
+    contents
+        .trim()
+        .parse()
+        .map(exit_status)
+        .map_err(|_| format!("`{path}` does not contain an exit code: `{contents}`"))
+}
+
+/// Parses the output of an accounting command.
+///
+/// The output must either be a JSON object or `key=value` pairs (one per line,
+/// with blank lines ignored). Values of `key=value` pairs that are numbers are
+/// parsed as such.
+fn parse_accounting(output: &str) -> std::result::Result<Accounting, String> {
+    let output = output.trim();
+
+    if output.starts_with('{') {
+        return serde_json::from_str(output).map_err(|err| err.to_string());
+    }
+
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line `{line}` is not a `key=value` pair"))?;
+            let value = value.trim();
+
+            let value = match serde_json::from_str::<serde_json::Number>(value) {
+                Ok(number) => serde_json::Value::Number(number),
+                Err(_) => serde_json::Value::String(value.to_owned()),
+            };
+
+            Ok((key.trim().to_owned(), value))
+        })
+        .collect()
+}
+
+/// Parses the output of a resource usage command.
+///
+/// The output has the same format as that of an accounting command, where only
+/// the numeric `cpu_percent`, `memory`, and `disk` keys are used.
+fn parse_usage(output: &str) -> std::result::Result<ResourceUsage, String> {
+    let data = parse_accounting(output)?;
+
+    let number = |key: &str| match data.get(key) {
+        Some(value) if !value.is_number() => {
+            Err(format!("value of `{key}` is not a number: {value}"))
+        }
+        value => Ok(value),
+    };
+
+    let bytes = |key: &str| {
+        number(key)?
+            .map(|value| {
+                value
+                    .as_u64()
+                    .ok_or_else(|| format!("value of `{key}` is not a number of bytes: {value}"))
+            })
+            .transpose()
+    };
+
+    Ok(ResourceUsage {
+        cpu_percent: number("cpu_percent")?.and_then(serde_json::Value::as_f64),
+        memory: bytes("memory")?,
+        disk: bytes("disk")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crankshaft_config::backend::generic::Config;
+
+    use super::*;
+    use crate::Backend as _;
+    use crate::events::Event;
+    use crate::events::Events;
+    use crate::service::runner::backend::generic::preview::Command;
+    use crate::task::Execution;
+
+    #[test]
+    fn accounting_is_parsed() {
+        let accounting = parse_accounting("\nqueue=normal\ncpu_hours = 1.5\n\n").unwrap();
+        assert_eq!(accounting["queue"], "normal");
+        assert_eq!(accounting["cpu_hours"], 1.5);
+
+        let accounting = parse_accounting(r#"{"queue": "normal", "cpu_hours": 1.5}"#).unwrap();
+        assert_eq!(accounting["queue"], "normal");
+        assert_eq!(accounting["cpu_hours"], 1.5);
+
+        assert!(parse_accounting("queue normal").is_err());
+        assert!(parse_accounting("{ not json").is_err());
+    }
+
+    #[test]
+    fn resource_usage_is_parsed() {
+        let usage = parse_usage("cpu_percent=150.5\nmemory=2048\nnode=a01").unwrap();
+        assert_eq!(
+            usage,
+            ResourceUsage {
+                cpu_percent: Some(150.5),
+                memory: Some(2048),
+                disk: None,
+            }
+        );
+
+        let usage = parse_usage(r#"{"cpu_percent": 100, "disk": 4096}"#).unwrap();
+        assert_eq!(usage.cpu_percent, Some(100.));
+        assert_eq!(usage.disk, Some(4096));
+
+        assert!(parse_usage("memory=lots").is_err());
+        assert!(parse_usage("memory=1.5").is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn failed_advisory_commands_do_not_fail_the_task() {
+        let dir = tempfile::tempdir().unwrap();
+        let monitored = dir.path().join("monitored");
+
+        // NOTE: the state and accounting commands cannot be resolved, as no
+        // value exists for their placeholder.
+        let config = Config::builder()
+            .default_driver()
+            .submit("echo 'Submitted job 42'")
+            .job_id_regex(r"Submitted job (\d+)")
+            .monitor(format!(
+                "test ! -e {path} && touch {path}",
+                path = monitored.display()
+            ))
+            .monitor_frequency(0u64)
+            .state("echo ~{missing}")
+            .accounting("echo ~{missing}")
+            .kill("true")
+            .try_build()
+            .unwrap();
+
+        let backend = Backend::initialize(config, None).await.unwrap();
+
+        let task = Task::builder()
+            .extend_executions([Execution::builder()
+                .image("ubuntu")
+                .args(["echo", "hello"])
+                .try_build()
+                .unwrap()])
+            .try_build()
+            .unwrap();
+
+        // NOTE: the job is only reported as finished if the failed queries
+        // didn't abort the monitoring of the job.
+        let events = Events::default();
+        backend
+            .run(task, events.task(events.next_id(), "generic"))
+            .await
+            .unwrap();
+        assert!(monitored.exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn resource_usage_is_probed_while_running() {
+        let dir = tempfile::tempdir().unwrap();
+        let monitored = dir.path().join("monitored");
+
+        // NOTE: the job is reported as running the first time it is
+        // monitored (and as finished afterwards).
+        let config = Config::builder()
+            .default_driver()
+            .submit("echo 'Submitted job 42'")
+            .job_id_regex(r"Submitted job (\d+)")
+            .monitor(format!(
+                "test ! -e {path} && touch {path}",
+                path = monitored.display()
+            ))
+            .monitor_frequency(0u64)
+            .usage("echo cpu_percent=50; echo memory=~{job_id}")
+            .kill("true")
+            .try_build()
+            .unwrap();
+
+        let backend = Backend::initialize(config, None).await.unwrap();
+        assert!(backend.reports_resource_usage());
+
+        let task = Task::builder()
+            .name("foo")
+            .extend_executions([Execution::builder()
+                .image("ubuntu")
+                .args(["echo", "hello"])
+                .try_build()
+                .unwrap()])
+            .try_build()
+            .unwrap();
+
+        let events = Events::default();
+        let mut receiver = events.subscribe();
+        backend
+            .run(task, events.task(events.next_id(), "generic"))
+            .await
+            .unwrap();
+
+        let mut samples = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            if let Event::TaskResourceUsage { usage, .. } = event {
+                samples.push(usage);
+            }
+        }
+
+        assert_eq!(
+            samples,
+            [ResourceUsage {
+                cpu_percent: Some(50.),
+                memory: Some(42),
+                disk: None,
+            }]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn terminated_jobs_are_force_killed_after_the_grace_period() {
+        let dir = tempfile::tempdir().unwrap();
+        let killed = dir.path().join("killed");
+        let forced = dir.path().join("forced");
+
+        // NOTE: the job ignores the kill command and keeps running until it is
+        // force killed.
+        let config = Config::builder()
+            .default_driver()
+            .submit("echo 'Submitted job 42'")
+            .job_id_regex(r"Submitted job (\d+)")
+            .monitor(format!("test ! -e {}", forced.display()))
+            .monitor_frequency(0u64)
+            .kill(format!("touch {}", killed.display()))
+            .force_kill(format!("touch {}", forced.display()))
+            .try_build()
+            .unwrap();
+
+        let backend = Backend::initialize(config, None).await.unwrap();
+        assert!(backend.terminates_gracefully());
+
+        let task = Task::builder()
+            .name("foo")
+            .extend_executions([Execution::builder()
+                .image("ubuntu")
+                .args(["sleep", "infinity"])
+                .try_build()
+                .unwrap()])
+            .try_build()
+            .unwrap();
+
+        let events = Events::default();
+        let events = events
+            .task(events.next_id(), "generic")
+            .with_grace_period(Duration::from_millis(100));
+        events.terminate();
+
+        let err = backend.run(task, events).await.unwrap_err();
+        assert!(matches!(err, FailureReason::Request(_)));
+        assert!(killed.exists());
+        assert!(forced.exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn commands_are_previewed_and_probed() {
+        let config = Config::builder()
+            .default_driver()
+            .submit("(~{shell}); echo $? > ~{exit_code_file}; echo 'Submitted job 42'")
+            .job_id_regex(r"Submitted job (\d+)")
+            .monitor("echo running ~{job_id}")
+            .kill("scancel ~{job_id}")
+            .accounting("sacct -j ~{job_id} --cpus ~{cpu}")
+            .try_build()
+            .unwrap();
+
+        let backend = crankshaft_config::Config::builder()
+            .push_backend(
+                crankshaft_config::backend::Config::builder()
+                    .name("cluster")
+                    .kind(Kind::Generic(config))
+                    .max_tasks(1)
+                    .try_build()
+                    .unwrap(),
+            )
+            .push_backend(
+                crankshaft_config::backend::Config::builder()
+                    .name("local")
+                    .kind(Kind::Local(Default::default()))
+                    .max_tasks(1)
+                    .try_build()
+                    .unwrap(),
+            )
+            .build();
+
+        assert!(
+            Backend::initialize_named(&backend, "missing")
+                .await
+                .is_err()
+        );
+        assert!(Backend::initialize_named(&backend, "local").await.is_err());
+        let backend = Backend::initialize_named(&backend, "cluster")
+            .await
+            .unwrap();
+
+        let task = Task::builder()
+            .name("foo")
+            .extend_executions([Execution::builder()
+                .image("ubuntu")
+                .args(["echo", "hello"])
+                .working_directory("/work")
+                .try_build()
+                .unwrap()])
+            .resources(
+                crate::task::resources::Builder::default()
+                    .cpu(2usize)
+                    .build(),
+            )
+            .try_build()
+            .unwrap();
+
+        let file = format!("/work/.crankshaft-{}-0-0.exit", std::process::id());
+        let rendered = backend.preview(&task, None);
+
+        assert_eq!(
+            rendered.iter().map(Rendered::command).collect::<Vec<_>>(),
+            [
+                Command::Submit,
+                Command::Monitor,
+                Command::ExitCode,
+                Command::Accounting,
+                Command::Kill
+            ]
+        );
+        assert_eq!(
+            rendered[0].to_string(),
+            format!("(echo hello); echo $? > {file}; echo 'Submitted job 42'")
+        );
+        assert_eq!(
+            rendered[2].to_string(),
+            format!("cat {file} && rm -f {file}")
+        );
+        assert_eq!(rendered[3].unresolved().collect::<Vec<_>>(), ["job_id"]);
+        assert_eq!(
+            rendered[3].highlighted(),
+            "sacct -j \x1b[1;31m~{job_id}\x1b[0m --cpus \x1b[1;32m2\x1b[0m"
+        );
+
+        let rendered = backend.preview(&task, Some("42"));
+        assert!(rendered.iter().all(Rendered::is_resolved));
+        assert_eq!(rendered[4].to_string(), "scancel 42");
+
+        let output = backend.probe_monitor(&task, "42").await.unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "running 42\n");
+    }
+
+    #[tokio::test]
+    async fn tasks_exceeding_the_limits_are_rejected() {
+        let config = Config::builder()
+            .default_driver()
+            .submit("echo 'Submitted job 42'")
+            .monitor("true")
+            .kill("true")
+            .max_cpu(4usize)
+            .try_build()
+            .unwrap();
+
+        let backend = Backend::initialize(config, None).await.unwrap();
+
+        let task = Task::builder()
+            .name("foo")
+            .extend_executions([Execution::builder()
+                .image("ubuntu")
+                .args(["echo", "hello"])
+                .try_build()
+                .unwrap()])
+            .resources(
+                crate::task::resources::Builder::default()
+                    .cpu(8usize)
+                    .build(),
+            )
+            .try_build()
+            .unwrap();
+
+        let engine = crate::Engine::default().with_backend("generic", Arc::new(backend), 1);
+        let mut events = engine.subscribe();
+        let handle = engine.submit("generic", task);
+        engine.run().await;
+        assert!(handle.await.is_none());
+
+        // NOTE: the task is rejected when it is submitted, so it never enters
+        // the queue of the backend.
+        let mut reason = None;
+        while let Ok(event) = events.try_recv() {
+            assert!(!matches!(event, Event::TaskQueued { .. }));
+
+            if let Event::TaskFailed { reason: failed, .. } = event {
+                reason = Some(failed);
+            }
+        }
+
+        assert_eq!(
+            reason,
+            Some(FailureReason::ExceedsLimits(String::from(
+                "8 CPU cores requested (maximum: 4)"
+            )))
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unparseable_submissions_fail_the_task() {
+        let config = Config::builder()
+            .default_driver()
+            .submit("echo 'no job here'")
+            .job_id_regex(r"Submitted job (\d+)")
+            .monitor("true")
+            .kill("true")
+            .try_build()
+            .unwrap();
+
+        let backend = Backend::initialize(config, None).await.unwrap();
+
+        let task = Task::builder()
+            .name("foo")
+            .extend_executions([Execution::builder()
+                .image("ubuntu")
+                .args(["echo", "hello"])
+                .try_build()
+                .unwrap()])
+            .try_build()
+            .unwrap();
+
+        let events = Events::default();
+        let err = backend
+            .run(task, events.task(events.next_id(), "generic"))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err,
+            FailureReason::SubmissionParseError {
+                stdout: String::from("no job here\n"),
+                stderr: String::new(),
+                pattern: String::from(r"Submitted job (\d+)"),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_job_id_regexes_are_rejected() {
+        let config = Config::builder()
+            .default_driver()
+            .submit("echo 'Submitted job 42'")
+            .job_id_regex(r"Submitted job (\d+")
+            .monitor("true")
+            .kill("true")
+            .try_build()
+            .unwrap();
+
+        let err = Backend::initialize(config, None).await.unwrap_err();
+        assert!(err.to_string().contains("job id regex"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unresolved_commands_fail_the_task() {
+        let config = Config::builder()
+            .default_driver()
+            .submit("echo 'Submitted job 42'")
+            .job_id_regex(r"Submitted job (\d+)")
+            .monitor("echo ~{missing}")
+            .kill("true")
+            .try_build()
+            .unwrap();
+
+        let backend = Backend::initialize(config, None).await.unwrap();
+
+        let task = Task::builder()
+            .name("foo")
+            .extend_executions([Execution::builder()
+                .image("ubuntu")
+                .args(["echo", "hello"])
+                .try_build()
+                .unwrap()])
+            .try_build()
+            .unwrap();
+
+        let events = Events::default();
+        let err = backend
+            .run(task, events.task(events.next_id(), "generic"))
+            .await
+            .unwrap_err();
+
+        assert!(
+            matches!(&err, FailureReason::Request(reason) if reason.contains("monitor command")),
+            "unexpected failure reason: {err:?}"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn exit_codes_are_read_from_the_exit_code_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config::builder()
+            .default_driver()
+            .submit("(~{shell}); echo $? > ~{exit_code_file}; echo 'Submitted job 42'")
+            .job_id_regex(r"Submitted job (\d+)")
+            .monitor("false")
+            .kill("true")
+            .try_build()
+            .unwrap();
+
+        let backend = Backend::initialize(config, None).await.unwrap();
+
+        let task = Task::builder()
+            .name("foo")
+            .extend_executions([Execution::builder()
+                .image("ubuntu")
+                .args(["exit", "3"])
+                .working_directory(dir.path().to_str().unwrap())
+                .try_build()
+                .unwrap()])
+            .try_build()
+            .unwrap();
+
+        let events = Events::default();
+        let result = backend
+            .run(task, events.task(events.next_id(), "generic"))
+            .await
+            .unwrap();
+
+        assert_eq!(result.executions().first().status.code(), Some(3));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn exit_code_files_are_quoted_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let cwd = dir.path().join("with space");
+        std::fs::create_dir(&cwd).unwrap();
+
+        let config = Config::builder()
+            .default_driver()
+            .submit("(~{shell}); echo $? > ~{exit_code_file}; echo 'Submitted job 42'")
+            .job_id_regex(r"Submitted job (\d+)")
+            .monitor("false")
+            .kill("true")
+            .try_build()
+            .unwrap();
+
+        let backend = Backend::initialize(config, None).await.unwrap();
+
+        let task = Task::builder()
+            .name("foo")
+            .extend_executions([Execution::builder()
+                .image("ubuntu")
+                .args(["exit", "3"])
+                .working_directory(cwd.to_str().unwrap())
+                .try_build()
+                .unwrap()])
+            .try_build()
+            .unwrap();
+
+        // NOTE: the file is only written within the working directory (and
+        // then removed) if the path was quoted exactly once.
+        let events = Events::default();
+        let result = backend
+            .run(task, events.task(events.next_id(), "generic"))
+            .await
+            .unwrap();
+
+        assert_eq!(result.executions().first().status.code(), Some(3));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+        assert_eq!(std::fs::read_dir(&cwd).unwrap().count(), 0);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn inputs_are_staged_before_submission() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("inputs/greeting.txt");
+        let config = Config::builder()
+            .default_driver()
+            .submit("(~{shell}); echo $? > ~{exit_code_file}; echo 'Submitted job 42'")
+            .job_id_regex(r"Submitted job (\d+)")
+            .monitor("false")
+            .kill("true")
+            .try_build()
+            .unwrap();
+
+        let backend = Backend::initialize(config, None).await.unwrap();
+
+        let task = Task::builder()
+            .name("foo")
+            .extend_inputs([crate::task::Input::builder()
+                .contents(crate::task::input::Contents::Literal(String::from("hello")))
+                .path(path.to_str().unwrap())
+                .r#type(crate::task::input::Type::File)
+                .try_build()
+                .unwrap()])
+            .extend_executions([Execution::builder()
+                .image("ubuntu")
+                .args(["grep", "-q", "hello", path.to_str().unwrap()])
+                .working_directory(dir.path().to_str().unwrap())
+                .try_build()
+                .unwrap()])
+            .try_build()
+            .unwrap();
+
+        let events = Events::default();
+        let result = backend
+            .run(task, events.task(events.next_id(), "generic"))
+            .await
+            .unwrap();
+
+        assert_eq!(result.executions().first().status.code(), Some(0));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn outputs_are_uploaded_after_the_executions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("result.txt");
+        let url = url::Url::from_file_path(dir.path().join("uploaded/result.txt")).unwrap();
+        let config = Config::builder()
+            .default_driver()
+            .submit("(~{shell}); echo $? > ~{exit_code_file}; echo 'Submitted job 42'")
+            .job_id_regex(r"Submitted job (\d+)")
+            .monitor("false")
+            .kill("true")
+            .try_build()
+            .unwrap();
+
+        let backend = Backend::initialize(config, None).await.unwrap();
+
+        let task = Task::builder()
+            .name("foo")
+            .extend_outputs([crate::task::output::Builder::default()
+                .url(url.clone())
+                .path(path.to_str().unwrap())
+                .r#type(crate::task::output::Type::File)
+                .try_build()
+                .unwrap()])
+            .extend_executions([Execution::builder()
+                .image("ubuntu")
+                .args(["echo", "done", ">", path.to_str().unwrap()])
+                .working_directory(dir.path().to_str().unwrap())
+                .try_build()
+                .unwrap()])
+            .try_build()
+            .unwrap();
+
+        let events = Events::default();
+        let mut rx = events.subscribe();
+        backend
+            .run(task, events.task(events.next_id(), "generic"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(url.to_file_path().unwrap()).unwrap(),
+            "done\n"
+        );
+
+        let mut uploaded = false;
+        while let Ok(event) = rx.try_recv() {
+            if let Event::TaskOutputUploaded { size, .. } = event {
+                uploaded = size == 5;
+            }
+        }
+
+        assert!(uploaded);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn suspended_jobs_fail_after_the_grace_period() {
+        let config = Config::builder()
+            .default_driver()
+            .submit("echo 'Submitted job 42'")
+            .job_id_regex(r"Submitted job (\d+)")
+            .monitor("true")
+            .kill("true")
+            .state("echo HELD")
+            .extend_suspended_states([String::from("held")])
+            .suspended_grace_period(0u64)
+            .try_build()
+            .unwrap();
+
+        let backend = Backend::initialize(config, None).await.unwrap();
+
+        let task = Task::builder()
+            .name("foo")
+            .extend_executions([Execution::builder()
+                .image("ubuntu")
+                .args(["echo", "hello"])
+                .try_build()
+                .unwrap()])
+            .try_build()
+            .unwrap();
+
+        let events = Events::default();
+        let mut receiver = events.subscribe();
+        let task_id = events.next_id();
+        let err = backend
+            .run(task, events.task(task_id, "generic"))
+            .await
+            .unwrap_err();
+
+        assert_eq!(err, FailureReason::Suspended(String::from("HELD")));
+
+        loop {
+            if let Event::TaskSuspended {
+                task_id: id,
+                reason,
+                ..
+            } = receiver.recv().await.unwrap()
+            {
+                assert_eq!(id, task_id);
+                assert_eq!(reason, "HELD");
+                break;
+            }
+        }
+    }
+}
@@ -12,7 +12,6 @@ use std::time::Duration;
 
 use crankshaft_config::backend::generic::driver::Config;
 use crankshaft_config::backend::generic::driver::Locale;
-use crankshaft_config::backend::generic::driver::Shell;
 use crankshaft_config::backend::generic::driver::ssh;
 use eyre::Context as _;
 use eyre::Result;
@@ -25,6 +24,13 @@ use tokio::process::Command;
 use tracing::debug;
 use tracing::error;
 use tracing::trace;
+use tracing::warn;
+
+pub mod pool;
+
+use crate::service::runner::backend::generic::driver::pool::Acquired;
+use crate::service::runner::backend::generic::driver::pool::Pool;
+use crate::task::Execution;
 
 /// An error related to a [`Driver`].
 #[derive(Debug)]
@@ -62,6 +68,9 @@ pub enum Transport {
 
     /// Command execution over an SSH session.
     SSH(Arc<Session>),
+
+    /// Command execution over SSH sessions with a pool of hosts.
+    Pool(Pool),
 }
 
 impl std::fmt::Debug for Transport {
@@ -69,6 +78,7 @@ impl std::fmt::Debug for Transport {
         match self {
             Self::Local => write!(f, "Local"),
             Self::SSH(_) => f.debug_tuple("SSH").finish(),
+            Self::Pool(pool) => f.debug_tuple("Pool").field(pool).finish(),
         }
     }
 }
@@ -109,6 +119,7 @@ impl Driver {
             // [`tokio::process::Command`] when [`command()`] is called.
             Locale::Local => Ok(Transport::Local),
             Locale::SSH { host, options } => create_ssh_transport(&host, &options).await,
+            Locale::SSHPool { hosts } => Pool::initialize(hosts).await.map(Transport::Pool),
         }?;
 
         Ok(Self { transport, config })
@@ -116,18 +127,35 @@ impl Driver {
 
     /// Runs a shell commmand within the configuration locale.
     ///
+    /// For a pool of hosts, the command is run on whichever host is acquired
+    /// from the pool. Use [`Driver::lease()`] to run multiple commands on the
+    /// same host.
+    ///
     /// **NOTE:** this method returns an [`eyre::Result`] because any errors
     /// are intended to be returned directly to the user in the calling binary
     /// (i.e., the errors are typically unrecoverable).
     pub async fn run(&self, command: impl Into<String>) -> Result<Output> {
-        let command = command.into();
+        self.lease().await?.run(command).await
+    }
 
-        match &self.transport {
-            Transport::Local => run_local_command(command, &self.config).await,
-            Transport::SSH(session) => {
-                run_ssh_command(session.clone(), &self.config, command).await
-            }
-        }
+    /// Leases a place to run shell commands within the configuration locale.
+    ///
+    /// Every command run through the returned [`Lease`] is run in the same
+    /// place (e.g., on the same host from a pool of hosts), which is necessary
+    /// when later commands refer to the results of earlier ones (such as
+    /// monitoring a submitted job). For a pool of hosts, this waits until a
+    /// host is within its limit, and the host is released when the lease is
+    /// dropped.
+    pub async fn lease(&self) -> Result<Lease<'_>> {
+        let acquired = match &self.transport {
+            Transport::Pool(pool) => Some(pool.acquire().await?),
+            _ => None,
+        };
+
+        Ok(Lease {
+            driver: self,
+            acquired,
+        })
     }
 
     /// Gets the inner transport.
@@ -141,6 +169,53 @@ impl Driver {
     }
 }
 
+/// A place to run shell commands leased from a [`Driver`].
+#[derive(Debug)]
+pub struct Lease<'a> {
+    /// The driver.
+    driver: &'a Driver,
+
+    /// The host acquired from a pool (if the transport is a pool of hosts).
+    acquired: Option<Acquired<'a>>,
+}
+
+impl Lease<'_> {
+    /// Gets the name of the remote host commands are run on (if the transport
+    /// is a pool of hosts).
+    pub fn host(&self) -> Option<&str> {
+        self.acquired.as_ref().map(|acquired| acquired.host())
+    }
+
+    /// Runs a shell command within the leased place.
+    ///
+    /// If the command cannot be run on a host acquired from a pool, the host
+    /// is marked as unreachable.
+    ///
+    /// **NOTE:** this method returns an [`eyre::Result`] because any errors
+    /// are intended to be returned directly to the user in the calling binary
+    /// (i.e., the errors are typically unrecoverable).
+    pub async fn run(&self, command: impl Into<String>) -> Result<Output> {
+        let command = command.into();
+        let config = &self.driver.config;
+
+        match (&self.driver.transport, &self.acquired) {
+            (Transport::Local, _) => run_local_command(command, config).await,
+            (Transport::SSH(session), _) => run_ssh_command(session.clone(), config, command).await,
+            (Transport::Pool(_), Some(acquired)) => {
+                let result = run_ssh_command(acquired.session(), config, command).await;
+
+                if result.is_err() {
+                    acquired.unreachable();
+                }
+
+                result
+            }
+            // SAFETY: a host is always acquired when leasing from a pool.
+            (Transport::Pool(_), None) => unreachable!(),
+        }
+    }
+}
+
 //=================//
 // Local Execution //
 //=================//
@@ -150,20 +225,20 @@ async fn run_local_command(command: String, config: &Config) -> Result<Output> {
     trace!("executing local command: `{command}`");
 
     // NOTE: this is cloned because `default()` is only implemented on the owned
-    // [`Locale`] type (not a reference).
-    let command = match config.shell().cloned().unwrap_or_default() {
-        Shell::Bash => Command::new("/usr/bin/env")
-            .args(["bash", "-c", &command])
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped())
-            .spawn(),
-        Shell::Sh => Command::new("/usr/bin/env")
-            .args(["sh", "-c", &command])
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped())
-            .spawn(),
-    }
-    .context("spawning the local command")?;
+    // [`Shell`] type (not a reference).
+    let mut args = config
+        .shell()
+        .cloned()
+        .unwrap_or_default()
+        .args([command.into()]);
+
+    // SAFETY: every shell has at least one base argument (the program).
+    let command = Command::new(args.next().unwrap())
+        .args(args)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .context("spawning the local command")?;
 
     command
         .wait_with_output()
@@ -176,7 +251,7 @@ async fn run_local_command(command: String, config: &Config) -> Result<Output> {
 //===============//
 
 /// Attempts to create an SSH transport.
-async fn create_ssh_transport(host: &str, config: &ssh::Config) -> Result<Transport> {
+pub(crate) async fn create_ssh_transport(host: &str, config: &ssh::Config) -> Result<Transport> {
     let addr = format!("{host}:{}", config.port());
 
     // Connect to the remote SSH host.
@@ -325,6 +400,27 @@ fn channel_session_with_backoff(
     unreachable!()
 }
 
+/// Converts an exit code reported by a remote host into an [`ExitStatus`].
+#[cfg(unix)]
+pub(crate) fn exit_status(code: i32) -> ExitStatus {
+    // NOTE: raw statuses on Unix are wait statuses, which hold the exit code in
+    // the second byte. Exit codes from Windows hosts are 32 bits wide, so a
+    // non-zero code that would be truncated to zero is reported as `1` to
+    // preserve that the command failed.
+    let code = match code & 0xFF {
+        0 if code != 0 => 1,
+        code => code,
+    };
+
+    ExitStatus::from_raw(code << 8)
+}
+
+/// Converts an exit code reported by a remote host into an [`ExitStatus`].
+#[cfg(windows)]
+pub(crate) fn exit_status(code: i32) -> ExitStatus {
+    ExitStatus::from_raw(code as u32)
+}
+
 /// Runs a remote command over SSH.
 async fn run_ssh_command(
     session: Arc<ssh2::Session>,
@@ -333,6 +429,14 @@ async fn run_ssh_command(
 ) -> Result<Output> {
     let max_attempts = config.max_attempts();
 
+    // NOTE: when no shell is configured, the command is executed by the login
+    // shell of the remote user (as it always has been). Otherwise, the command
+    // is rendered such that it runs within the configured shell.
+    let command = match config.shell() {
+        Some(shell) => shell.render(&command),
+        None => command,
+    };
+
     let f = move || {
         debug!("running command on remote host: `{}`", command);
 
@@ -394,21 +498,11 @@ async fn run_ssh_command(
             .map_err(Error::SSH2)
             .context("waiting for the SSH channel to be closed from the client's end")?;
 
-        #[cfg(unix)]
-        let output = Output {
-            status: ExitStatus::from_raw(status),
-            stdout,
-            stderr,
-        };
-
-        #[cfg(windows)]
-        let output = Output {
-            status: ExitStatus::from_raw(status as u32),
+        eyre::Result::<Output>::Ok(Output {
+            status: exit_status(status),
             stdout,
             stderr,
-        };
-
-        eyre::Result::<Output>::Ok(output)
+        })
     };
 
     tokio::task::spawn_blocking(f)
@@ -416,3 +510,98 @@ async fn run_ssh_command(
         .map_err(Error::Join)
         .context("running an SSH command")?
 }
+
+/// Quotes a value for use within a shell command.
+pub(crate) fn quote(value: &str) -> String {
+    // NOTE: quoting only fails for values containing a nul byte, which can
+    // never be passed as an argument anyway.
+    shlex::try_quote(value)
+        .map(|quoted| quoted.into_owned())
+        .unwrap_or_else(|_| value.replace('\0', ""))
+}
+
+/// Prefixes a shell command with exports of the environment variables of an
+/// execution.
+pub(crate) fn with_env(execution: &Execution, shell: String) -> String {
+    let exports = execution
+        .env()
+        .into_iter()
+        .flatten()
+        .map(|(name, value)| format!("export {};", quote(&format!("{name}={value}"))))
+        .collect::<Vec<_>>();
+
+    if exports.is_empty() {
+        return shell;
+    }
+
+    format!("{} {shell}", exports.join(" "))
+}
+
+/// Runs a command (e.g., one that cancels a job) when dropped, unless it was
+/// disarmed.
+///
+/// This ensures that the job of a canceled task does not keep running (or that
+/// the files staged for a task are removed however the task ends).
+pub(crate) struct Cancel {
+    /// The driver.
+    driver: Arc<Driver>,
+
+    /// The command that cancels the job.
+    command: String,
+
+    /// Whether or not the command is run when the guard is dropped.
+    armed: bool,
+}
+
+impl Cancel {
+    /// Creates a new, armed [`Cancel`] guard.
+    pub(crate) fn new(driver: Arc<Driver>, command: impl Into<String>) -> Self {
+        Self {
+            driver,
+            command: command.into(),
+            armed: true,
+        }
+    }
+
+    /// Disarms the guard (e.g., because the job finished).
+    pub(crate) fn disarm(&mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for Cancel {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+
+        let driver = self.driver.clone();
+        let command = std::mem::take(&mut self.command);
+
+        // NOTE: if the runtime is shutting down, the job cannot be canceled.
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            handle.spawn(async move {
+                if let Err(err) = driver.run(command.clone()).await {
+                    warn!("failed to run `{command}` after the guard was dropped: {err}");
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn remote_exit_codes_are_converted() {
+        assert!(exit_status(0).success());
+        assert_eq!(exit_status(2).code(), Some(2));
+
+        // NOTE: a Windows exit code whose lowest byte is zero must still be
+        // reported as a failure.
+        assert_eq!(exit_status(0x100).code(), Some(1));
+        assert!(!exit_status(0xC0000005_u32 as i32).success());
+    }
+}
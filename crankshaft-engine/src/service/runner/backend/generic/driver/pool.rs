@@ -0,0 +1,348 @@
+//! Pools of SSH hosts.
+//!
+//! A pool distributes executions across multiple SSH hosts (e.g., several
+//! login nodes of a cluster). Each execution is dispatched to the reachable
+//! host with the lowest load relative to its weight that is still within its
+//! limit. Hosts that cannot be reached are skipped until they are successfully
+//! reconnected, which is attempted at most once every [`RECHECK_INTERVAL`].
+
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use crankshaft_config::backend::generic::driver::ssh::Host;
+use eyre::Result;
+use eyre::bail;
+use ssh2::Session;
+use tokio::sync::Notify;
+use tracing::debug;
+use tracing::warn;
+
+use crate::service::runner::backend::generic::driver::Transport;
+use crate::service::runner::backend::generic::driver::create_ssh_transport;
+
+/// The minimum amount of time between attempts to reconnect to an unreachable
+/// host.
+pub const RECHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The state of a host within a pool.
+#[derive(Default)]
+struct State {
+    /// The SSH session with the host (if the host is reachable).
+    session: Option<Arc<Session>>,
+
+    /// When the host was last found to be unreachable (if it was).
+    failed_at: Option<Instant>,
+
+    /// The number of executions currently dispatched to the host.
+    running: usize,
+}
+
+/// A host within a pool.
+struct Member {
+    /// The configuration for the host.
+    host: Host,
+
+    /// The state of the host.
+    state: Mutex<State>,
+}
+
+impl std::fmt::Debug for Member {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.state.lock().unwrap();
+
+        f.debug_struct("Member")
+            .field("host", &self.host.host())
+            .field("reachable", &state.session.is_some())
+            .field("running", &state.running)
+            .finish()
+    }
+}
+
+/// A pool of SSH hosts.
+#[derive(Debug)]
+pub struct Pool {
+    /// The hosts within the pool.
+    members: Vec<Member>,
+
+    /// Notified whenever a host is released.
+    released: Notify,
+}
+
+/// The load of a candidate host: the number of executions dispatched to it
+/// and its weight.
+type Load = (usize, u32);
+
+/// Picks the index of the candidate with the lowest load relative to its
+/// weight (or [`None`] if there are no candidates).
+///
+/// Ties are broken in favor of the earliest candidate.
+fn pick(candidates: impl IntoIterator<Item = (usize, Load)>) -> Option<usize> {
+    candidates
+        .into_iter()
+        .filter(|(_, (_, weight))| *weight > 0)
+        .min_by(|(_, (a, a_weight)), (_, (b, b_weight))| {
+            // NOTE: `(a + 1) / a_weight` is compared with `(b + 1) / b_weight`
+            // without division by cross multiplying.
+            ((*a as u64 + 1) * *b_weight as u64).cmp(&((*b as u64 + 1) * *a_weight as u64))
+        })
+        .map(|(index, _)| index)
+}
+
+impl Pool {
+    /// Initializes a new [`Pool`] by connecting to each of the hosts.
+    ///
+    /// Hosts that cannot be reached are skipped (and reconnected later). If
+    /// none of the hosts can be reached, an error is returned.
+    pub async fn initialize(hosts: Vec<Host>) -> Result<Self> {
+        if hosts.is_empty() {
+            bail!("a pool of SSH hosts must contain at least one host");
+        }
+
+        // NOTE: a host with no weight is never picked, so an acquisition from
+        // a pool without a weighted host would wait forever.
+        if hosts.iter().all(|host| host.weight() == 0) {
+            bail!("a pool of SSH hosts must contain at least one host with a nonzero weight");
+        }
+
+        let mut members = Vec::with_capacity(hosts.len());
+
+        for host in hosts {
+            let mut state = State::default();
+
+            match connect(&host).await {
+                Ok(session) => state.session = Some(session),
+                Err(err) => {
+                    warn!("skipping unreachable SSH host `{}`: {err:#}", host.host());
+                    state.failed_at = Some(Instant::now());
+                }
+            }
+
+            members.push(Member {
+                host,
+                state: Mutex::new(state),
+            });
+        }
+
+        if members
+            .iter()
+            .all(|member| member.state.lock().unwrap().session.is_none())
+        {
+            bail!("none of the hosts within the pool of SSH hosts are reachable");
+        }
+
+        Ok(Self {
+            members,
+            released: Notify::new(),
+        })
+    }
+
+    /// Acquires a host from the pool, waiting for one to be within its limit
+    /// if necessary.
+    ///
+    /// If none of the hosts with a nonzero weight are reachable, an error is
+    /// returned rather than waiting.
+    ///
+    /// The host is released when the returned [`Acquired`] is dropped.
+    pub async fn acquire(&self) -> Result<Acquired<'_>> {
+        loop {
+            self.reconnect().await;
+
+            // NOTE: the notification is enabled before the hosts are checked
+            // so that a release between the check and the wait is not missed.
+            let released = self.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+
+            {
+                let mut states = self
+                    .members
+                    .iter()
+                    .map(|member| member.state.lock().unwrap())
+                    .collect::<Vec<_>>();
+
+                // NOTE: only reachable hosts with a weight are ever picked, so
+                // there is nothing to wait for without one.
+                if self
+                    .members
+                    .iter()
+                    .zip(&states)
+                    .all(|(member, state)| state.session.is_none() || member.host.weight() == 0)
+                {
+                    bail!(
+                        "none of the hosts with a nonzero weight within the pool of SSH hosts \
+                         are reachable"
+                    );
+                }
+
+                let candidates = self.members.iter().zip(&states).enumerate().filter_map(
+                    |(index, (member, state))| {
+                        state.session.as_ref()?;
+
+                        let within_limit = member
+                            .host
+                            .max_tasks()
+                            .is_none_or(|limit| state.running < limit);
+
+                        within_limit.then_some((index, (state.running, member.host.weight())))
+                    },
+                );
+
+                if let Some(index) = pick(candidates) {
+                    let state = &mut states[index];
+                    state.running += 1;
+
+                    return Ok(Acquired {
+                        pool: self,
+                        index,
+                        // SAFETY: only hosts with a session are candidates.
+                        session: state.session.clone().unwrap(),
+                    });
+                }
+            }
+
+            released.await;
+        }
+    }
+
+    /// Attempts to reconnect to any unreachable hosts that have not been
+    /// checked within the [`RECHECK_INTERVAL`].
+    async fn reconnect(&self) {
+        for member in &self.members {
+            {
+                let mut state = member.state.lock().unwrap();
+
+                match state.failed_at {
+                    Some(at) if state.session.is_none() && at.elapsed() >= RECHECK_INTERVAL => {
+                        // NOTE: this is reset first so that concurrent
+                        // acquisitions do not reconnect at the same time.
+                        state.failed_at = Some(Instant::now());
+                    }
+                    _ => continue,
+                }
+            }
+
+            match connect(&member.host).await {
+                Ok(session) => {
+                    debug!("SSH host `{}` is reachable again", member.host.host());
+                    let mut state = member.state.lock().unwrap();
+                    state.session = Some(session);
+                    state.failed_at = None;
+                }
+                Err(err) => {
+                    debug!(
+                        "SSH host `{}` is still unreachable: {err:#}",
+                        member.host.host()
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Connects to a host within a pool.
+async fn connect(host: &Host) -> Result<Arc<Session>> {
+    match create_ssh_transport(host.host(), host.options()).await? {
+        Transport::SSH(session) => Ok(session),
+        // SAFETY: creating an SSH transport always returns an SSH transport.
+        _ => unreachable!(),
+    }
+}
+
+/// A host acquired from a [`Pool`].
+///
+/// The host is released back to the pool when this is dropped.
+pub struct Acquired<'a> {
+    /// The pool the host was acquired from.
+    pool: &'a Pool,
+
+    /// The index of the host within the pool.
+    index: usize,
+
+    /// The SSH session with the host.
+    session: Arc<Session>,
+}
+
+impl Acquired<'_> {
+    /// Gets the name of the acquired host.
+    pub fn host(&self) -> &str {
+        self.pool.members[self.index].host.host()
+    }
+
+    /// Gets the SSH session with the acquired host.
+    pub fn session(&self) -> Arc<Session> {
+        self.session.clone()
+    }
+
+    /// Marks the acquired host as unreachable.
+    ///
+    /// The host is skipped until it is successfully reconnected.
+    pub fn unreachable(&self) {
+        warn!("marking SSH host `{}` as unreachable", self.host());
+
+        let mut state = self.pool.members[self.index].state.lock().unwrap();
+
+        // NOTE: the session is only cleared if it has not already been
+        // replaced by a reconnection.
+        if state
+            .session
+            .as_ref()
+            .is_some_and(|session| Arc::ptr_eq(session, &self.session))
+        {
+            state.session = None;
+            state.failed_at = Some(Instant::now());
+        }
+    }
+}
+
+impl std::fmt::Debug for Acquired<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Acquired")
+            .field("host", &self.host())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Acquired<'_> {
+    fn drop(&mut self) {
+        self.pool.members[self.index].state.lock().unwrap().running -= 1;
+        self.pool.released.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hosts_are_picked_by_weighted_load() {
+        assert_eq!(pick([]), None);
+
+        // NOTE: the least loaded host is picked.
+        assert_eq!(pick([(0, (2, 1)), (1, (1, 1))]), Some(1));
+
+        // NOTE: a host with twice the weight takes twice the load.
+        assert_eq!(pick([(0, (1, 2)), (1, (0, 1))]), Some(0));
+        assert_eq!(pick([(0, (2, 2)), (1, (0, 1))]), Some(1));
+
+        // NOTE: ties go to the earliest host, and hosts with no weight are
+        // never picked.
+        assert_eq!(pick([(0, (0, 1)), (1, (0, 1))]), Some(0));
+        assert_eq!(pick([(0, (0, 0)), (1, (5, 1))]), Some(1));
+    }
+
+    #[tokio::test]
+    async fn pools_without_a_weighted_host_are_rejected() {
+        let hosts = ["login1", "login2"]
+            .into_iter()
+            .map(|name| Host {
+                weight: 0,
+                ..Host::new(name)
+            })
+            .collect();
+
+        let err = Pool::initialize(hosts).await.unwrap_err();
+        assert!(err.to_string().contains("nonzero weight"));
+    }
+}
@@ -0,0 +1,252 @@
+//! Previews of the commands a generic backend runs for a task.
+//!
+//! Writing the command templates of a generic backend usually involves a lot
+//! of trial and error against a real scheduler. A preview renders every
+//! configured command for a sample task (see
+//! [`Backend::preview()`](super::Backend::preview)) while keeping track of
+//! which parts of each command came from substitutions, so that the rendered
+//! commands can be printed with the substitutions highlighted (see
+//! [`Rendered::highlighted()`]).
+
+use std::collections::HashMap;
+
+use crankshaft_config::backend::generic::Config;
+use crankshaft_config::backend::generic::Segment;
+use crankshaft_config::backend::generic::segments;
+
+/// The escape sequence that highlights a substituted value.
+const SUBSTITUTED_STYLE: &str = "\x1b[1;32m";
+
+/// The escape sequence that highlights an unresolved placeholder.
+const UNRESOLVED_STYLE: &str = "\x1b[1;31m";
+
+/// The escape sequence that resets the style of the text.
+const RESET_STYLE: &str = "\x1b[0m";
+
+/// A command run by a generic backend.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// The command that submits a job.
+    Submit,
+
+    /// The command that monitors a submitted job.
+    Monitor,
+
+    /// The command that reads the exit code of a finished job (when the
+    /// submit command writes it to the `~{exit_code_file}`).
+    ExitCode,
+
+    /// The command that queries the scheduler state of a submitted job.
+    State,
+
+    /// The command that releases a held or suspended job.
+    Release,
+
+    /// The command that queries the accounting data of a completed job.
+    Accounting,
+
+    /// The command that probes the resources used by a running job.
+    Usage,
+
+    /// The command that kills a job.
+    Kill,
+
+    /// The command that forcibly kills a job that is still running after the
+    /// grace period.
+    ForceKill,
+}
+
+impl std::fmt::Display for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Command::Submit => write!(f, "submit"),
+            Command::Monitor => write!(f, "monitor"),
+            Command::ExitCode => write!(f, "exit code"),
+            Command::State => write!(f, "state"),
+            Command::Release => write!(f, "release"),
+            Command::Accounting => write!(f, "accounting"),
+            Command::Usage => write!(f, "usage"),
+            Command::Kill => write!(f, "kill"),
+            Command::ForceKill => write!(f, "force kill"),
+        }
+    }
+}
+
+/// A part of a rendered command.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Part {
+    /// Literal text from the template of the command.
+    Literal(String),
+
+    /// A placeholder that was replaced with a value.
+    Substituted {
+        /// The key of the placeholder.
+        key: String,
+
+        /// The value the placeholder was replaced with.
+        value: String,
+    },
+
+    /// A placeholder without a value.
+    Unresolved(String),
+}
+
+/// A command rendered for an execution within a sample task.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rendered {
+    /// The index of the execution within the task.
+    execution: usize,
+
+    /// The command.
+    command: Command,
+
+    /// The parts of the rendered command.
+    parts: Vec<Part>,
+}
+
+impl Rendered {
+    /// Renders a template with the provided substitutions.
+    ///
+    /// Placeholders are replaced with the substitutions first and the
+    /// attributes of the backend second (as when the command runs).
+    fn new(
+        execution: usize,
+        command: Command,
+        template: &str,
+        substitutions: &HashMap<String, String>,
+        attributes: Option<&HashMap<String, String>>,
+    ) -> Self {
+        let parts = segments(template)
+            .into_iter()
+            .map(|segment| match segment {
+                Segment::Literal(text) => Part::Literal(text.to_owned()),
+                Segment::Placeholder(key) => {
+                    match substitutions
+                        .get(key)
+                        .or_else(|| attributes.and_then(|attributes| attributes.get(key)))
+                    {
+                        Some(value) => Part::Substituted {
+                            key: key.to_owned(),
+                            value: value.clone(),
+                        },
+                        None => Part::Unresolved(key.to_owned()),
+                    }
+                }
+            })
+            .collect();
+
+        Self {
+            execution,
+            command,
+            parts,
+        }
+    }
+
+    /// Gets the index of the execution within the task.
+    pub fn execution(&self) -> usize {
+        self.execution
+    }
+
+    /// Gets the command.
+    pub fn command(&self) -> Command {
+        self.command
+    }
+
+    /// Gets the parts of the rendered command.
+    pub fn parts(&self) -> &[Part] {
+        &self.parts
+    }
+
+    /// Gets the keys of the placeholders that have no value.
+    pub fn unresolved(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Unresolved(key) => Some(key.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Gets whether or not every placeholder within the command has a value.
+    pub fn is_resolved(&self) -> bool {
+        self.unresolved().next().is_none()
+    }
+
+    /// Renders the command with the substituted values highlighted (in green)
+    /// and the unresolved placeholders highlighted (in red) using ANSI escape
+    /// sequences.
+    pub fn highlighted(&self) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(text) => text.clone(),
+                Part::Substituted { value, .. } => {
+                    format!("{SUBSTITUTED_STYLE}{value}{RESET_STYLE}")
+                }
+                Part::Unresolved(key) => format!("{UNRESOLVED_STYLE}~{{{key}}}{RESET_STYLE}"),
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for Rendered {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => write!(f, "{text}")?,
+                Part::Substituted { value, .. } => write!(f, "{value}")?,
+                Part::Unresolved(key) => write!(f, "~{{{key}}}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Renders every configured command of a backend for an execution.
+///
+/// The `exit_code` substitutions are those of the command that reads the exit
+/// code of a finished job (if the backend uses an exit code file).
+pub(crate) fn render(
+    config: &Config,
+    execution: usize,
+    substitutions: &HashMap<String, String>,
+    exit_code: Option<(&str, &HashMap<String, String>)>,
+) -> Vec<Rendered> {
+    let attributes = config.attributes();
+    let render = |command, template: &str| {
+        Rendered::new(execution, command, template, substitutions, attributes)
+    };
+
+    let mut rendered = vec![
+        render(Command::Submit, config.submit()),
+        render(Command::Monitor, config.monitor()),
+    ];
+
+    if let Some((template, substitutions)) = exit_code {
+        rendered.push(Rendered::new(
+            execution,
+            Command::ExitCode,
+            template,
+            substitutions,
+            None,
+        ));
+    }
+
+    rendered.extend(
+        [
+            (Command::State, config.state()),
+            (Command::Release, config.release()),
+            (Command::Accounting, config.accounting()),
+            (Command::Usage, config.usage()),
+        ]
+        .into_iter()
+        .filter_map(|(command, template)| Some(render(command, template?))),
+    );
+
+    rendered.push(render(Command::Kill, config.kill()));
+
+    if let Some(force_kill) = config.force_kill() {
+        rendered.push(render(Command::ForceKill, force_kill));
+    }
+
+    rendered
+}
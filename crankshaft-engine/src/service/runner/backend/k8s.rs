@@ -0,0 +1,675 @@
+//! A Kubernetes backend.
+//!
+//! Each execution of a task is run (in order) as a Kubernetes [Job] with a
+//! single pod. The resources of the task are mapped onto the requests and
+//! limits of the pod's container:
+//!
+//! * The number of CPU cores is requested.
+//! * The soft amount of RAM is requested and the hard amount is the limit.
+//! * The disk size is requested as ephemeral storage.
+//!
+//! Kubernetes does not separate the standard output and standard error of a
+//! container within its logs, so the logs of the pod are reported as the
+//! standard output of the execution (and the standard error is empty).
+//!
+//! Preemptible tasks are additionally constrained to the preemptible node
+//! selector of the backend (e.g., to run on spot nodes). The checkpoint
+//! command of a task (if any) runs as a `preStop` hook of the container, so it
+//! runs before the container is stopped when its node is drained or
+//! reclaimed. As the outcome of the hook is not reported by Kubernetes, no
+//! [`Event::TaskCheckpointed`](crate::events::Event::TaskCheckpointed) events
+//! are sent.
+//!
+//! A pod whose container cannot be started (e.g., because its image cannot be
+//! pulled) never fails on its own, so the execution is failed (and its Job
+//! deleted) as soon as the container is observed waiting for such a reason.
+//! Unschedulable pods are left pending, as a cluster autoscaler may yet add a
+//! node that fits them. A Job that is deleted outside of Crankshaft fails the
+//! task with [`FailureReason::Request`].
+//!
+//! Task inputs, outputs, and shared volumes are not (yet) supported: tasks
+//! that use them fail with [`FailureReason::Unsupported`].
+//!
+//! This module is only available with the `kubernetes` feature enabled.
+//!
+//! [Job]: https://kubernetes.io/docs/concepts/workloads/controllers/job/
+
+use std::collections::BTreeMap;
+#[cfg(unix)]
+use std::os::unix::process::ExitStatusExt as _;
+#[cfg(windows)]
+use std::os::windows::process::ExitStatusExt as _;
+use std::process::ExitStatus;
+use std::process::Output;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use crankshaft_config::backend::k8s::Config;
+use eyre::Context;
+use futures::FutureExt as _;
+use futures::future::BoxFuture;
+use k8s_openapi::api::batch::v1::Job;
+use k8s_openapi::api::batch::v1::JobSpec;
+use k8s_openapi::api::core::v1::Container;
+use k8s_openapi::api::core::v1::ContainerStatus;
+use k8s_openapi::api::core::v1::EnvVar;
+use k8s_openapi::api::core::v1::ExecAction;
+use k8s_openapi::api::core::v1::Lifecycle;
+use k8s_openapi::api::core::v1::LifecycleHandler;
+use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::api::core::v1::PodSpec;
+use k8s_openapi::api::core::v1::PodTemplateSpec;
+use k8s_openapi::api::core::v1::ResourceRequirements;
+use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use kube::Api;
+use kube::Client;
+use kube::api::DeleteParams;
+use kube::api::ListParams;
+use kube::api::LogParams;
+use kube::api::PostParams;
+use nonempty::NonEmpty;
+use tracing::debug;
+use tracing::warn;
+
+use crate::Result;
+use crate::Task;
+use crate::events::TaskEvents;
+use crate::service::runner::backend::FailureReason;
+use crate::service::runner::backend::TaskResult;
+use crate::service::runner::backend::provenance::ExecutionProvenance;
+use crate::service::runner::backend::provenance::Provenance;
+use crate::service::runner::backend::redirect;
+use crate::task::Execution;
+use crate::task::Resources;
+
+/// The label applied to every Job created by Crankshaft.
+pub const MANAGED_LABEL: &str = "crankshaft.managed";
+
+/// The annotation containing the name of the task a Job was created for.
+///
+/// An annotation is used (rather than a label) because task names are not
+/// restricted to the characters allowed within label values.
+pub const TASK_ANNOTATION: &str = "crankshaft.task";
+
+/// The prefix of the names generated for Jobs.
+const JOB_NAME_PREFIX: &str = "crankshaft-";
+
+/// The name of the container within the pod of each Job.
+const CONTAINER_NAME: &str = "execution";
+
+/// The reasons a container waits for that it never recovers from without
+/// intervention.
+const FATAL_WAITING_REASONS: &[&str] = &[
+    "CreateContainerConfigError",
+    "CreateContainerError",
+    "ErrImageNeverPull",
+    "ErrImagePull",
+    "ImagePullBackOff",
+    "InvalidImageName",
+];
+
+/// A Kubernetes execution backend.
+pub struct Backend {
+    /// A handle to the inner Kubernetes client.
+    client: Client,
+
+    /// Configuration for the backend.
+    config: Config,
+}
+
+// NOTE: the Kubernetes client does not implement `Debug`.
+impl std::fmt::Debug for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Backend")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Backend {
+    /// Attempts to initialize a new Kubernetes [`Backend`] with the provided
+    /// configuration for the backend.
+    ///
+    /// The client is configured from the environment: the local kubeconfig
+    /// (respecting `KUBECONFIG`) or, when running within a cluster, the
+    /// in-cluster service account.
+    pub async fn initialize(config: Config) -> Result<Self> {
+        let client = Client::try_default()
+            .await
+            .context("error connecting to the Kubernetes API server")?;
+
+        Ok(Self { client, config })
+    }
+}
+
+#[async_trait]
+impl crate::Backend for Backend {
+    fn default_name(&self) -> &'static str {
+        "kubernetes"
+    }
+
+    fn run(
+        &self,
+        task: Task,
+        events: TaskEvents,
+    ) -> BoxFuture<'static, std::result::Result<TaskResult, FailureReason>> {
+        run(self, task, events)
+    }
+}
+
+/// Gets the resource requirements of a container from the resources of a
+/// task.
+fn requirements(resources: &Resources) -> ResourceRequirements {
+    let bytes = |gb: f64| Quantity((gb * 1024. * 1024. * 1024.).round().to_string());
+
+    let mut requests = BTreeMap::new();
+    let mut limits = BTreeMap::new();
+
+    if let Some(cpu) = resources.cpu() {
+        requests.insert(String::from("cpu"), Quantity(cpu.to_string()));
+    }
+
+    if let Some(ram) = resources.ram_soft() {
+        requests.insert(String::from("memory"), bytes(ram));
+    }
+
+    if let Some(ram) = resources.ram_hard() {
+        limits.insert(String::from("memory"), bytes(ram));
+    }
+
+    if let Some(disk) = resources.disk() {
+        requests.insert(String::from("ephemeral-storage"), bytes(disk));
+    }
+
+    ResourceRequirements {
+        requests: (!requests.is_empty()).then_some(requests),
+        limits: (!limits.is_empty()).then_some(limits),
+        ..Default::default()
+    }
+}
+
+/// Gets the Job that runs an execution of a task.
+fn to_job(task: &Task, execution: &Execution, config: &Config) -> Job {
+    let env = execution.env().map(|env| {
+        env.iter()
+            .map(|(name, value)| EnvVar {
+                name: name.clone(),
+                value: Some(value.clone()),
+                ..Default::default()
+            })
+            .collect()
+    });
+
+    let container = Container {
+        name: String::from(CONTAINER_NAME),
+        image: Some(execution.image().to_owned()),
+        command: Some(execution.args().iter().cloned().collect()),
+        working_dir: execution.workdir().cloned(),
+        env,
+        resources: task.resources().map(requirements),
+        lifecycle: task.checkpoint_command().map(|command| Lifecycle {
+            pre_stop: Some(LifecycleHandler {
+                exec: Some(ExecAction {
+                    command: Some(command.iter().cloned().collect()),
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    let mut node_selector = config.node_selector().clone();
+    if task.is_preemptible() {
+        node_selector.extend(
+            config
+                .preemptible_node_selector()
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+    }
+    let node_selector = (!node_selector.is_empty()).then_some(node_selector);
+
+    let mut annotations = BTreeMap::new();
+    if let Some(name) = task.name() {
+        annotations.insert(String::from(TASK_ANNOTATION), name.to_owned());
+    }
+
+    Job {
+        metadata: ObjectMeta {
+            generate_name: Some(String::from(JOB_NAME_PREFIX)),
+            labels: Some(BTreeMap::from([(
+                String::from(MANAGED_LABEL),
+                String::from("true"),
+            )])),
+            annotations: Some(annotations),
+            ..Default::default()
+        },
+        spec: Some(JobSpec {
+            // NOTE: failed executions are never retried by Kubernetes, as the
+            // exit code of the execution is reported as its result.
+            backoff_limit: Some(0),
+            template: PodTemplateSpec {
+                metadata: None,
+                spec: Some(PodSpec {
+                    containers: vec![container],
+                    restart_policy: Some(String::from("Never")),
+                    service_account_name: config.service_account().map(ToOwned::to_owned),
+                    node_selector,
+                    ..Default::default()
+                }),
+            },
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+/// Deletes a Job (and its pods) when dropped, unless it was disarmed.
+///
+/// This ensures that the Job of a canceled task does not keep running.
+struct Cleanup {
+    /// The API of the Jobs within the namespace.
+    jobs: Api<Job>,
+
+    /// The name of the Job.
+    name: String,
+
+    /// Whether or not the Job is deleted when the guard is dropped.
+    armed: bool,
+}
+
+impl Drop for Cleanup {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+
+        let jobs = self.jobs.clone();
+        let name = std::mem::take(&mut self.name);
+
+        // NOTE: if the runtime is shutting down, the Job cannot be deleted.
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            handle.spawn(async move {
+                if let Err(err) = jobs.delete(&name, &DeleteParams::background()).await {
+                    warn!("failed to delete Job `{name}`: {err}");
+                }
+            });
+        }
+    }
+}
+
+/// Gets the status of the container of the execution within a pod (if it is
+/// reported).
+fn container_status(pod: &Pod) -> Option<&ContainerStatus> {
+    pod.status
+        .as_ref()
+        .and_then(|status| status.container_statuses.as_ref())
+        .and_then(|statuses| statuses.iter().find(|status| status.name == CONTAINER_NAME))
+}
+
+/// Gets the reason (and message) of a container within a pod that is waiting
+/// for a reason it never recovers from (if it is).
+fn fatal_waiting(pod: &Pod) -> Option<String> {
+    let waiting = container_status(pod)?.state.as_ref()?.waiting.as_ref()?;
+    let reason = waiting.reason.as_deref()?;
+
+    if !FATAL_WAITING_REASONS.contains(&reason) {
+        return None;
+    }
+
+    Some(match &waiting.message {
+        Some(message) => format!("{reason}: {message}"),
+        None => reason.to_owned(),
+    })
+}
+
+/// Gets the exit code of the execution within a pod and the digest of its
+/// image (if they are reported).
+fn terminated(pod: &Pod) -> (Option<i32>, Option<String>) {
+    let status = container_status(pod);
+
+    let code = status
+        .and_then(|status| status.state.as_ref())
+        .and_then(|state| state.terminated.as_ref())
+        .map(|terminated| terminated.exit_code);
+
+    // NOTE: the image id is of the form `<repository>@<digest>`.
+    let digest = status
+        .and_then(|status| status.image_id.split_once('@'))
+        .map(|(_, digest)| digest.to_owned());
+
+    (code, digest)
+}
+
+/// Runs a [`Task`] in the backend.
+fn run(
+    backend: &Backend,
+    task: Task,
+    events: TaskEvents,
+) -> BoxFuture<'static, std::result::Result<TaskResult, FailureReason>> {
+    let jobs: Api<Job> = Api::namespaced(backend.client.clone(), backend.config.namespace());
+    let pods: Api<Pod> = Api::namespaced(backend.client.clone(), backend.config.namespace());
+    let config = backend.config.clone();
+
+    async move {
+        if task.inputs().is_some() || task.outputs().is_some() || task.shared_volumes().is_some() {
+            return Err(FailureReason::Unsupported(String::from(
+                "the Kubernetes backend does not support task inputs, outputs, or shared volumes",
+            )));
+        }
+
+        let mut outputs = Vec::new();
+        let mut provenance = Provenance::default().with_resources(task.resources().cloned());
+        let poll_interval = Duration::from_secs(config.poll_interval());
+
+        for (index, execution) in task.executions().enumerate() {
+            if execution.stdin().is_some() || execution.user().is_some() {
+                warn!(
+                    "the standard input and user of execution {index} are not supported by the \
+                     Kubernetes backend and are ignored"
+                );
+            }
+
+            // (1) Create the Job.
+            let job = jobs
+                .create(&PostParams::default(), &to_job(&task, execution, &config))
+                .await
+                .map_err(|err| FailureReason::Request(format!("creating Job: {err}")))?;
+
+            // SAFETY: the API server always names created objects.
+            let name = job.metadata.name.unwrap();
+            debug!("created Job `{name}` for execution {index}");
+
+            let mut cleanup = Cleanup {
+                jobs: jobs.clone(),
+                name: name.clone(),
+                armed: true,
+            };
+
+            events.execution_started(index);
+
+            // (2) Wait for the Job to finish (or its container to be unable to
+            // start).
+            let mut running = false;
+            let mut waiting = None;
+
+            loop {
+                tokio::time::sleep(poll_interval).await;
+
+                let status = match jobs.get(&name).await {
+                    Ok(job) => job.status.unwrap_or_default(),
+                    Err(kube::Error::Api(response)) if response.code == 404 => {
+                        cleanup.armed = false;
+                        return Err(FailureReason::Request(format!(
+                            "Job `{name}` no longer exists"
+                        )));
+                    }
+                    Err(err) => {
+                        warn!("error getting the status of Job `{name}`: {err}");
+                        continue;
+                    }
+                };
+
+                if status.succeeded.unwrap_or_default() > 0 || status.failed.unwrap_or_default() > 0
+                {
+                    break;
+                }
+
+                if status.active.unwrap_or_default() == 0 {
+                    continue;
+                }
+
+                events.heartbeat();
+
+                // NOTE: as pods are never restarted, a container that started
+                // running can no longer wait, so the pod stops being checked.
+                if !running {
+                    let pod = match pods
+                        .list(&ListParams::default().labels(&format!("job-name={name}")))
+                        .await
+                    {
+                        Ok(pods) => pods.items.into_iter().next(),
+                        Err(err) => {
+                            debug!("error listing the pods of Job `{name}`: {err}");
+                            None
+                        }
+                    };
+
+                    if let Some(pod) = pod {
+                        if let Some(reason) = fatal_waiting(&pod) {
+                            warn!("the container of Job `{name}` cannot start: {reason}");
+                            waiting = Some(reason);
+                            break;
+                        }
+
+                        running = container_status(&pod)
+                            .and_then(|status| status.state.as_ref())
+                            .is_some_and(|state| state.running.is_some());
+                    }
+                }
+            }
+
+            // (3) Get the exit code and logs of the execution from its pod.
+            let pod = pods
+                .list(&ListParams::default().labels(&format!("job-name={name}")))
+                .await
+                .map_err(|err| FailureReason::Request(format!("listing pods of Job: {err}")))?
+                .items
+                .into_iter()
+                .next()
+                .ok_or_else(|| FailureReason::Request(format!("no pod exists for Job `{name}`")))?;
+
+            // SAFETY: the API server always names created objects.
+            let pod_name = pod.metadata.name.clone().unwrap();
+            let (code, digest) = terminated(&pod);
+
+            let logs = match pods.logs(&pod_name, &LogParams::default()).await {
+                Ok(logs) => logs,
+                Err(err) => {
+                    warn!("error getting the logs of pod `{pod_name}`: {err}");
+                    String::new()
+                }
+            };
+
+            // NOTE: a pod that never ran its container (e.g., because its image
+            // could not be pulled) has no exit code, so it is reported as a
+            // failure (with the reason its container could not start as the
+            // standard error).
+            let code = code.unwrap_or(1);
+
+            #[cfg(unix)]
+            let status = ExitStatus::from_raw(code << 8);
+
+            #[cfg(windows)]
+            let status = ExitStatus::from_raw(code as u32);
+
+            events.execution_finished(index, status);
+
+            let output = redirect(
+                execution,
+                Output {
+                    status,
+                    stdout: logs.into_bytes(),
+                    stderr: waiting
+                        .as_ref()
+                        .map(|reason| reason.clone().into_bytes())
+                        .unwrap_or_default(),
+                },
+            )
+            .await;
+
+            provenance.push_execution(ExecutionProvenance::new(
+                execution.image(),
+                digest,
+                Some(name),
+            ));
+
+            // (4) Cleanup the Job (if desired). A Job whose container could
+            // not start is always deleted, as it would otherwise wait forever.
+            cleanup.armed = config.cleanup() || waiting.is_some();
+            drop(cleanup);
+
+            outputs.push(output);
+        }
+
+        let mut outputs = outputs.into_iter();
+
+        // SAFETY: each task _must_ have at least one execution, so at least one
+        // execution result _must_ exist at this stage. Thus, this will always unwrap.
+        let mut executions = NonEmpty::new(outputs.next().unwrap());
+        executions.extend(outputs);
+
+        Ok(TaskResult {
+            executions,
+            provenance,
+            accounting: Default::default(),
+        })
+    }
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::resources::Builder as ResourcesBuilder;
+
+    #[test]
+    fn jobs_are_built_from_tasks() {
+        let execution = Execution::builder()
+            .image("ubuntu")
+            .args(["echo", "hello"])
+            .working_directory("/data")
+            .try_build()
+            .unwrap();
+
+        let task = Task::builder()
+            .name("foo")
+            .extend_executions([execution.clone()])
+            .resources(
+                ResourcesBuilder::default()
+                    .cpu(2usize)
+                    .ram_soft(1.0)
+                    .ram_hard(2.0)
+                    .build(),
+            )
+            .try_build()
+            .unwrap();
+
+        let config = Config::builder()
+            .service_account("runner")
+            .extend_node_selector([("node-pool", "highmem")])
+            .build();
+
+        let job = to_job(&task, &execution, &config);
+        assert_eq!(job.metadata.generate_name.as_deref(), Some(JOB_NAME_PREFIX));
+        assert_eq!(
+            job.metadata.annotations.unwrap()[TASK_ANNOTATION],
+            String::from("foo")
+        );
+
+        let spec = job.spec.unwrap();
+        assert_eq!(spec.backoff_limit, Some(0));
+
+        let pod = spec.template.spec.unwrap();
+        assert_eq!(pod.restart_policy.as_deref(), Some("Never"));
+        assert_eq!(pod.service_account_name.as_deref(), Some("runner"));
+        assert_eq!(
+            pod.node_selector.unwrap()["node-pool"],
+            String::from("highmem")
+        );
+
+        let container = &pod.containers[0];
+        assert_eq!(container.image.as_deref(), Some("ubuntu"));
+        assert_eq!(container.command.as_deref().unwrap(), ["echo", "hello"]);
+        assert_eq!(container.working_dir.as_deref(), Some("/data"));
+
+        let resources = container.resources.as_ref().unwrap();
+        let requests = resources.requests.as_ref().unwrap();
+        let limits = resources.limits.as_ref().unwrap();
+        assert_eq!(requests["cpu"], Quantity(String::from("2")));
+        assert_eq!(requests["memory"], Quantity(String::from("1073741824")));
+        assert_eq!(limits["memory"], Quantity(String::from("2147483648")));
+        assert!(container.lifecycle.is_none());
+    }
+
+    #[test]
+    fn containers_that_cannot_start_are_detected() {
+        let pod = |reason: &str| -> Pod {
+            serde_json::from_value(serde_json::json!({
+                "status": {
+                    "containerStatuses": [{
+                        "name": CONTAINER_NAME,
+                        "image": "ubuntu:nope",
+                        "imageID": "",
+                        "ready": false,
+                        "restartCount": 0,
+                        "state": {
+                            "waiting": {
+                                "reason": reason,
+                                "message": "Back-off pulling image \"ubuntu:nope\"",
+                            },
+                        },
+                    }],
+                },
+            }))
+            .unwrap()
+        };
+
+        assert_eq!(
+            fatal_waiting(&pod("ImagePullBackOff")).as_deref(),
+            Some("ImagePullBackOff: Back-off pulling image \"ubuntu:nope\"")
+        );
+
+        // Containers that are merely being created are still starting.
+        assert_eq!(fatal_waiting(&pod("ContainerCreating")), None);
+        assert_eq!(fatal_waiting(&Pod::default()), None);
+    }
+
+    #[test]
+    fn preemptible_tasks_are_placed_and_checkpointed() {
+        let execution = Execution::builder()
+            .image("ubuntu")
+            .args(["sleep", "infinity"])
+            .try_build()
+            .unwrap();
+
+        let config = Config::builder()
+            .extend_node_selector([("node-pool", "highmem")])
+            .extend_preemptible_node_selector([("spot", "true")])
+            .build();
+
+        let task = Task::builder()
+            .extend_executions([execution.clone()])
+            .resources(ResourcesBuilder::default().preemptible(true).build())
+            .checkpoint_command(["sh", "-c", "touch /data/checkpoint"])
+            .try_build()
+            .unwrap();
+
+        let job = to_job(&task, &execution, &config);
+        let pod = job.spec.unwrap().template.spec.unwrap();
+        let node_selector = pod.node_selector.unwrap();
+        assert_eq!(node_selector["node-pool"], String::from("highmem"));
+        assert_eq!(node_selector["spot"], String::from("true"));
+
+        let pre_stop = pod.containers[0]
+            .lifecycle
+            .as_ref()
+            .and_then(|lifecycle| lifecycle.pre_stop.as_ref())
+            .and_then(|handler| handler.exec.as_ref())
+            .and_then(|exec| exec.command.as_deref())
+            .unwrap();
+        assert_eq!(pre_stop, ["sh", "-c", "touch /data/checkpoint"]);
+
+        // Tasks that are not preemptible are not placed on preemptible nodes.
+        let task = Task::builder()
+            .extend_executions([execution.clone()])
+            .try_build()
+            .unwrap();
+
+        let job = to_job(&task, &execution, &config);
+        let pod = job.spec.unwrap().template.spec.unwrap();
+        assert!(!pod.node_selector.unwrap().contains_key("spot"));
+    }
+}
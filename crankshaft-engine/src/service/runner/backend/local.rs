@@ -0,0 +1,285 @@
+//! A local backend.
+//!
+//! Executions are run directly as child processes of the engine (using
+//! [`tokio::process`]) rather than within containers, which is useful for
+//! quick testing and for sites where neither Docker nor an HPC scheduler is
+//! available. As such, the image of each execution is ignored.
+//!
+//! The working directory, environment variables, standard input, and
+//! redirections of the standard output and standard error of each execution
+//! are honored. Child processes are killed when their task is canceled.
+//!
+//! Task inputs, outputs, and shared volumes (whose paths refer to paths within
+//! a container) are not supported: tasks that use them fail with
+//! [`FailureReason::Unsupported`]. Neither are executions that run as a
+//! particular user.
+
+use std::process::Output;
+use std::process::Stdio;
+
+use crankshaft_config::backend::local::Config;
+use futures::FutureExt as _;
+use futures::future::BoxFuture;
+use nonempty::NonEmpty;
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::Task;
+use crate::events::TaskEvents;
+use crate::service::runner::backend::FailureReason;
+use crate::service::runner::backend::TaskResult;
+use crate::service::runner::backend::generic::driver::exit_status;
+use crate::service::runner::backend::provenance::ExecutionProvenance;
+use crate::service::runner::backend::provenance::Provenance;
+use crate::service::runner::backend::redirect;
+use crate::task::Execution;
+
+/// A local execution backend.
+#[derive(Debug, Default)]
+pub struct Backend {
+    /// The inner configuration.
+    config: Config,
+}
+
+impl Backend {
+    /// Creates a new local [`Backend`] with the provided configuration for the
+    /// backend.
+    pub fn new(config: Config) -> Self {
+        Self { config }
+    }
+}
+
+impl crate::Backend for Backend {
+    fn default_name(&self) -> &'static str {
+        "local"
+    }
+
+    fn run(
+        &self,
+        task: Task,
+        events: TaskEvents,
+    ) -> BoxFuture<'static, std::result::Result<TaskResult, FailureReason>> {
+        let clear_env = self.config.clear_env();
+
+        async move {
+            if task.inputs().is_some()
+                || task.outputs().is_some()
+                || task.shared_volumes().is_some()
+            {
+                return Err(FailureReason::Unsupported(String::from(
+                    "the local backend does not support task inputs, outputs, or shared volumes",
+                )));
+            }
+
+            if task
+                .executions()
+                .any(|execution| execution.user().is_some())
+            {
+                return Err(FailureReason::Unsupported(String::from(
+                    "the local backend does not support running executions as a user",
+                )));
+            }
+
+            let mut outputs = Vec::new();
+            let mut provenance = Provenance::default();
+
+            for (index, execution) in task.executions().enumerate() {
+                events.execution_started(index);
+
+                let (output, pid) = match spawn(execution, clear_env) {
+                    Ok(child) => {
+                        let pid = child.id();
+                        debug!("spawned process {pid:?} for execution {index}");
+
+                        // NOTE: the child is killed if this future is dropped
+                        // (i.e., when the task is canceled).
+                        let output = events
+                            .with_heartbeats(child.wait_with_output())
+                            .await
+                            .unwrap_or_else(|err| failed(1, &format!("waiting on process: {err}")));
+
+                        (output, pid)
+                    }
+                    Err(output) => (output, None),
+                };
+
+                events.execution_finished(index, output.status);
+                outputs.push(redirect(execution, output).await);
+
+                provenance.push_execution(ExecutionProvenance::new(
+                    execution.image(),
+                    None,
+                    pid.map(|pid| pid.to_string()),
+                ));
+            }
+
+            let mut outputs = outputs.into_iter();
+
+            // SAFETY: each task _must_ have at least one execution, so at least one
+            // execution result _must_ exist at this stage. Thus, this will always unwrap.
+            let mut executions = NonEmpty::new(outputs.next().unwrap());
+            executions.extend(outputs);
+
+            Ok(TaskResult {
+                executions,
+                provenance,
+                accounting: Default::default(),
+            })
+        }
+        .boxed()
+    }
+}
+
+/// Creates the output of an execution that failed before (or while) its
+/// process ran.
+fn failed(code: i32, message: &str) -> Output {
+    Output {
+        status: exit_status(code),
+        stdout: Vec::new(),
+        stderr: format!("{message}\n").into_bytes(),
+    }
+}
+
+/// Spawns the process of an execution.
+///
+/// If the process cannot be spawned, the output of the failed execution is
+/// returned instead (with the exit codes a shell would use).
+fn spawn(
+    execution: &Execution,
+    clear_env: bool,
+) -> std::result::Result<tokio::process::Child, Output> {
+    let args = execution.args();
+    let mut command = Command::new(args.first());
+    command
+        .args(args.tail())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    if clear_env {
+        command.env_clear();
+    }
+
+    if let Some(env) = execution.env() {
+        command.envs(env);
+    }
+
+    if let Some(workdir) = execution.workdir() {
+        command.current_dir(workdir);
+    }
+
+    match execution.stdin() {
+        Some(path) => {
+            let file = std::fs::File::open(path)
+                .map_err(|err| failed(1, &format!("opening standard input `{path}`: {err}")))?;
+            command.stdin(file);
+        }
+        None => {
+            command.stdin(Stdio::null());
+        }
+    }
+
+    command.spawn().map_err(|err| {
+        let code = match err.kind() {
+            std::io::ErrorKind::NotFound => 127,
+            _ => 126,
+        };
+
+        failed(code, &format!("spawning `{}`: {err}", args.first()))
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::Backend as _;
+    use crate::events::Events;
+    use crate::task::execution::Redirection;
+
+    /// Runs a task with a single execution in a local backend.
+    async fn run(config: Config, execution: Execution) -> TaskResult {
+        let task = Task::builder()
+            .name("foo")
+            .extend_executions([execution])
+            .try_build()
+            .unwrap();
+
+        let events = Events::default();
+        Backend::new(config)
+            .run(task, events.task(events.next_id(), "local"))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn executions_run_as_processes() {
+        let dir = tempfile::tempdir().unwrap();
+        let stdin = dir.path().join("stdin");
+        std::fs::write(&stdin, "from stdin").unwrap();
+
+        let result = run(
+            Config::default(),
+            Execution::builder()
+                .image("ignored")
+                .args(["sh", "-c", "pwd; echo $FOO; cat; echo oops >&2; exit 3"])
+                .working_directory(dir.path().to_str().unwrap())
+                .env("FOO", "bar")
+                .stdin(stdin.to_str().unwrap())
+                .stderr(Redirection::File(dir.path().join("stderr")))
+                .try_build()
+                .unwrap(),
+        )
+        .await;
+
+        let output = result.executions().first();
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            format!(
+                "{}\nbar\nfrom stdin",
+                dir.path().canonicalize().unwrap().display()
+            )
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("stderr")).unwrap(),
+            "oops\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn the_environment_is_optionally_cleared() {
+        // NOTE: `cargo` sets this variable for the test process.
+        let execution = Execution::builder()
+            .image("ignored")
+            .args(["sh", "-c", "echo ${CARGO_MANIFEST_DIR:-unset}"])
+            .try_build()
+            .unwrap();
+
+        let result = run(Config::default(), execution.clone()).await;
+        let output = result.executions().first();
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            format!("{}\n", env!("CARGO_MANIFEST_DIR"))
+        );
+
+        let result = run(Config::builder().clear_env(true).build(), execution).await;
+        let output = result.executions().first();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "unset\n");
+    }
+
+    #[tokio::test]
+    async fn missing_programs_exit_with_127() {
+        let result = run(
+            Config::default(),
+            Execution::builder()
+                .image("ignored")
+                .args(["crankshaft-does-not-exist"])
+                .try_build()
+                .unwrap(),
+        )
+        .await;
+
+        assert_eq!(result.executions().first().status.code(), Some(127));
+    }
+}
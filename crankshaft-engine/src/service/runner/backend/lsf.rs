@@ -0,0 +1,590 @@
+//! An LSF backend.
+//!
+//! Unlike a [generic backend](super::generic) configured for LSF, this backend
+//! knows how to talk to LSF natively:
+//!
+//! * Jobs are submitted with `bsub`, with the resources of the task (and the
+//!   queue and project from the configuration) mapped onto `bsub` arguments.
+//! * Jobs are monitored with `bjobs -o`, which reports the fine-grained state
+//!   of each job (`PEND`, `RUN`, `DONE`, `EXIT`, and so on) along with its exit
+//!   code. Failures to query a job are retried, and a job is only given up on
+//!   once LSF has repeatedly reported that it does not know the job.
+//! * The standard output and standard error of each job are written to files
+//!   within the working directory of the job, which are read (and removed) once
+//!   the job completes.
+//!
+//! As jobs may wait within a queue for a long time after they are submitted,
+//! the [`TaskStarted`](crate::events::Event::TaskStarted) event of a task is
+//! only sent once its first job is observed to be running (see
+//! [`Backend::reports_started()`](crate::Backend::reports_started)).
+
+use std::collections::HashMap;
+use std::process::ExitStatus;
+use std::process::Output;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crankshaft_config::backend::Defaults;
+use crankshaft_config::backend::lsf::Config;
+use futures::FutureExt as _;
+use futures::future::BoxFuture;
+use nonempty::NonEmpty;
+use tracing::debug;
+use tracing::warn;
+
+use crate::Result;
+use crate::Task;
+use crate::events::TaskEvents;
+use crate::service::runner::backend::Accounting;
+use crate::service::runner::backend::FailureReason;
+use crate::service::runner::backend::TaskResult;
+use crate::service::runner::backend::generic::DEFAULT_MONITOR_FREQUENCY;
+use crate::service::runner::backend::generic::driver::Cancel;
+use crate::service::runner::backend::generic::driver::Driver;
+use crate::service::runner::backend::generic::driver::exit_status;
+use crate::service::runner::backend::generic::driver::quote;
+use crate::service::runner::backend::generic::driver::with_env;
+use crate::service::runner::backend::generic::resolve_resources;
+use crate::service::runner::backend::provenance::ExecutionProvenance;
+use crate::service::runner::backend::provenance::Provenance;
+use crate::service::runner::backend::redirect;
+use crate::task::Execution;
+use crate::task::Resources;
+
+/// The fields queried from `bjobs` for a job.
+const BJOBS_FIELDS: &str = "stat exit_code exec_host run_time delimiter='|'";
+
+/// The error reported by `bjobs` for a job that LSF does not know about.
+const NOT_FOUND: &str = "is not found";
+
+/// The number of consecutive times `bjobs` must report that a job is not
+/// found before the job is given up on.
+const NOT_FOUND_ATTEMPTS: usize = 5;
+
+/// An LSF execution backend.
+#[derive(Debug)]
+pub struct Backend {
+    /// The driver.
+    driver: Arc<Driver>,
+
+    /// The inner configuration.
+    config: Config,
+
+    /// The execution defaults.
+    defaults: Option<Defaults>,
+}
+
+impl Backend {
+    /// Attempts to initialize a new LSF [`Backend`] with the provided
+    /// configuration for the backend.
+    pub async fn initialize(config: Config, defaults: Option<Defaults>) -> Result<Self> {
+        let driver = Driver::initialize(config.driver().clone())
+            .await
+            .map(Arc::new)?;
+
+        Ok(Self {
+            driver,
+            config,
+            defaults,
+        })
+    }
+}
+
+impl crate::Backend for Backend {
+    fn default_name(&self) -> &'static str {
+        "lsf"
+    }
+
+    fn reports_started(&self) -> bool {
+        true
+    }
+
+    fn run(
+        &self,
+        task: Task,
+        events: TaskEvents,
+    ) -> BoxFuture<'static, std::result::Result<TaskResult, FailureReason>> {
+        run(self, task, events)
+    }
+}
+
+/// Gets the path of the file that a stream of a job is written to.
+///
+/// The path contains the `$LSB_JOBID` variable, so it must be expanded by the
+/// shell of the job (or have the variable replaced with the job id).
+fn stream_path(execution: &Execution, extension: &str) -> String {
+    let file = format!("crankshaft-$LSB_JOBID.{extension}");
+
+    match execution.workdir() {
+        Some(workdir) => format!("{}/{file}", workdir.trim_end_matches('/')),
+        None => file,
+    }
+}
+
+/// Converts a number of gigabytes into megabytes (rounding up).
+fn megabytes(gigabytes: f64) -> u64 {
+    (gigabytes * 1024.0).ceil() as u64
+}
+
+/// Gets the `bsub` command that submits an execution of a task.
+fn bsub(
+    task: &Task,
+    execution: &Execution,
+    resources: Option<&Resources>,
+    config: &Config,
+) -> String {
+    let mut args = vec![String::from("bsub")];
+
+    if let Some(name) = task.name() {
+        args.push(format!("-J {}", quote(name)));
+    }
+
+    if let Some(queue) = config.queue() {
+        args.push(format!("-q {}", quote(queue)));
+    }
+
+    if let Some(project) = config.project() {
+        args.push(format!("-P {}", quote(project)));
+    }
+
+    // NOTE: every execution runs on a single host.
+    let mut requirements = vec![String::from("span[hosts=1]")];
+
+    if let Some(resources) = resources {
+        if let Some(cpu) = resources.cpu() {
+            args.push(format!("-n {cpu}"));
+        }
+
+        let mut usage = Vec::new();
+
+        if let Some(ram) = resources.ram_soft() {
+            usage.push(format!("mem={}MB", megabytes(ram)));
+        }
+
+        if let Some(disk) = resources.disk() {
+            usage.push(format!("tmp={}MB", megabytes(disk)));
+        }
+
+        if !usage.is_empty() {
+            requirements.insert(0, format!("rusage[{}]", usage.join(",")));
+        }
+
+        if let Some(ram) = resources.ram_hard() {
+            args.push(format!("-M {}MB", megabytes(ram)));
+        }
+    }
+
+    args.push(format!("-R {}", quote(&requirements.join(" "))));
+
+    if let Some(workdir) = execution.workdir() {
+        args.push(format!("-cwd {}", quote(workdir)));
+    }
+
+    // NOTE: LSF appends a job report to the output file of a job, so the
+    // streams are redirected by the job itself instead.
+    args.push(String::from("-o /dev/null"));
+    args.extend(config.extra_args().iter().cloned());
+
+    let shell = with_env(
+        execution,
+        execution
+            .args()
+            .iter()
+            .map(|arg| quote(arg))
+            .collect::<Vec<_>>()
+            .join(" "),
+    );
+
+    // NOTE: the paths are double quoted so that `$LSB_JOBID` is expanded by
+    // the shell of the job.
+    let command = format!(
+        "({shell}) > \"{}\" 2> \"{}\"",
+        stream_path(execution, "out"),
+        stream_path(execution, "err")
+    );
+
+    args.push(quote(&command));
+    args.join(" ")
+}
+
+/// Parses the job id from the output of `bsub`.
+///
+/// The output is of the form `Job <id> is submitted to queue <queue>.`.
+fn parse_job_id(stdout: &str) -> Option<&str> {
+    let (_, rest) = stdout.split_once("Job <")?;
+    let (id, _) = rest.split_once('>')?;
+    (!id.is_empty() && id.chars().all(|c| c.is_ascii_digit())).then_some(id)
+}
+
+/// The state of a job as reported by `bjobs`.
+#[derive(Debug, PartialEq, Eq)]
+struct Record {
+    /// The state of the job (e.g., `PEND` or `RUN`).
+    state: String,
+
+    /// The exit code of the job (if it exited with a nonzero code).
+    code: Option<i32>,
+
+    /// The host the job is executing on.
+    host: String,
+
+    /// The run time of the job.
+    run_time: String,
+}
+
+impl Record {
+    /// Gets whether or not the job has finished.
+    fn is_finished(&self) -> bool {
+        matches!(self.state.as_str(), "DONE" | "EXIT")
+    }
+
+    /// Gets whether or not the job has been suspended.
+    fn is_suspended(&self) -> bool {
+        matches!(self.state.as_str(), "PSUSP" | "USUSP" | "SSUSP")
+    }
+
+    /// Gets the exit status of a finished job.
+    fn status(&self) -> ExitStatus {
+        // NOTE: `bjobs` reports no exit code for jobs that succeeded, and jobs
+        // that were killed before they ran have no exit code to report.
+        let code = match (self.state.as_str(), self.code) {
+            ("DONE", _) => 0,
+            (_, Some(code)) => code,
+            (_, None) => 1,
+        };
+
+        exit_status(code)
+    }
+
+    /// Gets the record as accounting data.
+    fn accounting(&self) -> Accounting {
+        [
+            ("state", self.state.clone()),
+            ("host", self.host.clone()),
+            ("run_time", self.run_time.clone()),
+        ]
+        .into_iter()
+        .map(|(key, value)| (String::from(key), value.into()))
+        .collect()
+    }
+}
+
+/// Parses the output of `bjobs -noheader -o "stat exit_code exec_host
+/// run_time delimiter='|'"`.
+fn parse_bjobs(stdout: &str) -> Option<Record> {
+    let line = stdout.lines().find(|line| !line.trim().is_empty())?;
+    let mut fields = line.trim().split('|');
+
+    let state = fields.next()?.trim().to_owned();
+    let code = fields.next()?.trim().parse().ok();
+
+    // NOTE: `bjobs` reports missing values as `-`.
+    let mut field = || {
+        fields
+            .next()
+            .map(str::trim)
+            .filter(|value| *value != "-")
+            .unwrap_or_default()
+            .to_owned()
+    };
+
+    Some(Record {
+        state,
+        code,
+        host: field(),
+        run_time: field(),
+    })
+}
+
+/// Runs a [`Task`] in the backend.
+fn run(
+    backend: &Backend,
+    task: Task,
+    events: TaskEvents,
+) -> BoxFuture<'static, std::result::Result<TaskResult, FailureReason>> {
+    let driver = backend.driver.clone();
+    let config = backend.config.clone();
+    let resources = resolve_resources(backend.defaults.as_ref(), task.resources());
+    let frequency = Duration::from_secs(
+        config
+            .monitor_frequency()
+            .unwrap_or(DEFAULT_MONITOR_FREQUENCY),
+    );
+
+    async move {
+        let mut outputs = Vec::new();
+        let mut accounting = HashMap::new();
+        let mut provenance = Provenance::default().with_resources(resources.clone());
+        let mut started = false;
+
+        for (index, execution) in task.executions().enumerate() {
+            // NOTE: the output files of a job must be read in the same place
+            // that the job was submitted from (e.g., on the same host of a
+            // pool).
+            let lease = driver
+                .lease()
+                .await
+                .map_err(|err| FailureReason::Request(format!("leasing a host: {err}")))?;
+
+            // (1) Submitting the job.
+            let output = lease
+                .run(bsub(&task, execution, resources.as_ref(), &config))
+                .await
+                .map_err(|err| FailureReason::Request(format!("running `bsub`: {err}")))?;
+
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let id = match parse_job_id(&stdout) {
+                Some(id) if output.status.success() => id.to_owned(),
+                _ => {
+                    return Err(FailureReason::SubmissionParseError {
+                        stdout: stdout.into_owned(),
+                        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+                        pattern: String::from("Job <<job id>>"),
+                    });
+                }
+            };
+
+            debug!("submitted job `{id}` for execution {index}");
+
+            let mut cancel = Cancel::new(driver.clone(), format!("bkill {id}"));
+
+            // (2) Monitoring the job until it finishes.
+            let mut running = false;
+            let mut suspended = false;
+            let mut not_found = 0;
+
+            let record = loop {
+                tokio::time::sleep(frequency).await;
+
+                let output = match lease
+                    .run(format!("bjobs -noheader -o \"{BJOBS_FIELDS}\" {id}"))
+                    .await
+                {
+                    Ok(output) => output,
+                    Err(err) => {
+                        warn!("failed to query the state of job `{id}`: {err}");
+                        continue;
+                    }
+                };
+
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                let stderr = String::from_utf8_lossy(&output.stderr);
+
+                let record = match parse_bjobs(&stdout) {
+                    Some(record) if output.status.success() => record,
+                    // NOTE: `bjobs` fails for jobs that are no longer known
+                    // to LSF (e.g., because they finished long ago). As a
+                    // restarting batch daemon may also briefly forget about a
+                    // job, the job is only given up on after repeated answers.
+                    _ if stdout.contains(NOT_FOUND) || stderr.contains(NOT_FOUND) => {
+                        not_found += 1;
+
+                        if not_found >= NOT_FOUND_ATTEMPTS {
+                            break None;
+                        }
+
+                        debug!("job `{id}` was not found ({not_found} of {NOT_FOUND_ATTEMPTS})");
+                        continue;
+                    }
+                    _ => {
+                        warn!("failed to query the state of job `{id}`: {}", stderr.trim());
+                        continue;
+                    }
+                };
+
+                not_found = 0;
+
+                // NOTE: jobs that finish between polls are never observed
+                // running, but they still started.
+                if !running && (record.state == "RUN" || record.is_finished()) {
+                    running = true;
+
+                    if !started {
+                        started = true;
+                        events.started();
+                    }
+
+                    events.execution_started(index);
+                }
+
+                if record.is_finished() {
+                    cancel.disarm();
+                    break Some(record);
+                }
+
+                if record.is_suspended() {
+                    if !suspended {
+                        suspended = true;
+                        events.suspended(record.state);
+                    }
+                } else {
+                    suspended = false;
+
+                    if record.state == "RUN" {
+                        events.heartbeat();
+                    }
+                }
+            };
+
+            // NOTE: a job without a record has an unknown exit code, so it is
+            // reported as a failure.
+            let status = match &record {
+                Some(record) => {
+                    events.accounted(index, record.accounting());
+                    accounting.insert(index, record.accounting());
+                    record.status()
+                }
+                None => {
+                    warn!("job `{id}` is no longer known to LSF");
+                    exit_status(1)
+                }
+            };
+
+            events.execution_finished(index, status);
+
+            // (3) Reading (and removing) the output files of the job.
+            let mut streams = Vec::new();
+
+            for extension in ["out", "err"] {
+                let path = quote(&stream_path(execution, extension).replace("$LSB_JOBID", &id));
+
+                match lease.run(format!("cat {path} && rm -f {path}")).await {
+                    Ok(output) => streams.push(output.stdout),
+                    Err(err) => {
+                        warn!("failed to read the output of job `{id}`: {err}");
+                        streams.push(Vec::new());
+                    }
+                }
+            }
+
+            // SAFETY: exactly two streams are pushed above.
+            let stderr = streams.pop().unwrap();
+            let stdout = streams.pop().unwrap();
+
+            outputs.push(
+                redirect(
+                    execution,
+                    Output {
+                        status,
+                        stdout,
+                        stderr,
+                    },
+                )
+                .await,
+            );
+
+            provenance.push_execution(ExecutionProvenance::new(execution.image(), None, Some(id)));
+        }
+
+        let mut outputs = outputs.into_iter();
+
+        // SAFETY: each task _must_ have at least one execution, so at least one
+        // execution result _must_ exist at this stage. Thus, this will always unwrap.
+        let mut executions = NonEmpty::new(outputs.next().unwrap());
+        executions.extend(outputs);
+
+        Ok(TaskResult {
+            executions,
+            provenance,
+            accounting,
+        })
+    }
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::resources::Builder as ResourcesBuilder;
+
+    #[test]
+    fn bsub_commands_are_built() {
+        let execution = Execution::builder()
+            .image("ubuntu")
+            .args(["echo", "hello world"])
+            .working_directory("/scratch")
+            .try_build()
+            .unwrap();
+
+        let task = Task::builder()
+            .name("foo")
+            .extend_executions([execution.clone()])
+            .try_build()
+            .unwrap();
+
+        let resources = ResourcesBuilder::default().cpu(2usize).ram(1.5).build();
+        let config = Config::builder()
+            .queue("compbio")
+            .project("lab")
+            .extend_extra_args(["-W 60"])
+            .build();
+
+        let args = shlex::split(&bsub(&task, &execution, Some(&resources), &config)).unwrap();
+        assert_eq!(
+            args,
+            [
+                "bsub",
+                "-J",
+                "foo",
+                "-q",
+                "compbio",
+                "-P",
+                "lab",
+                "-n",
+                "2",
+                "-M",
+                "1536MB",
+                "-R",
+                "rusage[mem=1536MB] span[hosts=1]",
+                "-cwd",
+                "/scratch",
+                "-o",
+                "/dev/null",
+                "-W",
+                "60",
+                "(echo 'hello world') > \"/scratch/crankshaft-$LSB_JOBID.out\" 2> \
+                 \"/scratch/crankshaft-$LSB_JOBID.err\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn job_ids_are_parsed() {
+        assert_eq!(
+            parse_job_id("Job <1234> is submitted to queue <compbio>.\n"),
+            Some("1234")
+        );
+        assert_eq!(parse_job_id("Queue <foo> is not a valid queue."), None);
+        assert_eq!(parse_job_id(""), None);
+    }
+
+    #[test]
+    fn bjobs_records_are_parsed() {
+        let record = parse_bjobs("PEND|-|-|0 second(s)\n").unwrap();
+        assert_eq!(record.state, "PEND");
+        assert!(!record.is_finished());
+        assert_eq!(record.host, "");
+
+        let record = parse_bjobs("RUN|-|node01|10 second(s)\n").unwrap();
+        assert!(!record.is_finished());
+        assert_eq!(record.accounting()["host"], "node01");
+
+        let record = parse_bjobs("DONE|-|node01|65 second(s)\n").unwrap();
+        assert!(record.is_finished());
+        assert_eq!(record.status().code(), Some(0));
+
+        let record = parse_bjobs("EXIT|2|node01|65 second(s)\n").unwrap();
+        assert!(record.is_finished());
+        assert_eq!(record.status().code(), Some(2));
+
+        let record = parse_bjobs("EXIT|-|-|0 second(s)\n").unwrap();
+        assert!(!record.status().success());
+
+        assert!(
+            parse_bjobs("USUSP|-|node01|5 second(s)")
+                .unwrap()
+                .is_suspended()
+        );
+        assert_eq!(parse_bjobs(""), None);
+    }
+}
@@ -0,0 +1,127 @@
+//! A mock backend.
+//!
+//! The mock backend does not run anything: each execution simply sleeps for a
+//! simulated duration and then exits with a simulated exit code. Because the
+//! sleep is a [`tokio`] timer, the mock backend pairs with the paused time of
+//! a [simulation runtime](crate::simulation::runtime()) to run thousands of
+//! virtual tasks in milliseconds.
+
+#[cfg(unix)]
+use std::os::unix::process::ExitStatusExt as _;
+#[cfg(windows)]
+use std::os::windows::process::ExitStatusExt as _;
+use std::process::ExitStatus;
+use std::process::Output;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::FutureExt as _;
+use futures::future::BoxFuture;
+use nonempty::NonEmpty;
+
+use crate::Task;
+use crate::events::TaskEvents;
+use crate::service::runner::backend::FailureReason;
+use crate::service::runner::backend::TaskResult;
+
+/// The simulated outcome of an execution: how long it runs for and the exit
+/// code it exits with.
+pub type Outcome = (Duration, i32);
+
+/// A function that determines the simulated outcome of an execution given the
+/// task and the index of the execution within the task.
+pub type Behavior = dyn Fn(&Task, usize) -> Outcome + Send + Sync;
+
+/// A mock backend.
+#[derive(Clone)]
+pub struct Backend {
+    /// The behavior of the executions.
+    behavior: Arc<Behavior>,
+}
+
+impl Backend {
+    /// Creates a new mock [`Backend`] where the outcome of each execution is
+    /// determined by `behavior`.
+    pub fn new(behavior: impl Fn(&Task, usize) -> Outcome + Send + Sync + 'static) -> Self {
+        Self {
+            behavior: Arc::new(behavior),
+        }
+    }
+
+    /// Creates a new mock [`Backend`] where every execution runs for
+    /// `duration` and exits with `code`.
+    pub fn constant(duration: Duration, code: i32) -> Self {
+        Self::new(move |_, _| (duration, code))
+    }
+}
+
+impl std::fmt::Debug for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Backend").finish_non_exhaustive()
+    }
+}
+
+impl crate::Backend for Backend {
+    fn default_name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn terminates_gracefully(&self) -> bool {
+        true
+    }
+
+    fn run(
+        &self,
+        task: Task,
+        events: TaskEvents,
+    ) -> BoxFuture<'static, std::result::Result<TaskResult, FailureReason>> {
+        let behavior = self.behavior.clone();
+
+        async move {
+            let mut outputs = Vec::new();
+
+            for index in 0..task.executions().count() {
+                let (duration, code) = behavior(&task, index);
+
+                events.execution_started(index);
+
+                tokio::select! {
+                    _ = events.with_heartbeats(tokio::time::sleep(duration)) => {}
+                    _ = events.terminated() => {
+                        return Err(FailureReason::Request(String::from(
+                            "the task was terminated",
+                        )));
+                    }
+                }
+
+                #[cfg(unix)]
+                let status = ExitStatus::from_raw(code << 8);
+
+                #[cfg(windows)]
+                let status = ExitStatus::from_raw(code as u32);
+
+                events.execution_finished(index, status);
+
+                outputs.push(Output {
+                    status,
+                    stdout: Vec::new(),
+                    stderr: Vec::new(),
+                });
+            }
+
+            let mut outputs = outputs.into_iter();
+
+            // SAFETY: each task _must_ have at least one execution, so at least one
+            // execution result _must_ exist at this stage. Thus, this will always unwrap.
+            let mut executions = NonEmpty::new(outputs.next().unwrap());
+            executions.extend(outputs);
+
+            Ok(TaskResult {
+                executions,
+                provenance: Default::default(),
+                accounting: Default::default(),
+            })
+        }
+        .boxed()
+    }
+}
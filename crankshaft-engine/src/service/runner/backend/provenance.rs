@@ -0,0 +1,163 @@
+//! Provenance of task results.
+//!
+//! Every [`TaskResult`](super::TaskResult) carries a [`Provenance`] record
+//! describing the environment the task was run in: the backend (and a hash of
+//! its configuration), the version of Crankshaft, the effective resources, and
+//! (per execution) the resolved image digest and the backend-native id. These
+//! records support auditing the reproducibility of results after the fact.
+//!
+//! Backends fill in what they are able to observe (e.g., the TES backend cannot
+//! resolve image digests), so every field other than the backend and the
+//! version is optional.
+
+use sha2::Digest as _;
+use sha2::Sha256;
+
+use crate::task::Resources;
+
+/// The version of Crankshaft recorded within [`Provenance`] records.
+pub const CRANKSHAFT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The provenance of a single execution within a task.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExecutionProvenance {
+    /// The image the execution was requested to run in.
+    image: String,
+
+    /// The resolved digest of the image (if the backend resolves images).
+    digest: Option<String>,
+
+    /// The backend-native id of the execution (e.g., a container id).
+    native_id: Option<String>,
+}
+
+impl ExecutionProvenance {
+    /// Creates a new [`ExecutionProvenance`].
+    pub(crate) fn new(
+        image: impl Into<String>,
+        digest: Option<String>,
+        native_id: Option<String>,
+    ) -> Self {
+        Self {
+            image: image.into(),
+            digest,
+            native_id,
+        }
+    }
+
+    /// Gets the image the execution was requested to run in.
+    pub fn image(&self) -> &str {
+        &self.image
+    }
+
+    /// Gets the resolved digest of the image (if it was resolved).
+    pub fn digest(&self) -> Option<&str> {
+        self.digest.as_deref()
+    }
+
+    /// Gets the backend-native id of the execution (if it exists).
+    pub fn native_id(&self) -> Option<&str> {
+        self.native_id.as_deref()
+    }
+}
+
+/// The provenance of a task result.
+#[derive(Clone, Debug)]
+pub struct Provenance {
+    /// The name of the backend within the engine.
+    backend: String,
+
+    /// The hash of the configuration of the backend (if the backend was
+    /// initialized from a configuration).
+    config_hash: Option<String>,
+
+    /// The version of Crankshaft.
+    version: &'static str,
+
+    /// The backend-native id of the task (e.g., a TES task id).
+    native_id: Option<String>,
+
+    /// The effective resources of the task.
+    resources: Option<Resources>,
+
+    /// The provenance of each execution within the task.
+    executions: Vec<ExecutionProvenance>,
+}
+
+impl Default for Provenance {
+    fn default() -> Self {
+        Self {
+            backend: Default::default(),
+            config_hash: Default::default(),
+            version: CRANKSHAFT_VERSION,
+            native_id: Default::default(),
+            resources: Default::default(),
+            executions: Default::default(),
+        }
+    }
+}
+
+impl Provenance {
+    /// Gets the name of the backend within the engine.
+    pub fn backend(&self) -> &str {
+        &self.backend
+    }
+
+    /// Gets the hash of the configuration of the backend (if the backend was
+    /// initialized from a configuration).
+    pub fn config_hash(&self) -> Option<&str> {
+        self.config_hash.as_deref()
+    }
+
+    /// Gets the version of Crankshaft that ran the task.
+    pub fn version(&self) -> &str {
+        self.version
+    }
+
+    /// Gets the backend-native id of the task (if it exists).
+    pub fn native_id(&self) -> Option<&str> {
+        self.native_id.as_deref()
+    }
+
+    /// Gets the effective resources of the task (if they are known).
+    pub fn resources(&self) -> Option<&Resources> {
+        self.resources.as_ref()
+    }
+
+    /// Gets the provenance of each execution within the task.
+    pub fn executions(&self) -> &[ExecutionProvenance] {
+        &self.executions
+    }
+
+    /// Sets the backend-native id of the task.
+    pub(crate) fn with_native_id(mut self, id: impl Into<String>) -> Self {
+        self.native_id = Some(id.into());
+        self
+    }
+
+    /// Sets the effective resources of the task.
+    pub(crate) fn with_resources(mut self, resources: Option<Resources>) -> Self {
+        self.resources = resources;
+        self
+    }
+
+    /// Adds the provenance of an execution within the task.
+    pub(crate) fn push_execution(&mut self, execution: ExecutionProvenance) {
+        self.executions.push(execution);
+    }
+
+    /// Sets the backend that ran the task.
+    pub(crate) fn set_backend(&mut self, name: &str, config_hash: Option<&str>) {
+        self.backend = name.to_owned();
+        self.config_hash = config_hash.map(ToOwned::to_owned);
+    }
+}
+
+/// Computes the hash of a configuration.
+///
+/// The hash is the hex-encoded SHA-256 of the JSON serialization of the
+/// configuration.
+pub(crate) fn hash(config: &impl serde::Serialize) -> String {
+    // SAFETY: configurations only contain types that always serialize to JSON.
+    hex::encode(Sha256::digest(serde_json::to_vec(config).unwrap()))
+}
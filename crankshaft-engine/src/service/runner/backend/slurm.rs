@@ -0,0 +1,732 @@
+//! A SLURM backend.
+//!
+//! Unlike a [generic backend](super::generic) configured for SLURM, this
+//! backend knows how to talk to SLURM natively:
+//!
+//! * Jobs are submitted with `sbatch --parsable`, with the resources of the
+//!   task (and the partition, account, and QOS from the configuration) mapped
+//!   onto `sbatch` arguments.
+//! * Jobs are monitored with `squeue` until they leave the active states.
+//! * The exit code (and accounting data) of each job is queried with `sacct`.
+//!   Jobs are only considered finished once `sacct` reports a terminal state,
+//!   so a controller that briefly fails to answer `squeue` does not end the
+//!   monitoring of a job that is still running.
+//! * The standard output and standard error of each job are written to files
+//!   within the working directory of the job, which are read (and removed) once
+//!   the job completes.
+//! * Jobs that were preempted fail with [`FailureReason::Preempted`] (so that
+//!   they may be [retried](crate::task::RetryPolicy)).
+//! * Preemptible tasks are submitted to the preemptible partition (if one is
+//!   configured).
+//! * If a task has a checkpoint command, the batch shell of each job traps the
+//!   signals sent ahead of preemption (`SIGTERM`) and of the end of the time
+//!   limit (`SIGUSR1`, requested with `--signal`) and runs the command before
+//!   the job is killed. The exit code of the command is written to a marker
+//!   file, which is reported as a
+//!   [`Event::TaskCheckpointed`](crate::events::Event::TaskCheckpointed) event
+//!   once the job completes.
+//!
+//! Commands are run through the [driver](super::generic::driver) of generic
+//! backends, so they may be run locally or on a login node over SSH.
+
+use std::collections::HashMap;
+#[cfg(unix)]
+use std::os::unix::process::ExitStatusExt as _;
+#[cfg(windows)]
+use std::os::windows::process::ExitStatusExt as _;
+use std::process::ExitStatus;
+use std::process::Output;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crankshaft_config::backend::Defaults;
+use crankshaft_config::backend::slurm::Config;
+use futures::FutureExt as _;
+use futures::future::BoxFuture;
+use nonempty::NonEmpty;
+use tracing::debug;
+use tracing::warn;
+
+use crate::Result;
+use crate::Task;
+use crate::events::TaskEvents;
+use crate::service::runner::backend::Accounting;
+use crate::service::runner::backend::FailureReason;
+use crate::service::runner::backend::TaskResult;
+use crate::service::runner::backend::generic::DEFAULT_MONITOR_FREQUENCY;
+use crate::service::runner::backend::generic::driver::Cancel;
+use crate::service::runner::backend::generic::driver::Driver;
+use crate::service::runner::backend::generic::driver::Lease;
+use crate::service::runner::backend::generic::driver::quote;
+use crate::service::runner::backend::generic::driver::with_env;
+use crate::service::runner::backend::generic::resolve_resources;
+use crate::service::runner::backend::provenance::ExecutionProvenance;
+use crate::service::runner::backend::provenance::Provenance;
+use crate::service::runner::backend::redirect;
+use crate::task::Execution;
+use crate::task::Resources;
+
+/// The job states (as reported by `squeue`) in which a job has not yet
+/// finished.
+const ACTIVE_STATES: &[&str] = &[
+    "PENDING",
+    "CONFIGURING",
+    "RUNNING",
+    "SUSPENDED",
+    "COMPLETING",
+    "REQUEUED",
+    "RESIZING",
+    "SIGNALING",
+    "STAGE_OUT",
+    "STOPPED",
+];
+
+/// The error reported by `squeue` for a job that the controller no longer
+/// knows about (i.e., that left the queue).
+const INVALID_JOB_ID: &str = "Invalid job id specified";
+
+/// The number of times `sacct` is queried for a finished job before giving
+/// up (as the accounting database may lag behind the controller).
+const SACCT_ATTEMPTS: usize = 5;
+
+/// The default number of seconds before the end of the time limit of a job
+/// that the checkpoint command of its task is signaled to run.
+const DEFAULT_CHECKPOINT_GRACE: u64 = 60;
+
+/// A SLURM execution backend.
+#[derive(Debug)]
+pub struct Backend {
+    /// The driver.
+    driver: Arc<Driver>,
+
+    /// The inner configuration.
+    config: Config,
+
+    /// The execution defaults.
+    defaults: Option<Defaults>,
+}
+
+impl Backend {
+    /// Attempts to initialize a new SLURM [`Backend`] with the provided
+    /// configuration for the backend.
+    pub async fn initialize(config: Config, defaults: Option<Defaults>) -> Result<Self> {
+        let driver = Driver::initialize(config.driver().clone())
+            .await
+            .map(Arc::new)?;
+
+        Ok(Self {
+            driver,
+            config,
+            defaults,
+        })
+    }
+}
+
+impl crate::Backend for Backend {
+    fn default_name(&self) -> &'static str {
+        "slurm"
+    }
+
+    fn run(
+        &self,
+        task: Task,
+        events: TaskEvents,
+    ) -> BoxFuture<'static, std::result::Result<TaskResult, FailureReason>> {
+        run(self, task, events)
+    }
+}
+
+/// Gets the name of the file that a stream of a job is written to (relative to
+/// the working directory of the job).
+fn stream_file(extension: &str) -> String {
+    format!("crankshaft-%j.{extension}")
+}
+
+/// Gets the path of the file that a stream of a job is written to (relative to
+/// the working directory of the job submission).
+// NOTE: `sbatch` resolves `--output` and `--error` against `--chdir`, so the
+// files are only named relative to the working directory of the job when the
+// job is submitted (see [`stream_file()`]).
+fn stream_path(execution: &Execution, extension: &str) -> String {
+    let file = stream_file(extension);
+
+    match execution.workdir() {
+        Some(workdir) => format!("{}/{file}", workdir.trim_end_matches('/')),
+        None => file,
+    }
+}
+
+/// Gets the shell command that runs an execution of a task within a job.
+///
+/// If the task has a checkpoint command, the execution is run in the
+/// background so that the batch shell can run the checkpoint command when it
+/// is signaled (writing the exit code of the checkpoint command to a marker
+/// file) and then continue waiting on the execution.
+fn wrap(task: &Task, execution: &Execution) -> String {
+    let join = |args: &NonEmpty<String>| {
+        args.iter()
+            .map(|arg| quote(arg))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    let shell = with_env(execution, join(execution.args()));
+
+    match task.checkpoint_command() {
+        Some(command) => {
+            // NOTE: the batch shell runs within the working directory of the
+            // job, so the marker file is written alongside the output files.
+            let action = format!(
+                "{}; echo $? > \"crankshaft-$SLURM_JOB_ID.ckpt\"",
+                join(command)
+            );
+
+            format!(
+                "trap {action} USR1 TERM; {shell} & pid=$!; while :; do wait $pid; status=$?; \
+                 kill -0 $pid 2>/dev/null || break; done; exit $status",
+                action = quote(&action)
+            )
+        }
+        None => shell,
+    }
+}
+
+/// Gets the `sbatch` command that submits an execution of a task.
+fn sbatch(
+    task: &Task,
+    execution: &Execution,
+    resources: Option<&Resources>,
+    config: &Config,
+) -> String {
+    let mut args = vec![String::from("sbatch"), String::from("--parsable")];
+
+    if let Some(name) = task.name() {
+        args.push(format!("--job-name={}", quote(name)));
+    }
+
+    let partition = match config.preemptible_partition() {
+        Some(partition) if task.is_preemptible() => Some(partition),
+        _ => config.partition(),
+    };
+
+    for (flag, value) in [
+        ("partition", partition),
+        ("account", config.account()),
+        ("qos", config.qos()),
+    ] {
+        if let Some(value) = value {
+            args.push(format!("--{flag}={}", quote(value)));
+        }
+    }
+
+    if let Some(resources) = resources {
+        if let Some(cpu) = resources.cpu() {
+            args.push(format!("--cpus-per-task={cpu}"));
+        }
+
+        // NOTE: SLURM enforces the memory of a job, so the hard amount is
+        // requested.
+        if let Some(ram) = resources.ram_hard() {
+            args.push(format!("--mem={}M", (ram * 1024.0).ceil() as u64));
+        }
+
+        if let Some(disk) = resources.disk() {
+            args.push(format!("--tmp={}M", (disk * 1024.0).ceil() as u64));
+        }
+    }
+
+    if let Some(workdir) = execution.workdir() {
+        args.push(format!("--chdir={}", quote(workdir)));
+    }
+
+    args.push(format!("--output={}", quote(&stream_file("out"))));
+    args.push(format!("--error={}", quote(&stream_file("err"))));
+
+    if task.checkpoint_command().is_some() {
+        args.push(format!(
+            "--signal=B:USR1@{}",
+            config
+                .checkpoint_grace()
+                .unwrap_or(DEFAULT_CHECKPOINT_GRACE)
+        ));
+    }
+
+    args.extend(config.extra_args().iter().cloned());
+    args.push(format!("--wrap={}", quote(&wrap(task, execution))));
+    args.join(" ")
+}
+
+/// Parses the job id from the output of `sbatch --parsable`.
+///
+/// The output is either `<job id>` or `<job id>;<cluster>`.
+fn parse_job_id(stdout: &str) -> Option<&str> {
+    let id = stdout.trim().split(';').next()?;
+    (!id.is_empty() && id.chars().all(|c| c.is_ascii_digit())).then_some(id)
+}
+
+/// The record of a finished job as reported by `sacct`.
+#[derive(Debug, PartialEq, Eq)]
+struct Record {
+    /// The state of the job (e.g., `COMPLETED` or `FAILED`).
+    state: String,
+
+    /// The exit code of the job.
+    code: i32,
+
+    /// The signal that terminated the job (or zero).
+    signal: i32,
+
+    /// The elapsed time of the job.
+    elapsed: String,
+
+    /// The nodes the job ran on.
+    nodes: String,
+}
+
+impl Record {
+    /// Gets whether or not the job is still active (i.e., the accounting
+    /// database has not yet caught up with the end of the job).
+    fn is_active(&self) -> bool {
+        ACTIVE_STATES.contains(&self.state.as_str())
+    }
+
+    /// Gets whether or not the job was preempted.
+    fn is_preempted(&self) -> bool {
+        self.state == "PREEMPTED"
+    }
+
+    /// Gets the exit status of the job.
+    fn status(&self) -> ExitStatus {
+        #[cfg(unix)]
+        let status = if self.signal != 0 {
+            ExitStatus::from_raw(self.signal & 0x7F)
+        } else {
+            ExitStatus::from_raw(self.code << 8)
+        };
+
+        #[cfg(windows)]
+        let status = ExitStatus::from_raw(if self.signal != 0 {
+            1
+        } else {
+            self.code as u32
+        });
+
+        status
+    }
+
+    /// Gets the record as accounting data.
+    fn accounting(&self) -> Accounting {
+        [
+            ("state", self.state.clone()),
+            ("exit_code", format!("{}:{}", self.code, self.signal)),
+            ("elapsed", self.elapsed.clone()),
+            ("nodes", self.nodes.clone()),
+        ]
+        .into_iter()
+        .map(|(key, value)| (String::from(key), value.into()))
+        .collect()
+    }
+}
+
+/// Parses the output of `sacct -n -P -X -o State,ExitCode,Elapsed,NodeList`.
+fn parse_sacct(stdout: &str) -> Option<Record> {
+    let line = stdout.lines().find(|line| !line.trim().is_empty())?;
+    let mut fields = line.trim().split('|');
+
+    // NOTE: canceled jobs are reported as `CANCELLED by <uid>`.
+    let state = fields.next()?.split_whitespace().next()?.to_owned();
+    let (code, signal) = fields.next()?.split_once(':')?;
+
+    Some(Record {
+        state,
+        code: code.parse().ok()?,
+        signal: signal.parse().ok()?,
+        elapsed: fields.next().unwrap_or_default().to_owned(),
+        nodes: fields.next().unwrap_or_default().to_owned(),
+    })
+}
+
+/// Queries the accounting record of a job with `sacct`.
+///
+/// As the accounting database may lag behind the controller, `sacct` is
+/// queried up to [`SACCT_ATTEMPTS`] times before giving up.
+async fn account(lease: &Lease<'_>, id: &str, frequency: Duration) -> Option<Record> {
+    for attempt in 0..SACCT_ATTEMPTS {
+        if attempt > 0 {
+            tokio::time::sleep(frequency).await;
+        }
+
+        match lease
+            .run(format!(
+                "sacct -n -P -X -j {id} -o State,ExitCode,Elapsed,NodeList"
+            ))
+            .await
+        {
+            Ok(output) => {
+                let record = parse_sacct(&String::from_utf8_lossy(&output.stdout));
+
+                if record.is_some() {
+                    return record;
+                }
+            }
+            Err(err) => warn!("failed to query the accounting of job `{id}`: {err}"),
+        }
+    }
+
+    None
+}
+
+/// Runs a [`Task`] in the backend.
+fn run(
+    backend: &Backend,
+    task: Task,
+    events: TaskEvents,
+) -> BoxFuture<'static, std::result::Result<TaskResult, FailureReason>> {
+    let driver = backend.driver.clone();
+    let config = backend.config.clone();
+    let resources = resolve_resources(backend.defaults.as_ref(), task.resources());
+    let frequency = Duration::from_secs(
+        config
+            .monitor_frequency()
+            .unwrap_or(DEFAULT_MONITOR_FREQUENCY),
+    );
+
+    async move {
+        let mut outputs = Vec::new();
+        let mut accounting = HashMap::new();
+        let mut provenance = Provenance::default().with_resources(resources.clone());
+
+        for (index, execution) in task.executions().enumerate() {
+            // NOTE: the output files of a job must be read in the same place
+            // that the job was submitted from (e.g., on the same host of a
+            // pool).
+            let lease = driver
+                .lease()
+                .await
+                .map_err(|err| FailureReason::Request(format!("leasing a host: {err}")))?;
+
+            // (1) Submitting the job.
+            let output = lease
+                .run(sbatch(&task, execution, resources.as_ref(), &config))
+                .await
+                .map_err(|err| FailureReason::Request(format!("running `sbatch`: {err}")))?;
+
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let id = match parse_job_id(&stdout) {
+                Some(id) if output.status.success() => id.to_owned(),
+                _ => {
+                    return Err(FailureReason::SubmissionParseError {
+                        stdout: stdout.into_owned(),
+                        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+                        pattern: String::from("<job id>[;<cluster>]"),
+                    });
+                }
+            };
+
+            debug!("submitted job `{id}` for execution {index}");
+
+            let mut cancel = Cancel::new(driver.clone(), format!("scancel {id}"));
+
+            events.execution_started(index);
+
+            // (2) Monitoring the job until it is no longer active and (3)
+            // querying the exit code of the job.
+            let mut suspended = false;
+
+            let record = loop {
+                tokio::time::sleep(frequency).await;
+
+                match lease.run(format!("squeue -h -j {id} -o %T")).await {
+                    Ok(output) if output.status.success() => {
+                        let state = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+
+                        match state.as_str() {
+                            "SUSPENDED" | "STOPPED" if !suspended => {
+                                suspended = true;
+                                events.suspended(state);
+                                continue;
+                            }
+                            "RUNNING" => {
+                                suspended = false;
+                                events.heartbeat();
+                                continue;
+                            }
+                            state if ACTIVE_STATES.contains(&state) => continue,
+                            _ => {}
+                        }
+                    }
+                    // NOTE: `squeue` fails for jobs that have left the queue.
+                    Ok(output)
+                        if String::from_utf8_lossy(&output.stderr).contains(INVALID_JOB_ID) => {}
+                    Ok(output) => {
+                        warn!(
+                            "failed to query the state of job `{id}`: {}",
+                            String::from_utf8_lossy(&output.stderr).trim()
+                        );
+                        continue;
+                    }
+                    Err(err) => {
+                        warn!("failed to query the state of job `{id}`: {err}");
+                        continue;
+                    }
+                }
+
+                // NOTE: the job is only considered finished once its accounting
+                // record is terminal, as the accounting database may still
+                // report a job that just left the queue as running.
+                match account(&lease, &id, frequency).await {
+                    Some(record) if record.is_active() => {
+                        debug!("job `{id}` is still `{}` within `sacct`", record.state);
+                    }
+                    Some(record) => {
+                        cancel.disarm();
+                        break Some(record);
+                    }
+                    None => break None,
+                }
+            };
+
+            // NOTE: a job without a record has an unknown exit code, so it is
+            // reported as a failure.
+            let status = match &record {
+                Some(record) => {
+                    events.accounted(index, record.accounting());
+                    accounting.insert(index, record.accounting());
+                    record.status()
+                }
+                None => {
+                    warn!("no accounting record was found for job `{id}`");
+                    Record {
+                        state: String::new(),
+                        code: 1,
+                        signal: 0,
+                        elapsed: String::new(),
+                        nodes: String::new(),
+                    }
+                    .status()
+                }
+            };
+
+            // (4) Reading (and removing) the checkpoint marker of the job.
+            if task.checkpoint_command().is_some() {
+                let path = quote(&stream_path(execution, "ckpt").replace("%j", &id));
+
+                // NOTE: the marker only exists if the checkpoint command ran,
+                // so a failure to read it is expected.
+                if let Ok(output) = lease.run(format!("cat {path} && rm -f {path}")).await {
+                    if let Ok(code) = String::from_utf8_lossy(&output.stdout)
+                        .trim()
+                        .parse::<i32>()
+                    {
+                        debug!("job `{id}` was checkpointed with exit code {code}");
+                        events.checkpointed(index, code == 0);
+                    }
+                }
+            }
+
+            events.execution_finished(index, status);
+
+            // (5) Reading (and removing) the output files of the job.
+            let mut streams = Vec::new();
+
+            for extension in ["out", "err"] {
+                let path = quote(&stream_path(execution, extension).replace("%j", &id));
+
+                match lease.run(format!("cat {path} && rm -f {path}")).await {
+                    Ok(output) => streams.push(output.stdout),
+                    Err(err) => {
+                        warn!("failed to read the output of job `{id}`: {err}");
+                        streams.push(Vec::new());
+                    }
+                }
+            }
+
+            // SAFETY: exactly two streams are pushed above.
+            let stderr = streams.pop().unwrap();
+            let stdout = streams.pop().unwrap();
+
+            // NOTE: preempted jobs are reported as a failure of the task (rather
+            // than as an unsuccessful execution) so that they may be retried.
+            if record.as_ref().is_some_and(Record::is_preempted) {
+                return Err(FailureReason::Preempted(format!(
+                    "job `{id}` was preempted"
+                )));
+            }
+
+            outputs.push(
+                redirect(
+                    execution,
+                    Output {
+                        status,
+                        stdout,
+                        stderr,
+                    },
+                )
+                .await,
+            );
+
+            provenance.push_execution(ExecutionProvenance::new(execution.image(), None, Some(id)));
+        }
+
+        let mut outputs = outputs.into_iter();
+
+        // SAFETY: each task _must_ have at least one execution, so at least one
+        // execution result _must_ exist at this stage. Thus, this will always unwrap.
+        let mut executions = NonEmpty::new(outputs.next().unwrap());
+        executions.extend(outputs);
+
+        Ok(TaskResult {
+            executions,
+            provenance,
+            accounting,
+        })
+    }
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::resources::Builder as ResourcesBuilder;
+
+    #[test]
+    fn sbatch_commands_are_built() {
+        let execution = Execution::builder()
+            .image("ubuntu")
+            .args(["echo", "hello world"])
+            .working_directory("/scratch")
+            .try_build()
+            .unwrap();
+
+        let task = Task::builder()
+            .name("foo")
+            .extend_executions([execution.clone()])
+            .try_build()
+            .unwrap();
+
+        let resources = ResourcesBuilder::default().cpu(2usize).ram(1.5).build();
+        let config = Config::builder()
+            .partition("compute")
+            .account("lab")
+            .qos("normal")
+            .extend_extra_args(["--constraint=avx2"])
+            .build();
+
+        assert_eq!(
+            sbatch(&task, &execution, Some(&resources), &config),
+            "sbatch --parsable --job-name=foo --partition=compute --account=lab --qos=normal \
+             --cpus-per-task=2 --mem=1536M --chdir=/scratch --output='crankshaft-%j.out' \
+             --error='crankshaft-%j.err' --constraint=avx2 --wrap=\"echo 'hello world'\""
+        );
+    }
+
+    #[test]
+    fn streams_are_read_from_the_working_directory() {
+        let execution = Execution::builder()
+            .image("ubuntu")
+            .args(["echo", "hello world"])
+            .working_directory("runs/")
+            .try_build()
+            .unwrap();
+
+        let task = Task::builder()
+            .extend_executions([execution.clone()])
+            .try_build()
+            .unwrap();
+
+        // NOTE: the streams are named relative to `--chdir` when submitted,
+        // but are read relative to the working directory of the submission.
+        assert!(
+            sbatch(&task, &execution, None, &Config::builder().build())
+                .contains("--chdir=runs/ --output='crankshaft-%j.out'")
+        );
+        assert_eq!(stream_path(&execution, "out"), "runs/crankshaft-%j.out");
+        assert_eq!(stream_path(&execution, "ckpt"), "runs/crankshaft-%j.ckpt");
+    }
+
+    #[test]
+    fn preemptible_tasks_are_checkpointed() {
+        let execution = Execution::builder()
+            .image("ubuntu")
+            .args(["train", "--epochs", "10"])
+            .try_build()
+            .unwrap();
+
+        let task = Task::builder()
+            .extend_executions([execution.clone()])
+            .resources(ResourcesBuilder::default().preemptible(true).build())
+            .checkpoint_command(["save", "state dir"])
+            .try_build()
+            .unwrap();
+
+        let config = Config::builder()
+            .partition("compute")
+            .preemptible_partition("scavenger")
+            .checkpoint_grace(120)
+            .build();
+
+        let args = shlex::split(&sbatch(&task, &execution, task.resources(), &config)).unwrap();
+        assert!(args.contains(&String::from("--partition=scavenger")));
+        assert!(args.contains(&String::from("--signal=B:USR1@120")));
+
+        let script = args.last().unwrap().strip_prefix("--wrap=").unwrap();
+        let words = shlex::split(script).unwrap();
+        assert_eq!(words[0], "trap");
+        assert_eq!(
+            words[1],
+            "save 'state dir'; echo $? > \"crankshaft-$SLURM_JOB_ID.ckpt\""
+        );
+        assert!(script.ends_with(
+            "USR1 TERM; train --epochs 10 & pid=$!; while :; do wait $pid; status=$?; kill -0 \
+             $pid 2>/dev/null || break; done; exit $status"
+        ));
+
+        // Tasks without a checkpoint command are neither signaled nor wrapped.
+        let task = Task::builder()
+            .extend_executions([execution.clone()])
+            .try_build()
+            .unwrap();
+
+        let args = shlex::split(&sbatch(&task, &execution, task.resources(), &config)).unwrap();
+        assert!(args.contains(&String::from("--partition=compute")));
+        assert!(!args.iter().any(|arg| arg.starts_with("--signal")));
+        assert_eq!(args.last().unwrap(), "--wrap=train --epochs 10");
+    }
+
+    #[test]
+    fn job_ids_are_parsed() {
+        assert_eq!(parse_job_id("1234\n"), Some("1234"));
+        assert_eq!(parse_job_id("1234;cluster\n"), Some("1234"));
+        assert_eq!(parse_job_id("sbatch: error: invalid partition"), None);
+        assert_eq!(parse_job_id(""), None);
+    }
+
+    #[test]
+    fn sacct_records_are_parsed() {
+        let record = parse_sacct("FAILED|2:0|00:01:05|node01\n").unwrap();
+        assert_eq!(record.state, "FAILED");
+        assert_eq!(record.code, 2);
+        assert_eq!(record.status().code(), Some(2));
+        assert_eq!(record.accounting()["nodes"], "node01");
+
+        let record = parse_sacct("CANCELLED by 1000|0:15|00:00:10|node02\n").unwrap();
+        assert_eq!(record.state, "CANCELLED");
+        assert!(!record.status().success());
+        assert!(!record.is_preempted());
+
+        let record = parse_sacct("PREEMPTED|0:15|00:10:00|node03\n").unwrap();
+        assert!(record.is_preempted());
+        assert!(!record.is_active());
+
+        // Jobs that the accounting database still reports as running are not
+        // finished (even though their exit code is zero).
+        let record = parse_sacct("RUNNING|0:0|00:00:30|node04\n").unwrap();
+        assert!(record.is_active());
+
+        assert_eq!(
+            parse_sacct("COMPLETED|0:0||\n").unwrap().status().code(),
+            Some(0)
+        );
+        assert_eq!(parse_sacct(""), None);
+    }
+}
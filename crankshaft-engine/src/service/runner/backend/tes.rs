@@ -4,47 +4,191 @@
 //!
 //! [tes]: https://www.ga4gh.org/product/task-execution-service-tes/
 
-#[cfg(unix)]
-use std::os::unix::process::ExitStatusExt;
-#[cfg(windows)]
-use std::os::windows::process::ExitStatusExt;
-use std::process::ExitStatus;
+use std::collections::HashMap;
 use std::process::Output;
 use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
+use crankshaft_config::backend::Defaults;
 use crankshaft_config::backend::tes::Config;
+use crankshaft_config::backend::tes::OutputMapping;
+use crankshaft_config::backend::tes::View as CompletionView;
 use futures::FutureExt as _;
 use futures::future::BoxFuture;
 use nonempty::NonEmpty;
+use reqwest::StatusCode;
+use reqwest::header::AUTHORIZATION;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::header::HeaderMap;
+use reqwest::header::HeaderValue;
+use reqwest_retry::RetryTransientMiddleware;
+use reqwest_retry::policies::ExponentialBackoff;
 use tes::v1::Client;
 use tes::v1::client::tasks::View;
+use tes::v1::types::responses::CreateTask;
+use tes::v1::types::responses::ListTasks;
+use tes::v1::types::task::State;
 use tracing::debug;
 use tracing::error;
+use tracing::warn;
+use url::Url;
+use uuid::Uuid;
 
 use crate::Task;
+use crate::events::TaskEvents;
+use crate::service::runner::backend::FailureReason;
+use crate::service::runner::backend::Health;
 use crate::service::runner::backend::TaskResult;
+use crate::service::runner::backend::generic::driver::exit_status;
+use crate::service::runner::backend::provenance::ExecutionProvenance;
+use crate::service::runner::backend::provenance::Provenance;
+use crate::service::runner::backend::redirect;
+use crate::service::runner::backend::tes::breaker::Breaker;
+use crate::service::runner::backend::tes::breaker::DEFAULT_FAILURE_THRESHOLD;
+use crate::service::runner::backend::tes::breaker::DEFAULT_PROBE_INTERVAL;
+use crate::task::Resources;
+use crate::task::input::Contents;
+use crate::task::input::Fetchers;
+use crate::task::input::fetcher;
+use crate::task::input::fetcher::Http;
+use crate::task::output::Type;
+
+pub mod breaker;
+
+/// The tag containing the unique id of the submission that created a task.
+///
+/// When the creation of a task fails (e.g., because the request timed out),
+/// the task may have been created regardless. Before retrying the creation,
+/// the service is searched for a task with this tag so that the task is never
+/// created (and run) twice.
+pub const SUBMISSION_TAG: &str = "crankshaft.submission";
+
+/// The time to wait between polls of the state of a task.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// The number of times failed requests are retried when no number is
+/// configured (the default of the TES client).
+const DEFAULT_RETRIES: u32 = 3;
+
+/// An error creating a task within the service.
+#[derive(Debug)]
+enum CreateError {
+    /// The service could not be reached or failed to handle the request (i.e.,
+    /// a transport error or a server error), so the creation may be retried.
+    Transient(String),
+
+    /// The service rejected the task (i.e., a client error other than a
+    /// timeout or a rate limit), so retrying the creation would never succeed.
+    Rejected(String),
+}
+
+impl std::fmt::Display for CreateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CreateError::Transient(reason) => write!(f, "{reason}"),
+            CreateError::Rejected(reason) => write!(f, "the service rejected the task: {reason}"),
+        }
+    }
+}
 
 /// A backend driven by the Task Execution Service (TES) schema.
 #[derive(Debug)]
 pub struct Backend {
     /// A handle to the inner TES client.
     client: Arc<Client>,
+
+    /// The HTTP client used to create tasks and search for their submissions
+    /// (see [`create_task()`] and [`find_submission()`]).
+    http: reqwest_middleware::ClientWithMiddleware,
+
+    /// The URL of the service.
+    url: Url,
+
+    /// The circuit breaker guarding requests to the service.
+    breaker: Arc<Breaker>,
+
+    /// The mappings from local output URLs to the remote locations where the
+    /// service stores them.
+    output_mappings: Arc<Vec<OutputMapping>>,
+
+    /// The fetchers used to download outputs from their remote locations.
+    fetchers: Fetchers,
+
+    /// The view requested once a task has completed.
+    completion_view: CompletionView,
+
+    /// The execution defaults.
+    defaults: Option<Defaults>,
 }
 
 impl Backend {
-    /// AttemptsCreates a new [`Backend`].
-    pub fn initialize(config: Config) -> Self {
+    /// Creates a new [`Backend`].
+    pub fn initialize(config: Config, defaults: Option<Defaults>, health: Health) -> Self {
         let mut builder = Client::builder().url(config.url().to_owned());
+        let mut headers = HeaderMap::new();
 
         if let Some(token) = config.http().basic_auth_token() {
-            builder = builder.insert_header("Authorization", format!("Basic {}", token));
+            let value = format!("Basic {}", token);
+            builder = builder.insert_header(AUTHORIZATION, &value);
+
+            // SAFETY: the TES client panics above if the value is not a valid
+            // header value, so this will always unwrap.
+            headers.insert(AUTHORIZATION, HeaderValue::from_str(&value).unwrap());
         }
 
+        let retries = config.http().retries().unwrap_or(DEFAULT_RETRIES);
+        builder = builder.retries(retries);
+
+        // SAFETY: this is manually constructed to always build.
+        let client = Arc::new(builder.try_build().expect("client did not build"));
+
+        // NOTE: the HTTP client retries transient failures in the same way as
+        // the TES client.
+        let http = reqwest_middleware::ClientBuilder::new(
+            reqwest::Client::builder()
+                .default_headers(headers)
+                .build()
+                // SAFETY: this is manually constructed to always build.
+                .expect("HTTP client did not build"),
+        )
+        .with(RetryTransientMiddleware::new_with_policy(
+            ExponentialBackoff::builder().build_with_max_retries(retries),
+        ))
+        .build();
+
+        let probe = {
+            let client = client.clone();
+            Box::new(move || {
+                let client = client.clone();
+                async move { client.service_info().await.is_ok() }.boxed()
+            })
+        };
+
+        let breaker = Breaker::new(
+            config
+                .http()
+                .failure_threshold()
+                .unwrap_or(DEFAULT_FAILURE_THRESHOLD),
+            Duration::from_secs(
+                config
+                    .http()
+                    .probe_interval()
+                    .unwrap_or(DEFAULT_PROBE_INTERVAL),
+            ),
+            health,
+            probe,
+        );
+
         Self {
-            // SAFETY: this is manually constructed to always build.
-            client: Arc::new(builder.try_build().expect("client did not build")),
+            client,
+            http,
+            url: config.url().to_owned(),
+            breaker: Arc::new(breaker),
+            output_mappings: Arc::new(config.output_mappings().to_vec()),
+            fetchers: Fetchers::default().with(Http::default()),
+            completion_view: config.completion_view(),
+            defaults,
         }
     }
 }
@@ -55,14 +199,119 @@ impl crate::Backend for Backend {
         "tes"
     }
 
+    fn terminates_gracefully(&self) -> bool {
+        true
+    }
+
     /// Runs a task in a backend.
-    fn run(&self, task: Task) -> BoxFuture<'static, TaskResult> {
-        run(self, task)
+    // NOTE: the TES backend only observes the state of the task as a whole
+    // while it runs (and retrieves the logs of each execution once it has
+    // finished), so per-execution events are not reported.
+    fn run(
+        &self,
+        task: Task,
+        events: TaskEvents,
+    ) -> BoxFuture<'static, std::result::Result<TaskResult, FailureReason>> {
+        run(self, task, events)
+    }
+}
+
+/// Gets the remote location of an output with a local `url` (or [`None`] if
+/// none of the `mappings` apply).
+fn remote_url(url: &str, mappings: &[OutputMapping]) -> Option<Url> {
+    let url = url.parse().ok()?;
+    mappings.iter().find_map(|mapping| mapping.map(&url))
+}
+
+/// Gets the transfers (from the remote location to the local URL) needed to
+/// retrieve the outputs of a [`Task`] that are stored remotely by the service.
+fn transfers(task: &Task, mappings: &[OutputMapping]) -> Vec<(Url, Url)> {
+    task.outputs()
+        .into_iter()
+        .flatten()
+        .filter_map(|output| {
+            let remote = remote_url(output.url(), mappings)?;
+
+            if let Type::Directory = output.r#type() {
+                warn!(
+                    "directory output `{}` is stored at `{remote}` and will not be downloaded",
+                    output.url()
+                );
+                return None;
+            }
+
+            // SAFETY: the URL was parsed when mapping it above.
+            Some((remote, output.url().parse().unwrap()))
+        })
+        .collect()
+}
+
+/// Downloads the contents at `remote` to the local file at `local`.
+async fn download(fetchers: &Fetchers, remote: &Url, local: &Url) -> fetcher::Result<u64> {
+    let path = local
+        .to_file_path()
+        .map_err(|_| fetcher::Error::InvalidPath(local.clone()))?;
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(fetcher::Error::Io)?;
+    }
+
+    let mut file = tokio::fs::File::create(&path)
+        .await
+        .map_err(fetcher::Error::Io)?;
+
+    fetchers
+        .fetch(&Contents::URL(remote.clone()), &mut file)
+        .await
+}
+
+/// Resolves the resources requested by a task from the execution defaults of
+/// the backend.
+///
+/// Unlike other backends, no resources are assumed: only those set within the
+/// defaults of the backend or the task itself (which takes priority) are
+/// requested, so that the service can apply its own defaults to the rest.
+fn resolve_resources(defaults: Option<&Defaults>, task: Option<&Resources>) -> Option<Resources> {
+    match (defaults.map(Resources::from), task) {
+        (Some(defaults), Some(task)) => Some(defaults.apply(task)),
+        (Some(defaults), None) => Some(defaults),
+        (None, task) => task.cloned(),
+    }
+}
+
+/// Translates [`Resources`] to [TES
+/// resources](tes::v1::types::task::Resources).
+fn to_tes_resources(resources: &Resources) -> tes::v1::types::task::Resources {
+    tes::v1::types::task::Resources {
+        cpu_cores: resources.cpu().map(|cpu| cpu as i64),
+        preemptible: resources.preemptible(),
+        // NOTE: the service may enforce the RAM of a task, so the hard amount
+        // is requested.
+        ram_gb: resources.ram_hard().map(Into::into),
+        disk_gb: resources.disk().map(Into::into),
+        zones: resources
+            .zones()
+            .map(|zones| zones.iter().cloned().collect()),
     }
 }
 
 /// Translates a [`Task`] to a [TES Task](tes::v1::types::Task) for submission.
-fn to_tes_task(task: Task) -> tes::v1::types::Task {
+///
+/// The task requests the `resources` it was resolved to (if any).
+///
+/// Outputs with a local URL covered by one of the `mappings` are submitted
+/// with their remote location instead.
+///
+/// The task is tagged with the unique id of its `submission` (see
+/// [`SUBMISSION_TAG`]).
+fn to_tes_task(
+    task: &Task,
+    resources: Option<&Resources>,
+    mappings: &[OutputMapping],
+    submission: &str,
+) -> tes::v1::types::Task {
     // NOTE: a name is not required by the TES specification, so it is kept as
     // empty if no name is provided.
     let name = task.name().map(|v| v.to_owned());
@@ -73,89 +322,673 @@ fn to_tes_task(task: Task) -> tes::v1::types::Task {
         .map(|execution| tes::v1::types::task::Executor {
             image: execution.image().to_owned(),
             command: execution.args().into_iter().cloned().collect::<Vec<_>>(),
+            env: execution.env().map(|env| {
+                env.iter()
+                    .map(|(name, value)| (name.clone(), value.clone()))
+                    .collect()
+            }),
             ..Default::default()
         })
         .collect::<Vec<_>>();
 
+    let outputs = task.outputs().map(|outputs| {
+        outputs
+            .map(|output| tes::v1::types::task::Output {
+                name: output.name().map(ToOwned::to_owned),
+                description: output.description().map(ToOwned::to_owned),
+                url: remote_url(output.url(), mappings)
+                    .map(String::from)
+                    .unwrap_or_else(|| output.url().to_owned()),
+                path: output.path().to_owned(),
+                r#type: match output.r#type() {
+                    Type::File => tes::v1::types::task::file::Type::File,
+                    Type::Directory => tes::v1::types::task::file::Type::Directory,
+                },
+            })
+            .collect::<Vec<_>>()
+    });
+
     tes::v1::types::Task {
         name,
         description,
         executors,
+        outputs,
+        resources: resources.map(to_tes_resources),
+        tags: Some(HashMap::from([(
+            String::from(SUBMISSION_TAG),
+            submission.to_owned(),
+        )])),
         ..Default::default()
     }
 }
 
+/// Gets the name of a [`State`] within the TES specification.
+fn state_name(state: &State) -> &'static str {
+    match state {
+        State::Unknown => "UNKNOWN",
+        State::Queued => "QUEUED",
+        State::Initializing => "INITIALIZING",
+        State::Running => "RUNNING",
+        State::Paused => "PAUSED",
+        State::Complete => "COMPLETE",
+        State::ExecutorError => "EXECUTOR_ERROR",
+        State::SystemError => "SYSTEM_ERROR",
+        State::Canceled => "CANCELED",
+    }
+}
+
+/// Gets the outputs of the executions of a task that finished in `state` from
+/// the executor logs of its last attempt within the `completed` view of the
+/// task (if the view was retrieved).
+///
+/// The standard output and standard error of the executors are only reported
+/// within the full view of a task, so they are empty for the basic view.
+///
+/// Tasks that did not complete fail unless an executor reported the nonzero
+/// exit code it failed with, as the service would otherwise be reported as
+/// having run the task successfully.
+fn executions(
+    task_id: &str,
+    state: &State,
+    completed: Option<&tes::v1::types::Task>,
+) -> std::result::Result<Vec<Output>, FailureReason> {
+    let complete = matches!(state, State::Complete);
+
+    // NOTE: the specification requires an exit code for every executor that
+    // ran, so a missing exit code is only assumed to be successful if the
+    // task completed.
+    let outputs = completed
+        .and_then(|task| task.logs.as_ref())
+        .and_then(|attempts| attempts.last())
+        .map(|attempt| {
+            attempt
+                .logs
+                .iter()
+                .map(|log| Output {
+                    status: exit_status(match log.exit_code {
+                        Some(code) => code as i32,
+                        None if complete => 0,
+                        None => 1,
+                    }),
+                    stdout: log.stdout.clone().unwrap_or_default().into_bytes(),
+                    stderr: log.stderr.clone().unwrap_or_default().into_bytes(),
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    match state {
+        State::Complete if outputs.is_empty() => Ok(vec![Output {
+            status: exit_status(0),
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+        }]),
+        State::Complete => Ok(outputs),
+        State::ExecutorError if outputs.iter().any(|output| !output.status.success()) => {
+            Ok(outputs)
+        }
+        state => Err(FailureReason::Request(format!(
+            "task {task_id} finished in the `{}` state",
+            state_name(state)
+        ))),
+    }
+}
+
+/// Gets whether or not a response with `status` means that the service
+/// rejected the request (i.e., that retrying it would never succeed).
+fn is_rejection(status: StatusCode) -> bool {
+    status.is_client_error()
+        && status != StatusCode::REQUEST_TIMEOUT
+        && status != StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Creates a task within the service, returning its id.
+///
+/// The TES client does not expose the status of failed responses, so tasks are
+/// created with a plain HTTP request in order to tell the tasks the service
+/// rejects apart from failures of the service itself.
+async fn create_task(
+    http: &reqwest_middleware::ClientWithMiddleware,
+    url: &Url,
+    task: &tes::v1::types::Task,
+) -> std::result::Result<String, CreateError> {
+    // SAFETY: TES tasks always serialize and `tasks` is always joinable to the
+    // URL of the service (as within the TES client), so these always unwrap.
+    let body = serde_json::to_vec(task).unwrap();
+    let url = url.join("./tasks").unwrap();
+
+    let response = http
+        .post(url)
+        .header(CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .await
+        .map_err(|err| CreateError::Transient(err.to_string()))?;
+
+    let status = response.status();
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|err| CreateError::Transient(err.to_string()))?;
+
+    if is_rejection(status) {
+        return Err(CreateError::Rejected(format!(
+            "{status}: {}",
+            String::from_utf8_lossy(&bytes).trim()
+        )));
+    }
+
+    if !status.is_success() {
+        return Err(CreateError::Transient(format!(
+            "the service responded with {status}"
+        )));
+    }
+
+    // NOTE: the task may have been created even though the response cannot be
+    // read, so this is transient (and the task is searched for when retrying).
+    serde_json::from_slice::<CreateTask>(&bytes)
+        .map(|response| response.id)
+        .map_err(|err| CreateError::Transient(format!("invalid response: {err}")))
+}
+
+/// Gets the URL listing the tasks tagged with a submission (see
+/// [`SUBMISSION_TAG`]), starting from the page with `token` (if any).
+fn submission_url(url: &Url, submission: &str, token: Option<&str>) -> Url {
+    // SAFETY: `tasks` is always joinable to the URL of the service (as within
+    // the TES client), so this always unwraps.
+    let mut url = url.join("./tasks").unwrap();
+
+    {
+        let mut query = url.query_pairs_mut();
+        query
+            .append_pair("view", "BASIC")
+            .append_pair("tag_key", SUBMISSION_TAG)
+            .append_pair("tag_value", submission);
+
+        if let Some(token) = token {
+            query.append_pair("page_token", token);
+        }
+    }
+
+    url
+}
+
+/// Finds the id of the task created by a submission (if the service created
+/// one).
+///
+/// The TES client does not support filtering tasks by their tags, so tasks are
+/// listed with a plain HTTP request that only asks for the tasks tagged with
+/// the submission. The tags of the listed tasks are still checked (as a
+/// service may not support filtering), and the search stops at the first
+/// match.
+async fn find_submission(
+    http: &reqwest_middleware::ClientWithMiddleware,
+    url: &Url,
+    submission: &str,
+) -> std::result::Result<Option<String>, String> {
+    let mut next_token = None;
+
+    loop {
+        let response = http
+            .get(submission_url(url, submission, next_token.as_deref()))
+            .send()
+            .await
+            .map_err(|err| err.to_string())?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("the service responded with {status}"));
+        }
+
+        let page = response
+            .json::<ListTasks<tes::v1::types::Task>>()
+            .await
+            .map_err(|err| format!("invalid response: {err}"))?;
+
+        let found = page.tasks.into_iter().find_map(|task| {
+            let tagged = task
+                .tags
+                .as_ref()
+                .and_then(|tags| tags.get(SUBMISSION_TAG))
+                .is_some_and(|tag| tag == submission);
+
+            if tagged { task.id } else { None }
+        });
+
+        if found.is_some() {
+            return Ok(found);
+        }
+
+        next_token = page.next_page_token;
+        if next_token.is_none() {
+            return Ok(None);
+        }
+    }
+}
+
 /// Runs a [`Task`] in the backend.
-fn run(backend: &Backend, task: Task) -> BoxFuture<'static, TaskResult> {
+fn run(
+    backend: &Backend,
+    task: Task,
+    events: TaskEvents,
+) -> BoxFuture<'static, std::result::Result<TaskResult, FailureReason>> {
     let client = backend.client.clone();
-    let task = to_tes_task(task);
+    let http = backend.http.clone();
+    let url = backend.url.clone();
+    let breaker = backend.breaker.clone();
+    let mappings = backend.output_mappings.clone();
+    let fetchers = backend.fetchers.clone();
+    let completion_view = backend.completion_view;
+    let resources = resolve_resources(backend.defaults.as_ref(), task.resources());
 
     async move {
-        let task_id = client.create_task(task).await.unwrap().id;
+        // NOTE: failed requests have already been retried (with exponential
+        // backoff) by the client at this point, so each failure here counts
+        // towards opening the circuit breaker. Tasks that the service rejects
+        // fail immediately (without counting towards the breaker), as they say
+        // nothing about the health of the service.
+        let submission = Uuid::new_v4().to_string();
+        let mut attempted = false;
+
+        let task_id = loop {
+            breaker.ready().await;
+
+            // NOTE: a previous attempt may have created the task even though
+            // it failed from our perspective (e.g., the response was lost), in
+            // which case that task is adopted rather than created again.
+            if attempted {
+                match find_submission(&http, &url, &submission).await {
+                    Ok(Some(id)) => {
+                        warn!("adopting task {id} created by a previously failed submission");
+                        breaker.success();
+                        break id;
+                    }
+                    Ok(None) => {}
+                    Err(err) => {
+                        error!("error searching for previously submitted task: {err}");
+                        breaker.failure(err);
+                        tokio::time::sleep(POLL_INTERVAL).await;
+                        continue;
+                    }
+                }
+            }
+
+            attempted = true;
+
+            match create_task(
+                &http,
+                &url,
+                &to_tes_task(&task, resources.as_ref(), &mappings, &submission),
+            )
+            .await
+            {
+                Ok(id) => {
+                    breaker.success();
+                    break id;
+                }
+                Err(err @ CreateError::Rejected(_)) => {
+                    return Err(FailureReason::Request(err.to_string()));
+                }
+                Err(err) => {
+                    error!("error creating task: {err}");
+                    breaker.failure(err);
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+            }
+        };
 
         loop {
+            breaker.ready().await;
+
             debug!("looping on {task_id}");
-            match client.get_task(&task_id, View::Full).await {
-                Ok(task) => {
-                    debug!("Got response for {task_id}: {task:?}");
-                    // SAFETY: `get_task` called with `View::Full` will always
-                    // return a full [`Task`], so this will always unwrap.
-                    let task = task.into_task().unwrap();
-
-                    if let Some(ref state) = task.state {
+            // NOTE: only the state is needed while polling, so the minimal view
+            // is requested to keep the responses small.
+            match client.get_task(&task_id, View::Minimal).await {
+                Ok(response) => {
+                    breaker.success();
+
+                    debug!("Got response for {task_id}: {response:?}");
+                    // SAFETY: `get_task` called with `View::Minimal` will always
+                    // return a minimal task, so this will always unwrap.
+                    let response = response.into_minimal().unwrap();
+
+                    if let Some(ref state) = response.state {
                         debug!("State was found for {task_id}");
                         if !state.is_executing() {
                             debug!("Task is completed for {task_id}");
-                            // let mut results = task
-                            //     .logs
-                            //     .unwrap()
-                            //     .into_iter()
-                            //     .flat_map(|task| task.logs)
-                            //     .map(|log| {
-                            //         let status = log.exit_code.expect("exit code to be present");
-
-                            //         #[cfg(unix)]
-                            //         let output = Output {
-                            //             status: ExitStatus::from_raw(status as i32),
-                            //             stdout:
-                            // log.stdout.unwrap_or_default().as_bytes().to_vec(),
-                            //             stderr:
-                            // log.stderr.unwrap_or_default().as_bytes().to_vec(),
-                            //         };
-
-                            //         #[cfg(windows)]
-                            //         let output = Output {
-                            //             status: ExitStatus::from_raw(status),
-                            //             stdout:
-                            // log.stdout.unwrap_or_default().as_bytes().to_vec(),
-                            //             stderr:
-                            // log.stderr.unwrap_or_default().as_bytes().to_vec(),
-                            //         };
-
-                            //         output
-                            //     });
-
-                            // let mut executions = NonEmpty::new(results.next().unwrap());
-                            // executions.extend(results);
-                            let executions = NonEmpty::new(Output {
-                                status: ExitStatus::from_raw(0),
-                                stdout: Vec::new(),
-                                stderr: Vec::new(),
-                            });
 
-                            return TaskResult { executions };
+                            // Request the completed task once with the
+                            // configured view.
+                            let view = match completion_view {
+                                CompletionView::Basic => View::Basic,
+                                CompletionView::Full => View::Full,
+                            };
+
+                            let completed = match client.get_task(&task_id, view).await {
+                                Ok(response) => {
+                                    debug!("Got completed task for {task_id}: {response:?}");
+                                    response.into_task()
+                                }
+                                Err(err) => {
+                                    warn!("error getting completed task {task_id}: {err}");
+                                    None
+                                }
+                            };
+
+                            let outputs = executions(&task_id, state, completed.as_ref())?;
+
+                            // Download any outputs stored remotely by the service.
+                            // NOTE: every output is attempted (even if others
+                            // fail) so that the failures are reported together.
+                            if let State::Complete = state {
+                                let mut failures = Vec::new();
+
+                                for (remote, local) in transfers(&task, &mappings) {
+                                    match download(&fetchers, &remote, &local).await {
+                                        Ok(bytes) => {
+                                            debug!(
+                                                "downloaded {bytes} bytes from `{remote}` to \
+                                                 `{local}`"
+                                            )
+                                        }
+                                        Err(err) => {
+                                            failures
+                                                .push(format!("`{remote}` to `{local}`: {err}"));
+                                        }
+                                    }
+                                }
+
+                                if !failures.is_empty() {
+                                    return Err(FailureReason::Request(format!(
+                                        "downloading the outputs of task {task_id}: {}",
+                                        failures.join("; ")
+                                    )));
+                                }
+                            }
+
+                            let mut results = Vec::new();
+
+                            for (execution, output) in task.executions().zip(outputs) {
+                                results.push(redirect(execution, output).await);
+                            }
+
+                            let mut results = results.into_iter();
+
+                            // SAFETY: at least one output is always returned
+                            // for a finished task and each task _must_ have at
+                            // least one execution, so this will always unwrap.
+                            let mut executions = NonEmpty::new(results.next().unwrap());
+                            executions.extend(results);
+
+                            let mut provenance = Provenance::default()
+                                .with_native_id(task_id.clone())
+                                .with_resources(resources.clone());
+
+                            for execution in task.executions() {
+                                provenance.push_execution(ExecutionProvenance::new(
+                                    execution.image(),
+                                    None,
+                                    None,
+                                ));
+                            }
+
+                            return Ok(TaskResult {
+                                executions,
+                                provenance,
+                                accounting: Default::default(),
+                            });
                         } else {
                             debug!("Task was NOT completed for {task_id}. Looping...");
+
+                            if let State::Running = state {
+                                events.heartbeat();
+                            }
                         }
                     } else {
                         debug!("State was NOT set for {task_id}. Looping...");
                     }
+                }
+                Err(err) => {
+                    error!("error: {err}");
+                    breaker.failure(err);
+                }
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                // NOTE: the service is responsible for shutting down the task
+                // gracefully once it is canceled.
+                _ = events.terminated() => {
+                    if let Err(err) = client.cancel_task(&task_id).await {
+                        error!("error canceling task {task_id}: {err}");
+                    }
 
-                    tokio::time::sleep(Duration::from_millis(200)).await;
+                    return Err(FailureReason::Request(String::from("the task was terminated")));
                 }
-                Err(err) => error!("error: {err}"),
             }
         }
     }
     .boxed()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Execution;
+    use crate::task::output::Builder as OutputBuilder;
+    use crate::task::resources::Builder as ResourcesBuilder;
+
+    #[test]
+    fn mapped_outputs_are_submitted_remotely() {
+        let mappings = [OutputMapping::new(
+            "file:///data/results/".parse().unwrap(),
+            "s3://bucket/results/".parse().unwrap(),
+        )];
+
+        let output = |url: &str, r#type| {
+            OutputBuilder::default()
+                .url(url.parse::<Url>().unwrap())
+                .path("/out")
+                .r#type(r#type)
+                .try_build()
+                .unwrap()
+        };
+
+        let task = Task::builder()
+            .extend_executions([Execution::builder()
+                .image("ubuntu")
+                .args(["echo", "hello"])
+                .try_build()
+                .unwrap()])
+            .extend_outputs([
+                output("file:///data/results/a.txt", Type::File),
+                output("file:///data/results/dir", Type::Directory),
+                output("file:///tmp/b.txt", Type::File),
+            ])
+            .try_build()
+            .unwrap();
+
+        let urls = to_tes_task(&task, None, &mappings, "submission")
+            .outputs
+            .unwrap()
+            .into_iter()
+            .map(|output| output.url)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            urls,
+            [
+                "s3://bucket/results/a.txt",
+                "s3://bucket/results/dir",
+                "file:///tmp/b.txt"
+            ]
+        );
+
+        // NOTE: only mapped files are downloaded.
+        assert_eq!(
+            transfers(&task, &mappings),
+            [(
+                "s3://bucket/results/a.txt".parse().unwrap(),
+                "file:///data/results/a.txt".parse().unwrap()
+            )]
+        );
+    }
+
+    #[test]
+    fn executions_are_built_from_executor_logs() {
+        let completed = |codes: &[Option<u32>]| {
+            serde_json::from_value::<tes::v1::types::Task>(serde_json::json!({
+                "executors": [],
+                "logs": [
+                    { "logs": [{ "exit_code": 137, "stdout": "first attempt" }] },
+                    {
+                        "logs": codes
+                            .iter()
+                            .map(|code| serde_json::json!({ "exit_code": code, "stdout": "hi" }))
+                            .collect::<Vec<_>>(),
+                    },
+                ],
+            }))
+            .unwrap()
+        };
+
+        // Only the logs of the last attempt are considered.
+        let outputs = executions("a", &State::Complete, Some(&completed(&[Some(0)]))).unwrap();
+        assert_eq!(outputs.len(), 1);
+        assert!(outputs[0].status.success());
+        assert_eq!(outputs[0].stdout, b"hi");
+
+        let outputs = executions(
+            "a",
+            &State::ExecutorError,
+            Some(&completed(&[Some(0), Some(2)])),
+        )
+        .unwrap();
+        assert_eq!(outputs[1].status.code(), Some(2));
+
+        // Tasks that did not complete are never successful.
+        for state in [State::ExecutorError, State::SystemError, State::Canceled] {
+            assert!(matches!(
+                executions("a", &state, Some(&completed(&[Some(0)]))),
+                Err(FailureReason::Request(_))
+            ));
+            assert!(executions("a", &state, None).is_err());
+        }
+
+        // A completed task without logs is still successful.
+        let outputs = executions("a", &State::Complete, None).unwrap();
+        assert!(outputs[0].status.success());
+    }
+
+    #[test]
+    fn only_client_errors_are_rejections() {
+        assert!(is_rejection(StatusCode::BAD_REQUEST));
+        assert!(is_rejection(StatusCode::UNPROCESSABLE_ENTITY));
+
+        // Timeouts and rate limits are transient.
+        assert!(!is_rejection(StatusCode::REQUEST_TIMEOUT));
+        assert!(!is_rejection(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_rejection(StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(!is_rejection(StatusCode::SERVICE_UNAVAILABLE));
+    }
+
+    #[test]
+    fn tasks_are_tagged_with_their_submission() {
+        let task = Task::builder()
+            .extend_executions([Execution::builder()
+                .image("ubuntu")
+                .args(["echo", "hello"])
+                .try_build()
+                .unwrap()])
+            .try_build()
+            .unwrap();
+
+        let tags = to_tes_task(&task, None, &[], "abc").tags.unwrap();
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[SUBMISSION_TAG], "abc");
+    }
+
+    #[test]
+    fn submissions_are_searched_by_their_tag() {
+        let url = "http://localhost:8000/v1/".parse::<Url>().unwrap();
+
+        let listed = submission_url(&url, "abc", None);
+        assert_eq!(listed.path(), "/v1/tasks");
+        assert_eq!(
+            listed.query(),
+            Some("view=BASIC&tag_key=crankshaft.submission&tag_value=abc")
+        );
+
+        let listed = submission_url(&url, "abc", Some("next"));
+        assert!(listed.query().unwrap().ends_with("&page_token=next"));
+    }
+
+    #[test]
+    fn zones_are_requested() {
+        let defaults = serde_json::from_str::<Defaults>(r#"{"zones": ["us-east-1a"]}"#).unwrap();
+        let task = Task::builder()
+            .extend_executions([Execution::builder()
+                .image("ubuntu")
+                .args(["echo", "hello"])
+                .try_build()
+                .unwrap()])
+            .try_build()
+            .unwrap();
+
+        // The zones of the backend apply to tasks without zones of their own.
+        let resources = resolve_resources(Some(&defaults), task.resources());
+        let requested = to_tes_task(&task, resources.as_ref(), &[], "abc")
+            .resources
+            .unwrap();
+        assert_eq!(requested.zones.unwrap(), ["us-east-1a"]);
+
+        let resources = ResourcesBuilder::default()
+            .zones(["eu-west-1a", "eu-west-1b"].into_iter())
+            .build();
+        let resources = resolve_resources(Some(&defaults), Some(&resources));
+        let requested = to_tes_task(&task, resources.as_ref(), &[], "abc")
+            .resources
+            .unwrap();
+        assert_eq!(requested.zones.unwrap(), ["eu-west-1a", "eu-west-1b"]);
+
+        // Tasks with only a hard amount of RAM request it.
+        let resources = ResourcesBuilder::default().ram_hard(4.0).build();
+        let requested = to_tes_task(&task, Some(&resources), &[], "abc")
+            .resources
+            .unwrap();
+        assert_eq!(requested.ram_gb, Some(4.0.into()));
+    }
+
+    #[test]
+    fn only_configured_resources_are_requested() {
+        let defaults = serde_json::from_str::<Defaults>(r#"{"zones": ["us-east-1a"]}"#).unwrap();
+        let task = Task::builder()
+            .extend_executions([Execution::builder()
+                .image("ubuntu")
+                .args(["echo", "hello"])
+                .try_build()
+                .unwrap()])
+            .try_build()
+            .unwrap();
+
+        // NOTE: only zones are configured, so nothing else is requested.
+        let resources = resolve_resources(Some(&defaults), task.resources());
+        let requested = to_tes_task(&task, resources.as_ref(), &[], "abc")
+            .resources
+            .unwrap();
+        assert_eq!(requested.zones.unwrap(), ["us-east-1a"]);
+        assert_eq!(requested.cpu_cores, None);
+        assert_eq!(requested.ram_gb, None);
+        assert_eq!(requested.disk_gb, None);
+        assert_eq!(requested.preemptible, None);
+
+        // The resources of the task are merged with the defaults.
+        let resources = ResourcesBuilder::default().cpu(4usize).build();
+        let resources = resolve_resources(Some(&defaults), Some(&resources));
+        let requested = to_tes_task(&task, resources.as_ref(), &[], "abc")
+            .resources
+            .unwrap();
+        assert_eq!(requested.zones.unwrap(), ["us-east-1a"]);
+        assert_eq!(requested.cpu_cores, Some(4));
+        assert_eq!(requested.ram_gb, None);
+    }
+}
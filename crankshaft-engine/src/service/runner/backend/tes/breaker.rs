@@ -0,0 +1,203 @@
+//! A circuit breaker for the TES backend.
+//!
+//! The breaker counts consecutive failed requests to the service. Once the
+//! failure threshold is reached, the breaker _opens_: the backend is reported
+//! as unhealthy and no further requests are made for tasks until a probe of
+//! the service succeeds, at which point the breaker _closes_ again and the
+//! backend is reported as restored.
+
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::sync::Arc;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use tokio::sync::watch;
+use tracing::debug;
+use tracing::warn;
+
+use crate::service::runner::backend::Health;
+
+/// The default number of consecutive failed requests after which the breaker
+/// opens.
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+
+/// The default number of seconds between probes of the service while the
+/// breaker is open.
+pub const DEFAULT_PROBE_INTERVAL: u64 = 30;
+
+/// A probe that checks whether the service is reachable again.
+pub type Probe = Box<dyn Fn() -> BoxFuture<'static, bool> + Send + Sync>;
+
+/// A circuit breaker.
+pub struct Breaker {
+    /// The number of consecutive failures after which the breaker opens.
+    ///
+    /// A threshold of zero disables the breaker.
+    threshold: u32,
+
+    /// The number of consecutive failures.
+    failures: AtomicU32,
+
+    /// Whether or not the breaker is open.
+    open: watch::Sender<bool>,
+
+    /// The time between probes while the breaker is open.
+    interval: Duration,
+
+    /// The probe used to check whether the service is reachable again.
+    probe: Probe,
+
+    /// The health of the backend.
+    health: Health,
+}
+
+impl Breaker {
+    /// Creates a new [`Breaker`].
+    pub fn new(threshold: u32, interval: Duration, health: Health, probe: Probe) -> Self {
+        Self {
+            threshold,
+            failures: Default::default(),
+            open: watch::Sender::new(false),
+            interval,
+            probe,
+            health,
+        }
+    }
+
+    /// Gets whether or not the breaker is open.
+    pub fn is_open(&self) -> bool {
+        *self.open.borrow()
+    }
+
+    /// Waits until the breaker is closed.
+    pub async fn ready(&self) {
+        // NOTE: this can only fail if the sender is dropped, which cannot
+        // happen while `self` is borrowed.
+        let _ = self.open.subscribe().wait_for(|open| !open).await;
+    }
+
+    /// Records a successful request.
+    pub fn success(&self) {
+        self.failures.store(0, Ordering::Relaxed);
+    }
+
+    /// Records a failed request.
+    ///
+    /// If this failure reaches the failure threshold, the breaker is opened
+    /// and the service is probed in the background until it is reachable
+    /// again. Returns whether or not this failure opened the breaker.
+    pub fn failure(self: &Arc<Self>, reason: impl Display) -> bool {
+        let failures = self.failures.fetch_add(1, Ordering::Relaxed) + 1;
+
+        if self.threshold == 0 || failures < self.threshold {
+            return false;
+        }
+
+        let opened = self
+            .open
+            .send_if_modified(|open| !std::mem::replace(open, true));
+
+        if opened {
+            warn!(
+                "`{}` backend is unhealthy after {failures} consecutive failures: {reason}",
+                self.health.name()
+            );
+            self.health
+                .unhealthy(format!("{failures} consecutive failures: {reason}"));
+
+            let breaker = self.clone();
+            tokio::spawn(async move { breaker.recover().await });
+        }
+
+        opened
+    }
+
+    /// Probes the service until it is reachable and then closes the breaker.
+    async fn recover(&self) {
+        loop {
+            tokio::time::sleep(self.interval).await;
+
+            if (self.probe)().await {
+                break;
+            }
+
+            debug!("`{}` backend is still unreachable", self.health.name());
+        }
+
+        self.failures.store(0, Ordering::Relaxed);
+        self.health.restored();
+        self.open.send_replace(false);
+    }
+}
+
+impl Debug for Breaker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Breaker")
+            .field("threshold", &self.threshold)
+            .field("failures", &self.failures)
+            .field("open", &self.is_open())
+            .field("interval", &self.interval)
+            .field("health", &self.health)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+
+    use futures::FutureExt as _;
+
+    use super::*;
+    use crate::events::Event;
+    use crate::events::Events;
+    use crate::service::metrics::Counters;
+
+    #[tokio::test]
+    async fn breaker_opens_and_recovers() {
+        let events = Events::default();
+        let mut receiver = events.subscribe();
+        let counters = Arc::new(Counters::default());
+        let health = Health::new("tes", counters.clone(), events);
+
+        let reachable = Arc::new(AtomicBool::new(false));
+        let probe = {
+            let reachable = reachable.clone();
+            Box::new(move || {
+                let reachable = reachable.load(Ordering::Relaxed);
+                async move { reachable }.boxed()
+            })
+        };
+
+        let breaker = Arc::new(Breaker::new(2, Duration::from_millis(10), health, probe));
+
+        assert!(!breaker.failure("boom"));
+        breaker.success();
+        assert!(!breaker.failure("boom"));
+        assert!(breaker.failure("boom"));
+        assert!(breaker.is_open());
+        assert!(!counters.snapshot().is_healthy());
+        assert!(matches!(
+            receiver.recv().await.unwrap(),
+            Event::BackendUnhealthy { name, .. } if name == "tes"
+        ));
+
+        // NOTE: further failures while open do not report again.
+        assert!(!breaker.failure("boom"));
+
+        reachable.store(true, Ordering::Relaxed);
+        breaker.ready().await;
+
+        assert!(!breaker.is_open());
+        assert!(counters.snapshot().is_healthy());
+        assert_eq!(
+            receiver.recv().await.unwrap(),
+            Event::BackendRestored {
+                name: String::from("tes")
+            }
+        );
+    }
+}
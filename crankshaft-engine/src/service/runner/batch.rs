@@ -0,0 +1,252 @@
+//! Batches of tasks.
+//!
+//! A batch is a set of tasks submitted together (see
+//! [`Engine::submit_batch()`](crate::Engine::submit_batch)) that share an
+//! [`AbortPolicy`]. Once the failures within a batch reach the threshold of
+//! its policy, the batch is _aborted_: every task in the batch that has not yet
+//! completed is canceled and a [`BatchAborted`](Event::BatchAborted) event is
+//! emitted. This avoids spending time on the remaining tasks of a batch that is
+//! systematically broken (e.g., because of a bad input manifest).
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+use crate::events::BatchId;
+use crate::events::Event;
+use crate::events::Events;
+use crate::service::runner::TaskHandle;
+use crate::service::runner::backend::TaskResult;
+
+/// A policy that determines when a batch is aborted.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AbortPolicy {
+    /// The batch is never aborted.
+    #[default]
+    Never,
+
+    /// The batch is aborted once the given number of tasks within it have
+    /// failed.
+    AfterFailures(usize),
+
+    /// The batch is aborted once the given percentage (from `0` to `100`) of
+    /// the tasks within it have failed.
+    FailureRate(f64),
+}
+
+impl AbortPolicy {
+    /// Gets whether or not a batch of `size` tasks with `failures` failed
+    /// tasks should be aborted.
+    fn should_abort(&self, failures: usize, size: usize) -> bool {
+        match *self {
+            AbortPolicy::Never => false,
+            AbortPolicy::AfterFailures(threshold) => failures >= threshold,
+            AbortPolicy::FailureRate(percentage) => {
+                failures as f64 * 100.0 >= percentage * size as f64
+            }
+        }
+    }
+}
+
+/// The shared state of a batch.
+#[derive(Debug)]
+pub(crate) struct State {
+    /// The id of the batch.
+    id: BatchId,
+
+    /// The policy of the batch.
+    policy: AbortPolicy,
+
+    /// The number of tasks within the batch.
+    size: usize,
+
+    /// The number of failed tasks within the batch.
+    failures: AtomicUsize,
+
+    /// Whether or not the batch was aborted.
+    aborted: AtomicBool,
+
+    /// The cancellation token that is the parent of every task in the batch.
+    token: CancellationToken,
+
+    /// The events of the engine.
+    events: Events,
+}
+
+impl State {
+    /// Creates the state for a new batch.
+    pub(crate) fn new(policy: AbortPolicy, size: usize, events: Events) -> Self {
+        Self {
+            id: events.next_batch_id(),
+            policy,
+            size,
+            failures: Default::default(),
+            aborted: Default::default(),
+            token: CancellationToken::new(),
+            events,
+        }
+    }
+
+    /// Gets the cancellation token that is the parent of every task in the
+    /// batch.
+    pub(crate) fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    /// Records the result of a task within the batch (or [`None`] if the task
+    /// was canceled), aborting the batch if its policy is met.
+    pub(crate) fn record(&self, result: Option<&TaskResult>) {
+        match result {
+            Some(result) if !result.is_success() => {}
+            _ => return,
+        }
+
+        let failures = self.failures.fetch_add(1, Ordering::Relaxed) + 1;
+
+        if !self.policy.should_abort(failures, self.size) {
+            return;
+        }
+
+        // NOTE: only the first task to meet the policy aborts the batch.
+        if self.aborted.swap(true, Ordering::Relaxed) {
+            return;
+        }
+
+        warn!(
+            "aborting batch {} after {failures} of {} tasks failed",
+            self.id, self.size
+        );
+
+        self.token.cancel();
+        self.events.send(Event::BatchAborted {
+            batch_id: self.id,
+            failures,
+        });
+    }
+}
+
+/// A handle to a submitted batch of tasks.
+#[derive(Debug)]
+pub struct Batch {
+    /// The state of the batch.
+    state: Arc<State>,
+
+    /// The handles of the tasks within the batch.
+    handles: Vec<TaskHandle>,
+}
+
+impl Batch {
+    /// Creates a new [`Batch`].
+    pub(crate) fn new(state: Arc<State>, handles: Vec<TaskHandle>) -> Self {
+        Self { state, handles }
+    }
+
+    /// Gets the id of the batch.
+    ///
+    /// This is the id that is used to refer to the batch within
+    /// [`Event`]s.
+    pub fn id(&self) -> BatchId {
+        self.state.id
+    }
+
+    /// Gets the handles of the tasks within the batch.
+    pub fn handles(&self) -> &[TaskHandle] {
+        &self.handles
+    }
+
+    /// Consumes `self` and returns the handles of the tasks within the batch.
+    pub fn into_handles(self) -> Vec<TaskHandle> {
+        self.handles
+    }
+
+    /// Gets the number of failed tasks within the batch so far.
+    pub fn failures(&self) -> usize {
+        self.state.failures.load(Ordering::Relaxed)
+    }
+
+    /// Gets whether or not the batch was aborted by its [`AbortPolicy`].
+    pub fn is_aborted(&self) -> bool {
+        self.state.aborted.load(Ordering::Relaxed)
+    }
+
+    /// Cancels every task within the batch that has not yet completed.
+    pub fn cancel(&self) {
+        self.state.token.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::Engine;
+    use crate::Task;
+    use crate::simulation::MockBackend;
+    use crate::simulation::runtime;
+    use crate::task::Execution;
+
+    #[test]
+    fn policies_are_applied() {
+        assert!(!AbortPolicy::Never.should_abort(10, 10));
+        assert!(!AbortPolicy::AfterFailures(3).should_abort(2, 10));
+        assert!(AbortPolicy::AfterFailures(3).should_abort(3, 10));
+        assert!(!AbortPolicy::FailureRate(25.0).should_abort(2, 10));
+        assert!(AbortPolicy::FailureRate(25.0).should_abort(3, 10));
+    }
+
+    #[test]
+    fn batches_are_aborted() {
+        runtime().unwrap().block_on(async {
+            // NOTE: failing tasks finish quickly, so the batch is aborted well
+            // before the successful tasks complete.
+            let backend = MockBackend::new(|task, _| match task.name() {
+                Some("fail") => (Duration::from_secs(1), 1),
+                _ => (Duration::from_secs(60), 0),
+            });
+
+            let engine = Engine::default().with_backend("mock", Arc::new(backend), 10);
+            let metrics = engine.metrics();
+            let mut events = engine.subscribe();
+
+            let execution = Execution::builder()
+                .image("ubuntu")
+                .args(["echo", "hello"])
+                .try_build()
+                .unwrap();
+
+            let tasks = (0..100).map(|i| {
+                Task::builder()
+                    .name(if i % 2 == 0 { "fail" } else { "succeed" })
+                    .extend_executions([execution.clone()])
+                    .try_build()
+                    .unwrap()
+            });
+
+            let batch = engine.submit_batch("mock", tasks, AbortPolicy::AfterFailures(3));
+            let id = batch.id();
+            engine.run().await;
+
+            assert!(batch.is_aborted());
+            assert!(batch.failures() >= 3);
+
+            // NOTE: none of the successful tasks complete before the batch is
+            // aborted.
+            let snapshot = metrics.global();
+            assert_eq!(snapshot.failed(), snapshot.completed());
+            assert_eq!(snapshot.completed() + snapshot.canceled(), 100);
+
+            loop {
+                if let Event::BatchAborted { batch_id, failures } = events.recv().await.unwrap() {
+                    assert_eq!(batch_id, id);
+                    assert_eq!(failures, 3);
+                    break;
+                }
+            }
+        });
+    }
+}
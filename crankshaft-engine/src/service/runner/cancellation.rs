@@ -0,0 +1,202 @@
+//! Cancellation of runs.
+//!
+//! A run (i.e., every task submitted to an [`Engine`](crate::Engine)) can be
+//! canceled through a [`Canceler`] (see
+//! [`Engine::canceler()`](crate::Engine::canceler)). Cancellation is
+//! prioritized so that teardown is orderly:
+//!
+//! 1. Tasks that are still queued (i.e., waiting on a permit from their
+//!    backend) are canceled first, so that no task starts because a running
+//!    task released its permit during teardown.
+//! 2. Tasks that are running are canceled afterwards. Running tasks are
+//!    canceled together rather than in reverse dependency order: a task within
+//!    a [`TaskGroup`](crate::service::runner::TaskGroup) only starts once its
+//!    dependencies have succeeded, so no running task ever depends on another
+//!    running task (and blocked dependents are canceled with the queued tasks).
+//!
+//! Once the run finishes, a [`RunCanceled`](Event::RunCanceled) event
+//! summarizing the canceled tasks is emitted.
+
+use std::sync::Arc;
+use std::sync::OnceLock;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+#[cfg(doc)]
+use crate::events::Event;
+
+/// The shared state of a run's cancellation.
+#[derive(Debug, Default)]
+pub(crate) struct State {
+    /// The token canceled for queued tasks.
+    queued: CancellationToken,
+
+    /// The token canceled for running tasks.
+    running: CancellationToken,
+
+    /// The number of queued tasks that were canceled.
+    queued_canceled: AtomicUsize,
+
+    /// The number of running tasks that were canceled.
+    running_canceled: AtomicUsize,
+}
+
+impl State {
+    /// Completes once a task should be canceled because the run was canceled.
+    ///
+    /// Whether the task is queued or running is determined by whether or not
+    /// it has `started` when the queued tasks are canceled. Completing records
+    /// the cancellation of the task.
+    pub(crate) async fn cancelled(&self, started: &OnceLock<Instant>) {
+        self.queued.cancelled().await;
+
+        if started.get().is_none() {
+            self.queued_canceled.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        self.running.cancelled().await;
+        self.running_canceled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Gets whether or not the run was canceled.
+    pub(crate) fn is_canceled(&self) -> bool {
+        self.queued.is_cancelled()
+    }
+
+    /// Gets the number of queued and running tasks that were canceled.
+    pub(crate) fn counts(&self) -> (usize, usize) {
+        (
+            self.queued_canceled.load(Ordering::Relaxed),
+            self.running_canceled.load(Ordering::Relaxed),
+        )
+    }
+}
+
+/// A handle through which a run is canceled.
+#[derive(Clone, Debug)]
+pub struct Canceler {
+    /// The state of the run's cancellation.
+    state: Arc<State>,
+}
+
+impl Canceler {
+    /// Creates a new [`Canceler`].
+    pub(crate) fn new(state: Arc<State>) -> Self {
+        Self { state }
+    }
+
+    /// Cancels the run.
+    ///
+    /// Queued tasks are canceled before running tasks (see the [module
+    /// documentation](self)). Tasks submitted after the run was canceled are
+    /// canceled immediately.
+    pub fn cancel(&self) {
+        if self.state.is_canceled() {
+            return;
+        }
+
+        warn!("canceling run");
+
+        // NOTE: queued tasks observe their cancellation before any permit
+        // released by a canceled running task can be acquired, as task futures
+        // always poll for cancellation before acquiring a permit.
+        self.state.queued.cancel();
+        self.state.running.cancel();
+    }
+
+    /// Gets whether or not the run was canceled.
+    pub fn is_canceled(&self) -> bool {
+        self.state.is_canceled()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::Engine;
+    use crate::Task;
+    use crate::events::Event;
+    use crate::simulation::MockBackend;
+    use crate::simulation::runtime;
+    use crate::task::Execution;
+
+    #[test]
+    fn queued_and_running_tasks_are_canceled() {
+        runtime().unwrap().block_on(async {
+            let backend = MockBackend::new(|_, _| (Duration::from_secs(60), 0));
+            let engine = Engine::default().with_backend("mock", Arc::new(backend), 1);
+            let metrics = engine.metrics();
+            let canceler = engine.canceler();
+            let mut events = engine.subscribe();
+
+            let task = Task::builder()
+                .extend_executions([Execution::builder()
+                    .image("ubuntu")
+                    .args(["echo", "hello"])
+                    .try_build()
+                    .unwrap()])
+                .try_build()
+                .unwrap();
+
+            let _handles = (0..3)
+                .map(|_| engine.submit("mock", task.clone()))
+                .collect::<Vec<_>>();
+
+            tokio::spawn({
+                let canceler = canceler.clone();
+
+                async move {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    canceler.cancel();
+                }
+            });
+
+            engine.run().await;
+            assert!(canceler.is_canceled());
+
+            let snapshot = metrics.global();
+            assert_eq!(snapshot.completed(), 0);
+            assert_eq!(snapshot.canceled(), 3);
+
+            let mut received = Vec::new();
+            while let Ok(event) = events.try_recv() {
+                received.push(event);
+            }
+
+            assert!(received.iter().any(|event| matches!(
+                event,
+                Event::RunCanceled {
+                    queued: 2,
+                    running: 1
+                }
+            )));
+
+            // NOTE: only the running task is terminated, and it is terminated
+            // before it is reported as canceled.
+            let terminating = received
+                .iter()
+                .filter_map(|event| match event {
+                    Event::TaskTerminating { task_id, .. } => Some(*task_id),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(terminating.len(), 1);
+
+            let position = |f: &dyn Fn(&Event) -> bool| received.iter().position(f).unwrap();
+            assert!(
+                position(&|event| matches!(event, Event::TaskTerminating { .. }))
+                    < position(&|event| matches!(
+                        event,
+                        Event::TaskCanceled { task_id, .. } if *task_id == terminating[0]
+                    ))
+            );
+        });
+    }
+}
@@ -0,0 +1,536 @@
+//! Groups of tasks with dependencies between them.
+//!
+//! A [`TaskGroup`] is a set of named tasks along with `depends_on` edges
+//! between them (i.e., a directed acyclic graph) that is spawned together (see
+//! [`Engine::spawn_group()`](crate::Engine::spawn_group)). Every task within
+//! the group is submitted at once, but a task with dependencies is _blocked_
+//! (and does not enter the queue of its backend) until all of its
+//! dependencies have succeeded. If any dependency fails (or is canceled), the
+//! task fails without running, which in turn fails the tasks that depend on
+//! it.
+//!
+//! Blocked tasks are reported through the
+//! [`TaskBlocked`](crate::events::Event::TaskBlocked) and
+//! [`TaskUnblocked`](crate::events::Event::TaskUnblocked) events.
+
+use std::collections::BTreeSet;
+
+use futures::StreamExt as _;
+use futures::future::BoxFuture;
+use futures::stream::FuturesUnordered;
+use indexmap::IndexMap;
+use indexmap::IndexSet;
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+
+use crate::Task;
+use crate::events::TaskId;
+use crate::service::report::Outcome;
+use crate::service::runner::TaskHandle;
+
+/// An error related to a [`TaskGroup`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// A dependency was added for a task that is not within the group.
+    UnknownTask(String),
+
+    /// A task depends on a task that is not within the group.
+    UnknownDependency {
+        /// The name of the task.
+        task: String,
+
+        /// The name of the dependency.
+        dependency: String,
+    },
+
+    /// The dependencies of the tasks form a cycle (by the names of the tasks
+    /// along the cycle).
+    Cycle(Vec<String>),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnknownTask(task) => write!(
+                f,
+                "a dependency was added for task `{task}`, which is not within the group"
+            ),
+            Error::UnknownDependency { task, dependency } => write!(
+                f,
+                "task `{task}` depends on `{dependency}`, which is not within the group"
+            ),
+            Error::Cycle(tasks) => write!(
+                f,
+                "the dependencies of the group form a cycle: {}",
+                tasks
+                    .iter()
+                    .map(|task| format!("`{task}`"))
+                    .collect::<Vec<_>>()
+                    .join(" -> ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A [`Result`](std::result::Result) with an [`Error`].
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A set of named tasks with dependencies between them.
+#[derive(Debug, Default)]
+pub struct TaskGroup {
+    /// The tasks within the group (by name).
+    tasks: IndexMap<String, Task>,
+
+    /// The dependencies of the tasks within the group (by the name of the
+    /// dependent task).
+    dependencies: IndexMap<String, IndexSet<String>>,
+}
+
+impl TaskGroup {
+    /// Creates a new, empty [`TaskGroup`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a named task to the group.
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous task with the same name
+    /// added to the group (but not the dependencies of that name).
+    pub fn task(mut self, name: impl Into<String>, task: Task) -> Self {
+        self.tasks.insert(name.into(), task);
+        self
+    }
+
+    /// Adds an edge to the group such that the task named `task` only runs
+    /// once the task named `dependency` has succeeded.
+    ///
+    /// Both tasks must be added to the group (in any order) before it is
+    /// spawned.
+    pub fn depends_on(mut self, task: impl Into<String>, dependency: impl Into<String>) -> Self {
+        self.dependencies
+            .entry(task.into())
+            .or_default()
+            .insert(dependency.into());
+        self
+    }
+
+    /// Gets the number of tasks within the group.
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Gets whether or not the group contains no tasks.
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Consumes `self` and returns the tasks of the group (along with the
+    /// names of their dependencies) ordered such that every task comes after
+    /// its dependencies.
+    ///
+    /// Tasks without dependencies between them keep the order in which they
+    /// were added.
+    pub(crate) fn into_ordered(mut self) -> Result<Vec<(String, Task, Vec<String>)>> {
+        for (task, dependencies) in &self.dependencies {
+            if !self.tasks.contains_key(task) {
+                return Err(Error::UnknownTask(task.clone()));
+            }
+
+            for dependency in dependencies {
+                if !self.tasks.contains_key(dependency) {
+                    return Err(Error::UnknownDependency {
+                        task: task.clone(),
+                        dependency: dependency.clone(),
+                    });
+                }
+            }
+        }
+
+        // NOTE: the ready task that was added first is always ordered next
+        // (Kahn's algorithm), which preserves the order of insertion where
+        // possible.
+        let index = |name: &str| self.tasks.get_index_of(name).unwrap();
+        let mut remaining = vec![0; self.tasks.len()];
+        let mut dependents = vec![Vec::new(); self.tasks.len()];
+
+        for (task, dependencies) in &self.dependencies {
+            for dependency in dependencies {
+                remaining[index(task)] += 1;
+                dependents[index(dependency)].push(index(task));
+            }
+        }
+
+        let mut ready = (0..self.tasks.len())
+            .filter(|i| remaining[*i] == 0)
+            .collect::<BTreeSet<_>>();
+        let mut order = Vec::with_capacity(self.tasks.len());
+
+        while let Some(i) = ready.pop_first() {
+            order.push(i);
+
+            for dependent in &dependents[i] {
+                remaining[*dependent] -= 1;
+
+                if remaining[*dependent] == 0 {
+                    ready.insert(*dependent);
+                }
+            }
+        }
+
+        if order.len() < self.tasks.len() {
+            let name = |i: usize| self.tasks.get_index(i).unwrap().0.as_str();
+            let unordered = |i: &usize| remaining[*i] > 0;
+
+            return Err(Error::Cycle(cycle(
+                (0..self.tasks.len()).find(unordered).unwrap(),
+                |i| {
+                    self.dependencies[name(i)]
+                        .iter()
+                        .map(|dependency| index(dependency))
+                        .find(unordered)
+                        .unwrap()
+                },
+                name,
+            )));
+        }
+
+        let mut tasks = self.tasks.into_iter().map(Some).collect::<Vec<_>>();
+
+        Ok(order
+            .into_iter()
+            .map(|i| {
+                // SAFETY: every task is ordered exactly once.
+                let (name, task) = tasks[i].take().unwrap();
+                let dependencies = self
+                    .dependencies
+                    .swap_remove(&name)
+                    .map(|dependencies| dependencies.into_iter().collect())
+                    .unwrap_or_default();
+                (name, task, dependencies)
+            })
+            .collect())
+    }
+}
+
+/// Finds a cycle by following unordered dependencies from a task that could
+/// not be ordered.
+///
+/// Every task that could not be ordered has a dependency that could not be
+/// ordered either, so following them always leads back to a task that was
+/// already visited.
+fn cycle<'a>(
+    start: usize,
+    next: impl Fn(usize) -> usize,
+    name: impl Fn(usize) -> &'a str,
+) -> Vec<String> {
+    let mut path = IndexSet::new();
+    let mut current = start;
+
+    while path.insert(current) {
+        current = next(current);
+    }
+
+    // SAFETY: the loop only ends when `current` is already within the path.
+    let index = path.get_index_of(&current).unwrap();
+    path.into_iter()
+        .skip(index)
+        .chain([current])
+        .map(|i| name(i).to_owned())
+        .collect()
+}
+
+/// A dependency of a task within a group.
+#[derive(Debug)]
+pub(crate) struct Dependency {
+    /// The name of the dependency within the group.
+    name: String,
+
+    /// The id of the dependency.
+    id: TaskId,
+
+    /// The outcome of the dependency (once it has finished).
+    outcome: watch::Receiver<Option<Outcome>>,
+}
+
+impl Dependency {
+    /// Creates a new [`Dependency`].
+    pub(crate) fn new(name: String, id: TaskId, outcome: watch::Receiver<Option<Outcome>>) -> Self {
+        Self { name, id, outcome }
+    }
+}
+
+/// The dependencies that a task within a group waits on before it is queued.
+#[derive(Debug)]
+pub(crate) struct Dependencies(Vec<Dependency>);
+
+impl Dependencies {
+    /// Creates a new [`Dependencies`].
+    pub(crate) fn new(dependencies: Vec<Dependency>) -> Self {
+        Self(dependencies)
+    }
+
+    /// Gets the ids of the dependencies.
+    pub(crate) fn ids(&self) -> Vec<TaskId> {
+        self.0.iter().map(|dependency| dependency.id).collect()
+    }
+
+    /// Consumes `self` and waits until every dependency has succeeded.
+    ///
+    /// As soon as any dependency fails (or is canceled), the reason is
+    /// returned without waiting on the remaining dependencies.
+    pub(crate) fn wait(self) -> BoxFuture<'static, std::result::Result<(), String>> {
+        let mut pending = self
+            .0
+            .into_iter()
+            .map(|mut dependency| async move {
+                // NOTE: the sender is only dropped without an outcome if the
+                // dependency was dropped before it finished.
+                let outcome = dependency
+                    .outcome
+                    .wait_for(Option::is_some)
+                    .await
+                    .map(|outcome| *outcome)
+                    .unwrap_or(Some(Outcome::Canceled));
+
+                match outcome {
+                    Some(Outcome::Succeeded) => Ok(()),
+                    Some(Outcome::Failed) => Err(format!(
+                        "dependency `{}` (task {}) failed",
+                        dependency.name, dependency.id
+                    )),
+                    _ => Err(format!(
+                        "dependency `{}` (task {}) was canceled",
+                        dependency.name, dependency.id
+                    )),
+                }
+            })
+            .collect::<FuturesUnordered<_>>();
+
+        Box::pin(async move {
+            while let Some(result) = pending.next().await {
+                result?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+/// A handle to a spawned [`TaskGroup`].
+#[derive(Debug)]
+pub struct Group {
+    /// The cancellation token that is the parent of every task in the group.
+    token: CancellationToken,
+
+    /// The handles of the tasks within the group (by name).
+    handles: IndexMap<String, TaskHandle>,
+}
+
+impl Group {
+    /// Creates a new [`Group`].
+    pub(crate) fn new(token: CancellationToken, handles: IndexMap<String, TaskHandle>) -> Self {
+        Self { token, handles }
+    }
+
+    /// Gets the handle of a task within the group by its name (if it exists).
+    pub fn handle(&self, name: &str) -> Option<&TaskHandle> {
+        self.handles.get(name)
+    }
+
+    /// Gets the handles of the tasks within the group (by name).
+    ///
+    /// The handles are ordered such that every task comes after its
+    /// dependencies.
+    pub fn handles(&self) -> &IndexMap<String, TaskHandle> {
+        &self.handles
+    }
+
+    /// Consumes `self` and returns the handles of the tasks within the group
+    /// (by name).
+    pub fn into_handles(self) -> IndexMap<String, TaskHandle> {
+        self.handles
+    }
+
+    /// Cancels every task within the group that has not yet completed.
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::Engine;
+    use crate::events::Event;
+    use crate::simulation::MockBackend;
+    use crate::simulation::runtime;
+    use crate::task::Execution;
+
+    /// Creates a task with a name.
+    fn task(name: &str) -> Task {
+        Task::builder()
+            .name(name)
+            .extend_executions([Execution::builder()
+                .image("ubuntu")
+                .args(["echo", "hello"])
+                .try_build()
+                .unwrap()])
+            .try_build()
+            .unwrap()
+    }
+
+    /// Gets the names of the tasks of a group in order.
+    fn order(group: TaskGroup) -> Result<Vec<String>> {
+        Ok(group
+            .into_ordered()?
+            .into_iter()
+            .map(|(name, ..)| name)
+            .collect())
+    }
+
+    #[test]
+    fn tasks_are_ordered_after_their_dependencies() {
+        let group = TaskGroup::new()
+            .task("call", task("call"))
+            .task("index", task("index"))
+            .task("align", task("align"))
+            .depends_on("call", "align")
+            .depends_on("align", "index");
+        assert_eq!(order(group).unwrap(), ["index", "align", "call"]);
+
+        let group = TaskGroup::new()
+            .task("a", task("a"))
+            .depends_on("missing", "a");
+        assert_eq!(
+            order(group),
+            Err(Error::UnknownTask(String::from("missing")))
+        );
+
+        let group = TaskGroup::new()
+            .task("a", task("a"))
+            .depends_on("a", "missing");
+        assert_eq!(
+            order(group),
+            Err(Error::UnknownDependency {
+                task: String::from("a"),
+                dependency: String::from("missing"),
+            })
+        );
+
+        let group = TaskGroup::new()
+            .task("a", task("a"))
+            .task("b", task("b"))
+            .task("c", task("c"))
+            .depends_on("a", "b")
+            .depends_on("b", "c")
+            .depends_on("c", "b");
+        let err = order(group).unwrap_err();
+        assert_eq!(
+            err,
+            Error::Cycle(vec![
+                String::from("b"),
+                String::from("c"),
+                String::from("b")
+            ])
+        );
+        assert_eq!(
+            err.to_string(),
+            "the dependencies of the group form a cycle: `b` -> `c` -> `b`"
+        );
+    }
+
+    #[test]
+    fn failures_propagate_to_dependent_tasks() {
+        runtime().unwrap().block_on(async {
+            let backend = MockBackend::new(|task, _| match task.name() {
+                Some("align") => (Duration::from_secs(5), 1),
+                _ => (Duration::from_secs(5), 0),
+            });
+
+            let engine = Engine::default().with_backend("mock", Arc::new(backend), 10);
+            let metrics = engine.metrics();
+            let mut events = engine.subscribe();
+
+            // NOTE: `report` depends on `qc` (which succeeds) and on `call`
+            // (which fails because `align` fails).
+            let group = TaskGroup::new()
+                .task("index", task("index"))
+                .task("align", task("align"))
+                .task("call", task("call"))
+                .task("qc", task("qc"))
+                .task("report", task("report"))
+                .depends_on("align", "index")
+                .depends_on("call", "align")
+                .depends_on("qc", "index")
+                .depends_on("report", "call")
+                .depends_on("report", "qc");
+
+            let group = engine.spawn_group("mock", group).unwrap();
+            let id = |name| group.handle(name).unwrap().id();
+            let (index, align, call, qc, report) =
+                (id("index"), id("align"), id("call"), id("qc"), id("report"));
+            engine.run().await;
+
+            let snapshot = metrics.global();
+            assert_eq!(snapshot.completed(), 5);
+            assert_eq!(snapshot.failed(), 3);
+
+            let mut blocked = Vec::new();
+            let mut unblocked = Vec::new();
+            let mut started = Vec::new();
+            let mut failed = Vec::new();
+
+            while let Ok(event) = events.try_recv() {
+                match event {
+                    Event::TaskBlocked {
+                        task_id,
+                        dependencies,
+                        ..
+                    } => blocked.push((task_id, dependencies)),
+                    Event::TaskUnblocked { task_id, .. } => unblocked.push(task_id),
+                    Event::TaskStarted { task_id, .. } => started.push(task_id),
+                    Event::TaskFailed {
+                        task_id, reason, ..
+                    } => failed.push((task_id, reason.to_string())),
+                    _ => {}
+                }
+            }
+
+            // NOTE: `align` and `qc` are unblocked (and started) at the same
+            // time once `index` succeeds.
+            unblocked.sort();
+            started.sort();
+
+            assert_eq!(blocked.len(), 4);
+            assert!(blocked.contains(&(report, vec![call, qc])));
+            assert_eq!(unblocked, [align, qc]);
+            assert_eq!(started, [index, align, qc]);
+            assert_eq!(
+                failed,
+                [
+                    (
+                        call,
+                        format!(
+                            "a dependency of the task failed: dependency `align` (task {align}) \
+                             failed"
+                        )
+                    ),
+                    (
+                        report,
+                        format!(
+                            "a dependency of the task failed: dependency `call` (task {call}) \
+                             failed"
+                        )
+                    )
+                ]
+            );
+        });
+    }
+}
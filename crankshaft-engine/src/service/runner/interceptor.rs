@@ -0,0 +1,137 @@
+//! Submission interceptors.
+//!
+//! An interceptor inspects every task before it reaches the backend of a
+//! runner (see
+//! [`Engine::with_interceptor()`](crate::Engine::with_interceptor)).
+//! Interceptors may modify a task (e.g., to rewrite mount paths) or reject it
+//! outright (e.g., because it uses an image that is not allowed by policy),
+//! which allows policy to be enforced without modifying the backends
+//! themselves.
+//!
+//! Rejected tasks never reach the backend: they fail immediately with
+//! [`FailureReason::Rejected`](crate::service::runner::backend::FailureReason::Rejected).
+
+use std::fmt::Debug;
+
+use crate::Task;
+
+/// An interceptor of task submissions.
+pub trait SubmitInterceptor: Debug + Send + Sync + 'static {
+    /// Intercepts a task before it is submitted to the backend.
+    ///
+    /// The returned task is submitted in place of the original. If an error
+    /// is returned, the task is rejected with the error as the reason.
+    fn intercept(&self, task: Task) -> Result<Task, String>;
+}
+
+/// Applies a set of interceptors (in order) to a task.
+///
+/// The first interceptor to reject the task stops the remaining interceptors
+/// from being applied.
+pub(crate) fn apply<'a>(
+    interceptors: impl IntoIterator<Item = &'a dyn SubmitInterceptor>,
+    task: Task,
+) -> Result<Task, String> {
+    interceptors
+        .into_iter()
+        .try_fold(task, |task, interceptor| interceptor.intercept(task))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::Engine;
+    use crate::events::Event;
+    use crate::service::runner::backend::FailureReason;
+    use crate::simulation::MockBackend;
+    use crate::simulation::runtime;
+    use crate::task::Execution;
+
+    /// Only allows images from a particular registry.
+    #[derive(Debug)]
+    struct ImagePolicy;
+
+    impl SubmitInterceptor for ImagePolicy {
+        fn intercept(&self, task: Task) -> Result<Task, String> {
+            let disallowed = task
+                .executions()
+                .map(|execution| execution.image())
+                .find(|image| !image.starts_with("registry.example.com/"))
+                .map(ToOwned::to_owned);
+
+            match disallowed {
+                Some(image) => Err(format!("image `{image}` is not allowed")),
+                None => Ok(task),
+            }
+        }
+    }
+
+    /// Prefixes the name of every task.
+    #[derive(Debug)]
+    struct Prefix;
+
+    impl SubmitInterceptor for Prefix {
+        fn intercept(&self, mut task: Task) -> Result<Task, String> {
+            let name = format!("site-{}", task.name().unwrap_or_default());
+            task.override_name(name);
+            Ok(task)
+        }
+    }
+
+    fn task(image: &str) -> Task {
+        Task::builder()
+            .name("foo")
+            .extend_executions([Execution::builder()
+                .image(image)
+                .args(["echo", "hello"])
+                .try_build()
+                .unwrap()])
+            .try_build()
+            .unwrap()
+    }
+
+    #[test]
+    fn interceptors_modify_and_reject_tasks() {
+        runtime().unwrap().block_on(async {
+            let backend = MockBackend::new(|task, _| {
+                assert_eq!(task.name(), Some("site-foo"));
+                (Duration::from_secs(1), 0)
+            });
+
+            let engine = Engine::default()
+                .with_backend("mock", Arc::new(backend), 10)
+                .with_interceptor("mock", Arc::new(ImagePolicy))
+                .with_interceptor("mock", Arc::new(Prefix));
+            let metrics = engine.metrics();
+            let mut events = engine.subscribe();
+
+            let allowed = engine.submit("mock", task("registry.example.com/ubuntu"));
+            let rejected = engine.submit("mock", task("ubuntu"));
+            engine.run().await;
+
+            assert!(allowed.await.unwrap().is_success());
+            assert!(rejected.await.is_none());
+
+            let snapshot = metrics.global();
+            assert_eq!(snapshot.completed(), 2);
+            assert_eq!(snapshot.failed(), 1);
+
+            loop {
+                if let Event::TaskFailed {
+                    task_id, reason, ..
+                } = events.recv().await.unwrap()
+                {
+                    assert_eq!(task_id, 1);
+                    assert_eq!(
+                        reason,
+                        FailureReason::Rejected(String::from("image `ubuntu` is not allowed"))
+                    );
+                    break;
+                }
+            }
+        });
+    }
+}
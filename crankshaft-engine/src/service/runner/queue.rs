@@ -0,0 +1,363 @@
+//! Queueing of the tasks submitted to a backend.
+//!
+//! Each backend runs at most `max-tasks` tasks at once. Tasks submitted while
+//! the backend is at capacity wait within a [`Queue`] and are started in the
+//! [order](QueueOrder) configured for the backend as capacity frees up.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use crankshaft_config::backend::QueueOrder;
+use tokio::sync::oneshot;
+
+/// A point-in-time snapshot of the queue of a backend.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QueueStats {
+    /// The number of tasks waiting for the backend to have free capacity.
+    queued: usize,
+
+    /// The number of tasks currently running.
+    running: usize,
+
+    /// The number of tasks that have finished.
+    finished: u64,
+}
+
+impl QueueStats {
+    /// Gets the number of tasks waiting for the backend to have free
+    /// capacity.
+    pub fn queued(&self) -> usize {
+        self.queued
+    }
+
+    /// Gets the number of tasks currently running.
+    pub fn running(&self) -> usize {
+        self.running
+    }
+
+    /// Gets the number of tasks that have finished (whether they completed,
+    /// failed, or were canceled).
+    pub fn finished(&self) -> u64 {
+        self.finished
+    }
+}
+
+/// A task waiting within a [`Queue`].
+#[derive(Debug)]
+struct Waiter {
+    /// The order in which the task entered the queue.
+    sequence: u64,
+
+    /// The priority of the task.
+    priority: i32,
+
+    /// The group of the task.
+    group: Option<String>,
+
+    /// The sender through which the task is handed its permit.
+    tx: oneshot::Sender<Permit>,
+}
+
+/// The mutable state of a [`Queue`].
+#[derive(Debug, Default)]
+struct State {
+    /// The tasks waiting for the backend to have free capacity.
+    waiters: Vec<Waiter>,
+
+    /// The sequence number of the next task to enter the queue.
+    sequence: u64,
+
+    /// The number of tasks currently running.
+    running: usize,
+
+    /// The number of tasks currently running within each group.
+    groups: HashMap<Option<String>, usize>,
+
+    /// The number of tasks that have finished.
+    finished: u64,
+}
+
+impl State {
+    /// Removes the next waiter to start from the queue (if one exists).
+    fn next(&mut self, order: QueueOrder) -> Option<Waiter> {
+        // NOTE: waiters whose tasks were canceled (or otherwise dropped) have
+        // closed receivers and are never started.
+        self.waiters.retain(|waiter| !waiter.tx.is_closed());
+
+        let groups = &self.groups;
+        let running = |waiter: &Waiter| groups.get(&waiter.group).copied().unwrap_or(0);
+
+        let index = self
+            .waiters
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, waiter)| match order {
+                QueueOrder::Fifo => (0, 0, waiter.sequence),
+                QueueOrder::Priority => (0, -(waiter.priority as i64), waiter.sequence),
+                QueueOrder::FairShare => {
+                    (running(waiter), -(waiter.priority as i64), waiter.sequence)
+                }
+            })
+            .map(|(index, _)| index)?;
+
+        Some(self.waiters.remove(index))
+    }
+
+    /// Records a task of a group as running.
+    fn enter(&mut self, group: &Option<String>) {
+        self.running += 1;
+        *self.groups.entry(group.clone()).or_default() += 1;
+    }
+
+    /// Records a task of a group as no longer running.
+    fn leave(&mut self, group: &Option<String>) {
+        self.running -= 1;
+
+        if let Some(running) = self.groups.get_mut(group) {
+            *running -= 1;
+
+            if *running == 0 {
+                self.groups.remove(group);
+            }
+        }
+    }
+}
+
+/// The queue of the tasks submitted to a backend.
+#[derive(Debug)]
+pub(crate) struct Queue {
+    /// The maximum number of tasks that run at once.
+    capacity: usize,
+
+    /// The order in which waiting tasks are started.
+    order: QueueOrder,
+
+    /// The state of the queue.
+    state: Mutex<State>,
+}
+
+impl Queue {
+    /// Creates a new [`Queue`] for a backend that runs at most `capacity`
+    /// tasks at once.
+    pub(crate) fn new(capacity: usize, order: QueueOrder) -> Self {
+        Self {
+            capacity,
+            order,
+            state: Default::default(),
+        }
+    }
+
+    /// Creates a new [`Queue`] with the same capacity as this queue that
+    /// starts waiting tasks in a different order.
+    pub(crate) fn with_order(&self, order: QueueOrder) -> Self {
+        Self::new(self.capacity, order)
+    }
+
+    /// Waits until the backend has free capacity for a task.
+    ///
+    /// The task runs until the returned [`Permit`] is dropped.
+    pub(crate) async fn acquire(self: &Arc<Self>, priority: i32, group: Option<&str>) -> Permit {
+        let (tx, rx) = oneshot::channel();
+
+        let grants = {
+            let mut state = self.state.lock().unwrap();
+            let sequence = state.sequence;
+            state.sequence += 1;
+            state.waiters.push(Waiter {
+                sequence,
+                priority,
+                group: group.map(ToOwned::to_owned),
+                tx,
+            });
+
+            self.grant(&mut state)
+        };
+
+        Self::send(grants);
+
+        // SAFETY: the sender is only dropped after a permit is sent (as the
+        // queue is kept alive by this future).
+        rx.await
+            .expect("the sender of a permit should not be dropped")
+    }
+
+    /// Takes a snapshot of the queue.
+    pub(crate) fn stats(&self) -> QueueStats {
+        let state = self.state.lock().unwrap();
+
+        QueueStats {
+            queued: state
+                .waiters
+                .iter()
+                .filter(|waiter| !waiter.tx.is_closed())
+                .count(),
+            running: state.running,
+            finished: state.finished,
+        }
+    }
+
+    /// Records a finished task.
+    pub(crate) fn finished(&self) {
+        self.state.lock().unwrap().finished += 1;
+    }
+
+    /// Hands out permits to waiting tasks while the backend has free capacity.
+    ///
+    /// The permits are returned alongside their senders so that they can be
+    /// sent once the state is no longer locked.
+    fn grant(self: &Arc<Self>, state: &mut State) -> Vec<(oneshot::Sender<Permit>, Permit)> {
+        let mut grants = Vec::new();
+
+        while state.running < self.capacity {
+            let Some(waiter) = state.next(self.order) else {
+                break;
+            };
+
+            state.enter(&waiter.group);
+            grants.push((
+                waiter.tx,
+                Permit {
+                    queue: Some(self.clone()),
+                    group: waiter.group,
+                },
+            ));
+        }
+
+        grants
+    }
+
+    /// Sends permits to the tasks they were handed out to.
+    fn send(grants: Vec<(oneshot::Sender<Permit>, Permit)>) {
+        for (tx, permit) in grants {
+            // NOTE: if the task stopped waiting in the meantime, the permit is
+            // returned and dropped, which hands it to the next waiting task.
+            let _ = tx.send(permit);
+        }
+    }
+
+    /// Releases the capacity held by a task of a group.
+    fn release(self: &Arc<Self>, group: &Option<String>) {
+        let grants = {
+            let mut state = self.state.lock().unwrap();
+            state.leave(group);
+            self.grant(&mut state)
+        };
+
+        Self::send(grants);
+    }
+}
+
+/// Capacity of a backend held by a running task.
+///
+/// The capacity is released (and handed to the next waiting task) when the
+/// permit is dropped.
+#[derive(Debug)]
+pub(crate) struct Permit {
+    /// The queue that the permit was acquired from.
+    queue: Option<Arc<Queue>>,
+
+    /// The group of the task holding the permit.
+    group: Option<String>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if let Some(queue) = self.queue.take() {
+            queue.release(&self.group);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::FutureExt as _;
+
+    use super::*;
+
+    /// Acquires permits for a set of waiting tasks (given as priorities and
+    /// groups) from a full queue and returns the order they were started in.
+    fn order(order: QueueOrder, running: &[&str], tasks: &[(i32, &str)]) -> Vec<usize> {
+        let queue = Arc::new(Queue::new(running.len(), order));
+        let mut permits = running
+            .iter()
+            .map(|group| queue.acquire(0, Some(group)).now_or_never().unwrap())
+            .collect::<Vec<_>>();
+
+        let mut waiting = tasks
+            .iter()
+            .map(|(priority, group)| Box::pin(queue.acquire(*priority, Some(group))))
+            .collect::<Vec<_>>();
+
+        // NOTE: tasks enter the queue when they are first polled.
+        for future in &mut waiting {
+            assert!(future.as_mut().now_or_never().is_none());
+        }
+
+        assert_eq!(queue.stats().queued(), tasks.len());
+
+        let mut started = Vec::new();
+
+        while started.len() < tasks.len() {
+            // NOTE: the first permit is released, so exactly one waiting task
+            // is started at a time.
+            permits.remove(0);
+
+            let (index, permit) = waiting
+                .iter_mut()
+                .enumerate()
+                .filter(|(index, _)| !started.contains(index))
+                .find_map(|(index, future)| Some((index, future.as_mut().now_or_never()?)))
+                .unwrap();
+
+            started.push(index);
+            permits.push(permit);
+        }
+
+        started
+    }
+
+    #[test]
+    fn tasks_are_started_in_order() {
+        let tasks = [(0, "a"), (5, "a"), (0, "b"), (5, "b"), (1, "c")];
+
+        assert_eq!(order(QueueOrder::Fifo, &["a"], &tasks), [0, 1, 2, 3, 4]);
+        assert_eq!(order(QueueOrder::Priority, &["a"], &tasks), [1, 3, 4, 0, 2]);
+
+        // Group `a` already has running tasks, so group `b` goes first.
+        assert_eq!(
+            order(QueueOrder::FairShare, &["a", "a"], &tasks),
+            [3, 1, 4, 0, 2]
+        );
+    }
+
+    #[test]
+    fn canceled_waiters_are_skipped() {
+        let queue = Arc::new(Queue::new(1, QueueOrder::Fifo));
+        let permit = queue.acquire(0, None).now_or_never().unwrap();
+
+        let mut canceled = Box::pin(queue.acquire(0, None));
+        let mut waiting = Box::pin(queue.acquire(0, None));
+        assert!(canceled.as_mut().now_or_never().is_none());
+        assert!(waiting.as_mut().now_or_never().is_none());
+        assert_eq!(queue.stats().queued(), 2);
+
+        drop(canceled);
+        assert_eq!(queue.stats().queued(), 1);
+
+        drop(permit);
+        let permit = waiting.now_or_never().unwrap();
+        assert_eq!(queue.stats().running(), 1);
+
+        queue.finished();
+        drop(permit);
+        assert_eq!(
+            queue.stats(),
+            QueueStats {
+                queued: 0,
+                running: 0,
+                finished: 1,
+            }
+        );
+    }
+}
@@ -0,0 +1,122 @@
+//! Retrying of tasks according to their [retry policies](RetryPolicy).
+
+use std::time::Duration;
+
+use crankshaft_config::backend::retry::DEFAULT_BACKOFF;
+use crankshaft_config::backend::retry::DEFAULT_MAX_BACKOFF;
+
+use crate::service::runner::backend::FailureReason;
+use crate::service::runner::backend::TaskResult;
+use crate::task::RetryPolicy;
+
+/// Gets the reason an attempt of a task is retried under a policy (if it is
+/// retried at all).
+pub(crate) fn reason(
+    policy: &RetryPolicy,
+    result: &std::result::Result<TaskResult, FailureReason>,
+) -> Option<String> {
+    match result {
+        Ok(result) if !result.is_success() && policy.on_failure() => {
+            Some(String::from("an execution exited unsuccessfully"))
+        }
+        Ok(_) => None,
+        Err(reason @ FailureReason::Preempted(_)) if policy.on_preemption() => {
+            Some(reason.to_string())
+        }
+        // NOTE: these failures would recur with every attempt.
+        Err(
+            FailureReason::Preempted(_)
+            | FailureReason::Panicked(_)
+            | FailureReason::Rejected(_)
+            | FailureReason::DependencyFailed(_)
+            | FailureReason::ExceedsLimits(_)
+            | FailureReason::Unsupported(_),
+        ) => None,
+        Err(reason) if policy.on_failure() => Some(reason.to_string()),
+        Err(_) => None,
+    }
+}
+
+/// Gets the delay before a retry of a task under a policy (where the first
+/// retry is retry one).
+pub(crate) fn backoff(policy: &RetryPolicy, retry: u32) -> Duration {
+    let initial = policy.backoff().unwrap_or(DEFAULT_BACKOFF);
+    let max = policy.max_backoff().unwrap_or(DEFAULT_MAX_BACKOFF);
+    let factor = 1u64
+        .checked_shl(retry.saturating_sub(1))
+        .unwrap_or(u64::MAX);
+
+    Duration::from_secs(initial.saturating_mul(factor).min(max))
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(unix)]
+    use std::os::unix::process::ExitStatusExt as _;
+    #[cfg(windows)]
+    use std::os::windows::process::ExitStatusExt as _;
+    use std::process::ExitStatus;
+    use std::process::Output;
+
+    use nonempty::NonEmpty;
+
+    use super::*;
+
+    /// Gets the result of a task with a single execution that exited with the
+    /// provided exit code.
+    fn result(code: i32) -> std::result::Result<TaskResult, FailureReason> {
+        #[cfg(unix)]
+        let status = ExitStatus::from_raw(code << 8);
+        #[cfg(windows)]
+        let status = ExitStatus::from_raw(code as u32);
+
+        Ok(TaskResult {
+            executions: NonEmpty::new(Output {
+                status,
+                stdout: Vec::new(),
+                stderr: Vec::new(),
+            }),
+            provenance: Default::default(),
+            accounting: Default::default(),
+        })
+    }
+
+    #[test]
+    fn attempts_are_retried_according_to_the_policy() {
+        let preempted = Err(FailureReason::Preempted(String::from(
+            "job `1` was preempted",
+        )));
+        let lost = Err(FailureReason::ContainerLost(String::from(
+            "daemon restarted",
+        )));
+        let unsupported = Err(FailureReason::Unsupported(String::from("inputs")));
+
+        let policy = RetryPolicy::default();
+        assert_eq!(
+            reason(&policy, &preempted).as_deref(),
+            Some("task was preempted: job `1` was preempted")
+        );
+        assert_eq!(reason(&policy, &lost), None);
+        assert_eq!(reason(&policy, &result(1)), None);
+
+        let policy = RetryPolicy::builder()
+            .on_preemption(false)
+            .on_failure(true)
+            .build();
+        assert_eq!(reason(&policy, &preempted), None);
+        assert!(reason(&policy, &lost).is_some());
+        assert!(reason(&policy, &result(1)).is_some());
+        assert_eq!(reason(&policy, &result(0)), None);
+        assert_eq!(reason(&policy, &unsupported), None);
+    }
+
+    #[test]
+    fn backoffs_double_up_to_the_maximum() {
+        let policy = RetryPolicy::builder().backoff(5).max_backoff(30).build();
+        let backoffs = (1..=5)
+            .map(|retry| backoff(&policy, retry).as_secs())
+            .collect::<Vec<_>>();
+        assert_eq!(backoffs, [5, 10, 20, 30, 30]);
+        assert_eq!(backoff(&policy, 100).as_secs(), 30);
+    }
+}
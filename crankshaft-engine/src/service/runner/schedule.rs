@@ -0,0 +1,80 @@
+//! Waiting on the [schedules](Schedule) of backends.
+//!
+//! Schedules are evaluated in the local time of the engine. Tasks submitted
+//! to a backend whose schedule does not currently accept submissions are
+//! queued within the engine (before they acquire a permit from the backend)
+//! until the schedule opens.
+
+use std::time::Duration;
+
+use chrono::DateTime;
+use chrono::Datelike as _;
+use chrono::Local;
+use chrono::TimeZone;
+use chrono::Timelike as _;
+use crankshaft_config::backend::Schedule;
+use crankshaft_config::backend::schedule::Day;
+
+/// Gets how long to wait from a point in time until a schedule accepts
+/// submissions.
+///
+/// Returns [`Duration::ZERO`] if the schedule accepts submissions at that
+/// time and [`None`] if it never accepts submissions.
+fn delay_at<Tz: TimeZone>(schedule: &Schedule, now: &DateTime<Tz>) -> Option<Duration> {
+    let day = Day::ALL[now.weekday().num_days_from_monday() as usize];
+    let minute = (now.hour() * 60 + now.minute()) as u16;
+
+    schedule
+        .minutes_until_open(day, minute)
+        .map(|minutes| match minutes {
+            0 => Duration::ZERO,
+            // NOTE: the wait ends at the start of the minute the schedule
+            // opens.
+            minutes => Duration::from_secs(minutes as u64 * 60 - now.second() as u64),
+        })
+}
+
+/// Gets how long to wait from now until a schedule accepts submissions.
+///
+/// Returns [`Duration::ZERO`] if the schedule currently accepts submissions
+/// and [`None`] if it never accepts submissions.
+pub(crate) fn delay(schedule: &Schedule) -> Option<Duration> {
+    if schedule.is_always_open() {
+        return Some(Duration::ZERO);
+    }
+
+    delay_at(schedule, &Local::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use crankshaft_config::backend::schedule::TimeOfDay;
+    use crankshaft_config::backend::schedule::Window;
+
+    use super::*;
+
+    #[test]
+    fn delays_are_computed_from_the_time() {
+        let schedule = Schedule::new(
+            [Window::new(
+                [Day::Monday],
+                TimeOfDay::new(8, 0).unwrap(),
+                TimeOfDay::new(18, 0).unwrap(),
+            )],
+            [],
+        );
+
+        // NOTE: 2024-03-04 is a Monday.
+        let now = Utc.with_ymd_and_hms(2024, 3, 4, 7, 30, 15).unwrap();
+        assert_eq!(
+            delay_at(&schedule, &now),
+            Some(Duration::from_secs(29 * 60 + 45))
+        );
+
+        let now = Utc.with_ymd_and_hms(2024, 3, 4, 9, 0, 0).unwrap();
+        assert_eq!(delay_at(&schedule, &now), Some(Duration::ZERO));
+
+        assert_eq!(delay(&Schedule::default()), Some(Duration::ZERO));
+    }
+}
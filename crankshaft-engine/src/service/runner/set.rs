@@ -0,0 +1,281 @@
+//! Named sets of tasks.
+//!
+//! A [`TaskSet`] groups the handles of arbitrary tasks (across backends) so
+//! that they can be waited on, canceled, and observed together rather than
+//! each caller keeping its own bookkeeping over a collection of
+//! [`TaskHandle`]s.
+
+use std::sync::Arc;
+
+use futures::future::join_all;
+use futures::future::select_all;
+use tokio::sync::watch;
+use tracing::debug;
+
+use crate::events::TaskId;
+use crate::service::runner::TaskHandle;
+use crate::service::runner::backend::TaskResult;
+
+/// The progress of the tasks within a [`TaskSet`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// The number of tasks inserted into the set.
+    total: usize,
+
+    /// The number of tasks that completed successfully.
+    succeeded: usize,
+
+    /// The number of tasks that completed unsuccessfully (or that their
+    /// backend failed to run).
+    failed: usize,
+
+    /// The number of tasks that were canceled.
+    canceled: usize,
+}
+
+impl Progress {
+    /// Gets the number of tasks inserted into the set.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Gets the number of tasks that completed successfully.
+    pub fn succeeded(&self) -> usize {
+        self.succeeded
+    }
+
+    /// Gets the number of tasks that completed unsuccessfully (or that their
+    /// backend failed to run).
+    pub fn failed(&self) -> usize {
+        self.failed
+    }
+
+    /// Gets the number of tasks that were canceled.
+    pub fn canceled(&self) -> usize {
+        self.canceled
+    }
+
+    /// Gets the number of tasks that have finished (in any way).
+    pub fn finished(&self) -> usize {
+        self.succeeded + self.failed + self.canceled
+    }
+
+    /// Gets whether or not every task within the set has finished.
+    pub fn is_done(&self) -> bool {
+        self.finished() == self.total
+    }
+}
+
+/// A named set of tasks.
+///
+/// Tasks remain within the set until they are waited on (see
+/// [`TaskSet::wait_any()`] and [`TaskSet::wait_all()`]). As the set holds the
+/// handles of its tasks, tasks are canceled once the set is dropped (unless
+/// they were [detached](TaskHandle::detach)).
+#[derive(Debug)]
+pub struct TaskSet {
+    /// The name of the set.
+    name: String,
+
+    /// The handles of the tasks within the set that have not been waited on.
+    handles: Vec<TaskHandle>,
+
+    /// The progress of the tasks within the set.
+    progress: Arc<watch::Sender<Progress>>,
+}
+
+impl TaskSet {
+    /// Creates a new, empty [`TaskSet`].
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            handles: Default::default(),
+            progress: Arc::new(watch::Sender::new(Progress::default())),
+        }
+    }
+
+    /// Gets the name of the set.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the number of tasks within the set that have not been waited on.
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    /// Gets whether or not every task within the set has been waited on.
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    /// Gets the ids of the tasks within the set that have not been waited on.
+    pub fn ids(&self) -> impl Iterator<Item = TaskId> + '_ {
+        self.handles.iter().map(TaskHandle::id)
+    }
+
+    /// Inserts a task into the set.
+    ///
+    /// # Panics
+    ///
+    /// If this is not called from within a [`tokio`] runtime.
+    pub fn insert(&mut self, handle: TaskHandle) {
+        let rx = handle.callbacks.lock().unwrap().subscribe();
+        let token = handle.token.clone();
+        let progress = self.progress.clone();
+
+        progress.send_modify(|progress| progress.total += 1);
+
+        // NOTE: the progress is tracked separately from the handle's own
+        // callback so that it is reported regardless of whether (or when) the
+        // task is waited on.
+        tokio::spawn(async move {
+            let result = rx.await.ok();
+
+            progress.send_modify(|progress| match result {
+                Some(result) if result.is_success() => progress.succeeded += 1,
+                Some(_) => progress.failed += 1,
+                None if token.is_cancelled() => progress.canceled += 1,
+                None => progress.failed += 1,
+            });
+        });
+
+        self.handles.push(handle);
+    }
+
+    /// Gets the current progress of the tasks within the set.
+    pub fn progress(&self) -> Progress {
+        *self.progress.borrow()
+    }
+
+    /// Subscribes to the progress of the tasks within the set.
+    ///
+    /// The receiver is notified every time a task is inserted into the set or
+    /// finishes.
+    pub fn subscribe(&self) -> watch::Receiver<Progress> {
+        self.progress.subscribe()
+    }
+
+    /// Cancels every task within the set that has not been waited on.
+    pub fn cancel(&self) {
+        debug!(
+            "canceling {} tasks within task set `{}`",
+            self.handles.len(),
+            self.name
+        );
+
+        for handle in &self.handles {
+            handle.cancel();
+        }
+    }
+
+    /// Waits for any task within the set to finish and removes it from the
+    /// set.
+    ///
+    /// Returns the id of the task along with its result (or [`None`] if the
+    /// task was canceled or its backend failed to run it). If the set is
+    /// empty, [`None`] is returned immediately.
+    pub async fn wait_any(&mut self) -> Option<(TaskId, Option<TaskResult>)> {
+        if self.handles.is_empty() {
+            return None;
+        }
+
+        let (result, index, _) =
+            select_all(self.handles.iter_mut().map(|handle| &mut handle.callback)).await;
+        let handle = self.handles.remove(index);
+
+        Some((handle.id(), result.ok()))
+    }
+
+    /// Waits for every task within the set to finish.
+    ///
+    /// Returns the id of each task along with its result (or [`None`] if the
+    /// task was canceled or its backend failed to run it) in the order the
+    /// tasks were inserted (less any that were already waited on).
+    pub async fn wait_all(self) -> Vec<(TaskId, Option<TaskResult>)> {
+        join_all(
+            self.handles
+                .into_iter()
+                .map(|handle| async move { (handle.id(), handle.callback.await.ok()) }),
+        )
+        .await
+    }
+}
+
+impl Extend<TaskHandle> for TaskSet {
+    fn extend<T: IntoIterator<Item = TaskHandle>>(&mut self, iter: T) {
+        for handle in iter {
+            self.insert(handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::Engine;
+    use crate::Task;
+    use crate::simulation::MockBackend;
+    use crate::simulation::runtime;
+    use crate::task::Execution;
+
+    #[test]
+    fn tasks_are_waited_on_and_tracked() {
+        runtime().unwrap().block_on(async {
+            let backend = MockBackend::new(|task, _| match task.name() {
+                Some("fast") => (Duration::from_secs(1), 0),
+                Some("fail") => (Duration::from_secs(5), 1),
+                _ => (Duration::from_secs(60), 0),
+            });
+
+            let engine = Engine::default().with_backend("mock", Arc::new(backend), 10);
+
+            let task = |name: &str| {
+                Task::builder()
+                    .name(name)
+                    .extend_executions([Execution::builder()
+                        .image("ubuntu")
+                        .args(["echo", "hello"])
+                        .try_build()
+                        .unwrap()])
+                    .try_build()
+                    .unwrap()
+            };
+
+            let mut set = TaskSet::new("test");
+            set.extend(
+                ["slow", "fail", "fast"]
+                    .into_iter()
+                    .map(|name| engine.submit("mock", task(name))),
+            );
+
+            let fast = set.ids().last().unwrap();
+            let mut progress = set.subscribe();
+            assert_eq!(set.progress().total(), 3);
+
+            tokio::spawn(engine.run());
+
+            let (id, result) = set.wait_any().await.unwrap();
+            assert_eq!(id, fast);
+            assert!(result.unwrap().is_success());
+            assert_eq!(set.len(), 2);
+
+            // NOTE: the slow task is canceled once the failing task finishes.
+            progress
+                .wait_for(|progress| progress.failed() == 1)
+                .await
+                .unwrap();
+            set.cancel();
+
+            let results = set.wait_all().await;
+            assert_eq!(results.len(), 2);
+
+            let progress = progress.wait_for(Progress::is_done).await.unwrap();
+            assert_eq!(progress.succeeded(), 1);
+            assert_eq!(progress.failed(), 1);
+            assert_eq!(progress.canceled(), 1);
+        });
+    }
+}
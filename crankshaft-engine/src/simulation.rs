@@ -0,0 +1,113 @@
+//! Deterministic simulation of the engine.
+//!
+//! Simulations run the engine with [mock backends](MockBackend) on a
+//! [`tokio`] runtime whose clock is paused. Whenever every task is waiting on
+//! a timer, the clock automatically advances to the next timer, so the
+//! simulated durations of executions (and any other timers within the engine)
+//! elapse instantly and deterministically. This allows the behavior of the
+//! engine with thousands of long-running tasks to be tested in milliseconds.
+//!
+//! This module is only available with the `simulation` feature enabled.
+
+use std::io::Result;
+
+pub use crate::service::runner::backend::mock::Backend as MockBackend;
+
+/// Creates a new runtime for a simulation.
+///
+/// The runtime is single-threaded and starts with its clock paused.
+pub fn runtime() -> Result<tokio::runtime::Runtime> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .start_paused(true)
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+
+    use super::*;
+    use crate::Engine;
+    use crate::Task;
+    use crate::events::Event;
+    use crate::service::report::Outcome;
+    use crate::task::Execution;
+
+    #[test]
+    fn simulated_tasks_run_in_virtual_time() {
+        runtime().unwrap().block_on(async {
+            let backend = MockBackend::new(|task, _| match task.name() {
+                Some("fail") => (Duration::from_secs(600), 1),
+                _ => (Duration::from_secs(600), 0),
+            });
+
+            let engine = Engine::default().with_backend("mock", Arc::new(backend), 100);
+            let metrics = engine.metrics();
+
+            let execution = Execution::builder()
+                .image("ubuntu")
+                .args(["echo", "hello"])
+                .try_build()
+                .unwrap();
+
+            for i in 0..1000 {
+                let name = if i % 10 == 0 { "fail" } else { "succeed" };
+                let task = Task::builder()
+                    .name(name)
+                    .extend_executions([execution.clone()])
+                    .try_build()
+                    .unwrap();
+
+                engine.submit("mock", task).detach();
+            }
+
+            let start = Instant::now();
+            let report = engine.run().await;
+
+            // NOTE: 1,000 tasks of 10 minutes each with 100 running at a time
+            // take exactly 100 minutes of virtual time.
+            assert_eq!(start.elapsed(), Duration::from_secs(100 * 60));
+            assert_eq!(report.elapsed(), Duration::from_secs(100 * 60));
+            assert_eq!(report.total_duration(), Duration::from_secs(1000 * 600));
+
+            let backend = report.backend("mock").unwrap();
+            assert_eq!(backend.count(Outcome::Succeeded), 900);
+            assert_eq!(backend.count(Outcome::Failed), 100);
+            assert_eq!(backend.max_duration(), Duration::from_secs(600));
+            assert!(
+                backend.tasks().iter().all(|task| {
+                    (task.outcome() == Outcome::Failed) == task.failure().is_some()
+                })
+            );
+
+            let snapshot = metrics.global();
+            assert_eq!(snapshot.completed(), 1000);
+            assert_eq!(snapshot.failed(), 100);
+        });
+    }
+
+    #[test]
+    fn backends_report_initialization() {
+        runtime().unwrap().block_on(async {
+            let engine = Engine::default();
+            let mut events = engine.subscribe();
+
+            let backend = MockBackend::constant(Duration::from_secs(1), 0);
+            let _engine = engine.with_backend("local", Arc::new(backend), 4);
+
+            assert_eq!(
+                events.recv().await.unwrap(),
+                Event::BackendInitialized {
+                    name: String::from("local"),
+                    kind: String::from("mock"),
+                    capacity: 4,
+                    reports_resource_usage: false,
+                }
+            );
+        });
+    }
+}
@@ -0,0 +1,359 @@
+//! Staging of task inputs and outputs through the local filesystem.
+//!
+//! Backends need the contents of each input to exist as a local file before
+//! the task starts, either because their executions run directly on a host or
+//! so that the file can be streamed into the containers of the task. A
+//! [`Stager`] resolves the contents of each input—literals along with `file://`, `http(s)://`,
+//! `s3://`, and `gs://` URLs—through a set of [`Fetchers`] and writes them to
+//! local paths, verifying the [checksum](Input::checksum) of each input that
+//! has one.
+//!
+//! Remote contents are stored within the [`Cache`] of the fetchers (if one is
+//! configured), so an input shared between tasks is only downloaded once
+//! (for as long as its remote contents are unchanged).
+//!
+//! Symmetrically, once the executions of a task have finished, its outputs are
+//! uploaded from local paths to their URLs (`file://`, `http(s)://`, `s3://`,
+//! and `gs://`) through a set of [`Uploaders`].
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use futures::future::try_join_all;
+use tracing::debug;
+use url::Url;
+
+use crate::events::TaskEvents;
+use crate::task::Input;
+use crate::task::Output;
+use crate::task::input::Fetchers;
+use crate::task::input::Type;
+use crate::task::input::fetcher;
+#[cfg(doc)]
+use crate::task::input::fetcher::Cache;
+use crate::task::input::fetcher::Http;
+use crate::task::output::Uploaders;
+use crate::task::output::uploader;
+
+/// An error related to staging an input.
+#[derive(Debug)]
+pub enum Error {
+    /// The contents of an input could not be fetched.
+    Fetch(fetcher::Error),
+
+    /// The contents of an output could not be uploaded.
+    Upload(uploader::Error),
+
+    /// An i/o error.
+    Io(std::io::Error),
+
+    /// The input is a directory, which cannot be staged.
+    Directory(String),
+
+    /// The contents of an input did not match its checksum.
+    ChecksumMismatch {
+        /// The path of the input.
+        path: String,
+
+        /// The expected digest of the contents.
+        expected: String,
+
+        /// The actual digest of the contents.
+        actual: String,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Fetch(err) => write!(f, "fetch error: {err}"),
+            Error::Upload(err) => write!(f, "upload error: {err}"),
+            Error::Io(err) => write!(f, "i/o error: {err}"),
+            Error::Directory(path) => {
+                write!(f, "input `{path}` is a directory, which cannot be staged")
+            }
+            Error::ChecksumMismatch {
+                path,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "contents of input `{path}` have a digest of `{actual}` (expected `{expected}`)"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A [`Result`](std::result::Result) with an [`Error`].
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Verifies the digest of the contents of an input against its checksum (if
+/// it has one).
+pub(crate) fn verify(input: &Input, digest: &str) -> Result<()> {
+    match input.checksum() {
+        Some(expected) if !expected.eq_ignore_ascii_case(digest) => Err(Error::ChecksumMismatch {
+            path: input.path().to_owned(),
+            expected: expected.to_owned(),
+            actual: digest.to_owned(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// An input that was staged onto the local filesystem.
+#[derive(Clone, Debug)]
+pub struct Staged {
+    /// The local path of the contents.
+    path: PathBuf,
+
+    /// The hex-encoded SHA-256 digest of the contents.
+    digest: String,
+
+    /// The size of the contents (in bytes).
+    size: u64,
+}
+
+impl Staged {
+    /// Gets the local path of the contents.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Gets the hex-encoded SHA-256 digest of the contents.
+    pub fn digest(&self) -> &str {
+        &self.digest
+    }
+
+    /// Gets the size of the contents (in bytes).
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+/// Stages task inputs onto the local filesystem and uploads task outputs from
+/// it.
+///
+/// By default, the [`File`](fetcher::File) and [`Http`] fetchers along with the
+/// [`File`](uploader::File) and [`Http`](uploader::Http) uploaders are
+/// registered.
+#[derive(Clone, Debug)]
+pub struct Stager {
+    /// The fetchers used to retrieve the contents of inputs.
+    fetchers: Fetchers,
+
+    /// The uploaders used to store the contents of outputs.
+    uploaders: Uploaders,
+}
+
+impl Default for Stager {
+    fn default() -> Self {
+        Self::new(Fetchers::default().with(Http::default()))
+            .with_uploaders(Uploaders::default().with(uploader::Http::default()))
+    }
+}
+
+impl Stager {
+    /// Creates a new [`Stager`] that retrieves contents using the provided
+    /// fetchers.
+    ///
+    /// Only the [`File`](uploader::File) uploader is registered.
+    pub fn new(fetchers: Fetchers) -> Self {
+        Self {
+            fetchers,
+            uploaders: Uploaders::default(),
+        }
+    }
+
+    /// Sets the uploaders used to store the contents of outputs.
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous uploaders.
+    pub fn with_uploaders(mut self, uploaders: Uploaders) -> Self {
+        self.uploaders = uploaders;
+        self
+    }
+
+    /// Gets the fetchers used to retrieve the contents of inputs.
+    pub fn fetchers(&self) -> &Fetchers {
+        &self.fetchers
+    }
+
+    /// Gets the uploaders used to store the contents of outputs.
+    pub fn uploaders(&self) -> &Uploaders {
+        &self.uploaders
+    }
+
+    /// Stages the contents of an input at a local path.
+    ///
+    /// Any missing parent directories of the path are created. The contents
+    /// only appear at the path once they have been fully fetched (and their
+    /// checksum verified).
+    pub async fn stage(&self, input: &Input, path: impl AsRef<Path>) -> Result<Staged> {
+        let path = path.as_ref();
+
+        if let Type::Directory = input.r#type() {
+            return Err(Error::Directory(input.path().to_owned()));
+        }
+
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+
+        tokio::fs::create_dir_all(parent).await.map_err(Error::Io)?;
+
+        // NOTE: contents are first written to a temporary file next to the
+        // path so that partially fetched (or mismatched) contents never
+        // appear at the path.
+        let (file, temp) = tempfile::NamedTempFile::new_in(parent)
+            .map_err(Error::Io)?
+            .into_parts();
+        let mut file = tokio::fs::File::from_std(file);
+
+        let (size, digest) = self
+            .fetchers
+            .fetch_with_digest(input.contents(), &mut file)
+            .await
+            .map_err(Error::Fetch)?;
+
+        verify(input, &digest)?;
+        temp.persist(path).map_err(|err| Error::Io(err.error))?;
+
+        debug!("staged input `{}` at `{}`", input.path(), path.display());
+
+        Ok(Staged {
+            path: path.to_owned(),
+            digest,
+            size,
+        })
+    }
+
+    /// Stages the contents of all of the provided inputs concurrently.
+    ///
+    /// Each input is staged at its path (resolved relative to `root` if one is
+    /// provided). The staged inputs are returned in the same order as the
+    /// inputs.
+    pub async fn stage_all<'a>(
+        &self,
+        inputs: impl IntoIterator<Item = &'a Input>,
+        root: Option<&Path>,
+    ) -> Result<Vec<Staged>> {
+        try_join_all(inputs.into_iter().map(|input| {
+            let path = match root {
+                Some(root) => root.join(input.path().trim_start_matches('/')),
+                None => PathBuf::from(input.path()),
+            };
+
+            async move { self.stage(input, path).await }
+        }))
+        .await
+    }
+
+    /// Uploads the outputs of a task to their URLs.
+    ///
+    /// Each output is uploaded from the provided local path (where the file
+    /// or directory of the output exists). A
+    /// [`TaskOutputUploaded`](crate::events::Event::TaskOutputUploaded) event
+    /// is sent as each output finishes uploading.
+    pub async fn upload_all<'a>(
+        &self,
+        outputs: impl IntoIterator<Item = (&'a Output, PathBuf)>,
+        events: &TaskEvents,
+    ) -> Result<()> {
+        let (outputs, uploads): (Vec<_>, Vec<_>) = outputs
+            .into_iter()
+            .map(|(output, path)| {
+                // SAFETY: the URL of an output is always parsed from a valid
+                // URL when the output is built.
+                let url = Url::parse(output.url()).expect("output URLs should be valid");
+                (output, (path, url))
+            })
+            .unzip();
+
+        self.uploaders
+            .upload_all(uploads, |index, url, size| {
+                events.output_uploaded(outputs[index].path(), url.as_str(), size)
+            })
+            .await
+            .map_err(Error::Upload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::input::Contents;
+
+    /// Builds a file input with literal contents.
+    fn literal(contents: &str, path: &str, checksum: Option<&str>) -> Input {
+        let mut builder = Input::builder()
+            .contents(Contents::Literal(contents.to_owned()))
+            .path(path)
+            .r#type(Type::File);
+
+        if let Some(checksum) = checksum {
+            builder = builder.checksum(checksum);
+        }
+
+        builder.try_build().unwrap()
+    }
+
+    #[tokio::test]
+    async fn inputs_are_staged_under_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source.txt");
+        std::fs::write(&source, "from a file").unwrap();
+
+        let inputs = [
+            literal(
+                "hello, world!",
+                "/inputs/greeting.txt",
+                // NOTE: the SHA-256 digest of `hello, world!`.
+                Some("68E656B251E67E8358BEF8483AB0D51C6619F3E7A1A9F0E75838D41FF368F728"),
+            ),
+            Input::builder()
+                .contents(source)
+                .path("/inputs/nested/copy.txt")
+                .r#type(Type::File)
+                .try_build()
+                .unwrap(),
+        ];
+
+        let staged = Stager::default()
+            .stage_all(&inputs, Some(dir.path()))
+            .await
+            .unwrap();
+
+        assert_eq!(staged[0].path(), dir.path().join("inputs/greeting.txt"));
+        assert_eq!(staged[0].size(), 13);
+        assert_eq!(
+            std::fs::read_to_string(staged[1].path()).unwrap(),
+            "from a file"
+        );
+    }
+
+    #[tokio::test]
+    async fn mismatched_contents_are_not_staged() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("greeting.txt");
+
+        let input = literal("hello, world!", "/greeting.txt", Some("abc"));
+        let err = Stager::default().stage(&input, &path).await.unwrap_err();
+        assert!(matches!(err, Error::ChecksumMismatch { expected, .. } if expected == "abc"));
+
+        // Neither the contents nor a temporary file remain.
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        let input = Input::builder()
+            .contents(Contents::Literal(String::new()))
+            .path("/dir")
+            .r#type(Type::Directory)
+            .try_build()
+            .unwrap();
+        let err = Stager::default().stage(&input, &path).await.unwrap_err();
+        assert!(matches!(err, Error::Directory(path) if path == "/dir"));
+    }
+}
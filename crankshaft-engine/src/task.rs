@@ -1,5 +1,9 @@
 //! Tasks that can be run by execution runners.
 
+use std::path::Path;
+
+pub use crankshaft_config::backend::RetryPolicy;
+use futures::future::join_all;
 use nonempty::NonEmpty;
 
 mod builder;
@@ -7,6 +11,7 @@ pub mod execution;
 pub mod input;
 pub mod output;
 pub mod resources;
+pub mod spec;
 
 pub use builder::Builder;
 pub use execution::Execution;
@@ -38,6 +43,22 @@ pub struct Task {
 
     /// The list of volumes shared across executions in the task.
     shared_volumes: Option<NonEmpty<String>>,
+
+    /// An optional key identifying duplicate submissions of the task.
+    idempotency_key: Option<String>,
+
+    /// An optional command that checkpoints the task before it is preempted.
+    checkpoint_command: Option<NonEmpty<String>>,
+
+    /// An optional policy for retrying the task.
+    retry_policy: Option<RetryPolicy>,
+
+    /// The priority of the task within the queue of its backend.
+    priority: i32,
+
+    /// An optional group that the task is accounted to within the queue of
+    /// its backend.
+    group: Option<String>,
 }
 
 impl Task {
@@ -46,6 +67,24 @@ impl Task {
         Builder::default()
     }
 
+    /// Reads a task from a [specification](spec) file.
+    ///
+    /// The format of the file is determined by its extension (`.toml`,
+    /// `.yaml`, or `.yml`). Relative paths on the host within the
+    /// specification (e.g., of environment files) are resolved against the
+    /// directory of the file.
+    pub fn from_path(path: impl AsRef<Path>) -> spec::Result<Self> {
+        spec::read(path.as_ref())
+    }
+
+    /// Parses a task from a [specification](spec) in the provided format.
+    ///
+    /// Relative paths on the host within the specification are resolved
+    /// against the current directory.
+    pub fn from_str(s: &str, format: spec::Format) -> spec::Result<Self> {
+        spec::parse(s, format, None)
+    }
+
     /// Gets the name of the task (if it exists).
     pub fn name(&self) -> Option<&str> {
         self.name.as_deref()
@@ -81,10 +120,105 @@ impl Task {
         self.executions.iter()
     }
 
+    /// Sets environment variables for every execution within this task,
+    /// overwriting any existing variables with the same names.
+    pub(crate) fn extend_env<'a>(&mut self, variables: &[(&'a str, &'a str)]) {
+        for execution in self.executions.iter_mut() {
+            execution.extend_env(variables.iter().copied());
+        }
+    }
+
     /// Gets the shared volumes across executions within this task.
     pub fn shared_volumes(&self) -> Option<impl Iterator<Item = &str>> {
         self.shared_volumes
             .as_ref()
             .map(|volumes| volumes.iter().map(|a| a.as_str()))
     }
+
+    /// Gets the idempotency key of the task (if it exists).
+    ///
+    /// When a task with an idempotency key is submitted to a backend of an
+    /// engine that has already seen a task with the same key, the handle of
+    /// the existing task is returned instead of the task being run again (see
+    /// [`Engine::submit()`](crate::Engine::submit)).
+    pub fn idempotency_key(&self) -> Option<&str> {
+        self.idempotency_key.as_deref()
+    }
+
+    /// Gets whether or not the task may be preempted (as requested within its
+    /// [resources](Resources::preemptible)).
+    ///
+    /// Backends use this to place the task on preemptible capacity (e.g., spot
+    /// instances or preemptable queues).
+    pub fn is_preemptible(&self) -> bool {
+        self.resources
+            .as_ref()
+            .and_then(Resources::preemptible)
+            .unwrap_or(false)
+    }
+
+    /// Gets the command that checkpoints the task before it is preempted (if
+    /// one exists).
+    ///
+    /// The command runs within the same environment as the execution that is
+    /// being preempted (e.g., within its container). Backends that can observe
+    /// an impending preemption run the command before the kill deadline.
+    pub fn checkpoint_command(&self) -> Option<&NonEmpty<String>> {
+        self.checkpoint_command.as_ref()
+    }
+
+    /// Gets the policy for retrying the task (if one exists).
+    ///
+    /// If the task does not have a retry policy, the retry policy within the
+    /// defaults of its backend (if any) applies.
+    pub fn retry_policy(&self) -> Option<&RetryPolicy> {
+        self.retry_policy.as_ref()
+    }
+
+    /// Gets the priority of the task within the queue of its backend.
+    ///
+    /// Tasks with a higher priority are started first when the backend uses
+    /// [priority](crankshaft_config::backend::QueueOrder::Priority) (or
+    /// [fair-share](crankshaft_config::backend::QueueOrder::FairShare))
+    /// ordering. The default priority is zero.
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    /// Gets the group that the task is accounted to within the queue of its
+    /// backend (if it exists).
+    ///
+    /// When the backend uses
+    /// [fair-share](crankshaft_config::backend::QueueOrder::FairShare)
+    /// ordering, groups (e.g., users or workflows) receive a fair share of the
+    /// backend regardless of how many tasks each of them submitted.
+    pub fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
+    /// Checks the [preconditions](input::Preconditions) of the inputs of the
+    /// task.
+    ///
+    /// Every input is checked (concurrently), so all inputs that fail their
+    /// preconditions are reported rather than only the first.
+    pub async fn check_preconditions(&self) -> Result<(), Vec<input::precondition::Error>> {
+        let client = reqwest::Client::new();
+
+        let errors = join_all(
+            self.inputs()
+                .into_iter()
+                .flatten()
+                .map(|input| input::precondition::check(input, &client)),
+        )
+        .await
+        .into_iter()
+        .filter_map(Result::err)
+        .collect::<Vec<_>>();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
 }
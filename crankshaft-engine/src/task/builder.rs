@@ -7,6 +7,7 @@ use crate::task::Execution;
 use crate::task::Input;
 use crate::task::Output;
 use crate::task::Resources;
+use crate::task::RetryPolicy;
 
 /// An error related to a [`Builder`].
 #[derive(Debug)]
@@ -59,6 +60,22 @@ pub struct Builder {
 
     /// The list of volumes shared across executions in the task.
     shared_volumes: Option<NonEmpty<String>>,
+
+    /// An optional key identifying duplicate submissions of the task.
+    idempotency_key: Option<String>,
+
+    /// An optional command that checkpoints the task before it is preempted.
+    checkpoint_command: Option<NonEmpty<String>>,
+
+    /// An optional policy for retrying the task.
+    retry_policy: Option<RetryPolicy>,
+
+    /// The priority of the task within the queue of its backend.
+    priority: i32,
+
+    /// An optional group that the task is accounted to within the queue of
+    /// its backend.
+    group: Option<String>,
 }
 
 impl Builder {
@@ -84,6 +101,17 @@ impl Builder {
         self
     }
 
+    /// Adds an idempotency key to the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous idempotency key declarations
+    /// provided to the builder.
+    pub fn idempotency_key<S: Into<String>>(mut self, key: S) -> Self {
+        self.idempotency_key = Some(key.into());
+        self
+    }
+
     /// Extends the set of inputs within the [`Builder`].
     pub fn extend_inputs<Iter>(mut self, inputs: Iter) -> Self
     where
@@ -195,6 +223,57 @@ impl Builder {
         self
     }
 
+    /// Adds a command that checkpoints the task before it is preempted to the
+    /// [`Builder`].
+    ///
+    /// See [`Task::checkpoint_command()`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous checkpoint commands provided
+    /// to the builder (and an empty command removes the checkpoint command).
+    pub fn checkpoint_command(mut self, args: impl IntoIterator<Item: Into<String>>) -> Self {
+        self.checkpoint_command = NonEmpty::collect(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Adds a policy for retrying the task to the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous retry policies provided to
+    /// the builder.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Adds a priority to the [`Builder`].
+    ///
+    /// See [`Task::priority()`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous priorities provided to the
+    /// builder.
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Adds a group to the [`Builder`].
+    ///
+    /// See [`Task::group()`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous groups provided to the
+    /// builder.
+    pub fn group<S: Into<String>>(mut self, group: S) -> Self {
+        self.group = Some(group.into());
+        self
+    }
+
     /// Consumes `self` and attempts to return a built [`Task`].
     pub fn try_build(self) -> Result<Task> {
         let executors = self
@@ -210,6 +289,11 @@ impl Builder {
             resources: self.resources,
             executions: executors,
             shared_volumes: self.shared_volumes,
+            idempotency_key: self.idempotency_key,
+            checkpoint_command: self.checkpoint_command,
+            retry_policy: self.retry_policy,
+            priority: self.priority,
+            group: self.group,
         })
     }
 }
@@ -1,21 +1,55 @@
 //! A unit of executable work.
 
 mod builder;
+pub mod env_file;
+pub mod redirection;
 
+use std::collections::HashSet;
 use std::hash::RandomState;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::Mutex;
 
 pub use builder::Builder;
+pub use env_file::EnvFile;
 use indexmap::IndexMap;
 use nonempty::NonEmpty;
+pub use redirection::Redirection;
+
+/// The image names interned by [`intern()`].
+static IMAGES: LazyLock<Mutex<HashSet<Arc<str>>>> = LazyLock::new(Default::default);
+
+/// Interns an image name.
+///
+/// The same image is typically used by a large number of executions (e.g.,
+/// every task within a parameter sweep), so each distinct image name is only
+/// allocated once. The number of distinct images used within a process is
+/// expected to be small, so interned names are never freed.
+fn intern(image: String) -> Arc<str> {
+    let mut images = IMAGES.lock().unwrap();
+
+    match images.get(image.as_str()) {
+        Some(interned) => interned.clone(),
+        None => {
+            let interned = Arc::<str>::from(image);
+            images.insert(interned.clone());
+            interned
+        }
+    }
+}
 
 /// An execution.
+///
+/// Cloning an execution is cheap: the image, arguments, and environment
+/// variables are shared between clones (e.g., when the same execution is used
+/// within many tasks).
 #[derive(Clone, Debug)]
 pub struct Execution {
     /// The container image.
-    image: String,
+    image: Arc<str>,
 
     /// The command arguments to execute.
-    args: NonEmpty<String>,
+    args: Arc<NonEmpty<String>>,
 
     /// The working directory, if configured.
     workdir: Option<String>,
@@ -24,16 +58,17 @@ pub struct Execution {
     /// the standard input, if configured.
     stdin: Option<String>,
 
-    /// The path inside the container to a file where the contents of the
-    /// standard output stream will be written, if configured.
-    stdout: Option<String>,
+    /// Where the contents of the standard output stream are sent.
+    stdout: Redirection,
 
-    /// The path inside the container to a file where the contents of the
-    /// standard error stream will be written, if configured.
-    stderr: Option<String>,
+    /// Where the contents of the standard error stream are sent.
+    stderr: Redirection,
 
     /// A map of environment variables, if configured.
-    env: Option<IndexMap<String, String>>,
+    env: Option<Arc<IndexMap<String, String>>>,
+
+    /// The user (and, optionally, the group) to run as, if configured.
+    user: Option<String>,
 }
 
 impl Execution {
@@ -62,18 +97,106 @@ impl Execution {
         self.stdin.as_ref()
     }
 
-    /// The file to pipe the standard output stream to.
-    pub fn stdout(&self) -> Option<&String> {
-        self.stdout.as_ref()
+    /// Where the contents of the standard output stream are sent.
+    pub fn stdout(&self) -> &Redirection {
+        &self.stdout
     }
 
-    /// The file to pipe the standard error stream to.
-    pub fn stderr(&self) -> Option<&String> {
-        self.stderr.as_ref()
+    /// Where the contents of the standard error stream are sent.
+    pub fn stderr(&self) -> &Redirection {
+        &self.stderr
     }
 
     /// The environment variables for the execution.
     pub fn env(&self) -> Option<&IndexMap<String, String, RandomState>> {
-        self.env.as_ref()
+        self.env.as_deref()
+    }
+
+    /// The user (and, optionally, the group) to run as (e.g., `1000:1000`).
+    pub fn user(&self) -> Option<&str> {
+        self.user.as_deref()
+    }
+
+    /// Sets environment variables for the execution, overwriting any existing
+    /// variables with the same names.
+    pub(crate) fn extend_env<'a>(
+        &mut self,
+        variables: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) {
+        let env = Arc::make_mut(self.env.get_or_insert_default());
+        env.extend(
+            variables
+                .into_iter()
+                .map(|(name, value)| (name.to_owned(), value.to_owned())),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn executions_share_their_contents() {
+        let build = || {
+            Execution::builder()
+                .image("ubuntu:24.04")
+                .args(["echo", "hello"])
+                .env("FOO", "bar")
+                .try_build()
+                .unwrap()
+        };
+
+        let execution = build();
+        let clone = execution.clone();
+        assert!(Arc::ptr_eq(&execution.args, &clone.args));
+        assert!(Arc::ptr_eq(
+            execution.env.as_ref().unwrap(),
+            clone.env.as_ref().unwrap()
+        ));
+
+        // NOTE: images are shared even between separately built executions.
+        let other = build();
+        assert!(Arc::ptr_eq(&execution.image, &other.image));
+        assert!(!Arc::ptr_eq(&execution.args, &other.args));
+        assert_eq!(other.image(), "ubuntu:24.04");
+    }
+
+    #[test]
+    fn env_files_are_merged() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("first.env");
+        let second = dir.path().join("second.env");
+        std::fs::write(&first, "FOO=first\nBAR=first\nBAZ=first\n").unwrap();
+        std::fs::write(&second, "BAR=second\n").unwrap();
+
+        // NOTE: explicit variables win even when added before the files.
+        let execution = Execution::builder()
+            .image("ubuntu")
+            .args(["env"])
+            .env("BAZ", "explicit")
+            .env_file(&first)
+            .env_file(&second)
+            .try_build()
+            .unwrap();
+
+        let env = execution.env().unwrap();
+        assert_eq!(env["FOO"], "first");
+        assert_eq!(env["BAR"], "second");
+        assert_eq!(env["BAZ"], "explicit");
+
+        std::fs::write(&second, "BAR='second\n").unwrap();
+        let err = Execution::builder()
+            .image("ubuntu")
+            .args(["env"])
+            .env_file(&second)
+            .try_build()
+            .unwrap_err();
+
+        assert!(
+            err.to_string()
+                .ends_with("second.env` (line 1): unterminated single quote"),
+            "{err}"
+        );
     }
 }
@@ -1,15 +1,25 @@
 //! Builders for an [`Execution`].
 
+use std::path::PathBuf;
+use std::sync::Arc;
+
 use indexmap::IndexMap;
 use nonempty::NonEmpty;
 
 use crate::task::Execution;
+use crate::task::execution::EnvFile;
+use crate::task::execution::Redirection;
+use crate::task::execution::env_file;
+use crate::task::execution::intern;
 
 /// An error related to a [`Builder`].
 #[derive(Debug)]
 pub enum Error {
     /// A required value was missing for a builder field.
     Missing(&'static str),
+
+    /// An environment file could not be read or parsed.
+    EnvFile(env_file::Error),
 }
 
 impl std::fmt::Display for Error {
@@ -19,6 +29,7 @@ impl std::fmt::Display for Error {
                 f,
                 "missing required value for '{field}' in a task execution builder"
             ),
+            Error::EnvFile(err) => write!(f, "{err}"),
         }
     }
 }
@@ -44,16 +55,20 @@ pub struct Builder {
     /// the standard input, if configured.
     stdin: Option<String>,
 
-    /// The path inside the container to a file where the contents of the
-    /// standard output stream will be written, if configured.
-    stdout: Option<String>,
+    /// Where the contents of the standard output stream are sent.
+    stdout: Option<Redirection>,
 
-    /// The path inside the container to a file where the contents of the
-    /// standard error stream will be written, if configured.
-    stderr: Option<String>,
+    /// Where the contents of the standard error stream are sent.
+    stderr: Option<Redirection>,
 
     /// A map of environment variables, if configured.
     env: Option<IndexMap<String, String>>,
+
+    /// The paths of the environment files to load, if configured.
+    env_files: Vec<PathBuf>,
+
+    /// The user (and, optionally, the group) to run as, if configured.
+    user: Option<String>,
 }
 
 impl Builder {
@@ -124,24 +139,30 @@ impl Builder {
         self
     }
 
-    /// Adds a file with which to stream standard out to.
+    /// Adds a redirection for standard out to the [`Builder`].
+    ///
+    /// If no redirection is provided, standard out is
+    /// [captured](Redirection::Capture).
     ///
     /// # Notes
     ///
     /// This will silently overwrite any previous standard out declarations
     /// provided to the builder.
-    pub fn stdout(mut self, stdout: impl Into<String>) -> Self {
+    pub fn stdout(mut self, stdout: impl Into<Redirection>) -> Self {
         self.stdout = Some(stdout.into());
         self
     }
 
-    /// Adds a file with which to stream standard error to.
+    /// Adds a redirection for standard error to the [`Builder`].
+    ///
+    /// If no redirection is provided, standard error is
+    /// [captured](Redirection::Capture).
     ///
     /// # Notes
     ///
     /// This will silently overwrite any previous standard error declarations
     /// provided to the builder.
-    pub fn stderr(mut self, stderr: impl Into<String>) -> Self {
+    pub fn stderr(mut self, stderr: impl Into<Redirection>) -> Self {
         self.stderr = Some(stderr.into());
         self
     }
@@ -159,19 +180,63 @@ impl Builder {
         self
     }
 
+    /// Adds an [environment file](EnvFile) to load environment variables from
+    /// to the [`Builder`].
+    ///
+    /// The file is read (and parsed) when the execution is built, so any
+    /// errors within the file are reported by [`try_build()`](Self::try_build).
+    ///
+    /// # Notes
+    ///
+    /// Environment files are applied in the order they were added, and
+    /// variables added with [`env()`](Self::env) take precedence over any
+    /// variables from environment files (regardless of the order in which they
+    /// were added to the builder).
+    pub fn env_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.env_files.push(path.into());
+        self
+    }
+
+    /// Adds the user (and, optionally, the group) to run as to the [`Builder`]
+    /// (e.g., `1000` or `1000:1000`).
+    ///
+    /// If no user is provided, the user is determined by the backend.
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous users provided to the
+    /// builder.
+    pub fn user(mut self, value: impl Into<String>) -> Self {
+        self.user = Some(value.into());
+        self
+    }
+
     /// Consumes `self` and attempts to return a built [`Execution`].
     pub fn try_build(self) -> Result<Execution> {
         let image = self.image.map(Ok).unwrap_or(Err(Error::Missing("image")))?;
         let args = self.args.map(Ok).unwrap_or(Err(Error::Missing("args")))?;
 
+        let mut env = None;
+
+        for path in self.env_files {
+            let file = EnvFile::read(path).map_err(Error::EnvFile)?;
+            env.get_or_insert_with(IndexMap::new)
+                .extend(file.into_vars());
+        }
+
+        if let Some(explicit) = self.env {
+            env.get_or_insert_with(IndexMap::new).extend(explicit);
+        }
+
         Ok(Execution {
-            image,
-            args,
+            image: intern(image),
+            args: Arc::new(args),
             workdir: self.working_directory,
             stdin: self.stdin,
-            stdout: self.stdout,
-            stderr: self.stderr,
-            env: self.env,
+            stdout: self.stdout.unwrap_or_default(),
+            stderr: self.stderr.unwrap_or_default(),
+            env: env.map(Arc::new),
+            user: self.user,
         })
     }
 }
@@ -0,0 +1,264 @@
+//! Environment files.
+//!
+//! An environment file (often named `.env`) holds environment variables in
+//! the following format:
+//!
+//! ```text
+//! # Comments and blank lines are ignored.
+//! NAME=value
+//! export OTHER="a value with \"escapes\"\n"
+//! LITERAL='no $escapes or \n here'
+//! ```
+//!
+//! Unquoted values are trimmed and may be followed by a comment (separated by
+//! whitespace). Variables are not interpolated.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use indexmap::IndexMap;
+
+/// An error related to an environment file.
+#[derive(Debug)]
+pub enum Error {
+    /// The file could not be read.
+    Io(PathBuf, std::io::Error),
+
+    /// A line of the file could not be parsed.
+    Parse {
+        /// The path of the file (if the contents were read from a file).
+        path: Option<PathBuf>,
+
+        /// The (one-based) line number.
+        line: usize,
+
+        /// The reason the line could not be parsed.
+        reason: &'static str,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(path, err) => {
+                write!(
+                    f,
+                    "failed to read environment file `{}`: {err}",
+                    path.display()
+                )
+            }
+            Error::Parse { path, line, reason } => match path {
+                Some(path) => write!(
+                    f,
+                    "invalid environment file `{}` (line {line}): {reason}",
+                    path.display()
+                ),
+                None => write!(f, "invalid environment file (line {line}): {reason}"),
+            },
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A [`Result`](std::result::Result) with an [`Error`].
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A parsed environment file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EnvFile {
+    /// The variables within the file (in the order they were defined).
+    vars: IndexMap<String, String>,
+}
+
+impl EnvFile {
+    /// Parses the contents of an environment file.
+    ///
+    /// If a variable is defined more than once, the last definition wins.
+    pub fn parse(contents: &str) -> Result<Self> {
+        let mut vars = IndexMap::new();
+
+        for (index, line) in contents.lines().enumerate() {
+            let error = |reason| Error::Parse {
+                path: None,
+                line: index + 1,
+                reason,
+            };
+
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let line = line
+                .strip_prefix("export")
+                .filter(|rest| rest.starts_with(char::is_whitespace))
+                .map(str::trim_start)
+                .unwrap_or(line);
+
+            let (name, value) = line.split_once('=').ok_or_else(|| error("missing `=`"))?;
+            let name = name.trim_end();
+
+            if !is_name(name) {
+                return Err(error("invalid variable name"));
+            }
+
+            vars.insert(
+                name.to_owned(),
+                parse_value(value.trim_start()).map_err(error)?,
+            );
+        }
+
+        Ok(Self { vars })
+    }
+
+    /// Reads and parses an environment file.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents =
+            std::fs::read_to_string(path).map_err(|err| Error::Io(path.to_path_buf(), err))?;
+
+        Self::parse(&contents).map_err(|err| match err {
+            Error::Parse { line, reason, .. } => Error::Parse {
+                path: Some(path.to_path_buf()),
+                line,
+                reason,
+            },
+            err => err,
+        })
+    }
+
+    /// Gets the variables within the file (in the order they were defined).
+    pub fn vars(&self) -> &IndexMap<String, String> {
+        &self.vars
+    }
+
+    /// Consumes `self` and returns the variables within the file.
+    pub fn into_vars(self) -> IndexMap<String, String> {
+        self.vars
+    }
+}
+
+/// Gets whether or not a string is a valid environment variable name.
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Parses the value of a variable.
+fn parse_value(value: &str) -> std::result::Result<String, &'static str> {
+    let (quoted, rest) = match value.chars().next() {
+        Some('\'') => {
+            let (quoted, rest) = value[1..]
+                .split_once('\'')
+                .ok_or("unterminated single quote")?;
+            (quoted.to_owned(), rest)
+        }
+        Some('"') => {
+            let mut quoted = String::new();
+            let mut chars = value[1..].char_indices();
+
+            loop {
+                match chars.next().ok_or("unterminated double quote")? {
+                    (_, '\\') => match chars.next().ok_or("unterminated double quote")?.1 {
+                        'n' => quoted.push('\n'),
+                        't' => quoted.push('\t'),
+                        'r' => quoted.push('\r'),
+                        c @ ('"' | '\\' | '$') => quoted.push(c),
+                        c => {
+                            quoted.push('\\');
+                            quoted.push(c);
+                        }
+                    },
+                    (i, '"') => break (quoted, &value[i + 2..]),
+                    (_, c) => quoted.push(c),
+                }
+            }
+        }
+        _ => {
+            // NOTE: a `#` only starts a comment when preceded by whitespace
+            // (so that values such as `a#b` are kept whole).
+            let end = value
+                .char_indices()
+                .find(|(i, c)| *c == '#' && value[..*i].ends_with(char::is_whitespace))
+                .map(|(i, _)| i)
+                .unwrap_or(value.len());
+
+            return Ok(value[..end].trim_end().to_owned());
+        }
+    };
+
+    let rest = rest.trim_start();
+
+    if rest.is_empty() || rest.starts_with('#') {
+        Ok(quoted)
+    } else {
+        Err("unexpected characters after a quoted value")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_files_are_parsed() {
+        let file = EnvFile::parse(
+            r#"
+            # A comment.
+            FOO=bar
+            export BAZ = qux # trailing comment
+            HASH=a#b
+            EMPTY=
+            SINGLE='$HOME \n'
+            DOUBLE="line\n\"quoted\"" # comment
+            FOO=overwritten
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            file.vars().iter().collect::<Vec<_>>(),
+            [
+                (&"FOO".into(), &"overwritten".into()),
+                (&"BAZ".into(), &"qux".into()),
+                (&"HASH".into(), &"a#b".into()),
+                (&"EMPTY".into(), &"".into()),
+                (&"SINGLE".into(), &"$HOME \\n".into()),
+                (&"DOUBLE".into(), &"line\n\"quoted\"".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_env_files_are_rejected() {
+        for (contents, line, expected) in [
+            ("FOO=bar\nBAZ", 2, "missing `=`"),
+            ("1FOO=bar", 1, "invalid variable name"),
+            ("FOO='bar", 1, "unterminated single quote"),
+            ("\n\nFOO=\"bar", 3, "unterminated double quote"),
+            (
+                "FOO=\"bar\" baz",
+                1,
+                "unexpected characters after a quoted value",
+            ),
+        ] {
+            match EnvFile::parse(contents).unwrap_err() {
+                Error::Parse {
+                    line: actual,
+                    reason,
+                    ..
+                } => {
+                    assert_eq!(actual, line, "{contents}");
+                    assert_eq!(reason, expected, "{contents}");
+                }
+                err => panic!("unexpected error: {err}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Redirection of the output streams of an execution.
+
+use std::path::PathBuf;
+
+use tokio::io::AsyncWriteExt as _;
+use url::Url;
+
+/// An error related to a [`Redirection`].
+#[derive(Debug)]
+pub enum Error {
+    /// An i/o error.
+    Io(std::io::Error),
+
+    /// A URL with a scheme that is not supported for redirection.
+    UnsupportedScheme(String),
+
+    /// A `file://` URL that could not be converted to a path.
+    InvalidPath(Url),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "i/o error: {err}"),
+            Error::UnsupportedScheme(scheme) => {
+                write!(f, "redirecting to `{scheme}` URLs is not supported")
+            }
+            Error::InvalidPath(url) => write!(f, "URL `{url}` is not a valid file path"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A [`Result`](std::result::Result) with an [`Error`].
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// An output stream of an execution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stream {
+    /// The standard output stream.
+    Stdout,
+
+    /// The standard error stream.
+    Stderr,
+}
+
+/// Where the contents of an output stream of an execution are sent.
+///
+/// Redirections are applied by the engine (on the host running the engine)
+/// once an execution completes, so they are handled the same regardless of
+/// the backend that ran the execution.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Redirection {
+    /// The contents are captured within the result of the task.
+    #[default]
+    Capture,
+
+    /// The contents are written to the same stream of the engine process.
+    Inherit,
+
+    /// The contents are written to a file on the host.
+    File(PathBuf),
+
+    /// The contents are written to a URL.
+    ///
+    /// Only `file://` URLs are currently supported.
+    Url(Url),
+
+    /// The contents are discarded.
+    Discard,
+}
+
+impl Redirection {
+    /// Applies the redirection to the contents of a stream.
+    ///
+    /// Once applied, `contents` holds the contents that should be kept within
+    /// the result of the task (which are empty unless the redirection is
+    /// [`Redirection::Capture`]). If applying the redirection fails, `contents`
+    /// is left untouched.
+    pub(crate) async fn apply(&self, stream: Stream, contents: &mut Vec<u8>) -> Result<()> {
+        let path = match self {
+            Redirection::Capture => return Ok(()),
+            Redirection::Discard => {
+                contents.clear();
+                return Ok(());
+            }
+            Redirection::Inherit => {
+                match stream {
+                    Stream::Stdout => tokio::io::stdout().write_all(contents).await,
+                    Stream::Stderr => tokio::io::stderr().write_all(contents).await,
+                }
+                .map_err(Error::Io)?;
+
+                contents.clear();
+                return Ok(());
+            }
+            Redirection::File(path) => path.clone(),
+            Redirection::Url(url) => match url.scheme() {
+                "file" => url
+                    .to_file_path()
+                    .map_err(|_| Error::InvalidPath(url.clone()))?,
+                scheme => return Err(Error::UnsupportedScheme(scheme.to_owned())),
+            },
+        };
+
+        tokio::fs::write(path, &contents).await.map_err(Error::Io)?;
+        contents.clear();
+        Ok(())
+    }
+}
+
+impl From<PathBuf> for Redirection {
+    fn from(path: PathBuf) -> Self {
+        Redirection::File(path)
+    }
+}
+
+impl From<Url> for Redirection {
+    fn from(url: Url) -> Self {
+        Redirection::Url(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn redirections_keep_only_captured_contents() {
+        let expected = b"hello".to_vec();
+
+        let mut contents = expected.clone();
+        Redirection::Capture
+            .apply(Stream::Stdout, &mut contents)
+            .await
+            .unwrap();
+        assert_eq!(contents, expected);
+
+        Redirection::Discard
+            .apply(Stream::Stdout, &mut contents)
+            .await
+            .unwrap();
+        assert!(contents.is_empty());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stdout");
+        let url = Url::from_file_path(dir.path().join("stderr")).unwrap();
+
+        let mut contents = expected.clone();
+        Redirection::from(path.clone())
+            .apply(Stream::Stdout, &mut contents)
+            .await
+            .unwrap();
+        assert!(contents.is_empty());
+
+        let mut contents = expected.clone();
+        Redirection::from(url)
+            .apply(Stream::Stderr, &mut contents)
+            .await
+            .unwrap();
+        assert!(contents.is_empty());
+
+        assert_eq!(std::fs::read(path).unwrap(), expected);
+        assert_eq!(std::fs::read(dir.path().join("stderr")).unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn failed_redirections_keep_contents() {
+        let mut contents = b"hello".to_vec();
+        let url = Url::parse("s3://bucket/stdout").unwrap();
+
+        assert!(matches!(
+            Redirection::from(url).apply(Stream::Stdout, &mut contents).await,
+            Err(Error::UnsupportedScheme(scheme)) if scheme == "s3"
+        ));
+        assert_eq!(contents, b"hello");
+    }
+}
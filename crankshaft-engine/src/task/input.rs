@@ -1,12 +1,15 @@
 //! Task inputs.
 
 mod builder;
+pub mod fetcher;
+pub mod precondition;
 
 use std::path::PathBuf;
 
 pub use builder::Builder;
-use tokio::fs::File;
-use tokio::io::AsyncReadExt;
+pub use fetcher::Fetcher;
+pub use fetcher::Fetchers;
+pub use precondition::Preconditions;
 use url::Url;
 
 /// A type of input.
@@ -53,6 +56,12 @@ pub struct Input {
 
     /// The type of the input.
     r#type: Type,
+
+    /// The preconditions of the input.
+    preconditions: Preconditions,
+
+    /// The expected hex-encoded SHA-256 digest of the contents.
+    checksum: Option<String>,
 }
 
 impl Input {
@@ -86,24 +95,21 @@ impl Input {
         &self.r#type
     }
 
-    /// Fetches the file contents via an [`AsyncRead`]er.
-    pub async fn fetch(&self) -> Vec<u8> {
-        match &self.contents {
-            Contents::Literal(content) => content.as_bytes().to_vec(),
-            Contents::URL(url) => match url.scheme() {
-                "file" => {
-                    // SAFETY: we just checked to ensure this is a file, so
-                    // getting the file path should always unwrap.
-                    let path = url.to_file_path().unwrap();
-                    let mut file = File::open(path).await.unwrap();
-                    let mut buffer = Vec::with_capacity(4096);
-                    file.read_to_end(&mut buffer).await.unwrap();
-                    buffer
-                }
-                "http" | "https" => unimplemented!("http(s) URL support not implemented"),
-                "s3" => unimplemented!("s3 URL support not implemented"),
-                v => unreachable!("unsupported URL scheme: {v}"),
-            },
-        }
+    /// The preconditions of the input.
+    pub fn preconditions(&self) -> &Preconditions {
+        &self.preconditions
+    }
+
+    /// The expected hex-encoded SHA-256 digest of the contents of the input
+    /// (if it exists).
+    pub fn checksum(&self) -> Option<&str> {
+        self.checksum.as_deref()
+    }
+
+    /// Fetches the contents of the input using the provided [`Fetchers`].
+    pub async fn fetch(&self, fetchers: &Fetchers) -> fetcher::Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        fetchers.fetch(&self.contents, &mut buffer).await?;
+        Ok(buffer)
     }
 }
@@ -1,7 +1,10 @@
 //! Builders for an [`Input`].
 
+use std::time::SystemTime;
+
 use crate::task::Input;
 use crate::task::input::Contents;
+use crate::task::input::Preconditions;
 use crate::task::input::Type;
 
 /// An error related to a [`Builder`].
@@ -46,6 +49,12 @@ pub struct Builder {
 
     /// The type of the input.
     r#type: Option<Type>,
+
+    /// The preconditions of the input.
+    preconditions: Preconditions,
+
+    /// The expected SHA-256 digest of the contents.
+    checksum: Option<String>,
 }
 
 impl Builder {
@@ -104,6 +113,57 @@ impl Builder {
         self
     }
 
+    /// Sets whether or not the input must exist before the task is submitted.
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous existence requirement
+    /// provided to the builder.
+    pub fn must_exist(mut self, value: bool) -> Self {
+        self.preconditions.set_must_exist(value);
+        self
+    }
+
+    /// Sets the earliest modification time of the input.
+    ///
+    /// An input with an earliest modification time must exist.
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous modification time(s) provided
+    /// to the builder.
+    pub fn min_mtime(mut self, value: SystemTime) -> Self {
+        self.preconditions.set_min_mtime(value);
+        self
+    }
+
+    /// Sets the entity tag that the input must have.
+    ///
+    /// An input with an entity tag must exist.
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous entity tag(s) provided to the
+    /// builder.
+    pub fn etag(mut self, value: impl Into<String>) -> Self {
+        self.preconditions.set_etag(value.into());
+        self
+    }
+
+    /// Sets the expected hex-encoded SHA-256 digest of the contents of the
+    /// input.
+    ///
+    /// Inputs whose contents do not match the checksum fail to be staged.
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous checksum(s) provided to the
+    /// builder.
+    pub fn checksum(mut self, value: impl Into<String>) -> Self {
+        self.checksum = Some(value.into());
+        self
+    }
+
     /// Consumes `self` and attempts to return a built [`Input`].
     pub fn try_build(self) -> Result<Input> {
         let contents = self.contents.ok_or(Error::Missing("contents"))?;
@@ -116,6 +176,8 @@ impl Builder {
             contents,
             path,
             r#type,
+            preconditions: self.preconditions,
+            checksum: self.checksum,
         })
     }
 }
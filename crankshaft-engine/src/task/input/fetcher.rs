@@ -0,0 +1,660 @@
+//! Fetchers for the contents of task inputs.
+//!
+//! A [`Fetcher`] knows how to open a stream to the contents behind a URL.
+//! Fetchers are collected within [`Fetchers`], which is responsible for
+//! selecting the fetcher for a particular URL, enforcing size limits, and
+//! (optionally) storing retrieved contents within a content-addressed
+//! [`Cache`].
+//!
+//! Cached contents are only reused while the source reports the same
+//! [validator](Fetcher::validator()) (e.g., the `ETag` of an HTTP resource) for
+//! the URL as when the contents were cached, so changed remote contents are
+//! fetched again.
+
+use std::fmt::Debug;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::TryStreamExt as _;
+use sha2::Digest as _;
+use sha2::Sha256;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt as _;
+use tokio::io::AsyncWrite;
+use tokio::io::AsyncWriteExt as _;
+use tokio_util::io::StreamReader;
+use tracing::debug;
+use tracing::trace;
+use url::Url;
+
+use crate::task::input::Contents;
+
+/// The size of the buffer used when streaming contents.
+const BUFFER_SIZE: usize = 0x2000;
+
+/// The name of the directory within a [`Cache`] where contents are stored.
+const BLOBS_DIR: &str = "blobs";
+
+/// The name of the directory within a [`Cache`] where URLs are recorded.
+const URLS_DIR: &str = "urls";
+
+/// An error related to fetching the contents of an input.
+#[derive(Debug)]
+pub enum Error {
+    /// An i/o error.
+    Io(std::io::Error),
+
+    /// An HTTP error.
+    Http(reqwest::Error),
+
+    /// A URL could not be converted to a path on the local filesystem.
+    InvalidPath(Url),
+
+    /// The contents of an input exceeded the size limit (in bytes).
+    LimitExceeded {
+        /// A description of the source of the contents.
+        source: String,
+
+        /// The size limit (in bytes).
+        limit: u64,
+    },
+
+    /// No registered fetcher supports the scheme of a URL.
+    UnsupportedScheme(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "i/o error: {err}"),
+            Error::Http(err) => write!(f, "http error: {err}"),
+            Error::InvalidPath(url) => write!(f, "URL `{url}` is not a valid local path"),
+            Error::LimitExceeded { source, limit } => {
+                write!(
+                    f,
+                    "contents of {source} exceeded the limit of {limit} bytes"
+                )
+            }
+            Error::UnsupportedScheme(scheme) => {
+                write!(f, "no fetcher supports the `{scheme}` URL scheme")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A [`Result`](std::result::Result) with an [`Error`].
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A source of input contents.
+#[async_trait]
+pub trait Fetcher: Debug + Send + Sync + 'static {
+    /// Gets whether or not the fetcher can retrieve the contents at `url`.
+    fn supports(&self, url: &Url) -> bool;
+
+    /// Gets whether or not contents retrieved by the fetcher may be stored
+    /// within a [`Cache`].
+    ///
+    /// Fetchers for sources that are already cheap to read (e.g., local
+    /// files) should return `false` so that the cache isn't needlessly
+    /// populated.
+    fn cacheable(&self) -> bool {
+        true
+    }
+
+    /// Gets a validator for the current contents at `url` (if the source
+    /// provides one).
+    ///
+    /// A validator is an opaque value that changes whenever the contents
+    /// change (e.g., the `ETag` of an HTTP resource). Contents are only stored
+    /// within a [`Cache`] if a validator is provided, and cached contents are
+    /// only reused while the validator is unchanged.
+    async fn validator(&self, _url: &Url) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// Opens a stream to the contents at `url`.
+    async fn open(&self, url: &Url) -> Result<Box<dyn AsyncRead + Send + Unpin>>;
+}
+
+/// A [`Fetcher`] for `file://` URLs.
+#[derive(Debug, Default)]
+pub struct File;
+
+#[async_trait]
+impl Fetcher for File {
+    fn supports(&self, url: &Url) -> bool {
+        url.scheme() == "file"
+    }
+
+    fn cacheable(&self) -> bool {
+        false
+    }
+
+    async fn open(&self, url: &Url) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+        let path = url
+            .to_file_path()
+            .map_err(|_| Error::InvalidPath(url.clone()))?;
+
+        let file = tokio::fs::File::open(path).await.map_err(Error::Io)?;
+        Ok(Box::new(file))
+    }
+}
+
+/// A [`Fetcher`] for `http://` and `https://` URLs.
+///
+/// Publicly readable objects behind `s3://` and `gs://` URLs are also
+/// supported by fetching them from the HTTPS endpoints of the respective
+/// object stores.
+#[derive(Debug, Default)]
+pub struct Http {
+    /// The HTTP client.
+    client: reqwest::Client,
+}
+
+impl Http {
+    /// Resolves the HTTP(S) URL from which the contents at `url` are fetched.
+    pub(crate) fn resolve(url: &Url) -> Result<Url> {
+        let endpoint = match url.scheme() {
+            "http" | "https" => return Ok(url.clone()),
+            "s3" => format!(
+                "https://{}.s3.amazonaws.com{}",
+                url.host_str().unwrap_or_default(),
+                url.path()
+            ),
+            "gs" => format!(
+                "https://storage.googleapis.com/{}{}",
+                url.host_str().unwrap_or_default(),
+                url.path()
+            ),
+            scheme => return Err(Error::UnsupportedScheme(scheme.to_owned())),
+        };
+
+        Url::parse(&endpoint).map_err(|_| Error::UnsupportedScheme(url.scheme().to_owned()))
+    }
+}
+
+#[async_trait]
+impl Fetcher for Http {
+    fn supports(&self, url: &Url) -> bool {
+        matches!(url.scheme(), "http" | "https" | "s3" | "gs")
+    }
+
+    async fn validator(&self, url: &Url) -> Result<Option<String>> {
+        let response = self
+            .client
+            .head(Self::resolve(url)?)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(Error::Http)?;
+
+        // NOTE: an `ETag` identifies the contents themselves, whereas the
+        // modification time is only paired with the length of the contents
+        // when no `ETag` is provided.
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+        };
+
+        Ok(match header(reqwest::header::ETAG) {
+            Some(etag) => Some(format!("etag:{etag}")),
+            None => header(reqwest::header::LAST_MODIFIED).map(|modified| {
+                format!(
+                    "modified:{modified};length:{}",
+                    header(reqwest::header::CONTENT_LENGTH).unwrap_or_default()
+                )
+            }),
+        })
+    }
+
+    async fn open(&self, url: &Url) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+        let response = self
+            .client
+            .get(Self::resolve(url)?)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(Error::Http)?;
+
+        let stream = response.bytes_stream().map_err(std::io::Error::other);
+        Ok(Box::new(StreamReader::new(stream)))
+    }
+}
+
+/// Streams the contents of `reader` into `writer`.
+///
+/// If a `limit` is provided and more than `limit` bytes are read, an
+/// [`Error::LimitExceeded`] is returned. If a `hasher` is provided, it is
+/// updated with every byte that is written.
+async fn copy(
+    reader: &mut (impl AsyncRead + Unpin + ?Sized),
+    writer: &mut (impl AsyncWrite + Unpin + ?Sized),
+    limit: Option<u64>,
+    mut hasher: Option<&mut Sha256>,
+    source: &str,
+) -> Result<u64> {
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut total = 0u64;
+
+    loop {
+        let n = reader.read(&mut buffer).await.map_err(Error::Io)?;
+
+        if n == 0 {
+            break;
+        }
+
+        total += n as u64;
+
+        if let Some(limit) = limit {
+            if total > limit {
+                return Err(Error::LimitExceeded {
+                    source: source.to_owned(),
+                    limit,
+                });
+            }
+        }
+
+        if let Some(hasher) = hasher.as_mut() {
+            hasher.update(&buffer[..n]);
+        }
+
+        writer.write_all(&buffer[..n]).await.map_err(Error::Io)?;
+    }
+
+    writer.flush().await.map_err(Error::Io)?;
+    Ok(total)
+}
+
+/// A content-addressed cache of fetched contents.
+///
+/// Contents are stored within `<root>/blobs` by the hex-encoded SHA-256 digest
+/// of the contents. Each fetched URL is recorded within `<root>/urls` (by the
+/// digest of the URL) alongside the digest of the contents that were
+/// retrieved, so URLs that resolve to the same contents share storage. The
+/// record also holds the [validator](Fetcher::validator()) of the contents,
+/// and a record whose validator no longer matches is replaced when the URL is
+/// fetched again.
+#[derive(Clone, Debug)]
+pub struct Cache {
+    /// The root directory of the cache.
+    root: PathBuf,
+}
+
+impl Cache {
+    /// Creates a new [`Cache`] rooted at the provided directory.
+    ///
+    /// The directory is created on first use if it does not already exist.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Gets the root directory of the cache.
+    pub fn root(&self) -> &PathBuf {
+        &self.root
+    }
+
+    /// Gets the path where contents with the provided digest are stored.
+    fn blob_path(&self, digest: &str) -> PathBuf {
+        self.root.join(BLOBS_DIR).join(digest)
+    }
+
+    /// Gets the path where the record for a URL is stored.
+    fn url_path(&self, url: &Url) -> PathBuf {
+        let digest = hex::encode(Sha256::digest(url.as_str().as_bytes()));
+        self.root.join(URLS_DIR).join(digest)
+    }
+
+    /// Gets the path to the cached contents of a URL (if they exist and were
+    /// cached with the provided validator).
+    async fn get(&self, url: &Url, validator: &str) -> Option<PathBuf> {
+        let record = tokio::fs::read_to_string(self.url_path(url)).await.ok()?;
+        let (digest, cached) = record.split_once('\n')?;
+
+        if cached != validator {
+            debug!("cached contents for `{url}` are stale");
+            return None;
+        }
+
+        let path = self.blob_path(digest);
+
+        match tokio::fs::try_exists(&path).await {
+            Ok(true) => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Streams the contents of `reader` into the cache as the contents of
+    /// `url` (with the provided validator), returning the path to the cached
+    /// contents.
+    async fn insert(
+        &self,
+        url: &Url,
+        validator: &str,
+        reader: &mut (impl AsyncRead + Unpin + ?Sized),
+        limit: Option<u64>,
+    ) -> Result<PathBuf> {
+        for dir in [BLOBS_DIR, URLS_DIR] {
+            tokio::fs::create_dir_all(self.root.join(dir))
+                .await
+                .map_err(Error::Io)?;
+        }
+
+        // NOTE: contents are first streamed into a temporary file within the
+        // cache so that a partially written (or rejected) download never
+        // appears under a digest.
+        let (file, temp) = tempfile::NamedTempFile::new_in(&self.root)
+            .map_err(Error::Io)?
+            .into_parts();
+        let mut file = tokio::fs::File::from_std(file);
+        let mut hasher = Sha256::new();
+
+        copy(reader, &mut file, limit, Some(&mut hasher), url.as_str()).await?;
+
+        let digest = hex::encode(hasher.finalize());
+        let path = self.blob_path(&digest);
+        temp.persist(&path).map_err(|err| Error::Io(err.error))?;
+
+        tokio::fs::write(self.url_path(url), format!("{digest}\n{validator}"))
+            .await
+            .map_err(Error::Io)?;
+
+        trace!("cached `{url}` as `{digest}`");
+        Ok(path)
+    }
+}
+
+/// A set of [`Fetcher`]s along with the policies applied when fetching.
+///
+/// By default, only the [`File`] fetcher is registered, no size limit is
+/// enforced, and no cache is used.
+#[derive(Clone, Debug)]
+pub struct Fetchers {
+    /// The registered fetchers.
+    fetchers: Vec<Arc<dyn Fetcher>>,
+
+    /// The maximum number of bytes to fetch for any single input.
+    limit: Option<u64>,
+
+    /// The cache within which to store fetched contents.
+    cache: Option<Cache>,
+}
+
+impl Default for Fetchers {
+    fn default() -> Self {
+        Self {
+            fetchers: vec![Arc::new(File)],
+            limit: None,
+            cache: None,
+        }
+    }
+}
+
+impl Fetchers {
+    /// Registers a [`Fetcher`].
+    ///
+    /// Fetchers registered later take precedence over those registered earlier
+    /// when more than one fetcher supports a URL.
+    pub fn with(mut self, fetcher: impl Fetcher) -> Self {
+        self.fetchers.push(Arc::new(fetcher));
+        self
+    }
+
+    /// Sets the maximum number of bytes that will be fetched for any single
+    /// input.
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous limit.
+    pub fn limit(mut self, bytes: u64) -> Self {
+        self.limit = Some(bytes);
+        self
+    }
+
+    /// Sets the [`Cache`] within which fetched contents are stored.
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous cache.
+    pub fn cache(mut self, cache: Cache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Finds the fetcher for a URL.
+    fn find(&self, url: &Url) -> Result<&Arc<dyn Fetcher>> {
+        self.fetchers
+            .iter()
+            .rev()
+            .find(|fetcher| fetcher.supports(url))
+            .ok_or_else(|| Error::UnsupportedScheme(url.scheme().to_owned()))
+    }
+
+    /// Streams the provided contents into `writer`, returning the number of
+    /// bytes written.
+    pub async fn fetch(
+        &self,
+        contents: &Contents,
+        writer: &mut (impl AsyncWrite + Unpin + ?Sized),
+    ) -> Result<u64> {
+        self.fetch_inner(contents, writer, None).await
+    }
+
+    /// Streams the provided contents into `writer`, returning the number of
+    /// bytes written along with the hex-encoded SHA-256 digest of the
+    /// contents.
+    pub async fn fetch_with_digest(
+        &self,
+        contents: &Contents,
+        writer: &mut (impl AsyncWrite + Unpin + ?Sized),
+    ) -> Result<(u64, String)> {
+        let mut hasher = Sha256::new();
+        let size = self
+            .fetch_inner(contents, writer, Some(&mut hasher))
+            .await?;
+        Ok((size, hex::encode(hasher.finalize())))
+    }
+
+    /// Streams the provided contents into `writer` (updating the `hasher` with
+    /// the contents if one is provided).
+    async fn fetch_inner(
+        &self,
+        contents: &Contents,
+        writer: &mut (impl AsyncWrite + Unpin + ?Sized),
+        hasher: Option<&mut Sha256>,
+    ) -> Result<u64> {
+        let url = match contents {
+            Contents::Literal(literal) => {
+                return copy(
+                    &mut literal.as_bytes(),
+                    writer,
+                    self.limit,
+                    hasher,
+                    "literal contents",
+                )
+                .await;
+            }
+            Contents::URL(url) => url,
+        };
+
+        let fetcher = self.find(url)?;
+
+        let validator = match &self.cache {
+            Some(cache) if fetcher.cacheable() => fetcher
+                .validator(url)
+                .await?
+                .map(|validator| (cache, validator)),
+            _ => None,
+        };
+
+        let Some((cache, validator)) = validator else {
+            let mut reader = fetcher.open(url).await?;
+            return copy(&mut reader, writer, self.limit, hasher, url.as_str()).await;
+        };
+
+        let path = match cache.get(url, &validator).await {
+            Some(path) => {
+                debug!("using cached contents for `{url}`");
+                path
+            }
+            None => {
+                let mut reader = fetcher.open(url).await?;
+                cache
+                    .insert(url, &validator, &mut reader, self.limit)
+                    .await?
+            }
+        };
+
+        // NOTE: the limit is enforced for cached contents as well, as they may
+        // have been cached under a different (or without any) limit.
+        let mut file = tokio::fs::File::open(path).await.map_err(Error::Io)?;
+        copy(&mut file, writer, self.limit, hasher, url.as_str()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// A fetcher that serves the same contents for every `mem://` URL.
+    ///
+    /// The contents themselves are used as the validator.
+    #[derive(Debug)]
+    struct Memory(Arc<std::sync::Mutex<&'static str>>);
+
+    impl Memory {
+        /// Creates a new [`Memory`] fetcher serving the provided contents.
+        fn new(contents: &'static str) -> Self {
+            Self(Arc::new(std::sync::Mutex::new(contents)))
+        }
+    }
+
+    #[async_trait]
+    impl Fetcher for Memory {
+        fn supports(&self, url: &Url) -> bool {
+            url.scheme() == "mem"
+        }
+
+        async fn validator(&self, _: &Url) -> Result<Option<String>> {
+            Ok(Some(self.0.lock().unwrap().to_string()))
+        }
+
+        async fn open(&self, _: &Url) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+            Ok(Box::new(Cursor::new(self.0.lock().unwrap().as_bytes())))
+        }
+    }
+
+    /// Fetches the contents of a URL into a string.
+    async fn fetch(fetchers: &Fetchers, url: &str) -> Result<String> {
+        let mut buffer = Vec::new();
+        let contents = Contents::URL(url.parse().unwrap());
+        fetchers.fetch(&contents, &mut buffer).await?;
+        Ok(String::from_utf8(buffer).unwrap())
+    }
+
+    #[tokio::test]
+    async fn literals_respect_the_limit() {
+        let fetchers = Fetchers::default().limit(4);
+        let mut buffer = Vec::new();
+
+        let contents = Contents::Literal(String::from("abcd"));
+        assert_eq!(fetchers.fetch(&contents, &mut buffer).await.unwrap(), 4);
+
+        let contents = Contents::Literal(String::from("abcde"));
+        let err = fetchers.fetch(&contents, &mut buffer).await.unwrap_err();
+        assert!(matches!(err, Error::LimitExceeded { limit: 4, .. }));
+    }
+
+    #[test]
+    fn object_store_urls_are_resolved() {
+        let resolve = |url: &str| Http::resolve(&url.parse().unwrap()).unwrap().to_string();
+
+        assert_eq!(
+            resolve("s3://bucket/a/b.txt"),
+            "https://bucket.s3.amazonaws.com/a/b.txt"
+        );
+        assert_eq!(
+            resolve("gs://bucket/a/b.txt"),
+            "https://storage.googleapis.com/bucket/a/b.txt"
+        );
+        assert_eq!(
+            resolve("https://example.com/a.txt"),
+            "https://example.com/a.txt"
+        );
+    }
+
+    #[tokio::test]
+    async fn unsupported_schemes_are_rejected() {
+        let fetchers = Fetchers::default();
+        let contents = Contents::URL("mem://foo".parse().unwrap());
+
+        let err = fetchers
+            .fetch(&contents, &mut Vec::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::UnsupportedScheme(scheme) if scheme == "mem"));
+    }
+
+    #[tokio::test]
+    async fn urls_with_the_same_contents_share_a_blob() {
+        let dir = tempfile::tempdir().unwrap();
+        let fetchers = Fetchers::default()
+            .with(Memory::new("hello, world!"))
+            .cache(Cache::new(dir.path()));
+
+        for url in ["mem://foo", "mem://bar", "mem://foo"] {
+            assert_eq!(fetch(&fetchers, url).await.unwrap(), "hello, world!");
+        }
+
+        let blobs = std::fs::read_dir(dir.path().join(BLOBS_DIR)).unwrap();
+        assert_eq!(blobs.count(), 1);
+
+        let urls = std::fs::read_dir(dir.path().join(URLS_DIR)).unwrap();
+        assert_eq!(urls.count(), 2);
+    }
+
+    #[tokio::test]
+    async fn stale_contents_are_fetched_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let memory = Memory::new("hello, world!");
+        let contents = memory.0.clone();
+        let fetchers = Fetchers::default()
+            .with(memory)
+            .cache(Cache::new(dir.path()));
+
+        assert_eq!(
+            fetch(&fetchers, "mem://foo").await.unwrap(),
+            "hello, world!"
+        );
+
+        *contents.lock().unwrap() = "goodbye, world!";
+        assert_eq!(
+            fetch(&fetchers, "mem://foo").await.unwrap(),
+            "goodbye, world!"
+        );
+
+        let blobs = std::fs::read_dir(dir.path().join(BLOBS_DIR)).unwrap();
+        assert_eq!(blobs.count(), 2);
+    }
+
+    #[tokio::test]
+    async fn cached_contents_respect_the_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let fetchers = Fetchers::default()
+            .with(Memory::new("hello, world!"))
+            .cache(Cache::new(dir.path()));
+
+        fetch(&fetchers, "mem://foo").await.unwrap();
+
+        let err = fetch(&fetchers.clone().limit(4), "mem://foo")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::LimitExceeded { limit: 4, .. }));
+    }
+}
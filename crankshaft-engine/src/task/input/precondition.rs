@@ -0,0 +1,319 @@
+//! Preconditions of task inputs.
+//!
+//! Inputs may declare preconditions on the files they reference (e.g., that a
+//! reference genome must exist and must have been modified after a particular
+//! point in time). Preconditions are verified with
+//! [`Task::check_preconditions()`](crate::Task::check_preconditions) (or
+//! [`Engine::submit_verified()`](crate::Engine::submit_verified)) before a task
+//! is submitted so that missing or stale data is caught before work is handed
+//! to a (potentially slow) backend.
+//!
+//! Which preconditions can be verified depends on the source of an input:
+//!
+//! * Local (`file://`) inputs support existence and modification time checks.
+//! * HTTP(S) inputs support existence and entity tag checks.
+//! * Literal inputs always satisfy their preconditions.
+
+use std::time::SystemTime;
+
+use reqwest::StatusCode;
+use reqwest::header::ETAG;
+use url::Url;
+
+use crate::task::Input;
+use crate::task::input::Contents;
+
+/// The preconditions of an input.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Preconditions {
+    /// Whether or not the input must exist.
+    must_exist: bool,
+
+    /// The earliest modification time of the input.
+    min_mtime: Option<SystemTime>,
+
+    /// The entity tag that the input must have.
+    etag: Option<String>,
+}
+
+impl Preconditions {
+    /// Gets whether or not the input must exist.
+    ///
+    /// Inputs with any other precondition must always exist.
+    pub fn must_exist(&self) -> bool {
+        self.must_exist || self.min_mtime.is_some() || self.etag.is_some()
+    }
+
+    /// Gets the earliest modification time of the input (if it exists).
+    pub fn min_mtime(&self) -> Option<SystemTime> {
+        self.min_mtime
+    }
+
+    /// Gets the entity tag that the input must have (if it exists).
+    pub fn etag(&self) -> Option<&str> {
+        self.etag.as_deref()
+    }
+
+    /// Sets whether or not the input must exist.
+    pub(crate) fn set_must_exist(&mut self, value: bool) {
+        self.must_exist = value;
+    }
+
+    /// Sets the earliest modification time of the input.
+    pub(crate) fn set_min_mtime(&mut self, value: SystemTime) {
+        self.min_mtime = Some(value);
+    }
+
+    /// Sets the entity tag that the input must have.
+    pub(crate) fn set_etag(&mut self, value: String) {
+        self.etag = Some(value);
+    }
+}
+
+/// The reason an input failed its preconditions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reason {
+    /// The input does not exist.
+    Missing,
+
+    /// The input was last modified before its earliest modification time.
+    Stale {
+        /// When the input was last modified.
+        modified: SystemTime,
+
+        /// The earliest modification time of the input.
+        min: SystemTime,
+    },
+
+    /// The entity tag of the input did not match.
+    EtagMismatch {
+        /// The expected entity tag.
+        expected: String,
+
+        /// The actual entity tag (if one was reported).
+        actual: Option<String>,
+    },
+
+    /// The preconditions of the input could not be verified.
+    Unverifiable(String),
+}
+
+impl std::fmt::Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reason::Missing => write!(f, "does not exist"),
+            Reason::Stale { modified, min } => write!(
+                f,
+                "was last modified {:?} before its earliest allowed modification time",
+                min.duration_since(*modified).unwrap_or_default()
+            ),
+            Reason::EtagMismatch { expected, actual } => match actual {
+                Some(actual) => {
+                    write!(f, "has entity tag `{actual}` rather than `{expected}`")
+                }
+                None => write!(f, "has no entity tag (expected `{expected}`)"),
+            },
+            Reason::Unverifiable(reason) => write!(f, "could not be verified: {reason}"),
+        }
+    }
+}
+
+/// An error related to an input failing its preconditions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// The name of the input (or its path if it has no name).
+    input: String,
+
+    /// The reason the input failed its preconditions.
+    reason: Reason,
+}
+
+impl Error {
+    /// Gets the name of the input (or its path within the container if it
+    /// has no name).
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// Gets the reason the input failed its preconditions.
+    pub fn reason(&self) -> &Reason {
+        &self.reason
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "input `{}` {}", self.input, self.reason)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Checks the preconditions of a local file.
+async fn check_file(url: &Url, preconditions: &Preconditions) -> Result<(), Reason> {
+    let path = url
+        .to_file_path()
+        .map_err(|_| Reason::Unverifiable(format!("URL `{url}` is not a valid local path")))?;
+
+    if preconditions.etag().is_some() {
+        return Err(Reason::Unverifiable(String::from(
+            "entity tags can only be checked for HTTP(S) inputs",
+        )));
+    }
+
+    let metadata = match tokio::fs::metadata(&path).await {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Err(Reason::Missing),
+        Err(err) => return Err(Reason::Unverifiable(err.to_string())),
+    };
+
+    if let Some(min) = preconditions.min_mtime() {
+        let modified = metadata
+            .modified()
+            .map_err(|err| Reason::Unverifiable(err.to_string()))?;
+
+        if modified < min {
+            return Err(Reason::Stale { modified, min });
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks the preconditions of a file served over HTTP(S).
+async fn check_http(
+    client: &reqwest::Client,
+    url: &Url,
+    preconditions: &Preconditions,
+) -> Result<(), Reason> {
+    if preconditions.min_mtime().is_some() {
+        return Err(Reason::Unverifiable(String::from(
+            "modification times can only be checked for local inputs",
+        )));
+    }
+
+    let response = client
+        .head(url.clone())
+        .send()
+        .await
+        .map_err(|err| Reason::Unverifiable(err.to_string()))?;
+
+    match response.status() {
+        StatusCode::NOT_FOUND | StatusCode::GONE => return Err(Reason::Missing),
+        status if !status.is_success() => {
+            return Err(Reason::Unverifiable(format!(
+                "server responded with status {status}"
+            )));
+        }
+        _ => {}
+    }
+
+    if let Some(expected) = preconditions.etag() {
+        let actual = response
+            .headers()
+            .get(ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(ToOwned::to_owned);
+
+        // NOTE: entity tags are compared weakly (i.e., ignoring the weakness
+        // indicator and the surrounding quotes).
+        let normalize = |tag: &str| tag.trim_start_matches("W/").trim_matches('"').to_owned();
+
+        if actual.as_deref().map(normalize) != Some(normalize(expected)) {
+            return Err(Reason::EtagMismatch {
+                expected: expected.to_owned(),
+                actual,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks the preconditions of an input.
+pub(crate) async fn check(input: &Input, client: &reqwest::Client) -> Result<(), Error> {
+    let preconditions = input.preconditions();
+
+    if !preconditions.must_exist() {
+        return Ok(());
+    }
+
+    let result = match input.contents() {
+        Contents::Literal(_) => Ok(()),
+        Contents::URL(url) => match url.scheme() {
+            "file" => check_file(url, preconditions).await,
+            "http" | "https" => check_http(client, url, preconditions).await,
+            scheme => Err(Reason::Unverifiable(format!(
+                "preconditions cannot be checked for `{scheme}` inputs"
+            ))),
+        },
+    };
+
+    result.map_err(|reason| Error {
+        input: input.name().unwrap_or(input.path()).to_owned(),
+        reason,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::task::input::Type;
+
+    fn input(contents: impl Into<Contents>) -> crate::task::input::Builder {
+        Input::builder()
+            .name("reference")
+            .contents(contents)
+            .path("/ref.fa")
+            .r#type(Type::File)
+    }
+
+    #[tokio::test]
+    async fn local_preconditions_are_checked() {
+        let client = reqwest::Client::new();
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let modified = file.as_file().metadata().unwrap().modified().unwrap();
+
+        let fresh = input(file.path().to_path_buf())
+            .must_exist(true)
+            .min_mtime(modified - Duration::from_secs(60))
+            .try_build()
+            .unwrap();
+        assert_eq!(check(&fresh, &client).await, Ok(()));
+
+        let min = modified + Duration::from_secs(60);
+        let stale = input(file.path().to_path_buf())
+            .min_mtime(min)
+            .try_build()
+            .unwrap();
+        assert_eq!(
+            check(&stale, &client).await.unwrap_err().reason(),
+            &Reason::Stale { modified, min }
+        );
+
+        let missing = input(file.path().with_extension("missing"))
+            .must_exist(true)
+            .try_build()
+            .unwrap();
+        let err = check(&missing, &client).await.unwrap_err();
+        assert_eq!(err.input(), "reference");
+        assert_eq!(err.reason(), &Reason::Missing);
+
+        // NOTE: inputs without preconditions are never checked.
+        let unchecked = input(file.path().with_extension("missing"))
+            .try_build()
+            .unwrap();
+        assert_eq!(check(&unchecked, &client).await, Ok(()));
+
+        let etag = input(file.path().to_path_buf())
+            .etag("\"abc\"")
+            .try_build()
+            .unwrap();
+        assert!(matches!(
+            check(&etag, &client).await.unwrap_err().reason(),
+            Reason::Unverifiable(_)
+        ));
+    }
+}
@@ -1,8 +1,11 @@
 //! Task outputs.
 
 mod builder;
+pub mod uploader;
 
 pub use builder::Builder;
+pub use uploader::Uploader;
+pub use uploader::Uploaders;
 use url::Url;
 
 /// A type of task output.
@@ -0,0 +1,455 @@
+//! Uploaders for the contents of task outputs.
+//!
+//! An [`Uploader`] knows how to store the contents of a local file at a URL.
+//! Uploaders are collected within [`Uploaders`], which is responsible for
+//! selecting the uploader for a particular URL, expanding directories into the
+//! files within them, retrying failed uploads, and limiting how many uploads
+//! run at once.
+
+use std::fmt::Debug;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::StreamExt as _;
+use futures::TryStreamExt as _;
+use futures::future;
+use futures::stream;
+use reqwest::header::CONTENT_LENGTH;
+use tokio_util::io::ReaderStream;
+use tracing::trace;
+use tracing::warn;
+use url::Url;
+
+use crate::task::input::fetcher;
+
+/// The default maximum number of uploads that run at once.
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// The default maximum number of times a failed upload is retried.
+pub const DEFAULT_RETRIES: u32 = 3;
+
+/// The delay before the first retry of a failed upload (which doubles with
+/// each subsequent retry).
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// An error related to uploading the contents of an output.
+#[derive(Debug)]
+pub enum Error {
+    /// An i/o error.
+    Io(std::io::Error),
+
+    /// An HTTP error.
+    Http(reqwest::Error),
+
+    /// A URL could not be converted to a path on the local filesystem.
+    InvalidPath(Url),
+
+    /// No registered uploader supports the scheme of a URL.
+    UnsupportedScheme(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "i/o error: {err}"),
+            Error::Http(err) => write!(f, "http error: {err}"),
+            Error::InvalidPath(url) => write!(f, "URL `{url}` is not a valid local path"),
+            Error::UnsupportedScheme(scheme) => {
+                write!(f, "no uploader supports the `{scheme}` URL scheme")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A [`Result`](std::result::Result) with an [`Error`].
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A destination for output contents.
+#[async_trait]
+pub trait Uploader: Debug + Send + Sync + 'static {
+    /// Gets whether or not the uploader can store contents at `url`.
+    fn supports(&self, url: &Url) -> bool;
+
+    /// Stores the contents of the local file at `path` at `url`.
+    async fn upload(&self, path: &Path, url: &Url) -> Result<()>;
+}
+
+/// An [`Uploader`] for `file://` URLs.
+#[derive(Debug, Default)]
+pub struct File;
+
+#[async_trait]
+impl Uploader for File {
+    fn supports(&self, url: &Url) -> bool {
+        url.scheme() == "file"
+    }
+
+    async fn upload(&self, path: &Path, url: &Url) -> Result<()> {
+        let destination = url
+            .to_file_path()
+            .map_err(|_| Error::InvalidPath(url.clone()))?;
+
+        if let Some(parent) = destination.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(Error::Io)?;
+        }
+
+        tokio::fs::copy(path, destination)
+            .await
+            .map_err(Error::Io)?;
+        Ok(())
+    }
+}
+
+/// An [`Uploader`] for `http://` and `https://` URLs (using `PUT` requests).
+///
+/// `s3://` and `gs://` URLs are also supported by uploading to the HTTPS
+/// endpoints of the respective object stores (which only succeeds for buckets
+/// that accept unauthenticated writes).
+#[derive(Debug, Default)]
+pub struct Http {
+    /// The HTTP client.
+    client: reqwest::Client,
+}
+
+#[async_trait]
+impl Uploader for Http {
+    fn supports(&self, url: &Url) -> bool {
+        matches!(url.scheme(), "http" | "https" | "s3" | "gs")
+    }
+
+    async fn upload(&self, path: &Path, url: &Url) -> Result<()> {
+        let endpoint = fetcher::Http::resolve(url)
+            .map_err(|_| Error::UnsupportedScheme(url.scheme().to_owned()))?;
+
+        let file = tokio::fs::File::open(path).await.map_err(Error::Io)?;
+        let size = file.metadata().await.map_err(Error::Io)?.len();
+
+        self.client
+            .put(endpoint)
+            .header(CONTENT_LENGTH, size)
+            .body(reqwest::Body::wrap_stream(ReaderStream::new(file)))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(Error::Http)?;
+
+        Ok(())
+    }
+}
+
+/// Lists the files within a directory (recursively) along with their paths
+/// relative to the directory.
+async fn walk(dir: &Path) -> Result<Vec<(PathBuf, Vec<String>)>> {
+    let mut files = Vec::new();
+    let mut pending = vec![(dir.to_owned(), Vec::new())];
+
+    while let Some((dir, relative)) = pending.pop() {
+        let mut entries = tokio::fs::read_dir(&dir).await.map_err(Error::Io)?;
+
+        while let Some(entry) = entries.next_entry().await.map_err(Error::Io)? {
+            let mut relative = relative.clone();
+            relative.push(entry.file_name().to_string_lossy().into_owned());
+
+            if entry.file_type().await.map_err(Error::Io)?.is_dir() {
+                pending.push((entry.path(), relative));
+            } else {
+                files.push((entry.path(), relative));
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+/// A set of [`Uploader`]s along with the policies applied when uploading.
+///
+/// By default, only the [`File`] uploader is registered, at most
+/// [`DEFAULT_CONCURRENCY`] uploads run at once, and failed uploads are retried
+/// up to [`DEFAULT_RETRIES`] times.
+#[derive(Clone, Debug)]
+pub struct Uploaders {
+    /// The registered uploaders.
+    uploaders: Vec<Arc<dyn Uploader>>,
+
+    /// The maximum number of uploads that run at once.
+    concurrency: usize,
+
+    /// The maximum number of times a failed upload is retried.
+    retries: u32,
+}
+
+impl Default for Uploaders {
+    fn default() -> Self {
+        Self {
+            uploaders: vec![Arc::new(File)],
+            concurrency: DEFAULT_CONCURRENCY,
+            retries: DEFAULT_RETRIES,
+        }
+    }
+}
+
+impl Uploaders {
+    /// Registers an [`Uploader`].
+    ///
+    /// Uploaders registered later take precedence over those registered
+    /// earlier when more than one uploader supports a URL.
+    pub fn with(mut self, uploader: impl Uploader) -> Self {
+        self.uploaders.push(Arc::new(uploader));
+        self
+    }
+
+    /// Sets the maximum number of uploads that run at once.
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous concurrency.
+    pub fn concurrency(mut self, uploads: usize) -> Self {
+        self.concurrency = uploads.max(1);
+        self
+    }
+
+    /// Sets the maximum number of times a failed upload is retried.
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previous number of retries.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Finds the uploader for a URL.
+    fn find(&self, url: &Url) -> Result<&Arc<dyn Uploader>> {
+        self.uploaders
+            .iter()
+            .rev()
+            .find(|uploader| uploader.supports(url))
+            .ok_or_else(|| Error::UnsupportedScheme(url.scheme().to_owned()))
+    }
+
+    /// Uploads a single file, retrying failed attempts.
+    async fn upload_file(&self, path: &Path, url: &Url) -> Result<u64> {
+        let uploader = self.find(url)?;
+        let size = tokio::fs::metadata(path).await.map_err(Error::Io)?.len();
+        let mut attempt = 0;
+
+        loop {
+            match uploader.upload(path, url).await {
+                Ok(()) => {
+                    trace!("uploaded `{}` to `{url}`", path.display());
+                    return Ok(size);
+                }
+                Err(err) if attempt < self.retries => {
+                    let delay = INITIAL_BACKOFF * 2u32.saturating_pow(attempt);
+                    warn!(
+                        "uploading `{}` to `{url}` failed (retrying in {delay:?}): {err}",
+                        path.display()
+                    );
+
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Lists the files to upload for the file or directory at `path` (each
+    /// alongside the URL to upload it to).
+    ///
+    /// The files within a directory are uploaded to their paths (relative to
+    /// the directory) beneath `url`.
+    async fn expand(&self, path: PathBuf, url: &Url) -> Result<Vec<(PathBuf, Url)>> {
+        if !tokio::fs::metadata(&path)
+            .await
+            .map_err(Error::Io)?
+            .is_dir()
+        {
+            return Ok(vec![(path, url.clone())]);
+        }
+
+        walk(&path)
+            .await?
+            .into_iter()
+            .map(|(path, relative)| {
+                let mut file = url.clone();
+                file.path_segments_mut()
+                    .map_err(|_| Error::UnsupportedScheme(url.scheme().to_owned()))?
+                    .pop_if_empty()
+                    .extend(&relative);
+                Ok((path, file))
+            })
+            .collect()
+    }
+
+    /// Uploads the file or directory at `path` to `url`, returning the number
+    /// of bytes uploaded.
+    ///
+    /// The files within a directory are uploaded to their paths (relative to
+    /// the directory) beneath `url`.
+    pub async fn upload(&self, path: &Path, url: &Url) -> Result<u64> {
+        let mut total = 0;
+        self.upload_all([(path.to_owned(), url.clone())], |_, _, size| total = size)
+            .await?;
+        Ok(total)
+    }
+
+    /// Uploads a set of files or directories (each alongside the URL to upload
+    /// it to) with at most the configured number of files being uploaded at
+    /// once.
+    ///
+    /// `uploaded` is called with the index, URL, and size (in bytes) of each
+    /// file or directory once it has been uploaded in its entirety.
+    pub async fn upload_all(
+        &self,
+        uploads: impl IntoIterator<Item = (PathBuf, Url)>,
+        mut uploaded: impl FnMut(usize, &Url, u64),
+    ) -> Result<()> {
+        let mut urls = Vec::new();
+        let mut remaining = Vec::new();
+        let mut files = Vec::new();
+
+        for (index, (path, url)) in uploads.into_iter().enumerate() {
+            let expanded = self.expand(path, &url).await?;
+            remaining.push((expanded.len(), 0));
+            files.extend(expanded.into_iter().map(|file| (index, file)));
+            urls.push(url);
+        }
+
+        // NOTE: empty directories have nothing to upload.
+        for (index, (count, _)) in remaining.iter().enumerate() {
+            if *count == 0 {
+                uploaded(index, &urls[index], 0);
+            }
+        }
+
+        stream::iter(files)
+            .map(|(index, (path, url))| async move {
+                Ok((index, self.upload_file(&path, &url).await?))
+            })
+            .buffer_unordered(self.concurrency)
+            .try_for_each(|(index, size)| {
+                let (count, total) = &mut remaining[index];
+                *count -= 1;
+                *total += size;
+
+                if *count == 0 {
+                    uploaded(index, &urls[index], *total);
+                }
+
+                future::ready(Ok(()))
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use std::sync::atomic::AtomicU32;
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+
+    /// An uploader for `mem://` URLs that fails a number of times before
+    /// recording the uploads it receives.
+    #[derive(Debug, Default)]
+    struct Memory {
+        /// The number of uploads that fail before uploads succeed.
+        failures: AtomicU32,
+
+        /// The URLs and contents that were uploaded.
+        uploads: Arc<Mutex<Vec<(String, String)>>>,
+    }
+
+    #[async_trait]
+    impl Uploader for Memory {
+        fn supports(&self, url: &Url) -> bool {
+            url.scheme() == "mem"
+        }
+
+        async fn upload(&self, path: &Path, url: &Url) -> Result<()> {
+            let failures = self.failures.load(Ordering::SeqCst);
+
+            if failures > 0 {
+                self.failures.store(failures - 1, Ordering::SeqCst);
+                return Err(Error::Io(std::io::Error::other("transient")));
+            }
+
+            let contents = std::fs::read_to_string(path).unwrap();
+            self.uploads
+                .lock()
+                .unwrap()
+                .push((url.to_string(), contents));
+            Ok(())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_uploads_are_retried() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("result.txt");
+        std::fs::write(&path, "done").unwrap();
+
+        let uploads = Arc::new(Mutex::new(Vec::new()));
+        let uploaders = Uploaders::default().retries(2).with(Memory {
+            failures: AtomicU32::new(2),
+            uploads: uploads.clone(),
+        });
+
+        let url = "mem://bucket/result.txt".parse().unwrap();
+        assert_eq!(uploaders.upload(&path, &url).await.unwrap(), 4);
+        assert_eq!(
+            *uploads.lock().unwrap(),
+            [(
+                String::from("mem://bucket/result.txt"),
+                String::from("done")
+            )]
+        );
+
+        let uploaders = Uploaders::default().retries(1).with(Memory {
+            failures: AtomicU32::new(2),
+            ..Default::default()
+        });
+        assert!(matches!(
+            uploaders.upload(&path, &url).await.unwrap_err(),
+            Error::Io(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn directories_are_uploaded_file_by_file() {
+        let source = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(source.path().join("nested")).unwrap();
+        std::fs::write(source.path().join("a.txt"), "a").unwrap();
+        std::fs::write(source.path().join("nested/b.txt"), "bb").unwrap();
+
+        let destination = tempfile::tempdir().unwrap();
+        let url = Url::from_directory_path(destination.path().join("out")).unwrap();
+
+        let mut uploaded = Vec::new();
+        Uploaders::default()
+            .upload_all([(source.path().to_owned(), url)], |index, _, size| {
+                uploaded.push((index, size))
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(uploaded, [(0, 3)]);
+        assert_eq!(
+            std::fs::read_to_string(destination.path().join("out/nested/b.txt")).unwrap(),
+            "bb"
+        );
+
+        let err = Uploaders::default()
+            .upload(&source.path().join("a.txt"), &"mem://a".parse().unwrap())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::UnsupportedScheme(scheme) if scheme == "mem"));
+    }
+}
@@ -10,6 +10,18 @@ use crankshaft_config::backend::Defaults;
 use nonempty::NonEmpty;
 
 /// A set of requested resources.
+///
+/// Memory may be specified with a single amount ([`ram`](Self::ram)), which is
+/// both requested from the scheduler and enforced as a limit, or with separate
+/// soft and hard amounts (as schedulers such as LSF and Slurm distinguish
+/// between them):
+///
+/// * The soft amount ([`ram_soft`](Self::ram_soft)) informs scheduling (e.g.,
+///   an LSF `rusage` or a Docker memory reservation).
+/// * The hard amount ([`ram_hard`](Self::ram_hard)) is enforced (e.g., an LSF
+///   memory limit or a Docker memory limit).
+///
+/// Either amount falls back to [`ram`](Self::ram) when it is not set.
 #[derive(Clone, Debug)]
 pub struct Resources {
     /// The number of CPU cores requested.
@@ -21,6 +33,12 @@ pub struct Resources {
     /// The requested random access memory size in gigabytes.
     ram: Option<f64>,
 
+    /// The soft random access memory size in gigabytes.
+    ram_soft: Option<f64>,
+
+    /// The hard random access memory size in gigabytes.
+    ram_hard: Option<f64>,
+
     /// The requested disk size in gigabytes.
     disk: Option<f64>,
 
@@ -44,6 +62,22 @@ impl Resources {
         self.ram
     }
 
+    /// The soft amount of RAM in gigabytes (i.e., the amount used for
+    /// scheduling).
+    ///
+    /// Falls back to [`ram`](Self::ram) if no soft amount was set.
+    pub fn ram_soft(&self) -> Option<f64> {
+        self.ram_soft.or(self.ram)
+    }
+
+    /// The hard amount of RAM in gigabytes (i.e., the amount that is
+    /// enforced).
+    ///
+    /// Falls back to [`ram`](Self::ram) if no hard amount was set.
+    pub fn ram_hard(&self) -> Option<f64> {
+        self.ram_hard.or(self.ram)
+    }
+
     /// The amount of disk space in gigabytes.
     pub fn disk(&self) -> Option<f64> {
         self.disk
@@ -68,6 +102,14 @@ impl Resources {
             self.ram = Some(ram);
         }
 
+        if let Some(ram) = other.ram_soft {
+            self.ram_soft = Some(ram);
+        }
+
+        if let Some(ram) = other.ram_hard {
+            self.ram_hard = Some(ram);
+        }
+
         if let Some(disk) = other.disk {
             self.disk = Some(disk);
         }
@@ -106,6 +148,16 @@ impl Resources {
             hm.insert(String::from("ram_mb"), (ram * 1024.0).to_string());
         }
 
+        if let Some(ram) = self.ram_soft() {
+            hm.insert(String::from("ram_soft"), ram.to_string());
+            hm.insert(String::from("ram_soft_mb"), (ram * 1024.0).to_string());
+        }
+
+        if let Some(ram) = self.ram_hard() {
+            hm.insert(String::from("ram_hard"), ram.to_string());
+            hm.insert(String::from("ram_hard_mb"), (ram * 1024.0).to_string());
+        }
+
         if let Some(disk) = self.disk {
             hm.insert(String::from("disk"), disk.to_string());
             // TODO(clay): improve this.
@@ -123,6 +175,8 @@ impl Default for Resources {
             cpu: Some(1),
             preemptible: Some(false),
             ram: Some(2.0),
+            ram_soft: Default::default(),
+            ram_hard: Default::default(),
             disk: Some(8.0),
             zones: Default::default(),
         }
@@ -135,17 +189,29 @@ impl From<&Defaults> for Resources {
             cpu: defaults.cpu(),
             preemptible: Default::default(),
             ram: defaults.ram(),
+            ram_soft: Default::default(),
+            ram_hard: Default::default(),
             disk: defaults.disk(),
-            zones: Default::default(),
+            zones: defaults.zones().and_then(NonEmpty::from_slice),
         }
     }
 }
 
 impl From<&Resources> for HostConfig {
     fn from(resources: &Resources) -> Self {
+        let bytes = |gb: f64| (gb * 1024. * 1024. * 1024.).round() as i64;
+
         let mut host_config = HostConfig::default();
-        if let Some(ram) = resources.ram() {
-            host_config.memory = Some((ram * 1024. * 1024. * 1024.) as i64);
+        if let Some(ram) = resources.ram_hard() {
+            host_config.memory = Some(bytes(ram));
+        }
+
+        // NOTE: Docker requires a memory reservation to be lower than the
+        // memory limit, so a soft amount is only applied below the hard one.
+        if let Some(ram) = resources.ram_soft() {
+            if host_config.memory.is_none_or(|memory| bytes(ram) < memory) {
+                host_config.memory_reservation = Some(bytes(ram));
+            }
         }
 
         if let Some(cpu) = resources.cpu() {
@@ -161,3 +227,32 @@ impl From<&Resources> for HostConfig {
         host_config
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn soft_and_hard_memory_fall_back_to_ram() {
+        let resources = Builder::default().ram(4.0).build();
+        assert_eq!(resources.ram_soft(), Some(4.0));
+        assert_eq!(resources.ram_hard(), Some(4.0));
+
+        let config = HostConfig::from(&resources);
+        assert_eq!(config.memory, Some(4 * 1024 * 1024 * 1024));
+        assert_eq!(config.memory_reservation, None);
+
+        let resources = Builder::default().ram(4.0).ram_soft(2.0).build();
+        assert_eq!(resources.ram_soft(), Some(2.0));
+        assert_eq!(resources.ram_hard(), Some(4.0));
+
+        let config = HostConfig::from(&resources);
+        assert_eq!(config.memory, Some(4 * 1024 * 1024 * 1024));
+        assert_eq!(config.memory_reservation, Some(2 * 1024 * 1024 * 1024));
+
+        let map = resources.to_hashmap().unwrap();
+        assert_eq!(map["ram"], "4");
+        assert_eq!(map["ram_soft_mb"], "2048");
+        assert_eq!(map["ram_hard_mb"], "4096");
+    }
+}
@@ -16,6 +16,12 @@ pub struct Builder {
     /// The requested random access memory size in gigabytes.
     ram: Option<f64>,
 
+    /// The soft random access memory size in gigabytes.
+    ram_soft: Option<f64>,
+
+    /// The hard random access memory size in gigabytes.
+    ram_hard: Option<f64>,
+
     /// The requested disk size in gigabytes.
     disk: Option<f64>,
 
@@ -58,6 +64,30 @@ impl Builder {
         self
     }
 
+    /// Adds a soft amount of RAM in gigabytes (i.e., the amount used for
+    /// scheduling) to the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previously provided soft amount of RAM
+    /// provided to the builder.
+    pub fn ram_soft(mut self, value: impl Into<f64>) -> Self {
+        self.ram_soft = Some(value.into());
+        self
+    }
+
+    /// Adds a hard amount of RAM in gigabytes (i.e., the amount that is
+    /// enforced) to the [`Builder`].
+    ///
+    /// # Notes
+    ///
+    /// This will silently overwrite any previously provided hard amount of RAM
+    /// provided to the builder.
+    pub fn ram_hard(mut self, value: impl Into<f64>) -> Self {
+        self.ram_hard = Some(value.into());
+        self
+    }
+
     /// Adds a requested amount of disk space in gigabytes to the [`Builder`].
     ///
     /// # Notes
@@ -109,6 +139,8 @@ impl Builder {
             cpu: self.cpu,
             preemptible: self.preemptible,
             ram: self.ram,
+            ram_soft: self.ram_soft,
+            ram_hard: self.ram_hard,
             disk: self.disk,
             zones: self.zones,
         }
@@ -0,0 +1,734 @@
+//! Task specification files.
+//!
+//! A task can be defined outside of Rust code within a TOML or YAML file (see
+//! [`Task::from_path()`] and [`Task::from_str()`]). For example:
+//!
+//! ```toml
+//! name = "align"
+//! priority = 10
+//!
+//! [resources]
+//! cpu = 4
+//! ram = 8.0
+//!
+//! [[inputs]]
+//! path = "/data/reads.fastq"
+//! url = "https://example.com/reads.fastq"
+//! type = "file"
+//!
+//! [[outputs]]
+//! path = "/data/aligned.bam"
+//! url = "file:///results/aligned.bam"
+//! type = "file"
+//!
+//! [[executions]]
+//! image = "ubuntu"
+//! args = ["bash", "-c", "align /data/reads.fastq > /data/aligned.bam"]
+//! env = { THREADS = "4" }
+//! stdout = "discard"
+//! ```
+//!
+//! Unknown fields are rejected, and every error names the field it is about
+//! (e.g., `executions[0].args`) so that mistakes within a specification are
+//! easy to find.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use url::Url;
+
+use crate::Task;
+use crate::task::Execution;
+use crate::task::Input;
+use crate::task::Output;
+use crate::task::Resources;
+use crate::task::RetryPolicy;
+use crate::task::execution::Redirection;
+use crate::task::input;
+use crate::task::output;
+
+/// An error related to a task specification.
+#[derive(Debug)]
+pub enum Error {
+    /// The specification file could not be read.
+    Io(PathBuf, std::io::Error),
+
+    /// The format of the specification file could not be determined from its
+    /// extension.
+    UnknownFormat(PathBuf),
+
+    /// The specification is not valid TOML (or does not match the schema).
+    Toml(toml::de::Error),
+
+    /// The specification is not valid YAML (or does not match the schema).
+    Yaml(serde_yaml::Error),
+
+    /// A field within the specification has an invalid value.
+    Invalid {
+        /// The path of the field (e.g., `executions[0].args`).
+        field: String,
+
+        /// The reason the value is invalid.
+        reason: String,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(path, err) => write!(f, "failed to read `{}`: {err}", path.display()),
+            Error::UnknownFormat(path) => write!(
+                f,
+                "the format of `{}` is unknown (expected a `.toml`, `.yaml`, or `.yml` file)",
+                path.display()
+            ),
+            Error::Toml(err) => write!(f, "invalid task specification: {err}"),
+            Error::Yaml(err) => write!(f, "invalid task specification: {err}"),
+            Error::Invalid { field, reason } => write!(f, "invalid value for `{field}`: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A [`Result`](std::result::Result) with an [`Error`].
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Creates an [`Error::Invalid`] for a field.
+fn invalid(field: impl Into<String>, reason: impl ToString) -> Error {
+    Error::Invalid {
+        field: field.into(),
+        reason: reason.to_string(),
+    }
+}
+
+/// A format of a task specification.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// TOML.
+    Toml,
+
+    /// YAML.
+    Yaml,
+}
+
+impl Format {
+    /// Gets the format of a specification file from its extension (if it is
+    /// known).
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "toml" => Some(Format::Toml),
+            "yaml" | "yml" => Some(Format::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// A type of input or output.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Type {
+    /// A file.
+    File,
+
+    /// A directory.
+    Directory,
+}
+
+impl From<Type> for input::Type {
+    fn from(value: Type) -> Self {
+        match value {
+            Type::File => input::Type::File,
+            Type::Directory => input::Type::Directory,
+        }
+    }
+}
+
+impl From<Type> for output::Type {
+    fn from(value: Type) -> Self {
+        match value {
+            Type::File => output::Type::File,
+            Type::Directory => output::Type::Directory,
+        }
+    }
+}
+
+/// The specification of a task.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct TaskSpec {
+    /// The name of the task.
+    name: Option<String>,
+
+    /// The description of the task.
+    description: Option<String>,
+
+    /// The idempotency key of the task.
+    idempotency_key: Option<String>,
+
+    /// The priority of the task.
+    #[serde(default)]
+    priority: i32,
+
+    /// The group the task is accounted to.
+    group: Option<String>,
+
+    /// The volumes shared across the executions of the task.
+    #[serde(default)]
+    volumes: Vec<String>,
+
+    /// The command that checkpoints the task before it is preempted.
+    checkpoint_command: Option<Vec<String>>,
+
+    /// The policy for retrying the task.
+    retry_policy: Option<RetryPolicy>,
+
+    /// The resources requested by the task.
+    resources: Option<ResourcesSpec>,
+
+    /// The inputs of the task.
+    #[serde(default)]
+    inputs: Vec<InputSpec>,
+
+    /// The outputs of the task.
+    #[serde(default)]
+    outputs: Vec<OutputSpec>,
+
+    /// The executions of the task.
+    #[serde(default)]
+    executions: Vec<ExecutionSpec>,
+}
+
+/// The specification of the resources requested by a task.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct ResourcesSpec {
+    /// The number of CPU cores.
+    cpu: Option<usize>,
+
+    /// Whether or not the task may use preemptible resources.
+    preemptible: Option<bool>,
+
+    /// The amount of RAM in gigabytes.
+    ram: Option<f64>,
+
+    /// The soft amount of RAM in gigabytes.
+    ram_soft: Option<f64>,
+
+    /// The hard amount of RAM in gigabytes.
+    ram_hard: Option<f64>,
+
+    /// The disk size in gigabytes.
+    disk: Option<f64>,
+
+    /// The compute zones.
+    #[serde(default)]
+    zones: Vec<String>,
+}
+
+/// The specification of an input of a task.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct InputSpec {
+    /// The name of the input.
+    name: Option<String>,
+
+    /// The description of the input.
+    description: Option<String>,
+
+    /// The path of the input within the container.
+    path: String,
+
+    /// The type of the input.
+    r#type: Type,
+
+    /// The URL the contents of the input are sourced from.
+    url: Option<String>,
+
+    /// The literal contents of the input.
+    contents: Option<String>,
+
+    /// Whether or not the input must exist before the task is submitted.
+    must_exist: Option<bool>,
+
+    /// The entity tag the input must have.
+    etag: Option<String>,
+
+    /// The expected hex-encoded SHA-256 digest of the contents.
+    checksum: Option<String>,
+}
+
+/// The specification of an output of a task.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct OutputSpec {
+    /// The name of the output.
+    name: Option<String>,
+
+    /// The description of the output.
+    description: Option<String>,
+
+    /// The path of the output within the container.
+    path: String,
+
+    /// The type of the output.
+    r#type: Type,
+
+    /// The URL the output is copied to.
+    url: String,
+}
+
+/// The specification of an execution within a task.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct ExecutionSpec {
+    /// The container image.
+    image: String,
+
+    /// The command arguments.
+    args: Vec<String>,
+
+    /// The working directory.
+    workdir: Option<String>,
+
+    /// The path of the file piped to the standard input.
+    stdin: Option<String>,
+
+    /// Where the contents of the standard output stream are sent.
+    stdout: Option<String>,
+
+    /// Where the contents of the standard error stream are sent.
+    stderr: Option<String>,
+
+    /// The environment variables.
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+
+    /// The path of a file of environment variables.
+    env_file: Option<PathBuf>,
+
+    /// The user (and, optionally, the group) to run as.
+    user: Option<String>,
+}
+
+/// Resolves a path on the host relative to the directory of the
+/// specification file (if there is one).
+fn resolve(base: Option<&Path>, path: impl AsRef<Path>) -> PathBuf {
+    match base {
+        Some(base) => base.join(path),
+        None => path.as_ref().to_path_buf(),
+    }
+}
+
+/// Parses a URL within a field.
+fn url(field: impl Into<String>, value: &str) -> Result<Url> {
+    value.parse().map_err(|err| invalid(field, err))
+}
+
+/// Parses a redirection of an output stream.
+///
+/// A redirection is either `capture`, `inherit`, `discard`, a URL, or a path
+/// on the host.
+fn redirection(base: Option<&Path>, value: &str) -> Redirection {
+    match value {
+        "capture" => Redirection::Capture,
+        "inherit" => Redirection::Inherit,
+        "discard" => Redirection::Discard,
+        value => match Url::parse(value) {
+            // NOTE: single letter schemes are Windows drive letters.
+            Ok(url) if url.scheme().len() > 1 => Redirection::Url(url),
+            _ => Redirection::File(resolve(base, value)),
+        },
+    }
+}
+
+impl ExecutionSpec {
+    /// Converts the specification into an [`Execution`].
+    fn into_execution(self, field: &str, base: Option<&Path>) -> Result<Execution> {
+        if self.args.is_empty() {
+            return Err(invalid(
+                format!("{field}.args"),
+                "at least one argument is required",
+            ));
+        }
+
+        let mut builder = Execution::builder().image(self.image).args(self.args);
+
+        if let Some(workdir) = self.workdir {
+            builder = builder.working_directory(workdir);
+        }
+
+        if let Some(stdin) = self.stdin {
+            builder = builder.stdin(stdin);
+        }
+
+        if let Some(stdout) = self.stdout {
+            builder = builder.stdout(redirection(base, &stdout));
+        }
+
+        if let Some(stderr) = self.stderr {
+            builder = builder.stderr(redirection(base, &stderr));
+        }
+
+        for (name, value) in self.env {
+            builder = builder.env(name, value);
+        }
+
+        if let Some(user) = self.user {
+            builder = builder.user(user);
+        }
+
+        if let Some(path) = self.env_file {
+            builder = builder.env_file(resolve(base, path));
+        }
+
+        builder
+            .try_build()
+            .map_err(|err| invalid(format!("{field}.env-file"), err))
+    }
+}
+
+impl InputSpec {
+    /// Converts the specification into an [`Input`].
+    fn into_input(self, field: &str) -> Result<Input> {
+        let contents = match (self.url, self.contents) {
+            (Some(value), None) => input::Contents::URL(url(format!("{field}.url"), &value)?),
+            (None, Some(contents)) => input::Contents::Literal(contents),
+            (Some(_), Some(_)) => {
+                return Err(invalid(
+                    field,
+                    "only one of `url` or `contents` may be provided",
+                ));
+            }
+            (None, None) => {
+                return Err(invalid(field, "either `url` or `contents` is required"));
+            }
+        };
+
+        let mut builder = Input::builder()
+            .path(self.path)
+            .r#type(input::Type::from(self.r#type))
+            .contents(contents);
+
+        if let Some(name) = self.name {
+            builder = builder.name(name);
+        }
+
+        if let Some(description) = self.description {
+            builder = builder.description(description);
+        }
+
+        if let Some(must_exist) = self.must_exist {
+            builder = builder.must_exist(must_exist);
+        }
+
+        if let Some(etag) = self.etag {
+            builder = builder.etag(etag);
+        }
+
+        if let Some(checksum) = self.checksum {
+            builder = builder.checksum(checksum);
+        }
+
+        // SAFETY: the contents, path, and type are always set above, so this
+        // will always unwrap.
+        Ok(builder.try_build().unwrap())
+    }
+}
+
+impl OutputSpec {
+    /// Converts the specification into an [`Output`].
+    fn into_output(self, field: &str) -> Result<Output> {
+        let mut builder = output::Builder::default()
+            .path(self.path)
+            .r#type(output::Type::from(self.r#type))
+            .url(url(format!("{field}.url"), &self.url)?);
+
+        if let Some(name) = self.name {
+            builder = builder.name(name);
+        }
+
+        if let Some(description) = self.description {
+            builder = builder.description(description);
+        }
+
+        // SAFETY: the URL, path, and type are always set above, so this will
+        // always unwrap.
+        Ok(builder.try_build().unwrap())
+    }
+}
+
+impl From<ResourcesSpec> for Resources {
+    fn from(spec: ResourcesSpec) -> Self {
+        let mut builder = crate::task::resources::Builder::default();
+
+        if let Some(cpu) = spec.cpu {
+            builder = builder.cpu(cpu);
+        }
+
+        if let Some(preemptible) = spec.preemptible {
+            builder = builder.preemptible(preemptible);
+        }
+
+        if let Some(ram) = spec.ram {
+            builder = builder.ram(ram);
+        }
+
+        if let Some(ram) = spec.ram_soft {
+            builder = builder.ram_soft(ram);
+        }
+
+        if let Some(ram) = spec.ram_hard {
+            builder = builder.ram_hard(ram);
+        }
+
+        if let Some(disk) = spec.disk {
+            builder = builder.disk(disk);
+        }
+
+        builder.zones(spec.zones.into_iter()).build()
+    }
+}
+
+impl TaskSpec {
+    /// Converts the specification into a [`Task`].
+    ///
+    /// Relative paths on the host (e.g., of environment files) are resolved
+    /// against `base` (if it is provided).
+    fn into_task(self, base: Option<&Path>) -> Result<Task> {
+        if self.executions.is_empty() {
+            return Err(invalid("executions", "at least one execution is required"));
+        }
+
+        let executions = self
+            .executions
+            .into_iter()
+            .enumerate()
+            .map(|(i, execution)| execution.into_execution(&format!("executions[{i}]"), base))
+            .collect::<Result<Vec<_>>>()?;
+        let inputs = self
+            .inputs
+            .into_iter()
+            .enumerate()
+            .map(|(i, input)| input.into_input(&format!("inputs[{i}]")))
+            .collect::<Result<Vec<_>>>()?;
+        let outputs = self
+            .outputs
+            .into_iter()
+            .enumerate()
+            .map(|(i, output)| output.into_output(&format!("outputs[{i}]")))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut builder = Task::builder()
+            .extend_executions(executions)
+            .extend_inputs(inputs)
+            .extend_outputs(outputs)
+            .extend_volumes(self.volumes)
+            .priority(self.priority);
+
+        if let Some(name) = self.name {
+            builder = builder.name(name);
+        }
+
+        if let Some(description) = self.description {
+            builder = builder.description(description);
+        }
+
+        if let Some(key) = self.idempotency_key {
+            builder = builder.idempotency_key(key);
+        }
+
+        if let Some(group) = self.group {
+            builder = builder.group(group);
+        }
+
+        if let Some(command) = self.checkpoint_command {
+            if command.is_empty() {
+                return Err(invalid(
+                    "checkpoint-command",
+                    "at least one argument is required",
+                ));
+            }
+
+            builder = builder.checkpoint_command(command);
+        }
+
+        if let Some(policy) = self.retry_policy {
+            builder = builder.retry_policy(policy);
+        }
+
+        if let Some(resources) = self.resources {
+            builder = builder.resources(Resources::from(resources));
+        }
+
+        // SAFETY: at least one execution is always provided above, so this
+        // will always unwrap.
+        Ok(builder.try_build().unwrap())
+    }
+}
+
+/// Parses a task specification.
+pub(crate) fn parse(s: &str, format: Format, base: Option<&Path>) -> Result<Task> {
+    let spec: TaskSpec = match format {
+        Format::Toml => toml::from_str(s).map_err(Error::Toml)?,
+        Format::Yaml => serde_yaml::from_str(s).map_err(Error::Yaml)?,
+    };
+
+    spec.into_task(base)
+}
+
+/// Reads and parses a task specification file.
+pub(crate) fn read(path: &Path) -> Result<Task> {
+    let format = Format::from_path(path).ok_or_else(|| Error::UnknownFormat(path.into()))?;
+    let contents = std::fs::read_to_string(path).map_err(|err| Error::Io(path.into(), err))?;
+    parse(&contents, format, path.parent())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toml_specifications_are_parsed() {
+        let task = Task::from_str(
+            r#"
+name = "align"
+priority = 10
+volumes = ["/scratch"]
+
+[retry-policy]
+max-attempts = 3
+
+[resources]
+cpu = 4
+ram = 8.0
+zones = ["us-east-1a"]
+
+[[inputs]]
+path = "/data/reads.fastq"
+url = "https://example.com/reads.fastq"
+type = "file"
+
+[[inputs]]
+path = "/data/config.json"
+contents = "{}"
+type = "file"
+
+[[outputs]]
+path = "/data/aligned.bam"
+url = "file:///results/aligned.bam"
+type = "file"
+
+[[executions]]
+image = "ubuntu"
+args = ["echo", "hello"]
+env = { THREADS = "4" }
+stdout = "discard"
+stderr = "file:///logs/stderr.txt"
+"#,
+            Format::Toml,
+        )
+        .unwrap();
+
+        assert_eq!(task.name(), Some("align"));
+        assert_eq!(task.priority(), 10);
+        assert_eq!(
+            task.shared_volumes().unwrap().collect::<Vec<_>>(),
+            ["/scratch"]
+        );
+        assert_eq!(task.retry_policy().unwrap().max_attempts(), 3);
+        assert_eq!(task.resources().unwrap().cpu(), Some(4));
+        assert_eq!(task.inputs().unwrap().count(), 2);
+        assert_eq!(
+            task.outputs().unwrap().next().unwrap().url(),
+            "file:///results/aligned.bam"
+        );
+
+        let execution = task.executions().next().unwrap();
+        assert_eq!(execution.image(), "ubuntu");
+        assert_eq!(
+            execution.args().into_iter().collect::<Vec<_>>(),
+            ["echo", "hello"]
+        );
+        assert_eq!(execution.env().unwrap().get("THREADS").unwrap(), "4");
+        assert_eq!(execution.stdout(), &Redirection::Discard);
+        assert!(matches!(execution.stderr(), Redirection::Url(_)));
+    }
+
+    #[test]
+    fn yaml_specifications_are_parsed() {
+        let task = Task::from_str(
+            "
+name: align
+executions:
+  - image: ubuntu
+    args: [echo, hello]
+    stdout: out.txt
+",
+            Format::Yaml,
+        )
+        .unwrap();
+
+        assert_eq!(task.name(), Some("align"));
+        assert_eq!(
+            task.executions().next().unwrap().stdout(),
+            &Redirection::File(PathBuf::from("out.txt"))
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_field() {
+        let err = Task::from_str(
+            "[[executions]]\nimage = \"ubuntu\"\nargs = []\n",
+            Format::Toml,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid value for `executions[0].args`: at least one argument is required"
+        );
+
+        let err = Task::from_str(
+            "executions:\n  - image: ubuntu\n    args: [echo]\n    imag: typo\n",
+            Format::Yaml,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("executions[0]"));
+        assert!(err.to_string().contains("unknown field `imag`"));
+
+        let err = Task::from_str(
+            "[[executions]]\nimage = \"ubuntu\"\nargs = [\"echo\"]\n\n[[outputs]]\npath = \
+             \"/out\"\ntype = \"file\"\nurl = \"not a url\"\n",
+            Format::Toml,
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("invalid value for `outputs[0].url`")
+        );
+
+        let err = Task::from_str("name = \"empty\"\n", Format::Toml).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid value for `executions`: at least one execution is required"
+        );
+    }
+
+    #[test]
+    fn files_are_read_by_extension() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let path = dir.path().join("task.yml");
+        std::fs::write(&path, "executions:\n  - image: ubuntu\n    args: [echo]\n").unwrap();
+        assert!(Task::from_path(&path).is_ok());
+
+        let path = dir.path().join("task.json");
+        std::fs::write(&path, "{}").unwrap();
+        assert!(matches!(
+            Task::from_path(&path),
+            Err(Error::UnknownFormat(_))
+        ));
+    }
+}
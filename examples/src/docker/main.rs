@@ -11,10 +11,12 @@ use crankshaft::Engine;
 use crankshaft::config::backend::Kind;
 use crankshaft::config::backend::docker::Config;
 use crankshaft::engine::Task;
+use crankshaft::engine::service::runner::TaskSet;
 use crankshaft::engine::task::Execution;
 use eyre::Context;
 use eyre::Result;
 use tracing::info;
+use tracing::warn;
 use tracing_subscriber::EnvFilter;
 use tracing_subscriber::fmt;
 use tracing_subscriber::layer::SubscriberExt as _;
@@ -64,16 +66,26 @@ async fn run(args: Args) -> Result<()> {
         .try_build()
         .unwrap();
 
-    let receivers = (0..args.n_jobs)
-        .map(|_| engine.submit("docker", task.clone()).callback)
-        .collect::<Vec<_>>();
+    // NOTE: the tasks are kept within the set until the results are received,
+    // as tasks are canceled once every handle to them has been dropped.
+    let mut tasks = TaskSet::new("example");
+    tasks.extend((0..args.n_jobs).map(|_| engine.submit("docker", task.clone())));
 
-    engine.run().await;
+    let report = engine.run().await;
 
-    for rx in receivers {
-        info!(runner = "Docker", reply = ?rx.await.unwrap());
+    for (id, reply) in tasks.wait_all().await {
+        match reply {
+            Some(reply) => info!(runner = "Docker", task_id = id, reply = ?reply),
+            None => warn!(
+                runner = "Docker",
+                task_id = id,
+                "task was canceled or failed to run"
+            ),
+        }
     }
 
+    info!("{report}");
+
     Ok(())
 }
 
@@ -8,11 +8,13 @@ use clap::Parser;
 use crankshaft::Config;
 use crankshaft::Engine;
 use crankshaft::engine::Task;
+use crankshaft::engine::service::runner::TaskSet;
 use crankshaft::engine::task::Execution;
 use eyre::Context as _;
 use eyre::ContextCompat as _;
 use eyre::Result;
 use tracing::info;
+use tracing::warn;
 use tracing_subscriber::EnvFilter;
 use tracing_subscriber::fmt;
 use tracing_subscriber::layer::SubscriberExt as _;
@@ -50,7 +52,8 @@ const CONFIG: &str = r#"backends:
               -cwd ~{cwd}
               -o ~{cwd}/stdout.lsf
               -e ~{cwd}/stderr.lsf
-              -R "rusage[mem=~{ram_mb}] span[hosts=~{hosts}]"
+              -R "rusage[mem=~{ram_soft_mb}] span[hosts=~{hosts}]"
+              -M ~{ram_hard_mb}
               ~{shell}
 "#;
 
@@ -78,14 +81,18 @@ async fn run(args: Args) -> Result<()> {
         .try_build()
         .unwrap();
 
-    let receivers = (0..args.n_jobs)
-        .map(|_| engine.submit("lsf", task.clone()).callback)
-        .collect::<Vec<_>>();
+    // NOTE: the tasks are kept within the set until the results are received,
+    // as tasks are canceled once every handle to them has been dropped.
+    let mut tasks = TaskSet::new("example");
+    tasks.extend((0..args.n_jobs).map(|_| engine.submit("lsf", task.clone())));
 
     engine.run().await;
 
-    for rx in receivers {
-        info!(reply = ?rx.await.unwrap());
+    for (id, reply) in tasks.wait_all().await {
+        match reply {
+            Some(reply) => info!(task_id = id, reply = ?reply),
+            None => warn!(task_id = id, "task was canceled or failed to run"),
+        }
     }
 
     Ok(())
@@ -14,10 +14,12 @@ use crankshaft::config::backend::Kind;
 use crankshaft::config::backend::tes::Config;
 use crankshaft::config::backend::tes::http;
 use crankshaft::engine::Task;
+use crankshaft::engine::service::runner::TaskSet;
 use crankshaft::engine::task::Execution;
 use eyre::Context;
 use eyre::Result;
 use tracing::info;
+use tracing::warn;
 use tracing_subscriber::EnvFilter;
 use tracing_subscriber::fmt;
 use tracing_subscriber::layer::SubscriberExt as _;
@@ -79,17 +81,21 @@ async fn run(args: Args) -> Result<()> {
         .try_build()
         .unwrap();
 
-    let receivers = (0..args.n_jobs)
-        .map(|_| engine.submit("tes", task.clone()).callback)
-        .collect::<Vec<_>>();
+    // NOTE: the tasks are kept within the set until the results are received,
+    // as tasks are canceled once every handle to them has been dropped.
+    let mut tasks = TaskSet::new("example");
+    tasks.extend((0..args.n_jobs).map(|_| engine.submit("tes", task.clone())));
 
     #[cfg(tokio_unstable)]
     Engine::start_instrument(3000);
 
     engine.run().await;
 
-    for rx in receivers {
-        info!(reply = ?rx.await.unwrap());
+    for (id, reply) in tasks.wait_all().await {
+        match reply {
+            Some(reply) => info!(task_id = id, reply = ?reply),
+            None => warn!(task_id = id, "task was canceled or failed to run"),
+        }
     }
 
     Ok(())